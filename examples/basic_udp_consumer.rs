@@ -50,7 +50,7 @@ fn main() -> std::io::Result<()> {
     let mut face1receiver = default_buffered_receiver(face1receiver);
     let _local_face = forwarder.add_face(fs1, fr1).unwrap();
 
-    let interest = Interest::new(name_prefix, false, [1, 2, 3, 4]);
+    let interest = Interest::new(name_prefix, false, forwarder.next_nonce());
     interest.encode(&mut face1sender).unwrap();
 
     loop {
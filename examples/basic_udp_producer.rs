@@ -9,10 +9,9 @@ use reto::{
         local::default_local_face,
     },
     forwarder::ForwarderError,
-    hash::Hasher,
-    io::Encode,
+    io::Write,
     name::{Name, NameComponent},
-    packet::{Data, Interest, SignatureInfo, SignatureValue},
+    packet::{DataBuilder, Interest},
     platform::{sha::Sha256Hasher, udp::udp_face, DefaultForwarder},
     tlv::{TlvDecode, TlvEncode},
 };
@@ -62,17 +61,11 @@ fn main() -> std::io::Result<()> {
                     );
 
                     let payload = b"Reto Data";
-                    let signature_info = SignatureInfo::new_digest_sha256();
-                    let mut data = Data::new_unsigned(interest.name, payload, signature_info);
+                    let packet = DataBuilder::new(interest.name)
+                        .content(payload)
+                        .digest_sign(&mut Sha256Hasher::new());
 
-                    let mut hasher = Sha256Hasher::new();
-                    data.hash_signed_portion(&mut hasher);
-                    let digest = hasher.finalize_reset();
-                    data.signature_value = SignatureValue {
-                        bytes: digest.0.as_slice(),
-                    };
-
-                    data.encode(&mut face1sender).unwrap();
+                    face1sender.write(&packet).unwrap();
                 }
             }
             Err(BufferedRecvError::NothingReceived) => {}
@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+
+use reto::{
+    face::{
+        buffered::{default_buffered_receiver, BufferedFaceReceiver, BufferedReceiver},
+        local::{default_local_face, LocalReceiver, LocalSender},
+    },
+    forwarder::{FaceToken, Forwarder, ForwarderError, InertMetrics},
+    io::{Decode, Encode, Write},
+    name::{Name, NameBuf, NameComponent},
+    packet::{Data, DataBuilder, Interest},
+    platform::{clock::MonotonicClock, sha::Sha256Hasher},
+    security::hmac::HmacSha256Signer,
+    tables::reference::ReferenceTables,
+};
+
+type LocalForwarder = Forwarder<MonotonicClock, Sha256Hasher, InertMetrics, ReferenceTables>;
+
+// Everyone in the room shares the key, so that outsiders cannot post
+const ROOM_KEY: &[u8] = b"reto chat room key";
+
+// A chat room where every participant publishes its messages under
+//  /chat/<participant>/<sequence number> and keeps an interest outstanding for
+//  the next message of everyone else, which is the simplest form of state-vector sync.
+// The producers hold on to the interests for the messages they have not
+//  written yet and answer them as soon as they do.
+// Everything runs in-process over local faces of a single forwarder.
+fn main() {
+    let mut forwarder = Forwarder::new(
+        MonotonicClock::new(),
+        Sha256Hasher::new(),
+        InertMetrics {},
+        ReferenceTables::default(),
+    );
+
+    let script: &[(&str, &[&str])] = &[
+        (
+            "alice",
+            &["hi all", "anyone up for lunch?", "see you at noon"],
+        ),
+        ("bob", &["hello", "sure, where?"]),
+        ("carol", &["hey", "count me in", "the usual place"]),
+    ];
+    let names: Vec<&str> = script.iter().map(|(name, _)| *name).collect();
+    let mut participants: Vec<Participant> = script
+        .iter()
+        .map(|(name, messages)| Participant::new(&mut forwarder, name, &names, messages))
+        .collect();
+
+    let expected: usize = script.iter().map(|(_, messages)| messages.len()).sum();
+    let mut tick = 0;
+    while participants
+        .iter()
+        .any(|p| p.received + p.published < expected)
+    {
+        tick += 1;
+        assert!(tick < 10_000, "the room did not converge");
+
+        for participant in participants.iter_mut() {
+            // Everyone writes something every few ticks
+            if tick % (3 + participant.index) == 0 {
+                participant.publish_next();
+            }
+            participant.process_incoming();
+        }
+
+        loop {
+            match forwarder.try_forward_batch(&[]) {
+                Ok(_) => {}
+                Err(ForwarderError::NothingToForward) => break,
+                Err(_) => panic!("forwarding failed"),
+            }
+        }
+    }
+
+    for participant in participants.iter() {
+        println!(
+            "{} saw {} messages from the others",
+            participant.user, participant.received
+        );
+    }
+}
+
+struct Participant {
+    user: String,
+    index: usize,
+    prefix: NameBuf,
+    sender: LocalSender,
+    receiver: BufferedReceiver<LocalReceiver>,
+    // The messages still to be written and the packets of the ones already written
+    unpublished: VecDeque<String>,
+    published: usize,
+    packets: Vec<Vec<u8>>,
+    // Interests for the messages that have not been written yet
+    pending: Vec<NameBuf>,
+    // The next sequence number expected from every other participant
+    peers: Vec<(NameBuf, u64)>,
+    received: usize,
+    nonce: u32,
+}
+
+impl Participant {
+    fn new(forwarder: &mut LocalForwarder, user: &str, room: &[&str], messages: &[&str]) -> Self {
+        let (forwarder_sender, receiver) = default_local_face();
+        let (sender, forwarder_receiver) = default_local_face();
+        let face: FaceToken = forwarder
+            .add_face(forwarder_sender, forwarder_receiver)
+            .unwrap();
+
+        let prefix = chat_prefix(user);
+        forwarder.register_name_prefix_for_forwarding(prefix.as_name(), face, 0);
+
+        let mut participant = Self {
+            user: String::from(user),
+            index: room.iter().position(|u| *u == user).unwrap(),
+            prefix,
+            sender,
+            receiver: default_buffered_receiver(receiver),
+            unpublished: messages.iter().map(|m| String::from(*m)).collect(),
+            published: 0,
+            packets: Vec::new(),
+            pending: Vec::new(),
+            peers: room
+                .iter()
+                .filter(|u| **u != user)
+                .map(|u| (chat_prefix(u), 0))
+                .collect(),
+            received: 0,
+            nonce: 0,
+        };
+        for index in 0..participant.peers.len() {
+            participant.express_interest(index);
+        }
+        participant
+    }
+
+    fn publish_next(&mut self) {
+        let Some(message) = self.unpublished.pop_front() else {
+            return;
+        };
+        let name = with_sequence_number(&self.prefix, self.packets.len() as u64);
+        let key_name = room_key_name();
+        let mut signer = HmacSha256Signer::new(key_name.as_name(), ROOM_KEY, Sha256Hasher::new());
+        let packet = DataBuilder::new(name.as_name())
+            .content(message.as_bytes())
+            .freshness_period(10_000)
+            .sign(&mut signer);
+        println!("{}: {}", self.user, message);
+        self.packets.push(packet);
+        self.published += 1;
+
+        // Someone may already be waiting for it
+        if let Some(position) = self.pending.iter().position(|n| *n == name) {
+            self.pending.remove(position);
+            let _ = self.sender.write(self.packets.last().unwrap());
+        }
+    }
+
+    fn process_incoming(&mut self) {
+        while let Ok((_, bytes)) = self.receiver.try_recv() {
+            let packet = bytes.to_vec();
+            if let Ok((interest, _)) = Interest::try_decode(&packet) {
+                self.on_interest(interest);
+            } else if let Ok((data, _)) = Data::try_decode(&packet) {
+                self.on_data(data);
+            }
+        }
+    }
+
+    fn on_interest(&mut self, interest: Interest<'_>) {
+        let Some(sequence) = sequence_number(interest.name) else {
+            return;
+        };
+        match self.packets.get(sequence as usize) {
+            Some(packet) => {
+                let _ = self.sender.write(packet);
+            }
+            None => {
+                let name = NameBuf::from(interest.name);
+                if !self.pending.contains(&name) {
+                    self.pending.push(name);
+                }
+            }
+        }
+    }
+
+    fn on_data(&mut self, data: Data<'_>) {
+        let key_name = room_key_name();
+        let mut verifier = HmacSha256Signer::new(key_name.as_name(), ROOM_KEY, Sha256Hasher::new());
+        assert!(data.verify_signature(&mut verifier), "forged message");
+
+        let Some(sequence) = sequence_number(data.name) else {
+            return;
+        };
+        let Some(index) = self.peers.iter().position(|(prefix, next)| {
+            prefix.as_name().is_prefix_of(data.name) && *next == sequence
+        }) else {
+            return;
+        };
+
+        let author = data.name.component(1).unwrap();
+        let message = data.content.as_ref().map(|c| c.bytes).unwrap_or_default();
+        println!(
+            "  {} <- {}: {}",
+            self.user,
+            String::from_utf8_lossy(author.bytes),
+            String::from_utf8_lossy(message)
+        );
+        self.received += 1;
+        self.peers[index].1 += 1;
+        self.express_interest(index);
+    }
+
+    fn express_interest(&mut self, index: usize) {
+        let (prefix, next) = &self.peers[index];
+        let name = with_sequence_number(prefix, *next);
+        self.nonce += 1;
+        let mut nonce = self.nonce.to_be_bytes();
+        nonce[0] = self.index as u8;
+        let interest = Interest::new(name.as_name(), false, nonce);
+        let mut packet = Vec::new();
+        let _ = interest.encode(&mut packet);
+        let _ = self.sender.write(&packet);
+    }
+}
+
+fn chat_prefix(user: &str) -> NameBuf {
+    NameBuf::new()
+        .pushing(NameComponent::generic(b"chat"))
+        .pushing(NameComponent::generic(user.as_bytes()))
+}
+
+fn room_key_name() -> NameBuf {
+    NameBuf::new()
+        .pushing(NameComponent::generic(b"chat"))
+        .pushing(NameComponent::generic(b"KEY"))
+}
+
+fn with_sequence_number(prefix: &NameBuf, sequence: u64) -> NameBuf {
+    let mut bytes = Vec::new();
+    let _ = sequence.encode(&mut bytes);
+    let component = NameComponent::new(NameComponent::TYPE_SEQUENCE_NUM, &bytes).unwrap();
+    prefix.clone().pushing(component)
+}
+
+fn sequence_number(name: Name<'_>) -> Option<u64> {
+    let last = name.components().last()?;
+    if last.typ.get() != NameComponent::TYPE_SEQUENCE_NUM {
+        return None;
+    }
+    Some(u64::try_decode(last.bytes).ok()?.0)
+}
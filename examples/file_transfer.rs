@@ -0,0 +1,299 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use reto::{
+    face::{
+        buffered::{default_buffered_receiver, BufferedFaceReceiver, BufferedReceiver},
+        local::{default_local_face, LocalReceiver, LocalSender},
+        FaceSender,
+    },
+    forwarder::{Forwarder, ForwarderError, InertMetrics},
+    io::{Decode, Encode, Write},
+    name::{Name, NameBuf, NameComponent},
+    packet::{Data, DataBuilder, Interest, InterestLifetime},
+    platform::{clock::MonotonicClock, sha::Sha256Hasher},
+    tables::reference::ReferenceTables,
+};
+
+const FILE_SIZE: usize = 300_000;
+const SEGMENT_SIZE: usize = 1000;
+
+// The producer pretends that every LOSS_PERIOD-th interest is lost, the first time it sees it
+const LOSS_PERIOD: u64 = 23;
+
+// Retransmission timeout, the interests live slightly less so that the
+//  retransmissions are not aggregated with the lost ones
+const RTO: Duration = Duration::from_millis(50);
+const INTEREST_LIFETIME_MS: u64 = 40;
+
+const INITIAL_SSTHRESH: f64 = 32.0;
+
+// A file published as /files/<file>/<version>/<segment> and fetched by a consumer
+//  that pipelines the interests with an AIMD window: it grows by one per data in
+//  slow start and by one per window afterwards, and halves on a timeout,
+//  after which the lost segments are asked again with a new nonce.
+// Everything runs in-process over local faces of a single forwarder.
+fn main() {
+    let mut forwarder = Forwarder::new(
+        MonotonicClock::new(),
+        Sha256Hasher::new(),
+        InertMetrics {},
+        ReferenceTables::default(),
+    );
+
+    let file: Vec<u8> = (0..FILE_SIZE).map(|i| (i * 7 % 251) as u8).collect();
+    let prefix = versioned_prefix(b"report.bin", 1);
+
+    let (forwarder_sender, receiver) = default_local_face();
+    let (sender, forwarder_receiver) = default_local_face();
+    let producer_face = forwarder
+        .add_face(forwarder_sender, forwarder_receiver)
+        .unwrap();
+    forwarder.register_name_prefix_for_forwarding(prefix.as_name(), producer_face, 0);
+    let mut producer = Producer {
+        prefix: prefix.clone(),
+        segments: file.chunks(SEGMENT_SIZE).map(Vec::from).collect(),
+        sender,
+        receiver: default_buffered_receiver(receiver),
+        queue: VecDeque::new(),
+        seen: 0,
+        dropped: Vec::new(),
+    };
+
+    let (forwarder_sender, receiver) = default_local_face();
+    let (sender, forwarder_receiver) = default_local_face();
+    forwarder
+        .add_face(forwarder_sender, forwarder_receiver)
+        .unwrap();
+    let mut consumer = Consumer {
+        prefix,
+        sender,
+        receiver: default_buffered_receiver(receiver),
+        window: 1.0,
+        ssthresh: INITIAL_SSTHRESH,
+        segment_count: None,
+        next_segment: 0,
+        in_flight: BTreeMap::new(),
+        received: BTreeMap::new(),
+        retransmissions: 0,
+        nonce: 0,
+    };
+
+    let started = Instant::now();
+    while !consumer.is_done() {
+        assert!(
+            started.elapsed() < Duration::from_secs(30),
+            "the transfer did not finish"
+        );
+
+        consumer.send_interests();
+        producer.process_incoming();
+        loop {
+            match forwarder.try_forward_batch(&[]) {
+                Ok(_) => {}
+                Err(ForwarderError::NothingToForward) => break,
+                Err(_) => panic!("forwarding failed"),
+            }
+        }
+        consumer.process_incoming();
+        consumer.check_timeouts();
+    }
+
+    let fetched: Vec<u8> = consumer.received.into_values().flatten().collect();
+    assert!(fetched == file, "the file was corrupted");
+    println!(
+        "Fetched {} bytes in {} segments in {:?}, {} interests were lost and {} retransmitted, final window {:.1}",
+        fetched.len(),
+        producer.segments.len(),
+        started.elapsed(),
+        producer.dropped.len(),
+        consumer.retransmissions,
+        consumer.window
+    );
+}
+
+struct Producer {
+    prefix: NameBuf,
+    segments: Vec<Vec<u8>>,
+    sender: LocalSender,
+    receiver: BufferedReceiver<LocalReceiver>,
+    // The segments that were asked but did not fit in the face yet
+    queue: VecDeque<u64>,
+    seen: u64,
+    dropped: Vec<u64>,
+}
+
+impl Producer {
+    fn process_incoming(&mut self) {
+        while let Ok((_, bytes)) = self.receiver.try_recv() {
+            let Ok((interest, _)) = Interest::try_decode(bytes) else {
+                continue;
+            };
+            let Some(segment) = segment_number(interest.name) else {
+                continue;
+            };
+            self.seen += 1;
+            if self.seen.is_multiple_of(LOSS_PERIOD) && !self.dropped.contains(&segment) {
+                self.dropped.push(segment);
+                continue;
+            }
+            self.queue.push_back(segment);
+        }
+
+        while let Some(segment) = self.queue.front().copied() {
+            let Some(content) = self.segments.get(segment as usize) else {
+                self.queue.pop_front();
+                continue;
+            };
+            let name = with_segment_number(&self.prefix, segment);
+            let last = self.segments.len() as u64 - 1;
+            let last_bytes = encoded(last);
+            let last_component =
+                NameComponent::new(NameComponent::TYPE_SEGMENT, &last_bytes).unwrap();
+            let packet = DataBuilder::new(name.as_name())
+                .content(content)
+                .final_block_id(last_component)
+                .digest_sign(&mut Sha256Hasher::new());
+
+            // Writing to a full face would block, so the rest waits for the next round
+            if self
+                .sender
+                .send_capacity()
+                .is_some_and(|capacity| capacity < packet.len())
+            {
+                break;
+            }
+            let _ = self.sender.write(&packet);
+            self.queue.pop_front();
+        }
+    }
+}
+
+struct Consumer {
+    prefix: NameBuf,
+    sender: LocalSender,
+    receiver: BufferedReceiver<LocalReceiver>,
+    window: f64,
+    ssthresh: f64,
+    // Known from the FinalBlockId of the first segment received
+    segment_count: Option<u64>,
+    next_segment: u64,
+    in_flight: BTreeMap<u64, Instant>,
+    received: BTreeMap<u64, Vec<u8>>,
+    retransmissions: usize,
+    nonce: u32,
+}
+
+impl Consumer {
+    fn is_done(&self) -> bool {
+        self.segment_count == Some(self.received.len() as u64)
+    }
+
+    fn send_interests(&mut self) {
+        // Only the first segment is asked until the number of segments is known
+        let limit = self.segment_count.unwrap_or(1);
+        while (self.in_flight.len() as f64) < self.window.floor() && self.next_segment < limit {
+            let segment = self.next_segment;
+            self.next_segment += 1;
+            self.express_interest(segment);
+        }
+    }
+
+    fn process_incoming(&mut self) {
+        while let Ok((_, bytes)) = self.receiver.try_recv() {
+            let Ok((data, _)) = Data::try_decode(bytes) else {
+                continue;
+            };
+            let Some(segment) = segment_number(data.name) else {
+                continue;
+            };
+            if self.in_flight.remove(&segment).is_none() {
+                continue;
+            }
+
+            if self.segment_count.is_none() {
+                let last = data
+                    .meta_info
+                    .as_ref()
+                    .and_then(|meta_info| meta_info.final_block_id.as_ref())
+                    .and_then(|id| u64::try_decode(id.component.bytes).ok())
+                    .map(|(last, _)| last)
+                    .expect("the segments must carry the FinalBlockId");
+                self.segment_count = Some(last + 1);
+            }
+
+            let content = data.content.as_ref().map(|c| c.bytes).unwrap_or_default();
+            self.received.insert(segment, Vec::from(content));
+
+            if self.window < self.ssthresh {
+                self.window += 1.0;
+            } else {
+                self.window += 1.0 / self.window;
+            }
+        }
+    }
+
+    fn check_timeouts(&mut self) {
+        let now = Instant::now();
+        let timed_out: Vec<u64> = self
+            .in_flight
+            .iter()
+            .filter(|(_, sent)| now.duration_since(**sent) >= RTO)
+            .map(|(segment, _)| *segment)
+            .collect();
+        if timed_out.is_empty() {
+            return;
+        }
+
+        // A single loss event, no matter how many interests were lost with it
+        self.ssthresh = (self.window / 2.0).max(2.0);
+        self.window = self.ssthresh;
+        for segment in timed_out {
+            self.retransmissions += 1;
+            self.express_interest(segment);
+        }
+    }
+
+    fn express_interest(&mut self, segment: u64) {
+        let name = with_segment_number(&self.prefix, segment);
+        self.nonce += 1;
+        let mut interest = Interest::new(name.as_name(), false, self.nonce.to_be_bytes());
+        interest.interest_lifetime = Some(InterestLifetime {
+            val: INTEREST_LIFETIME_MS,
+        });
+        let mut packet = Vec::new();
+        let _ = interest.encode(&mut packet);
+        let _ = self.sender.write(&packet);
+        self.in_flight.insert(segment, Instant::now());
+    }
+}
+
+fn encoded(value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let _ = value.encode(&mut bytes);
+    bytes
+}
+
+fn versioned_prefix(file: &[u8], version: u64) -> NameBuf {
+    let version_bytes = encoded(version);
+    NameBuf::new()
+        .pushing(NameComponent::generic(b"files"))
+        .pushing(NameComponent::generic(file))
+        .pushing(NameComponent::new(NameComponent::TYPE_VERSION_NAME, &version_bytes).unwrap())
+}
+
+fn with_segment_number(prefix: &NameBuf, segment: u64) -> NameBuf {
+    let bytes = encoded(segment);
+    let component = NameComponent::new(NameComponent::TYPE_SEGMENT, &bytes).unwrap();
+    prefix.clone().pushing(component)
+}
+
+fn segment_number(name: Name<'_>) -> Option<u64> {
+    let last = name.components().last()?;
+    if last.typ.get() != NameComponent::TYPE_SEGMENT {
+        return None;
+    }
+    Some(u64::try_decode(last.bytes).ok()?.0)
+}
@@ -0,0 +1,359 @@
+use std::time::{Duration, Instant};
+
+use reto::{
+    face::{
+        buffered::{default_buffered_receiver, BufferedFaceReceiver, BufferedReceiver},
+        local::{default_local_face, LocalReceiver, LocalSender},
+    },
+    forwarder::{Forwarder, ForwarderError, InertMetrics},
+    io::{Decode, Encode, Write},
+    name::{Name, NameBuf, NameComponent},
+    packet::{Data, DataBuilder, Interest},
+    platform::{clock::MonotonicClock, sha::Sha256Hasher},
+    security::{
+        keychain::{HmacSha256Key, KeyChain},
+        trust::{NamePattern, TrustError, TrustRule, TrustSchema},
+    },
+    tables::reference::ReferenceTables,
+};
+
+type LocalForwarder = Forwarder<MonotonicClock, Sha256Hasher, InertMetrics, ReferenceTables>;
+
+const READING_PERIOD: Duration = Duration::from_millis(10);
+const READINGS_TO_COLLECT: u64 = 5;
+
+// Sensors take a reading every period and publish it as
+//  /farm/sensor/<sensor>/reading/<sequence number>, signed with their own key,
+//  which is certified by the key of the farm. A collector asks for the readings
+//  one after the other and only accepts those that the trust schema allows:
+//  a sensor can only sign its own readings, and only the farm can certify sensors.
+// A rogue device with a valid certificate answers for a sensor that is not its own,
+//  and all of its readings are rejected.
+// For brevity all the keys are HMAC keys in a single KeyChain; a real deployment
+//  would use asymmetric keys (see the ed25519 feature) so that the collector
+//  only needs the certificates.
+// Everything runs in-process over local faces of a single forwarder.
+fn main() {
+    let mut forwarder = Forwarder::new(
+        MonotonicClock::new(),
+        Sha256Hasher::new(),
+        InertMetrics {},
+        ReferenceTables::default(),
+    );
+
+    let farm = NameBuf::new().pushing(NameComponent::generic(b"farm"));
+    let farm_key = KeyChain::key_name(farm.as_name(), b"root");
+    let mut keychain = KeyChain::new();
+    keychain.add_identity(farm.as_name()).unwrap();
+    let material = HmacSha256Key::new(b"farm secret", Sha256Hasher::new());
+    keychain
+        .add_key(farm.as_name(), farm_key.as_name(), material)
+        .unwrap();
+
+    // The temperature sensor is legitimate, while the humidity readings
+    //  are served by a device that is certified under another name
+    let mut sensors = [
+        Sensor::new(
+            &mut forwarder,
+            &mut keychain,
+            &farm_key,
+            b"temperature",
+            b"temperature",
+        ),
+        Sensor::new(
+            &mut forwarder,
+            &mut keychain,
+            &farm_key,
+            b"rogue",
+            b"humidity",
+        ),
+    ];
+
+    let mut schema = TrustSchema::new();
+    let literal = NameComponent::generic;
+    // /farm/sensor/<sensor>/reading/... is signed by /farm/sensor/<sensor>/KEY/...
+    schema.add_rule(TrustRule::new(
+        NamePattern::new()
+            .literal(literal(b"farm"))
+            .literal(literal(b"sensor"))
+            .capture(0)
+            .literal(literal(b"reading"))
+            .rest(),
+        NamePattern::new()
+            .literal(literal(b"farm"))
+            .literal(literal(b"sensor"))
+            .capture(0)
+            .literal(literal(b"KEY"))
+            .rest(),
+    ));
+    // /farm/sensor/<sensor>/KEY/... is signed by /farm/KEY/...
+    schema.add_rule(TrustRule::new(
+        NamePattern::new()
+            .literal(literal(b"farm"))
+            .literal(literal(b"sensor"))
+            .any()
+            .literal(literal(b"KEY"))
+            .rest(),
+        NamePattern::new()
+            .literal(literal(b"farm"))
+            .literal(literal(b"KEY"))
+            .rest(),
+    ));
+    schema.add_trust_anchor(farm_key.as_name());
+
+    let mut collector = Collector::new(&mut forwarder, &[b"temperature", b"humidity"]);
+
+    let started = Instant::now();
+    while !collector.is_done() {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "the collector did not get the readings"
+        );
+
+        for sensor in sensors.iter_mut() {
+            sensor.take_reading_if_due(&keychain);
+            sensor.process_incoming();
+        }
+        loop {
+            match forwarder.try_forward_batch(&[]) {
+                Ok(_) => {}
+                Err(ForwarderError::NothingToForward) => break,
+                Err(_) => panic!("forwarding failed"),
+            }
+        }
+        collector.process_incoming(&schema, &keychain);
+    }
+
+    for stream in collector.streams.iter() {
+        println!(
+            "{}: {} accepted, {} rejected",
+            String::from_utf8_lossy(&stream.sensor),
+            stream.accepted,
+            stream.rejected
+        );
+    }
+    assert_eq!(collector.streams[1].accepted, 0);
+}
+
+struct Sensor {
+    key_name: NameBuf,
+    prefix: NameBuf,
+    sender: LocalSender,
+    receiver: BufferedReceiver<LocalReceiver>,
+    readings: Vec<Vec<u8>>,
+    last_reading: Option<Instant>,
+    // Interests for the readings that have not been taken yet
+    pending: Vec<NameBuf>,
+}
+
+impl Sensor {
+    // The sensor gets a key certified by the farm, but publishes under the name it is given
+    fn new(
+        forwarder: &mut LocalForwarder,
+        keychain: &mut KeyChain,
+        farm_key: &NameBuf,
+        sensor: &[u8],
+        publishes_as: &[u8],
+    ) -> Self {
+        let identity = sensor_prefix(sensor);
+        let key_name = KeyChain::key_name(identity.as_name(), b"1");
+        keychain.add_identity(identity.as_name()).unwrap();
+        let secret = [sensor, b" secret"].concat();
+        let material = HmacSha256Key::new(&secret, Sha256Hasher::new());
+        keychain
+            .add_key(identity.as_name(), key_name.as_name(), material)
+            .unwrap();
+
+        let version = encoded(1);
+        let certificate_name = key_name
+            .clone()
+            .pushing(NameComponent::generic(b"farm"))
+            .pushing(NameComponent::new(NameComponent::TYPE_VERSION_NAME, &version).unwrap());
+        let mut signer = keychain.signer_for_key(farm_key.as_name()).unwrap();
+        let certificate = DataBuilder::new(certificate_name.as_name()).sign(signer.as_mut());
+        drop(signer);
+        keychain.add_certificate(&certificate).unwrap();
+
+        let (forwarder_sender, receiver) = default_local_face();
+        let (sender, forwarder_receiver) = default_local_face();
+        let face = forwarder
+            .add_face(forwarder_sender, forwarder_receiver)
+            .unwrap();
+        let prefix = sensor_prefix(publishes_as).pushing(NameComponent::generic(b"reading"));
+        forwarder.register_name_prefix_for_forwarding(prefix.as_name(), face, 0);
+
+        Self {
+            key_name,
+            prefix,
+            sender,
+            receiver: default_buffered_receiver(receiver),
+            readings: Vec::new(),
+            last_reading: None,
+            pending: Vec::new(),
+        }
+    }
+
+    fn take_reading_if_due(&mut self, keychain: &KeyChain) {
+        let now = Instant::now();
+        if self
+            .last_reading
+            .is_some_and(|last| now.duration_since(last) < READING_PERIOD)
+        {
+            return;
+        }
+        self.last_reading = Some(now);
+
+        let sequence = self.readings.len() as u64;
+        let name = with_sequence_number(&self.prefix, sequence);
+        let value = format!("{:.1}", 20.0 + (sequence % 7) as f64 * 0.3);
+        let mut signer = keychain.signer_for_key(self.key_name.as_name()).unwrap();
+        let packet = DataBuilder::new(name.as_name())
+            .content(value.as_bytes())
+            .freshness_period(READING_PERIOD.as_millis() as u64)
+            .sign(signer.as_mut());
+        self.readings.push(packet);
+
+        if let Some(position) = self.pending.iter().position(|n| *n == name) {
+            self.pending.remove(position);
+            let _ = self.sender.write(self.readings.last().unwrap());
+        }
+    }
+
+    fn process_incoming(&mut self) {
+        while let Ok((_, bytes)) = self.receiver.try_recv() {
+            let Ok((interest, _)) = Interest::try_decode(bytes) else {
+                continue;
+            };
+            let Some(sequence) = sequence_number(interest.name) else {
+                continue;
+            };
+            match self.readings.get(sequence as usize) {
+                Some(packet) => {
+                    let _ = self.sender.write(packet);
+                }
+                None => self.pending.push(NameBuf::from(interest.name)),
+            }
+        }
+    }
+}
+
+struct Stream {
+    sensor: Vec<u8>,
+    prefix: NameBuf,
+    next: u64,
+    accepted: u64,
+    rejected: u64,
+}
+
+struct Collector {
+    sender: LocalSender,
+    receiver: BufferedReceiver<LocalReceiver>,
+    streams: Vec<Stream>,
+    nonce: u32,
+}
+
+impl Collector {
+    fn new(forwarder: &mut LocalForwarder, sensors: &[&[u8]]) -> Self {
+        let (forwarder_sender, receiver) = default_local_face();
+        let (sender, forwarder_receiver) = default_local_face();
+        forwarder
+            .add_face(forwarder_sender, forwarder_receiver)
+            .unwrap();
+
+        let mut collector = Self {
+            sender,
+            receiver: default_buffered_receiver(receiver),
+            streams: sensors
+                .iter()
+                .map(|sensor| Stream {
+                    sensor: Vec::from(*sensor),
+                    prefix: sensor_prefix(sensor).pushing(NameComponent::generic(b"reading")),
+                    next: 0,
+                    accepted: 0,
+                    rejected: 0,
+                })
+                .collect(),
+            nonce: 0,
+        };
+        for index in 0..collector.streams.len() {
+            collector.express_interest(index);
+        }
+        collector
+    }
+
+    fn is_done(&self) -> bool {
+        self.streams
+            .iter()
+            .all(|stream| stream.accepted + stream.rejected >= READINGS_TO_COLLECT)
+    }
+
+    fn process_incoming(&mut self, schema: &TrustSchema, keychain: &KeyChain) {
+        while let Ok((_, bytes)) = self.receiver.try_recv() {
+            let packet = bytes.to_vec();
+            let Ok((data, _)) = Data::try_decode(&packet) else {
+                continue;
+            };
+            let Some(index) = self
+                .streams
+                .iter()
+                .position(|stream| stream.prefix.as_name().is_prefix_of(data.name))
+            else {
+                continue;
+            };
+
+            let stream = &mut self.streams[index];
+            let sensor = String::from_utf8_lossy(&stream.sensor);
+            match schema.validate(&data, keychain) {
+                Ok(()) => {
+                    let value = data.content.as_ref().map(|c| c.bytes).unwrap_or_default();
+                    println!("{sensor}: {}", String::from_utf8_lossy(value));
+                    stream.accepted += 1;
+                }
+                Err(err) => {
+                    assert_eq!(err, TrustError::NoMatchingRule);
+                    println!("{sensor}: rejected a reading, {err:?}");
+                    stream.rejected += 1;
+                }
+            }
+            stream.next += 1;
+            self.express_interest(index);
+        }
+    }
+
+    fn express_interest(&mut self, index: usize) {
+        let stream = &self.streams[index];
+        let name = with_sequence_number(&stream.prefix, stream.next);
+        self.nonce += 1;
+        let interest = Interest::new(name.as_name(), false, self.nonce.to_be_bytes());
+        let mut packet = Vec::new();
+        let _ = interest.encode(&mut packet);
+        let _ = self.sender.write(&packet);
+    }
+}
+
+fn encoded(value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let _ = value.encode(&mut bytes);
+    bytes
+}
+
+fn sensor_prefix(sensor: &[u8]) -> NameBuf {
+    NameBuf::new()
+        .pushing(NameComponent::generic(b"farm"))
+        .pushing(NameComponent::generic(b"sensor"))
+        .pushing(NameComponent::generic(sensor))
+}
+
+fn with_sequence_number(prefix: &NameBuf, sequence: u64) -> NameBuf {
+    let bytes = encoded(sequence);
+    let component = NameComponent::new(NameComponent::TYPE_SEQUENCE_NUM, &bytes).unwrap();
+    prefix.clone().pushing(component)
+}
+
+fn sequence_number(name: Name<'_>) -> Option<u64> {
+    let last = name.components().last()?;
+    if last.typ.get() != NameComponent::TYPE_SEQUENCE_NUM {
+        return None;
+    }
+    Some(u64::try_decode(last.bytes).ok()?.0)
+}
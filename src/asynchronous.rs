@@ -0,0 +1,354 @@
+use alloc::{boxed::Box, rc::Rc};
+use core::{
+    cell::RefCell,
+    future::{poll_fn, Future},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use crate::{
+    clock::Clock,
+    face::{AsyncFaceReceiver, AsyncFaceSender, FaceError, FaceReceiver, FaceSender},
+    forwarder::{FaceToken, Forwarder, ForwarderError, ForwarderMetrics},
+    hash::{Hasher, Sha256Digest},
+    tables::Tables,
+};
+
+// How often the forwarder wakes up without any traffic, so that the interests and
+//  the routes time out and the paused faces are read again
+pub const HOUSEKEEPING_INTERVAL: Duration = Duration::from_millis(100);
+
+// How many packets are forwarded before yielding to the other tasks of the runtime
+const PACKETS_PER_POLL: usize = 64;
+
+// The waker of the task currently polling the forwarder, which the faces pass on
+//  to the async faces they wrap
+type SharedWaker = Rc<RefCell<Option<Waker>>>;
+
+// Runs the forwarder as a task of any async runtime, e.g. tokio or smol, instead
+//  of the dedicated thread of BlockingForwarder.
+// Nothing here depends on the runtime: the faces are polled with the waker of the task
+//  and the runtime only provides the timer to run.
+pub struct AsyncForwarder<C, H, M, T>
+where
+    C: Clock,
+    H: Hasher<Digest = Sha256Digest>,
+    M: ForwarderMetrics,
+    T: Tables,
+{
+    forwarder: Forwarder<C, H, M, T>,
+    waker: SharedWaker,
+}
+
+impl<C, H, M, T> AsyncForwarder<C, H, M, T>
+where
+    C: Clock,
+    H: Hasher<Digest = Sha256Digest>,
+    M: ForwarderMetrics,
+    T: Tables,
+{
+    pub fn new(forwarder: Forwarder<C, H, M, T>) -> Self {
+        Self {
+            forwarder,
+            waker: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    pub fn forwarder(&self) -> &Forwarder<C, H, M, T> {
+        &self.forwarder
+    }
+
+    // The faces that are not async can also be added here, but nothing wakes the task
+    //  when they are ready, so they are only read when something else happens
+    pub fn forwarder_mut(&mut self) -> &mut Forwarder<C, H, M, T> {
+        &mut self.forwarder
+    }
+
+    pub fn into_inner(self) -> Forwarder<C, H, M, T> {
+        self.forwarder
+    }
+
+    pub fn add_face<FS, FR>(&mut self, sender: FS, receiver: FR) -> Option<FaceToken>
+    where
+        FS: AsyncFaceSender + 'static,
+        FR: AsyncFaceReceiver + 'static,
+    {
+        let sender = AsyncSender {
+            sender,
+            waker: self.waker.clone(),
+            flush_pending: false,
+        };
+        let receiver = AsyncReceiver {
+            receiver,
+            waker: self.waker.clone(),
+        };
+        self.forwarder.add_face(sender, receiver)
+    }
+
+    // Resolves once a packet is forwarded, like BlockingForwarder::forward.
+    // Only the faces wake it up, so the timeouts are handled when the next packet
+    //  arrives, unlike with run.
+    pub fn forward(&mut self) -> impl Future<Output = Result<FaceToken, ForwarderError>> + '_ {
+        poll_fn(move |cx| {
+            self.set_waker(cx);
+            match self.forwarder.try_forward_from_any_face(&[]) {
+                Err(ForwarderError::NothingToForward) => Poll::Pending,
+                ret => Poll::Ready(ret),
+            }
+        })
+    }
+
+    // Forwards until the last face is gone, removing the faces that fail on the way.
+    // The sleep gives the timer of the runtime, e.g. "|d| smol::Timer::after(d)"
+    //  or "tokio::time::sleep", which wakes the forwarder for the housekeeping.
+    pub async fn run<S, F>(&mut self, mut sleep: S)
+    where
+        S: FnMut(Duration) -> F,
+        F: Future,
+    {
+        let mut timer = Box::pin(sleep(HOUSEKEEPING_INTERVAL));
+        poll_fn(|cx| {
+            self.set_waker(cx);
+            loop {
+                match self.forwarder.try_forward_budget(PACKETS_PER_POLL, &[]) {
+                    Ok(processed) if processed < PACKETS_PER_POLL => continue,
+                    Ok(_) => {
+                        // Lets the other tasks run before coming back to the rest
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    Err(ForwarderError::FaceDisconnected(face))
+                    | Err(ForwarderError::FaceUnrecoverableError(face, _)) => {
+                        self.forwarder.remove_face(face);
+                    }
+                    Err(_) => {
+                        if self.forwarder.face_count() == 0 {
+                            return Poll::Ready(());
+                        }
+                        if timer.as_mut().poll(cx).is_ready() {
+                            // The next poll also does the housekeeping
+                            timer = Box::pin(sleep(HOUSEKEEPING_INTERVAL));
+                            cx.waker().wake_by_ref();
+                        }
+                        return Poll::Pending;
+                    }
+                }
+            }
+        })
+        .await
+    }
+
+    fn set_waker(&mut self, cx: &Context<'_>) {
+        let mut waker = self.waker.borrow_mut();
+        match waker.as_mut() {
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => *waker = Some(cx.waker().clone()),
+        }
+    }
+}
+
+fn with_context<R>(waker: &SharedWaker, f: impl FnOnce(&mut Context<'_>) -> R) -> R {
+    let waker = waker.borrow();
+    let waker = waker.as_ref().unwrap_or(Waker::noop());
+    f(&mut Context::from_waker(waker))
+}
+
+struct AsyncSender<S: AsyncFaceSender> {
+    sender: S,
+    waker: SharedWaker,
+    flush_pending: bool,
+}
+
+impl<S: AsyncFaceSender> FaceSender for AsyncSender<S> {
+    fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+        let sender = &mut self.sender;
+        with_context(&self.waker, |cx| {
+            // The new bytes wait until the previous ones are flushed
+            if self.flush_pending {
+                match sender.poll_flush(cx) {
+                    Poll::Ready(Ok(())) => self.flush_pending = false,
+                    Poll::Ready(Err(err)) => return Err(err),
+                    Poll::Pending => return Ok(0),
+                }
+            }
+            match sender.poll_send(cx, src) {
+                Poll::Ready(ret) => ret,
+                Poll::Pending => Ok(0),
+            }
+        })
+    }
+
+    fn flush(&mut self) -> Result<(), FaceError> {
+        let sender = &mut self.sender;
+        with_context(&self.waker, |cx| match sender.poll_flush(cx) {
+            Poll::Ready(ret) => ret,
+            Poll::Pending => {
+                self.flush_pending = true;
+                Ok(())
+            }
+        })
+    }
+}
+
+struct AsyncReceiver<R: AsyncFaceReceiver> {
+    receiver: R,
+    waker: SharedWaker,
+}
+
+impl<R: AsyncFaceReceiver> FaceReceiver for AsyncReceiver<R> {
+    fn try_recv(&mut self, dst: &mut [u8]) -> Result<usize, FaceError> {
+        let receiver = &mut self.receiver;
+        with_context(&self.waker, |cx| match receiver.poll_recv(cx, dst) {
+            Poll::Ready(ret) => ret,
+            Poll::Pending => Ok(0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::VecDeque, rc::Rc, sync::Arc, vec::Vec};
+    use core::{
+        cell::RefCell,
+        future::{pending, Future},
+        pin::pin,
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Poll, Waker},
+    };
+    use std::task::Wake;
+
+    use crate::{
+        asynchronous::AsyncForwarder,
+        face::{AsyncFaceReceiver, AsyncFaceSender, FaceError},
+        forwarder::{Forwarder, InertMetrics},
+        io::Encode,
+        name::{Name, NameComponent},
+        packet::Interest,
+        platform::sha::Sha256Hasher,
+        tables::reference::ReferenceTables,
+    };
+
+    #[derive(Default)]
+    struct Pipe {
+        bytes: VecDeque<u8>,
+        waker: Option<Waker>,
+        closed: bool,
+    }
+
+    // One direction of an in-memory async face
+    #[derive(Clone, Default)]
+    struct PipeEnd(Rc<RefCell<Pipe>>);
+
+    impl PipeEnd {
+        fn push(&self, bytes: &[u8]) {
+            let mut pipe = self.0.borrow_mut();
+            pipe.bytes.extend(bytes);
+            if let Some(waker) = pipe.waker.take() {
+                waker.wake();
+            }
+        }
+
+        fn close(&self) {
+            self.0.borrow_mut().closed = true;
+            if let Some(waker) = self.0.borrow_mut().waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    impl AsyncFaceReceiver for PipeEnd {
+        fn poll_recv(
+            &mut self,
+            cx: &mut Context<'_>,
+            dst: &mut [u8],
+        ) -> Poll<Result<usize, FaceError>> {
+            let mut pipe = self.0.borrow_mut();
+            if pipe.bytes.is_empty() {
+                if pipe.closed {
+                    return Poll::Ready(Err(FaceError::Disconnected));
+                }
+                pipe.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+            let len = dst.len().min(pipe.bytes.len());
+            for (dst, byte) in dst.iter_mut().zip(pipe.bytes.drain(..len)) {
+                *dst = byte;
+            }
+            Poll::Ready(Ok(len))
+        }
+    }
+
+    impl AsyncFaceSender for PipeEnd {
+        fn poll_send(
+            &mut self,
+            _cx: &mut Context<'_>,
+            src: &[u8],
+        ) -> Poll<Result<usize, FaceError>> {
+            self.push(src);
+            Poll::Ready(Ok(src.len()))
+        }
+    }
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_async_forwarder() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let mut forwarder = AsyncForwarder::new(forwarder);
+        let (to_face1, from_face1) = (PipeEnd::default(), PipeEnd::default());
+        let face1 = forwarder
+            .add_face(to_face1.clone(), from_face1.clone())
+            .unwrap();
+        let (to_face2, from_face2) = (PipeEnd::default(), PipeEnd::default());
+        let face2 = forwarder
+            .add_face(to_face2.clone(), from_face2.clone())
+            .unwrap();
+        let root = Name::new();
+        let comps = &[NameComponent::generic(b"ndn")];
+        let name = root.adding_components(comps);
+        forwarder
+            .forwarder_mut()
+            .register_name_prefix_for_forwarding(name, face2, 0);
+
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        // Waits for the faces, which wake the task once the interest arrives
+        {
+            let mut forward = pin!(forwarder.forward());
+            assert!(forward.as_mut().poll(&mut cx).is_pending());
+            let mut interest = Vec::new();
+            assert!(Interest::new(name, false, [1; 4])
+                .encode(&mut interest)
+                .is_ok());
+            from_face1.push(&interest);
+            assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+            assert!(matches!(forward.poll(&mut cx), Poll::Ready(Ok(face)) if face == face1));
+            assert_eq!(to_face2.0.borrow().bytes.len(), interest.len());
+        }
+
+        // Runs until the faces are gone, without the timer ever firing
+        from_face1.close();
+        from_face2.close();
+        let mut run = pin!(forwarder.run(|_| pending::<()>()));
+        let mut polls = 0;
+        while run.as_mut().poll(&mut cx).is_pending() {
+            polls += 1;
+            assert!(polls < 10);
+        }
+        assert!(to_face1.0.borrow().bytes.is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+use alloc::boxed::Box;
+
+use crate::{
+    clock::Clock,
+    forwarder::{Forwarder, ForwarderMetrics},
+    hash::{Hasher, Sha256Digest},
+    tables::{
+        policy::{AdmissionPolicy, LruPolicy, ReplacementPolicy},
+        reference::{ReferenceTables, DEFAULT_DNL_CAPACITY, DEFAULT_DNL_FALSE_POSITIVE_ONE_IN},
+        TableParams, Tables,
+    },
+};
+
+// Everything that can be tuned when a forwarder is built, in one place.
+// The defaults are those of Forwarder::new with ReferenceTables::default.
+// The packet size is not here, since the receive buffers are sized by MAX_PACKET_SIZE.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ForwarderConfig {
+    // The timing of the tables, which can also be changed later (see Tables::set_params)
+    pub tables: TableParams,
+    // The content store is only bounded by data_cache_duration_ms by default
+    pub cs_capacity: Option<usize>,
+    pub cs_byte_budget: Option<usize>,
+    // The nonces per dead nonce duration, with at most one in dead_nonce_false_positive_one_in
+    //  new interests dropped as a loop by mistake
+    pub dead_nonce_list_capacity: usize,
+    pub dead_nonce_false_positive_one_in: u32,
+    pub serve_stale: bool,
+    pub prefix_stats: bool,
+}
+
+impl Default for ForwarderConfig {
+    fn default() -> Self {
+        Self {
+            tables: TableParams::default(),
+            cs_capacity: None,
+            cs_byte_budget: None,
+            dead_nonce_list_capacity: DEFAULT_DNL_CAPACITY,
+            dead_nonce_false_positive_one_in: DEFAULT_DNL_FALSE_POSITIVE_ONE_IN,
+            serve_stale: false,
+            prefix_stats: false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ConfigError {
+    // The tables would never be pruned
+    ZeroPruneInterval,
+    // The looping interests would not be detected
+    ZeroDeadNonceDuration,
+    ZeroDeadNonceListCapacity,
+    ZeroDeadNonceFalsePositiveRate,
+    // The minimum is above the maximum
+    InterestLifetimeLimits,
+    // The default is outside the limits or zero
+    DefaultInterestLifetime,
+}
+
+impl ForwarderConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let tables = &self.tables;
+        if tables.prune_interval_ms == 0 {
+            return Err(ConfigError::ZeroPruneInterval);
+        }
+        if tables.dead_nonce_duration_ms == 0 {
+            return Err(ConfigError::ZeroDeadNonceDuration);
+        }
+        if self.dead_nonce_list_capacity == 0 {
+            return Err(ConfigError::ZeroDeadNonceListCapacity);
+        }
+        if self.dead_nonce_false_positive_one_in == 0 {
+            return Err(ConfigError::ZeroDeadNonceFalsePositiveRate);
+        }
+        if tables.min_interest_lifetime_ms > tables.max_interest_lifetime_ms {
+            return Err(ConfigError::InterestLifetimeLimits);
+        }
+        let default_ms = tables.default_interest_lifetime_ms;
+        if default_ms == 0
+            || default_ms < tables.min_interest_lifetime_ms
+            || default_ms > tables.max_interest_lifetime_ms
+        {
+            return Err(ConfigError::DefaultInterestLifetime);
+        }
+        Ok(())
+    }
+}
+
+// Builds a forwarder with the reference tables from a validated configuration.
+// The hooks that are not part of the configuration, e.g. the nonce source or the
+//  registration policy, are set on the forwarder once it is built.
+pub struct ForwarderBuilder<C, H, M>
+where
+    C: Clock,
+    H: Hasher<Digest = Sha256Digest>,
+    M: ForwarderMetrics,
+{
+    clock: C,
+    hasher: H,
+    metrics: M,
+    config: ForwarderConfig,
+    replacement_policy: Option<Box<dyn ReplacementPolicy>>,
+    admission_policy: Option<Box<dyn AdmissionPolicy>>,
+}
+
+impl<C, H, M> ForwarderBuilder<C, H, M>
+where
+    C: Clock,
+    H: Hasher<Digest = Sha256Digest>,
+    M: ForwarderMetrics,
+{
+    pub fn new(clock: C, hasher: H, metrics: M) -> Self {
+        Self {
+            clock,
+            hasher,
+            metrics,
+            config: ForwarderConfig::default(),
+            replacement_policy: None,
+            admission_policy: None,
+        }
+    }
+
+    pub fn config(mut self, config: ForwarderConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    // Chooses the victims once cs_capacity or cs_byte_budget is reached, LRU by default
+    pub fn replacement_policy(mut self, policy: Box<dyn ReplacementPolicy>) -> Self {
+        self.replacement_policy = Some(policy);
+        self
+    }
+
+    pub fn admission_policy(mut self, policy: Box<dyn AdmissionPolicy>) -> Self {
+        self.admission_policy = Some(policy);
+        self
+    }
+
+    pub fn build(self) -> Result<Forwarder<C, H, M, ReferenceTables>, ConfigError> {
+        let config = self.config;
+        config.validate()?;
+
+        let mut tables = ReferenceTables::default().with_dead_nonce_list_capacity(
+            config.dead_nonce_list_capacity,
+            config.dead_nonce_false_positive_one_in,
+        );
+        let replacement_policy = self
+            .replacement_policy
+            .unwrap_or_else(|| Box::new(LruPolicy::default()));
+        tables = tables.with_content_store_capacity(
+            config.cs_capacity.unwrap_or(usize::MAX),
+            replacement_policy,
+        );
+        if let Some(bytes) = config.cs_byte_budget {
+            tables = tables.with_content_store_byte_budget(bytes);
+        }
+        if let Some(policy) = self.admission_policy {
+            tables = tables.with_admission_policy(policy);
+        }
+        // Also moves the dead nonce list to the configured duration
+        tables.set_params(&config.tables);
+
+        let mut forwarder = Forwarder::new(self.clock, self.hasher, self.metrics, tables);
+        forwarder.set_serve_stale(config.serve_stale);
+        forwarder
+            .prefix_stats_mut()
+            .set_enabled(config.prefix_stats);
+        Ok(forwarder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{ConfigError, ForwarderBuilder, ForwarderConfig},
+        forwarder::InertMetrics,
+        platform::sha::Sha256Hasher,
+        tables::TableParams,
+    };
+
+    #[test]
+    fn test_builder() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let config = ForwarderConfig {
+            tables: TableParams {
+                data_cache_duration_ms: 500,
+                dead_nonce_duration_ms: 2000,
+                min_interest_lifetime_ms: 100,
+                max_interest_lifetime_ms: 10 * 1000,
+                ..TableParams::default()
+            },
+            cs_capacity: Some(10),
+            serve_stale: true,
+            prefix_stats: true,
+            ..ForwarderConfig::default()
+        };
+        let forwarder = ForwarderBuilder::new(clock, Sha256Hasher::new(), InertMetrics {})
+            .config(config)
+            .build()
+            .ok()
+            .unwrap();
+        assert_eq!(forwarder.tables().params(), config.tables);
+        assert!(forwarder.prefix_stats().is_enabled());
+
+        let invalid = [
+            (
+                TableParams {
+                    prune_interval_ms: 0,
+                    ..TableParams::default()
+                },
+                ConfigError::ZeroPruneInterval,
+            ),
+            (
+                TableParams {
+                    min_interest_lifetime_ms: 2000,
+                    max_interest_lifetime_ms: 1000,
+                    ..TableParams::default()
+                },
+                ConfigError::InterestLifetimeLimits,
+            ),
+            (
+                TableParams {
+                    max_interest_lifetime_ms: 1000,
+                    ..TableParams::default()
+                },
+                ConfigError::DefaultInterestLifetime,
+            ),
+        ];
+        for (tables, error) in invalid {
+            let config = ForwarderConfig {
+                tables,
+                ..ForwarderConfig::default()
+            };
+            assert_eq!(config.validate(), Err(error));
+            let clock = crate::platform::clock::MonotonicClock::new();
+            let built = ForwarderBuilder::new(clock, Sha256Hasher::new(), InertMetrics {})
+                .config(config)
+                .build();
+            assert!(matches!(built, Err(e) if e == error));
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use alloc::boxed::Box;
+use core::fmt::Arguments;
+
+// Verbosity of the diagnostic messages, ordered from the least to the most verbose.
+// A message is recorded only if its level is at most the level set for its subsystem.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum Level {
+    Off,
+    Error,
+    #[default]
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Subsystem {
+    Faces,
+    Tables,
+    Strategy,
+    Management,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 4] = [
+        Subsystem::Faces,
+        Subsystem::Tables,
+        Subsystem::Strategy,
+        Subsystem::Management,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Subsystem::Faces => "faces",
+            Subsystem::Tables => "tables",
+            Subsystem::Strategy => "strategy",
+            Subsystem::Management => "mgmt",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Subsystem::Faces => 0,
+            Subsystem::Tables => 1,
+            Subsystem::Strategy => 2,
+            Subsystem::Management => 3,
+        }
+    }
+}
+
+// Receives the diagnostic messages that pass the level filter.
+// The message is only formatted if the sink chooses to do so,
+//  so filtered-out messages cost almost nothing.
+pub trait DiagnosticsSink {
+    fn record(&mut self, subsystem: Subsystem, level: Level, message: Arguments<'_>);
+}
+
+// Per-subsystem verbosity levels together with the sink for the messages.
+// The levels can be changed at any time, e.g. to debug a misbehaving
+//  forwarder without restarting it.
+pub struct Diagnostics {
+    levels: [Level; Subsystem::ALL.len()],
+    sink: Option<Box<dyn DiagnosticsSink>>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self {
+            levels: [Level::default(); Subsystem::ALL.len()],
+            sink: None,
+        }
+    }
+
+    pub fn set_sink<S>(&mut self, sink: S)
+    where
+        S: DiagnosticsSink + 'static,
+    {
+        self.sink = Some(Box::new(sink));
+    }
+
+    pub fn remove_sink(&mut self) {
+        self.sink = None;
+    }
+
+    pub fn level(&self, subsystem: Subsystem) -> Level {
+        self.levels[subsystem.index()]
+    }
+
+    pub fn set_level(&mut self, subsystem: Subsystem, level: Level) {
+        self.levels[subsystem.index()] = level;
+    }
+
+    pub fn set_all_levels(&mut self, level: Level) {
+        self.levels = [level; Subsystem::ALL.len()];
+    }
+
+    pub fn is_enabled(&self, subsystem: Subsystem, level: Level) -> bool {
+        level != Level::Off && level <= self.level(subsystem) && self.sink.is_some()
+    }
+
+    pub fn record(&mut self, subsystem: Subsystem, level: Level, message: Arguments<'_>) {
+        if !self.is_enabled(subsystem, level) {
+            return;
+        }
+        if let Some(sink) = self.sink.as_mut() {
+            sink.record(subsystem, level, message);
+        }
+    }
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, string::String, vec::Vec};
+    use core::{cell::RefCell, fmt::Write};
+
+    use crate::diagnostics::{Diagnostics, DiagnosticsSink, Level, Subsystem};
+
+    struct CollectingSink {
+        messages: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl DiagnosticsSink for CollectingSink {
+        fn record(
+            &mut self,
+            subsystem: Subsystem,
+            _level: Level,
+            message: core::fmt::Arguments<'_>,
+        ) {
+            let mut s = String::new();
+            let _ = write!(s, "{}: {}", subsystem.name(), message);
+            self.messages.borrow_mut().push(s);
+        }
+    }
+
+    #[test]
+    fn test_levels() {
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let mut diag = Diagnostics::new();
+
+        // Nothing is enabled without a sink
+        assert!(!diag.is_enabled(Subsystem::Faces, Level::Error));
+
+        diag.set_sink(CollectingSink {
+            messages: messages.clone(),
+        });
+        assert!(diag.is_enabled(Subsystem::Faces, Level::Warn));
+        assert!(!diag.is_enabled(Subsystem::Faces, Level::Info));
+        assert!(!diag.is_enabled(Subsystem::Faces, Level::Off));
+
+        diag.record(Subsystem::Faces, Level::Warn, format_args!("a {}", 1));
+        diag.record(Subsystem::Tables, Level::Debug, format_args!("b"));
+
+        diag.set_level(Subsystem::Tables, Level::Trace);
+        diag.record(Subsystem::Tables, Level::Debug, format_args!("c"));
+        diag.record(Subsystem::Strategy, Level::Debug, format_args!("d"));
+
+        diag.set_all_levels(Level::Off);
+        diag.record(Subsystem::Faces, Level::Error, format_args!("e"));
+        assert_eq!(diag.level(Subsystem::Management), Level::Off);
+
+        assert_eq!(*messages.borrow(), ["faces: a 1", "tables: c"]);
+
+        diag.set_all_levels(Level::Trace);
+        diag.remove_sink();
+        diag.record(Subsystem::Faces, Level::Error, format_args!("f"));
+        assert_eq!(messages.borrow().len(), 2);
+    }
+}
@@ -0,0 +1,264 @@
+use alloc::{rc::Rc, vec::Vec};
+use core::cell::RefCell;
+
+use crate::{
+    face::{
+        lp::{LpPacket, NackReason},
+        FaceError, FaceReceiver, FaceSender,
+    },
+    forwarder::MAX_PACKET_SIZE,
+    io::{Decode, Encode},
+    packet::{Data, Interest},
+    tlv::{TlvDecode, TlvDecodingError, TlvEncode, VarintDecodingError, TLV},
+};
+
+// How many bytes can wait in each direction
+pub const DEFAULT_APP_FACE_CAPACITY: usize = 10 * MAX_PACKET_SIZE;
+
+// The packets the forwarder hands to the application
+pub enum AppPacket<'a> {
+    Interest(Interest<'a>),
+    Data(Data<'a>),
+    // The interest the application sent could not be satisfied
+    Nack(Interest<'a>, NackReason),
+}
+
+// The application end of an in-process face, which sends and receives the packets
+//  instead of the bytes. Add the other two ends to the forwarder.
+pub struct AppFace {
+    shared: Rc<RefCell<Shared>>,
+}
+
+pub struct AppFaceSender {
+    shared: Rc<RefCell<Shared>>,
+}
+
+pub struct AppFaceReceiver {
+    shared: Rc<RefCell<Shared>>,
+}
+
+struct Shared {
+    capacity: usize,
+    to_forwarder: Vec<u8>,
+    to_app: Vec<u8>,
+    app_dropped: bool,
+}
+
+pub fn app_face(capacity: usize) -> (AppFace, AppFaceSender, AppFaceReceiver) {
+    let shared = Rc::new(RefCell::new(Shared {
+        capacity,
+        to_forwarder: Vec::new(),
+        to_app: Vec::new(),
+        app_dropped: false,
+    }));
+    (
+        AppFace {
+            shared: Rc::clone(&shared),
+        },
+        AppFaceSender {
+            shared: Rc::clone(&shared),
+        },
+        AppFaceReceiver { shared },
+    )
+}
+
+pub fn default_app_face() -> (AppFace, AppFaceSender, AppFaceReceiver) {
+    app_face(DEFAULT_APP_FACE_CAPACITY)
+}
+
+impl AppFace {
+    pub fn express_interest(&mut self, interest: &Interest<'_>) -> Result<(), FaceError> {
+        self.push(interest)
+    }
+
+    pub fn put_data(&mut self, data: &Data<'_>) -> Result<(), FaceError> {
+        self.push(data)
+    }
+
+    // Calls the closure with the next packet from the forwarder, if there is one.
+    // The packets that cannot be decoded are skipped.
+    pub fn try_recv<R>(
+        &mut self,
+        f: impl FnOnce(AppPacket<'_>) -> R,
+    ) -> Result<Option<R>, FaceError> {
+        let mut shared = self.shared.borrow_mut();
+        loop {
+            let (tlv, len) = match TLV::try_decode(&shared.to_app) {
+                Ok(decoded) => decoded,
+                Err(
+                    TlvDecodingError::CannotDecodeValue { .. }
+                    | TlvDecodingError::CannotDecodeType {
+                        err: VarintDecodingError::BufferTooShort,
+                    }
+                    | TlvDecodingError::CannotDecodeLength {
+                        err: VarintDecodingError::BufferTooShort,
+                        ..
+                    },
+                ) => {
+                    // Only a part of the packet is here yet
+                    if Rc::strong_count(&self.shared) <= 1 {
+                        return Err(FaceError::Disconnected);
+                    }
+                    return Ok(None);
+                }
+                Err(_) => return Err(FaceError::Fatal(0)),
+            };
+            if let Some(packet) = Self::decode(tlv) {
+                let result = f(packet);
+                shared.to_app.drain(..len);
+                return Ok(Some(result));
+            }
+            shared.to_app.drain(..len);
+        }
+    }
+
+    fn decode(tlv: TLV<'_>) -> Option<AppPacket<'_>> {
+        match tlv.typ.get() {
+            Interest::TLV_TYPE => Interest::try_decode_from_inner(tlv.val).map(AppPacket::Interest),
+            Data::TLV_TYPE => Data::try_decode_from_inner(tlv.val).map(AppPacket::Data),
+            LpPacket::TLV_TYPE => {
+                let packet = LpPacket::try_decode_from_inner(tlv.val)?;
+                let (inner, _) = TLV::try_decode(packet.fragment?.bytes).ok()?;
+                match packet.nack {
+                    Some(nack) => {
+                        let interest = Interest::try_decode_from_inner(inner.val)?;
+                        Some(AppPacket::Nack(interest, nack.reason))
+                    }
+                    // The packets that come with a CongestionMark
+                    None => Self::decode(inner),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn push<P: Encode>(&mut self, packet: &P) -> Result<(), FaceError> {
+        // Both forwarder ends are gone
+        if Rc::strong_count(&self.shared) <= 1 {
+            return Err(FaceError::Disconnected);
+        }
+        let mut shared = self.shared.borrow_mut();
+        if shared.to_forwarder.len() + packet.encoded_length() > shared.capacity {
+            return Err(FaceError::WouldBlock);
+        }
+        let _ = packet.encode(&mut shared.to_forwarder);
+        Ok(())
+    }
+}
+
+impl Drop for AppFace {
+    fn drop(&mut self) {
+        self.shared.borrow_mut().app_dropped = true;
+    }
+}
+
+impl FaceSender for AppFaceSender {
+    fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+        let mut shared = self.shared.borrow_mut();
+        if shared.app_dropped {
+            return Err(FaceError::Disconnected);
+        }
+        let len = src.len().min(shared.capacity - shared.to_app.len());
+        shared.to_app.extend_from_slice(&src[..len]);
+        Ok(len)
+    }
+
+    fn send_capacity(&self) -> Option<usize> {
+        let shared = self.shared.borrow();
+        Some(shared.capacity - shared.to_app.len())
+    }
+}
+
+impl FaceReceiver for AppFaceReceiver {
+    fn try_recv(&mut self, dst: &mut [u8]) -> Result<usize, FaceError> {
+        let mut shared = self.shared.borrow_mut();
+        let len = dst.len().min(shared.to_forwarder.len());
+
+        // We only report the disconnect after draining the available bytes
+        if len == 0 && shared.app_dropped {
+            return Err(FaceError::Disconnected);
+        }
+
+        dst[..len].copy_from_slice(&shared.to_forwarder[..len]);
+        shared.to_forwarder.drain(..len);
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        face::{
+            app::{default_app_face, AppPacket},
+            lp::NackReason,
+        },
+        forwarder::{Forwarder, InertMetrics},
+        hash::Hasher,
+        name::{Name, NameComponent},
+        packet::{Data, Interest, SignatureInfo, SignatureValue},
+        platform::sha::Sha256Hasher,
+        tables::reference::ReferenceTables,
+    };
+
+    #[test]
+    fn test_app_face() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (mut consumer, fs1, fr1) = default_app_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (mut producer, fs2, fr2) = default_app_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        forwarder.set_nack_on_no_route(face1, true);
+
+        let comps = &[NameComponent::generic(b"app"), NameComponent::generic(b"x")];
+        let root = Name::new();
+        let prefix = root.adding_components(&comps[..1]);
+        let name = prefix.adding_components(&comps[1..]);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+
+        // The interest reaches the producer as it was sent
+        let interest = Interest::new(name, false, [1; 4]);
+        assert!(consumer.express_interest(&interest).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        let received = producer.try_recv(|packet| match packet {
+            AppPacket::Interest(received) => received.name == name,
+            _ => false,
+        });
+        assert_eq!(received, Ok(Some(true)));
+        assert_eq!(producer.try_recv(|_| ()), Ok(None));
+
+        let mut data = Data::new_unsigned(name, b"content", SignatureInfo::new_digest_sha256());
+        let mut hasher = Sha256Hasher::new();
+        data.hash_signed_portion(&mut hasher);
+        let digest = hasher.finalize_reset();
+        data.signature_value = SignatureValue {
+            bytes: digest.0.as_slice(),
+        };
+        assert!(producer.put_data(&data).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        let received = consumer.try_recv(|packet| match packet {
+            AppPacket::Data(received) => received.content.is_some_and(|c| c.bytes == b"content"),
+            _ => false,
+        });
+        assert_eq!(received, Ok(Some(true)));
+
+        // Without a route the consumer gets the interest back in a Nack
+        let other = root.adding_components(&comps[1..]);
+        assert!(consumer
+            .express_interest(&Interest::new(other, false, [2; 4]))
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        let received = consumer.try_recv(|packet| match packet {
+            AppPacket::Nack(interest, reason) => {
+                interest.name == other && reason == NackReason::NoRoute
+            }
+            _ => false,
+        });
+        assert_eq!(received, Ok(Some(true)));
+    }
+}
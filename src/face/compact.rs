@@ -0,0 +1,491 @@
+use alloc::vec::Vec;
+use core::num::NonZeroU32;
+
+use crate::{
+    face::{FaceError, FaceReceiver, FaceSender},
+    forwarder::MAX_PACKET_SIZE,
+    io::{Decode, Encode, Write},
+    tlv::{TlvDecodingError, VarintDecodingError, TLV},
+};
+
+// An experimental compact encoding of NDN packets for constrained links,
+//  such as radios with 127-byte frames, where the type and length headers
+//  of the many small elements are a significant part of every packet.
+//
+// Every element starts with a header byte:
+//  1tttt lll - one of the COMMON_TYPES[tttt] with the length lll (less than 8)
+//  0100 tttt - one of the COMMON_TYPES[tttt], followed by the length as LEB128
+//  0000 0000 - followed by the type and then the length, both as LEB128
+// All other header bytes are reserved.
+// The values of the CONTAINER_TYPES are themselves sequences of compact elements,
+//  the other values are copied unchanged.
+//
+// The translation keeps the types and the values, so the CompactSender and
+//  CompactReceiver can be put in front of the link-facing face to act as a gateway to
+//  the regular NDN network. It is only lossless for the shortest regular headers, the
+//  elements with longer ones are rejected as invalid.
+// Most packets get shorter, but the lengths from 128 to 252 and the types that are not
+//  common take longer headers than in the regular encoding.
+
+const COMMON_TYPES: [u32; 16] = [
+    5,  // Interest
+    6,  // Data
+    7,  // Name
+    8,  // GenericNameComponent
+    10, // Nonce
+    12, // InterestLifetime
+    18, // MustBeFresh
+    20, // MetaInfo
+    21, // Content
+    22, // SignatureInfo
+    23, // SignatureValue
+    25, // FreshnessPeriod
+    27, // SignatureType
+    28, // KeyLocator
+    33, // CanBePrefix
+    34, // HopLimit
+];
+
+const CONTAINER_TYPES: [u32; 8] = [
+    5,  // Interest
+    6,  // Data
+    7,  // Name
+    20, // MetaInfo
+    22, // SignatureInfo
+    28, // KeyLocator
+    30, // ForwardingHint
+    44, // InterestSignatureInfo
+];
+
+const SHORT_HEADER: u8 = 0b1000_0000;
+const LONG_HEADER: u8 = 0b0100_0000;
+const GENERIC_HEADER: u8 = 0b0000_0000;
+const MAX_SHORT_LENGTH: usize = 0b111;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CompactCodecError {
+    // More bytes are needed to decode the element
+    Incomplete,
+    Invalid,
+}
+
+// Translates the regular NDN TLV element at the start of "element" into the compact
+//  encoding, appending it to "out". Returns the number of bytes consumed.
+pub fn encode_compact(element: &[u8], out: &mut Vec<u8>) -> Result<usize, CompactCodecError> {
+    let (tlv, tlv_len) = TLV::try_decode(element).map_err(|err| match err {
+        TlvDecodingError::CannotDecodeType {
+            err: VarintDecodingError::BufferTooShort,
+        }
+        | TlvDecodingError::CannotDecodeLength {
+            err: VarintDecodingError::BufferTooShort,
+            ..
+        }
+        | TlvDecodingError::CannotDecodeValue { .. } => CompactCodecError::Incomplete,
+        _ => CompactCodecError::Invalid,
+    })?;
+    let typ = tlv.typ.get();
+
+    if CONTAINER_TYPES.contains(&typ) {
+        let mut inner = Vec::with_capacity(tlv.val.len());
+        let mut offset = 0;
+        while offset < tlv.val.len() {
+            // The value is complete, so a missing byte means the element is malformed
+            offset += encode_compact(&tlv.val[offset..], &mut inner)
+                .map_err(|_| CompactCodecError::Invalid)?;
+        }
+        write_header(typ, inner.len(), out);
+        out.extend_from_slice(&inner);
+    } else {
+        write_header(typ, tlv.val.len(), out);
+        out.extend_from_slice(tlv.val);
+    }
+
+    Ok(tlv_len)
+}
+
+// Translates the compact element at the start of "element" back into the regular
+//  NDN TLV encoding, appending it to "out". Returns the number of bytes consumed.
+pub fn decode_compact(element: &[u8], out: &mut Vec<u8>) -> Result<usize, CompactCodecError> {
+    let (typ, len, header_len) = read_header(element)?;
+    let end = header_len
+        .checked_add(len)
+        .ok_or(CompactCodecError::Invalid)?;
+    if element.len() < end {
+        return Err(CompactCodecError::Incomplete);
+    }
+    let val = &element[header_len..end];
+    let typ = NonZeroU32::new(typ).ok_or(CompactCodecError::Invalid)?;
+
+    if CONTAINER_TYPES.contains(&typ.get()) {
+        let mut inner = Vec::with_capacity(val.len() + val.len() / 2);
+        let mut offset = 0;
+        while offset < val.len() {
+            offset += decode_compact(&val[offset..], &mut inner)
+                .map_err(|_| CompactCodecError::Invalid)?;
+        }
+        let _ = TLV { typ, val: &inner }.encode(out);
+    } else {
+        let _ = TLV { typ, val }.encode(out);
+    }
+
+    Ok(end)
+}
+
+fn write_header(typ: u32, len: usize, out: &mut Vec<u8>) {
+    match COMMON_TYPES.iter().position(|t| *t == typ) {
+        Some(idx) if len <= MAX_SHORT_LENGTH => {
+            out.push(SHORT_HEADER | ((idx as u8) << 3) | len as u8);
+        }
+        Some(idx) => {
+            out.push(LONG_HEADER | idx as u8);
+            write_leb128(len as u64, out);
+        }
+        None => {
+            out.push(GENERIC_HEADER);
+            write_leb128(typ as u64, out);
+            write_leb128(len as u64, out);
+        }
+    }
+}
+
+// Returns the type, the length, and the size of the header
+fn read_header(bytes: &[u8]) -> Result<(u32, usize, usize), CompactCodecError> {
+    let header = *bytes.first().ok_or(CompactCodecError::Incomplete)?;
+    if header & SHORT_HEADER != 0 {
+        let idx = (header >> 3) & 0b1111;
+        let len = header as usize & MAX_SHORT_LENGTH;
+        Ok((COMMON_TYPES[idx as usize], len, 1))
+    } else if header & 0b1111_0000 == LONG_HEADER {
+        let idx = header & 0b1111;
+        let (len, len_len) = read_leb128(&bytes[1..])?;
+        let len = len.try_into().map_err(|_| CompactCodecError::Invalid)?;
+        Ok((COMMON_TYPES[idx as usize], len, 1 + len_len))
+    } else if header == GENERIC_HEADER {
+        let (typ, typ_len) = read_leb128(&bytes[1..])?;
+        let typ = typ.try_into().map_err(|_| CompactCodecError::Invalid)?;
+        let (len, len_len) = read_leb128(&bytes[1 + typ_len..])?;
+        let len = len.try_into().map_err(|_| CompactCodecError::Invalid)?;
+        Ok((typ, len, 1 + typ_len + len_len))
+    } else {
+        Err(CompactCodecError::Invalid)
+    }
+}
+
+fn write_leb128(mut value: u64, out: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn read_leb128(bytes: &[u8]) -> Result<(u64, usize), CompactCodecError> {
+    let mut value = 0u64;
+    for (idx, byte) in bytes.iter().enumerate() {
+        if idx >= 10 {
+            return Err(CompactCodecError::Invalid);
+        }
+        value |= ((byte & 0x7f) as u64)
+            .checked_shl(7 * idx as u32)
+            .ok_or(CompactCodecError::Invalid)?;
+        if byte & 0x80 == 0 {
+            return Ok((value, idx + 1));
+        }
+    }
+    Err(CompactCodecError::Incomplete)
+}
+
+// Accepts regular NDN packets and sends them compactly encoded to the inner face.
+// The packets are translated on flush, once they are complete.
+pub struct CompactSender<FS: FaceSender> {
+    sender: FS,
+    pending: Vec<u8>,
+    compact: Vec<u8>,
+}
+
+impl<FS: FaceSender> CompactSender<FS> {
+    pub fn new(sender: FS) -> Self {
+        Self {
+            sender,
+            pending: Vec::new(),
+            compact: Vec::new(),
+        }
+    }
+}
+
+impl<FS: FaceSender> FaceSender for CompactSender<FS> {
+    fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+        self.pending.extend_from_slice(src);
+        Ok(src.len())
+    }
+
+    fn flush(&mut self) -> Result<(), FaceError> {
+        let mut consumed = 0;
+        while consumed < self.pending.len() {
+            match encode_compact(&self.pending[consumed..], &mut self.compact) {
+                Ok(len) => consumed += len,
+                Err(CompactCodecError::Incomplete) => break,
+                Err(CompactCodecError::Invalid) => {
+                    // Nothing sensible can be sent, so we drop the rest
+                    consumed = self.pending.len();
+                }
+            }
+        }
+        self.pending.drain(..consumed);
+
+        if !self.compact.is_empty() {
+            let written = self.sender.write(&self.compact);
+            // Cleared even if it failed, or the next flush would send it again in front
+            //  of the next packets
+            self.compact.clear();
+            written?;
+        }
+        self.sender.flush()
+    }
+
+    // Every element of the regular encoding takes at least 2 bytes, and its compact
+    //  header at most 3 more: the generic header byte, and one byte each for a type or
+    //  a length from 128 to 252. So the compact encoding is at most 5/2 as long.
+    fn send_capacity(&self) -> Option<usize> {
+        let capacity = self.sender.send_capacity()? * 2 / 5;
+        Some(capacity.saturating_sub(self.pending.len()))
+    }
+}
+
+// Receives compactly encoded packets from the inner face and returns them
+//  in the regular NDN encoding.
+pub struct CompactReceiver<FR: FaceReceiver> {
+    receiver: FR,
+    receiver_buffer: [u8; MAX_PACKET_SIZE],
+    receiver_buffer_cursor: usize,
+    translated: Vec<u8>,
+    translated_cursor: usize,
+}
+
+impl<FR: FaceReceiver> CompactReceiver<FR> {
+    pub fn new(receiver: FR) -> Self {
+        Self {
+            receiver,
+            receiver_buffer: [0; MAX_PACKET_SIZE],
+            receiver_buffer_cursor: 0,
+            translated: Vec::new(),
+            translated_cursor: 0,
+        }
+    }
+}
+
+impl<FR: FaceReceiver> FaceReceiver for CompactReceiver<FR> {
+    fn try_recv(&mut self, dst: &mut [u8]) -> Result<usize, FaceError> {
+        // First we hand out whatever was translated before
+        if self.translated_cursor == self.translated.len() {
+            self.translated.clear();
+            self.translated_cursor = 0;
+
+            let received = self
+                .receiver
+                .try_recv(&mut self.receiver_buffer[self.receiver_buffer_cursor..])?;
+            self.receiver_buffer_cursor += received;
+
+            let mut consumed = 0;
+            while consumed < self.receiver_buffer_cursor {
+                match decode_compact(
+                    &self.receiver_buffer[consumed..self.receiver_buffer_cursor],
+                    &mut self.translated,
+                ) {
+                    Ok(len) => consumed += len,
+                    Err(CompactCodecError::Incomplete) => break,
+                    // We cannot find the start of the next element in a corrupted stream
+                    Err(CompactCodecError::Invalid) => return Err(FaceError::Disconnected),
+                }
+            }
+            if consumed == 0 && self.receiver_buffer_cursor == MAX_PACKET_SIZE {
+                // The element would never fit
+                return Err(FaceError::Disconnected);
+            }
+            self.receiver_buffer
+                .copy_within(consumed..self.receiver_buffer_cursor, 0);
+            self.receiver_buffer_cursor -= consumed;
+        }
+
+        let available = &self.translated[self.translated_cursor..];
+        let len = available.len().min(dst.len());
+        dst[..len].copy_from_slice(&available[..len]);
+        self.translated_cursor += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        face::{
+            compact::{
+                decode_compact, encode_compact, CompactCodecError, CompactReceiver, CompactSender,
+            },
+            local::{default_local_face, local_face, LocalSender},
+            FaceError, FaceReceiver, FaceSender,
+        },
+        io::Encode,
+        name::{Name, NameComponent},
+        packet::{Data, Interest, InterestLifetime, SignatureInfo, SignatureValue},
+    };
+
+    fn roundtrip(packet: &[u8]) -> Vec<u8> {
+        let mut compact = Vec::new();
+        assert_eq!(encode_compact(packet, &mut compact), Ok(packet.len()));
+        assert!(compact.len() < packet.len());
+        let mut regular = Vec::new();
+        assert_eq!(decode_compact(&compact, &mut regular), Ok(compact.len()));
+        assert_eq!(
+            decode_compact(&compact[..compact.len() - 1], &mut Vec::new()),
+            Err(CompactCodecError::Incomplete)
+        );
+        regular
+    }
+
+    #[test]
+    fn test_codec() {
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(b"sensor"),
+            NameComponent::generic(b"temp"),
+            NameComponent::new(300, b"unusual").unwrap(),
+        ];
+        let name = root.adding_components(comps);
+
+        let mut interest = Interest::new(name, true, [1, 2, 3, 4]);
+        interest.interest_lifetime = Some(InterestLifetime { val: 100000 });
+        let mut packet = Vec::new();
+        let _ = interest.encode(&mut packet);
+        assert_eq!(roundtrip(&packet), packet);
+
+        let content = [7u8; 300];
+        let signature_info = SignatureInfo::new(0, Some(name));
+        let mut data = Data::new_unsigned(name, &content, signature_info);
+        data.signature_value = SignatureValue { bytes: &[9; 32] };
+        let mut packet = Vec::new();
+        let _ = data.encode(&mut packet);
+        assert_eq!(roundtrip(&packet), packet);
+
+        assert_eq!(
+            decode_compact(&[0b0010_0000, 1, 1], &mut Vec::new()),
+            Err(CompactCodecError::Invalid)
+        );
+
+        // The headers that grow: a common type with a length from 128 to 252, and the
+        //  uncommon types, the more so from 128 to 252
+        let cases: [(&[u8], usize); 3] = [(&[21, 200], 3), (&[100, 0], 3), (&[200, 200], 5)];
+        for (header, compact_header) in cases {
+            let mut packet = Vec::from(header);
+            packet.resize(header.len() + header[1] as usize, 7);
+            let mut compact = Vec::new();
+            assert_eq!(encode_compact(&packet, &mut compact), Ok(packet.len()));
+            assert_eq!(compact.len(), packet.len() - header.len() + compact_header);
+            let mut regular = Vec::new();
+            assert_eq!(decode_compact(&compact, &mut regular), Ok(compact.len()));
+            assert_eq!(regular, packet);
+        }
+
+        // The headers that are not the shortest could not come back the same
+        assert_eq!(
+            encode_compact(&[21, 253, 0, 1, 7], &mut Vec::new()),
+            Err(CompactCodecError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_faces() {
+        let (fs, fr) = default_local_face();
+        let mut sender = CompactSender::new(fs);
+        let mut receiver = CompactReceiver::new(fr);
+
+        let root = Name::new();
+        let comps = &[NameComponent::generic(b"a")];
+        let name = root.adding_components(comps);
+        let mut packet = Vec::new();
+        let _ = Interest::new(name, false, [1, 2, 3, 4]).encode(&mut packet);
+
+        // Written in pieces, sent on flush
+        assert_eq!(sender.try_send(&packet[..3]), Ok(3));
+        assert_eq!(sender.flush(), Ok(()));
+        assert_eq!(sender.try_send(&packet[3..]), Ok(packet.len() - 3));
+        assert_eq!(sender.flush(), Ok(()));
+
+        // Received in pieces smaller than the packet
+        let mut received = Vec::new();
+        let mut buf = [0u8; 4];
+        loop {
+            let len = receiver.try_recv(&mut buf).unwrap();
+            if len == 0 {
+                break;
+            }
+            received.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(received, packet);
+
+        // Whatever the capacity allows fits the inner face once encoded, even a name of
+        //  empty components of an uncommon type, whose compact encoding is twice as long
+        let (fs, _fr) = local_face::<256>();
+        let mut sender = CompactSender::new(fs);
+        let capacity = sender.send_capacity().unwrap();
+        let mut packet = Vec::from([7, (capacity - 2) as u8]);
+        for _ in 0..(capacity - 2) / 2 {
+            packet.extend_from_slice(&[200, 0]);
+        }
+        let mut compact = Vec::new();
+        assert_eq!(encode_compact(&packet, &mut compact), Ok(packet.len()));
+        assert!(compact.len() > 2 * packet.len() - 4 && compact.len() <= 255);
+        assert_eq!(sender.try_send(&packet), Ok(packet.len()));
+        assert_eq!(sender.send_capacity(), Some(0));
+        assert_eq!(sender.flush(), Ok(()));
+    }
+
+    // Refuses the next packet, as the datagram faces do with those over their MTU
+    struct RefusingSender {
+        sender: LocalSender,
+        refuse: bool,
+    }
+
+    impl FaceSender for RefusingSender {
+        fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+            if core::mem::take(&mut self.refuse) {
+                return Err(FaceError::MessageTooLarge);
+            }
+            self.sender.try_send(src)
+        }
+    }
+
+    #[test]
+    fn test_failed_flush() {
+        let (fs, mut fr) = default_local_face();
+        let mut sender = CompactSender::new(RefusingSender {
+            sender: fs,
+            refuse: true,
+        });
+
+        let root = Name::new();
+        let comps = &[NameComponent::generic(b"a")];
+        let name = root.adding_components(comps);
+        let mut refused = Vec::new();
+        let _ = Interest::new(name, false, [1, 2, 3, 4]).encode(&mut refused);
+        let mut packet = Vec::new();
+        let _ = Interest::new(name, false, [5, 6, 7, 8]).encode(&mut packet);
+        let mut compact = Vec::new();
+        assert_eq!(encode_compact(&packet, &mut compact), Ok(packet.len()));
+
+        // The refused packet is not sent again with the next one
+        assert_eq!(sender.try_send(&refused), Ok(refused.len()));
+        assert_eq!(sender.flush(), Err(FaceError::MessageTooLarge));
+        assert_eq!(sender.try_send(&packet), Ok(packet.len()));
+        assert_eq!(sender.flush(), Ok(()));
+        let mut buf = [0u8; 256];
+        assert_eq!(fr.try_recv(&mut buf), Ok(compact.len()));
+        assert_eq!(&buf[..compact.len()], &compact[..]);
+    }
+}
@@ -1,7 +1,10 @@
 use alloc::rc::Rc;
 use core::cell::RefCell;
 
-use crate::{face::{FaceError, FaceReceiver, FaceSender}, forwarder::MAX_PACKET_SIZE};
+use crate::{
+    face::{FaceError, FaceReceiver, FaceSender},
+    forwarder::MAX_PACKET_SIZE,
+};
 
 pub const DEFAULT_RING_BUFFER_SIZE: usize = 10 * MAX_PACKET_SIZE;
 
@@ -35,6 +38,10 @@ impl<const SIZE: usize> FaceSender for LocalSender<SIZE> {
 
         Ok(self.inner.borrow_mut().write(src))
     }
+
+    fn send_capacity(&self) -> Option<usize> {
+        Some(self.inner.borrow().free_space())
+    }
 }
 
 impl<const SIZE: usize> FaceReceiver for LocalReceiver<SIZE> {
@@ -65,6 +72,13 @@ impl<const SIZE: usize> RingBuffer<SIZE> {
         }
     }
 
+    // The number of bytes that can be written before the buffer is full
+    pub fn free_space(&self) -> usize {
+        let ring_len = self.storage.len();
+        let used = (self.write + ring_len - self.read) % ring_len;
+        ring_len - 1 - used
+    }
+
     pub fn write(&mut self, src: &[u8]) -> usize {
         let src_len = src.len();
         if src_len == 0 {
@@ -0,0 +1,1048 @@
+use alloc::{collections::VecDeque, rc::Rc, vec, vec::Vec};
+use core::cell::RefCell;
+
+use crate::{
+    clock::{Clock, Timestamp},
+    face::{FaceError, FaceReceiver, FaceSender},
+    forwarder::MAX_PACKET_SIZE,
+    io::{Decode, Encode, Write},
+    tlv::{
+        TlvDecode, TlvDecodingError, TlvEncode, TypedArray, TypedBytes, TypedInteger,
+        VarintDecodingError, TLV,
+    },
+};
+
+// The NDN link protocol (NDNLPv2), see https://redmine.named-data.net/projects/nfd/wiki/NDNLPv2
+// The packets on the link are wrapped in an LpPacket, whose header fields come
+//  in the increasing order of their types and are followed by the Fragment.
+// Only the fields used for fragmentation, reliability, the Nacks and the congestion marks
+//  are decoded, the others are skipped if the protocol allows to ignore them,
+//  otherwise the packet is dropped.
+
+pub struct LpPacket<'a> {
+    pub sequence: Option<LpSequence>,
+    pub frag_index: Option<FragIndex>,
+    pub frag_count: Option<FragCount>,
+    // Set when the fragment is an interest that is sent back because it could not
+    //  be forwarded
+    pub nack: Option<Nack>,
+    // Set by a sender whose queue is building up, for the consumers to slow down
+    pub congestion_mark: Option<CongestionMark>,
+    // The encoded Ack elements
+    pub acks: &'a [u8],
+    pub tx_sequence: Option<TxSequence>,
+    pub fragment: Option<Fragment<'a>>,
+}
+
+impl<'a> LpPacket<'a> {
+    // The TxSequences acknowledged by the packet
+    pub fn acked(&self) -> impl Iterator<Item = u64> + 'a {
+        let mut remaining = self.acks;
+        core::iter::from_fn(move || {
+            let (tlv, len) = TLV::try_decode(remaining).ok()?;
+            remaining = &remaining[len..];
+            Some(u64::from_be_bytes(tlv.val.try_into().ok()?))
+        })
+    }
+
+    fn can_be_ignored(typ: u32) -> bool {
+        (800..=959).contains(&typ) && typ & 0b11 == 0
+    }
+}
+
+impl<'a> TlvDecode<'a> for LpPacket<'a> {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let mut packet = LpPacket {
+            sequence: None,
+            frag_index: None,
+            frag_count: None,
+            nack: None,
+            congestion_mark: None,
+            acks: &[],
+            tx_sequence: None,
+            fragment: None,
+        };
+
+        let mut offset = 0;
+        let mut last_type = 0;
+        let mut acks_range = None;
+        while offset < inner_bytes.len() {
+            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            let typ = tlv.typ.get();
+            if typ == Fragment::TLV_TYPE {
+                // The fragment is always the last
+                if offset + tlv_len != inner_bytes.len() {
+                    return None;
+                }
+                packet.fragment = Some(Fragment { bytes: tlv.val });
+            } else {
+                // Only the Acks can be repeated
+                if typ < last_type || (typ == last_type && typ != Ack::TLV_TYPE) {
+                    return None;
+                }
+                match typ {
+                    LpSequence::TLV_TYPE => {
+                        packet.sequence = Some(LpSequence {
+                            bytes: tlv.val.try_into().ok()?,
+                        })
+                    }
+                    FragIndex::TLV_TYPE => {
+                        packet.frag_index = Some(FragIndex {
+                            val: u64::try_decode(tlv.val).ok()?.0,
+                        })
+                    }
+                    FragCount::TLV_TYPE => {
+                        packet.frag_count = Some(FragCount {
+                            val: u64::try_decode(tlv.val).ok()?.0,
+                        })
+                    }
+                    Nack::TLV_TYPE => packet.nack = Some(Nack::try_decode_from_inner(tlv.val)?),
+                    CongestionMark::TLV_TYPE => {
+                        packet.congestion_mark = Some(CongestionMark {
+                            val: u64::try_decode(tlv.val).ok()?.0,
+                        })
+                    }
+                    Ack::TLV_TYPE => {
+                        if tlv.val.len() != 8 {
+                            return None;
+                        }
+                        let (start, _) = acks_range.unwrap_or((offset, offset));
+                        acks_range = Some((start, offset + tlv_len));
+                    }
+                    TxSequence::TLV_TYPE => {
+                        packet.tx_sequence = Some(TxSequence {
+                            bytes: tlv.val.try_into().ok()?,
+                        })
+                    }
+                    _ if Self::can_be_ignored(typ) => {}
+                    _ => return None,
+                }
+                last_type = typ;
+            }
+            offset += tlv_len;
+        }
+
+        // The header fields are ordered, so the Acks are next to each other
+        if let Some((start, end)) = acks_range {
+            packet.acks = &inner_bytes[start..end];
+        }
+        Some(packet)
+    }
+}
+
+impl<'a> TlvEncode for LpPacket<'a> {
+    const TLV_TYPE: u32 = 100;
+
+    fn inner_length(&self) -> usize {
+        let mut len = self.sequence.encoded_length();
+        len += self.frag_index.encoded_length();
+        len += self.frag_count.encoded_length();
+        len += self.nack.encoded_length();
+        len += self.congestion_mark.encoded_length();
+        len += self.acks.len();
+        len += self.tx_sequence.encoded_length();
+        len += self.fragment.encoded_length();
+        len
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.sequence.encode(writer)?;
+        self.frag_index.encode(writer)?;
+        self.frag_count.encode(writer)?;
+        self.nack.encode(writer)?;
+        self.congestion_mark.encode(writer)?;
+        writer.write(self.acks)?;
+        self.tx_sequence.encode(writer)?;
+        self.fragment.encode(writer)
+    }
+}
+
+pub type Fragment<'a> = TypedBytes<'a, 80>;
+pub type LpSequence = TypedArray<81, 8>;
+pub type FragIndex = TypedInteger<82, u64>;
+pub type FragCount = TypedInteger<83, u64>;
+pub type CongestionMark = TypedInteger<832, u64>;
+pub type Ack = TypedArray<836, 8>;
+pub type TxSequence = TypedArray<840, 8>;
+pub type NackReasonCode = TypedInteger<801, u64>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum NackReason {
+    // The Nack came without a reason
+    Unspecified,
+    Congestion,
+    // The interest was a loop
+    Duplicate,
+    NoRoute,
+    Other(u64),
+}
+
+impl NackReason {
+    pub fn from_code(code: u64) -> Self {
+        match code {
+            50 => NackReason::Congestion,
+            100 => NackReason::Duplicate,
+            150 => NackReason::NoRoute,
+            code => NackReason::Other(code),
+        }
+    }
+
+    pub fn code(&self) -> Option<u64> {
+        match self {
+            NackReason::Unspecified => None,
+            NackReason::Congestion => Some(50),
+            NackReason::Duplicate => Some(100),
+            NackReason::NoRoute => Some(150),
+            NackReason::Other(code) => Some(*code),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Nack {
+    pub reason: NackReason,
+}
+
+impl Nack {
+    fn reason_code(&self) -> Option<NackReasonCode> {
+        self.reason.code().map(|val| NackReasonCode { val })
+    }
+}
+
+impl TlvEncode for Nack {
+    const TLV_TYPE: u32 = 800;
+
+    fn inner_length(&self) -> usize {
+        self.reason_code().encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.reason_code().encode(writer)
+    }
+}
+
+impl<'a> TlvDecode<'a> for Nack {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        // The unknown elements next to the reason are ignored
+        let mut reason = NackReason::Unspecified;
+        let mut offset = 0;
+        while offset < inner_bytes.len() {
+            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            if tlv.typ.get() == NackReasonCode::TLV_TYPE {
+                reason = NackReason::from_code(u64::try_decode(tlv.val).ok()?.0);
+            }
+            offset += tlv_len;
+        }
+        Some(Nack { reason })
+    }
+}
+
+// The room left for the LpPacket headers on top of the network packet
+pub const MAX_LP_HEADER_LENGTH: usize = 256;
+
+// The fragmentation feature of NDNLPv2, for the links whose MTU is below the size of
+//  the packets. The fragments of a packet carry consecutive Sequences with their FragIndex
+//  and the FragCount, and the other header fields, e.g. the Nack, only come with the first.
+
+// The room taken by the headers of a fragment, the Nack included
+pub const FRAGMENT_HEADER_LENGTH: usize = 64;
+
+// The least that a fragment carries but the last, which bounds the FragCount
+pub const MIN_FRAGMENT_PAYLOAD_LENGTH: usize = 64;
+
+// So that a peer cannot make the reassembler allocate for more fragments than it needs
+const MAX_FRAGMENT_COUNT: usize = MAX_PACKET_SIZE.div_ceil(MIN_FRAGMENT_PAYLOAD_LENGTH);
+
+// How many packets can be put back together at a time, the oldest are given up on first
+const MAX_PARTIAL_PACKETS: usize = 16;
+
+pub struct LpFragmenter {
+    mtu: usize,
+    next_sequence: u64,
+    encoded: Vec<u8>,
+}
+
+impl LpFragmenter {
+    // Returns None if the MTU leaves too little room for the fragments
+    pub fn new(mtu: usize) -> Option<Self> {
+        if mtu < FRAGMENT_HEADER_LENGTH + MIN_FRAGMENT_PAYLOAD_LENGTH {
+            return None;
+        }
+        Some(Self {
+            mtu,
+            next_sequence: 0,
+            encoded: Vec::with_capacity(mtu),
+        })
+    }
+
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    // The most bytes that the fragments of the packet take together
+    pub fn fragmented_length(&self, packet_len: usize) -> usize {
+        self.fragment_count(packet_len) * self.mtu
+    }
+
+    fn fragment_count(&self, packet_len: usize) -> usize {
+        packet_len
+            .div_ceil(self.mtu - FRAGMENT_HEADER_LENGTH)
+            .max(1)
+    }
+
+    // Passes the encoded fragments of the packet to send, in order
+    pub fn fragment<E>(
+        &mut self,
+        packet: &[u8],
+        nack: Option<Nack>,
+        send: &mut dyn FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let count = self.fragment_count(packet.len());
+        let chunks = packet.chunks(self.mtu - FRAGMENT_HEADER_LENGTH);
+        for (index, chunk) in chunks.enumerate() {
+            let sequence = self.next_sequence;
+            self.next_sequence = self.next_sequence.wrapping_add(1);
+            let fragment = LpPacket {
+                sequence: Some(LpSequence {
+                    bytes: sequence.to_be_bytes(),
+                }),
+                frag_index: Some(FragIndex { val: index as u64 }),
+                frag_count: Some(FragCount { val: count as u64 }),
+                nack: if index == 0 { nack } else { None },
+                congestion_mark: None,
+                acks: &[],
+                tx_sequence: None,
+                fragment: Some(Fragment { bytes: chunk }),
+            };
+            self.encoded.clear();
+            let _ = fragment.encode(&mut self.encoded);
+            debug_assert!(self.encoded.len() <= self.mtu);
+            send(&self.encoded)?;
+        }
+        Ok(())
+    }
+}
+
+struct PartialPacket {
+    // The Sequence of the first fragment
+    base_sequence: u64,
+    fragments: Vec<Option<Vec<u8>>>,
+    received: usize,
+    // The bytes of the fragments received so far
+    length: usize,
+    nack: Option<Nack>,
+}
+
+#[derive(Default)]
+pub struct LpReassembler {
+    // The oldest first
+    partial: VecDeque<PartialPacket>,
+}
+
+impl LpReassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Takes the fragment of a packet with a FragCount above 1. Returns the packet once all
+    //  its fragments are in, wrapped in an LpPacket if the first fragment had a Nack.
+    // The malformed fragments and the packets longer than MAX_PACKET_SIZE are dropped,
+    //  the latter as soon as their fragments add up to more.
+    pub fn receive(&mut self, packet: &LpPacket<'_>) -> Option<Vec<u8>> {
+        let count = usize::try_from(packet.frag_count?.val).ok()?;
+        let index = usize::try_from(packet.frag_index.map_or(0, |i| i.val)).ok()?;
+        let sequence = u64::from_be_bytes(packet.sequence?.bytes);
+        let fragment = packet.fragment?.bytes;
+        if index >= count || count > MAX_FRAGMENT_COUNT {
+            return None;
+        }
+        let base_sequence = sequence.wrapping_sub(index as u64);
+
+        let position = match self
+            .partial
+            .iter()
+            .position(|p| p.base_sequence == base_sequence && p.fragments.len() == count)
+        {
+            Some(position) => position,
+            None => {
+                if self.partial.len() == MAX_PARTIAL_PACKETS {
+                    self.partial.pop_front();
+                }
+                self.partial.push_back(PartialPacket {
+                    base_sequence,
+                    fragments: vec![None; count],
+                    received: 0,
+                    length: 0,
+                    nack: None,
+                });
+                self.partial.len() - 1
+            }
+        };
+        let partial = &mut self.partial[position];
+        if partial.fragments[index].is_none() {
+            partial.length += fragment.len();
+            if partial.length > MAX_PACKET_SIZE {
+                self.partial.remove(position);
+                return None;
+            }
+            partial.fragments[index] = Some(Vec::from(fragment));
+            partial.received += 1;
+        }
+        if index == 0 {
+            partial.nack = packet.nack;
+        }
+        if partial.received < count {
+            return None;
+        }
+
+        let partial = self.partial.remove(position)?;
+        let mut whole = Vec::new();
+        for fragment in partial.fragments.into_iter().flatten() {
+            whole.extend_from_slice(&fragment);
+        }
+        if let Some(nack) = partial.nack {
+            let packet = LpPacket {
+                sequence: None,
+                frag_index: None,
+                frag_count: None,
+                nack: Some(nack),
+                congestion_mark: None,
+                acks: &[],
+                tx_sequence: None,
+                fragment: Some(Fragment { bytes: &whole }),
+            };
+            let mut encoded = Vec::with_capacity(packet.encoded_length());
+            let _ = packet.encode(&mut encoded);
+            whole = encoded;
+        }
+        if whole.len() > MAX_PACKET_SIZE {
+            return None;
+        }
+        Some(whole)
+    }
+}
+
+// The reliability feature of NDNLPv2 for lossy links: every fragment carries
+//  a TxSequence that the other side acknowledges, piggybacked on its own packets
+//  or alone if it has nothing to send for a while. The fragments that are not
+//  acknowledged in time are sent again with a new TxSequence, and the duplicates
+//  are recognised by their Sequence and dropped on the receiving side.
+// Both sides of the link have to use it, and the receiver and the sender share
+//  the state of the face, so the timers run whenever either of them is used.
+
+#[derive(Clone, Copy, Debug)]
+pub struct LpReliabilityOptions {
+    // How long the Ack can take before the fragment is sent again
+    pub retransmission_timeout_ms: u64,
+    // The fragment is given up on after that many retransmissions
+    pub max_retransmissions: u8,
+    // How long the Acks wait for an outgoing packet before they are sent alone
+    pub ack_delay_ms: u64,
+    // Each Ack takes 11 bytes, and all of them must fit in MAX_LP_HEADER_LENGTH
+    pub max_acks_per_packet: usize,
+}
+
+impl Default for LpReliabilityOptions {
+    fn default() -> Self {
+        Self {
+            retransmission_timeout_ms: 200,
+            max_retransmissions: 3,
+            ack_delay_ms: 5,
+            max_acks_per_packet: 16,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LpReliabilityStats {
+    pub acknowledged: u64,
+    pub retransmitted: u64,
+    // Given up on after the maximum number of retransmissions
+    pub lost: u64,
+    pub duplicates_received: u64,
+}
+
+// How many of the last received Sequences are remembered to drop the duplicates
+const DUPLICATE_WINDOW: usize = 256;
+
+struct UnackedFragment {
+    tx_sequence: u64,
+    sequence: u64,
+    sent_at: Timestamp,
+    retransmissions: u8,
+    fragment: Vec<u8>,
+}
+
+struct ReliabilityState<S: FaceSender, C: Clock> {
+    sender: S,
+    clock: C,
+    options: LpReliabilityOptions,
+    next_sequence: u64,
+    next_tx_sequence: u64,
+    // In the order they were last sent
+    unacked: VecDeque<UnackedFragment>,
+    pending_acks: Vec<u64>,
+    oldest_pending_ack: Option<Timestamp>,
+    recent_sequences: VecDeque<u64>,
+    stats: LpReliabilityStats,
+    encoded: Vec<u8>,
+}
+
+impl<S: FaceSender, C: Clock> ReliabilityState<S, C> {
+    fn send_new(&mut self, fragment: &[u8]) -> Result<(), FaceError> {
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        let tx_sequence = self.allocate_tx_sequence();
+        self.transmit(Some(tx_sequence), Some(sequence), Some(fragment))?;
+        self.unacked.push_back(UnackedFragment {
+            tx_sequence,
+            sequence,
+            sent_at: self.clock.now(),
+            retransmissions: 0,
+            fragment: Vec::from(fragment),
+        });
+        Ok(())
+    }
+
+    // Retransmits the fragments whose Acks are late and sends the Acks
+    //  that waited too long for a packet to ride on
+    fn poll(&mut self) -> Result<(), FaceError> {
+        let now = self.clock.now();
+        while let Some(oldest) = self.unacked.front() {
+            let waited = now.difference(&oldest.sent_at).unwrap_or(0);
+            if waited < self.options.retransmission_timeout_ms {
+                break;
+            }
+            let Some(mut entry) = self.unacked.pop_front() else {
+                break;
+            };
+            if entry.retransmissions >= self.options.max_retransmissions {
+                self.stats.lost += 1;
+                continue;
+            }
+            entry.retransmissions += 1;
+            entry.tx_sequence = self.allocate_tx_sequence();
+            entry.sent_at = now;
+            self.stats.retransmitted += 1;
+            let result = self.transmit(
+                Some(entry.tx_sequence),
+                Some(entry.sequence),
+                Some(&entry.fragment),
+            );
+            self.unacked.push_back(entry);
+            result?;
+        }
+
+        let acks_are_late = self.oldest_pending_ack.is_some_and(|oldest| {
+            now.difference(&oldest).unwrap_or(0) >= self.options.ack_delay_ms
+        });
+        if acks_are_late {
+            while !self.pending_acks.is_empty() {
+                self.transmit(None, None, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Returns false if the fragment of the packet is a duplicate
+    fn on_received(&mut self, packet: &LpPacket<'_>) -> bool {
+        for acked in packet.acked() {
+            let position = self.unacked.iter().position(|e| e.tx_sequence == acked);
+            if let Some(position) = position {
+                self.unacked.remove(position);
+                self.stats.acknowledged += 1;
+            }
+        }
+
+        // Even the duplicates are acknowledged, since the Ack of the original was lost
+        if let Some(tx_sequence) = packet.tx_sequence {
+            if self.pending_acks.is_empty() {
+                self.oldest_pending_ack = Some(self.clock.now());
+            }
+            self.pending_acks
+                .push(u64::from_be_bytes(tx_sequence.bytes));
+        }
+
+        if let Some(sequence) = packet.sequence {
+            let sequence = u64::from_be_bytes(sequence.bytes);
+            if self.recent_sequences.contains(&sequence) {
+                self.stats.duplicates_received += 1;
+                return false;
+            }
+            if self.recent_sequences.len() == DUPLICATE_WINDOW {
+                self.recent_sequences.pop_front();
+            }
+            self.recent_sequences.push_back(sequence);
+        }
+        true
+    }
+
+    fn allocate_tx_sequence(&mut self) -> u64 {
+        let tx_sequence = self.next_tx_sequence;
+        self.next_tx_sequence = self.next_tx_sequence.wrapping_add(1);
+        tx_sequence
+    }
+
+    // Sends the LpPacket with as many of the pending Acks as fit
+    fn transmit(
+        &mut self,
+        tx_sequence: Option<u64>,
+        sequence: Option<u64>,
+        fragment: Option<&[u8]>,
+    ) -> Result<(), FaceError> {
+        let ack_count = self
+            .pending_acks
+            .len()
+            .min(self.options.max_acks_per_packet.max(1));
+        let mut acks = Vec::with_capacity(ack_count * 11);
+        for ack in self.pending_acks.drain(..ack_count) {
+            let _ = Ack {
+                bytes: ack.to_be_bytes(),
+            }
+            .encode(&mut acks);
+        }
+        if self.pending_acks.is_empty() {
+            self.oldest_pending_ack = None;
+        }
+
+        let packet = LpPacket {
+            sequence: sequence.map(|s| LpSequence {
+                bytes: s.to_be_bytes(),
+            }),
+            frag_index: None,
+            frag_count: None,
+            nack: None,
+            congestion_mark: None,
+            acks: &acks,
+            tx_sequence: tx_sequence.map(|s| TxSequence {
+                bytes: s.to_be_bytes(),
+            }),
+            fragment: fragment.map(|bytes| Fragment { bytes }),
+        };
+        self.encoded.clear();
+        let _ = packet.encode(&mut self.encoded);
+        self.sender.write(&self.encoded)?;
+        self.sender.flush()
+    }
+}
+
+pub struct LpReliableSender<S: FaceSender, C: Clock> {
+    state: Rc<RefCell<ReliabilityState<S, C>>>,
+    buffer: Vec<u8>,
+}
+
+pub struct LpReliableReceiver<R: FaceReceiver, S: FaceSender, C: Clock> {
+    pub(crate) receiver: R,
+    state: Rc<RefCell<ReliabilityState<S, C>>>,
+    incoming: Vec<u8>,
+    incoming_cursor: usize,
+    // The network packets taken out of the fragments, not yet read
+    delivered: Vec<u8>,
+    delivered_offset: usize,
+}
+
+// Wraps the two sides of a datagram face, e.g. UDP, so that the packets lost
+//  on the link are sent again. The clock drives the retransmission timers.
+pub fn lp_reliable_face<S: FaceSender, R: FaceReceiver, C: Clock>(
+    sender: S,
+    receiver: R,
+    clock: C,
+    options: LpReliabilityOptions,
+) -> (LpReliableSender<S, C>, LpReliableReceiver<R, S, C>) {
+    let state = Rc::new(RefCell::new(ReliabilityState {
+        sender,
+        clock,
+        options,
+        next_sequence: 0,
+        next_tx_sequence: 0,
+        unacked: VecDeque::new(),
+        pending_acks: Vec::new(),
+        oldest_pending_ack: None,
+        recent_sequences: VecDeque::with_capacity(DUPLICATE_WINDOW),
+        stats: LpReliabilityStats::default(),
+        encoded: Vec::with_capacity(MAX_PACKET_SIZE + MAX_LP_HEADER_LENGTH),
+    }));
+
+    let sender = LpReliableSender {
+        state: Rc::clone(&state),
+        buffer: Vec::with_capacity(MAX_PACKET_SIZE),
+    };
+    let receiver = LpReliableReceiver {
+        receiver,
+        state,
+        incoming: vec![0; MAX_PACKET_SIZE + MAX_LP_HEADER_LENGTH],
+        incoming_cursor: 0,
+        delivered: Vec::new(),
+        delivered_offset: 0,
+    };
+    (sender, receiver)
+}
+
+impl<S: FaceSender, C: Clock> LpReliableSender<S, C> {
+    pub fn stats(&self) -> LpReliabilityStats {
+        self.state.borrow().stats
+    }
+
+    // The number of fragments still waiting for their Acks
+    pub fn unacknowledged(&self) -> usize {
+        self.state.borrow().unacked.len()
+    }
+}
+
+impl<S: FaceSender, C: Clock> FaceSender for LpReliableSender<S, C> {
+    fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+        self.buffer.extend_from_slice(src);
+        Ok(src.len())
+    }
+
+    // Every flush sends the bytes written since the previous one as a fragment
+    fn flush(&mut self) -> Result<(), FaceError> {
+        let mut state = self.state.borrow_mut();
+        if !self.buffer.is_empty() {
+            let result = state.send_new(&self.buffer);
+            self.buffer.clear();
+            result?;
+        }
+        state.poll()
+    }
+
+    fn send_capacity(&self) -> Option<usize> {
+        self.state.borrow().sender.send_capacity()
+    }
+}
+
+impl<R: FaceReceiver, S: FaceSender, C: Clock> FaceReceiver for LpReliableReceiver<R, S, C> {
+    fn try_recv(&mut self, dst: &mut [u8]) -> Result<usize, FaceError> {
+        // The errors of the sender are reported when it is used
+        let _ = self.state.borrow_mut().poll();
+
+        if self.delivered_offset == self.delivered.len() {
+            self.delivered.clear();
+            self.delivered_offset = 0;
+
+            let received = self
+                .receiver
+                .try_recv(&mut self.incoming[self.incoming_cursor..])?;
+            self.incoming_cursor += received;
+
+            let mut consumed = 0;
+            let mut state = self.state.borrow_mut();
+            while consumed < self.incoming_cursor {
+                let bytes = &self.incoming[consumed..self.incoming_cursor];
+                match TLV::try_decode(bytes) {
+                    Ok((tlv, tlv_len)) => {
+                        if tlv.typ.get() == LpPacket::TLV_TYPE {
+                            // The malformed packets are dropped
+                            if let Some(packet) = LpPacket::try_decode_from_inner(tlv.val) {
+                                if state.on_received(&packet) {
+                                    if let Some(fragment) = packet.fragment {
+                                        self.delivered.extend_from_slice(fragment.bytes);
+                                    }
+                                }
+                            }
+                        } else {
+                            // The network packets without any headers are passed as they are
+                            self.delivered.extend_from_slice(&bytes[..tlv_len]);
+                        }
+                        consumed += tlv_len;
+                    }
+                    Err(TlvDecodingError::CannotDecodeType {
+                        err: VarintDecodingError::BufferTooShort,
+                    })
+                    | Err(TlvDecodingError::CannotDecodeLength {
+                        err: VarintDecodingError::BufferTooShort,
+                        ..
+                    })
+                    | Err(TlvDecodingError::CannotDecodeValue { .. })
+                        if self.incoming_cursor < self.incoming.len() =>
+                    {
+                        break
+                    }
+                    Err(_) => {
+                        // Nothing can be made of these bytes anymore
+                        consumed = self.incoming_cursor;
+                    }
+                }
+            }
+            self.incoming.copy_within(consumed..self.incoming_cursor, 0);
+            self.incoming_cursor -= consumed;
+        }
+
+        let available = &self.delivered[self.delivered_offset..];
+        let len = available.len().min(dst.len());
+        dst[..len].copy_from_slice(&available[..len]);
+        self.delivered_offset += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, vec::Vec};
+    use core::cell::Cell;
+
+    use crate::{
+        clock::{Clock, Timestamp},
+        face::{
+            local::default_local_face,
+            lp::{
+                lp_reliable_face, FragCount, FragIndex, Fragment, LpFragmenter, LpPacket,
+                LpReassembler, LpReliabilityOptions, LpSequence, Nack, NackReason, TxSequence,
+                FRAGMENT_HEADER_LENGTH, MIN_FRAGMENT_PAYLOAD_LENGTH,
+            },
+            FaceError, FaceReceiver, FaceSender,
+        },
+        forwarder::MAX_PACKET_SIZE,
+        io::{Decode, Encode, Write},
+        name::{Name, NameComponent},
+        packet::Interest,
+    };
+
+    #[derive(Clone)]
+    struct ManualClock {
+        now: Rc<Cell<u64>>,
+    }
+
+    impl Clock for ManualClock {
+        fn now(&mut self) -> Timestamp {
+            Timestamp {
+                ms_since_1970: self.now.get(),
+            }
+        }
+    }
+
+    // Loses the packets while the flag is set
+    struct LossySender<S: FaceSender> {
+        sender: S,
+        losing: Rc<Cell<bool>>,
+        buffer: Vec<u8>,
+    }
+
+    impl<S: FaceSender> FaceSender for LossySender<S> {
+        fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+            self.buffer.extend_from_slice(src);
+            Ok(src.len())
+        }
+
+        fn flush(&mut self) -> Result<(), FaceError> {
+            if !self.losing.get() {
+                self.sender.write(&self.buffer)?;
+            }
+            self.buffer.clear();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_lp_packet() {
+        let bytes = &[
+            100, 37, 81, 8, 0, 0, 0, 0, 0, 0, 0, 7, // Sequence
+            253, 3, 68, 8, 0, 0, 0, 0, 0, 0, 0, 1, // Ack
+            253, 3, 72, 8, 0, 0, 0, 0, 0, 0, 0, 2, // TxSequence
+            80, 1, 42, // Fragment
+        ];
+        let (packet, len) = LpPacket::try_decode(bytes).unwrap();
+        assert_eq!(len, bytes.len());
+        assert_eq!(packet.sequence.unwrap().bytes, 7u64.to_be_bytes());
+        assert!(packet.acked().eq([1]));
+        assert_eq!(packet.tx_sequence.unwrap().bytes, 2u64.to_be_bytes());
+        assert_eq!(packet.fragment.unwrap().bytes, &[42]);
+        let mut encoded = Vec::new();
+        let _ = packet.encode(&mut encoded);
+        assert_eq!(encoded.as_slice(), bytes);
+
+        // Out of order fields and the fields that cannot be ignored are not accepted
+        let packet = LpPacket {
+            sequence: Some(LpSequence { bytes: [0; 8] }),
+            frag_index: None,
+            frag_count: None,
+            nack: None,
+            congestion_mark: None,
+            acks: &[],
+            tx_sequence: Some(TxSequence { bytes: [0; 8] }),
+            fragment: None,
+        };
+        let mut encoded = Vec::new();
+        let _ = packet.encode(&mut encoded);
+        assert!(LpPacket::try_decode(&encoded).is_ok());
+        assert!(LpPacket::try_decode(&[100, 5, 80, 0, 81, 1, 0]).is_err());
+        assert!(LpPacket::try_decode(&[100, 4, 253, 3, 33, 0]).is_err());
+        assert!(LpPacket::try_decode(&[100, 4, 253, 3, 32, 0]).is_ok());
+    }
+
+    #[test]
+    fn test_lp_nack() {
+        let bytes = &[
+            100, 12, 253, 3, 32, 5, 253, 3, 33, 1, 150, // Nack with the NoRoute reason
+            80, 1, 42, // Fragment
+        ];
+        let (packet, _) = LpPacket::try_decode(bytes).unwrap();
+        assert_eq!(
+            packet.nack,
+            Some(Nack {
+                reason: NackReason::NoRoute
+            })
+        );
+        let mut encoded = Vec::new();
+        let _ = packet.encode(&mut encoded);
+        assert_eq!(encoded.as_slice(), bytes);
+
+        // The Nack without a reason and the unknown reasons
+        let (packet, _) = LpPacket::try_decode(&[100, 4, 253, 3, 32, 0]).unwrap();
+        assert_eq!(packet.nack.unwrap().reason, NackReason::Unspecified);
+        let (packet, _) = LpPacket::try_decode(&[100, 9, 253, 3, 32, 5, 253, 3, 33, 1, 7]).unwrap();
+        assert_eq!(packet.nack.unwrap().reason, NackReason::Other(7));
+    }
+
+    #[test]
+    fn test_lp_fragmentation() {
+        assert!(LpFragmenter::new(FRAGMENT_HEADER_LENGTH).is_none());
+        let mut fragmenter = LpFragmenter::new(200).unwrap();
+        let packet: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let nack = Nack {
+            reason: NackReason::Congestion,
+        };
+        let mut fragments: Vec<Vec<u8>> = Vec::new();
+        let sent: Result<(), ()> = fragmenter.fragment(&packet, Some(nack), &mut |bytes| {
+            fragments.push(Vec::from(bytes));
+            Ok(())
+        });
+        assert!(sent.is_ok());
+        assert_eq!(
+            fragments.len(),
+            1000usize.div_ceil(200 - FRAGMENT_HEADER_LENGTH)
+        );
+        assert!(fragments.iter().all(|f| f.len() <= 200));
+        assert!(
+            fragments.iter().map(|f| f.len()).sum::<usize>() <= fragmenter.fragmented_length(1000)
+        );
+
+        // The fragments can come in any order, and only the last one completes the packet
+        let mut reassembler = LpReassembler::new();
+        for fragment in fragments.iter().rev().skip(1) {
+            let (lp, _) = LpPacket::try_decode(fragment).unwrap();
+            assert!(reassembler.receive(&lp).is_none());
+        }
+        assert_eq!(reassembler.partial.len(), 1);
+        let (lp, _) = LpPacket::try_decode(fragments.last().unwrap()).unwrap();
+        let whole = reassembler.receive(&lp).unwrap();
+        let (lp, _) = LpPacket::try_decode(&whole).unwrap();
+        assert_eq!(lp.nack, Some(nack));
+        assert_eq!(lp.fragment.unwrap().bytes, packet.as_slice());
+
+        // Without the Nack the packet comes back as it was sent
+        let mut fragments: Vec<Vec<u8>> = Vec::new();
+        let sent: Result<(), ()> = fragmenter.fragment(&packet, None, &mut |bytes| {
+            fragments.push(Vec::from(bytes));
+            Ok(())
+        });
+        assert!(sent.is_ok());
+        let mut whole = None;
+        for fragment in fragments.iter() {
+            let (lp, _) = LpPacket::try_decode(fragment).unwrap();
+            whole = reassembler.receive(&lp);
+        }
+        assert_eq!(whole, Some(packet));
+
+        // A peer cannot claim more fragments than a packet can need, nor send more bytes
+        let payload = [0u8; 1000];
+        let fragment = |index: u64, count: u64| LpPacket {
+            sequence: Some(LpSequence {
+                bytes: (100 + index).to_be_bytes(),
+            }),
+            frag_index: Some(FragIndex { val: index }),
+            frag_count: Some(FragCount { val: count }),
+            nack: None,
+            congestion_mark: None,
+            acks: &[],
+            tx_sequence: None,
+            fragment: Some(Fragment { bytes: &payload }),
+        };
+        assert!(reassembler
+            .receive(&fragment(0, MAX_PACKET_SIZE as u64))
+            .is_none());
+        assert!(reassembler.partial.is_empty());
+        let count = MAX_PACKET_SIZE.div_ceil(MIN_FRAGMENT_PAYLOAD_LENGTH) as u64;
+        for index in 0..MAX_PACKET_SIZE as u64 / 1000 {
+            assert!(reassembler.receive(&fragment(index, count)).is_none());
+            assert_eq!(reassembler.partial.len(), 1);
+        }
+        assert!(reassembler
+            .receive(&fragment(MAX_PACKET_SIZE as u64 / 1000, count))
+            .is_none());
+        assert!(reassembler.partial.is_empty());
+    }
+
+    #[test]
+    fn test_lp_reliability() {
+        let now = Rc::new(Cell::new(1000));
+        let losing = Rc::new(Cell::new(false));
+        let options = LpReliabilityOptions::default();
+
+        // Only the link from a to b loses packets
+        let (a_to_b_sender, a_to_b_receiver) = default_local_face();
+        let (b_to_a_sender, b_to_a_receiver) = default_local_face();
+        let lossy = LossySender {
+            sender: a_to_b_sender,
+            losing: Rc::clone(&losing),
+            buffer: Vec::new(),
+        };
+        let clock = ManualClock {
+            now: Rc::clone(&now),
+        };
+        let (mut a_sender, mut a_receiver) =
+            lp_reliable_face(lossy, b_to_a_receiver, clock.clone(), options);
+        let (mut b_sender, mut b_receiver) =
+            lp_reliable_face(b_to_a_sender, a_to_b_receiver, clock, options);
+
+        let comp = &[NameComponent::generic(b"ndn")];
+        let name = Name::new();
+        let name = name.adding_components(comp);
+        let mut packet = Vec::new();
+        let _ = Interest::new(name, false, [1, 2, 3, 4]).encode(&mut packet);
+
+        // The first one is lost and sent again after the timeout
+        losing.set(true);
+        assert!(a_sender.write(&packet).is_ok());
+        assert!(a_sender.flush().is_ok());
+        losing.set(false);
+        let mut buf = [0; 100];
+        assert_eq!(b_receiver.try_recv(&mut buf), Ok(0));
+        now.set(now.get() + options.retransmission_timeout_ms);
+        assert_eq!(a_receiver.try_recv(&mut buf), Ok(0));
+        assert_eq!(a_sender.stats().retransmitted, 1);
+        assert_eq!(b_receiver.try_recv(&mut buf), Ok(packet.len()));
+        assert_eq!(&buf[..packet.len()], packet.as_slice());
+
+        // The Ack goes alone after a while, since b has nothing to send
+        assert_eq!(a_sender.unacknowledged(), 1);
+        now.set(now.get() + options.ack_delay_ms);
+        assert!(b_sender.flush().is_ok());
+        assert_eq!(a_receiver.try_recv(&mut buf), Ok(0));
+        assert_eq!(a_sender.unacknowledged(), 0);
+        assert_eq!(a_sender.stats().acknowledged, 1);
+
+        // A retransmission whose original arrived is not delivered twice
+        assert!(a_sender.write(&packet).is_ok());
+        assert!(a_sender.flush().is_ok());
+        assert_eq!(b_receiver.try_recv(&mut buf), Ok(packet.len()));
+        now.set(now.get() + options.retransmission_timeout_ms);
+        assert!(a_sender.flush().is_ok());
+        assert_eq!(b_receiver.try_recv(&mut buf), Ok(0));
+        assert_eq!(b_sender.stats().duplicates_received, 1);
+        now.set(now.get() + options.ack_delay_ms);
+        assert!(b_sender.flush().is_ok());
+        assert_eq!(a_receiver.try_recv(&mut buf), Ok(0));
+        assert_eq!(a_sender.unacknowledged(), 0);
+
+        // Without any Acks the fragment is eventually given up on
+        losing.set(true);
+        assert!(a_sender.write(&packet).is_ok());
+        assert!(a_sender.flush().is_ok());
+        for _ in 0..=options.max_retransmissions {
+            now.set(now.get() + options.retransmission_timeout_ms);
+            assert!(a_sender.flush().is_ok());
+        }
+        assert_eq!(a_sender.stats().lost, 1);
+    }
+}
@@ -1,12 +1,45 @@
-use core::time::Duration;
+use core::{
+    task::{Context, Poll},
+    time::Duration,
+};
+
+pub mod app;
 
 pub mod buffered;
 
+pub mod compact;
+
 pub mod local;
 
-#[derive(Debug, PartialEq, Eq)]
+pub mod lp;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum FaceError {
+    // The other side is gone, the face cannot be used anymore
     Disconnected,
+    // The face is temporarily not ready, the same operation can be retried later
+    WouldBlock,
+    // The operation was interrupted before anything was done and can be retried right away
+    Interrupted,
+    // This particular packet could not be sent, but the face can still be used for others
+    MessageTooLarge,
+    // The operation was not allowed, e.g. sending to a broadcast address or
+    //  a firewall rule. Other packets could still be allowed, so the face is kept.
+    PermissionDenied,
+    // Any other error the face cannot recover from, with the OS error code if there is one
+    Fatal(i32),
+}
+
+impl FaceError {
+    // Fatal errors mean the face has to be closed, while the others only
+    //  affect the current operation.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, FaceError::Disconnected | FaceError::Fatal(_))
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, FaceError::WouldBlock | FaceError::Interrupted)
+    }
 }
 
 pub trait FaceReceiver {
@@ -28,6 +61,31 @@ pub trait FaceSender {
     fn flush(&mut self) -> Result<(), FaceError> {
         Ok(())
     }
+
+    // The number of bytes that can be sent right now without blocking, if known.
+    // The forwarder uses it for backpressure, pausing the reads from the faces
+    //  whose packets fill this one up.
+    fn send_capacity(&self) -> Option<usize> {
+        None
+    }
+}
+
+// The faces driven by an async runtime, e.g. tokio or smol, see AsyncForwarder.
+// Like AsyncRead and AsyncWrite, Pending means that the waker of the context is woken
+//  once the face is ready again.
+pub trait AsyncFaceReceiver {
+    fn poll_recv(&mut self, cx: &mut Context<'_>, dst: &mut [u8])
+        -> Poll<Result<usize, FaceError>>;
+}
+
+pub trait AsyncFaceSender {
+    fn poll_send(&mut self, cx: &mut Context<'_>, src: &[u8]) -> Poll<Result<usize, FaceError>>;
+
+    // Only retried before the next packet is sent, so the senders that hold the bytes
+    //  back until they are flushed should not stay Pending for long
+    fn poll_flush(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), FaceError>> {
+        Poll::Ready(Ok(()))
+    }
 }
 
 pub trait BlockingFaceReceiver {
@@ -45,7 +103,11 @@ impl<FS: FaceSender + ?Sized> crate::io::Write for FS {
         let len = bytes.len();
         let mut sent_so_far = 0;
         while sent_so_far < len {
-            sent_so_far += self.try_send(&bytes[sent_so_far..])?;
+            match self.try_send(&bytes[sent_so_far..]) {
+                Ok(bytes_sent) => sent_so_far += bytes_sent,
+                Err(err) if err.is_retryable() => {}
+                Err(err) => return Err(err),
+            }
         }
         debug_assert!(sent_so_far == len);
         Ok(())
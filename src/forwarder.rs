@@ -1,14 +1,28 @@
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, collections::vec_deque::VecDeque, vec, vec::Vec};
 
 use crate::{
-    clock::Clock,
-    face::{FaceError, FaceReceiver, FaceSender},
+    clock::{Clock, Timestamp},
+    diagnostics::{Diagnostics, Level, Subsystem},
+    face::{
+        lp::{CongestionMark, Fragment, LpFragmenter, LpPacket, LpReassembler, Nack, NackReason},
+        FaceError, FaceReceiver, FaceSender,
+    },
     hash::{Hasher, Sha256Digest},
-    io::{Decode, Write},
-    name::Name,
-    packet::{Data, Interest},
-    tables::Tables,
-    tlv::{TlvDecode, TlvDecodingError, TlvEncode, VarintDecodingError, TLV},
+    io::{Decode, Encode, Write},
+    mgmt::{
+        self, CommandValidator, ControlCommand, ControlParameters, ControlResponse, CsInfo,
+        FaceCreationError, FaceFactory, FaceStatus, FibEntry, ForwarderStatus, RibEntry,
+        RouteRecord, StatusCode, StatusDataset, StatusDatasetKind,
+    },
+    name::{Name, NameBuf, NameComponent},
+    nonce::{NonceSource, SplitMixNonces},
+    packet::{Data, DataBuilder, HopLimit, Interest, InterestBuf, MustBeFresh},
+    stats::PrefixStats,
+    tables::{
+        rib::{RegistrationPolicy, Rib, Route, RouteOrigin},
+        ContentStoreUsage, NackOutcome, Tables,
+    },
+    tlv::{TlvDecode, TlvDecodingError, TlvEncode, Varint, VarintDecodingError, TLV},
 };
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -27,23 +41,522 @@ pub trait ForwarderMetrics {
     fn interest_satisfied(&mut self, _from_face: FaceToken) {}
     fn interest_timed_out(&mut self, _from_face: FaceToken) {}
     fn interest_sent(&mut self, _to_face: FaceToken) {}
+    // An identical interest was already forwarded in the same batch
+    fn interest_coalesced(&mut self, _from_face: FaceToken) {}
+    // The interest was sent back in a Nack
+    fn nack_sent(&mut self, _to_face: FaceToken) {}
+    fn nack_received(&mut self, _from_face: FaceToken, _reason: NackReason) {}
 
     fn data_received(&mut self, _from_face: FaceToken) {}
     fn data_sent(&mut self, _to_face: FaceToken) {}
     fn data_dropped(&mut self, _from_face: FaceToken) {}
 
+    // The length of every packet taken from the face, and of what went to the face,
+    //  including the queued bytes and the headers of the fragments
+    fn bytes_received(&mut self, _from_face: FaceToken, _len: usize) {}
+    fn bytes_sent(&mut self, _to_face: FaceToken, _len: usize) {}
+
+    // Whether an interest that went through the content store found the data there,
+    //  the stale data served without a route included
+    fn cs_hit(&mut self, _from_face: FaceToken) {}
+    fn cs_miss(&mut self, _from_face: FaceToken) {}
+
     fn invalid_packet_received(&mut self, _from_face: FaceToken) {}
+    // A packet longer than MAX_PACKET_SIZE was discarded, with the length of the whole TLV
+    fn oversized_packet_skipped(&mut self, _from_face: FaceToken, _len: usize) {}
+
+    // A route to the face was withdrawn because its lifetime lapsed
+    fn route_expired(&mut self, _to_face: FaceToken) {}
+
+    // What the status datasets report for the face, which is all zeros unless the
+    //  metrics keep the counts
+    fn face_counters(&self, _face: FaceToken) -> FaceCounters {
+        FaceCounters::default()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct FaceCounters {
+    pub in_interests: u64,
+    pub in_data: u64,
+    pub in_nacks: u64,
+    pub out_interests: u64,
+    pub out_data: u64,
+    pub out_nacks: u64,
+    pub satisfied_interests: u64,
+    pub unsatisfied_interests: u64,
+    pub in_bytes: u64,
+    pub out_bytes: u64,
+}
 
-    // TODO: probably count how many data from cache vs
-    // TODO: add bytes
+impl core::ops::AddAssign for FaceCounters {
+    fn add_assign(&mut self, other: Self) {
+        self.in_interests += other.in_interests;
+        self.in_data += other.in_data;
+        self.in_nacks += other.in_nacks;
+        self.out_interests += other.out_interests;
+        self.out_data += other.out_data;
+        self.out_nacks += other.out_nacks;
+        self.satisfied_interests += other.satisfied_interests;
+        self.unsatisfied_interests += other.unsatisfied_interests;
+        self.in_bytes += other.in_bytes;
+        self.out_bytes += other.out_bytes;
+    }
 }
 
 pub struct InertMetrics {}
 
+// Whether the face leads to the applications on the same host or to the network.
+// The /localhost namespace never leaves the local faces, which also keeps the management
+//  commands local, and the /localhop one never goes further than one hop from them.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Scope {
+    // Only the faces known to lead to this host, e.g. the Unix, local and app faces,
+    //  are added with it, so that a remote peer is never trusted by mistake
+    Local,
+    #[default]
+    NonLocal,
+}
+
+fn is_under(name: Name<'_>, first_component: &[u8]) -> bool {
+    name.components().next() == Some(NameComponent::generic(first_component))
+}
+
+// What happens to a face that stays idle for longer than its timeout, see set_idle_timeout
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Persistency {
+    // Kept open, and sent the keepalives so that the other end does not see it as idle
+    #[default]
+    Persistent,
+    // Closed, e.g. the faces created for the peers that contacted the forwarder first
+    OnDemand,
+}
+
+// Whether a packet from a face of the scope "from" can go to a face of the scope "to"
+fn scope_allows(from: Scope, to: Scope, name: Name<'_>) -> bool {
+    match (from, to) {
+        (_, Scope::Local) => true,
+        (Scope::Local, Scope::NonLocal) => !is_under(name, mgmt::LOCALHOST_COMPONENT),
+        (Scope::NonLocal, Scope::NonLocal) => {
+            !is_under(name, mgmt::LOCALHOST_COMPONENT) && !is_under(name, mgmt::LOCALHOP_COMPONENT)
+        }
+    }
+}
+
+// Restricts which packet types the forwarder accepts from a face.
+// A face to an untrusted consumer, for example, can be made interest-only
+//  so that it can never inject data into the content store.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum InboundPacketFilter {
+    #[default]
+    AcceptAll,
+    InterestsOnly,
+    DataOnly,
+}
+
+impl InboundPacketFilter {
+    pub fn accepts_interests(&self) -> bool {
+        !matches!(self, InboundPacketFilter::DataOnly)
+    }
+
+    pub fn accepts_data(&self) -> bool {
+        !matches!(self, InboundPacketFilter::InterestsOnly)
+    }
+}
+
 impl ForwarderMetrics for InertMetrics {}
 
 pub const MAX_PACKET_SIZE: usize = 8800;
 
+// How many bytes can wait for a face that does not take them fast enough, by default
+pub const DEFAULT_OUTBOUND_QUEUE_LIMIT: usize = 16 * MAX_PACKET_SIZE;
+
+// The parameters of the congestion marking of a face, as in CoDel, see set_congestion_marking
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CongestionMarking {
+    // How long the packets can wait in the outbound queue
+    pub target_ms: u64,
+    // How long the wait can stay above the target before the face is congested
+    pub interval_ms: u64,
+}
+
+impl Default for CongestionMarking {
+    fn default() -> Self {
+        CongestionMarking {
+            target_ms: 5,
+            interval_ms: 100,
+        }
+    }
+}
+
+// The queue of a face with an egress scheduling that a packet waits in
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TrafficClass {
+    // By default the interests and the Nacks
+    Interactive,
+    // By default the data
+    Bulk,
+}
+
+// The shares of a face that the traffic classes get while both have packets waiting,
+//  see set_egress_scheduling
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct EgressScheduling {
+    pub interactive_weight: u32,
+    pub bulk_weight: u32,
+}
+
+impl Default for EgressScheduling {
+    fn default() -> Self {
+        EgressScheduling {
+            interactive_weight: 4,
+            bulk_weight: 1,
+        }
+    }
+}
+
+// How many bytes a class may send for each unit of its weight in a round of the scheduling
+const SCHEDULING_QUANTUM: usize = 1500;
+
+// How the forwarder retransmits the interests of a namespace, see set_reliable_fetch
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ReliableFetch {
+    // How long to wait for the data before sending the interest again
+    pub retry_after_ms: u64,
+    pub max_retries: u8,
+}
+
+impl Default for ReliableFetch {
+    fn default() -> Self {
+        ReliableFetch {
+            retry_after_ms: 500,
+            max_retries: 3,
+        }
+    }
+}
+
+// The lifetime of the interests that do not give one, as in the packet format
+const DEFAULT_INTEREST_LIFETIME_MS: u64 = 4000;
+
+// How many of the interests that found no route wait for one, the oldest going first
+const MAX_UNROUTED_INTERESTS: usize = 64;
+
+// How many receive buffers are kept for the faces once they are returned
+const MAX_SPARE_RECV_BUFFERS: usize = 4;
+
+// Face tokens start at 1, so this never collides with a real face.
+// The PIT entries of the warm-up probes reply to it, which means the data
+//  answering a probe is cached but not sent anywhere.
+const WARM_UP_FACE: FaceToken = FaceToken(0);
+
+// Reported in the status datasets for the faces that were not created by a command
+const INTERNAL_FACE_URI: &[u8] = b"internal://";
+
+// The outcome of the warm-up phase, which can be used to hold off
+//  the traffic until the forwarder is actually able to serve it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReadinessReport {
+    pub faces: Vec<FaceProbe>,
+    pub routes: Vec<RouteProbe>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FaceProbe {
+    pub face: FaceToken,
+    pub is_connected: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RouteProbe {
+    pub name: NameBuf,
+    // The faces the probe interest was successfully sent to
+    pub sent_to: Vec<FaceToken>,
+}
+
+impl ReadinessReport {
+    // Ready means that every face is connected and every probed route
+    //  has at least one working next hop.
+    pub fn is_ready(&self) -> bool {
+        self.faces.iter().all(|f| f.is_connected)
+            && self.routes.iter().all(|r| !r.sent_to.is_empty())
+    }
+
+    pub fn disconnected_faces(&self) -> impl Iterator<Item = FaceToken> + '_ {
+        self.faces
+            .iter()
+            .filter(|f| !f.is_connected)
+            .map(|f| f.face)
+    }
+
+    pub fn unreachable_routes(&self) -> impl Iterator<Item = &NameBuf> + '_ {
+        self.routes
+            .iter()
+            .filter(|r| r.sent_to.is_empty())
+            .map(|r| &r.name)
+    }
+}
+
+// Gets the names of popular cached data shortly before its freshness lapses,
+//  e.g. so that an attached producer republishes it or a prefetcher re-expresses
+//  the interest, while the cached copy still satisfies the consumers.
+pub trait RefreshAheadHook {
+    fn data_expiring(&mut self, name: Name<'_>);
+}
+
+// Gets the events of the forwarder with the names of the packets involved, e.g. to pass
+//  them on to a logger, while the metrics only count them
+pub trait ForwarderObserver {
+    fn event(&mut self, event: &ForwarderEvent<'_>);
+}
+
+#[derive(Copy, Clone)]
+pub enum ForwarderEvent<'a> {
+    FaceAdded {
+        face: FaceToken,
+    },
+    // The failures come first, and the face is removed later by its owner
+    FaceClosed {
+        face: FaceToken,
+        reason: FaceCloseReason,
+    },
+    InterestForwarded {
+        from: FaceToken,
+        to: FaceToken,
+        name: Name<'a>,
+    },
+    // Also for the stale data served when there is no route
+    CacheHit {
+        face: FaceToken,
+        name: Name<'a>,
+        stale: bool,
+    },
+    // An identical interest from the same batch was already forwarded, so this one only
+    //  joined its PIT entry
+    InterestAggregated {
+        face: FaceToken,
+        name: Name<'a>,
+    },
+    InterestDropped {
+        face: FaceToken,
+        name: Name<'a>,
+    },
+    NackReceived {
+        from: FaceToken,
+        name: Name<'a>,
+        reason: NackReason,
+    },
+    NackSent {
+        to: FaceToken,
+        name: Name<'a>,
+        reason: NackReason,
+    },
+    DataForwarded {
+        from: FaceToken,
+        to: FaceToken,
+        name: Name<'a>,
+    },
+    UnsolicitedData {
+        from: FaceToken,
+        name: Name<'a>,
+    },
+    // Dropped by the packet filter
+    PacketFiltered {
+        face: FaceToken,
+        name: Name<'a>,
+        kind: PacketKind,
+        direction: Direction,
+    },
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FaceCloseReason {
+    Disconnected,
+    // A fatal error of the transport, while sending or receiving
+    Failed,
+    // The face sent bytes that are not a TLV
+    Undecodable,
+    Removed,
+    // An on-demand face received nothing for longer than its idle timeout
+    Idle,
+}
+
+// Decides which packets get in from the faces and out to them, e.g. so that a gateway keeps
+//  some namespaces away from the untrusted faces. Asked once the packet is decoded, before
+//  it reaches the tables, and for every face the forwarder is about to send it to.
+pub trait PacketFilter {
+    fn filter(
+        &mut self,
+        direction: Direction,
+        face: FaceToken,
+        kind: PacketKind,
+        name: Name<'_>,
+    ) -> FilterVerdict;
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Ingress,
+    Egress,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PacketKind {
+    Interest,
+    Data,
+    // Named after the interest it carries
+    Nack,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FilterVerdict {
+    Accept,
+    Drop,
+}
+
+// Gets the packets the faces send to the forwarder and those it sends to them, e.g. to
+//  replay them later, see replay::Replay
+pub trait PacketCapture {
+    fn capture(&mut self, packet: &CapturedPacket<'_>);
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct CapturedPacket<'a> {
+    pub face: FaceToken,
+    pub direction: Direction,
+    pub timestamp: Timestamp,
+    // A whole packet, which on ingress is taken out of the LpPacket it came alone in
+    pub bytes: &'a [u8],
+}
+
+// Drops the packets under the blocked prefixes, in both directions, on the faces
+//  they are blocked for
+#[derive(Default)]
+pub struct NamespaceBlocklist {
+    rules: Vec<(FaceToken, NameBuf)>,
+}
+
+impl NamespaceBlocklist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn block(&mut self, face: FaceToken, prefix: Name<'_>) {
+        if !self.is_blocked(face, prefix) {
+            self.rules.push((face, NameBuf::from(prefix)));
+        }
+    }
+
+    pub fn unblock(&mut self, face: FaceToken, prefix: Name<'_>) {
+        self.rules
+            .retain(|(f, p)| *f != face || p.as_name() != prefix);
+    }
+
+    pub fn is_blocked(&self, face: FaceToken, name: Name<'_>) -> bool {
+        self.rules
+            .iter()
+            .any(|(f, prefix)| *f == face && prefix.as_name().is_prefix_of(name))
+    }
+}
+
+impl PacketFilter for NamespaceBlocklist {
+    fn filter(
+        &mut self,
+        _direction: Direction,
+        face: FaceToken,
+        _kind: PacketKind,
+        name: Name<'_>,
+    ) -> FilterVerdict {
+        if self.is_blocked(face, name) {
+            FilterVerdict::Drop
+        } else {
+            FilterVerdict::Accept
+        }
+    }
+}
+
+// The hooks that the packet handlers need besides the metrics
+#[derive(Default)]
+struct Hooks {
+    observer: Option<Box<dyn ForwarderObserver>>,
+    packet_filter: Option<Box<dyn PacketFilter>>,
+    // The interests flooded for the lack of a route only go to the non-local faces
+    flood_non_local_only: bool,
+    // The prefixes under which each face may push the data that nobody asked for
+    unsolicited_allowed: Vec<(NameBuf, FaceToken)>,
+    capture: Option<Box<dyn PacketCapture>>,
+}
+
+impl Hooks {
+    fn observe(&mut self, event: ForwarderEvent<'_>) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.event(&event);
+        }
+    }
+
+    // Every packet is allowed until a filter is set
+    fn allows(
+        &mut self,
+        direction: Direction,
+        face: FaceToken,
+        kind: PacketKind,
+        name: Name<'_>,
+    ) -> bool {
+        let Some(filter) = self.packet_filter.as_mut() else {
+            return true;
+        };
+        if filter.filter(direction, face, kind, name) == FilterVerdict::Accept {
+            return true;
+        }
+        self.observe(ForwarderEvent::PacketFiltered {
+            face,
+            name,
+            kind,
+            direction,
+        });
+        false
+    }
+
+    fn capture(
+        &mut self,
+        face: FaceToken,
+        direction: Direction,
+        timestamp: Timestamp,
+        bytes: &[u8],
+    ) {
+        if let Some(capture) = self.capture.as_mut() {
+            capture.capture(&CapturedPacket {
+                face,
+                direction,
+                timestamp,
+                bytes,
+            });
+        }
+    }
+
+    fn accepts_unsolicited(&self, from: FaceToken, name: Name<'_>) -> bool {
+        self.unsolicited_allowed
+            .iter()
+            .any(|(prefix, face)| *face == from && prefix.as_name().is_prefix_of(name))
+    }
+}
+
+struct RefreshAhead {
+    lead_time_ms: u64,
+    min_hits: u32,
+    hook: Box<dyn RefreshAheadHook>,
+    last_check: Timestamp,
+}
+
+// The parts of the forwarder that the packet handlers use, borrowed apart from the
+//  receive buffers of the faces
+struct PacketContext<'f, C, M, T> {
+    tables: &'f mut T,
+    metrics: &'f mut M,
+    hooks: &'f mut Hooks,
+    clock: &'f mut C,
+    faces: &'f mut [(u32, FaceEntry)],
+}
+
 pub struct Forwarder<C, H, M, T>
 where
     C: Clock,
@@ -53,10 +566,42 @@ where
 {
     faces: Faces,
     tables: T,
+    rib: Rib,
     metrics: M,
     clock: C,
     hasher: H,
+    diagnostics: Diagnostics,
+    batch: InterestBatch,
+    prefix_stats: PrefixStats,
+    refresh_ahead: Option<RefreshAhead>,
+    hooks: Hooks,
+    nonces: Box<dyn NonceSource>,
+    serve_stale: bool,
+    registration_policy: Option<Box<dyn RegistrationPolicy>>,
+    // The management commands are only executed once a validator is set
+    command_validator: Option<Box<dyn CommandValidator>>,
+    face_factory: Option<Box<dyn FaceFactory>>,
+    // Removed in the housekeeping, so that the faces do not shift while being read from
+    destroyed_faces: Vec<FaceToken>,
+    // Removed by the forwarder itself, until the caller takes them
+    reaped_faces: Vec<FaceToken>,
+    // The failed faces are removed in the housekeeping when enabled
+    reap_dead_faces: bool,
+    dead_faces: Vec<(FaceToken, FaceCloseReason)>,
     last_checked_face: usize,
+    // Where the interests that got a default HopLimit are re-encoded
+    reencoded_packet: Vec<u8>,
+    reliable_fetch: Vec<(NameBuf, ReliableFetch)>,
+    // The interests forwarded under those prefixes that did not get their data yet
+    pending_retries: Vec<PendingRetry>,
+    // The interests that went nowhere for the lack of a route, which are forwarded
+    //  once a route for them is registered
+    unrouted_interests: Vec<UnroutedInterest>,
+    // The latest version of each status dataset, indexed by the kind
+    status_datasets: [Option<StatusDataset>; 4],
+    started_at: Timestamp,
+    cs_hits: u64,
+    cs_misses: u64,
 }
 
 impl<C, H, M, T> Forwarder<C, H, M, T>
@@ -66,16 +611,43 @@ where
     M: ForwarderMetrics,
     T: Tables,
 {
-    pub fn new(clock: C, hasher: H, metrics: M, tables: T) -> Self {
+    pub fn new(mut clock: C, hasher: H, metrics: M, tables: T) -> Self {
         let faces = Faces::new();
+        // Only differs between the runs that start at different times, which is why
+        //  the platforms set a better source
+        let started_at = clock.now();
+        let nonces = SplitMixNonces::new(started_at.ms_since_1970);
 
         Self {
             faces,
             tables,
+            rib: Rib::new(),
             metrics,
             clock,
             hasher,
+            diagnostics: Diagnostics::new(),
+            batch: InterestBatch::new(),
+            prefix_stats: PrefixStats::new(),
+            refresh_ahead: None,
+            hooks: Hooks::default(),
+            nonces: Box::new(nonces),
+            serve_stale: false,
+            registration_policy: None,
+            command_validator: None,
+            face_factory: None,
+            destroyed_faces: Vec::new(),
+            reaped_faces: Vec::new(),
+            reap_dead_faces: false,
+            dead_faces: Vec::new(),
             last_checked_face: 0,
+            reencoded_packet: Vec::new(),
+            reliable_fetch: Vec::new(),
+            pending_retries: Vec::new(),
+            unrouted_interests: Vec::new(),
+            status_datasets: Default::default(),
+            started_at,
+            cs_hits: 0,
+            cs_misses: 0,
         }
     }
 
@@ -84,598 +656,7062 @@ where
         FS: FaceSender + 'static,
         FR: FaceReceiver + 'static,
     {
-        self.faces.add_face(sender, receiver)
+        self.add_boxed_face(Box::new(sender), Box::new(receiver))
+    }
+
+    // For the datagram faces, whose packets are fragmented above the MTU and whose
+    //  fragments are put back together before they reach the forwarder.
+    // Returns None if the MTU is too small to carry any fragment.
+    pub fn add_face_with_mtu<FS, FR>(
+        &mut self,
+        sender: FS,
+        receiver: FR,
+        mtu: usize,
+    ) -> Option<FaceToken>
+    where
+        FS: FaceSender + 'static,
+        FR: FaceReceiver + 'static,
+    {
+        let fragmenter = LpFragmenter::new(mtu)?;
+        let token = self.add_face(sender, receiver)?;
+        if let Some(idx) = Faces::find_face(&self.faces.faces, &token) {
+            let entry = &mut self.faces.faces[idx].1;
+            entry.fragmenter = Some(fragmenter);
+            entry.reassembler = Some(LpReassembler::new());
+        }
+        Some(token)
+    }
+
+    // The MTU the face was added with, if any
+    pub fn face_mtu(&self, token: FaceToken) -> Option<usize> {
+        let idx = Faces::find_face(&self.faces.faces, &token)?;
+        self.faces.faces[idx].1.fragmenter.as_ref().map(|f| f.mtu())
+    }
+
+    // Also see set_face_scope
+    pub fn add_face_with_scope<FS, FR>(
+        &mut self,
+        sender: FS,
+        receiver: FR,
+        scope: Scope,
+    ) -> Option<FaceToken>
+    where
+        FS: FaceSender + 'static,
+        FR: FaceReceiver + 'static,
+    {
+        let token = self.add_face(sender, receiver)?;
+        self.set_face_scope(token, scope);
+        Some(token)
+    }
+
+    pub fn add_boxed_face(
+        &mut self,
+        sender: Box<dyn FaceSender>,
+        receiver: Box<dyn FaceReceiver>,
+    ) -> Option<FaceToken> {
+        let token = self.faces.add_face(sender, receiver);
+        if let Some(token) = token {
+            self.tables.register_face(token);
+            if self.hooks.capture.is_some() {
+                if let Some(index) = Faces::find_face(&self.faces.faces, &token) {
+                    self.faces.faces[index].1.captured_egress = Some(Vec::new());
+                }
+            }
+            self.diagnostics.record(
+                Subsystem::Faces,
+                Level::Info,
+                format_args!("added face {}", token.0),
+            );
+            self.hooks
+                .observe(ForwarderEvent::FaceAdded { face: token });
+        }
+        token
     }
 
     pub fn remove_face(&mut self, token: FaceToken) -> bool {
+        self.close_face(token, FaceCloseReason::Removed)
+    }
+
+    fn close_face(&mut self, token: FaceToken, reason: FaceCloseReason) -> bool {
+        let removed = self.drop_face(token, reason);
+        if removed {
+            self.hooks.observe(ForwarderEvent::FaceClosed {
+                face: token,
+                reason,
+            });
+        }
+        removed
+    }
+
+    // Like close_face, for the faces whose closing was already reported
+    fn drop_face(&mut self, token: FaceToken, reason: FaceCloseReason) -> bool {
+        self.rib.remove_face(token, &mut self.tables);
         self.tables.unregister_face(token);
-        self.faces.remove_face(token)
+        let removed = self.faces.remove_face(token);
+        if removed {
+            // The token may go to another face later
+            self.hooks
+                .unsolicited_allowed
+                .retain(|(_, face)| *face != token);
+            self.unrouted_interests
+                .retain(|unrouted| unrouted.origin != token);
+            self.prefix_stats.face_removed(token);
+            self.diagnostics.record(
+                Subsystem::Faces,
+                Level::Info,
+                format_args!("removed face {} ({:?})", token.0, reason),
+            );
+        }
+        removed
+    }
+
+    // Swaps the transport of an existing face, e.g. after reconnecting, while keeping
+    //  its token, so that the FIB registrations and the pending PIT in-records stay valid.
+    // Bytes of an incomplete packet received from the old transport are discarded.
+    // Returns false if the face is unknown.
+    pub fn replace_face<FS, FR>(&mut self, token: FaceToken, sender: FS, receiver: FR) -> bool
+    where
+        FS: FaceSender + 'static,
+        FR: FaceReceiver + 'static,
+    {
+        let replaced = self.faces.replace_face(token, sender, receiver);
+        if replaced {
+            self.diagnostics.record(
+                Subsystem::Faces,
+                Level::Info,
+                format_args!("replaced transport of face {}", token.0),
+            );
+        }
+        replaced
     }
 
     pub fn next_face_token(&self) -> Option<FaceToken> {
         Some(FaceToken(self.faces.next_face_token()?))
     }
 
-    pub fn register_name_prefix_for_forwarding<'a>(
-        &mut self,
-        name_prefix: Name<'a>,
-        forward_to: FaceToken,
-        cost: u32,
-    ) {
-        self.tables.register_prefix(name_prefix, forward_to, cost)
+    pub fn has_face(&self, token: FaceToken) -> bool {
+        Faces::find_face(&self.faces.faces, &token).is_some()
     }
 
-    pub fn unregister_name_prefix_for_forwarding<'a>(
-        &mut self,
-        name_prefix: Name<'a>,
-        forward_to: FaceToken,
-    ) -> bool {
-        self.tables.unregister_prefix(name_prefix, forward_to)
+    pub fn face_count(&self) -> usize {
+        self.faces.len()
     }
 
-    pub fn try_forward_from_face(&mut self, face: FaceToken) -> Result<(), ForwarderError> {
-        let ret = if let Some(index) = Faces::find_face(&self.faces.faces, &face) {
-            if self.try_recv_from_face_at_index(index)? {
-                Ok(())
-            } else {
-                Err(ForwarderError::NothingToForward)
-            }
-        } else {
-            Err(ForwarderError::FaceNotfound)
-        };
-        self.tables.prune_if_needed(self.clock.now());
-        ret
+    // The faces that are not read from because the faces their packets went to
+    //  cannot take more, e.g. a local producer sending data faster than it can be
+    //  delivered. The reads resume once those faces drain.
+    pub fn paused_faces(&self) -> impl Iterator<Item = FaceToken> + '_ {
+        self.faces
+            .faces
+            .iter()
+            .filter(|(_, entry)| !entry.waiting_for.is_empty())
+            .map(|(token, _)| FaceToken(*token))
     }
 
-    pub fn try_forward_from_any_face(
-        &mut self,
-        excluded: &[FaceToken],
-    ) -> Result<FaceToken, ForwarderError> {
-        let mut ret = Err(ForwarderError::NothingToForward);
-        for _ in 0..self.faces.len() {
-            self.last_checked_face = (self.last_checked_face + 1) % self.faces.len();
-            if excluded.contains(&FaceToken(self.faces.faces[self.last_checked_face].0)) {
-                continue;
-            }
-            if self.try_recv_from_face_at_index(self.last_checked_face)? {
-                ret = Ok(FaceToken(self.faces.faces[self.last_checked_face].0));
-                break;
+    // The faces are local unless told otherwise, except for the ones created by a command.
+    // Returns false if the face is unknown.
+    pub fn set_face_scope(&mut self, token: FaceToken, scope: Scope) -> bool {
+        match Faces::find_face(&self.faces.faces, &token) {
+            Some(index) => {
+                self.faces.faces[index].1.scope = scope;
+                true
             }
+            None => false,
         }
-        self.tables.prune_if_needed(self.clock.now());
-        ret
     }
 
-    fn try_recv_from_face_at_index(&mut self, index: usize) -> Result<bool, ForwarderError> {
-        let (token, entry) = &mut self.faces.faces[index];
-        let origin = FaceToken(*token);
+    pub fn face_scope(&self, token: FaceToken) -> Option<Scope> {
+        let index = Faces::find_face(&self.faces.faces, &token)?;
+        Some(self.faces.faces[index].1.scope)
+    }
 
-        if entry.should_close {
-            return Err(ForwarderError::FaceDisconnected(origin));
-        }
+    // The faces the forwarder removed on its own since the last call, for being idle or
+    //  dead, so that the caller can release whatever it keeps for them
+    pub fn take_reaped_faces(&mut self) -> Vec<FaceToken> {
+        core::mem::take(&mut self.reaped_faces)
+    }
 
-        let (recv_buffer, recv_buffer_cursor) = &mut self.faces.recv_buffers[index];
+    // The faces that disconnect or fail are removed in the next housekeeping, together with
+    //  their routes and PIT records, instead of waiting for remove_face. The errors are still
+    //  returned, and the observer still gets the FaceClosed event with the reason.
+    // Off by default, so that the failed faces can be reconnected with replace_face.
+    pub fn set_reap_dead_faces(&mut self, reap: bool) {
+        self.reap_dead_faces = reap;
+        if !reap {
+            self.dead_faces.clear();
+        }
+    }
 
-        let mut should_try_recv = true;
+    fn face_closed(&mut self, face: FaceToken, reason: FaceCloseReason) {
+        self.hooks
+            .observe(ForwarderEvent::FaceClosed { face, reason });
+        if self.reap_dead_faces && !self.dead_faces.iter().any(|(dead, _)| *dead == face) {
+            self.dead_faces.push((face, reason));
+        }
+    }
 
-        // First, it could be possible that we already have a ready packet in buffer from last recv
-        // If so we will not try to receive new data
-        if *recv_buffer_cursor > 0 {
-            match TLV::try_decode(&recv_buffer[0..*recv_buffer_cursor]) {
-                Ok(_) => should_try_recv = false,
-                // If we have too few bytes this could be solved with a recv
-                Err(TlvDecodingError::CannotDecodeType {
-                    err: VarintDecodingError::BufferTooShort,
-                }) => {}
-                Err(TlvDecodingError::CannotDecodeLength {
-                    err: VarintDecodingError::BufferTooShort,
-                    ..
-                }) => {}
-                Err(TlvDecodingError::CannotDecodeValue { .. }) => {}
-                Err(err) => return Err(ForwarderError::FaceUnrecoverableError(origin, err)),
+    // The faces are persistent by default. Returns false if the face is unknown.
+    pub fn set_face_persistency(&mut self, token: FaceToken, persistency: Persistency) -> bool {
+        match Faces::find_face(&self.faces.faces, &token) {
+            Some(index) => {
+                self.faces.faces[index].1.persistency = persistency;
+                true
             }
+            None => false,
         }
+    }
 
-        if should_try_recv {
-            match entry.try_recv(recv_buffer, recv_buffer_cursor) {
-                Ok(bytes_received) => {
-                    if bytes_received == 0 {
-                        return Ok(false); // Nothing was received, the face is not ready
-                    }
-                }
-                Err(FaceError::Disconnected) => {
-                    return Err(ForwarderError::FaceDisconnected(origin))
-                }
+    // Once nothing is received from the face for the timeout, an on-demand face is closed.
+    // A persistent face is instead sent an LP IDLE packet whenever nothing else was sent
+    //  to it for half the timeout. No timeout, the default, turns both off.
+    // Returns false if the face is unknown.
+    pub fn set_idle_timeout(&mut self, token: FaceToken, timeout_ms: Option<u64>) -> bool {
+        match Faces::find_face(&self.faces.faces, &token) {
+            Some(index) => {
+                let entry = &mut self.faces.faces[index].1;
+                entry.idle_timeout_ms = timeout_ms;
+                // The face only starts being idle from now
+                entry.last_received = None;
+                entry.last_sent = None;
+                true
             }
+            None => false,
         }
+    }
 
-        let (tlv, tlv_len) = match TLV::try_decode(&recv_buffer[0..*recv_buffer_cursor]) {
-            Ok((tlv, tlv_len)) => (tlv, tlv_len),
-            // If we have too few bytes this could be solved with a recv
-            Err(TlvDecodingError::CannotDecodeType {
-                err: VarintDecodingError::BufferTooShort,
-            }) => return Ok(false),
-            Err(TlvDecodingError::CannotDecodeLength {
-                err: VarintDecodingError::BufferTooShort,
-                ..
-            }) => return Ok(false),
-            Err(TlvDecodingError::CannotDecodeValue { len, .. }) => {
-                if len > MAX_PACKET_SIZE {
-                    // TODO: should skip too-large packets, probably
-                }
-                return Ok(false);
+    // Sets which packet types are accepted from the face, returns false if the face is unknown.
+    pub fn set_inbound_packet_filter(
+        &mut self,
+        token: FaceToken,
+        filter: InboundPacketFilter,
+    ) -> bool {
+        match Faces::find_face(&self.faces.faces, &token) {
+            Some(index) => {
+                self.faces.faces[index].1.inbound_filter = filter;
+                true
             }
-            Err(err) => return Err(ForwarderError::FaceUnrecoverableError(origin, err)),
-        };
+            None => false,
+        }
+    }
 
-        // If we are here, we could process the full packet
-        let mut any_processed = false;
-        match tlv.typ.get() {
-            Interest::TLV_TYPE => {
-                // Handle interest
-                if let Some(interest) = Interest::try_decode_from_inner(tlv.val) {
-                    Self::handle_interest(
-                        interest,
-                        &recv_buffer[0..tlv_len],
-                        origin,
-                        &mut self.tables,
-                        &mut self.metrics,
-                        &mut self.clock,
-                        &mut self.faces.faces,
-                    );
-                    any_processed = true;
-                } else {
-                    // Otherwise ignore the malformed packet
-                    self.metrics.invalid_packet_received(origin);
+    // Interests arriving from the face without a HopLimit get this one, so that
+    //  they cannot loop forever in a mesh of forwarders, e.g. when the applications
+    //  on local faces do not set it. None (the default) leaves the interests as they are.
+    pub fn set_default_hop_limit(&mut self, token: FaceToken, hop_limit: Option<u8>) -> bool {
+        match Faces::find_face(&self.faces.faces, &token) {
+            Some(index) => {
+                self.faces.faces[index].1.default_hop_limit = hop_limit;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The interests from the face that have no route are sent back in a NoRoute Nack,
+    //  so that the consumer does not wait for them to time out. Off by default, since
+    //  the applications that do not understand the LpPackets would not expect them.
+    pub fn set_nack_on_no_route(&mut self, token: FaceToken, enabled: bool) -> bool {
+        match Faces::find_face(&self.faces.faces, &token) {
+            Some(index) => {
+                self.faces.faces[index].1.nack_no_route = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Bounds the bytes queued for the face while it cannot take them. The packets that
+    //  would go over the limit are dropped whole, so that the others still arrive intact.
+    // Returns false if the face is unknown.
+    pub fn set_outbound_queue_limit(&mut self, token: FaceToken, limit: usize) -> bool {
+        match Faces::find_face(&self.faces.faces, &token) {
+            Some(index) => {
+                self.faces.faces[index].1.outbound_limit = limit;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn outbound_queue(&self, token: FaceToken) -> Option<OutboundQueueStats> {
+        let index = Faces::find_face(&self.faces.faces, &token)?;
+        let entry = &self.faces.faces[index].1;
+        let marker = entry.marker.as_ref();
+        Some(OutboundQueueStats {
+            queued_bytes: entry.queued_len(),
+            dropped_packets: entry.outbound_dropped,
+            congested: marker.is_some_and(|marker| marker.congested),
+            marked_packets: marker.map_or(0, |marker| marker.marked_packets),
+        })
+    }
+
+    // Measures how long the packets wait in the outbound queue of the face. Once the wait
+    //  stays above the target for the interval, the face is congested: the strategies see
+    //  it in the next hops and the packets sent to it get an LP CongestionMark, first once
+    //  per interval and then more and more often while the congestion lasts, as in CoDel.
+    // None, the default, turns it off. Returns false if the face is unknown.
+    pub fn set_congestion_marking(
+        &mut self,
+        token: FaceToken,
+        marking: Option<CongestionMarking>,
+    ) -> bool {
+        let Some(index) = Faces::find_face(&self.faces.faces, &token) else {
+            return false;
+        };
+        let entry = &mut self.faces.faces[index].1;
+        if entry.marker.as_ref().is_some_and(|marker| marker.congested) {
+            self.tables.set_face_congested(token, false);
+        }
+        entry.marker = marking.map(CongestionMarker::new);
+        true
+    }
+
+    // Keeps the packets that have to wait for the face in a queue for each traffic class,
+    //  behind the packet being sent, and sends from the queues in turns weighted by the
+    //  shares of the classes, so that a bulk transfer cannot hold up the interests going
+    //  out of the same face. The packets are classed by kind unless set_traffic_class
+    //  says otherwise, and the fragments of an MTU are not scheduled.
+    // None, the default, keeps them all in the order they came.
+    // Returns false if the face is unknown.
+    pub fn set_egress_scheduling(
+        &mut self,
+        token: FaceToken,
+        scheduling: Option<EgressScheduling>,
+    ) -> bool {
+        let Some(index) = Faces::find_face(&self.faces.faces, &token) else {
+            return false;
+        };
+        let entry = &mut self.faces.faces[index].1;
+        match (scheduling, entry.scheduler.as_mut()) {
+            (Some(params), Some(scheduler)) => scheduler.params = params,
+            (Some(params), None) => entry.scheduler = Some(EgressScheduler::new(params)),
+            (None, _) => {
+                // The packets already waiting keep their place
+                while let Some(packet) = entry.scheduler.as_mut().and_then(|s| s.dequeue()) {
+                    entry.outbound.extend(&packet);
                 }
+                entry.scheduler = None;
             }
-            Data::TLV_TYPE => {
-                // Handle data
-                if let Some(data) = Data::try_decode_from_inner(tlv.val) {
-                    Self::handle_data(
-                        data,
-                        &recv_buffer[0..tlv_len],
-                        origin,
-                        &mut self.tables,
-                        &mut self.metrics,
-                        &mut self.clock,
-                        &mut self.hasher,
-                        &mut self.faces.faces,
-                    );
-                    any_processed = true;
-                } else {
-                    // Otherwise ignore the malformed packet
-                    self.metrics.invalid_packet_received(origin);
+        }
+        true
+    }
+
+    // The packets under the prefix go to the face in the class, rather than in the class
+    //  of their kind, with the longest prefix deciding. None removes the setting.
+    // Returns false if the face is unknown or has no egress scheduling.
+    pub fn set_traffic_class(
+        &mut self,
+        token: FaceToken,
+        prefix: Name<'_>,
+        class: Option<TrafficClass>,
+    ) -> bool {
+        let Some(index) = Faces::find_face(&self.faces.faces, &token) else {
+            return false;
+        };
+        let Some(scheduler) = self.faces.faces[index].1.scheduler.as_mut() else {
+            return false;
+        };
+        scheduler
+            .prefixes
+            .retain(|(other, _)| other.as_name() != prefix);
+        if let Some(class) = class {
+            scheduler.prefixes.push((NameBuf::from(prefix), class));
+        }
+        true
+    }
+
+    // The faces with bytes that wait to be sent, which are sent in the housekeeping
+    //  once the faces take them, e.g. when the sockets become writable
+    pub fn faces_with_queued_output(&self) -> impl Iterator<Item = FaceToken> + '_ {
+        self.faces
+            .faces
+            .iter()
+            .filter(|(_, entry)| entry.queued_len() > 0)
+            .map(|(token, _)| FaceToken(*token))
+    }
+
+    pub fn metrics(&self) -> &M {
+        &self.metrics
+    }
+
+    pub fn metrics_mut(&mut self) -> &mut M {
+        &mut self.metrics
+    }
+
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    // Allows changing the sink and the per-subsystem verbosity at runtime.
+    pub fn diagnostics_mut(&mut self) -> &mut Diagnostics {
+        &mut self.diagnostics
+    }
+
+    // Interest counts and cache hit rates per prefix, collected once enabled
+    pub fn prefix_stats(&self) -> &PrefixStats {
+        &self.prefix_stats
+    }
+
+    pub fn prefix_stats_mut(&mut self) -> &mut PrefixStats {
+        &mut self.prefix_stats
+    }
+
+    // The number and total size of the packets in the content store
+    pub fn content_store_usage(&self) -> ContentStoreUsage {
+        self.tables.content_store_usage()
+    }
+
+    // Removes the cached packets under the prefix, at most "limit" of them if given, e.g.
+    //  once they are known to be stale, and returns how many were removed. The empty
+    //  name flushes the whole content store. Also available as the cs/erase command.
+    pub fn erase_cached_data(&mut self, prefix: Name<'_>, limit: Option<usize>) -> usize {
+        let erased = self.tables.erase_data(prefix, limit);
+        self.diagnostics.record(
+            Subsystem::Tables,
+            Level::Info,
+            format_args!("erased {} cached packets", erased),
+        );
+        erased
+    }
+
+    pub fn tables(&self) -> &T {
+        &self.tables
+    }
+
+    // Allows configuring the tables at runtime, e.g. assigning the strategies
+    pub fn tables_mut(&mut self) -> &mut T {
+        &mut self.tables
+    }
+
+    // Calls the hook "lead_time_ms" before the freshness of cached data lapses,
+    //  if the data was retrieved from the cache at least "min_hits" times.
+    pub fn set_refresh_ahead_hook<R>(&mut self, lead_time_ms: u64, min_hits: u32, hook: R)
+    where
+        R: RefreshAheadHook + 'static,
+    {
+        self.refresh_ahead = Some(RefreshAhead {
+            lead_time_ms,
+            min_hits,
+            hook: Box::new(hook),
+            last_check: Timestamp::default(),
+        });
+    }
+
+    pub fn clear_refresh_ahead_hook(&mut self) {
+        self.refresh_ahead = None;
+    }
+
+    pub fn set_observer<O>(&mut self, observer: O)
+    where
+        O: ForwarderObserver + 'static,
+    {
+        self.hooks.observer = Some(Box::new(observer));
+    }
+
+    pub fn clear_observer(&mut self) {
+        self.hooks.observer = None;
+    }
+
+    pub fn set_packet_filter<F>(&mut self, filter: F)
+    where
+        F: PacketFilter + 'static,
+    {
+        self.hooks.packet_filter = Some(Box::new(filter));
+    }
+
+    pub fn clear_packet_filter(&mut self) {
+        self.hooks.packet_filter = None;
+    }
+
+    // The packets received are captured as they are processed, while those sent are
+    //  captured in the housekeeping that follows, face by face, with its time.
+    // The packets sent are captured before any fragmenting, as they were admitted.
+    pub fn set_packet_capture<P>(&mut self, capture: P)
+    where
+        P: PacketCapture + 'static,
+    {
+        self.hooks.capture = Some(Box::new(capture));
+        for (_, entry) in self.faces.faces.iter_mut() {
+            entry.captured_egress.get_or_insert_with(Vec::new);
+        }
+    }
+
+    pub fn clear_packet_capture(&mut self) {
+        self.hooks.capture = None;
+        for (_, entry) in self.faces.faces.iter_mut() {
+            entry.captured_egress = None;
+        }
+    }
+
+    // Makes the forwarder send the interests under the prefix upstream again, with a new
+    //  nonce, when their data did not come back after "retry_after_ms", rather than leaving
+    //  it to the consumers. Each interest is retried at most "max_retries" times and never
+    //  past its lifetime, and the consumer retransmitting it starts the count again.
+    // The longest prefix with a setting applies, None removes the setting of the prefix.
+    pub fn set_reliable_fetch(&mut self, prefix: Name<'_>, fetch: Option<ReliableFetch>) {
+        self.reliable_fetch
+            .retain(|(other, _)| other.as_name() != prefix);
+        if let Some(fetch) = fetch {
+            self.reliable_fetch.push((NameBuf::from(prefix), fetch));
+        }
+    }
+
+    // Caches the data under the prefix that the face sends without any interest asking
+    //  for it, instead of dropping it, so that a producer can push the data towards the
+    //  consumers (e.g. under /local/push) without a PIT round trip.
+    pub fn allow_unsolicited_data(&mut self, prefix: Name<'_>, from: FaceToken) {
+        let allowed = &mut self.hooks.unsolicited_allowed;
+        if !allowed
+            .iter()
+            .any(|(other, face)| *face == from && other.as_name() == prefix)
+        {
+            allowed.push((NameBuf::from(prefix), from));
+        }
+    }
+
+    // Returns false if the prefix was not allowed for the face
+    pub fn disallow_unsolicited_data(&mut self, prefix: Name<'_>, from: FaceToken) -> bool {
+        let allowed = &mut self.hooks.unsolicited_allowed;
+        let count = allowed.len();
+        allowed.retain(|(other, face)| *face != from || other.as_name() != prefix);
+        allowed.len() != count
+    }
+
+    // Answers the interests that must be fresh with the stale data from the content
+    //  store when there is no route to fetch a fresh copy, e.g. for the devices that are
+    //  only connected now and then. The stale data goes out as it was cached, since
+    //  flagging it would break the signature. Off by default.
+    pub fn set_serve_stale(&mut self, serve_stale: bool) {
+        self.serve_stale = serve_stale;
+    }
+
+    // Floods the interests that have no route on all the non-local faces, and once their
+    //  data comes back the tables learn a route for its namespace (its name without the
+    //  last component) towards the face it came from, which lapses after the lifetime
+    //  unless learned again. Meant for the ad-hoc and mesh networks that are not configured.
+    // Unlike SelfLearningStrategy it applies to all the namespaces and keeps the flooded
+    //  interests away from the applications. None, the default, turns it off.
+    pub fn set_flood_on_no_route(&mut self, route_lifetime_ms: Option<u64>) {
+        self.tables.set_flood_without_route(route_lifetime_ms);
+        self.hooks.flood_non_local_only = route_lifetime_ms.is_some();
+    }
+
+    // Passes the parameters, the key~value components of the name, to the strategy of the
+    //  names under the prefix, e.g. /probing-interval~30000 for AsfStrategy or
+    //  /suppression-initial~20/suppression-max~500 for the retransmissions. The longest
+    //  prefix with parameters applies, and None removes those of the prefix.
+    // Also the strategy-choice/set-parameters and unset-parameters commands.
+    // Returns false if the tables do not support it.
+    pub fn set_strategy_parameters(
+        &mut self,
+        prefix: Name<'_>,
+        parameters: Option<Name<'_>>,
+    ) -> bool {
+        self.tables.set_strategy_parameters(prefix, parameters)
+    }
+
+    // The source of the nonces for the interests that the forwarder originates,
+    //  also available to the applications through "next_nonce"
+    pub fn set_nonce_source<N>(&mut self, source: N)
+    where
+        N: NonceSource + 'static,
+    {
+        self.nonces = Box::new(source);
+    }
+
+    pub fn next_nonce(&mut self) -> [u8; 4] {
+        self.nonces.next_nonce()
+    }
+
+    // Checked before the prefixes are registered for the faces, both directly and through
+    //  the RIB. Every registration is allowed until a policy is set.
+    pub fn set_registration_policy<P>(&mut self, policy: P)
+    where
+        P: RegistrationPolicy + 'static,
+    {
+        self.registration_policy = Some(Box::new(policy));
+    }
+
+    pub fn clear_registration_policy(&mut self) {
+        self.registration_policy = None;
+    }
+
+    fn may_register(&mut self, name_prefix: Name<'_>, face: FaceToken) -> bool {
+        let Some(policy) = self.registration_policy.as_mut() else {
+            return true;
+        };
+        if policy.may_register(face, name_prefix) {
+            return true;
+        }
+        self.diagnostics.record(
+            Subsystem::Tables,
+            Level::Info,
+            format_args!(
+                "face {} is not allowed to register the prefix with {} components",
+                face.0,
+                name_prefix.component_count()
+            ),
+        );
+        false
+    }
+
+    // Returns false if the registration policy does not allow it
+    pub fn register_name_prefix_for_forwarding<'a>(
+        &mut self,
+        name_prefix: Name<'a>,
+        forward_to: FaceToken,
+        cost: u32,
+    ) -> bool {
+        if !self.may_register(name_prefix, forward_to) {
+            return false;
+        }
+        self.diagnostics.record(
+            Subsystem::Tables,
+            Level::Debug,
+            format_args!(
+                "registered prefix with {} components to face {} with cost {}",
+                name_prefix.component_count(),
+                forward_to.0,
+                cost
+            ),
+        );
+        self.prefix_stats.prefix_registered(name_prefix, forward_to);
+        self.tables.register_prefix(name_prefix, forward_to, cost);
+        self.forward_unrouted_interests(Some(name_prefix));
+        true
+    }
+
+    pub fn unregister_name_prefix_for_forwarding<'a>(
+        &mut self,
+        name_prefix: Name<'a>,
+        forward_to: FaceToken,
+    ) -> bool {
+        let removed = self.tables.unregister_prefix(name_prefix, forward_to);
+        if removed {
+            self.prefix_stats
+                .prefix_unregistered(name_prefix, forward_to);
+        }
+        self.diagnostics.record(
+            Subsystem::Tables,
+            Level::Debug,
+            format_args!(
+                "unregistered prefix with {} components from face {}: {}",
+                name_prefix.component_count(),
+                forward_to.0,
+                if removed { "removed" } else { "not found" }
+            ),
+        );
+        removed
+    }
+
+    // The interests that no other route matches go to the face, e.g. the upstream of an
+    //  edge node. The policy sees it as a registration of the empty prefix.
+    pub fn register_default_route(&mut self, forward_to: FaceToken, cost: u32) -> bool {
+        if !self.may_register(Name::new(), forward_to) {
+            return false;
+        }
+        self.diagnostics.record(
+            Subsystem::Tables,
+            Level::Debug,
+            format_args!(
+                "registered default route to face {} with cost {}",
+                forward_to.0, cost
+            ),
+        );
+        self.tables.register_default_route(forward_to, cost);
+        self.forward_unrouted_interests(None);
+        true
+    }
+
+    pub fn unregister_default_route(&mut self, forward_to: FaceToken) -> bool {
+        let removed = self.tables.unregister_default_route(forward_to);
+        self.diagnostics.record(
+            Subsystem::Tables,
+            Level::Debug,
+            format_args!(
+                "unregistered default route from face {}: {}",
+                forward_to.0,
+                if removed { "removed" } else { "not found" }
+            ),
+        );
+        removed
+    }
+
+    // Adds the route to the RIB, which compiles it into the FIB along with the other routes
+    //  for the prefix, unlike register_name_prefix_for_forwarding that goes to the FIB directly.
+    // With a lifetime the route is withdrawn unless it is added again before it lapses,
+    //  and its expiry is set accordingly. Returns false if the registration policy
+    //  does not allow it.
+    pub fn add_route<'a>(
+        &mut self,
+        name_prefix: Name<'a>,
+        mut route: Route,
+        lifetime_ms: Option<u64>,
+    ) -> bool {
+        if !self.may_register(name_prefix, route.face) {
+            return false;
+        }
+        route.expiry = lifetime_ms.map(|ms| self.clock.now().adding(ms));
+        self.diagnostics.record(
+            Subsystem::Tables,
+            Level::Debug,
+            format_args!(
+                "added {:?} route with {} components to face {} with cost {}",
+                route.origin,
+                name_prefix.component_count(),
+                route.face.0,
+                route.cost
+            ),
+        );
+        self.prefix_stats.prefix_registered(name_prefix, route.face);
+        self.rib.add_route(name_prefix, route, &mut self.tables);
+        self.forward_unrouted_interests(Some(name_prefix));
+        true
+    }
+
+    pub fn remove_route<'a>(
+        &mut self,
+        name_prefix: Name<'a>,
+        face: FaceToken,
+        origin: RouteOrigin,
+    ) -> bool {
+        let removed = self
+            .rib
+            .remove_route(name_prefix, face, origin, &mut self.tables);
+        self.route_removed(name_prefix, face);
+        self.diagnostics.record(
+            Subsystem::Tables,
+            Level::Debug,
+            format_args!(
+                "removed {:?} route with {} components from face {}: {}",
+                origin,
+                name_prefix.component_count(),
+                face.0,
+                if removed { "removed" } else { "not found" }
+            ),
+        );
+        removed
+    }
+
+    // The routes of the other origins may still be there
+    fn route_removed(&mut self, name_prefix: Name<'_>, face: FaceToken) {
+        if !self.rib.routes(name_prefix).iter().any(|r| r.face == face) {
+            self.prefix_stats.prefix_unregistered(name_prefix, face);
+        }
+    }
+
+    pub fn rib(&self) -> &Rib {
+        &self.rib
+    }
+
+    // A snapshot of the routes, to restore them with import_routes after a restart rather
+    //  than adding them again one by one. It has the routes of the RIB but the self-learned
+    //  ones, with the time left of those that expire, and the next hops registered in the
+    //  FIB directly, which include the routes that the tables learned by then.
+    // It is made of the RibEntry of the rib/list dataset. Being the NDN TLV the forwarder
+    //  already speaks rather than serde, it needs only alloc, so unlike the std parts it
+    //  is not behind a feature.
+    pub fn export_routes(&mut self) -> Vec<u8> {
+        let now = self.clock.now();
+        let mut snapshot = Vec::new();
+        for (prefix, routes) in self.rib.entries() {
+            let routes: Vec<RouteRecord> = routes
+                .iter()
+                .filter_map(|route| {
+                    let expiration_period = match route.expiry {
+                        Some(expiry) => Some(mgmt::ExpirationPeriod {
+                            val: expiry.difference(&now)?,
+                        }),
+                        None => None,
+                    };
+                    Some(RouteRecord {
+                        face_id: mgmt::FaceId {
+                            val: route.face.0 as u64,
+                        },
+                        origin: Some(mgmt::Origin {
+                            val: mgmt::origin_code(route.origin)?,
+                        }),
+                        cost: mgmt::Cost {
+                            val: route.cost as u64,
+                        },
+                        flags: mgmt::Flags {
+                            val: mgmt::flags_code(route.flags),
+                        },
+                        expiration_period,
+                    })
+                })
+                .collect();
+            if !routes.is_empty() {
+                let entry = RibEntry {
+                    name: prefix,
+                    routes,
+                };
+                let _ = entry.encode(&mut snapshot);
+            }
+        }
+
+        let rib = &self.rib;
+        self.tables.list_routes(&mut |info| {
+            // The next hops the RIB installed are already in the snapshot
+            let installed = if info.is_default {
+                &[]
+            } else {
+                rib.next_hops(info.name)
+            };
+            let flags = if info.is_default {
+                mgmt::Flags::DEFAULT_ROUTE
+            } else {
+                0
+            };
+            let routes: Vec<RouteRecord> = info
+                .next_hops
+                .iter()
+                .filter(|hop| !installed.iter().any(|(face, _)| *face == hop.face))
+                .map(|hop| RouteRecord {
+                    face_id: mgmt::FaceId {
+                        val: hop.face.0 as u64,
+                    },
+                    origin: None,
+                    cost: mgmt::Cost {
+                        val: hop.cost as u64,
+                    },
+                    flags: mgmt::Flags { val: flags },
+                    expiration_period: None,
+                })
+                .collect();
+            if !routes.is_empty() {
+                let entry = RibEntry {
+                    name: info.name,
+                    routes,
+                };
+                let _ = entry.encode(&mut snapshot);
+            }
+        });
+        snapshot
+    }
+
+    // Restores the routes of a snapshot from export_routes, with the faces that map_face
+    //  gives, e.g. from the tokens of the faces before the restart to those after it.
+    // The routes to the faces it gives None for are left out, and so are the ones that
+    //  the registration policy does not allow.
+    // Returns how many routes were restored, or None if the snapshot is malformed,
+    //  in which case none are.
+    pub fn import_routes(
+        &mut self,
+        snapshot: &[u8],
+        mut map_face: impl FnMut(FaceToken) -> Option<FaceToken>,
+    ) -> Option<usize> {
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while offset < snapshot.len() {
+            let (tlv, tlv_len) = TLV::try_decode(&snapshot[offset..]).ok()?;
+            if tlv.typ.get() != RibEntry::TLV_TYPE {
+                return None;
+            }
+            entries.push(RibEntry::try_decode_from_inner(tlv.val)?);
+            offset += tlv_len;
+        }
+
+        let mut restored = 0;
+        for entry in entries {
+            for record in entry.routes {
+                let Some(face) = u32::try_from(record.face_id.val)
+                    .ok()
+                    .and_then(|token| map_face(FaceToken(token)))
+                else {
+                    continue;
+                };
+                let Ok(cost) = u32::try_from(record.cost.val) else {
+                    continue;
+                };
+                let added = match record.origin {
+                    Some(origin) => {
+                        let Some(origin) = mgmt::route_origin(origin.val) else {
+                            continue;
+                        };
+                        let route = Route {
+                            face,
+                            origin,
+                            cost,
+                            flags: mgmt::route_flags(record.flags.val),
+                            expiry: None,
+                        };
+                        let lifetime_ms = record.expiration_period.map(|p| p.val);
+                        self.add_route(entry.name, route, lifetime_ms)
+                    }
+                    None if record.flags.val & mgmt::Flags::DEFAULT_ROUTE != 0 => {
+                        self.register_default_route(face, cost)
+                    }
+                    None => self.register_name_prefix_for_forwarding(entry.name, face, cost),
+                };
+                if added {
+                    restored += 1;
                 }
             }
-            _ => {
-                self.metrics.invalid_packet_received(origin);
-            } // Otherwise we ignore the packet
         }
+        Some(restored)
+    }
+
+    // Executes the NFD management commands (see the mgmt module) that the faces send
+    //  under /localhost/nfd, once the validator accepts them, instead of forwarding them.
+    pub fn enable_management<V>(&mut self, validator: V)
+    where
+        V: CommandValidator + 'static,
+    {
+        self.command_validator = Some(Box::new(validator));
+    }
+
+    pub fn disable_management(&mut self) {
+        self.command_validator = None;
+    }
+
+    // Opens the faces for faces/create, which is not supported until a factory is set
+    pub fn set_face_factory<F>(&mut self, factory: F)
+    where
+        F: FaceFactory + 'static,
+    {
+        self.face_factory = Some(Box::new(factory));
+    }
+
+    pub fn clear_face_factory(&mut self) {
+        self.face_factory = None;
+    }
+
+    fn process_command(&mut self, command: InterestBuf, origin: FaceToken) {
+        let interest = command.as_interest();
+        if self.serve_status_dataset(&interest, origin) {
+            return;
+        }
+        let response = self.execute_command(&interest, origin);
+        self.diagnostics.record(
+            Subsystem::Management,
+            Level::Info,
+            format_args!(
+                "management command from face {} answered with status {}",
+                origin.0, response.status_code.val
+            ),
+        );
+
+        let mut content = Vec::with_capacity(response.encoded_length());
+        let _ = response.encode(&mut content);
+        let packet = DataBuilder::new(interest.name)
+            .content(&content)
+            .digest_sign(&mut self.hasher);
+        if let Some(index) = Faces::find_face(&self.faces.faces, &origin) {
+            self.metrics.data_sent(origin);
+            self.faces.faces[index].1.send_whole_packet(&packet);
+        }
+    }
+
+    // Answers the interests for the status datasets, returning false for the other names.
+    // The interest without a version gets a new version, whose later segments are then
+    //  served from the stored one.
+    fn serve_status_dataset(&mut self, interest: &Interest<'_>, origin: FaceToken) -> bool {
+        let mut components = interest.name.components().skip(2);
+        let (Some(module), Some(verb)) = (components.next(), components.next()) else {
+            return false;
+        };
+        let Some(kind) = StatusDatasetKind::from_verb(module.bytes, verb.bytes) else {
+            return false;
+        };
+        match components.next() {
+            None => {
+                let content = self.encode_status_dataset(kind);
+                let now = self.clock.now().ms_since_1970;
+                // The versions must increase even if the clock does not
+                let version = self.status_datasets[kind as usize]
+                    .as_ref()
+                    .map_or(now, |dataset| now.max(dataset.version() + 1));
+                let dataset =
+                    StatusDataset::new(interest.name, version, &content, &mut self.hasher);
+                self.diagnostics.record(
+                    Subsystem::Management,
+                    Level::Debug,
+                    format_args!(
+                        "status dataset {:?} for face {} has {} segments",
+                        kind,
+                        origin.0,
+                        dataset.segment_count()
+                    ),
+                );
+                self.status_datasets[kind as usize] = Some(dataset);
+            }
+            Some(version) if version.typ.get() == NameComponent::TYPE_VERSION_NAME => {}
+            Some(_) => return false,
+        }
+
+        // An unknown version or segment is not answered
+        let segment =
+            self.status_datasets[kind as usize]
+                .as_ref()
+                .and_then(|dataset| match interest.name.component_count() {
+                    4 => dataset.segment(dataset.version_prefix()),
+                    _ => dataset.segment(interest.name),
+                });
+        if let (Some(segment), Some(index)) =
+            (segment, Faces::find_face(&self.faces.faces, &origin))
+        {
+            self.metrics.data_sent(origin);
+            self.faces.faces[index].1.send_whole_packet(segment);
+        }
+        true
+    }
+
+    fn encode_status_dataset(&mut self, kind: StatusDatasetKind) -> Vec<u8> {
+        let mut content = Vec::new();
+        match kind {
+            StatusDatasetKind::Faces => {
+                for (token, entry) in &self.faces.faces {
+                    // Only the faces created by the commands know where they lead
+                    let (uri, persistency) = match (&entry.uri, entry.persistency) {
+                        (_, Persistency::OnDemand) => (
+                            entry.uri.as_deref().unwrap_or(INTERNAL_FACE_URI),
+                            mgmt::FacePersistency::ON_DEMAND,
+                        ),
+                        (Some(uri), _) => (&uri[..], mgmt::FacePersistency::PERSISTENT),
+                        (None, _) => (INTERNAL_FACE_URI, mgmt::FacePersistency::PERMANENT),
+                    };
+                    let scope = match entry.scope {
+                        Scope::Local => mgmt::FaceScope::LOCAL,
+                        Scope::NonLocal => mgmt::FaceScope::NON_LOCAL,
+                    };
+                    let status = FaceStatus {
+                        face_id: mgmt::FaceId { val: *token as u64 },
+                        uri: mgmt::Uri { bytes: uri },
+                        local_uri: mgmt::LocalUri {
+                            bytes: INTERNAL_FACE_URI,
+                        },
+                        face_scope: mgmt::FaceScope { val: scope },
+                        face_persistency: mgmt::FacePersistency { val: persistency },
+                        link_type: mgmt::LinkType {
+                            val: mgmt::LinkType::POINT_TO_POINT,
+                        },
+                        counters: self.metrics.face_counters(FaceToken(*token)),
+                    };
+                    let _ = status.encode(&mut content);
+                }
+            }
+            StatusDatasetKind::Fib => {
+                self.tables.list_routes(&mut |info| {
+                    let entry = FibEntry {
+                        name: info.name,
+                        next_hops: info.next_hops,
+                    };
+                    let _ = entry.encode(&mut content);
+                });
+            }
+            StatusDatasetKind::ContentStore => {
+                let usage = self.tables.content_store_usage();
+                let info = CsInfo {
+                    // The content store has no fixed capacity
+                    capacity: mgmt::Capacity { val: u64::MAX },
+                    entries: mgmt::NCsEntries {
+                        val: usage.entries as u64,
+                    },
+                    hits: mgmt::NHits { val: self.cs_hits },
+                    misses: mgmt::NMisses {
+                        val: self.cs_misses,
+                    },
+                };
+                let _ = info.encode(&mut content);
+            }
+            StatusDatasetKind::General => {
+                let mut fib_entries = 0;
+                self.tables.list_routes(&mut |_| fib_entries += 1);
+                let mut pit_entries = 0;
+                self.tables.list_pit_entries(&mut |_| pit_entries += 1);
+                let mut counters = FaceCounters::default();
+                for (token, _) in &self.faces.faces {
+                    counters += self.metrics.face_counters(FaceToken(*token));
+                }
+                let status = ForwarderStatus {
+                    version: mgmt::NfdVersion {
+                        bytes: env!("CARGO_PKG_VERSION").as_bytes(),
+                    },
+                    start_timestamp: mgmt::StartTimestamp {
+                        val: self.started_at.ms_since_1970,
+                    },
+                    current_timestamp: mgmt::CurrentTimestamp {
+                        val: self.clock.now().ms_since_1970,
+                    },
+                    fib_entries: mgmt::NFibEntries { val: fib_entries },
+                    pit_entries: mgmt::NPitEntries { val: pit_entries },
+                    cs_entries: mgmt::NCsEntries {
+                        val: self.tables.content_store_usage().entries as u64,
+                    },
+                    counters,
+                };
+                let _ = status.encode(&mut content);
+            }
+        }
+        content
+    }
+
+    fn execute_command<'a>(
+        &mut self,
+        interest: &Interest<'a>,
+        origin: FaceToken,
+    ) -> ControlResponse<'a> {
+        let Some(command) = ControlCommand::parse(interest.name) else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Malformed command");
+        };
+        let authorized = self
+            .command_validator
+            .as_mut()
+            .is_some_and(|validator| validator.validate(interest));
+        if !authorized {
+            return ControlResponse::new(StatusCode::UNAUTHORIZED, "Not authorized");
+        }
+        match (command.module, command.verb) {
+            (b"rib", b"register") => self.register_command(command.parameters, origin),
+            (b"rib", b"unregister") => self.unregister_command(command.parameters, origin),
+            (b"faces", b"create") => self.create_face_command(command.parameters),
+            (b"faces", b"destroy") => self.destroy_face_command(command.parameters),
+            (b"cs", b"erase") => self.erase_command(command.parameters),
+            (b"strategy-choice", b"set-parameters") => {
+                self.set_strategy_parameters_command(command.parameters)
+            }
+            (b"strategy-choice", b"unset-parameters") => {
+                self.unset_strategy_parameters_command(command.parameters)
+            }
+            _ => ControlResponse::new(StatusCode::NOT_IMPLEMENTED, "Unsupported command"),
+        }
+    }
+
+    fn register_command<'a>(
+        &mut self,
+        mut parameters: ControlParameters<'a>,
+        origin: FaceToken,
+    ) -> ControlResponse<'a> {
+        let Some(name) = parameters.name else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Missing the name");
+        };
+        let Some(face) = self.command_face(&parameters, origin) else {
+            return ControlResponse::new(StatusCode::FACE_NOT_FOUND, "Face not found");
+        };
+        let origin_code = parameters.origin.map_or(mgmt::Origin::APP, |o| o.val);
+        let Some(route_origin) = mgmt::route_origin(origin_code) else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Unsupported origin");
+        };
+        let Ok(cost) = u32::try_from(parameters.cost.map_or(0, |c| c.val)) else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Cost out of range");
+        };
+        let flags = parameters
+            .flags
+            .map_or(mgmt::Flags::CHILD_INHERIT, |f| f.val);
+
+        let route = Route {
+            face,
+            origin: route_origin,
+            cost,
+            flags: mgmt::route_flags(flags),
+            expiry: None,
+        };
+        let lifetime_ms = parameters.expiration_period.map(|p| p.val);
+        if !self.add_route(name, route, lifetime_ms) {
+            return ControlResponse::new(StatusCode::UNAUTHORIZED, "Registration not allowed");
+        }
+
+        // The response has all the parameters, including the defaults
+        parameters.face_id = Some(mgmt::FaceId { val: face.0 as u64 });
+        parameters.origin = Some(mgmt::Origin { val: origin_code });
+        parameters.cost = Some(mgmt::Cost { val: cost as u64 });
+        parameters.flags = Some(mgmt::Flags { val: flags });
+        ControlResponse::ok(parameters)
+    }
+
+    fn unregister_command<'a>(
+        &mut self,
+        parameters: ControlParameters<'a>,
+        origin: FaceToken,
+    ) -> ControlResponse<'a> {
+        let Some(name) = parameters.name else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Missing the name");
+        };
+        let Some(face) = self.command_face(&parameters, origin) else {
+            return ControlResponse::new(StatusCode::FACE_NOT_FOUND, "Face not found");
+        };
+        let origin_code = parameters.origin.map_or(mgmt::Origin::APP, |o| o.val);
+        let Some(route_origin) = mgmt::route_origin(origin_code) else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Unsupported origin");
+        };
+
+        // Removing a route that does not exist succeeds, as in NFD
+        self.remove_route(name, face, route_origin);
+        ControlResponse::ok(ControlParameters {
+            name: Some(name),
+            face_id: Some(mgmt::FaceId { val: face.0 as u64 }),
+            origin: Some(mgmt::Origin { val: origin_code }),
+            ..ControlParameters::default()
+        })
+    }
+
+    fn create_face_command<'a>(
+        &mut self,
+        parameters: ControlParameters<'a>,
+    ) -> ControlResponse<'a> {
+        let Some(uri) = parameters.uri else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Missing the URI");
+        };
+        let Ok(uri_str) = core::str::from_utf8(uri.bytes) else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Malformed URI");
+        };
+        let Some(factory) = self.face_factory.as_mut() else {
+            return ControlResponse::new(StatusCode::NOT_IMPLEMENTED, "Unsupported command");
+        };
+        let Some(token) = self.faces.next_face_token().map(FaceToken) else {
+            return ControlResponse::new(StatusCode::FACE_CREATION_FAILED, "Out of faces");
+        };
+        let created = match factory.create_face(token, uri_str) {
+            Ok((sender, receiver)) => self.add_boxed_face(sender, receiver),
+            Err(FaceCreationError::UnsupportedUri) => {
+                return ControlResponse::new(StatusCode::NOT_ACCEPTABLE, "Unsupported URI")
+            }
+            Err(FaceCreationError::Failed) => None,
+        };
+        let Some(face) = created else {
+            return ControlResponse::new(StatusCode::FACE_CREATION_FAILED, "Face creation failed");
+        };
+        if let Some(index) = Faces::find_face(&self.faces.faces, &face) {
+            let entry = &mut self.faces.faces[index].1;
+            entry.uri = Some(uri.bytes.to_vec());
+            // The commands create the faces to the other hosts
+            entry.scope = Scope::NonLocal;
+        }
+        ControlResponse::ok(ControlParameters {
+            face_id: Some(mgmt::FaceId { val: face.0 as u64 }),
+            uri: Some(uri),
+            ..ControlParameters::default()
+        })
+    }
+
+    fn erase_command<'a>(&mut self, parameters: ControlParameters<'a>) -> ControlResponse<'a> {
+        let Some(name) = parameters.name else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Missing the name");
+        };
+        let limit = match parameters.count {
+            Some(count) if count.val == 0 => {
+                return ControlResponse::new(StatusCode::BAD_REQUEST, "Count must be positive")
+            }
+            Some(count) => Some(usize::try_from(count.val).unwrap_or(usize::MAX)),
+            None => None,
+        };
+        let erased = self.erase_cached_data(name, limit);
+        ControlResponse::ok(ControlParameters {
+            name: Some(name),
+            count: Some(mgmt::Count { val: erased as u64 }),
+            ..ControlParameters::default()
+        })
+    }
+
+    fn set_strategy_parameters_command<'a>(
+        &mut self,
+        parameters: ControlParameters<'a>,
+    ) -> ControlResponse<'a> {
+        let Some(name) = parameters.name else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Missing the name");
+        };
+        let Some(strategy) = parameters.strategy else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Missing the parameters");
+        };
+        if !self.set_strategy_parameters(name, Some(strategy.name)) {
+            return ControlResponse::new(StatusCode::NOT_IMPLEMENTED, "Unsupported by the tables");
+        }
+        ControlResponse::ok(ControlParameters {
+            name: Some(name),
+            strategy: Some(strategy),
+            ..ControlParameters::default()
+        })
+    }
+
+    fn unset_strategy_parameters_command<'a>(
+        &mut self,
+        parameters: ControlParameters<'a>,
+    ) -> ControlResponse<'a> {
+        let Some(name) = parameters.name else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Missing the name");
+        };
+        if !self.set_strategy_parameters(name, None) {
+            return ControlResponse::new(StatusCode::NOT_IMPLEMENTED, "Unsupported by the tables");
+        }
+        ControlResponse::ok(ControlParameters {
+            name: Some(name),
+            ..ControlParameters::default()
+        })
+    }
+
+    fn destroy_face_command<'a>(
+        &mut self,
+        parameters: ControlParameters<'a>,
+    ) -> ControlResponse<'a> {
+        let Some(face_id) = parameters.face_id else {
+            return ControlResponse::new(StatusCode::BAD_REQUEST, "Missing the FaceId");
+        };
+        // Destroying a face that does not exist succeeds, as in NFD
+        if let Ok(token) = u32::try_from(face_id.val) {
+            if self.has_face(FaceToken(token)) && !self.destroyed_faces.contains(&FaceToken(token))
+            {
+                self.destroyed_faces.push(FaceToken(token));
+            }
+        }
+        ControlResponse::ok(ControlParameters {
+            face_id: Some(face_id),
+            ..ControlParameters::default()
+        })
+    }
+
+    // The face a command refers to, where 0 or no FaceId means the face it came from
+    fn command_face(
+        &self,
+        parameters: &ControlParameters<'_>,
+        origin: FaceToken,
+    ) -> Option<FaceToken> {
+        match parameters.face_id.map_or(0, |id| id.val) {
+            0 => Some(origin),
+            id => u32::try_from(id)
+                .ok()
+                .map(FaceToken)
+                .filter(|face| self.has_face(*face)),
+        }
+    }
+
+    // Meant to be called once the faces and routes are configured, but before serving traffic.
+    // Sends a probe interest (with a nonce from the nonce source) for each of the "probe_names",
+    //  which primes the routes and the content store, and then checks that every face is
+    //  still connected.
+    // Repeating a probe while the previous one is still pending will be suppressed by the
+    //  PIT and the route will be reported as unreachable, so each probe name should be fresh.
+    pub fn warm_up<'a>(&mut self, probe_names: &[Name<'a>]) -> ReadinessReport {
+        let now = self.clock.now();
+        let mut routes = Vec::with_capacity(probe_names.len());
+        let mut packet = Vec::new();
+
+        for &name in probe_names {
+            let nonce = self.nonces.next_nonce();
+            let mut interest = Interest::new(name, true, nonce);
+            interest.must_be_fresh = Some(MustBeFresh {});
+            packet.clear();
+            let _ = interest.encode(&mut packet);
+
+            let mut sent_to = Vec::new();
+            for next_hop in self
+                .tables
+                .register_interest(&interest, nonce, WARM_UP_FACE, now)
+            {
+                if let Some(index) = Faces::find_face(&self.faces.faces, &next_hop) {
+                    let entry = &mut self.faces.faces[index].1;
+                    if entry.should_close
+                        || !self.hooks.allows(
+                            Direction::Egress,
+                            next_hop,
+                            PacketKind::Interest,
+                            name,
+                        )
+                    {
+                        continue;
+                    }
+                    entry.send_whole_packet(&packet);
+                    // The probes do not come from a face that could be paused
+                    entry.congested = false;
+                    if !entry.should_close {
+                        self.metrics.interest_sent(next_hop);
+                        sent_to.push(next_hop);
+                    }
+                }
+            }
+
+            self.diagnostics.record(
+                Subsystem::Strategy,
+                Level::Info,
+                format_args!(
+                    "warm-up probe with {} components sent to {} faces",
+                    name.component_count(),
+                    sent_to.len()
+                ),
+            );
+            routes.push(RouteProbe {
+                name: NameBuf::from(name),
+                sent_to,
+            });
+        }
+
+        // An empty send detects the faces that already know they are disconnected
+        //  without putting anything on the wire.
+        let mut faces = Vec::with_capacity(self.faces.len());
+        for (token, entry) in self.faces.faces.iter_mut() {
+            if !entry.should_close && entry.sender.try_send(&[]).is_err() {
+                entry.should_close = true;
+            }
+            if entry.should_close {
+                self.diagnostics.record(
+                    Subsystem::Faces,
+                    Level::Warn,
+                    format_args!("face {} is not connected after warm-up", token),
+                );
+            }
+            faces.push(FaceProbe {
+                face: FaceToken(*token),
+                is_connected: !entry.should_close,
+            });
+        }
+
+        ReadinessReport { faces, routes }
+    }
+
+    pub fn try_forward_from_face(&mut self, face: FaceToken) -> Result<(), ForwarderError> {
+        let ret = if let Some(index) = Faces::find_face(&self.faces.faces, &face) {
+            match self.try_recv_from_face_at_index(index) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(ForwarderError::NothingToForward),
+                Err(err) => Err(err),
+            }
+        } else {
+            Err(ForwarderError::FaceNotfound)
+        };
+        self.housekeeping();
+        ret
+    }
+
+    pub fn try_forward_from_any_face(
+        &mut self,
+        excluded: &[FaceToken],
+    ) -> Result<FaceToken, ForwarderError> {
+        let mut ret = Err(ForwarderError::NothingToForward);
+        for _ in 0..self.faces.len() {
+            self.last_checked_face = (self.last_checked_face + 1) % self.faces.len();
+            if excluded.contains(&FaceToken(self.faces.faces[self.last_checked_face].0)) {
+                continue;
+            }
+            match self.try_recv_from_face_at_index(self.last_checked_face) {
+                Ok(true) => {
+                    ret = Ok(FaceToken(self.faces.faces[self.last_checked_face].0));
+                    break;
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    ret = Err(err);
+                    break;
+                }
+            }
+        }
+        self.housekeeping();
+        ret
+    }
+
+    // Receives at most one packet from every face (except the excluded ones) in a single pass.
+    // Identical interests arriving from several faces within the pass are coalesced:
+    //  only the first one goes through the FIB and the strategy, while the rest are
+    //  only added to the in-records of its PIT entry.
+    // Returns the number of processed packets.
+    pub fn try_forward_batch(&mut self, excluded: &[FaceToken]) -> Result<usize, ForwarderError> {
+        self.batch.begin();
+        let mut ret = Ok(0);
+        for index in 0..self.faces.len() {
+            if excluded.contains(&FaceToken(self.faces.faces[index].0)) {
+                continue;
+            }
+            match self.try_recv_from_face_at_index(index) {
+                Ok(true) => ret = ret.map(|processed| processed + 1),
+                Ok(false) => {}
+                Err(err) => {
+                    ret = Err(err);
+                    break;
+                }
+            }
+        }
+        self.batch.end();
+        self.housekeeping();
+        match ret {
+            Ok(0) => Err(ForwarderError::NothingToForward),
+            ret => ret,
+        }
+    }
+
+    // Keeps going around the faces (except the excluded ones) until "max_packets" are
+    //  processed or none of them has anything left, so the housekeeping is only done once
+    //  for the whole lot. The identical interests are coalesced as in try_forward_batch.
+    // Returns the number of processed packets.
+    pub fn try_forward_budget(
+        &mut self,
+        max_packets: usize,
+        excluded: &[FaceToken],
+    ) -> Result<usize, ForwarderError> {
+        self.batch.begin();
+        let mut processed = 0;
+        let mut ret = Ok(());
+        'passes: while processed < max_packets {
+            let mut any_processed = false;
+            for index in 0..self.faces.len() {
+                if processed == max_packets {
+                    break 'passes;
+                }
+                if excluded.contains(&FaceToken(self.faces.faces[index].0)) {
+                    continue;
+                }
+                match self.try_recv_from_face_at_index(index) {
+                    Ok(true) => {
+                        processed += 1;
+                        any_processed = true;
+                    }
+                    Ok(false) => {}
+                    Err(err) => {
+                        ret = Err(err);
+                        break 'passes;
+                    }
+                }
+            }
+            if !any_processed {
+                break;
+            }
+        }
+        self.batch.end();
+        self.housekeeping();
+        match ret {
+            Err(err) => Err(err),
+            Ok(()) if processed == 0 => Err(ForwarderError::NothingToForward),
+            Ok(()) => Ok(processed),
+        }
+    }
+
+    fn housekeeping(&mut self) {
+        let now = self.clock.now();
+        let mut idle_faces = Vec::new();
+        for (token, entry) in self.faces.faces.iter_mut() {
+            entry.drain_outbound();
+            let queued = entry.queued_len();
+            if let Some(marker) = entry.marker.as_mut() {
+                let was_congested = marker.congested;
+                let congested = marker.update(queued, now);
+                if congested != was_congested {
+                    self.tables.set_face_congested(FaceToken(*token), congested);
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Debug,
+                        format_args!("face {} congested: {}", token, congested),
+                    );
+                }
+            }
+            if entry.idle_timeout_ms.is_some() && entry.check_idle(now) {
+                idle_faces.push(FaceToken(*token));
+            }
+            // Those only sent to are never read, which is where the failures are reported
+            if self.reap_dead_faces
+                && entry.should_close
+                && !self.dead_faces.iter().any(|(dead, _)| dead.0 == *token)
+            {
+                self.hooks.observe(ForwarderEvent::FaceClosed {
+                    face: FaceToken(*token),
+                    reason: FaceCloseReason::Failed,
+                });
+                self.dead_faces
+                    .push((FaceToken(*token), FaceCloseReason::Failed));
+            }
+            if let Some(captured) = entry.captured_egress.as_mut() {
+                for packet in captured.drain(..) {
+                    self.hooks
+                        .capture(FaceToken(*token), Direction::Egress, now, &packet);
+                }
+            }
+            if entry.unreported_sent_bytes > 0 {
+                self.metrics
+                    .bytes_sent(FaceToken(*token), entry.unreported_sent_bytes);
+                entry.unreported_sent_bytes = 0;
+            }
+        }
+        if self
+            .pending_retries
+            .iter()
+            .any(|retry| retry.retry_at <= now)
+        {
+            self.retry_interests(now);
+        }
+        for token in idle_faces {
+            if self.close_face(token, FaceCloseReason::Idle) {
+                self.reaped_faces.push(token);
+            }
+        }
+        for (token, reason) in core::mem::take(&mut self.dead_faces) {
+            if self.drop_face(token, reason) {
+                self.reaped_faces.push(token);
+            }
+        }
+
+        for token in core::mem::take(&mut self.destroyed_faces) {
+            if self.remove_face(token) {
+                if let Some(factory) = self.face_factory.as_mut() {
+                    factory.face_destroyed(token);
+                }
+            }
+        }
+
+        self.tables.prune_if_needed(now);
+        let (metrics, diagnostics) = (&mut self.metrics, &mut self.diagnostics);
+        self.tables.report_timed_out_interests(&mut |name, faces| {
+            // Nobody waits for the data of the warm-up probes
+            for face in faces.iter().filter(|face| **face != WARM_UP_FACE) {
+                metrics.interest_timed_out(*face);
+            }
+            diagnostics.record(
+                Subsystem::Tables,
+                Level::Debug,
+                format_args!(
+                    "interest with {} components timed out for {} faces",
+                    name.component_count(),
+                    faces.len()
+                ),
+            );
+        });
+        let mut expired = Vec::new();
+        self.rib
+            .prune(now, &mut self.tables, &mut |name_prefix, route| {
+                expired.push((NameBuf::from(name_prefix), route.face));
+                metrics.route_expired(route.face);
+                diagnostics.record(
+                    Subsystem::Tables,
+                    Level::Info,
+                    format_args!(
+                        "route with {} components to face {} expired",
+                        name_prefix.component_count(),
+                        route.face.0
+                    ),
+                );
+            });
+        for (name_prefix, face) in expired {
+            self.route_removed(name_prefix.as_name(), face);
+        }
+
+        if let Some(refresh_ahead) = self.refresh_ahead.as_mut() {
+            // Going through the whole cache is costly, so it is only done a few times
+            //  per lead time
+            if now
+                < refresh_ahead
+                    .last_check
+                    .adding(refresh_ahead.lead_time_ms / 4)
+            {
+                return;
+            }
+            refresh_ahead.last_check = now;
+            let hook = &mut refresh_ahead.hook;
+            self.tables.report_expiring_data(
+                now,
+                refresh_ahead.lead_time_ms,
+                refresh_ahead.min_hits,
+                &mut |name| hook.data_expiring(name),
+            );
+        }
+    }
+
+    // Sends the interests that are due again through the tables, as retransmissions
+    //  from the face they came from
+    fn retry_interests(&mut self, now: Timestamp) {
+        let mut retries = core::mem::take(&mut self.pending_retries);
+        retries.retain_mut(|retry| {
+            if retry.retry_at > now {
+                return true;
+            }
+            if retry.retries_left == 0
+                || now >= retry.expires_at
+                || Faces::find_face(&self.faces.faces, &retry.origin).is_none()
+            {
+                return false;
+            }
+            let nonces = &mut self.nonces;
+            let remaining_ms = retry.expires_at.difference(&now).unwrap_or(0);
+            retry.interest = retry
+                .interest
+                .reexpressed(|| nonces.next_nonce(), Some(remaining_ms));
+            let mut packet = Vec::with_capacity(retry.interest.encoded_length());
+            let _ = retry.interest.encode(&mut packet);
+            let outcome = Self::handle_interest(
+                retry.interest.as_interest(),
+                &packet,
+                retry.origin,
+                PacketContext {
+                    tables: &mut self.tables,
+                    metrics: &mut self.metrics,
+                    hooks: &mut self.hooks,
+                    clock: &mut self.clock,
+                    faces: &mut self.faces.faces,
+                },
+            );
+            retry.retries_left -= 1;
+            retry.retry_at = now.adding(retry.retry_after_ms);
+            // Still pending even if the strategy held it back this time, unlike when it
+            //  was answered from the cache or could not go anywhere
+            let InterestOutcome::Forwarded(sent_to) = outcome else {
+                return false;
+            };
+            self.diagnostics.record(
+                Subsystem::Strategy,
+                Level::Debug,
+                format_args!(
+                    "interest from face {} retried on {} faces",
+                    retry.origin.0, sent_to
+                ),
+            );
+            true
+        });
+        self.pending_retries = retries;
+    }
+
+    // Sends the interests that had no route and are under the prefix through the tables
+    //  again, as retransmissions from the face they came from. None is for all of them,
+    //  e.g. after a default route was registered.
+    fn forward_unrouted_interests(&mut self, prefix: Option<Name<'_>>) {
+        if self.unrouted_interests.is_empty() {
+            return;
+        }
+        let now = self.clock.now();
+        let mut unrouted = core::mem::take(&mut self.unrouted_interests);
+        unrouted.retain_mut(|unrouted| {
+            let name = unrouted.interest.as_interest().name;
+            if now >= unrouted.expires_at {
+                return false;
+            }
+            if prefix.is_some_and(|prefix| !prefix.is_prefix_of(name)) {
+                return true;
+            }
+            let nonces = &mut self.nonces;
+            let remaining_ms = unrouted.expires_at.difference(&now).unwrap_or(0);
+            let interest = unrouted
+                .interest
+                .reexpressed(|| nonces.next_nonce(), Some(remaining_ms));
+            let mut packet = Vec::with_capacity(interest.encoded_length());
+            let _ = interest.encode(&mut packet);
+            let outcome = Self::handle_interest(
+                interest.as_interest(),
+                &packet,
+                unrouted.origin,
+                PacketContext {
+                    tables: &mut self.tables,
+                    metrics: &mut self.metrics,
+                    hooks: &mut self.hooks,
+                    clock: &mut self.clock,
+                    faces: &mut self.faces.faces,
+                },
+            );
+            if let InterestOutcome::Forwarded(sent_to) = outcome {
+                self.diagnostics.record(
+                    Subsystem::Strategy,
+                    Level::Debug,
+                    format_args!(
+                        "unrouted interest from face {} forwarded to {} faces",
+                        unrouted.origin.0, sent_to
+                    ),
+                );
+            }
+            false
+        });
+        self.unrouted_interests = unrouted;
+    }
+
+    fn try_recv_from_face_at_index(&mut self, index: usize) -> Result<bool, ForwarderError> {
+        let token = FaceToken(self.faces.faces[index].0);
+        let result = self.recv_from_face_at_index(index);
+        // The face may be gone after an error
+        if let Some(index) = Faces::find_face(&self.faces.faces, &token) {
+            self.faces.release_recv_buffer(index);
+        }
+        result
+    }
+
+    fn recv_from_face_at_index(&mut self, index: usize) -> Result<bool, ForwarderError> {
+        let (token, entry) = &mut self.faces.faces[index];
+        let origin = FaceToken(*token);
+
+        if entry.should_close {
+            self.diagnostics.record(
+                Subsystem::Faces,
+                Level::Warn,
+                format_args!("face {} failed while sending", origin.0),
+            );
+            self.face_closed(origin, FaceCloseReason::Failed);
+            return Err(ForwarderError::FaceDisconnected(origin));
+        }
+
+        if !entry.waiting_for.is_empty() && self.faces.still_paused(index) {
+            return Ok(false);
+        }
+        let entry = &mut self.faces.faces[index].1;
+
+        let RecvBuffer {
+            bytes,
+            cursor: recv_buffer_cursor,
+        } = &mut self.faces.recv_buffers[index];
+        let spare_recv_buffers = &mut self.faces.spare_recv_buffers;
+        let recv_buffer = &mut bytes.get_or_insert_with(|| {
+            spare_recv_buffers
+                .pop()
+                .unwrap_or_else(|| vec![0u8; MAX_PACKET_SIZE].into_boxed_slice())
+        })[..];
+
+        let mut should_try_recv = true;
+
+        // First, it could be possible that we already have a ready packet in buffer from last recv
+        // If so we will not try to receive new data
+        if *recv_buffer_cursor > 0 {
+            match TLV::try_decode(&recv_buffer[0..*recv_buffer_cursor]) {
+                Ok(_) => should_try_recv = false,
+                // If we have too few bytes this could be solved with a recv
+                Err(TlvDecodingError::CannotDecodeType {
+                    err: VarintDecodingError::BufferTooShort,
+                }) => {}
+                Err(TlvDecodingError::CannotDecodeLength {
+                    err: VarintDecodingError::BufferTooShort,
+                    ..
+                }) => {}
+                Err(TlvDecodingError::CannotDecodeValue { .. }) => {}
+                Err(err) => {
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Error,
+                        format_args!("face {} sent undecodable bytes: {:?}", origin.0, err),
+                    );
+                    self.face_closed(origin, FaceCloseReason::Undecodable);
+                    return Err(ForwarderError::FaceUnrecoverableError(origin, err));
+                }
+            }
+        }
+
+        if should_try_recv {
+            match entry.try_recv(recv_buffer, recv_buffer_cursor) {
+                Ok(bytes_received) => {
+                    if bytes_received == 0 {
+                        return Ok(false); // Nothing was received, the face is not ready
+                    }
+                    entry.received_since_check = true;
+                    // The rest of an oversized packet is thrown away as it arrives
+                    if entry.skip_remaining > 0 {
+                        let skipped = entry.skip_remaining.min(*recv_buffer_cursor);
+                        recv_buffer.copy_within(skipped..*recv_buffer_cursor, 0);
+                        *recv_buffer_cursor -= skipped;
+                        entry.skip_remaining -= skipped;
+                        if *recv_buffer_cursor == 0 {
+                            return Ok(false);
+                        }
+                    }
+                }
+                Err(FaceError::Disconnected) => {
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Warn,
+                        format_args!("face {} disconnected", origin.0),
+                    );
+                    self.face_closed(origin, FaceCloseReason::Disconnected);
+                    return Err(ForwarderError::FaceDisconnected(origin));
+                }
+                Err(err) if err.is_fatal() => {
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Error,
+                        format_args!("face {} failed: {:?}", origin.0, err),
+                    );
+                    self.face_closed(origin, FaceCloseReason::Failed);
+                    return Err(ForwarderError::FaceDisconnected(origin));
+                }
+                Err(err) => {
+                    // Transient errors do not affect the face, we will try again later
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Debug,
+                        format_args!("face {} could not receive: {:?}", origin.0, err),
+                    );
+                    return Ok(false);
+                }
+            }
+        }
+
+        let (tlv, tlv_len) = match TLV::try_decode(&recv_buffer[0..*recv_buffer_cursor]) {
+            Ok((tlv, tlv_len)) => (tlv, tlv_len),
+            // If we have too few bytes this could be solved with a recv
+            Err(TlvDecodingError::CannotDecodeType {
+                err: VarintDecodingError::BufferTooShort,
+            }) => return Ok(false),
+            Err(TlvDecodingError::CannotDecodeLength {
+                err: VarintDecodingError::BufferTooShort,
+                ..
+            }) => return Ok(false),
+            Err(TlvDecodingError::CannotDecodeValue { typ, len }) => {
+                let header_len =
+                    Varint(typ.get() as u64).encoded_length() + Varint(len as u64).encoded_length();
+                let tlv_len = header_len.saturating_add(len);
+                if tlv_len > MAX_PACKET_SIZE {
+                    // Would never fit in the buffer, so it is discarded, including the bytes
+                    //  that are still to come, and the face resumes with the next packet
+                    let skipped = tlv_len.min(*recv_buffer_cursor);
+                    recv_buffer.copy_within(skipped..*recv_buffer_cursor, 0);
+                    *recv_buffer_cursor -= skipped;
+                    entry.skip_remaining = tlv_len - skipped;
+                    self.metrics.oversized_packet_skipped(origin, tlv_len);
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Warn,
+                        format_args!(
+                            "face {} sent a packet of {} bytes, which was skipped",
+                            origin.0, tlv_len
+                        ),
+                    );
+                }
+                return Ok(false);
+            }
+            Err(err) => {
+                self.diagnostics.record(
+                    Subsystem::Faces,
+                    Level::Error,
+                    format_args!("face {} sent undecodable bytes: {:?}", origin.0, err),
+                );
+                self.face_closed(origin, FaceCloseReason::Undecodable);
+                return Err(ForwarderError::FaceUnrecoverableError(origin, err));
+            }
+        };
+
+        // The packets that come whole in an LpPacket, e.g. for a CongestionMark, are
+        //  processed as if they came alone. The fragment is the last in the LpPacket.
+        let (mut tlv, mut tlv_len) = (tlv, tlv_len);
+        while tlv.typ.get() == LpPacket::TLV_TYPE {
+            let Some(inner_len) = Self::wrapped_packet_length(tlv.val) else {
+                break;
+            };
+            let header_len = tlv_len - inner_len;
+            recv_buffer.copy_within(header_len..*recv_buffer_cursor, 0);
+            *recv_buffer_cursor -= header_len;
+            // The fragment was decoded whole otherwise
+            (tlv, tlv_len) = TLV::try_decode(&recv_buffer[0..*recv_buffer_cursor]).unwrap();
+        }
+
+        // If we are here, we could process the full packet
+        let mut any_processed = false;
+        let mut command = None;
+        if self.hooks.capture.is_some() {
+            let now = self.clock.now();
+            let packet = &recv_buffer[..tlv_len];
+            self.hooks.capture(origin, Direction::Ingress, now, packet);
+        }
+        self.metrics.bytes_received(origin, tlv_len);
+        match tlv.typ.get() {
+            Interest::TLV_TYPE => self.metrics.interest_received(origin),
+            Data::TLV_TYPE => self.metrics.data_received(origin),
+            _ => {}
+        }
+        match tlv.typ.get() {
+            Interest::TLV_TYPE if !entry.inbound_filter.accepts_interests() => {
+                // The face is not allowed to send us interests
+                self.metrics.interest_dropped(origin);
+                self.diagnostics.record(
+                    Subsystem::Faces,
+                    Level::Debug,
+                    format_args!("filtered out interest from face {}", origin.0),
+                );
+            }
+            Data::TLV_TYPE if !entry.inbound_filter.accepts_data() => {
+                // The face is not allowed to send us data
+                self.metrics.data_dropped(origin);
+                self.diagnostics.record(
+                    Subsystem::Faces,
+                    Level::Debug,
+                    format_args!("filtered out data from face {}", origin.0),
+                );
+            }
+            Interest::TLV_TYPE => {
+                // Handle interest
+                let default_hop_limit = entry.default_hop_limit;
+                let decoded = Interest::try_decode_from_inner(tlv.val);
+                if decoded.as_ref().is_some_and(|interest| {
+                    entry.scope == Scope::NonLocal
+                        && is_under(interest.name, mgmt::LOCALHOST_COMPONENT)
+                }) {
+                    // Which also keeps the management commands away from the other hosts
+                    self.metrics.interest_dropped(origin);
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Debug,
+                        format_args!(
+                            "dropped /localhost interest from non-local face {}",
+                            origin.0
+                        ),
+                    );
+                    any_processed = true;
+                } else if decoded.as_ref().is_some_and(|interest| {
+                    !self.hooks.allows(
+                        Direction::Ingress,
+                        origin,
+                        PacketKind::Interest,
+                        interest.name,
+                    )
+                }) {
+                    self.metrics.interest_dropped(origin);
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Debug,
+                        format_args!("packet filter dropped interest from face {}", origin.0),
+                    );
+                    any_processed = true;
+                } else if let Some(interest) = decoded.as_ref().filter(|interest| {
+                    self.command_validator.is_some() && mgmt::is_management_name(interest.name)
+                }) {
+                    // The commands change the tables and the faces, so they are executed
+                    //  once the receive buffer is no longer needed
+                    command = Some(InterestBuf::from(interest));
+                    any_processed = true;
+                } else if let Some(mut interest) = decoded {
+                    let mut packet = &recv_buffer[0..tlv_len];
+                    if let (None, Some(val)) = (interest.hop_limit, default_hop_limit) {
+                        // Adding the HopLimit changes the length, so the packet is re-encoded
+                        interest.hop_limit = Some(HopLimit { val });
+                        self.reencoded_packet.clear();
+                        let _ = interest.encode(&mut self.reencoded_packet);
+                        packet = &self.reencoded_packet;
+                    }
+                    let name = interest.name;
+                    let can_be_prefix = interest.can_be_prefix.is_some();
+                    let must_be_fresh = interest.must_be_fresh.is_some();
+                    let reliable_fetch = find_reliable_fetch(&self.reliable_fetch, name)
+                        .map(|fetch| (fetch, InterestBuf::from(&interest)));
+                    if self.batch.check_and_insert(&interest)
+                        && Self::coalesce_interest(
+                            &interest,
+                            origin,
+                            &mut self.tables,
+                            &mut self.metrics,
+                            &mut self.clock,
+                        )
+                    {
+                        self.prefix_stats.record_interest(name, false);
+                        self.diagnostics.record(
+                            Subsystem::Strategy,
+                            Level::Trace,
+                            format_args!("interest from face {} coalesced", origin.0),
+                        );
+                        self.hooks
+                            .observe(ForwarderEvent::InterestAggregated { face: origin, name });
+                    } else {
+                        let mut outcome = Self::handle_interest(
+                            interest,
+                            packet,
+                            origin,
+                            PacketContext {
+                                tables: &mut self.tables,
+                                metrics: &mut self.metrics,
+                                hooks: &mut self.hooks,
+                                clock: &mut self.clock,
+                                faces: &mut self.faces.faces,
+                            },
+                        );
+                        if let InterestOutcome::Forwarded(0) = outcome {
+                            // Without MustBeFresh the stale data would have satisfied it already
+                            if self.serve_stale
+                                && must_be_fresh
+                                && !self.tables.has_route(name)
+                                && Self::serve_stale_data(
+                                    name,
+                                    can_be_prefix,
+                                    origin,
+                                    PacketContext {
+                                        tables: &mut self.tables,
+                                        metrics: &mut self.metrics,
+                                        hooks: &mut self.hooks,
+                                        clock: &mut self.clock,
+                                        faces: &mut self.faces.faces,
+                                    },
+                                )
+                            {
+                                outcome = InterestOutcome::ServedStale;
+                            }
+                        }
+                        if let InterestOutcome::Forwarded(0) = outcome {
+                            if let Some(index) = Faces::find_face(&self.faces.faces, &origin) {
+                                let face = &mut self.faces.faces[index].1;
+                                if face.nack_no_route
+                                    && !self.tables.has_route(name)
+                                    && self.hooks.allows(
+                                        Direction::Egress,
+                                        origin,
+                                        PacketKind::Nack,
+                                        name,
+                                    )
+                                {
+                                    self.metrics.nack_sent(origin);
+                                    self.hooks.observe(ForwarderEvent::NackSent {
+                                        to: origin,
+                                        name,
+                                        reason: NackReason::NoRoute,
+                                    });
+                                    face.send_nack(packet, NackReason::NoRoute);
+                                    outcome = InterestOutcome::Nacked;
+                                }
+                            }
+                        }
+                        if let InterestOutcome::Forwarded(0) = outcome {
+                            let now = self.clock.now();
+                            let unrouted = &mut self.unrouted_interests;
+                            if !self.tables.has_route(name) {
+                                if unrouted.len() >= MAX_UNROUTED_INTERESTS {
+                                    unrouted.remove(0);
+                                }
+                                unrouted.extend(UnroutedInterest::new(packet, origin, now));
+                            }
+                        }
+                        if let (InterestOutcome::Forwarded(1..), Some((fetch, interest))) =
+                            (&outcome, reliable_fetch)
+                        {
+                            // The consumer retransmitting the interest replaces the retry
+                            self.pending_retries.retain(|retry| {
+                                retry.origin != origin || !retry.is_for(&interest.as_interest())
+                            });
+                            let retry =
+                                PendingRetry::new(interest, origin, fetch, self.clock.now());
+                            self.pending_retries.push(retry);
+                        }
+                        match outcome {
+                            InterestOutcome::Invalid => {
+                                self.hooks.observe(ForwarderEvent::InterestDropped {
+                                    face: origin,
+                                    name,
+                                });
+                            }
+                            InterestOutcome::SatisfiedFromCache => {
+                                self.metrics.cs_hit(origin);
+                                self.hooks.observe(ForwarderEvent::CacheHit {
+                                    face: origin,
+                                    name,
+                                    stale: false,
+                                });
+                                self.cs_hits += 1;
+                                self.prefix_stats.record_interest(name, true);
+                                self.diagnostics.record(
+                                    Subsystem::Strategy,
+                                    Level::Trace,
+                                    format_args!(
+                                        "interest from face {} satisfied from cache",
+                                        origin.0
+                                    ),
+                                );
+                            }
+                            InterestOutcome::ServedStale => {
+                                self.metrics.cs_hit(origin);
+                                self.hooks.observe(ForwarderEvent::CacheHit {
+                                    face: origin,
+                                    name,
+                                    stale: true,
+                                });
+                                self.cs_hits += 1;
+                                self.prefix_stats.record_interest(name, true);
+                                self.diagnostics.record(
+                                    Subsystem::Strategy,
+                                    Level::Debug,
+                                    format_args!(
+                                        "interest from face {} answered with stale data",
+                                        origin.0
+                                    ),
+                                );
+                            }
+                            InterestOutcome::Forwarded(sent_to) => {
+                                self.metrics.cs_miss(origin);
+                                self.cs_misses += 1;
+                                self.prefix_stats.record_interest(name, false);
+                                self.diagnostics.record(
+                                    Subsystem::Strategy,
+                                    Level::Trace,
+                                    format_args!(
+                                        "interest from face {} forwarded to {} faces",
+                                        origin.0, sent_to
+                                    ),
+                                );
+                            }
+                            InterestOutcome::Nacked => {
+                                self.metrics.cs_miss(origin);
+                                self.cs_misses += 1;
+                                self.prefix_stats.record_interest(name, false);
+                                self.diagnostics.record(
+                                    Subsystem::Strategy,
+                                    Level::Debug,
+                                    format_args!(
+                                        "interest from face {} has no route, sent a Nack",
+                                        origin.0
+                                    ),
+                                );
+                            }
+                            InterestOutcome::OverQuota => {
+                                self.hooks.observe(ForwarderEvent::InterestDropped {
+                                    face: origin,
+                                    name,
+                                });
+                                self.diagnostics.record(
+                                    Subsystem::Strategy,
+                                    Level::Debug,
+                                    format_args!(
+                                        "interest from face {} over its PIT quota",
+                                        origin.0
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                    any_processed = true;
+                } else {
+                    // Otherwise ignore the malformed packet
+                    self.metrics.invalid_packet_received(origin);
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Debug,
+                        format_args!("malformed interest from face {}", origin.0),
+                    );
+                }
+            }
+            Data::TLV_TYPE => {
+                // Handle data
+                let decoded = Data::try_decode_from_inner(tlv.val);
+                if decoded.as_ref().is_some_and(|data| {
+                    !self
+                        .hooks
+                        .allows(Direction::Ingress, origin, PacketKind::Data, data.name)
+                }) {
+                    self.metrics.data_dropped(origin);
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Debug,
+                        format_args!("packet filter dropped data from face {}", origin.0),
+                    );
+                    any_processed = true;
+                } else if let Some(data) = decoded {
+                    let data_name = data.name;
+                    let sent_to = Self::handle_data(
+                        data,
+                        &recv_buffer[0..tlv_len],
+                        origin,
+                        PacketContext {
+                            tables: &mut self.tables,
+                            metrics: &mut self.metrics,
+                            hooks: &mut self.hooks,
+                            clock: &mut self.clock,
+                            faces: &mut self.faces.faces,
+                        },
+                        &mut self.hasher,
+                    );
+                    self.diagnostics.record(
+                        Subsystem::Strategy,
+                        Level::Trace,
+                        format_args!("data from face {} forwarded to {} faces", origin.0, sent_to),
+                    );
+                    self.pending_retries
+                        .retain(|retry| !retry.is_answered_by(data_name));
+                    self.unrouted_interests.retain(|unrouted| {
+                        !is_answered_by(unrouted.interest.as_interest(), data_name)
+                    });
+                    any_processed = true;
+                } else {
+                    // Otherwise ignore the malformed packet
+                    self.metrics.invalid_packet_received(origin);
+                    self.diagnostics.record(
+                        Subsystem::Faces,
+                        Level::Debug,
+                        format_args!("malformed data from face {}", origin.0),
+                    );
+                }
+            }
+            LpPacket::TLV_TYPE if Self::is_idle_packet(tlv.val) => {
+                // Only there to keep the face alive
+                self.diagnostics.record(
+                    Subsystem::Faces,
+                    Level::Trace,
+                    format_args!("LP IDLE packet from face {}", origin.0),
+                );
+                any_processed = true;
+            }
+            LpPacket::TLV_TYPE if !entry.inbound_filter.accepts_data() => {
+                // The Nacks answer the interests just like the data
+                self.diagnostics.record(
+                    Subsystem::Faces,
+                    Level::Debug,
+                    format_args!("filtered out Nack from face {}", origin.0),
+                );
+            }
+            LpPacket::TLV_TYPE if !Self::nack_allowed(&mut self.hooks, origin, tlv.val) => {
+                self.diagnostics.record(
+                    Subsystem::Faces,
+                    Level::Debug,
+                    format_args!("packet filter dropped Nack from face {}", origin.0),
+                );
+                any_processed = true;
+            }
+            LpPacket::TLV_TYPE => {
+                let handled = LpPacket::try_decode_from_inner(tlv.val).and_then(|packet| {
+                    Self::handle_nack(
+                        packet,
+                        origin,
+                        PacketContext {
+                            tables: &mut self.tables,
+                            metrics: &mut self.metrics,
+                            hooks: &mut self.hooks,
+                            clock: &mut self.clock,
+                            faces: &mut self.faces.faces,
+                        },
+                    )
+                });
+                match handled {
+                    Some((NackOutcome::Unmatched, _)) => {
+                        self.diagnostics.record(
+                            Subsystem::Strategy,
+                            Level::Trace,
+                            format_args!("Nack from face {} matched no interest", origin.0),
+                        );
+                    }
+                    Some((NackOutcome::Retried, sent_to)) => {
+                        self.diagnostics.record(
+                            Subsystem::Strategy,
+                            Level::Debug,
+                            format_args!(
+                                "Nack from face {}, interest retried on {} faces",
+                                origin.0, sent_to
+                            ),
+                        );
+                    }
+                    Some((NackOutcome::Pending, _)) => {
+                        self.diagnostics.record(
+                            Subsystem::Strategy,
+                            Level::Debug,
+                            format_args!(
+                                "Nack from face {}, waiting for the other upstreams",
+                                origin.0
+                            ),
+                        );
+                    }
+                    Some((NackOutcome::AllUpstreamsFailed, sent_to)) => {
+                        // The downstreams got the Nack, so there is nothing left to retry
+                        if let Some(nacked) = LpPacket::try_decode_from_inner(tlv.val)
+                            .and_then(|packet| packet.fragment)
+                            .and_then(|fragment| Interest::try_decode(fragment.bytes).ok())
+                        {
+                            self.pending_retries
+                                .retain(|retry| !retry.is_for(&nacked.0));
+                        }
+                        self.diagnostics.record(
+                            Subsystem::Strategy,
+                            Level::Debug,
+                            format_args!(
+                                "Nack from face {}, all upstreams failed, passed to {} faces",
+                                origin.0, sent_to
+                            ),
+                        );
+                    }
+                    None => {
+                        // Only the Nacks are expected in the LpPackets reaching the forwarder
+                        self.metrics.invalid_packet_received(origin);
+                        self.diagnostics.record(
+                            Subsystem::Faces,
+                            Level::Debug,
+                            format_args!("malformed Nack from face {}", origin.0),
+                        );
+                    }
+                }
+                any_processed = true;
+            }
+            typ => {
+                self.metrics.invalid_packet_received(origin);
+                self.diagnostics.record(
+                    Subsystem::Faces,
+                    Level::Debug,
+                    format_args!("unknown packet type {} from face {}", typ, origin.0),
+                );
+            } // Otherwise we ignore the packet
+        }
+
+        // Reset the cursor back by the size of the processed element
+        if tlv_len < *recv_buffer_cursor {
+            // There are still some unprocessed bytes
+            recv_buffer.copy_within(tlv_len..*recv_buffer_cursor, 0);
+            *recv_buffer_cursor -= tlv_len;
+        } else {
+            // We are done with this bunch of bytes
+            *recv_buffer_cursor = 0;
+        }
+
+        if let Some(command) = command {
+            self.process_command(command, origin);
+        }
+
+        if self.faces.apply_backpressure(index) {
+            self.diagnostics.record(
+                Subsystem::Faces,
+                Level::Debug,
+                format_args!("paused face {} until its next hops drain", origin.0),
+            );
+        }
+
+        Ok(any_processed)
+    }
+
+    // The length of the packet that the LpPacket carries whole without a Nack. An LpPacket
+    //  is never carried whole by another, so the nested ones are left undecoded.
+    fn wrapped_packet_length(lp_packet: &[u8]) -> Option<usize> {
+        let packet = LpPacket::try_decode_from_inner(lp_packet)?;
+        if packet.nack.is_some()
+            || packet.frag_count.is_some_and(|count| count.val > 1)
+            || packet.frag_index.is_some_and(|index| index.val > 0)
+        {
+            return None;
+        }
+        let fragment = packet.fragment?.bytes;
+        let (tlv, len) = TLV::try_decode(fragment).ok()?;
+        (len == fragment.len() && tlv.typ.get() != LpPacket::TLV_TYPE).then_some(len)
+    }
+
+    // The malformed Nacks are left for handle_nack to report
+    fn is_idle_packet(lp_packet: &[u8]) -> bool {
+        LpPacket::try_decode_from_inner(lp_packet)
+            .is_some_and(|packet| packet.fragment.is_none() && packet.nack.is_none())
+    }
+
+    fn nack_allowed(hooks: &mut Hooks, origin: FaceToken, lp_packet: &[u8]) -> bool {
+        if hooks.packet_filter.is_none() {
+            return true;
+        }
+        let Some(interest_packet) = LpPacket::try_decode_from_inner(lp_packet)
+            .and_then(|packet| packet.nack.and(packet.fragment))
+        else {
+            return true;
+        };
+        match Interest::try_decode(interest_packet.bytes) {
+            Ok((interest, _)) => {
+                hooks.allows(Direction::Ingress, origin, PacketKind::Nack, interest.name)
+            }
+            Err(_) => true,
+        }
+    }
+
+    // Sends the cached data back to the face regardless of its freshness,
+    //  returns false if there is none
+    fn serve_stale_data(
+        name: Name<'_>,
+        can_be_prefix: bool,
+        origin: FaceToken,
+        context: PacketContext<'_, C, M, T>,
+    ) -> bool {
+        let PacketContext {
+            tables,
+            metrics,
+            hooks,
+            clock,
+            faces,
+        } = context;
+        let Some(retrieved) = tables.get_data(name, can_be_prefix, false, clock.now()) else {
+            return false;
+        };
+        if !hooks.allows(Direction::Egress, origin, PacketKind::Data, name) {
+            return true;
+        }
+        if let Some(index) = Faces::find_face(faces, &origin) {
+            metrics.interest_satisfied(origin);
+            metrics.data_sent(origin);
+            faces[index].1.send_whole_packet(retrieved)
+        }
+        true
+    }
+
+    // Tries the other next hops for the interest in the Nack, and only passes the Nack on
+    //  to the downstreams once all the upstreams failed. Returns none if the packet is not
+    //  a Nack with an interest.
+    fn handle_nack(
+        packet: LpPacket<'_>,
+        origin: FaceToken,
+        context: PacketContext<'_, C, M, T>,
+    ) -> Option<(NackOutcome, usize)> {
+        let PacketContext {
+            tables,
+            metrics,
+            hooks,
+            clock,
+            faces,
+        } = context;
+        let nack = packet.nack?;
+        let interest_packet = packet.fragment?.bytes;
+        let (interest, _) = Interest::try_decode(interest_packet).ok()?;
+        let nonce = interest.nonce?.bytes;
+        metrics.nack_received(origin, nack.reason);
+        hooks.observe(ForwarderEvent::NackReceived {
+            from: origin,
+            name: interest.name,
+            reason: nack.reason,
+        });
+
+        let (outcome, next_faces) = tables.process_nack(
+            interest.name,
+            interest.can_be_prefix.is_some(),
+            nonce,
+            nack.reason,
+            origin,
+            clock.now(),
+        );
+        let mut sent_to = 0;
+        for face in next_faces {
+            if face == origin {
+                continue;
+            }
+            let kind = if outcome == NackOutcome::Retried {
+                PacketKind::Interest
+            } else {
+                PacketKind::Nack
+            };
+            // The downstreams of a retried interest are not known here, so it is treated
+            //  as coming from the network
+            if kind == PacketKind::Interest
+                && !scope_allows(
+                    Scope::NonLocal,
+                    Faces::scope_of(faces, &face),
+                    interest.name,
+                )
+            {
+                continue;
+            }
+            if !hooks.allows(Direction::Egress, face, kind, interest.name) {
+                continue;
+            }
+            if let Some(index) = Faces::find_face(faces, &face) {
+                sent_to += 1;
+                if outcome == NackOutcome::Retried {
+                    metrics.interest_sent(face);
+                    hooks.observe(ForwarderEvent::InterestForwarded {
+                        from: origin,
+                        to: face,
+                        name: interest.name,
+                    });
+                    faces[index].1.send_whole_packet(interest_packet);
+                } else {
+                    metrics.nack_sent(face);
+                    hooks.observe(ForwarderEvent::NackSent {
+                        to: face,
+                        name: interest.name,
+                        reason: nack.reason,
+                    });
+                    faces[index].1.send_nack(interest_packet, nack.reason);
+                }
+            }
+        }
+        Some((outcome, sent_to))
+    }
+
+    fn handle_interest<'a>(
+        mut interest: Interest<'a>,
+        original_packet: &'a [u8],
+        origin: FaceToken,
+        context: PacketContext<'_, C, M, T>,
+    ) -> InterestOutcome {
+        let PacketContext {
+            tables,
+            metrics,
+            hooks,
+            clock,
+            faces,
+        } = context;
+        // Interest must have a non-empty name
+        if interest.name.component_count() == 0 {
+            metrics.interest_dropped(origin);
+            return InterestOutcome::Invalid;
+        };
+
+        // We drop all the interests without a nonce, since
+        //  we don't know which faces are local
+        let nonce = match interest.nonce {
+            Some(nonce) => nonce.bytes,
+            None => {
+                metrics.interest_dropped(origin);
+                return InterestOutcome::Invalid;
+            }
+        };
+
+        // We want to drop packets if they have hop limit of 0,
+        //  otherwise we want to decrement it. If the resulting
+        //  hop limit is 0 we will only try to satisfy this from
+        //  the content store, but not forward.
+        // If no hop limit is present we always accept the interest.
+        let is_last_hop = match &interest.hop_limit {
+            Some(hop) => {
+                if hop.val == 0 {
+                    metrics.interest_dropped(origin);
+                    return InterestOutcome::Invalid;
+                } else {
+                    hop.val == 1
+                }
+            }
+            None => false,
+        };
+
+        let now = clock.now();
+
+        // We need to decrement the hop byte if it is present
+        let hop_value_and_byte_idx = if let Some(v) = interest.hop_limit.as_mut() {
+            v.val = v.val.saturating_sub(1);
+            let hop_val = v.val;
+            if let Some(idx) = interest.index_of_hop_byte_in_encoded_tlv() {
+                Some((hop_val, idx))
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        // The cached packet is simply sent back to the same face
+        let name = interest.name;
+        let flooded = hooks.flood_non_local_only && !tables.has_route(name);
+        let mut reply = |retrieved: &[u8]| {
+            if !hooks.allows(Direction::Egress, origin, PacketKind::Data, name) {
+                return;
+            }
+            if let Some(index) = Faces::find_face(faces, &origin) {
+                metrics.interest_satisfied(origin);
+                metrics.data_sent(origin);
+                faces[index].1.send_whole_packet(retrieved)
+            }
+        };
+
+        // If this is the last hop for the interest we can only try to satisfy it locally
+        if is_last_hop {
+            if let Some(retrieved) = tables.get_data(
+                interest.name,
+                interest.can_be_prefix.is_some(),
+                interest.must_be_fresh.is_some(),
+                now,
+            ) {
+                reply(retrieved);
+                return InterestOutcome::SatisfiedFromCache;
+            }
+            metrics.interest_dropped(origin);
+            return InterestOutcome::Forwarded(0);
+        }
+
+        // Otherwise the tables try the cache and then register the interest in one go
+        let Some(next_hops) = tables.process_interest(&interest, nonce, origin, now, &mut reply)
+        else {
+            return InterestOutcome::SatisfiedFromCache;
+        };
+        let mut sent_to = 0;
+        for next_hop in next_hops {
+            // Never forward back to the same face
+            if next_hop == origin
+                || flooded && Faces::scope_of(faces, &next_hop) == Scope::Local
+                || !scope_allows(
+                    Faces::scope_of(faces, &origin),
+                    Faces::scope_of(faces, &next_hop),
+                    name,
+                )
+                || !hooks.allows(Direction::Egress, next_hop, PacketKind::Interest, name)
+            {
+                continue;
+            }
+            if let Some(index) = Faces::find_face(&faces, &next_hop) {
+                metrics.interest_sent(next_hop);
+                hooks.observe(ForwarderEvent::InterestForwarded {
+                    from: origin,
+                    to: next_hop,
+                    name: interest.name,
+                });
+                sent_to += 1;
+                if let Some((hop, idx)) = hop_value_and_byte_idx {
+                    // Use the original packet, but substituting the byte at index
+                    faces[index]
+                        .1
+                        .send_modified_packet(original_packet, &[(idx, idx + 1, &[hop])])
+                } else {
+                    // Use the original packet
+                    faces[index].1.send_whole_packet(&original_packet)
+                }
+            }
+        }
+        if tables.interest_over_quota() {
+            metrics.interest_dropped(origin);
+            return InterestOutcome::OverQuota;
+        }
+        InterestOutcome::Forwarded(sent_to)
+    }
+
+    // Adds an interest identical to one already processed in this batch to the in-records
+    //  of its PIT entry. Returns false if the interest has to go through the regular path,
+    //  e.g. because the first one was satisfied from the content store.
+    fn coalesce_interest(
+        interest: &Interest<'_>,
+        origin: FaceToken,
+        tables: &mut T,
+        metrics: &mut M,
+        clock: &mut C,
+    ) -> bool {
+        // The regular path takes care of dropping the invalid interests
+        let nonce = match interest.nonce {
+            Some(nonce) => nonce.bytes,
+            None => return false,
+        };
+        if let Some(hop) = &interest.hop_limit {
+            if hop.val <= 1 {
+                return false;
+            }
+        }
+
+        let interest_lifetime = interest.interest_lifetime.as_ref().map(|x| x.val);
+        if tables.add_pit_in_record(
+            interest.name,
+            interest.can_be_prefix.is_some(),
+            interest_lifetime,
+            nonce,
+            origin,
+            clock.now(),
+        ) {
+            metrics.interest_coalesced(origin);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn handle_data<'a>(
+        data: Data<'a>,
+        original_packet: &'a [u8],
+        origin: FaceToken,
+        context: PacketContext<'_, C, M, T>,
+        hasher: &mut H,
+    ) -> usize {
+        let PacketContext {
+            tables,
+            metrics,
+            hooks,
+            clock,
+            faces,
+        } = context;
+        let mut is_unsolicited: bool = true;
+        let mut sent_to = 0;
+
+        let now = clock.now();
+
+        // We set up a way to compute the digest of the packet,
+        //  but only if actually needed.
+        let mut digest = None;
+        let mut digest_computation = || match digest {
+            Some(inner) => inner,
+            None => {
+                hasher.reset();
+                hasher.update(original_packet);
+                let inner = hasher.finalize_reset().0;
+                digest = Some(inner);
+                inner
+            }
+        };
+
+        // First we try to find the interest in the PIT and send it to every
+        //  requesting face other than the face we got it from.
+        for face in tables.satisfy_interests(data.name, origin, now, &mut digest_computation) {
+            is_unsolicited = false;
+            if face != origin
+                && scope_allows(
+                    Faces::scope_of(faces, &origin),
+                    Faces::scope_of(faces, &face),
+                    data.name,
+                )
+                && hooks.allows(Direction::Egress, face, PacketKind::Data, data.name)
+            {
+                if let Some(index) = Faces::find_face(&faces, &face) {
+                    metrics.interest_satisfied(face);
+                    metrics.data_sent(face);
+                    hooks.observe(ForwarderEvent::DataForwarded {
+                        from: origin,
+                        to: face,
+                        name: data.name,
+                    });
+                    sent_to += 1;
+                    faces[index].1.send_whole_packet(original_packet)
+                }
+            }
+        }
+
+        // For security we should drop the unsolicited data, unless the face may push it
+        if is_unsolicited && !hooks.accepts_unsolicited(origin, data.name) {
+            metrics.data_dropped(origin);
+            hooks.observe(ForwarderEvent::UnsolicitedData {
+                from: origin,
+                name: data.name,
+            });
+            return 0;
+        }
+
+        // Then, if there was actually any interest, we want to store
+        //  the data to satisfy future requests.
+
+        // The freshness deadline is the last instant when the data packet will be
+        //  considered "fresh" for the purposes of responding to "must be fresh" interests.
+        // No freshness_period means the freshness period of 0, i.e. immediately non-fresh.
+        let freshness_period = data
+            .meta_info
+            .map(|mi| mi.freshness_period)
+            .flatten()
+            .map(|fp| fp.val)
+            .unwrap_or(0);
+
+        let digest = digest_computation();
+        tables.insert_data(data.name, digest, freshness_period, now, original_packet);
+        sent_to
+    }
+}
+
+// The longest prefix of the name with a reliable fetch setting decides
+fn find_reliable_fetch(
+    reliable_fetch: &[(NameBuf, ReliableFetch)],
+    name: Name<'_>,
+) -> Option<ReliableFetch> {
+    reliable_fetch
+        .iter()
+        .filter(|(prefix, _)| prefix.as_name().is_prefix_of(name))
+        .max_by_key(|(prefix, _)| prefix.component_count())
+        .map(|(_, fetch)| *fetch)
+}
+
+// An interest that the forwarder sends again unless its data comes back first
+struct PendingRetry {
+    interest: InterestBuf,
+    origin: FaceToken,
+    retry_at: Timestamp,
+    retry_after_ms: u64,
+    retries_left: u8,
+    // When the interest the consumer sent expires
+    expires_at: Timestamp,
+}
+
+impl PendingRetry {
+    fn new(interest: InterestBuf, origin: FaceToken, fetch: ReliableFetch, now: Timestamp) -> Self {
+        let lifetime_ms = interest
+            .as_interest()
+            .interest_lifetime
+            .map_or(DEFAULT_INTEREST_LIFETIME_MS, |lifetime| lifetime.val);
+        Self {
+            interest,
+            origin,
+            retry_at: now.adding(fetch.retry_after_ms),
+            retry_after_ms: fetch.retry_after_ms,
+            retries_left: fetch.max_retries,
+            expires_at: now.adding(lifetime_ms),
+        }
+    }
+
+    fn is_for(&self, interest: &Interest<'_>) -> bool {
+        let pending = self.interest.as_interest();
+        pending.name == interest.name
+            && pending.can_be_prefix.is_some() == interest.can_be_prefix.is_some()
+    }
+
+    fn is_answered_by(&self, data_name: Name<'_>) -> bool {
+        is_answered_by(self.interest.as_interest(), data_name)
+    }
+}
+
+fn is_answered_by(interest: Interest<'_>, data_name: Name<'_>) -> bool {
+    interest.name == data_name
+        || interest.can_be_prefix.is_some() && interest.name.is_prefix_of(data_name)
+}
+
+// An interest that waits for a route, see forward_unrouted_interests
+struct UnroutedInterest {
+    interest: InterestBuf,
+    origin: FaceToken,
+    expires_at: Timestamp,
+}
+
+impl UnroutedInterest {
+    fn new(packet: &[u8], origin: FaceToken, now: Timestamp) -> Option<Self> {
+        let (interest, _) = Interest::try_decode(packet).ok()?;
+        let lifetime_ms = interest
+            .interest_lifetime
+            .map_or(DEFAULT_INTEREST_LIFETIME_MS, |lifetime| lifetime.val);
+        Some(Self {
+            interest: InterestBuf::from(&interest),
+            origin,
+            expires_at: now.adding(lifetime_ms),
+        })
+    }
+}
+
+// The interests processed during the current batch, so that identical interests arriving
+//  from many faces (e.g. in a broadcast storm) go through the FIB and the strategy only once.
+struct InterestBatch {
+    active: bool,
+    // The ranges of the encoded names in name_bytes, with can_be_prefix and must_be_fresh
+    entries: Vec<(usize, usize, bool, bool)>,
+    name_bytes: Vec<u8>,
+}
+
+impl InterestBatch {
+    fn new() -> Self {
+        Self {
+            active: false,
+            entries: Vec::new(),
+            name_bytes: Vec::new(),
+        }
+    }
+
+    fn begin(&mut self) {
+        self.active = true;
+        self.entries.clear();
+        self.name_bytes.clear();
+    }
+
+    fn end(&mut self) {
+        self.active = false;
+    }
+
+    // Returns true if an identical interest was already seen in this batch,
+    //  otherwise remembers this one.
+    fn check_and_insert(&mut self, interest: &Interest<'_>) -> bool {
+        if !self.active {
+            return false;
+        }
+
+        let can_be_prefix = interest.can_be_prefix.is_some();
+        let must_be_fresh = interest.must_be_fresh.is_some();
+        let start = self.name_bytes.len();
+        let _ = interest.name.encode(&mut self.name_bytes);
+        let end = self.name_bytes.len();
+
+        let (seen, name) = self.name_bytes.split_at(start);
+        let is_duplicate = self.entries.iter().any(|&(s, e, cbp, mbf)| {
+            cbp == can_be_prefix && mbf == must_be_fresh && &seen[s..e] == name
+        });
+
+        if is_duplicate {
+            self.name_bytes.truncate(start);
+        } else {
+            self.entries
+                .push((start, end, can_be_prefix, must_be_fresh));
+        }
+        is_duplicate
+    }
+}
+
+struct Faces {
+    faces: Vec<(u32, FaceEntry)>,
+    recv_buffers: Vec<RecvBuffer>,
+    // The buffers returned by the faces, lent again before allocating
+    spare_recv_buffers: Vec<Box<[u8]>>,
+    latest_face_token: u32,
+}
+
+// The faces only hold a buffer while they have the part of a packet,
+//  so that the idle ones do not take MAX_PACKET_SIZE bytes each
+#[derive(Default)]
+struct RecvBuffer {
+    bytes: Option<Box<[u8]>>,
+    cursor: usize,
+}
+
+impl Faces {
+    fn new() -> Self {
+        Self {
+            faces: Default::default(),
+            recv_buffers: Default::default(),
+            spare_recv_buffers: Vec::new(),
+            latest_face_token: 0,
+        }
+    }
+
+    // Takes the buffer back from the face once it is empty
+    fn release_recv_buffer(&mut self, index: usize) {
+        let recv_buffer = &mut self.recv_buffers[index];
+        if recv_buffer.cursor > 0 {
+            return;
+        }
+        if let Some(bytes) = recv_buffer.bytes.take() {
+            if self.spare_recv_buffers.len() < MAX_SPARE_RECV_BUFFERS {
+                self.spare_recv_buffers.push(bytes);
+            }
+        }
+    }
+
+    fn add_face(
+        &mut self,
+        sender: Box<dyn FaceSender>,
+        receiver: Box<dyn FaceReceiver>,
+    ) -> Option<FaceToken> {
+        let token = self.next_face_token()?;
+        self.latest_face_token = self.latest_face_token.max(token);
+        let entry = FaceEntry {
+            sender,
+            receiver,
+            should_close: false,
+            inbound_filter: InboundPacketFilter::AcceptAll,
+            default_hop_limit: None,
+            nack_no_route: false,
+            scope: Scope::default(),
+            persistency: Persistency::default(),
+            idle_timeout_ms: None,
+            received_since_check: false,
+            last_received: None,
+            last_sent: None,
+            congested: false,
+            waiting_for: Vec::new(),
+            uri: None,
+            outbound: VecDeque::new(),
+            outbound_limit: DEFAULT_OUTBOUND_QUEUE_LIMIT,
+            outbound_dropped: 0,
+            marker: None,
+            scheduler: None,
+            skip_remaining: 0,
+            fragmenter: None,
+            reassembler: None,
+            reassembled: None,
+            unreported_sent_bytes: 0,
+            captured_egress: None,
+        };
+        // Only a reused token can end up before the others
+        let index = self.faces.partition_point(|(other, _)| *other < token);
+        self.faces.insert(index, (token, entry));
+        self.recv_buffers.insert(index, RecvBuffer::default());
+        Some(FaceToken(token))
+    }
+
+    fn remove_face(&mut self, token: FaceToken) -> bool {
+        // Want to ensure we _consume_ the token (and can thus reuse the index)
+        if let Some(idx) = Self::find_face(&self.faces, &token) {
+            self.recv_buffers[idx].cursor = 0;
+            self.release_recv_buffer(idx);
+            self.faces.remove(idx);
+            self.recv_buffers.remove(idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn replace_face<FS, FR>(&mut self, token: FaceToken, sender: FS, receiver: FR) -> bool
+    where
+        FS: FaceSender + 'static,
+        FR: FaceReceiver + 'static,
+    {
+        if let Some(idx) = Self::find_face(&self.faces, &token) {
+            let entry = &mut self.faces[idx].1;
+            entry.sender = Box::new(sender);
+            entry.receiver = Box::new(receiver);
+            entry.should_close = false;
+            entry.congested = false;
+            // The rest of a packet would confuse the new sender
+            entry.outbound.clear();
+            if let Some(scheduler) = entry.scheduler.as_mut() {
+                scheduler.clear();
+            }
+            entry.skip_remaining = 0;
+            if entry.reassembler.is_some() {
+                entry.reassembler = Some(LpReassembler::new());
+            }
+            entry.reassembled = None;
+            self.recv_buffers[idx].cursor = 0;
+            self.release_recv_buffer(idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn next_face_token(&self) -> Option<u32> {
+        if let Some(token) = self.latest_face_token.checked_add(1) {
+            return Some(token);
+        }
+        // Once every token was handed out, the lowest one not in use is reused, which is
+        //  safe since the tables forget the removed faces, including their measurements
+        //  and what the strategies kept about them. Token 0 is never used.
+        let mut expected = 1u32;
+        for (token, _) in self.faces.iter() {
+            if *token != expected {
+                return Some(expected);
+            }
+            expected = expected.checked_add(1)?;
+        }
+        Some(expected)
+    }
+
+    // Makes the face wait for the faces congested by its last packet.
+    // Returns true if there are any.
+    fn apply_backpressure(&mut self, index: usize) -> bool {
+        let mut any_congested = false;
+        for i in 0..self.faces.len() {
+            if self.faces[i].1.congested {
+                self.faces[i].1.congested = false;
+                let token = FaceToken(self.faces[i].0);
+                let waiting_for = &mut self.faces[index].1.waiting_for;
+                if !waiting_for.contains(&token) {
+                    waiting_for.push(token);
+                }
+                any_congested = true;
+            }
+        }
+        any_congested
+    }
+
+    // Forgets the faces that the face waits for once they drain or are removed
+    fn still_paused(&mut self, index: usize) -> bool {
+        let mut waiting_for = core::mem::take(&mut self.faces[index].1.waiting_for);
+        waiting_for.retain(|token| match Self::find_face(&self.faces, token) {
+            Some(i) => self.faces[i].1.is_congested(),
+            None => false,
+        });
+        let paused = !waiting_for.is_empty();
+        self.faces[index].1.waiting_for = waiting_for;
+        paused
+    }
+
+    fn len(&self) -> usize {
+        self.faces.len()
+    }
+
+    fn find_face(faces: &[(u32, FaceEntry)], token: &FaceToken) -> Option<usize> {
+        // Can do binary search because the faces are kept sorted by their tokens
+        faces.binary_search_by_key(&token.0, |x| x.0).ok()
+    }
+
+    // The faces that are gone are treated as the network
+    fn scope_of(faces: &[(u32, FaceEntry)], token: &FaceToken) -> Scope {
+        Self::find_face(faces, token).map_or(Scope::NonLocal, |index| faces[index].1.scope)
+    }
+}
+
+// What happened to an interest that went through the regular path
+enum InterestOutcome {
+    // Dropped before reaching the content store
+    Invalid,
+    SatisfiedFromCache,
+    // The number of faces it was sent to, which can be 0, e.g. without a route
+    Forwarded(usize),
+    // Rejected by the tables for going over the limits of the face
+    OverQuota,
+    // Answered with stale cached data after it could not be forwarded
+    ServedStale,
+    // Sent back in a NoRoute Nack after it could not be forwarded
+    Nacked,
+}
+
+struct FaceEntry {
+    sender: Box<dyn FaceSender>,
+    receiver: Box<dyn FaceReceiver>,
+    should_close: bool,
+    inbound_filter: InboundPacketFilter,
+    default_hop_limit: Option<u8>,
+    nack_no_route: bool,
+    scope: Scope,
+    persistency: Persistency,
+    idle_timeout_ms: Option<u64>,
+    // Set on every receive, so that the clock is only read in the housekeeping
+    received_since_check: bool,
+    last_received: Option<Timestamp>,
+    last_sent: Option<Timestamp>,
+    // Set when a packet left less than MAX_PACKET_SIZE of room in the sender or had to
+    //  be queued
+    congested: bool,
+    // The congested faces the packets from this face went to. The reads from this
+    //  face are paused until all of them drain.
+    waiting_for: Vec<FaceToken>,
+    // Set for the faces created by a command
+    uri: Option<Vec<u8>>,
+    // What the sender did not take yet, in order
+    outbound: VecDeque<u8>,
+    outbound_limit: usize,
+    outbound_dropped: u64,
+    // Set for the faces with a congestion marking
+    marker: Option<CongestionMarker>,
+    // Set for the faces with an egress scheduling, whose packets wait there once
+    //  the outbound queue is not empty
+    scheduler: Option<EgressScheduler>,
+    // The bytes of an oversized packet that were not received yet and are to be discarded
+    skip_remaining: usize,
+    // Set for the faces added with an MTU
+    fragmenter: Option<LpFragmenter>,
+    reassembler: Option<LpReassembler>,
+    // A reassembled packet that did not fit in the receive buffer yet
+    reassembled: Option<Vec<u8>>,
+    // Passed to the metrics in the housekeeping
+    unreported_sent_bytes: usize,
+    // Set while capturing, passed to the capture in the housekeeping
+    captured_egress: Option<Vec<Vec<u8>>>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct OutboundQueueStats {
+    pub queued_bytes: usize,
+    // The packets that did not fit in the queue
+    pub dropped_packets: u64,
+    // Only set with a congestion marking, see Forwarder::set_congestion_marking
+    pub congested: bool,
+    pub marked_packets: u64,
+}
+
+// A deficit round robin between the queues of the traffic classes
+struct EgressScheduler {
+    params: EgressScheduling,
+    prefixes: Vec<(NameBuf, TrafficClass)>,
+    // By class, Interactive first
+    queues: [VecDeque<Vec<u8>>; 2],
+    deficits: [usize; 2],
+    turn: usize,
+    queued_bytes: usize,
+}
+
+impl EgressScheduler {
+    fn new(params: EgressScheduling) -> Self {
+        EgressScheduler {
+            params,
+            prefixes: Vec::new(),
+            queues: Default::default(),
+            deficits: [0; 2],
+            turn: 0,
+            queued_bytes: 0,
+        }
+    }
+
+    fn classify(&self, packet: &[u8]) -> TrafficClass {
+        let Ok((tlv, _)) = TLV::try_decode(packet) else {
+            return TrafficClass::Interactive;
+        };
+        let by_kind = match tlv.typ.get() {
+            Data::TLV_TYPE => TrafficClass::Bulk,
+            _ => TrafficClass::Interactive,
+        };
+        if self.prefixes.is_empty() {
+            return by_kind;
+        }
+        // The name comes first in both the interests and the data
+        let Ok((name, _)) = Name::try_decode(tlv.val) else {
+            return by_kind;
+        };
+        self.prefixes
+            .iter()
+            .filter(|(prefix, _)| prefix.as_name().is_prefix_of(name))
+            .max_by_key(|(prefix, _)| prefix.component_count())
+            .map_or(by_kind, |(_, class)| *class)
+    }
+
+    fn enqueue(&mut self, packet: Vec<u8>) {
+        let class = self.classify(&packet) as usize;
+        self.queued_bytes += packet.len();
+        self.queues[class].push_back(packet);
+    }
+
+    fn dequeue(&mut self) -> Option<Vec<u8>> {
+        if self.queued_bytes == 0 {
+            return None;
+        }
+        loop {
+            let class = self.turn;
+            match self.queues[class].front() {
+                Some(front) if self.deficits[class] >= front.len() => {
+                    self.deficits[class] -= front.len();
+                    let packet = self.queues[class].pop_front()?;
+                    self.queued_bytes -= packet.len();
+                    return Some(packet);
+                }
+                Some(_) => {}
+                // An empty queue does not keep its credit
+                None => self.deficits[class] = 0,
+            }
+            self.turn = (class + 1) % self.queues.len();
+            if !self.queues[self.turn].is_empty() {
+                let weight = match self.turn {
+                    0 => self.params.interactive_weight,
+                    _ => self.params.bulk_weight,
+                };
+                self.deficits[self.turn] += weight.max(1) as usize * SCHEDULING_QUANTUM;
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        for queue in self.queues.iter_mut() {
+            queue.clear();
+        }
+        self.deficits = [0; 2];
+        self.queued_bytes = 0;
+    }
+}
+
+// The CoDel state of a face with a congestion marking
+struct CongestionMarker {
+    params: CongestionMarking,
+    // The bytes that left the queue so far, and the totals that went into it by each
+    //  housekeeping, which tell for how long the oldest queued bytes have waited
+    dequeued: u64,
+    queued_at: VecDeque<(u64, Timestamp)>,
+    // When the face becomes congested unless the wait goes below the target before
+    first_above_time: Option<Timestamp>,
+    congested: bool,
+    mark_count: u64,
+    next_mark_time: Timestamp,
+    // The next packet sent to the face gets the mark
+    mark_pending: bool,
+    marked_packets: u64,
+}
+
+impl CongestionMarker {
+    fn new(params: CongestionMarking) -> Self {
+        CongestionMarker {
+            params,
+            dequeued: 0,
+            queued_at: VecDeque::new(),
+            first_above_time: None,
+            congested: false,
+            mark_count: 0,
+            next_mark_time: Timestamp { ms_since_1970: 0 },
+            mark_pending: false,
+            marked_packets: 0,
+        }
+    }
+
+    // Returns whether the face is congested
+    fn update(&mut self, queued: usize, now: Timestamp) -> bool {
+        let total = self.dequeued + queued as u64;
+        if self.queued_at.back().map_or(self.dequeued, |(t, _)| *t) < total {
+            self.queued_at.push_back((total, now));
+        }
+        while self
+            .queued_at
+            .front()
+            .is_some_and(|(t, _)| *t <= self.dequeued)
+        {
+            self.queued_at.pop_front();
+        }
+        let wait_ms = self
+            .queued_at
+            .front()
+            .and_then(|(_, since)| now.difference(since))
+            .unwrap_or(0);
+        if wait_ms < self.params.target_ms {
+            self.first_above_time = None;
+            self.congested = false;
+            return false;
+        }
+        let first_above_time = *self
+            .first_above_time
+            .get_or_insert(now.adding(self.params.interval_ms));
+        if now < first_above_time {
+            return false;
+        }
+        if !self.congested {
+            self.congested = true;
+            self.mark_count = 0;
+            self.next_mark_time = now;
+        }
+        // The marks get closer as the congestion lasts, to slow the consumers down further
+        if now >= self.next_mark_time {
+            self.mark_pending = true;
+            self.mark_count += 1;
+            self.next_mark_time = now.adding(self.params.interval_ms / self.mark_count.isqrt());
+        }
+        true
+    }
+}
+
+// Sends what the face takes right away and queues the rest behind what is already
+//  queued, so that a slow face never blocks the forwarder
+struct QueuedWriter<'a> {
+    sender: &'a mut dyn FaceSender,
+    queue: &'a mut VecDeque<u8>,
+}
+
+impl<'a> Write for QueuedWriter<'a> {
+    type Error = FaceError;
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        let mut sent = 0;
+        if self.queue.is_empty() {
+            sent = send_available(self.sender, bytes)?;
+        }
+        self.queue.extend(&bytes[sent..]);
+        Ok(())
+    }
+}
+
+// Returns how much of the bytes the sender took before it would block
+fn send_available(sender: &mut dyn FaceSender, bytes: &[u8]) -> Result<usize, FaceError> {
+    let mut sent = 0;
+    while sent < bytes.len() {
+        match sender.try_send(&bytes[sent..]) {
+            Ok(0) | Err(FaceError::WouldBlock) => break,
+            Ok(bytes_sent) => sent += bytes_sent,
+            Err(FaceError::Interrupted) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(sent)
+}
+
+impl FaceEntry {
+    fn try_recv(
+        &mut self,
+        recv_buffer: &mut [u8],
+        recv_buffer_cursor: &mut usize,
+    ) -> Result<usize, FaceError> {
+        if let Some(packet) = self.reassembled.take() {
+            if let Some(len) = self.place(packet, recv_buffer, recv_buffer_cursor) {
+                return Ok(len);
+            }
+        }
+        let bytes_received = self
+            .receiver
+            .try_recv(&mut recv_buffer[*recv_buffer_cursor..])?;
+        debug_assert!(*recv_buffer_cursor + bytes_received <= MAX_PACKET_SIZE);
+
+        // The fragments are kept aside until the whole packet is there
+        if let Some(reassembler) = self.reassembler.as_mut() {
+            let received = &recv_buffer[*recv_buffer_cursor..*recv_buffer_cursor + bytes_received];
+            if let Ok((packet, _)) = LpPacket::try_decode(received) {
+                if packet.frag_count.is_some_and(|count| count.val > 1) {
+                    let Some(whole) = reassembler.receive(&packet) else {
+                        return Ok(0);
+                    };
+                    return match self.place(whole, recv_buffer, recv_buffer_cursor) {
+                        Some(len) => Ok(len),
+                        None => Ok(0),
+                    };
+                }
+            }
+        }
+
+        *recv_buffer_cursor += bytes_received;
+        Ok(bytes_received)
+    }
+
+    // Appends the reassembled packet to the receive buffer, or keeps it for later
+    fn place(
+        &mut self,
+        packet: Vec<u8>,
+        recv_buffer: &mut [u8],
+        recv_buffer_cursor: &mut usize,
+    ) -> Option<usize> {
+        let end = *recv_buffer_cursor + packet.len();
+        if end > recv_buffer.len() {
+            self.reassembled = Some(packet);
+            return None;
+        }
+        recv_buffer[*recv_buffer_cursor..end].copy_from_slice(&packet);
+        *recv_buffer_cursor = end;
+        Some(packet.len())
+    }
+
+    // Only the senders that know their capacity, e.g. local faces, or that could not
+    //  take a packet can be congested
+    fn is_congested(&self) -> bool {
+        !self.outbound.is_empty()
+            || self
+                .sender
+                .send_capacity()
+                .is_some_and(|capacity| capacity < MAX_PACKET_SIZE)
+    }
+
+    fn writer(&mut self) -> QueuedWriter<'_> {
+        QueuedWriter {
+            sender: &mut *self.sender,
+            queue: &mut self.outbound,
+        }
+    }
+
+    // Whether a packet of the length can be sent or queued, which is always the case
+    //  when nothing is queued yet. The packets that are not admitted are dropped.
+    fn admits(&mut self, len: usize) -> bool {
+        if self.outbound.is_empty() || self.queued_len() + len <= self.outbound_limit {
+            true
+        } else {
+            self.outbound_dropped += 1;
+            false
+        }
+    }
+
+    // Updates the activity of a face with an idle timeout, sending a keepalive if needed.
+    // Returns true if the face should be closed.
+    fn check_idle(&mut self, now: Timestamp) -> bool {
+        let Some(timeout_ms) = self.idle_timeout_ms else {
+            return false;
+        };
+        if self.received_since_check || self.last_received.is_none() {
+            self.last_received = Some(now);
+            self.received_since_check = false;
+        }
+        if self.unreported_sent_bytes > 0 || self.last_sent.is_none() {
+            self.last_sent = Some(now);
+        }
+        let idle_for =
+            |last: Option<Timestamp>| last.and_then(|last| now.difference(&last)).unwrap_or(0);
+        match self.persistency {
+            Persistency::OnDemand => idle_for(self.last_received) > timeout_ms,
+            Persistency::Persistent => {
+                if idle_for(self.last_sent) >= timeout_ms / 2 {
+                    self.send_idle();
+                    self.last_sent = Some(now);
+                }
+                false
+            }
+        }
+    }
+
+    fn send_idle(&mut self) {
+        let packet = LpPacket {
+            sequence: None,
+            frag_index: None,
+            frag_count: None,
+            nack: None,
+            congestion_mark: None,
+            acks: &[],
+            tx_sequence: None,
+            fragment: None,
+        };
+        let mut encoded = Vec::with_capacity(packet.encoded_length());
+        let _ = packet.encode(&mut encoded);
+        self.send_whole_packet(&encoded);
+    }
+
+    fn queued_len(&self) -> usize {
+        let scheduled = self.scheduler.as_ref().map_or(0, |s| s.queued_bytes);
+        self.outbound.len() + scheduled
+    }
+
+    // Whether the packet has to wait in the queue of its class
+    fn should_schedule(&self) -> bool {
+        self.scheduler.is_some() && self.queued_len() > 0
+    }
+
+    fn schedule(&mut self, packet: Vec<u8>) {
+        if let Some(scheduler) = self.scheduler.as_mut() {
+            scheduler.enqueue(packet);
+        }
+        self.congested = true;
+    }
+
+    // Sends as much of the queue as the face takes now
+    fn drain_outbound(&mut self) {
+        if self.queued_len() == 0 || self.should_close {
+            return;
+        }
+        let mut sent_any = false;
+        loop {
+            if self.outbound.is_empty() {
+                // The next of the scheduled packets, which goes to the outbound queue
+                //  in turn if the face does not take all of it
+                let Some(packet) = self.scheduler.as_mut().and_then(|s| s.dequeue()) else {
+                    break;
+                };
+                if let Err(err) = self.writer().write(&packet) {
+                    // Only that packet is lost, the others are still sent in turn
+                    if let Some(marker) = self.marker.as_mut() {
+                        marker.dequeued += packet.len() as u64;
+                    }
+                    self.check_sent(Err(err));
+                    if self.should_close {
+                        return;
+                    }
+                    continue;
+                }
+                if let Some(marker) = self.marker.as_mut() {
+                    marker.dequeued += (packet.len() - self.outbound.len()) as u64;
+                }
+                sent_any = true;
+                continue;
+            }
+            let (front, _) = self.outbound.as_slices();
+            match send_available(&mut *self.sender, front) {
+                Ok(0) => break,
+                Ok(sent) => {
+                    self.outbound.drain(..sent);
+                    if let Some(marker) = self.marker.as_mut() {
+                        marker.dequeued += sent as u64;
+                    }
+                    sent_any = true;
+                }
+                Err(err) => {
+                    // The queued bytes cannot be resent in a meaningful way
+                    let queued = self.queued_len();
+                    if let Some(marker) = self.marker.as_mut() {
+                        marker.dequeued += queued as u64;
+                    }
+                    self.outbound.clear();
+                    if let Some(scheduler) = self.scheduler.as_mut() {
+                        scheduler.clear();
+                    }
+                    self.check_sent(Err(err));
+                    return;
+                }
+            }
+        }
+        if sent_any {
+            let result = self.sender.flush();
+            self.check_sent(result);
+        }
+    }
+
+    // Only fatal errors close the face, the others just lose the current packet
+    fn check_sent(&mut self, result: Result<(), FaceError>) -> bool {
+        match result {
+            Ok(()) => true,
+            Err(err) => {
+                if err.is_fatal() {
+                    self.should_close = true;
+                }
+                false
+            }
+        }
+    }
+
+    fn needs_fragmenting(&self, len: usize) -> bool {
+        self.fragmenter.as_ref().is_some_and(|f| len > f.mtu())
+    }
+
+    fn capture(&mut self, packet: &[u8]) {
+        if let Some(captured) = self.captured_egress.as_mut() {
+            captured.push(packet.to_vec());
+        }
+    }
+
+    // Sends the packet in fragments that each fit in the MTU, each flushed on its own
+    fn send_fragmented(&mut self, packet: &[u8], nack: Option<Nack>) {
+        let Some(fragmenter) = self.fragmenter.as_ref() else {
+            return;
+        };
+        if !self.admits(fragmenter.fragmented_length(packet.len())) {
+            self.congested = true;
+            return;
+        }
+        if self.captured_egress.is_some() {
+            match nack {
+                Some(nack) => self.capture(&nack_packet(packet, nack.reason)),
+                None => self.capture(packet),
+            }
+        }
+        let FaceEntry {
+            fragmenter: Some(fragmenter),
+            sender,
+            outbound,
+            ..
+        } = self
+        else {
+            return;
+        };
+        let mut sent_bytes = 0;
+        let result = fragmenter.fragment(packet, nack, &mut |fragment| {
+            sent_bytes += fragment.len();
+            let mut writer = QueuedWriter {
+                sender: &mut **sender,
+                queue: outbound,
+            };
+            writer.write(fragment)?;
+            if outbound.is_empty() {
+                sender.flush()?;
+            }
+            Ok(())
+        });
+        self.unreported_sent_bytes += sent_bytes;
+        self.check_sent(result);
+        self.congested |= self.is_congested();
+    }
+
+    fn has_pending_mark(&self) -> bool {
+        self.marker
+            .as_ref()
+            .is_some_and(|marker| marker.mark_pending)
+    }
+
+    fn take_mark(&mut self) -> Option<CongestionMark> {
+        let marker = self.marker.as_mut().filter(|marker| marker.mark_pending)?;
+        marker.mark_pending = false;
+        marker.marked_packets += 1;
+        Some(CongestionMark { val: 1 })
+    }
+
+    // Wraps the packet in an LpPacket with the pending mark, unless it is an LpPacket
+    //  already or would then need fragmenting, in which case the mark waits
+    fn marked(&mut self, packet: &[u8]) -> Option<Vec<u8>> {
+        if TLV::try_decode(packet).is_ok_and(|(tlv, _)| tlv.typ.get() == LpPacket::TLV_TYPE) {
+            return None;
+        }
+        let mut marked = LpPacket {
+            sequence: None,
+            frag_index: None,
+            frag_count: None,
+            nack: None,
+            congestion_mark: Some(CongestionMark { val: 1 }),
+            acks: &[],
+            tx_sequence: None,
+            fragment: Some(Fragment { bytes: packet }),
+        };
+        if self.needs_fragmenting(marked.encoded_length()) {
+            return None;
+        }
+        marked.congestion_mark = self.take_mark();
+        let mut encoded = Vec::with_capacity(marked.encoded_length());
+        let _ = marked.encode(&mut encoded);
+        Some(encoded)
+    }
+
+    fn send_whole_packet(&mut self, packet: &[u8]) {
+        if self.has_pending_mark() {
+            if let Some(marked) = self.marked(packet) {
+                self.send_whole_packet(&marked);
+                return;
+            }
+        }
+        if self.needs_fragmenting(packet.len()) {
+            self.send_fragmented(packet, None);
+            return;
+        }
+        if !self.admits(packet.len()) {
+            self.congested = true;
+            return;
+        }
+        self.capture(packet);
+        self.unreported_sent_bytes += packet.len();
+        if self.should_schedule() {
+            self.schedule(packet.to_vec());
+            return;
+        }
+        let result = self.writer().write(packet);
+        if !self.check_sent(result) {
+            return;
+        }
+        let result = self.sender.flush();
+        self.check_sent(result);
+        self.congested |= self.is_congested();
+    }
+
+    // Sends the interest back in an LpPacket with the Nack
+    fn send_nack(&mut self, interest_packet: &[u8], reason: NackReason) {
+        let packet = LpPacket {
+            sequence: None,
+            frag_index: None,
+            frag_count: None,
+            nack: Some(Nack { reason }),
+            congestion_mark: None,
+            acks: &[],
+            tx_sequence: None,
+            fragment: Some(Fragment {
+                bytes: interest_packet,
+            }),
+        };
+        if self.needs_fragmenting(packet.encoded_length()) {
+            self.send_fragmented(interest_packet, Some(Nack { reason }));
+            return;
+        }
+        if !self.admits(packet.encoded_length()) {
+            self.congested = true;
+            return;
+        }
+        let packet = LpPacket {
+            congestion_mark: self.take_mark(),
+            ..packet
+        };
+        if self.captured_egress.is_some() {
+            let mut encoded = Vec::with_capacity(packet.encoded_length());
+            let _ = packet.encode(&mut encoded);
+            self.capture(&encoded);
+        }
+        self.unreported_sent_bytes += packet.encoded_length();
+        if self.should_schedule() {
+            let mut encoded = Vec::with_capacity(packet.encoded_length());
+            let _ = packet.encode(&mut encoded);
+            self.schedule(encoded);
+            return;
+        }
+        let result = packet.encode(&mut self.writer());
+        if !self.check_sent(result) {
+            return;
+        }
+        let result = self.sender.flush();
+        self.check_sent(result);
+        self.congested |= self.is_congested();
+    }
+
+    fn send_modified_packet(
+        &mut self,
+        packet: &[u8],
+        ranges_and_replacements: &[(usize, usize, &[u8])],
+    ) {
+        let mut len = packet.len();
+        for &(start, end, replacement) in ranges_and_replacements {
+            len = len + replacement.len() - (end - start);
+        }
+        // Also when capturing, which takes the whole packet
+        if self.needs_fragmenting(len)
+            || self.has_pending_mark()
+            || self.should_schedule()
+            || self.captured_egress.is_some()
+        {
+            let mut modified = Vec::with_capacity(len);
+            let mut offset = 0;
+            for &(start, end, replacement) in ranges_and_replacements {
+                modified.extend_from_slice(&packet[offset..start]);
+                modified.extend_from_slice(replacement);
+                offset = end;
+            }
+            modified.extend_from_slice(&packet[offset..]);
+            if self.needs_fragmenting(len) {
+                self.send_fragmented(&modified, None);
+            } else {
+                self.send_whole_packet(&modified);
+            }
+            return;
+        }
+        if !self.admits(len) {
+            self.congested = true;
+            return;
+        }
+        self.unreported_sent_bytes += len;
+
+        let mut offset = 0;
+
+        for &(start, end, replacement) in ranges_and_replacements {
+            debug_assert!(start >= offset && end >= start);
+            if start > offset {
+                let result = self.writer().write(&packet[offset..start]);
+                if !self.check_sent(result) {
+                    return;
+                }
+            }
+            let result = self.writer().write(replacement);
+            if !self.check_sent(result) {
+                return;
+            }
+            offset = end;
+        }
+
+        if offset < packet.len() {
+            let result = self.writer().write(&packet[offset..]);
+            if !self.check_sent(result) {
+                return;
+            }
+        }
+
+        let result = self.sender.flush();
+        self.check_sent(result);
+        self.congested |= self.is_congested();
+    }
+}
+
+// The interest sent back in an LpPacket with the Nack, as it is captured
+fn nack_packet(interest_packet: &[u8], reason: NackReason) -> Vec<u8> {
+    let packet = LpPacket {
+        sequence: None,
+        frag_index: None,
+        frag_count: None,
+        nack: Some(Nack { reason }),
+        congestion_mark: None,
+        acks: &[],
+        tx_sequence: None,
+        fragment: Some(Fragment {
+            bytes: interest_packet,
+        }),
+    };
+    let mut encoded = Vec::with_capacity(packet.encoded_length());
+    let _ = packet.encode(&mut encoded);
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clock::{Clock, Timestamp},
+        face::{
+            buffered::{default_buffered_receiver, BufferedFaceReceiver, BufferedRecvError},
+            local::{default_local_face, local_face},
+            lp::{
+                CongestionMark, Fragment, LpFragmenter, LpPacket, LpReassembler, Nack, NackReason,
+            },
+            FaceError, FaceReceiver, FaceSender,
+        },
+        forwarder::{
+            CongestionMarking, EgressScheduling, FaceCloseReason, FaceToken, Forwarder,
+            ForwarderError, ForwarderEvent, ForwarderMetrics, ForwarderObserver,
+            InboundPacketFilter, InertMetrics, NamespaceBlocklist, Persistency, RefreshAheadHook,
+            ReliableFetch, Scope, TrafficClass, MAX_PACKET_SIZE,
+        },
+        hash::Hasher,
+        io::{Decode, Encode, Write},
+        mgmt::{
+            self, signed_command, BoxedFace, ControlParameters, ControlResponse, Cost,
+            FaceCreationError, FaceFactory, FaceId, StatusCode, Uri,
+        },
+        name::{Name, NameBuf, NameComponent},
+        packet::{
+            Data, DataBuilder, ForwardingHint, HopLimit, Interest, MustBeFresh, SignatureInfo,
+            SignatureValue,
+        },
+        platform::sha::Sha256Hasher,
+        security::DigestSha256,
+        stats::{CountingMetrics, MetricsSnapshot},
+        tables::{
+            reference::ReferenceTables,
+            rib::{NamespaceRestrictions, Route, RouteFlags, RouteOrigin},
+            CombinedTables, ContentStore, ContentStoreUsage, Fib,
+        },
+        tlv::{TlvDecode, TlvEncode, TLV},
+    };
+
+    use alloc::{rc::Rc, vec::Vec};
+    use core::{
+        cell::{Cell, RefCell},
+        num::NonZeroU32,
+    };
+
+    #[test]
+    fn test_basics() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let hasher = Sha256Hasher::new();
+        let metrics = InertMetrics {};
+        let tables = ReferenceTables::default();
+
+        // Create two pairs of local faces (each "local face" needs a tx pair and rx pair)
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let mut forwarder = Forwarder::new(clock, hasher, metrics, tables);
+
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        let name_prefix = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let name_prefix = name_prefix.adding_components(comp);
+
+        forwarder.register_name_prefix_for_forwarding(name_prefix, face2, 0);
+
+        let comp = &[NameComponent::generic(b"version")];
+        let name = name_prefix.adding_components(comp);
+        let nonce = [1, 2, 3, 4];
+        let interest = Interest::new(name, false, nonce);
+
+        assert!(interest.encode(&mut face1sender).is_ok());
+
+        match forwarder.try_forward_from_any_face(&[]) {
+            Ok(ff) => assert_eq!(ff, face1),
+            Err(_) => panic!(),
+        }
+
+        loop {
+            match face2receiver.try_recv() {
+                Ok((tlv, _)) => {
+                    assert_eq!(tlv.typ.get(), Interest::TLV_TYPE);
+                    let received_interest = Interest::try_decode_from_inner(tlv.val).unwrap();
+                    assert_eq!(
+                        interest.name.component_count(),
+                        received_interest.name.component_count()
+                    );
+                    assert!(Iterator::eq(
+                        interest.name.components(),
+                        received_interest.name.components()
+                    ));
+                    assert_eq!(received_interest.nonce.unwrap().bytes, nonce);
+
+                    let payload = b"v0.3";
+                    let signature_info = SignatureInfo::new_digest_sha256();
+                    let mut data = Data::new_unsigned(name, payload, signature_info);
+                    let mut hasher = Sha256Hasher::new();
+                    data.hash_signed_portion(&mut hasher);
+                    let digest = hasher.finalize_reset();
+                    data.signature_value = SignatureValue {
+                        bytes: digest.0.as_slice(),
+                    };
+
+                    assert!(data.encode(&mut face2sender).is_ok());
+                    break;
+                }
+                Err(BufferedRecvError::NothingReceived) => continue,
+                Err(_) => panic!(),
+            }
+        }
+
+        match forwarder.try_forward_from_any_face(&[]) {
+            Ok(ff) => assert_eq!(ff, face2),
+            Err(_) => panic!(),
+        }
+
+        loop {
+            match face1receiver.try_recv() {
+                Ok((tlv, _)) => {
+                    assert_eq!(tlv.typ.get(), Data::TLV_TYPE);
+                    let received_data = Data::try_decode_from_inner(tlv.val).unwrap();
+                    assert_eq!(received_data.content.unwrap().bytes, b"v0.3");
+
+                    let mut hasher = Sha256Hasher::new();
+                    received_data.hash_signed_portion(&mut hasher);
+                    let digest = hasher.finalize_reset();
+                    assert_eq!(received_data.signature_value.bytes, digest.0.as_slice());
+                    break;
+                }
+                Err(BufferedRecvError::NothingReceived) => continue,
+                Err(_) => panic!(),
+            }
+        }
+
+        forwarder.remove_face(face1);
+        forwarder.remove_face(face2);
+    }
+
+    // A face that fails with whatever error is currently set
+    struct FailingFace {
+        error: Rc<Cell<Option<FaceError>>>,
+    }
+
+    impl FaceSender for FailingFace {
+        fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+            match self.error.get() {
+                Some(err) => Err(err),
+                None => Ok(src.len()),
+            }
+        }
+    }
+
+    impl FaceReceiver for FailingFace {
+        fn try_recv(&mut self, _dst: &mut [u8]) -> Result<usize, FaceError> {
+            match self.error.get() {
+                Some(err) => Err(err),
+                None => Ok(0),
+            }
+        }
+    }
+
+    // Takes nothing while blocked, and never the packets longer than the limit
+    struct LimitedFace {
+        blocked: Rc<Cell<bool>>,
+        limit: usize,
+        sent: Rc<RefCell<Vec<usize>>>,
+    }
+
+    impl FaceSender for LimitedFace {
+        fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+            if self.blocked.get() {
+                return Err(FaceError::WouldBlock);
+            }
+            if src.len() > self.limit {
+                return Err(FaceError::MessageTooLarge);
+            }
+            self.sent.borrow_mut().push(src.len());
+            Ok(src.len())
+        }
+    }
+
+    #[test]
+    fn test_scheduled_packet_too_large() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let blocked = Rc::new(Cell::new(true));
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let sender = LimitedFace {
+            blocked: blocked.clone(),
+            limit: 200,
+            sent: sent.clone(),
+        };
+        let receiver = FailingFace {
+            error: Rc::new(Cell::new(None)),
+        };
+        let face2 = forwarder.add_face(sender, receiver).unwrap();
+        assert!(forwarder.set_egress_scheduling(face2, Some(EgressScheduling::default())));
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+
+        // The first waits in the outbound queue and the others in the scheduler. Each comes
+        //  from its own consumer, since the reads from a consumer are paused once it
+        //  congests the face.
+        let long = [b'x'; 300];
+        let mut lengths = Vec::new();
+        let mut consumers = Vec::new();
+        for (i, id) in [b"a".as_slice(), &long, b"c"].into_iter().enumerate() {
+            let (fs, receiver) = default_local_face();
+            let (mut consumer, fr) = default_local_face();
+            let face = forwarder.add_face(fs, fr).unwrap();
+            let comp = &[NameComponent::generic(id)];
+            let interest = Interest::new(prefix.adding_components(comp), false, [i as u8; 4]);
+            lengths.push(interest.encoded_length());
+            assert!(interest.encode(&mut consumer).is_ok());
+            assert!(forwarder.try_forward_from_face(face).is_ok());
+            consumers.push(receiver);
+        }
+        assert!(sent.borrow().is_empty());
+
+        // The one that is too large is lost, but not the one scheduled after it
+        blocked.set(false);
+        let _ = forwarder.try_forward_from_face(face2);
+        assert_eq!(*sent.borrow(), [lengths[0], lengths[2]]);
+        assert!(forwarder.has_face(face2));
+        assert_eq!(forwarder.outbound_queue(face2).unwrap().queued_bytes, 0);
+        assert_eq!(forwarder.faces_with_queued_output().count(), 0);
+    }
+
+    #[test]
+    fn test_face_errors() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+
+        let error = Rc::new(Cell::new(None));
+        let face2 = forwarder
+            .add_face(
+                FailingFace {
+                    error: error.clone(),
+                },
+                FailingFace {
+                    error: error.clone(),
+                },
+            )
+            .unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+
+        // A packet that cannot be sent is lost, but the face stays
+        error.set(Some(FaceError::MessageTooLarge));
+        let comp = &[NameComponent::generic(b"a")];
+        let name = prefix.adding_components(comp);
+        assert!(Interest::new(name, false, [1, 1, 1, 1])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        error.set(None);
+        assert!(matches!(
+            forwarder.try_forward_from_face(face2),
+            Err(ForwarderError::NothingToForward)
+        ));
+
+        // Transient receive errors are the same as having nothing to receive
+        error.set(Some(FaceError::WouldBlock));
+        assert!(matches!(
+            forwarder.try_forward_from_face(face2),
+            Err(ForwarderError::NothingToForward)
+        ));
+        error.set(Some(FaceError::Interrupted));
+        assert!(matches!(
+            forwarder.try_forward_from_face(face2),
+            Err(ForwarderError::NothingToForward)
+        ));
+
+        // Fatal errors while sending close the face
+        error.set(Some(FaceError::Fatal(5)));
+        let comp = &[NameComponent::generic(b"b")];
+        let name = prefix.adding_components(comp);
+        assert!(Interest::new(name, false, [2, 2, 2, 2])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        error.set(None);
+        assert!(matches!(
+            forwarder.try_forward_from_face(face2),
+            Err(ForwarderError::FaceDisconnected(token)) if token == face2
+        ));
+    }
+
+    #[test]
+    fn test_prefix_stats() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        forwarder.prefix_stats_mut().set_enabled(true);
+
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+
+        let comp = &[NameComponent::generic(b"a")];
+        let name = prefix.adding_components(comp);
+        assert!(Interest::new(name, false, [1, 1, 1, 1])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+
+        let packet = DataBuilder::new(name)
+            .content(b"a")
+            .digest_sign(&mut Sha256Hasher::new());
+        assert!(face2sender.write(&packet).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+
+        // The second one is satisfied from the cache
+        assert!(Interest::new(name, false, [2, 2, 2, 2])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+
+        // Without a registered prefix
+        let comp = &[NameComponent::generic(b"other")];
+        let other = root.adding_components(comp);
+        assert!(Interest::new(other, false, [3, 3, 3, 3])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+
+        let counters = forwarder.prefix_stats().registered_prefix(prefix).unwrap();
+        assert_eq!(counters.interests, 2);
+        assert_eq!(counters.cache_hits, 1);
+        let top = forwarder.prefix_stats().top_unregistered();
+        assert_eq!(top.len(), 1);
+        assert!(top[0].prefix.as_name() == other);
+
+        // Not kept once unregistered
+        assert!(forwarder.unregister_name_prefix_for_forwarding(prefix, face2));
+        assert_eq!(forwarder.prefix_stats().registered_prefix(prefix), None);
+    }
+
+    struct ManualClock {
+        now: Rc<Cell<u64>>,
+    }
+
+    impl Clock for ManualClock {
+        fn now(&mut self) -> Timestamp {
+            Timestamp {
+                ms_since_1970: self.now.get(),
+            }
+        }
+    }
+
+    struct RecordingHook {
+        names: Rc<RefCell<Vec<NameBuf>>>,
+    }
+
+    impl RefreshAheadHook for RecordingHook {
+        fn data_expiring(&mut self, name: Name<'_>) {
+            self.names.borrow_mut().push(NameBuf::from(name));
+        }
+    }
+
+    #[test]
+    fn test_refresh_ahead() {
+        let now = Rc::new(Cell::new(1000));
+        let mut forwarder = Forwarder::new(
+            ManualClock { now: now.clone() },
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let names = Rc::new(RefCell::new(Vec::new()));
+        forwarder.set_refresh_ahead_hook(
+            200,
+            2,
+            RecordingHook {
+                names: names.clone(),
+            },
+        );
+
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+        let comp = &[NameComponent::generic(b"popular")];
+        let popular = prefix.adding_components(comp);
+        let comp = &[NameComponent::generic(b"unpopular")];
+        let unpopular = prefix.adding_components(comp);
+
+        let mut nonce = 0u8;
+        let mut express = |forwarder: &mut Forwarder<_, _, _, _>, name| {
+            nonce += 1;
+            assert!(Interest::new(name, false, [nonce; 4])
+                .encode(&mut face1sender)
+                .is_ok());
+            assert!(forwarder.try_forward_from_face(face1).is_ok());
+        };
+
+        for name in [popular, unpopular] {
+            express(&mut forwarder, name);
+            let packet = DataBuilder::new(name)
+                .freshness_period(1000)
+                .digest_sign(&mut Sha256Hasher::new());
+            assert!(face2sender.write(&packet).is_ok());
+            assert!(forwarder.try_forward_from_face(face2).is_ok());
+        }
+
+        // Satisfied from the cache
+        now.set(1100);
+        express(&mut forwarder, popular);
+        express(&mut forwarder, popular);
+        express(&mut forwarder, unpopular);
+
+        now.set(1500);
+        let _ = forwarder.try_forward_from_face(face1);
+        assert!(names.borrow().is_empty());
+
+        // Only the popular one is reported, and only once
+        now.set(1850);
+        let _ = forwarder.try_forward_from_face(face1);
+        assert_eq!(names.borrow().len(), 1);
+        assert!(names.borrow()[0].as_name() == popular);
+        now.set(1950);
+        let _ = forwarder.try_forward_from_face(face1);
+        assert_eq!(names.borrow().len(), 1);
+
+        forwarder.clear_refresh_ahead_hook();
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+
+        // The consumer face fits a bit more than one packet of the maximum size
+        let (fs1, mut face1receiver) = local_face::<{ MAX_PACKET_SIZE + 2000 }>();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+
+        // The producer answers all the interests at once
+        let content = [7u8; 3000];
+        for i in 0..3u8 {
+            let component = [i];
+            let comp = &[NameComponent::generic(&component)];
+            let name = prefix.adding_components(comp);
+            assert!(Interest::new(name, false, [i; 4])
+                .encode(&mut face1sender)
+                .is_ok());
+            assert!(forwarder.try_forward_from_face(face1).is_ok());
+            let packet = DataBuilder::new(name)
+                .content(&content)
+                .digest_sign(&mut Sha256Hasher::new());
+            assert!(face2sender.write(&packet).is_ok());
+        }
+
+        // The first data fills the consumer face up, so the producer face is paused
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert!(forwarder.paused_faces().eq([face2]));
+        assert!(matches!(
+            forwarder.try_forward_from_face(face2),
+            Err(ForwarderError::NothingToForward)
+        ));
+
+        // Once the consumer reads, the producer face resumes
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        assert!(face1receiver.try_recv(&mut buffer).unwrap() > 3000);
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert!(forwarder.paused_faces().eq([face2]));
+        assert!(face1receiver.try_recv(&mut buffer).unwrap() > 3000);
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert!(face1receiver.try_recv(&mut buffer).unwrap() > 3000);
+        assert!(matches!(
+            forwarder.try_forward_from_face(face2),
+            Err(ForwarderError::NothingToForward)
+        ));
+        assert_eq!(forwarder.paused_faces().count(), 0);
+    }
+
+    #[test]
+    fn test_replace_face() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+
+        // The producer face fails and is later replaced
+        let error = Rc::new(Cell::new(Some(FaceError::Fatal(5))));
+        let face2 = forwarder
+            .add_face(
+                FailingFace {
+                    error: error.clone(),
+                },
+                FailingFace {
+                    error: error.clone(),
+                },
+            )
+            .unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+
+        let comp = &[NameComponent::generic(b"a")];
+        let name = prefix.adding_components(comp);
+        assert!(Interest::new(name, false, [1, 1, 1, 1])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(matches!(
+            forwarder.try_forward_from_face(face2),
+            Err(ForwarderError::FaceDisconnected(_))
+        ));
+
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+        assert!(forwarder.replace_face(face2, fs2, fr2));
+        assert!(!forwarder.replace_face(
+            FaceToken(100),
+            FailingFace {
+                error: error.clone(),
+            },
+            FailingFace { error },
+        ));
+
+        // The route is still there
+        let comp = &[NameComponent::generic(b"b")];
+        let other_name = prefix.adding_components(comp);
+        assert!(Interest::new(other_name, false, [2, 2, 2, 2])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        match face2receiver.try_recv() {
+            Ok((tlv, _)) => assert_eq!(tlv.typ.get(), Interest::TLV_TYPE),
+            Err(_) => panic!(),
+        }
+
+        // And so is the in-record of the interest sent before the replacement
+        let packet = DataBuilder::new(name)
+            .content(b"a")
+            .digest_sign(&mut Sha256Hasher::new());
+        assert!(face2sender.write(&packet).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        match face1receiver.try_recv() {
+            Ok((tlv, _)) => assert_eq!(tlv.typ.get(), Data::TLV_TYPE),
+            Err(_) => panic!(),
+        }
+    }
+
+    struct DropCountingMetrics {
+        interests_dropped: Rc<Cell<usize>>,
+        data_dropped: Rc<Cell<usize>>,
+    }
+
+    impl ForwarderMetrics for DropCountingMetrics {
+        fn interest_dropped(&mut self, _from_face: FaceToken) {
+            self.interests_dropped.set(self.interests_dropped.get() + 1);
+        }
+
+        fn data_dropped(&mut self, _from_face: FaceToken) {
+            self.data_dropped.set(self.data_dropped.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_inbound_packet_filter() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let hasher = Sha256Hasher::new();
+        let interests_dropped = Rc::new(Cell::new(0));
+        let data_dropped = Rc::new(Cell::new(0));
+        let metrics = DropCountingMetrics {
+            interests_dropped: Rc::clone(&interests_dropped),
+            data_dropped: Rc::clone(&data_dropped),
+        };
+        let tables = ReferenceTables::default();
+
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let (fs2, _face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+
+        let mut forwarder = Forwarder::new(clock, hasher, metrics, tables);
+
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        assert!(forwarder.set_inbound_packet_filter(face1, InboundPacketFilter::InterestsOnly));
+        assert!(forwarder.set_inbound_packet_filter(face2, InboundPacketFilter::DataOnly));
+        assert!(!forwarder.set_inbound_packet_filter(FaceToken(42), InboundPacketFilter::DataOnly));
+
+        let comp = &[NameComponent::generic(b"ndn")];
+        let name = Name::new();
+        let name = name.adding_components(comp);
+
+        // Data arriving on an interest-only face is dropped before reaching the tables
+        let mut data = Data::new_unsigned(name, b"v0.3", SignatureInfo::new_digest_sha256());
+        data.signature_value = SignatureValue { bytes: &[0; 32] };
+        assert!(data.encode(&mut face1sender).is_ok());
+        assert!(matches!(
+            forwarder.try_forward_from_face(face1),
+            Err(ForwarderError::NothingToForward)
+        ));
+        assert_eq!(data_dropped.get(), 1);
+
+        // Interests arriving on a data-only face are dropped as well
+        let interest = Interest::new(name, false, [1, 2, 3, 4]);
+        assert!(interest.encode(&mut face2sender).is_ok());
+        assert!(matches!(
+            forwarder.try_forward_from_face(face2),
+            Err(ForwarderError::NothingToForward)
+        ));
+        assert_eq!(interests_dropped.get(), 1);
+
+        // The allowed packet type still goes through
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+    }
+
+    #[test]
+    fn test_forwarding_hint() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let hasher = Sha256Hasher::new();
+        let metrics = InertMetrics {};
+        let tables = ReferenceTables::default();
+
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let (fs2, face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let mut forwarder = Forwarder::new(clock, hasher, metrics, tables);
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        // Only the region of the producer is routable
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"region")];
+        let region = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"other")];
+        let other = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(region, face2, 0);
+
+        let comp = &[
+            NameComponent::generic(b"alice"),
+            NameComponent::generic(b"photo"),
+        ];
+        let name = root.adding_components(comp);
+        let mut hint = Vec::new();
+        let _ = other.encode(&mut hint);
+        let _ = region.encode(&mut hint);
+        let forwarding_hint = ForwardingHint { bytes: &hint };
+        assert!(forwarding_hint.delegations().eq([other, region]));
+
+        // Without the hint there is no route
+        let interest = Interest::new(name, false, [1, 2, 3, 4]);
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(matches!(
+            face2receiver.try_recv(),
+            Err(BufferedRecvError::NothingReceived)
+        ));
+
+        // The first delegation that has a route is used, and the hint is kept
+        let mut interest = Interest::new(name, false, [5, 6, 7, 8]);
+        interest.forwarding_hint = Some(forwarding_hint);
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        match face2receiver.try_recv() {
+            Ok((tlv, _)) => {
+                let received = Interest::try_decode_from_inner(tlv.val).unwrap();
+                assert!(received.name == name);
+                assert_eq!(received.forwarding_hint.unwrap().bytes, hint.as_slice());
+            }
+            Err(_) => panic!("the interest was not forwarded"),
+        }
+    }
+
+    #[test]
+    fn test_default_hop_limit() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let hasher = Sha256Hasher::new();
+        let metrics = InertMetrics {};
+        let tables = ReferenceTables::default();
+
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let (fs2, face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+        let (fs3, _face3receiver) = default_local_face();
+        let (mut face3sender, fr3) = default_local_face();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let mut forwarder = Forwarder::new(clock, hasher, metrics, tables);
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let face3 = forwarder.add_face(fs3, fr3).unwrap();
+
+        assert!(forwarder.set_default_hop_limit(face1, Some(16)));
+        assert!(!forwarder.set_default_hop_limit(FaceToken(42), Some(16)));
+
+        let comp = &[NameComponent::generic(b"ndn")];
+        let name = Name::new();
+        let name = name.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(name, face2, 0);
+
+        // The configured face gets the default, decremented on the way out
+        let interest = Interest::new(name, false, [1, 2, 3, 4]);
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        match face2receiver.try_recv() {
+            Ok((tlv, _)) => {
+                let received = Interest::try_decode_from_inner(tlv.val).unwrap();
+                assert!(received.name == name);
+                assert_eq!(received.hop_limit.unwrap().val, 15);
+            }
+            Err(_) => panic!("the interest was not forwarded"),
+        }
+
+        // An explicit HopLimit is kept
+        let comp = &[NameComponent::generic(b"explicit")];
+        let explicit = name.adding_components(comp);
+        let mut interest = Interest::new(explicit, false, [5, 6, 7, 8]);
+        interest.hop_limit = Some(HopLimit { val: 3 });
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        match face2receiver.try_recv() {
+            Ok((tlv, _)) => {
+                let received = Interest::try_decode_from_inner(tlv.val).unwrap();
+                assert_eq!(received.hop_limit.unwrap().val, 2);
+            }
+            Err(_) => panic!("the interest was not forwarded"),
+        }
+
+        // Other faces are left alone
+        let comp = &[NameComponent::generic(b"other")];
+        let other = name.adding_components(comp);
+        let interest = Interest::new(other, false, [9, 10, 11, 12]);
+        assert!(interest.encode(&mut face3sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face3).is_ok());
+        match face2receiver.try_recv() {
+            Ok((tlv, _)) => {
+                let received = Interest::try_decode_from_inner(tlv.val).unwrap();
+                assert!(received.hop_limit.is_none());
+            }
+            Err(_) => panic!("the interest was not forwarded"),
+        }
+    }
+
+    // Keeps only the latest data packet, regardless of freshness
+    #[derive(Default)]
+    struct LatestDataStore {
+        latest: Option<(NameBuf, Vec<u8>)>,
+        retrieved: usize,
+    }
+
+    impl ContentStore for LatestDataStore {
+        fn insert_data<'a>(
+            &mut self,
+            name: Name<'a>,
+            _digest: [u8; 32],
+            _freshness: u64,
+            _now: Timestamp,
+            packet: &'a [u8],
+        ) {
+            self.latest = Some((NameBuf::from(name), Vec::from(packet)));
+        }
+
+        fn get_data<'a>(
+            &mut self,
+            name: Name<'a>,
+            _can_be_prefix: bool,
+            _must_be_fresh: bool,
+            _now: Timestamp,
+        ) -> Option<&[u8]> {
+            let (latest_name, packet) = self.latest.as_ref()?;
+            if latest_name.as_name() != name {
+                return None;
+            }
+            self.retrieved += 1;
+            Some(packet)
+        }
+
+        fn prune_cs_if_needed(&mut self, _now: Timestamp) {}
+    }
+
+    #[test]
+    fn test_combined_tables() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let hasher = Sha256Hasher::new();
+        let metrics = InertMetrics {};
+        let tables = CombinedTables::new(ReferenceTables::default(), LatestDataStore::default());
+
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let mut forwarder = Forwarder::new(clock, hasher, metrics, tables);
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        let comp = &[NameComponent::generic(b"ndn")];
+        let name = Name::new();
+        let name = name.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(name, face2, 0);
+
+        // The reference tables route the interest
+        let interest = Interest::new(name, false, [1, 2, 3, 4]);
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(face2receiver.try_recv().is_ok());
+
+        // The data goes to the custom store
+        let mut data = Data::new_unsigned(name, b"content", SignatureInfo::new_digest_sha256());
+        data.signature_value = SignatureValue { bytes: &[0; 32] };
+        assert!(data.encode(&mut face2sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert!(face1receiver.try_recv().is_ok());
+        assert!(forwarder.tables.content_store.latest.is_some());
+
+        // ... and is served from there
+        let interest = Interest::new(name, false, [5, 6, 7, 8]);
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        match face1receiver.try_recv() {
+            Ok((tlv, _)) => assert_eq!(tlv.typ.get(), Data::TLV_TYPE),
+            Err(_) => panic!("the data was not served from the store"),
+        }
+        assert!(matches!(
+            face2receiver.try_recv(),
+            Err(BufferedRecvError::NothingReceived)
+        ));
+        assert_eq!(forwarder.tables.content_store.retrieved, 1);
+    }
+
+    #[test]
+    fn test_content_store_byte_budget() {
+        let mut tables = ReferenceTables::default().with_content_store_byte_budget(1000);
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let small = [1u8; 100];
+        let large = [2u8; 800];
+
+        let comp_a = &[NameComponent::generic(b"a")];
+        let comp_b = &[NameComponent::generic(b"b")];
+        let comp_c = &[NameComponent::generic(b"c")];
+        tables.insert_data(root.adding_components(comp_a), [1; 32], 1000, now, &small);
+        tables.insert_data(root.adding_components(comp_b), [2; 32], 1000, now, &large);
+        let usage = tables.content_store_usage();
+        assert_eq!(usage.entries, 2);
+        assert_eq!(usage.bytes, 900);
+
+        // Another large packet does not fit, so the least recently used ones go
+        //  until it does, even though there are only three entries
+        tables.insert_data(root.adding_components(comp_c), [3; 32], 1000, now, &large);
+        let usage = tables.content_store_usage();
+        assert_eq!(usage.entries, 1);
+        assert_eq!(usage.bytes, 800);
+        assert!(tables
+            .get_data(root.adding_components(comp_a), false, false, now)
+            .is_none());
+        assert!(tables
+            .get_data(root.adding_components(comp_c), false, false, now)
+            .is_some());
+
+        // The usage also goes down when the data gets stale
+        let later = now.adding(60 * 1000);
+        tables.prune_cs_if_needed(later);
+        assert_eq!(tables.content_store_usage(), ContentStoreUsage::default());
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let hasher = Sha256Hasher::new();
+        let metrics = InertMetrics {};
+        let tables = ReferenceTables::default();
+
+        let (fs1, face1receiver) = default_local_face();
+        let (_face1sender, fr1) = default_local_face();
+        let (fs2, face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let mut forwarder = Forwarder::new(clock, hasher, metrics, tables);
+
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let ndn = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"other")];
+        let other = root.adding_components(comp);
+
+        forwarder.register_name_prefix_for_forwarding(ndn, face2, 0);
+        forwarder.set_nonce_source(|| [1, 2, 3, 4]);
+
+        // Nothing is broken yet, but the second route is missing
+        let report = forwarder.warm_up(&[ndn]);
+        assert!(report.is_ready());
+        assert_eq!(report.routes[0].sent_to, [face2]);
+
+        match face2receiver.try_recv() {
+            Ok((tlv, _)) => {
+                assert_eq!(tlv.typ.get(), Interest::TLV_TYPE);
+                let probe = Interest::try_decode_from_inner(tlv.val).unwrap();
+                assert!(probe.name == ndn);
+                assert_eq!(probe.nonce.unwrap().bytes, [1, 2, 3, 4]);
+            }
+            Err(_) => panic!(),
+        }
+
+        // The other end of the first face goes away
+        drop(face1receiver);
+
+        // A fresh name, since the first probe is still pending in the PIT
+        let comp = &[NameComponent::generic(b"probe")];
+        let ndn_probe = ndn.adding_components(comp);
+        forwarder.set_nonce_source(|| [5, 6, 7, 8]);
+        let report = forwarder.warm_up(&[ndn_probe, other]);
+        assert!(!report.is_ready());
+        assert_eq!(report.disconnected_faces().collect::<Vec<_>>(), [face1]);
+        assert_eq!(report.routes[0].sent_to, [face2]);
+        assert!(report.unreachable_routes().eq([&NameBuf::from(other)]));
+
+        assert!(matches!(
+            forwarder.try_forward_from_face(face1),
+            Err(ForwarderError::FaceDisconnected(_))
+        ));
+    }
+
+    struct CoalescingMetrics {
+        interests_sent: Rc<Cell<usize>>,
+        interests_coalesced: Rc<Cell<usize>>,
+    }
+
+    impl ForwarderMetrics for CoalescingMetrics {
+        fn interest_sent(&mut self, _to_face: FaceToken) {
+            self.interests_sent.set(self.interests_sent.get() + 1);
+        }
+
+        fn interest_coalesced(&mut self, _from_face: FaceToken) {
+            self.interests_coalesced
+                .set(self.interests_coalesced.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_batch_coalescing() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let hasher = Sha256Hasher::new();
+        let interests_sent = Rc::new(Cell::new(0));
+        let interests_coalesced = Rc::new(Cell::new(0));
+        let metrics = CoalescingMetrics {
+            interests_sent: Rc::clone(&interests_sent),
+            interests_coalesced: Rc::clone(&interests_coalesced),
+        };
+        let tables = ReferenceTables::default();
+        let mut forwarder = Forwarder::new(clock, hasher, metrics, tables);
+
+        // Two consumers and one producer
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let (fs3, _face3receiver) = default_local_face();
+        let (mut face3sender, fr3) = default_local_face();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let _face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let _face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let face3 = forwarder.add_face(fs3, fr3).unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let name = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(name, face3, 0);
+
+        assert!(Interest::new(name, false, [1, 1, 1, 1])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(Interest::new(name, false, [2, 2, 2, 2])
+            .encode(&mut face2sender)
+            .is_ok());
+
+        // Both are processed, but only the first is forwarded
+        assert_eq!(forwarder.try_forward_batch(&[]).ok(), Some(2));
+        assert_eq!(interests_sent.get(), 1);
+        assert_eq!(interests_coalesced.get(), 1);
+        assert!(matches!(
+            forwarder.try_forward_batch(&[]),
+            Err(ForwarderError::NothingToForward)
+        ));
+
+        // Both consumers get the data
+        let mut expected = Vec::new();
+        let mut data = Data::new_unsigned(name, b"content", SignatureInfo::new_digest_sha256());
+        data.signature_value = SignatureValue { bytes: &[0; 32] };
+        assert!(data.encode(&mut expected).is_ok());
+        assert!(data.encode(&mut face3sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face3).is_ok());
+
+        for receiver in [&mut face1receiver, &mut face2receiver] {
+            match receiver.try_recv() {
+                Ok((tlv, bytes)) => {
+                    assert_eq!(tlv.typ.get(), Data::TLV_TYPE);
+                    assert_eq!(bytes, expected.as_slice());
+                }
+                Err(_) => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_forward_budget() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let (fs3, face3receiver) = default_local_face();
+        let (_face3sender, fr3) = default_local_face();
+        let face3 = forwarder.add_face(fs3, fr3).unwrap();
+        let mut face3receiver = default_buffered_receiver(face3receiver);
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face3, 0);
+
+        // Several packets are waiting on each face
+        let mut nonce = 0u8;
+        for sender in [&mut face1sender, &mut face2sender] {
+            for _ in 0..3 {
+                nonce += 1;
+                let component = [nonce];
+                let comp = &[NameComponent::generic(&component)];
+                let name = prefix.adding_components(comp);
+                assert!(Interest::new(name, false, [nonce; 4])
+                    .encode(&mut *sender)
+                    .is_ok());
+            }
+        }
+
+        assert_eq!(forwarder.try_forward_budget(4, &[]).ok(), Some(4));
+        assert_eq!(forwarder.try_forward_budget(4, &[face2]).ok(), Some(1));
+        assert_eq!(forwarder.try_forward_budget(4, &[]).ok(), Some(1));
+        assert!(matches!(
+            forwarder.try_forward_budget(4, &[]),
+            Err(ForwarderError::NothingToForward)
+        ));
+        let mut forwarded = 0;
+        while face3receiver.try_recv().is_ok() {
+            forwarded += 1;
+        }
+        assert_eq!(forwarded, 6);
+        assert!(forwarder.has_face(face1));
+    }
+
+    struct TimeoutMetrics {
+        timed_out: Rc<RefCell<Vec<FaceToken>>>,
+    }
+
+    impl ForwarderMetrics for TimeoutMetrics {
+        fn interest_timed_out(&mut self, from_face: FaceToken) {
+            self.timed_out.borrow_mut().push(from_face);
+        }
+    }
+
+    #[test]
+    fn test_interest_timeout() {
+        let now = Rc::new(Cell::new(1000));
+        let timed_out = Rc::new(RefCell::new(Vec::new()));
+        let metrics = TimeoutMetrics {
+            timed_out: timed_out.clone(),
+        };
+        let mut forwarder = Forwarder::new(
+            ManualClock { now: now.clone() },
+            Sha256Hasher::new(),
+            metrics,
+            ReferenceTables::default(),
+        );
+
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let name = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(name, face2, 0);
+        assert!(Interest::new(name, false, [1; 4])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(timed_out.borrow().is_empty());
+
+        // Nothing answers, so the consumer's interest times out once the tables are pruned
+        now.set(20 * 1000);
+        assert!(matches!(
+            forwarder.try_forward_batch(&[]),
+            Err(ForwarderError::NothingToForward)
+        ));
+        assert_eq!(timed_out.borrow().as_slice(), [face1]);
+    }
+
+    #[test]
+    fn test_serve_stale() {
+        let now = Rc::new(Cell::new(1000));
+        let mut forwarder = Forwarder::new(
+            ManualClock { now: now.clone() },
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"sensor")];
+        let name = prefix.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+
+        let mut express = |forwarder: &mut Forwarder<_, _, _, _>, nonce| {
+            let mut interest = Interest::new(name, false, [nonce; 4]);
+            interest.must_be_fresh = Some(MustBeFresh {});
+            assert!(interest.encode(&mut face1sender).is_ok());
+            assert!(forwarder.try_forward_from_face(face1).is_ok());
+        };
+
+        // Cache the data, which is only fresh for a while
+        express(&mut forwarder, 1);
+        assert!(face2receiver.try_recv().is_ok());
+        let packet = DataBuilder::new(name)
+            .freshness_period(100)
+            .digest_sign(&mut Sha256Hasher::new());
+        assert!(face2sender.write(&packet).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert!(face1receiver.try_recv().is_ok());
+
+        // Without a route the stale data is only used once enabled
+        now.set(5000);
+        assert!(forwarder.unregister_name_prefix_for_forwarding(prefix, face2));
+        express(&mut forwarder, 2);
+        assert!(matches!(
+            face1receiver.try_recv(),
+            Err(BufferedRecvError::NothingReceived)
+        ));
+        forwarder.set_serve_stale(true);
+        express(&mut forwarder, 3);
+        match face1receiver.try_recv() {
+            Ok((tlv, _)) => assert_eq!(tlv.typ.get(), Data::TLV_TYPE),
+            Err(_) => panic!(),
+        }
+
+        // With a route a fresh copy is fetched instead
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+        express(&mut forwarder, 4);
+        assert!(face2receiver.try_recv().is_ok());
+        assert!(matches!(
+            face1receiver.try_recv(),
+            Err(BufferedRecvError::NothingReceived)
+        ));
+    }
+
+    #[test]
+    fn test_forward_on_new_route() {
+        let now = Rc::new(Cell::new(1000));
+        let mut forwarder = Forwarder::new(
+            ManualClock { now: now.clone() },
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(b"ndn"),
+            NameComponent::generic(b"x"),
+            NameComponent::generic(b"other"),
+        ];
+        let prefix = root.adding_components(&comps[..1]);
+        let name = prefix.adding_components(&comps[1..2]);
+        let other = root.adding_components(&comps[2..]);
+        for (name, nonce) in [(name, 1), (other, 2)] {
+            assert!(Interest::new(name, false, [nonce; 4])
+                .encode(&mut face1sender)
+                .is_ok());
+            assert!(forwarder.try_forward_from_face(face1).is_ok());
+        }
+        assert!(face2receiver.try_recv().is_err());
+
+        // Only the interests under the new route go, and their data comes back
+        now.set(2000);
+        assert!(forwarder.register_name_prefix_for_forwarding(prefix, face2, 0));
+        let Ok((tlv, _)) = face2receiver.try_recv() else {
+            panic!()
+        };
+        let interest = Interest::try_decode_from_inner(tlv.val).unwrap();
+        assert!(interest.name == name);
+        assert!(interest.nonce.unwrap().bytes != [1; 4]);
+        assert_eq!(interest.interest_lifetime.unwrap().val, 3000);
+        assert!(face2receiver.try_recv().is_err());
+        let packet = DataBuilder::new(name).digest_sign(&mut Sha256Hasher::new());
+        assert!(face2sender.write(&packet).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        match face1receiver.try_recv() {
+            Ok((tlv, _)) => assert_eq!(tlv.typ.get(), Data::TLV_TYPE),
+            Err(_) => panic!(),
+        }
+
+        // Once
+        assert!(forwarder.register_name_prefix_for_forwarding(prefix, face2, 5));
+        assert!(face2receiver.try_recv().is_err());
+
+        // Not after the interest expired
+        now.set(6000);
+        assert!(forwarder.register_default_route(face2, 0));
+        assert!(face2receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_nack_on_no_route() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"nowhere")];
+        let name = root.adding_components(comp);
+
+        // Dropped silently unless the face asks for the Nacks
+        let interest = Interest::new(name, false, [1; 4]);
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(matches!(
+            face1receiver.try_recv(),
+            Err(BufferedRecvError::NothingReceived)
+        ));
+
+        assert!(forwarder.set_nack_on_no_route(face1, true));
+        let interest = Interest::new(name, false, [2; 4]);
+        let mut interest_packet = Vec::new();
+        assert!(interest.encode(&mut interest_packet).is_ok());
+        assert!(face1sender.write(&interest_packet).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        match face1receiver.try_recv() {
+            Ok((tlv, _)) => {
+                assert_eq!(tlv.typ.get(), LpPacket::TLV_TYPE);
+                let packet = LpPacket::try_decode_from_inner(tlv.val).unwrap();
+                assert_eq!(packet.nack.unwrap().reason, NackReason::NoRoute);
+                assert_eq!(packet.fragment.unwrap().bytes, &interest_packet[..]);
+            }
+            Err(_) => panic!(),
+        }
+        assert!(!forwarder.set_nack_on_no_route(FaceToken(100), true));
+    }
+
+    // The status code and the FaceId of the ControlResponse the face received
+    fn control_response<R: BufferedFaceReceiver>(receiver: &mut R) -> (u64, Option<u64>) {
+        match receiver.try_recv() {
+            Ok((tlv, _)) => {
+                assert_eq!(tlv.typ.get(), Data::TLV_TYPE);
+                let data = Data::try_decode_from_inner(tlv.val).unwrap();
+                let content = data.content.unwrap().bytes;
+                let (response, _) = ControlResponse::try_decode(content).unwrap();
+                let face_id = response.body.and_then(|body| body.face_id);
+                (response.status_code.val, face_id.map(|id| id.val))
+            }
+            Err(_) => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_management_commands() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder
+            .add_face_with_scope(fs1, fr1, Scope::Local)
+            .unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"app")];
+        let prefix = root.adding_components(comp);
+        let mut hasher = Sha256Hasher::new();
+        let mut signer = DigestSha256::new(Sha256Hasher::new());
+        let mut command = |verb: &[u8], parameters: &ControlParameters, time: u64| {
+            let nonce = (time as u32).to_be_bytes();
+            signed_command(
+                b"rib",
+                verb,
+                parameters,
+                nonce,
+                time,
+                &mut signer,
+                &mut hasher,
+            )
+        };
+        let parameters = ControlParameters {
+            name: Some(prefix),
+            cost: Some(Cost { val: 5 }),
+            ..ControlParameters::default()
+        };
+
+        // Forwarded like any other interest until management is enabled
+        let register = command(b"register", &parameters, 1);
+        assert!(face1sender.write(&register).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(matches!(
+            face1receiver.try_recv(),
+            Err(BufferedRecvError::NothingReceived)
+        ));
+
+        forwarder
+            .enable_management(|interest: &Interest<'_>| interest.application_parameters.is_some());
+        let register = command(b"register", &parameters, 2);
+        assert!(face1sender.write(&register).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(
+            control_response(&mut face1receiver),
+            (StatusCode::OK, Some(face1.0 as u64))
+        );
+        let routes = forwarder.rib().routes(prefix);
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].face, face1);
+        assert_eq!(routes[0].origin, RouteOrigin::App);
+        assert_eq!(routes[0].cost, 5);
+        assert_eq!(routes[0].flags, RouteFlags::default());
+
+        // Unsigned commands are rejected by the validator
+        let mut unsigned = parameters;
+        unsigned.cost = None;
+        let mut encoded_parameters = Vec::new();
+        assert!(unsigned.encode(&mut encoded_parameters).is_ok());
+        let comps = &[
+            NameComponent::generic(b"localhost"),
+            NameComponent::generic(b"nfd"),
+            NameComponent::generic(b"rib"),
+            NameComponent::generic(b"unregister"),
+            NameComponent::generic(&encoded_parameters),
+        ];
+        let interest = Interest::new(root.adding_components(comps), false, [3; 4]);
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(
+            control_response(&mut face1receiver),
+            (StatusCode::UNAUTHORIZED, None)
+        );
+        assert_eq!(forwarder.rib().routes(prefix).len(), 1);
+
+        let mut elsewhere = parameters;
+        elsewhere.face_id = Some(FaceId { val: 100 });
+        let register = command(b"register", &elsewhere, 4);
+        assert!(face1sender.write(&register).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(
+            control_response(&mut face1receiver),
+            (StatusCode::FACE_NOT_FOUND, None)
+        );
+
+        let unknown = command(b"announce", &parameters, 5);
+        assert!(face1sender.write(&unknown).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(
+            control_response(&mut face1receiver),
+            (StatusCode::NOT_IMPLEMENTED, None)
+        );
+
+        let unregister = command(b"unregister", &parameters, 6);
+        assert!(face1sender.write(&unregister).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(
+            control_response(&mut face1receiver),
+            (StatusCode::OK, Some(face1.0 as u64))
+        );
+        assert!(forwarder.rib().routes(prefix).is_empty());
+    }
+
+    struct LocalFaceFactory {
+        destroyed: Rc<RefCell<Vec<FaceToken>>>,
+    }
+
+    impl FaceFactory for LocalFaceFactory {
+        fn create_face(
+            &mut self,
+            _token: FaceToken,
+            uri: &str,
+        ) -> Result<BoxedFace, FaceCreationError> {
+            if !uri.starts_with("local://") {
+                return Err(FaceCreationError::UnsupportedUri);
+            }
+            let (sender, _) = default_local_face();
+            let (_, receiver) = default_local_face();
+            Ok((Box::new(sender), Box::new(receiver)))
+        }
+
+        fn face_destroyed(&mut self, token: FaceToken) {
+            self.destroyed.borrow_mut().push(token);
+        }
+    }
+
+    #[test]
+    fn test_face_commands() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder
+            .add_face_with_scope(fs1, fr1, Scope::Local)
+            .unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        forwarder.enable_management(|_: &Interest<'_>| true);
+
+        let mut hasher = Sha256Hasher::new();
+        let mut signer = DigestSha256::new(Sha256Hasher::new());
+        let mut command = |verb: &[u8], parameters: &ControlParameters, time: u64| {
+            let nonce = (time as u32).to_be_bytes();
+            signed_command(
+                b"faces",
+                verb,
+                parameters,
+                nonce,
+                time,
+                &mut signer,
+                &mut hasher,
+            )
+        };
+        let create = ControlParameters {
+            uri: Some(Uri {
+                bytes: b"local://app",
+            }),
+            ..ControlParameters::default()
+        };
+
+        // Not supported without a factory
+        assert!(face1sender.write(&command(b"create", &create, 1)).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(
+            control_response(&mut face1receiver),
+            (StatusCode::NOT_IMPLEMENTED, None)
+        );
+
+        let destroyed = Rc::new(RefCell::new(Vec::new()));
+        forwarder.set_face_factory(LocalFaceFactory {
+            destroyed: destroyed.clone(),
+        });
+        assert!(face1sender.write(&command(b"create", &create, 2)).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        let (status, face_id) = control_response(&mut face1receiver);
+        assert_eq!(status, StatusCode::OK);
+        let face2 = FaceToken(face_id.unwrap() as u32);
+        assert_ne!(face2, face1);
+        assert!(forwarder.has_face(face2));
+
+        let udp = ControlParameters {
+            uri: Some(Uri {
+                bytes: b"udp4://192.0.2.1:6363",
+            }),
+            ..ControlParameters::default()
+        };
+        assert!(face1sender.write(&command(b"create", &udp, 3)).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(
+            control_response(&mut face1receiver),
+            (StatusCode::NOT_ACCEPTABLE, None)
+        );
+
+        let destroy = ControlParameters {
+            face_id: Some(FaceId {
+                val: face2.0 as u64,
+            }),
+            ..ControlParameters::default()
+        };
+        assert!(face1sender.write(&command(b"destroy", &destroy, 4)).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(
+            control_response(&mut face1receiver),
+            (StatusCode::OK, Some(face2.0 as u64))
+        );
+        assert!(!forwarder.has_face(face2));
+        assert_eq!(*destroyed.borrow(), [face2]);
+    }
+
+    #[test]
+    fn test_cs_erase_command() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder
+            .add_face_with_scope(fs1, fr1, Scope::Local)
+            .unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        forwarder.enable_management(|_: &Interest<'_>| true);
+
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(b"a"),
+            NameComponent::generic(b"b"),
+            NameComponent::generic(b"1"),
+            NameComponent::generic(b"2"),
+        ];
+        let a = root.adding_components(&comps[..1]);
+        let b = root.adding_components(&comps[1..2]);
+        let now = forwarder.clock.now();
+        let packet = [0u8; 100];
+        let names = [
+            a.adding_components(&comps[2..3]),
+            a.adding_components(&comps[3..4]),
+            b.adding_components(&comps[2..3]),
+        ];
+        for (i, name) in names.iter().enumerate() {
+            forwarder
+                .tables
+                .insert_data(*name, [i as u8; 32], 1000, now, &packet);
+        }
+
+        assert_eq!(forwarder.content_store_usage().entries, 3);
+
+        let mut hasher = Sha256Hasher::new();
+        let mut signer = DigestSha256::new(Sha256Hasher::new());
+        let mut erase = |name: Name<'_>, count: Option<u64>, time: u64| {
+            let parameters = ControlParameters {
+                name: Some(name),
+                count: count.map(|val| mgmt::Count { val }),
+                ..ControlParameters::default()
+            };
+            let nonce = (time as u32).to_be_bytes();
+            let command = signed_command(
+                b"cs",
+                b"erase",
+                &parameters,
+                nonce,
+                time,
+                &mut signer,
+                &mut hasher,
+            );
+            assert!(face1sender.write(&command).is_ok());
+            assert!(forwarder.try_forward_from_face(face1).is_ok());
+            let Ok((tlv, _)) = face1receiver.try_recv() else {
+                panic!();
+            };
+            let data = Data::try_decode_from_inner(tlv.val).unwrap();
+            let (response, _) = ControlResponse::try_decode(data.content.unwrap().bytes).unwrap();
+            let erased = response.body.and_then(|body| body.count);
+            (response.status_code.val, erased.map(|count| count.val))
+        };
+
+        // The limit is kept, and only the packets under the prefix are erased
+        assert_eq!(erase(a, Some(1), 1), (StatusCode::OK, Some(1)));
+        assert_eq!(erase(a, None, 2), (StatusCode::OK, Some(1)));
+        assert_eq!(erase(a, None, 3), (StatusCode::OK, Some(0)));
+        assert_eq!(erase(a, Some(0), 4), (StatusCode::BAD_REQUEST, None));
+        assert_eq!(forwarder.content_store_usage().entries, 1);
+
+        // The empty name flushes everything
+        assert_eq!(forwarder.erase_cached_data(root, None), 1);
+        assert_eq!(
+            forwarder.content_store_usage(),
+            ContentStoreUsage::default()
+        );
+        assert!(forwarder.tables.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_reliable_fetch() {
+        let now = Rc::new(Cell::new(1000));
+        let mut forwarder = Forwarder::new(
+            ManualClock { now: now.clone() },
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(b"ndn"),
+            NameComponent::generic(b"a"),
+            NameComponent::generic(b"b"),
+        ];
+        let prefix = root.adding_components(&comps[..1]);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+        let fetch = ReliableFetch {
+            retry_after_ms: 300,
+            max_retries: 2,
+        };
+        forwarder.set_reliable_fetch(prefix, Some(fetch));
+
+        let mut upstream_nonces = || {
+            let mut nonces = Vec::new();
+            while let Ok((tlv, _)) = face2receiver.try_recv() {
+                let interest = Interest::try_decode_from_inner(tlv.val).unwrap();
+                nonces.push(interest.nonce.unwrap().bytes);
+            }
+            nonces
+        };
+
+        // Retried with a new nonce until the retries run out
+        let name = prefix.adding_components(&comps[1..2]);
+        assert!(Interest::new(name, false, [1; 4])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(upstream_nonces(), [[1; 4]]);
+        now.set(1200);
+        assert!(forwarder.try_forward_from_face(face1).is_err());
+        assert!(upstream_nonces().is_empty());
+        let mut sent = Vec::new();
+        for time in [1300, 1600, 1900] {
+            now.set(time);
+            assert!(forwarder.try_forward_from_face(face1).is_err());
+            sent.push(upstream_nonces());
+        }
+        assert_eq!(sent[0].len(), 1);
+        assert_eq!(sent[1].len(), 1);
+        assert!(sent[0][0] != [1; 4] && sent[1][0] != sent[0][0]);
+        assert!(sent[2].is_empty());
+
+        // Not once the data came back
+        let name = prefix.adding_components(&comps[2..3]);
+        assert!(Interest::new(name, false, [2; 4])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(upstream_nonces(), [[2; 4]]);
+        let mut data = Data::new_unsigned(name, b"content", SignatureInfo::new_digest_sha256());
+        let mut hasher = Sha256Hasher::new();
+        data.hash_signed_portion(&mut hasher);
+        let digest = hasher.finalize_reset();
+        data.signature_value = SignatureValue {
+            bytes: digest.0.as_slice(),
+        };
+        assert!(data.encode(&mut face2sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert!(face1receiver.try_recv().is_ok());
+        now.set(2500);
+        assert!(forwarder.try_forward_from_face(face1).is_err());
+        assert!(upstream_nonces().is_empty());
+    }
+
+    #[test]
+    fn test_unsolicited_data() {
+        let now = Rc::new(Cell::new(1000));
+        let mut forwarder = Forwarder::new(
+            ManualClock { now: now.clone() },
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(b"local"),
+            NameComponent::generic(b"push"),
+            NameComponent::generic(b"x"),
+            NameComponent::generic(b"y"),
+        ];
+        let prefix = root.adding_components(&comps[..2]);
+        let pushed = prefix.adding_components(&comps[2..3]);
+        let other = root.adding_components(&comps[2..]);
+
+        let mut push = |forwarder: &mut Forwarder<_, _, _, _>, name| {
+            let packet = DataBuilder::new(name)
+                .freshness_period(10000)
+                .digest_sign(&mut Sha256Hasher::new());
+            assert!(face2sender.write(&packet).is_ok());
+            assert!(forwarder.try_forward_from_face(face2).is_ok());
+        };
+        let mut is_cached = |forwarder: &mut Forwarder<_, _, _, _>, name, nonce| {
+            assert!(Interest::new(name, false, [nonce; 4])
+                .encode(&mut face1sender)
+                .is_ok());
+            assert!(forwarder.try_forward_from_face(face1).is_ok());
+            face1receiver.try_recv().is_ok()
+        };
+
+        // Dropped until the face may push under the prefix
+        push(&mut forwarder, pushed);
+        assert!(!is_cached(&mut forwarder, pushed, 1));
+        forwarder.allow_unsolicited_data(prefix, face1);
+        push(&mut forwarder, pushed);
+        assert!(!is_cached(&mut forwarder, pushed, 2));
+        forwarder.allow_unsolicited_data(prefix, face2);
+        push(&mut forwarder, pushed);
+        assert!(is_cached(&mut forwarder, pushed, 3));
+        push(&mut forwarder, other);
+        assert!(!is_cached(&mut forwarder, other, 4));
+
+        assert!(forwarder.disallow_unsolicited_data(prefix, face2));
+        assert!(!forwarder.disallow_unsolicited_data(prefix, face2));
+        let name = pushed.adding_components(&comps[3..]);
+        push(&mut forwarder, name);
+        assert!(!is_cached(&mut forwarder, name, 5));
+    }
+
+    #[test]
+    fn test_outbound_queue() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
 
-        // Reset the cursor back by the size of the processed element
-        if tlv_len < *recv_buffer_cursor {
-            // There are still some unprocessed bytes
-            recv_buffer.copy_within(tlv_len..*recv_buffer_cursor, 0);
-            *recv_buffer_cursor -= tlv_len;
-        } else {
-            // We are done with this bunch of bytes
-            *recv_buffer_cursor = 0;
+        // The producer face takes a bit more than one interest at a time
+        let (fs1, mut face1receiver) = local_face::<4000>();
+        let (_face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        assert!(forwarder.set_outbound_queue_limit(face1, 4000));
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face1, 0);
+
+        // The interests come from different faces, so that none of them is paused
+        let mut consumers = Vec::new();
+        for i in 0..3u8 {
+            let (fs, receiver) = default_local_face();
+            let (mut sender, fr) = default_local_face();
+            let face = forwarder.add_face(fs, fr).unwrap();
+            let component = [i; 3000];
+            let comp = &[NameComponent::generic(&component)];
+            let name = prefix.adding_components(comp);
+            assert!(Interest::new(name, false, [i; 4])
+                .encode(&mut sender)
+                .is_ok());
+            assert!(forwarder.try_forward_from_face(face).is_ok());
+            consumers.push((face, sender, receiver));
         }
 
-        Ok(any_processed)
-    }
+        // The second interest is partly queued and the third does not fit anymore
+        let stats = forwarder.outbound_queue(face1).unwrap();
+        assert!(stats.queued_bytes > 0);
+        assert_eq!(stats.dropped_packets, 1);
+        assert!(forwarder.faces_with_queued_output().eq([face1]));
 
-    fn handle_interest<'a>(
-        mut interest: Interest<'a>,
-        original_packet: &'a [u8],
-        origin: FaceToken,
-        tables: &mut T,
-        metrics: &mut M,
-        clock: &mut C,
-        faces: &mut [(u32, FaceEntry)],
-    ) {
-        // Interest must have a non-empty name
-        if interest.name.component_count() == 0 {
-            metrics.interest_dropped(origin);
-            return;
+        let mut received = Vec::new();
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        let mut read = |received: &mut Vec<u8>| loop {
+            match face1receiver.try_recv(&mut buffer) {
+                Ok(0) => break,
+                Ok(len) => received.extend_from_slice(&buffer[..len]),
+                Err(_) => panic!(),
+            }
         };
+        read(&mut received);
+        assert!(matches!(
+            forwarder.try_forward_from_face(face1),
+            Err(ForwarderError::NothingToForward)
+        ));
+        assert_eq!(forwarder.faces_with_queued_output().count(), 0);
+        read(&mut received);
 
-        // We drop all the interests without a nonce, since
-        //  we don't know which faces are local
-        let nonce = match interest.nonce {
-            Some(nonce) => nonce.bytes,
-            None => {
-                metrics.interest_dropped(origin);
-                return;
+        let mut nonces = Vec::new();
+        let mut cursor = 0;
+        while cursor < received.len() {
+            let (tlv, len) = TLV::try_decode(&received[cursor..]).unwrap();
+            let interest = Interest::try_decode_from_inner(tlv.val).unwrap();
+            nonces.push(interest.nonce.unwrap().bytes);
+            cursor += len;
+        }
+        assert_eq!(nonces, [[0; 4], [1; 4]]);
+    }
+
+    #[test]
+    fn test_egress_scheduling() {
+        let received_order = |scheduling: Option<EgressScheduling>, class| {
+            let clock = crate::platform::clock::MonotonicClock::new();
+            let mut forwarder = Forwarder::new(
+                clock,
+                Sha256Hasher::new(),
+                InertMetrics {},
+                ReferenceTables::default(),
+            );
+            // The consumer of the data, which is also where the interests go
+            let (fs1, mut face1receiver) = local_face::<4000>();
+            let (mut face1sender, fr1) = default_local_face();
+            let face1 = forwarder.add_face(fs1, fr1).unwrap();
+            let (fs2, _face2receiver) = default_local_face();
+            let (mut face2sender, fr2) = default_local_face();
+            let face2 = forwarder.add_face(fs2, fr2).unwrap();
+            assert!(forwarder.set_egress_scheduling(face1, scheduling));
+
+            let root = Name::new();
+            let comps = &[
+                NameComponent::generic(b"d"),
+                NameComponent::generic(b"i"),
+                NameComponent::generic(b"1"),
+                NameComponent::generic(b"2"),
+                NameComponent::generic(b"3"),
+            ];
+            let data_prefix = root.adding_components(&comps[..1]);
+            let interest_prefix = root.adding_components(&comps[1..2]);
+            forwarder.register_name_prefix_for_forwarding(interest_prefix, face1, 0);
+            if let Some(class) = class {
+                assert!(forwarder.set_traffic_class(face1, interest_prefix, Some(class)));
             }
-        };
 
-        // We want to drop packets if they have hop limit of 0,
-        //  otherwise we want to decrement it. If the resulting
-        //  hop limit is 0 we will only try to satisfy this from
-        //  the content store, but not forward.
-        // If no hop limit is present we always accept the interest.
-        let is_last_hop = match &interest.hop_limit {
-            Some(hop) => {
-                if hop.val == 0 {
-                    metrics.interest_dropped(origin);
-                    return;
-                } else {
-                    hop.val == 1
+            // Three data packets of 3000 bytes, the last of which waits. Each comes from
+            //  its own producer, since the reads from a producer are paused once it
+            //  congests the face.
+            let content = [7u8; 3000];
+            let mut producers = Vec::new();
+            for (i, comp) in comps[2..].iter().enumerate() {
+                let name = data_prefix.adding_components(core::slice::from_ref(comp));
+                let (fs, receiver) = default_local_face();
+                let (mut producer, fr) = default_local_face();
+                let face = forwarder.add_face(fs, fr).unwrap();
+                forwarder.register_name_prefix_for_forwarding(name, face, 0);
+                assert!(Interest::new(name, false, [i as u8; 4])
+                    .encode(&mut face1sender)
+                    .is_ok());
+                assert!(forwarder.try_forward_from_face(face1).is_ok());
+                let packet = DataBuilder::new(name)
+                    .content(&content)
+                    .digest_sign(&mut Sha256Hasher::new());
+                assert!(producer.write(&packet).is_ok());
+                assert!(forwarder.try_forward_from_face(face).is_ok());
+                producers.push(receiver);
+            }
+            let name = interest_prefix.adding_components(&comps[2..3]);
+            assert!(Interest::new(name, false, [9; 4])
+                .encode(&mut face2sender)
+                .is_ok());
+            assert!(forwarder.try_forward_from_face(face2).is_ok());
+
+            let mut received = Vec::new();
+            let mut buffer = [0u8; MAX_PACKET_SIZE];
+            loop {
+                match face1receiver.try_recv(&mut buffer) {
+                    Ok(0) if forwarder.faces_with_queued_output().count() == 0 => break,
+                    // The housekeeping sends more of the queue
+                    Ok(0) => {
+                        let _ = forwarder.try_forward_from_face(face1);
+                    }
+                    Ok(len) => received.extend_from_slice(&buffer[..len]),
+                    Err(_) => panic!(),
                 }
             }
-            None => false,
+            let mut types = Vec::new();
+            let mut cursor = 0;
+            while cursor < received.len() {
+                let (tlv, len) = TLV::try_decode(&received[cursor..]).unwrap();
+                types.push(tlv.typ.get());
+                cursor += len;
+            }
+            types
         };
 
-        let now = clock.now();
+        // The interest no longer waits behind all the data
+        let (i, d) = (Interest::TLV_TYPE, Data::TLV_TYPE);
+        assert_eq!(received_order(None, None), [d, d, d, i]);
+        let scheduling = Some(EgressScheduling::default());
+        assert_eq!(received_order(scheduling, None), [d, d, i, d]);
+        let bulk = Some(TrafficClass::Bulk);
+        assert_eq!(received_order(scheduling, bulk), [d, d, d, i]);
+    }
 
-        // First we try to satisfy the interest from our local cache
-        if let Some(retrieved) = tables.get_data(
-            interest.name,
-            interest.can_be_prefix.is_some(),
-            interest.must_be_fresh.is_some(),
-            now,
-        ) {
-            // The packet is found so we simply reply to the same face
-            if let Some(index) = Faces::find_face(&faces, &origin) {
-                metrics.interest_satisfied(origin);
-                metrics.data_sent(origin);
-                faces[index].1.send_whole_packet(retrieved)
+    #[test]
+    fn test_congestion_marking() {
+        let now = Rc::new(Cell::new(1000));
+        let clock = ManualClock {
+            now: Rc::clone(&now),
+        };
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+
+        // The preferred producer face takes a bit more than one interest at a time
+        let (fs1, mut face1receiver) = local_face::<4000>();
+        let (_face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, mut face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        assert!(forwarder.set_congestion_marking(face1, Some(CongestionMarking::default())));
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face1, 0);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 10);
+
+        // The interests come from different faces, so that none of them is paused
+        let mut consumers = Vec::new();
+        for _ in 0..4 {
+            let (fs, receiver) = default_local_face();
+            let (sender, fr) = default_local_face();
+            let face = forwarder.add_face(fs, fr).unwrap();
+            consumers.push((face, sender, receiver));
+        }
+        let big = [[0u8; 3000], [1u8; 3000]];
+        let names: Vec<_> = big
+            .iter()
+            .map(|component| NameComponent::generic(component))
+            .chain([NameComponent::generic(b"a"), NameComponent::generic(b"b")])
+            .collect();
+        let mut send = |forwarder: &mut Forwarder<_, _, _, _>, i: usize| {
+            let (face, sender, _) = &mut consumers[i];
+            let name = prefix.adding_components(&names[i..i + 1]);
+            assert!(Interest::new(name, false, [i as u8; 4])
+                .encode(sender)
+                .is_ok());
+            assert!(forwarder.try_forward_from_face(*face).is_ok());
+            name
+        };
+
+        // The second interest is partly queued, and stays there for longer than the interval
+        send(&mut forwarder, 0);
+        send(&mut forwarder, 1);
+        let stats = forwarder.outbound_queue(face1).unwrap();
+        assert!(stats.queued_bytes > 0 && !stats.congested);
+        now.set(1010);
+        assert!(forwarder.try_forward_from_face(face1).is_err());
+        now.set(1111);
+        assert!(forwarder.try_forward_from_face(face1).is_err());
+        assert!(forwarder.outbound_queue(face1).unwrap().congested);
+
+        // So the next interest goes to the other face
+        let name2 = send(&mut forwarder, 2);
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        let len = face2receiver.try_recv(&mut buffer).unwrap();
+        let (tlv, _) = TLV::try_decode(&buffer[..len]).unwrap();
+        assert!(Interest::try_decode_from_inner(tlv.val).unwrap().name == name2);
+
+        // Once the queue drained, the face is used again and the next packet has the mark
+        let mut received = Vec::new();
+        let mut read = |received: &mut Vec<u8>| loop {
+            match face1receiver.try_recv(&mut buffer) {
+                Ok(0) => break,
+                Ok(len) => received.extend_from_slice(&buffer[..len]),
+                Err(_) => panic!(),
             }
-            return;
+        };
+        read(&mut received);
+        assert!(forwarder.try_forward_from_face(face1).is_err());
+        assert!(!forwarder.outbound_queue(face1).unwrap().congested);
+        let name3 = send(&mut forwarder, 3);
+        read(&mut received);
+        let mut packets = Vec::new();
+        let mut cursor = 0;
+        while cursor < received.len() {
+            let (tlv, len) = TLV::try_decode(&received[cursor..]).unwrap();
+            packets.push(tlv);
+            cursor += len;
         }
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[2].typ.get(), LpPacket::TLV_TYPE);
+        let marked = LpPacket::try_decode_from_inner(packets[2].val).unwrap();
+        assert_eq!(marked.congestion_mark.map(|mark| mark.val), Some(1));
+        let (interest, _) = Interest::try_decode(marked.fragment.unwrap().bytes).unwrap();
+        assert!(interest.name == name3);
+        assert_eq!(forwarder.outbound_queue(face1).unwrap().marked_packets, 1);
 
-        // If this is the last hop for the interest we return, since we could only
-        //  try to satisfy it locally.
-        if is_last_hop {
-            metrics.interest_dropped(origin);
-            return;
+        // The marked packets are received as if they came alone
+        let mut data = Data::new_unsigned(name2, b"content", SignatureInfo::new_digest_sha256());
+        let mut hasher = Sha256Hasher::new();
+        data.hash_signed_portion(&mut hasher);
+        let digest = hasher.finalize_reset();
+        data.signature_value = SignatureValue {
+            bytes: digest.0.as_slice(),
+        };
+        let mut encoded = Vec::new();
+        assert!(data.encode(&mut encoded).is_ok());
+        let packet = LpPacket {
+            sequence: None,
+            frag_index: None,
+            frag_count: None,
+            nack: None,
+            congestion_mark: Some(CongestionMark { val: 1 }),
+            acks: &[],
+            tx_sequence: None,
+            fragment: Some(Fragment { bytes: &encoded }),
+        };
+        assert!(packet.encode(&mut face2sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        let len = consumers[2].2.try_recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..len], &encoded[..]);
+
+        // The LpPackets nested in each other are not unwrapped, however deep they go
+        let mut nested = Vec::new();
+        assert!(packet.encode(&mut nested).is_ok());
+        for _ in 0..200 {
+            let mut outer = Vec::new();
+            let packet = LpPacket {
+                fragment: Some(Fragment { bytes: &nested }),
+                ..packet
+            };
+            assert!(packet.encode(&mut outer).is_ok());
+            nested = outer;
         }
+        assert!(face2sender.try_send(&nested).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert_eq!(consumers[2].2.try_recv(&mut buffer), Ok(0));
+    }
 
-        // We need to decrement the hop byte if it is present
-        let hop_value_and_byte_idx = if let Some(v) = interest.hop_limit.as_mut() {
-            v.val = v.val.saturating_sub(1);
-            let hop_val = v.val;
-            if let Some(idx) = interest.index_of_hop_byte_in_encoded_tlv() {
-                Some((hop_val, idx))
-            } else {
-                None
-            }
-        } else {
-            None
+    struct SkipCountingMetrics {
+        skipped: Rc<RefCell<Vec<usize>>>,
+    }
+
+    impl ForwarderMetrics for SkipCountingMetrics {
+        fn oversized_packet_skipped(&mut self, _from_face: FaceToken, len: usize) {
+            self.skipped.borrow_mut().push(len);
+        }
+    }
+
+    #[test]
+    fn test_oversized_packet_skipped() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let skipped = Rc::new(RefCell::new(Vec::new()));
+        let metrics = SkipCountingMetrics {
+            skipped: Rc::clone(&skipped),
         };
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            metrics,
+            ReferenceTables::default(),
+        );
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = local_face::<{ 4 * MAX_PACKET_SIZE }>();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
 
-        let interest_lifetime = interest.interest_lifetime.map(|x| x.val);
-        for next_hop in tables.register_interest(
-            interest.name,
-            interest.can_be_prefix.is_some(),
-            interest_lifetime,
-            nonce,
-            origin,
-            now,
-        ) {
-            // Never forward back to the same face
-            if next_hop == origin {
-                continue;
+        // A data packet of 20000 bytes, followed by an interest
+        let mut oversized = Vec::new();
+        assert!(TLV {
+            typ: NonZeroU32::new(6).unwrap(),
+            val: &[9u8; 20000],
+        }
+        .encode(&mut oversized)
+        .is_ok());
+        assert!(face1sender.write(&oversized).is_ok());
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let prefix = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+        let interest = Interest::new(prefix, false, [1; 4]);
+        assert!(interest.encode(&mut face1sender).is_ok());
+
+        // The face keeps working once the oversized packet is skipped
+        for _ in 0..4 {
+            let _ = forwarder.try_forward_from_face(face1);
+        }
+        assert_eq!(*skipped.borrow(), [oversized.len()]);
+        match face2receiver.try_recv() {
+            Ok((tlv, _)) => assert_eq!(tlv.typ.get(), Interest::TLV_TYPE),
+            Err(_) => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_recv_buffer_pool() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let lent = |forwarder: &Forwarder<_, _, _, _>| {
+            let faces = &forwarder.faces;
+            let lent = faces.recv_buffers.iter();
+            let lent = lent.filter(|buffer| buffer.bytes.is_some()).count();
+            (lent, faces.spare_recv_buffers.len())
+        };
+        assert_eq!(lent(&forwarder), (0, 0));
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"ndn")];
+        let name = root.adding_components(comp);
+        let mut encoded = Vec::new();
+        assert!(Interest::new(name, false, [1; 4])
+            .encode(&mut encoded)
+            .is_ok());
+
+        // Only the face with the part of a packet holds a buffer
+        assert!(face1sender.write(&encoded[..4]).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_err());
+        assert_eq!(lent(&forwarder), (1, 0));
+        assert!(face1sender.write(&encoded[4..]).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert_eq!(lent(&forwarder), (0, 1));
+
+        // The buffer is lent again rather than allocated
+        assert!(face2sender.write(&encoded).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert_eq!(lent(&forwarder), (0, 1));
+        assert!(face1sender.write(&encoded[..4]).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_err());
+        assert!(forwarder.remove_face(face1));
+        assert_eq!(lent(&forwarder), (0, 1));
+    }
+
+    #[test]
+    fn test_mtu_fragmentation() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let (fs, fr) = default_local_face();
+        assert!(forwarder.add_face_with_mtu(fs, fr, 10).is_none());
+        let face2 = forwarder.add_face_with_mtu(fs2, fr2, 300).unwrap();
+        assert_eq!(forwarder.face_mtu(face2), Some(300));
+        assert_eq!(forwarder.face_mtu(face1), None);
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let root = Name::new();
+        let long = [7u8; 500];
+        let comps = &[
+            NameComponent::generic(b"ndn"),
+            NameComponent::generic(&long),
+        ];
+        let name = root.adding_components(comps);
+        forwarder.register_name_prefix_for_forwarding(
+            root.adding_components(&comps[..1]),
+            face2,
+            0,
+        );
+        let interest = Interest::new(name, false, [1; 4]);
+        let mut encoded_interest = Vec::new();
+        assert!(interest.encode(&mut encoded_interest).is_ok());
+        assert!(face1sender.write(&encoded_interest).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+
+        // The interest goes out in fragments that fit in the MTU
+        let mut reassembler = LpReassembler::new();
+        let mut reassembled = None;
+        let mut fragments = 0;
+        while let Ok((tlv, bytes)) = face2receiver.try_recv() {
+            assert!(bytes.len() <= 300);
+            assert_eq!(tlv.typ.get(), LpPacket::TLV_TYPE);
+            let packet = LpPacket::try_decode_from_inner(tlv.val).unwrap();
+            reassembled = reassembled.or(reassembler.receive(&packet));
+            fragments += 1;
+        }
+        assert!(fragments > 1);
+        let reassembled = reassembled.unwrap();
+        let (received, _) = Interest::try_decode(&reassembled).unwrap();
+        assert_eq!(received.name.component_count(), 2);
+
+        // The fragments of the data are put back together before being forwarded
+        let signature_info = SignatureInfo::new_digest_sha256();
+        let payload = [3u8; 1000];
+        let mut data = Data::new_unsigned(name, &payload, signature_info);
+        let mut hasher = Sha256Hasher::new();
+        data.hash_signed_portion(&mut hasher);
+        let digest = hasher.finalize_reset();
+        data.signature_value = SignatureValue {
+            bytes: digest.0.as_slice(),
+        };
+        let mut encoded_data = Vec::new();
+        assert!(data.encode(&mut encoded_data).is_ok());
+        let mut fragmenter = LpFragmenter::new(300).unwrap();
+        let mut fragments: Vec<Vec<u8>> = Vec::new();
+        let fragmented: Result<(), ()> = fragmenter.fragment(&encoded_data, None, &mut |f| {
+            fragments.push(f.to_vec());
+            Ok(())
+        });
+        assert!(fragmented.is_ok());
+        for fragment in fragments.iter() {
+            assert!(matches!(
+                face1receiver.try_recv(),
+                Err(BufferedRecvError::NothingReceived)
+            ));
+            assert!(face2sender.write(fragment).is_ok());
+            let _ = forwarder.try_forward_from_face(face2);
+        }
+        match face1receiver.try_recv() {
+            Ok((tlv, bytes)) => {
+                assert_eq!(bytes, encoded_data.as_slice());
+                let data = Data::try_decode_from_inner(tlv.val).unwrap();
+                assert_eq!(data.content.unwrap().bytes, payload.as_slice());
             }
-            if let Some(index) = Faces::find_face(&faces, &next_hop) {
-                metrics.interest_sent(next_hop);
-                if let Some((hop, idx)) = hop_value_and_byte_idx {
-                    // Use the original packet, but substituting the byte at index
-                    faces[index]
-                        .1
-                        .send_modified_packet(original_packet, &[(idx, idx + 1, &[hop])])
-                } else {
-                    // Use the original packet
-                    faces[index].1.send_whole_packet(&original_packet)
+            Err(_) => panic!(),
+        }
+    }
+
+    // Keeps the kind of every event with the face it concerns
+    struct RecordingObserver {
+        events: Rc<RefCell<Vec<(&'static str, FaceToken)>>>,
+    }
+
+    impl ForwarderObserver for RecordingObserver {
+        fn event(&mut self, event: &ForwarderEvent<'_>) {
+            let recorded = match *event {
+                ForwarderEvent::FaceAdded { face } => ("added", face),
+                ForwarderEvent::FaceClosed { face, reason } => match reason {
+                    FaceCloseReason::Removed => ("removed", face),
+                    _ => ("closed", face),
+                },
+                ForwarderEvent::InterestForwarded { to, name, .. } => {
+                    assert_eq!(name.component_count(), 2);
+                    ("forwarded", to)
                 }
-            }
+                ForwarderEvent::CacheHit { face, .. } => ("cache hit", face),
+                ForwarderEvent::InterestAggregated { face, .. } => ("aggregated", face),
+                ForwarderEvent::InterestDropped { face, .. } => ("dropped", face),
+                ForwarderEvent::NackReceived { from, .. } => ("nack received", from),
+                ForwarderEvent::NackSent { to, .. } => ("nack sent", to),
+                ForwarderEvent::DataForwarded { to, name, .. } => {
+                    assert_eq!(name.component_count(), 2);
+                    ("data", to)
+                }
+                ForwarderEvent::UnsolicitedData { from, .. } => ("unsolicited", from),
+                ForwarderEvent::PacketFiltered { face, .. } => ("filtered", face),
+            };
+            self.events.borrow_mut().push(recorded);
         }
     }
 
-    fn handle_data<'a>(
-        data: Data<'a>,
-        original_packet: &'a [u8],
-        origin: FaceToken,
-        tables: &mut T,
-        metrics: &mut M,
-        clock: &mut C,
-        hasher: &mut H,
-        faces: &mut [(u32, FaceEntry)],
-    ) {
-        let mut is_unsolicited: bool = true;
+    #[test]
+    fn test_observer() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let events = Rc::new(RefCell::new(Vec::new()));
+        forwarder.set_observer(RecordingObserver {
+            events: Rc::clone(&events),
+        });
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
 
-        let now = clock.now();
+        let root = Name::new();
+        let comps = &[NameComponent::generic(b"ndn"), NameComponent::generic(b"a")];
+        let name = root.adding_components(comps);
+        forwarder.register_name_prefix_for_forwarding(
+            root.adding_components(&comps[..1]),
+            face2,
+            0,
+        );
+        assert!(Interest::new(name, false, [1; 4])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(face2receiver.try_recv().is_ok());
 
-        // We set up a way to compute the digest of the packet,
-        //  but only if actually needed.
-        let mut digest = None;
-        let mut digest_computation = || match digest {
-            Some(inner) => inner,
-            None => {
-                hasher.reset();
-                hasher.update(original_packet);
-                let inner = hasher.finalize_reset().0;
-                digest = Some(inner);
-                inner
-            }
+        let signature_info = SignatureInfo::new_digest_sha256();
+        let mut data = Data::new_unsigned(name, b"a", signature_info);
+        let mut hasher = Sha256Hasher::new();
+        data.hash_signed_portion(&mut hasher);
+        let digest = hasher.finalize_reset();
+        data.signature_value = SignatureValue {
+            bytes: digest.0.as_slice(),
         };
+        assert!(data.encode(&mut face2sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert!(data.encode(&mut face2sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
 
-        // First we try to find the interest in the PIT and send it to every
-        //  requesting face other than the face we got it from.
-        for face in tables.satisfy_interests(data.name, now, &mut digest_computation) {
-            is_unsolicited = false;
-            if face != origin {
-                if let Some(index) = Faces::find_face(&faces, &face) {
-                    metrics.interest_satisfied(face);
-                    metrics.data_sent(face);
-                    faces[index].1.send_whole_packet(original_packet)
-                }
+        // The interest without a nonce is dropped, the next one is served from the cache
+        let mut interest = Interest::new(name, false, [2; 4]);
+        interest.nonce = None;
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(Interest::new(name, false, [3; 4])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(forwarder.remove_face(face2));
+
+        assert_eq!(
+            *events.borrow(),
+            [
+                ("added", face1),
+                ("added", face2),
+                ("forwarded", face2),
+                ("data", face1),
+                ("unsolicited", face2),
+                ("dropped", face1),
+                ("cache hit", face1),
+                ("removed", face2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_counting_metrics() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            CountingMetrics::new(),
+            ReferenceTables::default(),
+        );
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+
+        let root = Name::new();
+        let comps = &[NameComponent::generic(b"ndn")];
+        let name = root.adding_components(comps);
+        forwarder.register_name_prefix_for_forwarding(name, face2, 0);
+        let mut interest_len = 0;
+        for nonce in [[1; 4], [2; 4]] {
+            let interest = Interest::new(name, false, nonce);
+            interest_len = interest.encoded_length();
+            assert!(interest.encode(&mut face1sender).is_ok());
+            assert!(forwarder.try_forward_from_face(face1).is_ok());
+            if nonce == [1; 4] {
+                let signature_info = SignatureInfo::new_digest_sha256();
+                let mut data = Data::new_unsigned(name, b"a", signature_info);
+                let mut hasher = Sha256Hasher::new();
+                data.hash_signed_portion(&mut hasher);
+                let digest = hasher.finalize_reset();
+                data.signature_value = SignatureValue {
+                    bytes: digest.0.as_slice(),
+                };
+                assert!(data.encode(&mut face2sender).is_ok());
+                assert!(forwarder.try_forward_from_face(face2).is_ok());
             }
         }
 
-        // For security we should drop the unsolicited data
-        if is_unsolicited {
-            metrics.data_dropped(origin);
-            return;
-        }
+        // The first interest is forwarded, the second one is served from the cache
+        let snapshot = forwarder.metrics().snapshot();
+        assert_eq!((snapshot.cs_hits, snapshot.cs_misses), (1, 1));
+        assert_eq!(snapshot.cs_hit_rate(), Some(0.5));
+        let counters1 = snapshot.face(face1).unwrap();
+        assert_eq!(counters1.in_interests, 2);
+        assert_eq!(counters1.out_data, 2);
+        assert_eq!(counters1.satisfied_interests, 2);
+        assert_eq!(counters1.in_bytes, 2 * interest_len as u64);
+        let counters2 = snapshot.face(face2).unwrap();
+        assert_eq!(counters2.out_interests, 1);
+        assert_eq!(counters2.in_data, 1);
+        assert_eq!(counters2.out_bytes, interest_len as u64);
+        assert_eq!(counters1.out_bytes, counters2.in_bytes * 2);
+        assert_eq!(snapshot.totals().in_interests, 2);
+        assert_eq!(forwarder.metrics().face_counters(face1), counters1);
 
-        // Then, if there was actually any interest, we want to store
-        //  the data to satisfy future requests.
+        forwarder.metrics_mut().reset();
+        assert_eq!(forwarder.metrics().snapshot(), MetricsSnapshot::default());
+    }
 
-        // The freshness deadline is the last instant when the data packet will be
-        //  considered "fresh" for the purposes of responding to "must be fresh" interests.
-        // No freshness_period means the freshness period of 0, i.e. immediately non-fresh.
-        let freshness_period = data
-            .meta_info
-            .map(|mi| mi.freshness_period)
-            .flatten()
-            .map(|fp| fp.val)
-            .unwrap_or(0);
+    #[test]
+    fn test_packet_filter() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(b"public"),
+            NameComponent::generic(b"private"),
+        ];
+        let public = root.adding_components(&comps[..1]);
+        let private = root.adding_components(&comps[1..]);
+        forwarder.register_name_prefix_for_forwarding(public, face2, 0);
+        forwarder.register_name_prefix_for_forwarding(private, face2, 0);
 
-        let digest = digest_computation();
-        tables.insert_data(data.name, digest, freshness_period, now, original_packet)
+        let mut send = |forwarder: &mut Forwarder<_, _, _, _>, name: Name<'_>, nonce| {
+            assert!(Interest::new(name, false, nonce)
+                .encode(&mut face1sender)
+                .is_ok());
+            assert!(forwarder.try_forward_from_face(face1).is_ok());
+        };
+
+        // The private namespace is not taken from face 1
+        let mut blocklist = NamespaceBlocklist::new();
+        blocklist.block(face1, private);
+        assert!(blocklist.is_blocked(face1, private.adding_components(&comps[..1])));
+        assert!(!blocklist.is_blocked(face2, private));
+        forwarder.set_packet_filter(blocklist);
+        send(&mut forwarder, public, [1; 4]);
+        assert!(face2receiver.try_recv().is_ok());
+        send(&mut forwarder, private, [2; 4]);
+        assert!(face2receiver.try_recv().is_err());
+
+        // Nor sent to face 2
+        let mut blocklist = NamespaceBlocklist::new();
+        blocklist.block(face2, private);
+        forwarder.set_packet_filter(blocklist);
+        send(&mut forwarder, private, [3; 4]);
+        assert!(face2receiver.try_recv().is_err());
+
+        forwarder.clear_packet_filter();
+        let comp = &[NameComponent::generic(b"b")];
+        send(&mut forwarder, private.adding_components(comp), [4; 4]);
+        assert!(face2receiver.try_recv().is_ok());
     }
-}
 
-struct Faces {
-    faces: Vec<(u32, FaceEntry)>,
-    recv_buffers: Vec<([u8; MAX_PACKET_SIZE], usize)>,
-    latest_face_token: u32,
-}
+    #[test]
+    fn test_idle_faces() {
+        let now = Rc::new(Cell::new(1000));
+        let mut forwarder = Forwarder::new(
+            ManualClock { now: now.clone() },
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (_face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let (fs2, _face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let (fs3, _face3receiver) = default_local_face();
+        let (mut face3sender, fr3) = default_local_face();
+        let face3 = forwarder.add_face(fs3, fr3).unwrap();
+        for face in [face1, face2, face3] {
+            assert!(forwarder.set_idle_timeout(face, Some(1000)));
+        }
+        assert!(forwarder.set_face_persistency(face2, Persistency::OnDemand));
+        assert!(forwarder.set_face_persistency(face3, Persistency::OnDemand));
+        let _ = forwarder.try_forward_from_any_face(&[]);
 
-impl Faces {
-    fn new() -> Self {
-        Self {
-            faces: Default::default(),
-            recv_buffers: Default::default(),
-            latest_face_token: 0,
+        // The persistent face is kept alive from our side, the on-demand one from theirs
+        now.set(1600);
+        let idle = LpPacket {
+            sequence: None,
+            frag_index: None,
+            frag_count: None,
+            nack: None,
+            congestion_mark: None,
+            acks: &[],
+            tx_sequence: None,
+            fragment: None,
+        };
+        assert!(idle.encode(&mut face3sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face3).is_ok());
+        match face1receiver.try_recv() {
+            Ok((tlv, _)) => assert_eq!(tlv.typ.get(), LpPacket::TLV_TYPE),
+            Err(_) => panic!(),
         }
+
+        now.set(2200);
+        let _ = forwarder.try_forward_from_any_face(&[]);
+        assert!(forwarder.has_face(face1));
+        assert!(!forwarder.has_face(face2));
+        assert!(forwarder.has_face(face3));
+        assert_eq!(forwarder.take_reaped_faces(), vec![face2]);
     }
 
-    fn add_face<FS, FR>(&mut self, sender: FS, receiver: FR) -> Option<FaceToken>
-    where
-        FS: FaceSender + 'static,
-        FR: FaceReceiver + 'static,
-    {
-        let token = self.next_face_token()?;
-        self.latest_face_token = token;
-        let entry = FaceEntry {
-            sender: Box::new(sender),
-            receiver: Box::new(receiver),
-            should_close: false,
-        };
-        self.faces.push((token, entry));
-        self.recv_buffers.push(([0u8; MAX_PACKET_SIZE], 0));
-        Some(FaceToken(token))
+    struct ClosedFaces {
+        closed: Rc<RefCell<Vec<(FaceToken, FaceCloseReason)>>>,
     }
 
-    fn remove_face(&mut self, token: FaceToken) -> bool {
-        // Want to ensure we _consume_ the token (and can thus reuse the index)
-        if let Some(idx) = Self::find_face(&self.faces, &token) {
-            self.faces.remove(idx);
-            self.recv_buffers.remove(idx);
-            true
-        } else {
-            false
+    impl ForwarderObserver for ClosedFaces {
+        fn event(&mut self, event: &ForwarderEvent<'_>) {
+            if let ForwarderEvent::FaceClosed { face, reason } = *event {
+                self.closed.borrow_mut().push((face, reason));
+            }
         }
     }
 
-    pub fn next_face_token(&self) -> Option<u32> {
-        self.latest_face_token.checked_add(1)
-    }
+    #[test]
+    fn test_reap_dead_faces() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let closed = Rc::new(RefCell::new(Vec::new()));
+        forwarder.set_observer(ClosedFaces {
+            closed: closed.clone(),
+        });
+        forwarder.set_reap_dead_faces(true);
+        let (fs1, _face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        // Nobody reads what is sent to face 3
+        let (fs3, face3receiver) = default_local_face();
+        let (_face3sender, fr3) = default_local_face();
+        let face3 = forwarder.add_face(fs3, fr3).unwrap();
+        drop(face3receiver);
 
-    fn len(&self) -> usize {
-        self.faces.len()
-    }
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"a")];
+        let name = root.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(name, face3, 0);
 
-    fn find_face(faces: &[(u32, FaceEntry)], token: &FaceToken) -> Option<usize> {
-        // Can do binary search because we always push higher ids to the end
-        faces.binary_search_by_key(&token.0, |x| x.0).ok()
+        // The face that disconnects while being read from
+        drop(face2sender);
+        assert!(matches!(
+            forwarder.try_forward_from_face(face2),
+            Err(ForwarderError::FaceDisconnected(face)) if face == face2
+        ));
+        assert!(!forwarder.has_face(face2));
+
+        // And the one that fails while being sent to
+        assert!(Interest::new(name, false, [1; 4])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(!forwarder.has_face(face3));
+        assert!(forwarder.has_face(face1));
+
+        assert_eq!(forwarder.take_reaped_faces(), vec![face2, face3]);
+        assert_eq!(
+            *closed.borrow(),
+            vec![
+                (face2, FaceCloseReason::Disconnected),
+                (face3, FaceCloseReason::Failed)
+            ]
+        );
     }
-}
 
-struct FaceEntry {
-    sender: Box<dyn FaceSender>,
-    receiver: Box<dyn FaceReceiver>,
-    should_close: bool,
-}
+    #[test]
+    fn test_flood_on_no_route() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        forwarder.set_flood_on_no_route(Some(60_000));
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder
+            .add_face_with_scope(fs1, fr1, Scope::Local)
+            .unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let (fs2, face2receiver) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder
+            .add_face_with_scope(fs2, fr2, Scope::NonLocal)
+            .unwrap();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+        let (fs3, face3receiver) = default_local_face();
+        let (mut face3sender, fr3) = default_local_face();
+        let face3 = forwarder
+            .add_face_with_scope(fs3, fr3, Scope::NonLocal)
+            .unwrap();
+        let mut face3receiver = default_buffered_receiver(face3receiver);
+        // Another application, which is not flooded
+        let (fs4, face4receiver) = default_local_face();
+        let (_face4sender, fr4) = default_local_face();
+        forwarder
+            .add_face_with_scope(fs4, fr4, Scope::Local)
+            .unwrap();
+        let mut face4receiver = default_buffered_receiver(face4receiver);
 
-impl FaceEntry {
-    fn try_recv(
-        &mut self,
-        recv_buffer: &mut [u8],
-        recv_buffer_cursor: &mut usize,
-    ) -> Result<usize, FaceError> {
-        let bytes_received = self
-            .receiver
-            .try_recv(&mut recv_buffer[*recv_buffer_cursor..])?;
-        debug_assert!(*recv_buffer_cursor + bytes_received <= MAX_PACKET_SIZE);
-        *recv_buffer_cursor += bytes_received;
-        Ok(bytes_received)
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(b"mesh"),
+            NameComponent::generic(b"a"),
+            NameComponent::generic(b"b"),
+        ];
+        let prefix = root.adding_components(&comps[..1]);
+        let first = prefix.adding_components(&comps[1..2]);
+        let second = prefix.adding_components(&comps[2..]);
+
+        let interest = Interest::new(first, false, [1; 4]);
+        assert!(interest.encode(&mut face1sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(face2receiver.try_recv().is_ok());
+        assert!(face3receiver.try_recv().is_ok());
+        assert!(face4receiver.try_recv().is_err());
+
+        // The copy that loops back is not flooded again
+        assert!(interest.encode(&mut face3sender).is_ok());
+        let _ = forwarder.try_forward_from_face(face3);
+        assert!(face2receiver.try_recv().is_err());
+
+        // The data teaches the forwarder where the namespace is
+        let mut data = Data::new_unsigned(first, b"content", SignatureInfo::new_digest_sha256());
+        let mut hasher = Sha256Hasher::new();
+        data.hash_signed_portion(&mut hasher);
+        let digest = hasher.finalize_reset();
+        data.signature_value = SignatureValue {
+            bytes: digest.0.as_slice(),
+        };
+        assert!(data.encode(&mut face2sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert!(face1receiver.try_recv().is_ok());
+        // The looped copy made face 3 a downstream as well
+        assert!(face3receiver.try_recv().is_ok());
+        assert!(forwarder.tables().has_route(second));
+
+        assert!(Interest::new(second, false, [2; 4])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(face2receiver.try_recv().is_ok());
+        assert!(face3receiver.try_recv().is_err());
     }
 
-    fn send_whole_packet(&mut self, packet: &[u8]) {
-        if let Err(FaceError::Disconnected) = self.sender.write(&packet) {
-            self.should_close = true;
-            return;
+    #[test]
+    fn test_face_token_reuse() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let add_face = |forwarder: &mut Forwarder<_, _, _, _>| {
+            let (fs, _) = default_local_face();
+            let (_, fr) = default_local_face();
+            forwarder.add_face(fs, fr)
+        };
+        let face1 = add_face(&mut forwarder).unwrap();
+        let face2 = add_face(&mut forwarder).unwrap();
+        let face3 = add_face(&mut forwarder).unwrap();
+
+        // As if the node had been running for a long time
+        forwarder.faces.latest_face_token = u32::MAX - 1;
+        let last = add_face(&mut forwarder).unwrap();
+        assert_eq!(last, FaceToken(u32::MAX));
+        assert_eq!(forwarder.next_face_token(), Some(FaceToken(4)));
+
+        // The lowest free token is taken, and the faces can still be found
+        assert!(forwarder.remove_face(face2));
+        assert_eq!(forwarder.next_face_token(), Some(face2));
+        assert_eq!(add_face(&mut forwarder), Some(face2));
+        for face in [face1, face2, face3, last] {
+            assert!(forwarder.has_face(face));
+            assert!(forwarder.set_face_scope(face, Scope::NonLocal));
         }
-        if let Err(FaceError::Disconnected) = self.sender.flush() {
-            self.should_close = true;
-            return;
+        assert_eq!(forwarder.next_face_token(), Some(FaceToken(4)));
+    }
+
+    #[test]
+    fn test_face_scope() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder
+            .add_face_with_scope(fs1, fr1, Scope::Local)
+            .unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let (fs2, face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+        let face2 = forwarder
+            .add_face_with_scope(fs2, fr2, Scope::NonLocal)
+            .unwrap();
+        let mut face2receiver = default_buffered_receiver(face2receiver);
+        let (fs3, face3receiver) = default_local_face();
+        let (mut face3sender, fr3) = default_local_face();
+        let face3 = forwarder.add_face(fs3, fr3).unwrap();
+        let mut face3receiver = default_buffered_receiver(face3receiver);
+        assert_eq!(forwarder.face_scope(face1), Some(Scope::Local));
+        // Not trusted unless it is added as a local one
+        assert_eq!(forwarder.face_scope(face3), Some(Scope::NonLocal));
+        assert!(forwarder.set_face_scope(face3, Scope::Local));
+        assert_eq!(forwarder.face_scope(face3), Some(Scope::Local));
+        assert!(forwarder.set_face_scope(face3, Scope::NonLocal));
+
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(mgmt::LOCALHOST_COMPONENT),
+            NameComponent::generic(mgmt::LOCALHOP_COMPONENT),
+            NameComponent::generic(b"app"),
+            NameComponent::generic(b"x"),
+            NameComponent::generic(b"y"),
+        ];
+        for prefix in 0..3 {
+            let name = root.adding_components(&comps[prefix..prefix + 1]);
+            forwarder.register_name_prefix_for_forwarding(name, face2, 0);
         }
+
+        let mut send = |forwarder: &mut Forwarder<_, _, _, _>, from, name: Name<'_>, nonce| {
+            let sender = if from == face1 {
+                &mut face1sender
+            } else {
+                &mut face3sender
+            };
+            assert!(Interest::new(name, true, nonce).encode(sender).is_ok());
+            assert!(forwarder.try_forward_from_face(from).is_ok());
+        };
+        let pairs = [
+            [comps[0], comps[3]],
+            [comps[1], comps[3]],
+            [comps[2], comps[3]],
+            [comps[1], comps[4]],
+            [comps[2], comps[4]],
+        ];
+        let name = |pair: usize| root.adding_components(&pairs[pair]);
+
+        // Only /localhost stays on the host
+        send(&mut forwarder, face1, name(0), [1; 4]);
+        assert!(face2receiver.try_recv().is_err());
+        send(&mut forwarder, face1, name(1), [2; 4]);
+        assert!(face2receiver.try_recv().is_ok());
+        send(&mut forwarder, face1, name(2), [3; 4]);
+        assert!(face2receiver.try_recv().is_ok());
+
+        // And /localhop does not go further than one hop
+        send(&mut forwarder, face3, name(3), [4; 4]);
+        assert!(face2receiver.try_recv().is_err());
+        send(&mut forwarder, face3, name(4), [5; 4]);
+        assert!(face2receiver.try_recv().is_ok());
+
+        // The management only answers the local faces
+        forwarder.enable_management(|_: &Interest<'_>| true);
+        let comp = &[
+            NameComponent::generic(mgmt::LOCALHOST_COMPONENT),
+            NameComponent::generic(mgmt::NFD_COMPONENT),
+            NameComponent::generic(b"faces"),
+            NameComponent::generic(b"list"),
+        ];
+        send(&mut forwarder, face3, root.adding_components(comp), [6; 4]);
+        assert!(face3receiver.try_recv().is_err());
+        send(&mut forwarder, face1, root.adding_components(comp), [7; 4]);
+        assert!(face1receiver.try_recv().is_ok());
     }
 
-    fn send_modified_packet(
-        &mut self,
-        packet: &[u8],
-        ranges_and_replacements: &[(usize, usize, &[u8])],
-    ) {
-        let mut offset = 0;
+    #[test]
+    fn test_status_datasets() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, face1receiver) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder
+            .add_face_with_scope(fs1, fr1, Scope::Local)
+            .unwrap();
+        let mut face1receiver = default_buffered_receiver(face1receiver);
+        let (fs2, _face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+        let face2 = forwarder
+            .add_face_with_scope(fs2, fr2, Scope::Local)
+            .unwrap();
 
-        for &(start, end, replacement) in ranges_and_replacements {
-            debug_assert!(start >= offset && end >= start);
-            if start > offset {
-                if let Err(FaceError::Disconnected) = self.sender.write(&packet[offset..start]) {
-                    self.should_close = true;
-                    return;
+        // The datasets are not signed, so they are served whatever the validator says
+        forwarder.enable_management(|_: &Interest<'_>| false);
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"app")];
+        assert!(forwarder.register_name_prefix_for_forwarding(
+            root.adding_components(comp),
+            face2,
+            0
+        ));
+
+        // Returns the encoded entries and the FinalBlockId of the segment
+        let mut fetch = |comps: &[NameComponent<'_>]| {
+            let interest = Interest::new(root.adding_components(comps), true, [1; 4]);
+            assert!(interest.encode(&mut face1sender).is_ok());
+            assert!(forwarder.try_forward_from_face(face1).is_ok());
+            match face1receiver.try_recv() {
+                Ok((tlv, _)) => {
+                    let data = Data::try_decode_from_inner(tlv.val).unwrap();
+                    assert_eq!(data.name.component_count(), comps.len() + 2);
+                    let final_block_id = data.meta_info.unwrap().final_block_id.unwrap();
+                    let content = data.content.unwrap().bytes.to_vec();
+                    (content, final_block_id.component.bytes.to_vec())
                 }
+                Err(BufferedRecvError::NothingReceived) => (Vec::new(), Vec::new()),
+                Err(_) => panic!(),
             }
-            if let Err(FaceError::Disconnected) = self.sender.write(replacement) {
-                self.should_close = true;
-                return;
-            }
-            offset = end;
+        };
+        let localhost = NameComponent::generic(b"localhost");
+        let nfd = NameComponent::generic(b"nfd");
+
+        let (faces, final_block_id) = fetch(&[
+            localhost,
+            nfd,
+            NameComponent::generic(b"faces"),
+            NameComponent::generic(b"list"),
+        ]);
+        assert_eq!(final_block_id, [0]);
+        let mut face_ids = Vec::new();
+        let mut cursor = 0;
+        while cursor < faces.len() {
+            let (tlv, len) = TLV::try_decode(&faces[cursor..]).unwrap();
+            assert_eq!(tlv.typ.get(), 128);
+            let (face_id, _) = TLV::try_decode(tlv.val).unwrap();
+            assert_eq!(face_id.typ.get(), 105);
+            face_ids.push(u64::try_decode(face_id.val).unwrap().0);
+            cursor += len;
         }
+        assert_eq!(face_ids, [face1.0 as u64, face2.0 as u64]);
 
-        if offset < packet.len() {
-            if let Err(FaceError::Disconnected) = self.sender.write(&packet[offset..]) {
-                self.should_close = true;
-                return;
+        let (general, _) = fetch(&[
+            localhost,
+            nfd,
+            NameComponent::generic(b"status"),
+            NameComponent::generic(b"general"),
+        ]);
+        let mut fib_entries = None;
+        let mut cursor = 0;
+        while cursor < general.len() {
+            let (tlv, len) = TLV::try_decode(&general[cursor..]).unwrap();
+            if tlv.typ.get() == 132 {
+                fib_entries = Some(u64::try_decode(tlv.val).unwrap().0);
             }
+            cursor += len;
         }
+        assert_eq!(fib_entries, Some(1));
 
-        if let Err(FaceError::Disconnected) = self.sender.flush() {
-            self.should_close = true;
-            return;
-        }
+        // Only the stored version is served
+        let (faces, _) = fetch(&[
+            localhost,
+            nfd,
+            NameComponent::generic(b"faces"),
+            NameComponent::generic(b"list"),
+            NameComponent::new(NameComponent::TYPE_VERSION_NAME, &[1]).unwrap(),
+        ]);
+        assert!(faces.is_empty());
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::{
-        face::{
-            buffered::{default_buffered_receiver, BufferedFaceReceiver, BufferedRecvError},
-            local::default_local_face,
-        },
-        forwarder::{Forwarder, InertMetrics},
-        hash::Hasher,
-        io::Encode,
-        name::{Name, NameComponent},
-        packet::{Data, Interest, SignatureInfo, SignatureValue},
-        platform::sha::Sha256Hasher,
-        tables::reference::ReferenceTables,
-        tlv::{TlvDecode, TlvEncode},
-    };
 
     #[test]
-    fn test_basics() {
+    fn test_handle_nack() {
         let clock = crate::platform::clock::MonotonicClock::new();
-        let hasher = Sha256Hasher::new();
-        let metrics = InertMetrics {};
-        let tables = ReferenceTables::default();
-
-        // Create two pairs of local faces (each "local face" needs a tx pair and rx pair)
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
         let (fs1, face1receiver) = default_local_face();
         let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
         let (fs2, face2receiver) = default_local_face();
         let (mut face2sender, fr2) = default_local_face();
-
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        let (fs3, face3receiver) = default_local_face();
+        let (mut face3sender, fr3) = default_local_face();
+        let face3 = forwarder.add_face(fs3, fr3).unwrap();
         let mut face1receiver = default_buffered_receiver(face1receiver);
         let mut face2receiver = default_buffered_receiver(face2receiver);
+        let mut face3receiver = default_buffered_receiver(face3receiver);
 
-        let mut forwarder = Forwarder::new(clock, hasher, metrics, tables);
-
-        let face1 = forwarder.add_face(fs1, fr1).unwrap();
-        let face2 = forwarder.add_face(fs2, fr2).unwrap();
-
-        let name_prefix = Name::new();
+        let root = Name::new();
         let comp = &[NameComponent::generic(b"ndn")];
-        let name_prefix = name_prefix.adding_components(comp);
-
-        forwarder.register_name_prefix_for_forwarding(name_prefix, face2, 0);
-
-        let comp = &[NameComponent::generic(b"version")];
-        let name = name_prefix.adding_components(comp);
-        let nonce = [1, 2, 3, 4];
-        let interest = Interest::new(name, false, nonce);
+        let prefix = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"sensor")];
+        let name = prefix.adding_components(comp);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+        forwarder.register_name_prefix_for_forwarding(prefix, face3, 10);
 
-        assert!(interest.encode(&mut face1sender).is_ok());
+        let mut interest_packet = Vec::new();
+        assert!(Interest::new(name, false, [1; 4])
+            .encode(&mut interest_packet)
+            .is_ok());
+        let nack = |reason| LpPacket {
+            sequence: None,
+            frag_index: None,
+            frag_count: None,
+            nack: Some(Nack { reason }),
+            congestion_mark: None,
+            acks: &[],
+            tx_sequence: None,
+            fragment: Some(Fragment {
+                bytes: &interest_packet,
+            }),
+        };
+        assert!(face1sender.write(&interest_packet).is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        assert!(face2receiver.try_recv().is_ok());
 
-        match forwarder.try_forward_from_any_face(&[]) {
-            Ok(ff) => assert_eq!(ff, face1),
+        // The next best route is tried after the first one fails
+        assert!(nack(NackReason::NoRoute).encode(&mut face2sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        match face3receiver.try_recv() {
+            Ok((tlv, _)) => assert_eq!(tlv.typ.get(), Interest::TLV_TYPE),
             Err(_) => panic!(),
         }
+        assert!(matches!(
+            face1receiver.try_recv(),
+            Err(BufferedRecvError::NothingReceived)
+        ));
 
-        loop {
-            match face2receiver.try_recv() {
-                Ok((tlv, _)) => {
-                    assert_eq!(tlv.typ.get(), Interest::TLV_TYPE);
-                    let received_interest = Interest::try_decode_from_inner(tlv.val).unwrap();
-                    assert_eq!(
-                        interest.name.component_count(),
-                        received_interest.name.component_count()
-                    );
-                    assert!(Iterator::eq(
-                        interest.name.components(),
-                        received_interest.name.components()
-                    ));
-                    assert_eq!(received_interest.nonce.unwrap().bytes, nonce);
-
-                    let payload = b"v0.3";
-                    let signature_info = SignatureInfo::new_digest_sha256();
-                    let mut data = Data::new_unsigned(name, payload, signature_info);
-                    let mut hasher = Sha256Hasher::new();
-                    data.hash_signed_portion(&mut hasher);
-                    let digest = hasher.finalize_reset();
-                    data.signature_value = SignatureValue {
-                        bytes: digest.0.as_slice(),
-                    };
-
-                    assert!(data.encode(&mut face2sender).is_ok());
-                    break;
-                }
-                Err(BufferedRecvError::NothingReceived) => continue,
-                Err(_) => panic!(),
+        // Once all of them failed the consumer gets the Nack
+        assert!(nack(NackReason::Congestion)
+            .encode(&mut face3sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face3).is_ok());
+        match face1receiver.try_recv() {
+            Ok((tlv, _)) => {
+                assert_eq!(tlv.typ.get(), LpPacket::TLV_TYPE);
+                let packet = LpPacket::try_decode_from_inner(tlv.val).unwrap();
+                assert_eq!(packet.nack.unwrap().reason, NackReason::Congestion);
             }
-        }
-
-        match forwarder.try_forward_from_any_face(&[]) {
-            Ok(ff) => assert_eq!(ff, face2),
             Err(_) => panic!(),
         }
 
-        loop {
-            match face1receiver.try_recv() {
-                Ok((tlv, _)) => {
-                    assert_eq!(tlv.typ.get(), Data::TLV_TYPE);
-                    let received_data = Data::try_decode_from_inner(tlv.val).unwrap();
-                    assert_eq!(received_data.content.unwrap().bytes, b"v0.3");
+        // The entry is gone, so another Nack for it is ignored
+        assert!(nack(NackReason::NoRoute).encode(&mut face2sender).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        assert!(matches!(
+            face1receiver.try_recv(),
+            Err(BufferedRecvError::NothingReceived)
+        ));
+        assert!(matches!(
+            face3receiver.try_recv(),
+            Err(BufferedRecvError::NothingReceived)
+        ));
+    }
 
-                    let mut hasher = Sha256Hasher::new();
-                    received_data.hash_signed_portion(&mut hasher);
-                    let digest = hasher.finalize_reset();
-                    assert_eq!(received_data.signature_value.bytes, digest.0.as_slice());
-                    break;
-                }
-                Err(BufferedRecvError::NothingReceived) => continue,
-                Err(_) => panic!(),
+    #[test]
+    fn test_route_snapshot() {
+        let now = Rc::new(Cell::new(1000));
+        let new_forwarder = || {
+            let mut forwarder = Forwarder::new(
+                ManualClock { now: now.clone() },
+                Sha256Hasher::new(),
+                InertMetrics {},
+                ReferenceTables::default(),
+            );
+            let mut faces = Vec::new();
+            for _ in 0..2 {
+                let (fs, _) = default_local_face();
+                let (_, fr) = default_local_face();
+                faces.push(forwarder.add_face(fs, fr).unwrap());
             }
-        }
+            (forwarder, faces)
+        };
+        let fib = |forwarder: &Forwarder<_, _, _, ReferenceTables>| {
+            let mut entries = Vec::new();
+            forwarder.tables.list_routes(&mut |info| {
+                let hops: Vec<_> = info.next_hops.iter().map(|h| (h.face, h.cost)).collect();
+                entries.push((NameBuf::from(info.name), info.is_default, hops));
+            });
+            entries
+        };
 
-        forwarder.remove_face(face1);
-        forwarder.remove_face(face2);
+        let (mut forwarder, faces) = new_forwarder();
+        let root = Name::new();
+        let comps = &[NameComponent::generic(b"a"), NameComponent::generic(b"b")];
+        let a = root.adding_components(&comps[..1]);
+        let ab = a.adding_components(&comps[1..]);
+        let c = root.adding_components(&comps[1..]);
+        let route = Route {
+            face: faces[0],
+            origin: RouteOrigin::Static,
+            cost: 5,
+            flags: RouteFlags {
+                child_inherit: true,
+                capture: true,
+            },
+            expiry: None,
+        };
+        assert!(forwarder.add_route(a, route, Some(10000)));
+        let route = Route {
+            face: faces[1],
+            origin: RouteOrigin::App,
+            ..route
+        };
+        assert!(forwarder.add_route(ab, route, None));
+        assert!(forwarder.register_name_prefix_for_forwarding(c, faces[1], 7));
+        assert!(forwarder.register_default_route(faces[0], 3));
+        let snapshot = forwarder.export_routes();
+
+        // The same routes come back, with the time left of the expiring one
+        now.set(3000);
+        let (mut restored, _) = new_forwarder();
+        assert_eq!(restored.import_routes(&snapshot, Some), Some(4));
+        assert_eq!(restored.rib().routes(ab), forwarder.rib().routes(ab));
+        let expiry = restored.rib().routes(a)[0].expiry;
+        assert_eq!(
+            expiry,
+            Some(Timestamp {
+                ms_since_1970: 13000
+            })
+        );
+        assert!(fib(&restored) == fib(&forwarder));
+
+        // Without the routes of the faces that are gone
+        let (mut restored, _) = new_forwarder();
+        let only_first = |face| (face == faces[0]).then_some(face);
+        assert_eq!(restored.import_routes(&snapshot, only_first), Some(2));
+        assert!(restored.rib().routes(ab).is_empty());
+
+        let (mut restored, _) = new_forwarder();
+        let truncated = &snapshot[..snapshot.len() - 1];
+        assert_eq!(restored.import_routes(truncated, Some), None);
+        assert!(fib(&restored).is_empty());
+    }
+
+    #[test]
+    fn test_registration_policy() {
+        let clock = crate::platform::clock::MonotonicClock::new();
+        let mut forwarder = Forwarder::new(
+            clock,
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let (fs1, _face1receiver) = default_local_face();
+        let (_face1sender, fr1) = default_local_face();
+        let trusted = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _face2receiver) = default_local_face();
+        let (_face2sender, fr2) = default_local_face();
+        let tunnel = forwarder.add_face(fs2, fr2).unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"edge")];
+        let edge = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"guest")];
+        let guest = edge.adding_components(comp);
+        let comp = &[NameComponent::generic(b"phone")];
+        let phone = guest.adding_components(comp);
+
+        let mut restrictions = NamespaceRestrictions::new();
+        restrictions.allow(tunnel, guest);
+        forwarder.set_registration_policy(restrictions);
+
+        // The tunnel is kept under its namespace, while the other faces are not restricted
+        assert!(forwarder.register_name_prefix_for_forwarding(guest, tunnel, 0));
+        assert!(forwarder.register_name_prefix_for_forwarding(phone, tunnel, 0));
+        assert!(!forwarder.register_name_prefix_for_forwarding(edge, tunnel, 0));
+        assert!(forwarder.register_name_prefix_for_forwarding(edge, trusted, 0));
+        let route = Route {
+            face: tunnel,
+            origin: RouteOrigin::App,
+            cost: 0,
+            flags: RouteFlags::default(),
+            expiry: None,
+        };
+        assert!(!forwarder.add_route(root, route, None));
+        assert!(forwarder.add_route(phone, route, None));
+
+        forwarder.clear_registration_policy();
+        assert!(forwarder.register_name_prefix_for_forwarding(edge, tunnel, 0));
     }
 }
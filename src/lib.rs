@@ -13,12 +13,30 @@ pub mod tlv;
 
 pub mod name;
 
+pub mod nonce;
+
 pub mod packet;
 
+pub mod rdr;
+
+pub mod security;
+
 pub mod face;
 
 pub mod tables;
 
 pub mod forwarder;
 
+pub mod config;
+
+pub mod asynchronous;
+
+pub mod mgmt;
+
+pub mod diagnostics;
+
+pub mod stats;
+
+pub mod replay;
+
 pub mod platform;
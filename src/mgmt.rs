@@ -0,0 +1,1165 @@
+use alloc::{boxed::Box, collections::btree_map::BTreeMap, vec::Vec};
+
+use crate::{
+    face::{FaceReceiver, FaceSender},
+    forwarder::{FaceCounters, FaceToken, MAX_PACKET_SIZE},
+    hash::{Hasher, Sha256Digest},
+    io::{Decode, Encode, Write},
+    name::{Name, NameBuf, NameComponent},
+    packet::{
+        ApplicationParameters, DataBuilder, Interest, InterestSignatureInfo, InterestSignatureTime,
+        InterestSignatureValue, KeyLocator, SignatureType,
+    },
+    security::{keychain::KeyChain, Signer},
+    tables::{
+        rib::{RouteFlags, RouteOrigin},
+        strategy::NextHop,
+    },
+    tlv::{TlvDecode, TlvEncode, TypedBytes, TypedInteger, TLV},
+};
+
+// The management protocol of NFD, see https://redmine.named-data.net/projects/nfd/wiki/Management
+// A command is a signed interest named /localhost/nfd/<module>/<verb>/<ControlParameters>,
+//  possibly followed by the digest of its parameters, and it is answered with the data
+//  of the same name carrying a ControlResponse.
+// Only the signed interests with the signature after the application parameters are
+//  understood, which is what the current NDN libraries send.
+
+pub const LOCALHOST_COMPONENT: &[u8] = b"localhost";
+pub const LOCALHOP_COMPONENT: &[u8] = b"localhop";
+pub const NFD_COMPONENT: &[u8] = b"nfd";
+
+// Whether the name is under /localhost/nfd, where the commands go
+pub fn is_management_name(name: Name<'_>) -> bool {
+    let mut components = name.components();
+    components.next() == Some(NameComponent::generic(LOCALHOST_COMPONENT))
+        && components.next() == Some(NameComponent::generic(NFD_COMPONENT))
+}
+
+pub type FaceId = TypedInteger<105, u64>;
+pub type Uri<'a> = TypedBytes<'a, 114>;
+pub type Cost = TypedInteger<106, u64>;
+// For cs/erase, at most how many packets to erase, and how many were
+pub type Count = TypedInteger<132, u64>;
+pub type Flags = TypedInteger<108, u64>;
+pub type ExpirationPeriod = TypedInteger<109, u64>;
+pub type Origin = TypedInteger<111, u64>;
+
+impl Origin {
+    pub const APP: u64 = 0;
+    pub const STATIC: u64 = 255;
+}
+
+impl Flags {
+    pub const CHILD_INHERIT: u64 = 1;
+    pub const CAPTURE: u64 = 2;
+    // Not from NFD, only in the route snapshots for the default routes
+    pub const DEFAULT_ROUTE: u64 = 1 << 16;
+}
+
+// The origins of the routes that the commands can add, the other ones are not supported
+pub fn route_origin(origin: u64) -> Option<RouteOrigin> {
+    match origin {
+        Origin::APP => Some(RouteOrigin::App),
+        Origin::STATIC => Some(RouteOrigin::Static),
+        _ => None,
+    }
+}
+
+pub fn origin_code(origin: RouteOrigin) -> Option<u64> {
+    match origin {
+        RouteOrigin::App => Some(Origin::APP),
+        RouteOrigin::Static => Some(Origin::STATIC),
+        RouteOrigin::SelfLearned => None,
+    }
+}
+
+pub fn route_flags(flags: u64) -> RouteFlags {
+    RouteFlags {
+        child_inherit: flags & Flags::CHILD_INHERIT != 0,
+        capture: flags & Flags::CAPTURE != 0,
+    }
+}
+
+pub fn flags_code(flags: RouteFlags) -> u64 {
+    let mut code = 0;
+    if flags.child_inherit {
+        code |= Flags::CHILD_INHERIT;
+    }
+    if flags.capture {
+        code |= Flags::CAPTURE;
+    }
+    code
+}
+
+// The fields of the commands, each of which only uses some of them.
+// The fields that are not known are skipped.
+#[derive(Copy, Clone, Default)]
+pub struct ControlParameters<'a> {
+    pub name: Option<Name<'a>>,
+    pub face_id: Option<FaceId>,
+    // Where a face goes, e.g. udp4://192.0.2.1:6363
+    pub uri: Option<Uri<'a>>,
+    pub origin: Option<Origin>,
+    pub cost: Option<Cost>,
+    pub count: Option<Count>,
+    pub flags: Option<Flags>,
+    // In milliseconds
+    pub expiration_period: Option<ExpirationPeriod>,
+    pub strategy: Option<Strategy<'a>>,
+}
+
+impl<'a> TlvDecode<'a> for ControlParameters<'a> {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let mut parameters = ControlParameters::default();
+        let mut offset = 0;
+        while offset < inner_bytes.len() {
+            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            match tlv.typ.get() {
+                Name::TLV_TYPE => {
+                    parameters.name = Some(Name::try_decode(&inner_bytes[offset..]).ok()?.0)
+                }
+                FaceId::TLV_TYPE => {
+                    parameters.face_id = Some(FaceId {
+                        val: u64::try_decode(tlv.val).ok()?.0,
+                    })
+                }
+                Uri::TLV_TYPE => parameters.uri = Some(Uri { bytes: tlv.val }),
+                Origin::TLV_TYPE => {
+                    parameters.origin = Some(Origin {
+                        val: u64::try_decode(tlv.val).ok()?.0,
+                    })
+                }
+                Cost::TLV_TYPE => {
+                    parameters.cost = Some(Cost {
+                        val: u64::try_decode(tlv.val).ok()?.0,
+                    })
+                }
+                Count::TLV_TYPE => {
+                    parameters.count = Some(Count {
+                        val: u64::try_decode(tlv.val).ok()?.0,
+                    })
+                }
+                Flags::TLV_TYPE => {
+                    parameters.flags = Some(Flags {
+                        val: u64::try_decode(tlv.val).ok()?.0,
+                    })
+                }
+                ExpirationPeriod::TLV_TYPE => {
+                    parameters.expiration_period = Some(ExpirationPeriod {
+                        val: u64::try_decode(tlv.val).ok()?.0,
+                    })
+                }
+                Strategy::TLV_TYPE => {
+                    parameters.strategy = Some(Strategy::try_decode_from_inner(tlv.val)?)
+                }
+                _ => {}
+            }
+            offset += tlv_len;
+        }
+        Some(parameters)
+    }
+}
+
+impl<'a> TlvEncode for ControlParameters<'a> {
+    const TLV_TYPE: u32 = 104;
+
+    fn inner_length(&self) -> usize {
+        let mut len = self.name.encoded_length();
+        len += self.face_id.encoded_length();
+        len += self.uri.encoded_length();
+        len += self.origin.encoded_length();
+        len += self.cost.encoded_length();
+        len += self.count.encoded_length();
+        len += self.flags.encoded_length();
+        len += self.expiration_period.encoded_length();
+        len += self.strategy.encoded_length();
+        len
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.name.encode(writer)?;
+        self.face_id.encode(writer)?;
+        self.uri.encode(writer)?;
+        self.origin.encode(writer)?;
+        self.cost.encode(writer)?;
+        self.count.encode(writer)?;
+        self.flags.encode(writer)?;
+        self.expiration_period.encode(writer)?;
+        self.strategy.encode(writer)
+    }
+}
+
+// Wraps a name, as in NFD. In strategy-choice/set-parameters the components of the
+//  name are the parameters, each key~value, e.g. /probing-interval~30000.
+#[derive(Copy, Clone)]
+pub struct Strategy<'a> {
+    pub name: Name<'a>,
+}
+
+impl<'a> TlvDecode<'a> for Strategy<'a> {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let (name, _) = Name::try_decode(inner_bytes).ok()?;
+        Some(Self { name })
+    }
+}
+
+impl<'a> TlvEncode for Strategy<'a> {
+    const TLV_TYPE: u32 = 107;
+
+    fn inner_length(&self) -> usize {
+        self.name.encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.name.encode(writer)
+    }
+}
+
+pub type StatusCode = TypedInteger<102, u64>;
+pub type StatusText<'a> = TypedBytes<'a, 103>;
+
+impl StatusCode {
+    pub const OK: u64 = 200;
+    pub const BAD_REQUEST: u64 = 400;
+    pub const UNAUTHORIZED: u64 = 403;
+    pub const NOT_ACCEPTABLE: u64 = 406;
+    pub const FACE_NOT_FOUND: u64 = 410;
+    pub const NOT_IMPLEMENTED: u64 = 501;
+    pub const FACE_CREATION_FAILED: u64 = 504;
+}
+
+pub struct ControlResponse<'a> {
+    pub status_code: StatusCode,
+    pub status_text: StatusText<'a>,
+    // The parameters the command was executed with, if it succeeded
+    pub body: Option<ControlParameters<'a>>,
+}
+
+impl<'a> ControlResponse<'a> {
+    pub fn new(status_code: u64, status_text: &'a str) -> Self {
+        Self {
+            status_code: StatusCode { val: status_code },
+            status_text: StatusText {
+                bytes: status_text.as_bytes(),
+            },
+            body: None,
+        }
+    }
+
+    pub fn ok(body: ControlParameters<'a>) -> Self {
+        Self {
+            body: Some(body),
+            ..Self::new(StatusCode::OK, "OK")
+        }
+    }
+}
+
+impl<'a> TlvDecode<'a> for ControlResponse<'a> {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let mut status_code = None;
+        let mut status_text = None;
+        let mut body = None;
+        let mut offset = 0;
+        while offset < inner_bytes.len() {
+            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            match tlv.typ.get() {
+                StatusCode::TLV_TYPE => {
+                    status_code = Some(StatusCode {
+                        val: u64::try_decode(tlv.val).ok()?.0,
+                    })
+                }
+                StatusText::TLV_TYPE => status_text = Some(StatusText { bytes: tlv.val }),
+                ControlParameters::TLV_TYPE => {
+                    body = Some(ControlParameters::try_decode_from_inner(tlv.val)?)
+                }
+                _ => {}
+            }
+            offset += tlv_len;
+        }
+        Some(Self {
+            status_code: status_code?,
+            status_text: status_text?,
+            body,
+        })
+    }
+}
+
+impl<'a> TlvEncode for ControlResponse<'a> {
+    const TLV_TYPE: u32 = 101;
+
+    fn inner_length(&self) -> usize {
+        self.status_code.encoded_length()
+            + self.status_text.encoded_length()
+            + self.body.encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.status_code.encode(writer)?;
+        self.status_text.encode(writer)?;
+        self.body.encode(writer)
+    }
+}
+
+// A command taken apart, with the parameters decoded from its name
+pub struct ControlCommand<'a> {
+    pub module: &'a [u8],
+    pub verb: &'a [u8],
+    pub parameters: ControlParameters<'a>,
+}
+
+impl<'a> ControlCommand<'a> {
+    // Returns none if the name is not that of a command
+    pub fn parse(name: Name<'a>) -> Option<Self> {
+        if !is_management_name(name) {
+            return None;
+        }
+        let mut components = name.components().skip(2);
+        let module = components.next()?.bytes;
+        let verb = components.next()?.bytes;
+        let parameters = ControlParameters::try_decode(components.next()?.bytes)
+            .ok()?
+            .0;
+        Some(Self {
+            module,
+            verb,
+            parameters,
+        })
+    }
+}
+
+// Decides whether a command may be executed, e.g. by checking its signature
+pub trait CommandValidator {
+    fn validate(&mut self, command: &Interest<'_>) -> bool;
+}
+
+impl<F> CommandValidator for F
+where
+    F: FnMut(&Interest<'_>) -> bool,
+{
+    fn validate(&mut self, command: &Interest<'_>) -> bool {
+        self(command)
+    }
+}
+
+// Accepts the commands signed by the keys of the key chain. The signature time of a
+//  command must be later than that of the previous one signed by the same key, so that
+//  the commands cannot be replayed.
+pub struct KeyChainValidator {
+    key_chain: KeyChain,
+    last_signature_times: BTreeMap<NameBuf, u64>,
+}
+
+impl KeyChainValidator {
+    pub fn new(key_chain: KeyChain) -> Self {
+        Self {
+            key_chain,
+            last_signature_times: BTreeMap::new(),
+        }
+    }
+
+    pub fn key_chain(&self) -> &KeyChain {
+        &self.key_chain
+    }
+}
+
+impl CommandValidator for KeyChainValidator {
+    fn validate(&mut self, command: &Interest<'_>) -> bool {
+        let Some((_, Some((signature_info, _)))) = &command.application_parameters else {
+            return false;
+        };
+        let (Some(KeyLocator::Name(key_locator)), Some(signature_time)) =
+            (&signature_info.key_locator, signature_info.signature_time)
+        else {
+            return false;
+        };
+        let Some(mut verifier) = self.key_chain.verifier_for_key_locator(*key_locator) else {
+            return false;
+        };
+        if !command.verify_signature(&mut *verifier) {
+            return false;
+        }
+
+        let key = NameBuf::from(*key_locator);
+        if self
+            .last_signature_times
+            .get(&key)
+            .is_some_and(|last| signature_time.val <= *last)
+        {
+            return false;
+        }
+        self.last_signature_times.insert(key, signature_time.val);
+        true
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FaceCreationError {
+    // The scheme is not supported or the address is not canonical, e.g. a host name
+    UnsupportedUri,
+    // The remote end could not be reached
+    Failed,
+}
+
+pub type BoxedFace = (Box<dyn FaceSender>, Box<dyn FaceReceiver>);
+
+// Opens the faces that faces/create asks for, since only the platform knows how to
+//  reach a URI. Without a factory the forwarder does not support faces/create.
+pub trait FaceFactory {
+    // The face is added to the forwarder with the given token
+    fn create_face(&mut self, token: FaceToken, uri: &str) -> Result<BoxedFace, FaceCreationError>;
+
+    // Called once faces/destroy removed the face, whoever created it
+    fn face_destroyed(&mut self, _token: FaceToken) {}
+}
+
+// The status datasets are fetched with unsigned interests for /localhost/nfd/<module>/<verb>
+//  and answered with segmented data under /localhost/nfd/<module>/<verb>/<version>.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StatusDatasetKind {
+    // faces/list, a FaceStatus per face
+    Faces,
+    // fib/list, a FibEntry per prefix
+    Fib,
+    // cs/info, a single CsInfo
+    ContentStore,
+    // status/general, the ForwarderStatus
+    General,
+}
+
+impl StatusDatasetKind {
+    pub fn from_verb(module: &[u8], verb: &[u8]) -> Option<Self> {
+        match (module, verb) {
+            (b"faces", b"list") => Some(Self::Faces),
+            (b"fib", b"list") => Some(Self::Fib),
+            (b"cs", b"info") => Some(Self::ContentStore),
+            (b"status", b"general") => Some(Self::General),
+            _ => None,
+        }
+    }
+}
+
+pub type LocalUri<'a> = TypedBytes<'a, 129>;
+pub type FaceScope = TypedInteger<132, u64>;
+pub type FacePersistency = TypedInteger<133, u64>;
+pub type LinkType = TypedInteger<134, u64>;
+pub type NInInterests = TypedInteger<144, u64>;
+pub type NInData = TypedInteger<145, u64>;
+pub type NOutInterests = TypedInteger<146, u64>;
+pub type NOutData = TypedInteger<147, u64>;
+pub type NInBytes = TypedInteger<148, u64>;
+pub type NOutBytes = TypedInteger<149, u64>;
+pub type NInNacks = TypedInteger<151, u64>;
+pub type NOutNacks = TypedInteger<152, u64>;
+pub type NSatisfiedInterests = TypedInteger<153, u64>;
+pub type NUnsatisfiedInterests = TypedInteger<154, u64>;
+
+impl FaceScope {
+    pub const NON_LOCAL: u64 = 0;
+    pub const LOCAL: u64 = 1;
+}
+
+impl FacePersistency {
+    pub const PERSISTENT: u64 = 0;
+    pub const ON_DEMAND: u64 = 1;
+    pub const PERMANENT: u64 = 2;
+}
+
+impl LinkType {
+    pub const POINT_TO_POINT: u64 = 0;
+    pub const MULTI_ACCESS: u64 = 1;
+}
+
+// The packet counts in the order that both FaceStatus and ForwarderStatus use
+struct PacketCounters<'a>(&'a FaceCounters);
+
+impl<'a> Encode for PacketCounters<'a> {
+    fn encoded_length(&self) -> usize {
+        let counters = self.0;
+        let mut len = NInInterests {
+            val: counters.in_interests,
+        }
+        .encoded_length();
+        len += NInData {
+            val: counters.in_data,
+        }
+        .encoded_length();
+        len += NInNacks {
+            val: counters.in_nacks,
+        }
+        .encoded_length();
+        len += NOutInterests {
+            val: counters.out_interests,
+        }
+        .encoded_length();
+        len += NOutData {
+            val: counters.out_data,
+        }
+        .encoded_length();
+        len += NOutNacks {
+            val: counters.out_nacks,
+        }
+        .encoded_length();
+        len
+    }
+
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        let counters = self.0;
+        NInInterests {
+            val: counters.in_interests,
+        }
+        .encode(writer)?;
+        NInData {
+            val: counters.in_data,
+        }
+        .encode(writer)?;
+        NInNacks {
+            val: counters.in_nacks,
+        }
+        .encode(writer)?;
+        NOutInterests {
+            val: counters.out_interests,
+        }
+        .encode(writer)?;
+        NOutData {
+            val: counters.out_data,
+        }
+        .encode(writer)?;
+        NOutNacks {
+            val: counters.out_nacks,
+        }
+        .encode(writer)
+    }
+}
+
+// An entry of faces/list
+pub struct FaceStatus<'a> {
+    pub face_id: FaceId,
+    pub uri: Uri<'a>,
+    pub local_uri: LocalUri<'a>,
+    pub face_scope: FaceScope,
+    pub face_persistency: FacePersistency,
+    pub link_type: LinkType,
+    pub counters: FaceCounters,
+}
+
+impl<'a> TlvEncode for FaceStatus<'a> {
+    const TLV_TYPE: u32 = 128;
+
+    fn inner_length(&self) -> usize {
+        let mut len = self.face_id.encoded_length();
+        len += self.uri.encoded_length();
+        len += self.local_uri.encoded_length();
+        len += self.face_scope.encoded_length();
+        len += self.face_persistency.encoded_length();
+        len += self.link_type.encoded_length();
+        len += PacketCounters(&self.counters).encoded_length();
+        len += NInBytes {
+            val: self.counters.in_bytes,
+        }
+        .encoded_length();
+        len += NOutBytes {
+            val: self.counters.out_bytes,
+        }
+        .encoded_length();
+        len += Flags { val: 0 }.encoded_length();
+        len
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.face_id.encode(writer)?;
+        self.uri.encode(writer)?;
+        self.local_uri.encode(writer)?;
+        self.face_scope.encode(writer)?;
+        self.face_persistency.encode(writer)?;
+        self.link_type.encode(writer)?;
+        PacketCounters(&self.counters).encode(writer)?;
+        NInBytes {
+            val: self.counters.in_bytes,
+        }
+        .encode(writer)?;
+        NOutBytes {
+            val: self.counters.out_bytes,
+        }
+        .encode(writer)?;
+        Flags { val: 0 }.encode(writer)
+    }
+}
+
+// An entry of fib/list
+pub struct FibEntry<'a> {
+    pub name: Name<'a>,
+    pub next_hops: &'a [NextHop],
+}
+
+struct NextHopRecord(NextHop);
+
+impl NextHopRecord {
+    fn fields(&self) -> (FaceId, Cost) {
+        (
+            FaceId {
+                val: self.0.face.0 as u64,
+            },
+            Cost {
+                val: self.0.cost as u64,
+            },
+        )
+    }
+}
+
+impl TlvEncode for NextHopRecord {
+    const TLV_TYPE: u32 = 129;
+
+    fn inner_length(&self) -> usize {
+        self.fields().encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.fields().encode(writer)
+    }
+}
+
+impl<'a> TlvEncode for FibEntry<'a> {
+    const TLV_TYPE: u32 = 128;
+
+    fn inner_length(&self) -> usize {
+        let mut len = self.name.encoded_length();
+        for next_hop in self.next_hops {
+            len += NextHopRecord(*next_hop).encoded_length();
+        }
+        len
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.name.encode(writer)?;
+        for next_hop in self.next_hops {
+            NextHopRecord(*next_hop).encode(writer)?;
+        }
+        Ok(())
+    }
+}
+
+// An entry of rib/list, which the route snapshots are made of (see Forwarder::export_routes).
+// Only TLV and alloc, so it is not behind a feature.
+pub struct RibEntry<'a> {
+    pub name: Name<'a>,
+    pub routes: Vec<RouteRecord>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RouteRecord {
+    pub face_id: FaceId,
+    // None in the snapshots for the next hops registered in the FIB directly
+    pub origin: Option<Origin>,
+    pub cost: Cost,
+    pub flags: Flags,
+    // The time left, for the routes that expire
+    pub expiration_period: Option<ExpirationPeriod>,
+}
+
+impl<'a> TlvDecode<'a> for RibEntry<'a> {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let (name, mut offset) = Name::try_decode(inner_bytes).ok()?;
+        let mut routes = Vec::new();
+        while offset < inner_bytes.len() {
+            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            if tlv.typ.get() == RouteRecord::TLV_TYPE {
+                routes.push(RouteRecord::try_decode_from_inner(tlv.val)?);
+            }
+            offset += tlv_len;
+        }
+        Some(Self { name, routes })
+    }
+}
+
+impl<'a> TlvEncode for RibEntry<'a> {
+    const TLV_TYPE: u32 = 128;
+
+    fn inner_length(&self) -> usize {
+        let mut len = self.name.encoded_length();
+        for route in self.routes.iter() {
+            len += route.encoded_length();
+        }
+        len
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.name.encode(writer)?;
+        for route in self.routes.iter() {
+            route.encode(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> TlvDecode<'a> for RouteRecord {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let mut face_id = None;
+        let mut origin = None;
+        let mut cost = None;
+        let mut flags = None;
+        let mut expiration_period = None;
+        let mut offset = 0;
+        while offset < inner_bytes.len() {
+            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            let val = || u64::try_decode(tlv.val).ok().map(|(val, _)| val);
+            match tlv.typ.get() {
+                FaceId::TLV_TYPE => face_id = Some(FaceId { val: val()? }),
+                Origin::TLV_TYPE => origin = Some(Origin { val: val()? }),
+                Cost::TLV_TYPE => cost = Some(Cost { val: val()? }),
+                Flags::TLV_TYPE => flags = Some(Flags { val: val()? }),
+                ExpirationPeriod::TLV_TYPE => {
+                    expiration_period = Some(ExpirationPeriod { val: val()? })
+                }
+                _ => {}
+            }
+            offset += tlv_len;
+        }
+        Some(Self {
+            face_id: face_id?,
+            origin,
+            cost: cost?,
+            flags: flags?,
+            expiration_period,
+        })
+    }
+}
+
+impl TlvEncode for RouteRecord {
+    const TLV_TYPE: u32 = 129;
+
+    fn inner_length(&self) -> usize {
+        self.face_id.encoded_length()
+            + self.origin.encoded_length()
+            + self.cost.encoded_length()
+            + self.flags.encoded_length()
+            + self.expiration_period.encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.face_id.encode(writer)?;
+        self.origin.encode(writer)?;
+        self.cost.encode(writer)?;
+        self.flags.encode(writer)?;
+        self.expiration_period.encode(writer)
+    }
+}
+
+pub type Capacity = TypedInteger<131, u64>;
+pub type NCsEntries = TypedInteger<135, u64>;
+pub type NHits = TypedInteger<129, u64>;
+pub type NMisses = TypedInteger<130, u64>;
+
+// The content of cs/info
+pub struct CsInfo {
+    pub capacity: Capacity,
+    pub entries: NCsEntries,
+    pub hits: NHits,
+    pub misses: NMisses,
+}
+
+impl CsInfo {
+    // The cache always admits and serves the data
+    const FLAGS: Flags = Flags { val: 0b11 };
+}
+
+impl TlvEncode for CsInfo {
+    const TLV_TYPE: u32 = 128;
+
+    fn inner_length(&self) -> usize {
+        self.capacity.encoded_length()
+            + Self::FLAGS.encoded_length()
+            + self.entries.encoded_length()
+            + self.hits.encoded_length()
+            + self.misses.encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.capacity.encode(writer)?;
+        Self::FLAGS.encode(writer)?;
+        self.entries.encode(writer)?;
+        self.hits.encode(writer)?;
+        self.misses.encode(writer)
+    }
+}
+
+pub type NfdVersion<'a> = TypedBytes<'a, 128>;
+pub type StartTimestamp = TypedInteger<129, u64>;
+pub type CurrentTimestamp = TypedInteger<130, u64>;
+pub type NNameTreeEntries = TypedInteger<131, u64>;
+pub type NFibEntries = TypedInteger<132, u64>;
+pub type NPitEntries = TypedInteger<133, u64>;
+pub type NMeasurementsEntries = TypedInteger<134, u64>;
+
+// The content of status/general, which is not wrapped in an outer TLV.
+// The counters are the sums over the faces.
+pub struct ForwarderStatus<'a> {
+    pub version: NfdVersion<'a>,
+    // In milliseconds since 1970
+    pub start_timestamp: StartTimestamp,
+    pub current_timestamp: CurrentTimestamp,
+    pub fib_entries: NFibEntries,
+    pub pit_entries: NPitEntries,
+    pub cs_entries: NCsEntries,
+    pub counters: FaceCounters,
+}
+
+impl<'a> ForwarderStatus<'a> {
+    // There is neither a name tree nor the measurements, which are reported as empty
+    fn fields(
+        &self,
+    ) -> (
+        NNameTreeEntries,
+        NMeasurementsEntries,
+        NSatisfiedInterests,
+        NUnsatisfiedInterests,
+    ) {
+        (
+            NNameTreeEntries { val: 0 },
+            NMeasurementsEntries { val: 0 },
+            NSatisfiedInterests {
+                val: self.counters.satisfied_interests,
+            },
+            NUnsatisfiedInterests {
+                val: self.counters.unsatisfied_interests,
+            },
+        )
+    }
+}
+
+impl<'a> Encode for ForwarderStatus<'a> {
+    fn encoded_length(&self) -> usize {
+        let (name_tree, measurements, satisfied, unsatisfied) = self.fields();
+        let mut len = self.version.encoded_length();
+        len += self.start_timestamp.encoded_length();
+        len += self.current_timestamp.encoded_length();
+        len += name_tree.encoded_length();
+        len += self.fib_entries.encoded_length();
+        len += self.pit_entries.encoded_length();
+        len += measurements.encoded_length();
+        len += self.cs_entries.encoded_length();
+        len += PacketCounters(&self.counters).encoded_length();
+        len += satisfied.encoded_length();
+        len += unsatisfied.encoded_length();
+        len
+    }
+
+    fn encode<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        let (name_tree, measurements, satisfied, unsatisfied) = self.fields();
+        self.version.encode(writer)?;
+        self.start_timestamp.encode(writer)?;
+        self.current_timestamp.encode(writer)?;
+        name_tree.encode(writer)?;
+        self.fib_entries.encode(writer)?;
+        self.pit_entries.encode(writer)?;
+        measurements.encode(writer)?;
+        self.cs_entries.encode(writer)?;
+        PacketCounters(&self.counters).encode(writer)?;
+        satisfied.encode(writer)?;
+        unsatisfied.encode(writer)
+    }
+}
+
+// How much of a dataset goes into a segment, leaving room for the name and the signature
+pub const DATASET_SEGMENT_SIZE: usize = MAX_PACKET_SIZE / 2;
+
+// How long the segments stay fresh in the caches of the consumers
+pub const DATASET_FRESHNESS_MS: u64 = 1000;
+
+// The segments of a generated dataset, kept so that the later segments of the same
+//  version can be served while the state changes
+pub struct StatusDataset {
+    // With the version
+    prefix: NameBuf,
+    version: u64,
+    segments: Vec<Vec<u8>>,
+}
+
+impl StatusDataset {
+    pub fn new<H: Hasher<Digest = Sha256Digest>>(
+        prefix: Name<'_>,
+        version: u64,
+        content: &[u8],
+        hasher: &mut H,
+    ) -> Self {
+        let mut version_bytes = Vec::new();
+        let _ = version.encode(&mut version_bytes);
+        let version_comp = NameComponent::new(NameComponent::TYPE_VERSION_NAME, &version_bytes);
+        let prefix = NameBuf::from(prefix).pushing(version_comp.unwrap());
+
+        // An empty dataset still has a segment
+        let mut chunks: Vec<&[u8]> = content.chunks(DATASET_SEGMENT_SIZE).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        let mut last_bytes = Vec::new();
+        let _ = (chunks.len() as u64 - 1).encode(&mut last_bytes);
+        let last = NameComponent::new(NameComponent::TYPE_SEGMENT, &last_bytes).unwrap();
+
+        let mut segments = Vec::with_capacity(chunks.len());
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut segment_bytes = Vec::new();
+            let _ = (index as u64).encode(&mut segment_bytes);
+            let comp = &[NameComponent::new(NameComponent::TYPE_SEGMENT, &segment_bytes).unwrap()];
+            let prefix = prefix.as_name();
+            segments.push(
+                DataBuilder::new(prefix.adding_components(comp))
+                    .content(chunk)
+                    .freshness_period(DATASET_FRESHNESS_MS)
+                    .final_block_id(last)
+                    .digest_sign(hasher),
+            );
+        }
+        Self {
+            prefix,
+            version,
+            segments,
+        }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn version_prefix(&self) -> Name<'_> {
+        self.prefix.as_name()
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    // The encoded segment that the name asks for, which is either the versioned prefix
+    //  for the first segment or the name of a segment
+    pub fn segment(&self, name: Name<'_>) -> Option<&[u8]> {
+        let prefix = self.prefix.as_name();
+        if name == prefix {
+            return self.segments.first().map(|segment| &segment[..]);
+        }
+        let last = name.components().last()?;
+        if last.typ.get() != NameComponent::TYPE_SEGMENT
+            || name.dropping_last_component()? != prefix
+        {
+            return None;
+        }
+        let index = usize::try_from(u64::try_decode(last.bytes).ok()?.0).ok()?;
+        self.segments.get(index).map(|segment| &segment[..])
+    }
+}
+
+// Client side: the encoded command, e.g. to register a prefix with a forwarder.
+// The signature time (in milliseconds since 1970) has to grow from one command to the next.
+pub fn signed_command<S, H>(
+    module: &[u8],
+    verb: &[u8],
+    parameters: &ControlParameters<'_>,
+    nonce: [u8; 4],
+    signature_time: u64,
+    signer: &mut S,
+    hasher: &mut H,
+) -> Vec<u8>
+where
+    S: Signer + ?Sized,
+    H: Hasher<Digest = Sha256Digest>,
+{
+    let mut encoded_parameters = Vec::with_capacity(parameters.encoded_length());
+    let _ = parameters.encode(&mut encoded_parameters);
+    let components = [
+        NameComponent::generic(LOCALHOST_COMPONENT),
+        NameComponent::generic(NFD_COMPONENT),
+        NameComponent::generic(module),
+        NameComponent::generic(verb),
+        NameComponent::generic(&encoded_parameters),
+    ];
+    let name = Name::with_components(&components);
+
+    // The key locator borrows the signer, which has to be mutable to sign
+    let key_locator = signer.key_locator().map(NameBuf::from);
+    let signature_type = signer.signature_type();
+    let signature_info = || InterestSignatureInfo {
+        signature_type: SignatureType {
+            val: signature_type,
+        },
+        key_locator: key_locator
+            .as_ref()
+            .map(|name| KeyLocator::Name(name.as_name())),
+        nonce: None,
+        signature_time: Some(InterestSignatureTime {
+            val: signature_time,
+        }),
+        signature_seq_num: None,
+    };
+    let application_parameters = ApplicationParameters { bytes: &[] };
+    let mut interest = Interest::new(name, false, nonce);
+    interest.application_parameters = Some((
+        application_parameters,
+        Some((signature_info(), InterestSignatureValue { bytes: &[] })),
+    ));
+    let signature = interest.sign_signed_portion(signer);
+
+    // The name ends with the digest of everything from the application parameters on
+    let signed_parameters = Some((
+        application_parameters,
+        Some((
+            signature_info(),
+            InterestSignatureValue { bytes: &signature },
+        )),
+    ));
+    let mut parameters_bytes = Vec::with_capacity(signed_parameters.encoded_length());
+    let _ = signed_parameters.encode(&mut parameters_bytes);
+    hasher.reset();
+    hasher.update(&parameters_bytes);
+    let digest = hasher.finalize_reset();
+
+    let digest_component = [NameComponent::parameter_sha256(&digest.0)];
+    let name = name.adding_components(&digest_component);
+    let mut interest = Interest::new(name, false, nonce);
+    interest.application_parameters = signed_parameters;
+    let mut bytes = Vec::with_capacity(interest.encoded_length());
+    let _ = interest.encode(&mut bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        io::{Decode, Encode},
+        mgmt::{
+            signed_command, CommandValidator, ControlCommand, ControlParameters, ControlResponse,
+            Cost, FaceId, KeyChainValidator, StatusCode, StatusDataset, DATASET_SEGMENT_SIZE,
+        },
+        name::{Name, NameComponent},
+        packet::{Data, Interest},
+        platform::sha::Sha256Hasher,
+        security::keychain::{HmacSha256Key, KeyChain},
+        tlv::{TlvDecode, TLV},
+    };
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_signed_command() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"alice")];
+        let alice = root.adding_components(comp);
+        let key_name = KeyChain::key_name(alice, b"1");
+        let mut key_chain = KeyChain::new();
+        key_chain.add_identity(alice).unwrap();
+        let key = HmacSha256Key::new(b"secret", Sha256Hasher::new());
+        key_chain.add_key(alice, key_name.as_name(), key).unwrap();
+
+        let comp = &[NameComponent::generic(b"app")];
+        let prefix = root.adding_components(comp);
+        let parameters = ControlParameters {
+            name: Some(prefix),
+            face_id: Some(FaceId { val: 3 }),
+            cost: Some(Cost { val: 10 }),
+            ..ControlParameters::default()
+        };
+        let mut hasher = Sha256Hasher::new();
+        let mut signer = key_chain.signer().unwrap();
+        let first = signed_command(
+            b"rib",
+            b"register",
+            &parameters,
+            [1; 4],
+            1000,
+            &mut *signer,
+            &mut hasher,
+        );
+        let second = signed_command(
+            b"rib",
+            b"register",
+            &parameters,
+            [2; 4],
+            1001,
+            &mut *signer,
+            &mut hasher,
+        );
+        drop(signer);
+
+        let (interest, _) = Interest::try_decode(&first).unwrap();
+        let command = ControlCommand::parse(interest.name).unwrap();
+        assert_eq!(command.module, b"rib");
+        assert_eq!(command.verb, b"register");
+        assert!(command.parameters.name.unwrap() == prefix);
+        assert_eq!(command.parameters.face_id, Some(FaceId { val: 3 }));
+        assert_eq!(command.parameters.cost, Some(Cost { val: 10 }));
+        assert_eq!(command.parameters.origin, None);
+        assert!(ControlCommand::parse(prefix).is_none());
+
+        // The commands cannot be replayed, nor signed with another key
+        let mut validator = KeyChainValidator::new(key_chain);
+        assert!(validator.validate(&interest));
+        assert!(!validator.validate(&interest));
+        let (interest, _) = Interest::try_decode(&second).unwrap();
+        assert!(validator.validate(&interest));
+
+        let mut other_chain = KeyChain::new();
+        other_chain.add_identity(alice).unwrap();
+        let key = HmacSha256Key::new(b"other secret", Sha256Hasher::new());
+        other_chain.add_key(alice, key_name.as_name(), key).unwrap();
+        let mut signer = other_chain.signer().unwrap();
+        let forged = signed_command(
+            b"rib",
+            b"register",
+            &parameters,
+            [3; 4],
+            2000,
+            &mut *signer,
+            &mut hasher,
+        );
+        let (interest, _) = Interest::try_decode(&forged).unwrap();
+        assert!(!validator.validate(&interest));
+
+        let response = ControlResponse::ok(parameters);
+        let mut encoded = Vec::new();
+        response.encode(&mut encoded).unwrap();
+        let (decoded, _) = ControlResponse::try_decode(&encoded).unwrap();
+        assert_eq!(
+            decoded.status_code,
+            StatusCode {
+                val: StatusCode::OK
+            }
+        );
+        assert_eq!(decoded.status_text.bytes, b"OK");
+        assert_eq!(decoded.body.unwrap().cost, Some(Cost { val: 10 }));
+    }
+
+    #[test]
+    fn test_status_dataset_segments() {
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(b"localhost"),
+            NameComponent::generic(b"nfd"),
+            NameComponent::generic(b"fib"),
+            NameComponent::generic(b"list"),
+        ];
+        let prefix = root.adding_components(comps);
+        let content = [7u8; DATASET_SEGMENT_SIZE + 1];
+        let dataset = StatusDataset::new(prefix, 5, &content, &mut Sha256Hasher::new());
+        assert_eq!(dataset.version(), 5);
+        assert_eq!(dataset.segment_count(), 2);
+
+        let version_prefix = dataset.version_prefix();
+        let first = dataset.segment(version_prefix).unwrap();
+        let (tlv, _) = TLV::try_decode(first).unwrap();
+        let data = Data::try_decode_from_inner(tlv.val).unwrap();
+        assert_eq!(data.content.unwrap().bytes.len(), DATASET_SEGMENT_SIZE);
+        let final_block_id = data.meta_info.unwrap().final_block_id.unwrap();
+        assert_eq!(final_block_id.component.bytes, [1]);
+
+        let last = NameComponent::new(NameComponent::TYPE_SEGMENT, &[1]).unwrap();
+        let last_name = data.name.dropping_last_component().unwrap();
+        let last_comps = &[last];
+        let last_name = last_name.adding_components(last_comps);
+        let (tlv, _) = TLV::try_decode(dataset.segment(last_name).unwrap()).unwrap();
+        let data = Data::try_decode_from_inner(tlv.val).unwrap();
+        assert_eq!(data.content.unwrap().bytes, [7]);
+
+        let missing = NameComponent::new(NameComponent::TYPE_SEGMENT, &[2]).unwrap();
+        let missing_comps = &[missing];
+        let missing_name = version_prefix.adding_components(missing_comps);
+        assert!(dataset.segment(missing_name).is_none());
+        assert!(dataset.segment(prefix).is_none());
+    }
+}
@@ -1,5 +1,7 @@
 use core::num::NonZeroU16;
 
+use alloc::vec::Vec;
+
 use crate::{
     io::{Decode, Encode, Write},
     tlv::{TlvDecode, TlvEncode, TLV},
@@ -114,6 +116,7 @@ impl<'a> Name<'a> {
                 component_bytes,
                 component_count,
                 original_count,
+                cache,
             } => {
                 if component_count == 1 {
                     return Some(Name {
@@ -125,6 +128,7 @@ impl<'a> Name<'a> {
                         component_bytes,
                         component_count: component_count - 1,
                         original_count,
+                        cache,
                     },
                 })
             }
@@ -159,26 +163,163 @@ impl<'a> Name<'a> {
     pub fn components(&self) -> impl Iterator<Item = NameComponent<'a>> {
         let mut innermost_bytes = None;
         let mut innermost_count = 0;
+        let mut innermost_cache = ComponentCache::default();
         let mut free_components = 0;
 
         self.compute_iter(
             &mut innermost_bytes,
             &mut innermost_count,
+            &mut innermost_cache,
             &mut free_components,
         );
 
         NameComponentIterator {
             innermost_bytes: innermost_bytes.map(|b| (b, 0)),
+            innermost_index: 0,
             innermost_remaining: innermost_count,
+            innermost_cache,
             free_components,
             name: *self,
         }
     }
 
+    // The first "count" components (or the whole name if it has fewer) as a view
+    //  into the same bytes, without re-encoding anything.
+    pub fn prefix(&self, count: usize) -> Self {
+        match self.inner {
+            NameInner::Empty => *self,
+            NameInner::Buffer {
+                component_bytes,
+                component_count,
+                original_count,
+                cache,
+            } => {
+                if count == 0 {
+                    return Name::new();
+                }
+                Name {
+                    inner: NameInner::Buffer {
+                        component_bytes,
+                        component_count: count.min(component_count),
+                        original_count,
+                        cache,
+                    },
+                }
+            }
+            NameInner::Components {
+                original,
+                components,
+                remaining_count,
+            } => {
+                let original_count = original.component_count();
+                if count <= original_count {
+                    original.prefix(count)
+                } else {
+                    Name {
+                        inner: NameInner::Components {
+                            original,
+                            components,
+                            remaining_count: (count - original_count).min(remaining_count),
+                        },
+                    }
+                }
+            }
+        }
+    }
+
+    // The last "count" components as a view into the same bytes.
+    // Returns None if the name has fewer components, or if the name was extended
+    //  with adding_components and the suffix would reach into the original name,
+    //  since such a suffix cannot be expressed without copying.
+    pub fn suffix(&self, count: usize) -> Option<Self> {
+        let component_count = self.component_count();
+        if count > component_count {
+            return None;
+        }
+        if count == 0 {
+            return Some(Name::new());
+        }
+        match self.inner {
+            NameInner::Empty => None,
+            NameInner::Buffer {
+                component_bytes,
+                component_count,
+                original_count,
+                cache,
+            } => {
+                let first = component_count - count;
+                let start = cache.prefix_length(component_bytes, first);
+                let end = if component_count == original_count {
+                    component_bytes.len()
+                } else {
+                    cache.prefix_length(component_bytes, component_count)
+                };
+                Some(Name {
+                    inner: NameInner::Buffer {
+                        component_bytes: &component_bytes[start..end],
+                        component_count: count,
+                        original_count: count,
+                        cache: cache.shifted(first, start),
+                    },
+                })
+            }
+            NameInner::Components {
+                components,
+                remaining_count,
+                ..
+            } => {
+                if count > remaining_count {
+                    return None;
+                }
+                Some(Name::with_components(
+                    &components[remaining_count - count..remaining_count],
+                ))
+            }
+        }
+    }
+
+    // The "count" components starting at "start", see suffix for when it returns None
+    pub fn subname(&self, start: usize, count: usize) -> Option<Self> {
+        if start + count > self.component_count() {
+            return None;
+        }
+        self.prefix(start + count).suffix(count)
+    }
+
+    // The component at "index", which is a direct lookup for the first components
+    //  of a decoded name
+    pub fn component(&self, index: usize) -> Option<NameComponent<'a>> {
+        if let NameInner::Buffer {
+            component_bytes,
+            component_count,
+            cache,
+            ..
+        } = self.inner
+        {
+            if index < component_count {
+                if let Some((component, _)) = cache.get(component_bytes, index) {
+                    return Some(component);
+                }
+            }
+        }
+        self.components().nth(index)
+    }
+
+    // Checks if all of the components of this name are the leading components of the other.
+    pub fn is_prefix_of(&self, other: Name<'_>) -> bool {
+        if self.component_count() > other.component_count() {
+            return false;
+        }
+        self.components()
+            .zip(other.components())
+            .all(|(a, b)| a == b)
+    }
+
     fn compute_iter(
         &self,
         innermost_bytes: &mut Option<&'a [u8]>,
         innermost_count: &mut usize,
+        innermost_cache: &mut ComponentCache,
         free_components: &mut usize,
     ) {
         match self.inner {
@@ -186,10 +327,12 @@ impl<'a> Name<'a> {
             NameInner::Buffer {
                 component_bytes,
                 component_count,
+                cache,
                 ..
             } => {
                 *innermost_count = component_count;
                 *innermost_bytes = Some(&component_bytes);
+                *innermost_cache = cache;
             }
             NameInner::Components {
                 original,
@@ -197,12 +340,26 @@ impl<'a> Name<'a> {
                 ..
             } => {
                 *free_components += remaining_count;
-                original.compute_iter(innermost_bytes, innermost_count, free_components)
+                original.compute_iter(
+                    innermost_bytes,
+                    innermost_count,
+                    innermost_cache,
+                    free_components,
+                )
             }
         }
     }
 }
 
+impl<'a, 'b> PartialEq<Name<'b>> for Name<'a> {
+    fn eq(&self, other: &Name<'b>) -> bool {
+        self.component_count() == other.component_count()
+            && Iterator::eq(self.components(), other.components())
+    }
+}
+
+impl<'a> Eq for Name<'a> {}
+
 impl<'a> TlvEncode for Name<'a> {
     const TLV_TYPE: u32 = 7;
 
@@ -213,22 +370,13 @@ impl<'a> TlvEncode for Name<'a> {
                 component_bytes,
                 component_count,
                 original_count,
+                cache,
             } => {
                 if component_count == original_count {
                     component_bytes.len()
                 } else {
                     // Must only take component_count components
-                    let mut offset = 0;
-                    let mut cc = 0;
-                    while cc < component_count {
-                        let nc_len = match TLV::try_decode(&component_bytes[offset..]) {
-                            Ok((_, nc_len)) => nc_len,
-                            Err(_) => unreachable!(), // otherwise would not have been created
-                        };
-                        cc += 1;
-                        offset += nc_len;
-                    }
-                    offset
+                    cache.prefix_length(component_bytes, component_count)
                 }
             }
             NameInner::Components {
@@ -256,21 +404,13 @@ impl<'a> TlvEncode for Name<'a> {
                 component_bytes,
                 component_count,
                 original_count,
+                cache,
             } => {
                 if component_count == original_count {
                     // Can copy unmodified buffer
                     writer.write(component_bytes)
                 } else {
-                    let mut offset = 0;
-                    let mut cc = 0;
-                    while cc < component_count {
-                        let nc_len = match TLV::try_decode(&component_bytes[offset..]) {
-                            Ok((_, nc_len)) => nc_len,
-                            Err(_) => unreachable!(), // otherwise would not have been created
-                        };
-                        cc += 1;
-                        offset += nc_len;
-                    }
+                    let offset = cache.prefix_length(component_bytes, component_count);
                     writer.write(&component_bytes[0..offset])
                 }
             }
@@ -297,9 +437,11 @@ impl<'a> TlvDecode<'a> for Name<'a> {
     fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Name<'a>> {
         let mut component_count = 0;
         let mut offset = 0;
+        let mut cache = ComponentCache::default();
         while offset < inner_bytes.len() {
             let (nc_tlv, nc_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
-            let _: NonZeroU16 = nc_tlv.typ.try_into().ok()?;
+            let typ: NonZeroU16 = nc_tlv.typ.try_into().ok()?;
+            cache.push(typ, offset + nc_len - nc_tlv.val.len(), offset + nc_len);
             component_count += 1;
             offset += nc_len;
         }
@@ -315,6 +457,7 @@ impl<'a> TlvDecode<'a> for Name<'a> {
                 component_bytes: inner_bytes,
                 component_count,
                 original_count: component_count,
+                cache,
             }
         };
 
@@ -322,6 +465,60 @@ impl<'a> TlvDecode<'a> for Name<'a> {
     }
 }
 
+// An owned name that can outlive the buffer it was decoded from.
+// It keeps the encoded components (i.e. the value of the Name TLV), so that
+//  a borrowed Name can always be produced from it without allocations.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct NameBuf {
+    component_bytes: Vec<u8>,
+}
+
+impl NameBuf {
+    pub fn new() -> Self {
+        Self {
+            component_bytes: Vec::new(),
+        }
+    }
+
+    pub fn as_name(&self) -> Name<'_> {
+        // Would not have been created otherwise
+        Name::try_decode_from_inner(&self.component_bytes).unwrap()
+    }
+
+    pub fn component_count(&self) -> usize {
+        self.as_name().component_count()
+    }
+
+    pub fn push(&mut self, component: NameComponent<'_>) {
+        let _ = component.encode(&mut self.component_bytes);
+    }
+
+    pub fn pushing(mut self, component: NameComponent<'_>) -> Self {
+        self.push(component);
+        self
+    }
+}
+
+impl<'a> From<Name<'a>> for NameBuf {
+    fn from(name: Name<'a>) -> Self {
+        let mut component_bytes = Vec::with_capacity(name.inner_length());
+        let _ = name.encode_inner(&mut component_bytes);
+        Self { component_bytes }
+    }
+}
+
+impl TlvEncode for NameBuf {
+    const TLV_TYPE: u32 = Name::TLV_TYPE;
+
+    fn inner_length(&self) -> usize {
+        self.component_bytes.len()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        writer.write(&self.component_bytes)
+    }
+}
+
 #[derive(Copy, Clone)]
 enum NameInner<'a> {
     Empty,
@@ -329,6 +526,7 @@ enum NameInner<'a> {
         component_bytes: &'a [u8],
         component_count: usize,
         original_count: usize,
+        cache: ComponentCache,
     },
     Components {
         original: &'a Name<'a>,
@@ -337,9 +535,101 @@ enum NameInner<'a> {
     },
 }
 
+// The positions of the first few components within the encoded bytes of a name.
+// They are computed once when the name is decoded, so that iterating over the
+//  components, or measuring and encoding a prefix of the name (which the tables
+//  do for every packet), is a walk over this array rather than repeated parsing
+//  of the component TLVs. Components beyond the cached ones are parsed as before.
+const CACHED_COMPONENTS: usize = 8;
+
+#[derive(Copy, Clone, Default)]
+struct CachedComponent {
+    typ: u16,
+    value_start: u16,
+    end: u16,
+}
+
+#[derive(Copy, Clone, Default)]
+struct ComponentCache {
+    len: u8,
+    components: [CachedComponent; CACHED_COMPONENTS],
+}
+
+impl ComponentCache {
+    // Called for every component in order while decoding
+    fn push(&mut self, typ: NonZeroU16, value_start: usize, end: usize) {
+        let len = self.len as usize;
+        if len == CACHED_COMPONENTS {
+            return;
+        }
+        // Names in huge buffers are not cached
+        if let Ok(end) = end.try_into() {
+            self.components[len] = CachedComponent {
+                typ: typ.get(),
+                value_start: value_start as u16,
+                end,
+            };
+            self.len += 1;
+        }
+    }
+
+    fn get<'a>(
+        &self,
+        component_bytes: &'a [u8],
+        index: usize,
+    ) -> Option<(NameComponent<'a>, usize)> {
+        if index >= self.len as usize {
+            return None;
+        }
+        let cached = &self.components[index];
+        let component = NameComponent {
+            // Would not have been cached otherwise
+            typ: NonZeroU16::new(cached.typ).unwrap(),
+            bytes: &component_bytes[cached.value_start as usize..cached.end as usize],
+        };
+        Some((component, cached.end as usize))
+    }
+
+    // The length of the first "count" components
+    fn prefix_length(&self, component_bytes: &[u8], count: usize) -> usize {
+        if count == 0 {
+            return 0;
+        }
+        let cached = (self.len as usize).min(count);
+        let mut offset = match cached {
+            0 => 0,
+            cached => self.components[cached - 1].end as usize,
+        };
+        for _ in cached..count {
+            let nc_len = match TLV::try_decode(&component_bytes[offset..]) {
+                Ok((_, nc_len)) => nc_len,
+                Err(_) => unreachable!(), // otherwise would not have been created
+            };
+            offset += nc_len;
+        }
+        offset
+    }
+
+    // The cache of the components starting at "index", which start at "offset" in the bytes
+    fn shifted(&self, index: usize, offset: usize) -> Self {
+        let mut shifted = Self::default();
+        for cached in self.components[..self.len as usize].iter().skip(index) {
+            shifted.components[shifted.len as usize] = CachedComponent {
+                typ: cached.typ,
+                value_start: cached.value_start - offset as u16,
+                end: cached.end - offset as u16,
+            };
+            shifted.len += 1;
+        }
+        shifted
+    }
+}
+
 struct NameComponentIterator<'a> {
     innermost_bytes: Option<(&'a [u8], usize)>,
+    innermost_index: usize,
     innermost_remaining: usize,
+    innermost_cache: ComponentCache,
     free_components: usize,
     name: Name<'a>,
 }
@@ -360,9 +650,18 @@ impl<'a> Iterator for NameComponentIterator<'a> {
 
         match self.innermost_bytes.as_mut() {
             Some((bytes, offset)) => {
+                if let Some((component, end)) =
+                    self.innermost_cache.get(bytes, self.innermost_index)
+                {
+                    *offset = end;
+                    self.innermost_index += 1;
+                    self.innermost_remaining -= 1;
+                    return Some(component);
+                }
                 match TLV::try_decode(&bytes[*offset..]) {
                     Ok((nc_tlv, nc_len)) => {
                         *offset += nc_len;
+                        self.innermost_index += 1;
                         self.innermost_remaining -= 1;
                         Some(NameComponent {
                             // Would not have been created
@@ -406,7 +705,7 @@ impl<'a> Iterator for NameComponentIterator<'a> {
 mod tests {
     use crate::{
         io::{Decode, Encode},
-        name::{Name, NameComponent},
+        name::{Name, NameBuf, NameComponent},
         tlv::{TlvDecode, TlvEncode, TLV},
     };
 
@@ -755,6 +1054,142 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_comparison() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"hello")];
+        let short = root.adding_components(comp);
+        let inner_bytes = &[
+            8, 5, b'h', b'e', b'l', b'l', b'o', 1, 5, b'w', b'o', b'r', b'l', b'd',
+        ];
+        let long = Name::try_decode_from_inner(inner_bytes).unwrap();
+
+        assert!(short.is_prefix_of(long));
+        assert!(short.is_prefix_of(short));
+        assert!(!long.is_prefix_of(short));
+        assert!(Name::new().is_prefix_of(long));
+        assert!(short == long.dropping_last_component().unwrap());
+        assert!(short != long);
+
+        let comp = &[NameComponent::generic(b"hellO")];
+        let other = root.adding_components(comp);
+        assert!(!other.is_prefix_of(long));
+    }
+
+    #[test]
+    fn test_name_buf() {
+        let inner_bytes = &[
+            8, 5, b'h', b'e', b'l', b'l', b'o', 1, 5, b'w', b'o', b'r', b'l', b'd',
+        ];
+        let name = Name::try_decode_from_inner(inner_bytes).unwrap();
+
+        let buf = NameBuf::from(name);
+        assert_eq!(buf.component_count(), 2);
+        assert!(buf.as_name() == name);
+
+        let built = NameBuf::new()
+            .pushing(NameComponent::generic(b"hello"))
+            .pushing(NameComponent::implicit_sha256(b"world"));
+        assert_eq!(built, buf);
+
+        let mut encoded = Vec::new();
+        let _ = buf.encode(&mut encoded);
+        let mut expected = Vec::new();
+        let _ = name.encode(&mut expected);
+        assert_eq!(encoded, expected);
+
+        assert_eq!(NameBuf::new().component_count(), 0);
+        assert!(NameBuf::new().as_name() == Name::new());
+    }
+
+    #[test]
+    fn test_long_names() {
+        // Longer than the cached components, so both paths are exercised
+        let values: Vec<[u8; 2]> = (0..12u8).map(|i| [b'c', i]).collect();
+        let mut buf = NameBuf::new();
+        for (i, value) in values.iter().enumerate() {
+            let typ = if i % 3 == 0 {
+                NameComponent::TYPE_SEGMENT
+            } else {
+                NameComponent::TYPE_GENERIC
+            };
+            buf.push(NameComponent::new(typ, value).unwrap());
+        }
+        let name = buf.as_name();
+        assert_eq!(name.component_count(), 12);
+
+        let mut prefix = name;
+        for count in (0..=12).rev() {
+            assert_eq!(prefix.component_count(), count);
+            assert_eq!(prefix.components().count(), count);
+            for (i, component) in prefix.components().enumerate() {
+                assert_eq!(component.bytes, &values[i]);
+            }
+
+            let mut expected = NameBuf::new();
+            for component in name.components().take(count) {
+                expected.push(component);
+            }
+            let mut encoded = Vec::new();
+            let _ = prefix.encode(&mut encoded);
+            assert_eq!(prefix.encoded_length(), encoded.len());
+            let mut expected_encoded = Vec::new();
+            let _ = expected.encode(&mut expected_encoded);
+            assert_eq!(encoded, expected_encoded);
+
+            if count > 0 {
+                prefix = prefix.dropping_last_component().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_views() {
+        let inner_bytes = &[8, 1, b'a', 8, 1, b'b', 8, 1, b'c', 8, 1, b'd', 8, 1, b'e'];
+        let name = Name::try_decode_from_inner(inner_bytes).unwrap();
+        let root = Name::new();
+        let comp = &[
+            NameComponent::generic(b"a"),
+            NameComponent::generic(b"b"),
+            NameComponent::generic(b"c"),
+        ];
+        let abc = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"c"), NameComponent::generic(b"d")];
+        let cd = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"d"), NameComponent::generic(b"e")];
+        let de = root.adding_components(comp);
+
+        assert!(name.prefix(3) == abc);
+        assert!(name.prefix(0) == Name::new());
+        assert!(name.prefix(10) == name);
+        assert!(name.suffix(2).unwrap() == de);
+        assert!(name.suffix(5).unwrap() == name);
+        assert!(name.suffix(0).unwrap() == Name::new());
+        assert!(name.suffix(6).is_none());
+        assert!(name.subname(2, 2).unwrap() == cd);
+        assert!(name.subname(4, 2).is_none());
+        assert_eq!(name.component(4).unwrap().bytes, b"e");
+        assert!(name.component(5).is_none());
+
+        // Views of views keep working, including encoding
+        let view = name.prefix(4).suffix(2).unwrap();
+        assert!(view == cd);
+        assert_eq!(view.component(1).unwrap().bytes, b"d");
+        let mut encoded = Vec::new();
+        let _ = view.encode(&mut encoded);
+        assert_eq!(encoded, [7, 6, 8, 1, b'c', 8, 1, b'd']);
+        assert!(view.prefix(1) == cd.dropping_last_component().unwrap());
+
+        // Names with added components
+        let comp = &[NameComponent::generic(b"d"), NameComponent::generic(b"e")];
+        let extended = abc.adding_components(comp);
+        assert!(extended.prefix(4) == name.prefix(4));
+        assert!(extended.prefix(2) == name.prefix(2));
+        assert!(extended.suffix(2).unwrap() == de);
+        assert!(extended.suffix(3).is_none());
+        assert_eq!(extended.component(3).unwrap().bytes, b"d");
+    }
+
     use alloc::vec::Vec;
 
     #[test]
@@ -0,0 +1,58 @@
+// Where the forwarder and the applications take the nonces of the interests they
+//  originate. The nonces only need to be unlikely to repeat within the lifetime of the
+//  dead nonces, not to be unpredictable, so any decent generator will do.
+pub trait NonceSource {
+    fn next_nonce(&mut self) -> [u8; 4];
+}
+
+impl<F> NonceSource for F
+where
+    F: FnMut() -> [u8; 4],
+{
+    fn next_nonce(&mut self) -> [u8; 4] {
+        self()
+    }
+}
+
+// SplitMix64, which goes through all of its states before repeating one and does fine
+//  with any seed. The same seed gives the same nonces, so the seed should differ
+//  between the runs (see RandomNonces on the native platform).
+pub struct SplitMixNonces {
+    state: u64,
+}
+
+impl SplitMixNonces {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl NonceSource for SplitMixNonces {
+    fn next_nonce(&mut self) -> [u8; 4] {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        ((z >> 32) as u32).to_be_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+
+    use crate::nonce::{NonceSource, SplitMixNonces};
+
+    #[test]
+    fn test_split_mix_nonces() {
+        let mut nonces = SplitMixNonces::new(0);
+        let drawn: BTreeSet<_> = (0..1000).map(|_| nonces.next_nonce()).collect();
+        assert_eq!(drawn.len(), 1000);
+
+        let mut first = SplitMixNonces::new(42);
+        let mut second = SplitMixNonces::new(42);
+        assert_eq!(first.next_nonce(), second.next_nonce());
+        assert_ne!(first.next_nonce(), nonces.next_nonce());
+    }
+}
@@ -1,8 +1,14 @@
+use alloc::vec::Vec;
+
 use crate::{
-    hash::Hasher,
+    hash::{Hasher, Sha256Digest},
     io::{Decode, Encode, Write},
-    name::{Name, NameComponent},
-    tlv::{TlvDecode, TlvEncode, TypedArray, TypedBytes, TypedEmpty, TypedInteger, TLV},
+    name::{Name, NameBuf, NameComponent},
+    security::{Signer, Verifier},
+    tlv::{
+        TlvDecode, TlvDecodingError, TlvEncode, TypedArray, TypedBytes, TypedEmpty, TypedInteger,
+        VarintDecodingError, TLV,
+    },
 };
 use core::num::NonZeroU16;
 
@@ -50,6 +56,44 @@ impl<'a> Interest<'a> {
     }
 
     pub fn hash_signed_portion<H: Hasher>(&self, hasher: &mut H) -> bool {
+        let mut hh = EncodedHasher { hasher };
+        self.encode_signed_portion(&mut hh)
+    }
+
+    // Produces the signature value for this interest, assuming the application parameters
+    //  and the signature info were already set up with the signer's type and key locator.
+    pub fn sign_signed_portion<S: Signer + ?Sized>(&self, signer: &mut S) -> Vec<u8> {
+        signer.reset();
+        let mut ss = EncodedSigner { signer };
+        let _ = self.encode_signed_portion(&mut ss);
+        ss.signer.finalize_reset()
+    }
+
+    // False for the interests that are not signed
+    pub fn verify_signature<V: Verifier + ?Sized>(&self, verifier: &mut V) -> bool {
+        let Some((_, Some((signature_info, signature_value)))) = &self.application_parameters
+        else {
+            return false;
+        };
+        if signature_info.signature_type.val != verifier.signature_type() {
+            return false;
+        }
+        verifier.reset();
+        let mut vv = EncodedVerifier { verifier };
+        let _ = self.encode_signed_portion(&mut vv);
+        vv.verifier
+            .finalize_verify_reset(signature_value.bytes)
+    }
+
+    // The name without the parameters digest, the application parameters and the signature
+    //  info, or nothing if the interest is not signed
+    fn encode_signed_portion<W: Write<Error = ()> + ?Sized>(&self, writer: &mut W) -> bool {
+        let Some((application_parameters, Some((signature_info, _)))) =
+            &self.application_parameters
+        else {
+            return false;
+        };
+
         let mut relevant_name = self.name;
         if let Some(last_component) = relevant_name.components().last() {
             if last_component.typ.get() == NameComponent::TYPE_PARAMETER_SHA256 {
@@ -57,20 +101,9 @@ impl<'a> Interest<'a> {
             }
         }
 
-        let mut hh = EncodedHasher { hasher };
-        let _ = relevant_name.encode(&mut hh);
-
-        let (application_parameters, signature_info) = match self.application_parameters.as_ref() {
-            Some((ap, signature)) => match signature {
-                Some((signature_info, _)) => (ap, signature_info),
-                None => return false,
-            },
-            None => return false,
-        };
-
-        let _ = application_parameters.encode(&mut hh);
-        let _ = signature_info.encode(&mut hh);
-
+        let _ = relevant_name.encode(writer);
+        let _ = application_parameters.encode(writer);
+        let _ = signature_info.encode(writer);
         true
     }
 
@@ -132,14 +165,54 @@ impl<'a> TlvEncode for Interest<'a> {
 
 impl<'a> TlvDecode<'a> for Interest<'a> {
     fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        Self::decode_inner_diagnosed(inner_bytes, 0, false).ok()
+    }
+}
+
+impl<'a> Interest<'a> {
+    // Like try_decode, but tells what is wrong with the packet and where
+    pub fn try_decode_diagnosed(bytes: &'a [u8]) -> Result<(Self, usize), PacketDecodingError> {
+        let (tlv, tlv_len, header_len) = decode_packet_tlv(bytes, Self::TLV_TYPE)?;
+        Ok((
+            Self::decode_inner_diagnosed(tlv.val, header_len, false)?,
+            tlv_len,
+        ))
+    }
+
+    // Like try_decode_diagnosed, but also rejects the packets that are accepted
+    //  for interoperability even though they do not follow the specification,
+    //  see PacketDecodingReason::NotMinimal and STRICT_MAX_NAME_COMPONENTS.
+    pub fn try_decode_strict(bytes: &'a [u8]) -> Result<(Self, usize), PacketDecodingError> {
+        let (tlv, tlv_len, header_len) = decode_packet_tlv(bytes, Self::TLV_TYPE)?;
+        Ok((
+            Self::decode_inner_diagnosed(tlv.val, header_len, true)?,
+            tlv_len,
+        ))
+    }
+
+    // The errors carry the offsets of the inner bytes shifted by "base"
+    fn decode_inner_diagnosed(
+        inner_bytes: &'a [u8],
+        base: usize,
+        strict: bool,
+    ) -> Result<Self, PacketDecodingError> {
         let mut offset = 0;
 
-        let (name_tlv, name_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+        let (name_tlv, name_len) = diagnosed_tlv(inner_bytes, offset, base)?;
+        let fail = move |reason| PacketDecodingError::new(&name_tlv, base + offset, reason);
         if name_tlv.typ.get() != Name::TLV_TYPE {
-            return None; // Name must be the first TLV
+            return Err(fail(PacketDecodingReason::MissingName)); // Name must be the first TLV
+        }
+        let name = Name::try_decode_from_inner(name_tlv.val)
+            .ok_or(fail(PacketDecodingReason::InvalidValue))?;
+        if strict {
+            // Interests cannot ask for the empty name
+            if name.component_count() == 0 {
+                return Err(fail(PacketDecodingReason::InvalidValue));
+            }
+            check_strict_name(&name_tlv, name, base + offset)?;
         }
         offset += name_len;
-        let name = Name::try_decode_from_inner(name_tlv.val)?;
 
         // The rest should typically be a few known TLVs in order,
         //  but they may contain arbitrary non-critical TLVs too.
@@ -152,6 +225,8 @@ impl<'a> TlvDecode<'a> for Interest<'a> {
         let mut hop_limit = None;
         let mut application_parameters = None;
         let mut unknown_tlv_ranges = [(0usize, 0usize); 7];
+        // Unknown TLVs after the known TLV with index i go to slot i + 1
+        let mut unknown_slot = 0;
 
         let known = [
             CanBePrefix::TLV_TYPE,
@@ -165,49 +240,70 @@ impl<'a> TlvDecode<'a> for Interest<'a> {
         let mut minimum_possible_known = 0;
 
         while offset < inner_bytes.len() {
-            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            let (tlv, tlv_len) = diagnosed_tlv(inner_bytes, offset, base)?;
+            let fail = move |reason| PacketDecodingError::new(&tlv, base + offset, reason);
             let typ = tlv.typ.get();
             if let Some(idx) = known.iter().position(|x| &typ == x) {
                 // It is one of the known and expected TLVs
                 if idx < minimum_possible_known {
-                    return None; // ...but the order of known elements is incorrect
+                    // ...but the order of known elements is incorrect
+                    return Err(fail(PacketDecodingReason::OutOfOrder));
                 }
 
+                let invalid = move || fail(PacketDecodingReason::InvalidValue);
                 match idx {
                     0 => can_be_prefix = Some(CanBePrefix {}),
                     1 => must_be_fresh = Some(MustBeFresh {}),
                     2 => forwarding_hint = Some(ForwardingHint { bytes: tlv.val }),
                     3 => {
                         nonce = Some(InterestNonce {
-                            bytes: tlv.val.try_into().ok()?,
+                            bytes: tlv.val.try_into().map_err(|_| invalid())?,
                         })
                     }
                     4 => {
-                        interest_lifetime = Some(InterestLifetime {
-                            val: u64::try_decode(tlv.val).ok()?.0,
-                        })
+                        let lifetime = InterestLifetime {
+                            val: u64::try_decode(tlv.val).map_err(|_| invalid())?.0,
+                        };
+                        if strict && lifetime.inner_length() != tlv.val.len() {
+                            return Err(fail(PacketDecodingReason::NotMinimal));
+                        }
+                        interest_lifetime = Some(lifetime)
                     }
                     5 => {
+                        if strict && tlv.val.len() != 1 {
+                            return Err(invalid());
+                        }
                         hop_limit = Some(HopLimit {
-                            val: u64::try_decode(tlv.val).ok()?.0.try_into().ok()?,
+                            val: u64::try_decode(tlv.val)
+                                .map_err(|_| invalid())?
+                                .0
+                                .try_into()
+                                .map_err(|_| invalid())?,
                         })
                     }
                     6 => application_parameters = Some(ApplicationParameters { bytes: tlv.val }),
                     _ => unreachable!(),
                 }
                 minimum_possible_known = idx;
+                unknown_slot = idx + 1;
+                if idx == 6 {
+                    // Only the signature can follow the parameters, which is handled below
+                    offset += tlv_len;
+                    break;
+                }
             } else {
                 // It is an unknown TLV
                 if tlv.type_is_critical() {
-                    return None; // There is a critical unknown type, so we must bail
+                    // There is a critical unknown type, so we must bail
+                    return Err(fail(PacketDecodingReason::CriticalUnknownType));
                 }
 
                 // Check if we already have something in this range
-                if unknown_tlv_ranges[minimum_possible_known] == (0, 0) {
-                    unknown_tlv_ranges[minimum_possible_known] = (offset, offset + tlv_len)
+                if unknown_tlv_ranges[unknown_slot] == (0, 0) {
+                    unknown_tlv_ranges[unknown_slot] = (offset, offset + tlv_len)
                 } else {
-                    debug_assert!(unknown_tlv_ranges[minimum_possible_known].1 == offset);
-                    unknown_tlv_ranges[minimum_possible_known].1 += tlv_len;
+                    debug_assert!(unknown_tlv_ranges[unknown_slot].1 == offset);
+                    unknown_tlv_ranges[unknown_slot].1 += tlv_len;
                 }
             }
 
@@ -221,19 +317,33 @@ impl<'a> TlvDecode<'a> for Interest<'a> {
                 // There can be an optional signature here
                 let mut signature = None;
                 if offset < inner_bytes.len() {
-                    let (si_tlv, si_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+                    let (si_tlv, si_len) = diagnosed_tlv(inner_bytes, offset, base)?;
+                    let fail =
+                        move |reason| PacketDecodingError::new(&si_tlv, base + offset, reason);
                     if si_tlv.typ.get() != InterestSignatureInfo::TLV_TYPE {
-                        return None;
+                        return Err(fail(PacketDecodingReason::OutOfOrder));
                     }
-                    let si = InterestSignatureInfo::try_decode_from_inner(si_tlv.val)?;
+                    let si = InterestSignatureInfo::try_decode_from_inner(si_tlv.val)
+                        .ok_or(fail(PacketDecodingReason::InvalidValue))?;
                     offset += si_len;
-                    let (sv_tlv, sv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+                    if offset == inner_bytes.len() {
+                        return Err(fail(PacketDecodingReason::MissingSignature));
+                    }
+                    let (sv_tlv, sv_len) = diagnosed_tlv(inner_bytes, offset, base)?;
+                    let fail =
+                        move |reason| PacketDecodingError::new(&sv_tlv, base + offset, reason);
                     if sv_tlv.typ.get() != InterestSignatureValue::TLV_TYPE {
-                        return None;
+                        return Err(fail(PacketDecodingReason::OutOfOrder));
                     }
                     let sv = InterestSignatureValue { bytes: sv_tlv.val };
-                    if offset + sv_len != inner_bytes.len() {
-                        return None;
+                    offset += sv_len;
+                    if offset != inner_bytes.len() {
+                        let (tlv, _) = diagnosed_tlv(inner_bytes, offset, base)?;
+                        return Err(PacketDecodingError::new(
+                            &tlv,
+                            base + offset,
+                            PacketDecodingReason::AfterSignature,
+                        ));
                     }
                     signature = Some((si, sv))
                 }
@@ -242,7 +352,7 @@ impl<'a> TlvDecode<'a> for Interest<'a> {
             None => None,
         };
 
-        Some(Interest {
+        Ok(Interest {
             name,
             can_be_prefix,
             must_be_fresh,
@@ -256,9 +366,192 @@ impl<'a> TlvDecode<'a> for Interest<'a> {
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PacketDecodingReason {
+    // The packet ends in the middle of the TLV
+    Truncated,
+    // The type or the length of the TLV is not a valid number
+    MalformedTlv(VarintDecodingError),
+    // The outer TLV is not of the expected packet type
+    WrongPacketType,
+    // The first element of the packet is not a Name
+    MissingName,
+    // A known element comes after one that must follow it
+    OutOfOrder,
+    // An unknown element with a type that must be understood
+    CriticalUnknownType,
+    // A known element with a value that cannot be parsed
+    InvalidValue,
+    // The signature is missing or incomplete
+    MissingSignature,
+    // There are elements after the signature
+    AfterSignature,
+    // Only in strict mode: a number is encoded with more bytes than needed
+    NotMinimal,
+    // Only in strict mode: the name has more than STRICT_MAX_NAME_COMPONENTS components
+    TooManyComponents,
+}
+
+// Names longer than this are rejected in strict mode. The specification has no limit
+//  other than the packet size, but legitimate names are far shorter, and the cost
+//  of the table lookups grows with the number of components.
+pub const STRICT_MAX_NAME_COMPONENTS: usize = 64;
+
+// Why a packet could not be decoded: the type of the offending TLV, if it could
+//  be read, and its offset from the start of the packet.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PacketDecodingError {
+    pub typ: Option<u32>,
+    pub offset: usize,
+    pub reason: PacketDecodingReason,
+}
+
+impl PacketDecodingError {
+    fn new(tlv: &TLV<'_>, offset: usize, reason: PacketDecodingReason) -> Self {
+        Self {
+            typ: Some(tlv.typ.get()),
+            offset,
+            reason,
+        }
+    }
+}
+
+// Decodes the TLV at "offset" of the bytes, which start at "base" in the packet
+fn diagnosed_tlv(
+    bytes: &[u8],
+    offset: usize,
+    base: usize,
+) -> Result<(TLV<'_>, usize), PacketDecodingError> {
+    let (typ, reason) = match TLV::try_decode(&bytes[offset..]) {
+        Ok(decoded) => return Ok(decoded),
+        Err(TlvDecodingError::CannotDecodeType { err }) => (None, err),
+        Err(TlvDecodingError::CannotDecodeLength { typ, err }) => (Some(typ.get()), err),
+        Err(TlvDecodingError::CannotDecodeValue { typ, .. }) => {
+            (Some(typ.get()), VarintDecodingError::BufferTooShort)
+        }
+    };
+    let reason = match reason {
+        VarintDecodingError::BufferTooShort => PacketDecodingReason::Truncated,
+        err => PacketDecodingReason::MalformedTlv(err),
+    };
+    Err(PacketDecodingError {
+        typ,
+        offset: base + offset,
+        reason,
+    })
+}
+
+fn check_strict_name(
+    name_tlv: &TLV<'_>,
+    name: Name<'_>,
+    offset: usize,
+) -> Result<(), PacketDecodingError> {
+    if name.component_count() > STRICT_MAX_NAME_COMPONENTS {
+        return Err(PacketDecodingError::new(
+            name_tlv,
+            offset,
+            PacketDecodingReason::TooManyComponents,
+        ));
+    }
+    Ok(())
+}
+
+// Returns the outer TLV of the packet, its length and the length of its type and length
+fn decode_packet_tlv(
+    bytes: &[u8],
+    expected_type: u32,
+) -> Result<(TLV<'_>, usize, usize), PacketDecodingError> {
+    let (tlv, tlv_len) = diagnosed_tlv(bytes, 0, 0)?;
+    if tlv.typ.get() != expected_type {
+        return Err(PacketDecodingError::new(
+            &tlv,
+            0,
+            PacketDecodingReason::WrongPacketType,
+        ));
+    }
+    Ok((tlv, tlv_len, tlv_len - tlv.val.len()))
+}
+
+// An owned Interest that can be queued and processed after the receive buffer is reused.
+// It keeps the encoded value of the Interest TLV, including any unknown TLVs,
+//  so that the borrowed Interest can be produced from it at any time.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct InterestBuf {
+    inner_bytes: Vec<u8>,
+}
+
+impl InterestBuf {
+    pub fn as_interest(&self) -> Interest<'_> {
+        // Would not have been created otherwise
+        Interest::try_decode_from_inner(&self.inner_bytes).unwrap()
+    }
+
+    // A copy of the interest for retrying it, with a nonce from "next_nonce" that
+    //  differs from the current one, since forwarders drop a repeated nonce as a loop.
+    // The lifetime (in milliseconds) is replaced if given, everything else is kept.
+    pub fn reexpressed<N>(&self, mut next_nonce: N, interest_lifetime: Option<u64>) -> Self
+    where
+        N: FnMut() -> [u8; 4],
+    {
+        let mut interest = self.as_interest();
+        let previous = interest.nonce.map(|nonce| nonce.bytes);
+        let mut nonce = next_nonce();
+        while Some(nonce) == previous {
+            nonce = next_nonce();
+        }
+        interest.nonce = Some(InterestNonce { bytes: nonce });
+        if let Some(val) = interest_lifetime {
+            interest.interest_lifetime = Some(InterestLifetime { val });
+        }
+        Self::from(&interest)
+    }
+}
+
+impl<'a> From<&Interest<'a>> for InterestBuf {
+    fn from(interest: &Interest<'a>) -> Self {
+        let mut inner_bytes = Vec::with_capacity(interest.inner_length());
+        let _ = interest.encode_inner(&mut inner_bytes);
+        Self { inner_bytes }
+    }
+}
+
+impl TlvEncode for InterestBuf {
+    const TLV_TYPE: u32 = Interest::TLV_TYPE;
+
+    fn inner_length(&self) -> usize {
+        self.inner_bytes.len()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        writer.write(&self.inner_bytes)
+    }
+}
+
+impl<'a> TlvDecode<'a> for InterestBuf {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        Interest::try_decode_from_inner(inner_bytes)?;
+        Some(Self {
+            inner_bytes: Vec::from(inner_bytes),
+        })
+    }
+}
+
 pub type CanBePrefix = TypedEmpty<33>;
 pub type MustBeFresh = TypedEmpty<18>;
 pub type ForwardingHint<'a> = TypedBytes<'a, 30>;
+
+impl<'a> ForwardingHint<'a> {
+    // The names of the delegations, in the order of preference.
+    // Anything after the first element that is not a Name is ignored.
+    pub fn delegations(&self) -> impl Iterator<Item = Name<'a>> {
+        let mut remaining = self.bytes;
+        core::iter::from_fn(move || {
+            let (name, len) = Name::try_decode(remaining).ok()?;
+            remaining = &remaining[len..];
+            Some(name)
+        })
+    }
+}
 pub type InterestNonce = TypedArray<10, 4>;
 pub type InterestLifetime = TypedInteger<12, u64>;
 pub type HopLimit = TypedInteger<34, u8>;
@@ -313,6 +606,20 @@ impl<'a> Data<'a> {
         let _ = self.encode_signed_portion(&mut hh);
     }
 
+    // One of the ContentType constants, BLOB if absent
+    pub fn content_type(&self) -> u64 {
+        self.meta_info
+            .as_ref()
+            .and_then(|mi| mi.content_type)
+            .map(|ct| ct.val)
+            .unwrap_or(ContentType::BLOB)
+    }
+
+    // An application-level nack, by which the producer says it cannot satisfy the interest
+    pub fn is_nack(&self) -> bool {
+        self.content_type() == ContentType::NACK
+    }
+
     fn encode_signed_portion<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
         self.name.encode(writer)?;
         writer.write(self.unknown_tlvs[0])?;
@@ -327,6 +634,169 @@ impl<'a> Data<'a> {
         let mut hh = EncodedHasher { hasher };
         let _ = self.encode(&mut hh);
     }
+
+    // The name with the ImplicitSha256Digest component appended, which is the digest
+    //  of the whole encoded packet and identifies this exact packet.
+    pub fn full_name<H: Hasher<Digest = Sha256Digest>>(&self, hasher: &mut H) -> NameBuf {
+        let digest = self.implicit_digest(hasher);
+        NameBuf::from(self.name).pushing(NameComponent::implicit_sha256(&digest.0))
+    }
+
+    // Computed every time, see DataBuf for a cached one
+    pub fn implicit_digest<H: Hasher<Digest = Sha256Digest>>(
+        &self,
+        hasher: &mut H,
+    ) -> Sha256Digest {
+        hasher.reset();
+        self.hash_whole_packet(hasher);
+        hasher.finalize_reset()
+    }
+
+    // Produces the signature value for this packet, assuming the signature_info
+    //  was already set up with the signer's type and key locator.
+    pub fn sign_signed_portion<S: Signer + ?Sized>(&self, signer: &mut S) -> Vec<u8> {
+        signer.reset();
+        let mut ss = EncodedSigner { signer };
+        let _ = self.encode_signed_portion(&mut ss);
+        ss.signer.finalize_reset()
+    }
+
+    pub fn verify_signature<V: Verifier + ?Sized>(&self, verifier: &mut V) -> bool {
+        if self.signature_info.signature_type.val != verifier.signature_type() {
+            return false;
+        }
+        verifier.reset();
+        let mut vv = EncodedVerifier { verifier };
+        let _ = self.encode_signed_portion(&mut vv);
+        vv.verifier
+            .finalize_verify_reset(self.signature_value.bytes)
+    }
+}
+
+// Assembles a Data packet step by step and produces the encoded bytes
+//  together with the signature in a single call, e.g.
+//  DataBuilder::new(name).content(b"hello").freshness_period(1000).digest_sign(&mut hasher)
+pub struct DataBuilder<'a> {
+    name: Name<'a>,
+    content: Option<&'a [u8]>,
+    meta_info: MetaInfo<'a>,
+    validity_period: Option<ValidityPeriod<'a>>,
+    additional_description: Option<AdditionalDescription<'a>>,
+}
+
+impl<'a> DataBuilder<'a> {
+    // Nacks should not stay in caches for long, so that the consumers
+    //  can get the real data as soon as it is available
+    pub const DEFAULT_NACK_FRESHNESS_PERIOD: u64 = 100;
+
+    pub fn new(name: Name<'a>) -> Self {
+        Self {
+            name,
+            content: None,
+            meta_info: MetaInfo {
+                content_type: None,
+                freshness_period: None,
+                final_block_id: None,
+                unknown_tlvs: &[],
+            },
+            validity_period: None,
+            additional_description: None,
+        }
+    }
+
+    // An application-level nack for the interest name, with empty content
+    //  and a short freshness period, which can be changed
+    pub fn nack(name: Name<'a>) -> Self {
+        Self::new(name)
+            .content(&[])
+            .content_type(ContentType::NACK)
+            .freshness_period(Self::DEFAULT_NACK_FRESHNESS_PERIOD)
+    }
+
+    pub fn content(mut self, content: &'a [u8]) -> Self {
+        self.content = Some(content);
+        self
+    }
+
+    pub fn content_type(mut self, content_type: u64) -> Self {
+        self.meta_info.content_type = Some(ContentType { val: content_type });
+        self
+    }
+
+    // In milliseconds
+    pub fn freshness_period(mut self, freshness_period: u64) -> Self {
+        self.meta_info.freshness_period = Some(FreshnessPeriod {
+            val: freshness_period,
+        });
+        self
+    }
+
+    pub fn final_block_id(mut self, component: NameComponent<'a>) -> Self {
+        self.meta_info.final_block_id = Some(FinalBlockId { component });
+        self
+    }
+
+    // Certificates must have one
+    pub fn validity_period(mut self, validity_period: ValidityPeriod<'a>) -> Self {
+        self.validity_period = Some(validity_period);
+        self
+    }
+
+    // The encoded DescriptionEntry elements
+    pub fn additional_description(mut self, entries: &'a [u8]) -> Self {
+        self.additional_description = Some(AdditionalDescription { bytes: entries });
+        self
+    }
+
+    // Signs the packet with the signer, using its type and key locator for the SignatureInfo
+    pub fn sign<S: Signer + ?Sized>(self, signer: &mut S) -> Vec<u8> {
+        // The key locator borrows the signer, which has to be mutable to sign
+        let key_locator = signer.key_locator().map(NameBuf::from);
+        let signature_info = SignatureInfo::new(
+            signer.signature_type(),
+            key_locator.as_ref().map(|name| name.as_name()),
+        );
+        let mut data = self.build(signature_info);
+        let signature = data.sign_signed_portion(signer);
+        data.signature_value = SignatureValue { bytes: &signature };
+        let mut packet = Vec::with_capacity(data.encoded_length());
+        let _ = data.encode(&mut packet);
+        packet
+    }
+
+    // Uses the DigestSha256 signature, which only protects against corruption
+    pub fn digest_sign<H: Hasher<Digest = Sha256Digest>>(self, hasher: &mut H) -> Vec<u8> {
+        let mut data = self.build(SignatureInfo::new_digest_sha256());
+        hasher.reset();
+        data.hash_signed_portion(hasher);
+        let digest = hasher.finalize_reset();
+        data.signature_value = SignatureValue { bytes: &digest.0 };
+        let mut packet = Vec::with_capacity(data.encoded_length());
+        let _ = data.encode(&mut packet);
+        packet
+    }
+
+    fn build<'b>(&'b self, mut signature_info: SignatureInfo<'b>) -> Data<'b> {
+        signature_info.validity_period = self.validity_period;
+        signature_info.additional_description = self.additional_description;
+        let meta_info = &self.meta_info;
+        let has_meta_info = meta_info.content_type.is_some()
+            || meta_info.freshness_period.is_some()
+            || meta_info.final_block_id.is_some();
+        Data {
+            name: self.name,
+            meta_info: has_meta_info.then_some(MetaInfo {
+                content_type: meta_info.content_type,
+                freshness_period: meta_info.freshness_period,
+                final_block_id: meta_info.final_block_id,
+                unknown_tlvs: &[],
+            }),
+            content: self.content.map(|bytes| Content { bytes }),
+            signature_info,
+            signature_value: SignatureValue { bytes: &[] },
+            unknown_tlvs: Default::default(),
+        }
+    }
 }
 
 impl<'a> TlvEncode for Data<'a> {
@@ -344,14 +814,50 @@ impl<'a> TlvEncode for Data<'a> {
 
 impl<'a> TlvDecode<'a> for Data<'a> {
     fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        Self::decode_inner_diagnosed(inner_bytes, 0, false).ok()
+    }
+}
+
+impl<'a> Data<'a> {
+    // Like try_decode, but tells what is wrong with the packet and where
+    pub fn try_decode_diagnosed(bytes: &'a [u8]) -> Result<(Self, usize), PacketDecodingError> {
+        let (tlv, tlv_len, header_len) = decode_packet_tlv(bytes, Self::TLV_TYPE)?;
+        Ok((
+            Self::decode_inner_diagnosed(tlv.val, header_len, false)?,
+            tlv_len,
+        ))
+    }
+
+    // Like try_decode_diagnosed, but also rejects the packets that are accepted
+    //  for interoperability even though they do not follow the specification,
+    //  see PacketDecodingReason::NotMinimal and STRICT_MAX_NAME_COMPONENTS.
+    pub fn try_decode_strict(bytes: &'a [u8]) -> Result<(Self, usize), PacketDecodingError> {
+        let (tlv, tlv_len, header_len) = decode_packet_tlv(bytes, Self::TLV_TYPE)?;
+        Ok((
+            Self::decode_inner_diagnosed(tlv.val, header_len, true)?,
+            tlv_len,
+        ))
+    }
+
+    // The errors carry the offsets of the inner bytes shifted by "base"
+    fn decode_inner_diagnosed(
+        inner_bytes: &'a [u8],
+        base: usize,
+        strict: bool,
+    ) -> Result<Self, PacketDecodingError> {
         let mut offset = 0;
 
-        let (name_tlv, name_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+        let (name_tlv, name_len) = diagnosed_tlv(inner_bytes, offset, base)?;
+        let fail = move |reason| PacketDecodingError::new(&name_tlv, base + offset, reason);
         if name_tlv.typ.get() != Name::TLV_TYPE {
-            return None; // Name must be the first TLV
+            return Err(fail(PacketDecodingReason::MissingName)); // Name must be the first TLV
+        }
+        let name = Name::try_decode_from_inner(name_tlv.val)
+            .ok_or(fail(PacketDecodingReason::InvalidValue))?;
+        if strict {
+            check_strict_name(&name_tlv, name, base + offset)?;
         }
         offset += name_len;
-        let name = Name::try_decode_from_inner(name_tlv.val)?;
 
         // The rest should typically be a few known TLVs in order,
         //  but they may contain arbitrary non-critical TLVs too.
@@ -361,6 +867,8 @@ impl<'a> TlvDecode<'a> for Data<'a> {
         let mut signature_info = None;
         let mut signature_value = None;
         let mut unknown_tlv_ranges = [(0usize, 0usize); 3];
+        // Unknown TLVs after the known TLV with index i go to slot i + 1
+        let mut unknown_slot = 0;
 
         let known = [
             MetaInfo::TLV_TYPE,
@@ -371,45 +879,74 @@ impl<'a> TlvDecode<'a> for Data<'a> {
         let mut minimum_possible_known = 0;
 
         while offset < inner_bytes.len() {
-            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            let (tlv, tlv_len) = diagnosed_tlv(inner_bytes, offset, base)?;
+            let fail = move |reason| PacketDecodingError::new(&tlv, base + offset, reason);
             let typ = tlv.typ.get();
             if let Some(idx) = known.iter().position(|x| &typ == x) {
                 // It is one of the known and expected TLVs
                 if idx < minimum_possible_known {
-                    return None; // ...but the order of known elements is incorrect
+                    // ...but the order of known elements is incorrect
+                    return Err(fail(PacketDecodingReason::OutOfOrder));
                 }
 
+                let invalid = move || fail(PacketDecodingReason::InvalidValue);
                 match idx {
-                    0 => meta_info = Some(MetaInfo::try_decode_from_inner(tlv.val)?),
+                    0 => {
+                        let decoded =
+                            MetaInfo::try_decode_from_inner(tlv.val).ok_or_else(invalid)?;
+                        // Only the numbers can be encoded with more bytes than needed
+                        if strict && decoded.inner_length() != tlv.val.len() {
+                            return Err(fail(PacketDecodingReason::NotMinimal));
+                        }
+                        meta_info = Some(decoded)
+                    }
                     1 => content = Some(Content { bytes: tlv.val }),
-                    2 => signature_info = Some(SignatureInfo::try_decode_from_inner(tlv.val)?),
+                    2 => {
+                        signature_info = Some(
+                            SignatureInfo::try_decode_from_inner(tlv.val).ok_or_else(invalid)?,
+                        )
+                    }
                     3 => signature_value = Some(SignatureValue { bytes: tlv.val }),
                     _ => unreachable!(),
                 }
                 minimum_possible_known = idx;
+                unknown_slot = idx + 1;
             } else {
                 // It is an unknown TLV
                 if tlv.type_is_critical() {
-                    return None; // There is a critical unknown type, so we must bail
+                    // There is a critical unknown type, so we must bail
+                    return Err(fail(PacketDecodingReason::CriticalUnknownType));
+                }
+                if unknown_slot >= unknown_tlv_ranges.len() {
+                    // Nothing is allowed within or after the signature
+                    return Err(fail(PacketDecodingReason::AfterSignature));
                 }
 
                 // Check if we already have something in this range
-                if unknown_tlv_ranges[minimum_possible_known] == (0, 0) {
-                    unknown_tlv_ranges[minimum_possible_known] = (offset, offset + tlv_len)
+                if unknown_tlv_ranges[unknown_slot] == (0, 0) {
+                    unknown_tlv_ranges[unknown_slot] = (offset, offset + tlv_len)
                 } else {
-                    debug_assert!(unknown_tlv_ranges[minimum_possible_known].1 == offset);
-                    unknown_tlv_ranges[minimum_possible_known].1 += tlv_len;
+                    debug_assert!(unknown_tlv_ranges[unknown_slot].1 == offset);
+                    unknown_tlv_ranges[unknown_slot].1 += tlv_len;
                 }
             }
 
             offset += tlv_len;
         }
 
-        let signature_info = signature_info?;
-        let signature_value = signature_value?;
+        let (signature_info, signature_value) = match (signature_info, signature_value) {
+            (Some(signature_info), Some(signature_value)) => (signature_info, signature_value),
+            _ => {
+                return Err(PacketDecodingError {
+                    typ: None,
+                    offset: base + offset,
+                    reason: PacketDecodingReason::MissingSignature,
+                })
+            }
+        };
         let unknown_tlvs = unknown_tlv_ranges.map(|(b, e)| &inner_bytes[b..e]);
 
-        Some(Data {
+        Ok(Data {
             name,
             meta_info,
             content,
@@ -420,6 +957,96 @@ impl<'a> TlvDecode<'a> for Data<'a> {
     }
 }
 
+// An owned Data, see InterestBuf.
+// It also keeps the implicit digest once computed, so that it is hashed only once
+//  no matter how many times the digest or the full name are needed.
+#[derive(Clone, Debug)]
+pub struct DataBuf {
+    inner_bytes: Vec<u8>,
+    digest: Option<Sha256Digest>,
+}
+
+impl DataBuf {
+    pub fn as_data(&self) -> Data<'_> {
+        // Would not have been created otherwise
+        Data::try_decode_from_inner(&self.inner_bytes).unwrap()
+    }
+
+    // The digest of the whole encoded packet, computed on the first call
+    pub fn implicit_digest<H: Hasher<Digest = Sha256Digest>>(
+        &mut self,
+        hasher: &mut H,
+    ) -> Sha256Digest {
+        if let Some(digest) = self.digest {
+            return digest;
+        }
+        hasher.reset();
+        let _ = self.encode(&mut EncodedHasher { hasher });
+        let digest = hasher.finalize_reset();
+        self.digest = Some(digest);
+        digest
+    }
+
+    // The digest if it was already computed
+    pub fn cached_implicit_digest(&self) -> Option<Sha256Digest> {
+        self.digest
+    }
+
+    // See Data::full_name
+    pub fn full_name<H: Hasher<Digest = Sha256Digest>>(&mut self, hasher: &mut H) -> NameBuf {
+        let digest = self.implicit_digest(hasher);
+        NameBuf::from(self.as_data().name).pushing(NameComponent::implicit_sha256(&digest.0))
+    }
+}
+
+// The cached digest follows from the bytes, so only those are compared
+impl PartialEq for DataBuf {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner_bytes == other.inner_bytes
+    }
+}
+
+impl Eq for DataBuf {}
+
+impl core::hash::Hash for DataBuf {
+    fn hash<S: core::hash::Hasher>(&self, state: &mut S) {
+        self.inner_bytes.hash(state);
+    }
+}
+
+impl<'a> From<&Data<'a>> for DataBuf {
+    fn from(data: &Data<'a>) -> Self {
+        let mut inner_bytes = Vec::with_capacity(data.inner_length());
+        let _ = data.encode_inner(&mut inner_bytes);
+        Self {
+            inner_bytes,
+            digest: None,
+        }
+    }
+}
+
+impl TlvEncode for DataBuf {
+    const TLV_TYPE: u32 = Data::TLV_TYPE;
+
+    fn inner_length(&self) -> usize {
+        self.inner_bytes.len()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        writer.write(&self.inner_bytes)
+    }
+}
+
+impl<'a> TlvDecode<'a> for DataBuf {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        Data::try_decode_from_inner(inner_bytes)?;
+        Some(Self {
+            inner_bytes: Vec::from(inner_bytes),
+            digest: None,
+        })
+    }
+}
+
 pub type Content<'a> = TypedBytes<'a, 21>;
 
 pub type ApplicationParameters<'a> = TypedBytes<'a, 36>;
@@ -552,6 +1179,8 @@ impl<'a> TlvEncode for FinalBlockId<'a> {
 pub struct SignatureInfo<'a> {
     pub signature_type: SignatureType,
     pub key_locator: Option<KeyLocator<'a>>,
+    pub validity_period: Option<ValidityPeriod<'a>>,
+    pub additional_description: Option<AdditionalDescription<'a>>,
 }
 
 impl<'a> SignatureInfo<'a> {
@@ -561,6 +1190,19 @@ impl<'a> SignatureInfo<'a> {
                 val: SignatureType::DIGEST_SHA256,
             },
             key_locator: None,
+            validity_period: None,
+            additional_description: None,
+        }
+    }
+
+    pub fn new(signature_type: u64, key_locator: Option<Name<'a>>) -> Self {
+        Self {
+            signature_type: TypedInteger {
+                val: signature_type,
+            },
+            key_locator: key_locator.map(KeyLocator::Name),
+            validity_period: None,
+            additional_description: None,
         }
     }
 }
@@ -578,16 +1220,32 @@ impl<'a> TlvDecode<'a> for SignatureInfo<'a> {
         };
 
         let mut key_locator = None;
+        let mut validity_period = None;
+        let mut additional_description = None;
 
-        if let Ok((key_locator_tlv, _)) = TLV::try_decode(&inner_bytes[offset..]) {
-            if key_locator_tlv.typ.get() == KeyLocator::TLV_TYPE {
-                key_locator = Some(KeyLocator::try_decode_from_inner(key_locator_tlv.val)?)
+        // Other elements, e.g. the ones only meaningful for signed interests, are skipped
+        while offset < inner_bytes.len() {
+            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            match tlv.typ.get() {
+                KeyLocator::TLV_TYPE => {
+                    key_locator = Some(KeyLocator::try_decode_from_inner(tlv.val)?)
+                }
+                ValidityPeriod::TLV_TYPE => {
+                    validity_period = Some(ValidityPeriod::try_decode_from_inner(tlv.val)?)
+                }
+                AdditionalDescription::TLV_TYPE => {
+                    additional_description = Some(AdditionalDescription { bytes: tlv.val })
+                }
+                _ => {}
             }
+            offset += tlv_len;
         }
 
         Some(Self {
             signature_type,
             key_locator,
+            validity_period,
+            additional_description,
         })
     }
 }
@@ -598,16 +1256,146 @@ impl<'a> TlvEncode for SignatureInfo<'a> {
     fn inner_length(&self) -> usize {
         let mut len = self.signature_type.encoded_length();
         len += self.key_locator.encoded_length();
+        len += self.validity_period.encoded_length();
+        len += self.additional_description.encoded_length();
         len
     }
 
     fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
         self.signature_type.encode(writer)?;
         self.key_locator.encode(writer)?;
+        self.validity_period.encode(writer)?;
+        self.additional_description.encode(writer)?;
         Ok(())
     }
 }
 
+// The times are in the ISO 8601 basic format, YYYYMMDDThhmmss in UTC,
+//  so they can be compared byte by byte
+#[derive(Clone, Copy)]
+pub struct ValidityPeriod<'a> {
+    pub not_before: NotBefore<'a>,
+    pub not_after: NotAfter<'a>,
+}
+
+impl<'a> ValidityPeriod<'a> {
+    pub const TIMESTAMP_LENGTH: usize = 15;
+
+    pub fn new(not_before: &'a [u8], not_after: &'a [u8]) -> Option<Self> {
+        if !Self::is_timestamp(not_before) || !Self::is_timestamp(not_after) {
+            return None;
+        }
+        Some(Self {
+            not_before: NotBefore { bytes: not_before },
+            not_after: NotAfter { bytes: not_after },
+        })
+    }
+
+    // Both ends are included
+    pub fn includes(&self, timestamp: &[u8]) -> bool {
+        Self::is_timestamp(timestamp)
+            && self.not_before.bytes <= timestamp
+            && timestamp <= self.not_after.bytes
+    }
+
+    fn is_timestamp(bytes: &[u8]) -> bool {
+        bytes.len() == Self::TIMESTAMP_LENGTH
+            && bytes.iter().enumerate().all(|(i, b)| {
+                if i == 8 {
+                    *b == b'T'
+                } else {
+                    b.is_ascii_digit()
+                }
+            })
+    }
+}
+
+impl<'a> TlvDecode<'a> for ValidityPeriod<'a> {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let (not_before_tlv, not_before_len) = TLV::try_decode(inner_bytes).ok()?;
+        if not_before_tlv.typ.get() != NotBefore::TLV_TYPE {
+            return None;
+        }
+        let (not_after_tlv, _) = TLV::try_decode(&inner_bytes[not_before_len..]).ok()?;
+        if not_after_tlv.typ.get() != NotAfter::TLV_TYPE {
+            return None;
+        }
+        Self::new(not_before_tlv.val, not_after_tlv.val)
+    }
+}
+
+impl<'a> TlvEncode for ValidityPeriod<'a> {
+    const TLV_TYPE: u32 = 253;
+
+    fn inner_length(&self) -> usize {
+        self.not_before.encoded_length() + self.not_after.encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.not_before.encode(writer)?;
+        self.not_after.encode(writer)
+    }
+}
+
+pub type NotBefore<'a> = TypedBytes<'a, 254>;
+pub type NotAfter<'a> = TypedBytes<'a, 255>;
+
+// A sequence of encoded DescriptionEntry elements
+pub type AdditionalDescription<'a> = TypedBytes<'a, 258>;
+
+impl<'a> AdditionalDescription<'a> {
+    // Anything after the first element that is not a DescriptionEntry is ignored
+    pub fn entries(&self) -> impl Iterator<Item = DescriptionEntry<'a>> {
+        let mut remaining = self.bytes;
+        core::iter::from_fn(move || {
+            let (entry, len) = DescriptionEntry::try_decode(remaining).ok()?;
+            remaining = &remaining[len..];
+            Some(entry)
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct DescriptionEntry<'a> {
+    pub key: DescriptionKey<'a>,
+    pub value: DescriptionValue<'a>,
+}
+
+impl<'a> TlvDecode<'a> for DescriptionEntry<'a> {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let (key_tlv, key_len) = TLV::try_decode(inner_bytes).ok()?;
+        if key_tlv.typ.get() != DescriptionKey::TLV_TYPE {
+            return None;
+        }
+        let (value_tlv, _) = TLV::try_decode(&inner_bytes[key_len..]).ok()?;
+        if value_tlv.typ.get() != DescriptionValue::TLV_TYPE {
+            return None;
+        }
+        Some(Self {
+            key: DescriptionKey { bytes: key_tlv.val },
+            value: DescriptionValue {
+                bytes: value_tlv.val,
+            },
+        })
+    }
+}
+
+impl<'a> TlvEncode for DescriptionEntry<'a> {
+    const TLV_TYPE: u32 = 512;
+
+    fn inner_length(&self) -> usize {
+        self.key.encoded_length() + self.value.encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.key.encode(writer)?;
+        self.value.encode(writer)
+    }
+}
+
+pub type DescriptionKey<'a> = TypedBytes<'a, 513>;
+pub type DescriptionValue<'a> = TypedBytes<'a, 514>;
+
 pub type SignatureType = TypedInteger<27, u64>;
 
 impl SignatureType {
@@ -755,16 +1543,49 @@ impl<'a, H: Hasher> crate::io::Write for EncodedHasher<'a, H> {
     }
 }
 
+struct EncodedSigner<'a, S: Signer + ?Sized> {
+    signer: &'a mut S,
+}
+
+impl<'a, S: Signer + ?Sized> crate::io::Write for EncodedSigner<'a, S> {
+    type Error = ();
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.signer.update(bytes);
+        Ok(())
+    }
+}
+
+struct EncodedVerifier<'a, V: Verifier + ?Sized> {
+    verifier: &'a mut V,
+}
+
+impl<'a, V: Verifier + ?Sized> crate::io::Write for EncodedVerifier<'a, V> {
+    type Error = ();
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.verifier.update(bytes);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::vec::Vec;
 
     use crate::{
-        io::Encode,
+        hash::Hasher,
+        io::{Decode, Encode},
+        name::{Name, NameBuf, NameComponent},
         packet::{
-            ContentType, InterestSignatureInfo, KeyLocator, MetaInfo, SignatureInfo, SignatureType,
+            ContentType, Data, DataBuf, DataBuilder, DescriptionEntry, DescriptionKey,
+            DescriptionValue, HopLimit, Interest, InterestBuf, InterestLifetime,
+            InterestSignatureInfo, KeyLocator, MetaInfo, PacketDecodingError, PacketDecodingReason,
+            SignatureInfo, SignatureType, ValidityPeriod, STRICT_MAX_NAME_COMPONENTS,
         },
-        tlv::TlvDecode,
+        platform::sha::Sha256Hasher,
+        security::{hmac::HmacSha256Signer, DigestSha256},
+        tlv::{TlvDecode, TlvEncode},
     };
 
     #[test]
@@ -923,6 +1744,59 @@ mod tests {
         assert!(buf.as_slice() == signature_outer_bytes);
     }
 
+    #[test]
+    fn test_validity_period() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"cert")];
+        let name = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"key")];
+        let key_name = root.adding_components(comp);
+
+        assert!(ValidityPeriod::new(b"20240101T000000", b"2025").is_none());
+        assert!(ValidityPeriod::new(b"20240101-000000", b"20250101T000000").is_none());
+        let validity_period = ValidityPeriod::new(b"20240101T000000", b"20250101T000000").unwrap();
+        assert!(validity_period.includes(b"20240101T000000"));
+        assert!(validity_period.includes(b"20240615T120000"));
+        assert!(validity_period.includes(b"20250101T000000"));
+        assert!(!validity_period.includes(b"20250101T000001"));
+        assert!(!validity_period.includes(b"20231231T235959"));
+
+        let mut description = Vec::new();
+        let entry = DescriptionEntry {
+            key: DescriptionKey { bytes: b"issuer" },
+            value: DescriptionValue { bytes: b"root" },
+        };
+        let _ = entry.encode(&mut description);
+
+        let mut signer = HmacSha256Signer::new(key_name, b"secret", Sha256Hasher::new());
+        let packet = DataBuilder::new(name)
+            .content_type(ContentType::KEY)
+            .validity_period(validity_period)
+            .additional_description(&description)
+            .sign(&mut signer);
+        let (data, _) = Data::try_decode(&packet).unwrap();
+        assert!(data.verify_signature(&mut signer));
+        let signature_info = &data.signature_info;
+        assert!(signature_info.key_locator.is_some());
+        let decoded = signature_info.validity_period.unwrap();
+        assert_eq!(decoded.not_before.bytes, b"20240101T000000");
+        assert_eq!(decoded.not_after.bytes, b"20250101T000000");
+        let mut entries = signature_info.additional_description.unwrap().entries();
+        let entry = entries.next().unwrap();
+        assert_eq!(entry.key.bytes, b"issuer");
+        assert_eq!(entry.value.bytes, b"root");
+        assert!(entries.next().is_none());
+
+        // Unknown elements are skipped, a malformed validity period is not
+        let signature_inner_bytes = &[27, 1, 0, 200, 1, 0];
+        let signature_info = SignatureInfo::try_decode_from_inner(signature_inner_bytes).unwrap();
+        assert!(signature_info.validity_period.is_none());
+        let signature_inner_bytes = &[
+            27, 1, 0, 253, 0, 253, 9, 253, 0, 254, 1, b'0', 253, 0, 255, 0,
+        ];
+        assert!(SignatureInfo::try_decode_from_inner(signature_inner_bytes).is_none());
+    }
+
     #[test]
     fn test_meta_info() {
         let meta_info_inner_bytes = &[24, 1, 0, 25, 4, 0x00, 0x06, 0x1A, 0x80, 126, 1, 1];
@@ -965,4 +1839,308 @@ mod tests {
         // Including hashing
         // Including hop byte
     }
+
+    #[test]
+    fn test_owned_packets() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"hello")];
+        let name = root.adding_components(comp);
+
+        let mut interest_bytes = Vec::new();
+        let mut interest = Interest::new(name, true, [1, 2, 3, 4]);
+        interest.unknown_tlvs[1] = &[200, 1, 7];
+        let _ = interest.encode(&mut interest_bytes);
+
+        let interest_buf = {
+            let received = interest_bytes.clone();
+            let (interest_buf, len) = InterestBuf::try_decode(&received).unwrap();
+            assert_eq!(len, received.len());
+            interest_buf
+        };
+        let interest = interest_buf.as_interest();
+        assert!(interest.name == name);
+        assert!(interest.can_be_prefix.is_some());
+        assert_eq!(interest.nonce.unwrap().bytes, [1, 2, 3, 4]);
+        assert_eq!(interest.unknown_tlvs[1], &[200, 1, 7]);
+        let mut encoded = Vec::new();
+        let _ = interest_buf.encode(&mut encoded);
+        assert_eq!(encoded, interest_bytes);
+        assert_eq!(InterestBuf::from(&interest), interest_buf);
+
+        let mut data_bytes = Vec::new();
+        let mut data = Data::new_unsigned(name, b"world", SignatureInfo::new_digest_sha256());
+        data.unknown_tlvs[2] = &[202, 0];
+        let _ = data.encode(&mut data_bytes);
+
+        let data_buf = DataBuf::from(&data);
+        let data = data_buf.as_data();
+        assert_eq!(data.content.unwrap().bytes, b"world");
+        assert_eq!(data.unknown_tlvs[2], &[202, 0]);
+        let mut encoded = Vec::new();
+        let _ = data_buf.encode(&mut encoded);
+        assert_eq!(encoded, data_bytes);
+
+        // Malformed packets are rejected
+        assert!(DataBuf::try_decode(&interest_bytes).is_err());
+        assert!(InterestBuf::try_decode_from_inner(&[8, 1]).is_none());
+    }
+
+    #[test]
+    fn test_full_name() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"hello")];
+        let name = root.adding_components(comp);
+        let packet = DataBuilder::new(name)
+            .content(b"world")
+            .digest_sign(&mut Sha256Hasher::new());
+        let (data, _) = Data::try_decode(&packet).unwrap();
+
+        let mut hasher = Sha256Hasher::new();
+        hasher.update(&packet);
+        let digest = hasher.finalize_reset();
+
+        let full_name = data.full_name(&mut hasher);
+        let full_name = full_name.as_name();
+        assert_eq!(full_name.component_count(), 2);
+        assert!(name.is_prefix_of(full_name));
+        let last = full_name.components().last().unwrap();
+        assert!(last == NameComponent::implicit_sha256(&digest.0));
+
+        // The owned packet computes the digest only once
+        let mut data_buf = DataBuf::from(&data);
+        assert_eq!(data_buf.cached_implicit_digest(), None);
+        assert_eq!(data_buf.implicit_digest(&mut hasher), digest);
+        assert_eq!(data_buf.cached_implicit_digest(), Some(digest));
+        assert!(data_buf.full_name(&mut hasher).as_name() == full_name);
+        assert!(data_buf == DataBuf::from(&data));
+    }
+
+    #[test]
+    fn test_decode_diagnosed() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"hello")];
+        let name = root.adding_components(comp);
+
+        let mut packet = Vec::new();
+        let _ = Interest::new(name, false, [1, 2, 3, 4]).encode(&mut packet);
+        let (interest, len) = Interest::try_decode_diagnosed(&packet).unwrap();
+        assert!(interest.name == name);
+        assert_eq!(len, packet.len());
+
+        // Type 5, length, Name (9 bytes), then the nonce at offset 11
+        assert_eq!(packet[11], 10);
+        assert_eq!(
+            Data::try_decode_diagnosed(&packet).err().unwrap(),
+            PacketDecodingError {
+                typ: Some(5),
+                offset: 0,
+                reason: PacketDecodingReason::WrongPacketType
+            }
+        );
+        let err = Interest::try_decode_diagnosed(&packet[..packet.len() - 1]).err();
+        assert_eq!(err.unwrap().reason, PacketDecodingReason::Truncated);
+
+        // A critical unknown TLV before the nonce
+        let mut interest = Interest::new(name, false, [1, 2, 3, 4]);
+        interest.unknown_tlvs[0] = &[201, 0];
+        let mut critical = Vec::new();
+        let _ = interest.encode(&mut critical);
+        assert_eq!(
+            Interest::try_decode_diagnosed(&critical).err().unwrap(),
+            PacketDecodingError {
+                typ: Some(201),
+                offset: 11,
+                reason: PacketDecodingReason::CriticalUnknownType
+            }
+        );
+        assert!(Interest::try_decode(&critical).is_err());
+
+        // CanBePrefix after the nonce
+        let mut out_of_order = packet.clone();
+        out_of_order[1] += 2;
+        out_of_order.extend_from_slice(&[33, 0]);
+        let err = Interest::try_decode_diagnosed(&out_of_order).err().unwrap();
+        assert_eq!(err.typ, Some(33));
+        assert_eq!(err.offset, packet.len());
+        assert_eq!(err.reason, PacketDecodingReason::OutOfOrder);
+
+        // A nonce of the wrong size
+        let mut invalid = packet.clone();
+        invalid[1] -= 1;
+        invalid[12] -= 1;
+        invalid.pop();
+        let err = Interest::try_decode_diagnosed(&invalid).err().unwrap();
+        assert_eq!((err.typ, err.offset), (Some(10), 11));
+        assert_eq!(err.reason, PacketDecodingReason::InvalidValue);
+
+        // Data without the signature
+        let data = Data::new_unsigned(name, b"world", SignatureInfo::new_digest_sha256());
+        let mut unsigned = Vec::new();
+        let _ = data.encode_inner(&mut unsigned);
+        let signature_info_len = data.signature_info.encoded_length();
+        unsigned.truncate(unsigned.len() - signature_info_len - 2);
+        let mut packet = Vec::from([6, unsigned.len() as u8]);
+        packet.extend_from_slice(&unsigned);
+        let err = Data::try_decode_diagnosed(&packet).err().unwrap();
+        assert_eq!(err.offset, packet.len());
+        assert_eq!(err.reason, PacketDecodingReason::MissingSignature);
+    }
+
+    #[test]
+    fn test_decode_strict() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"hello")];
+        let name = root.adding_components(comp);
+
+        let mut interest = Interest::new(name, false, [1, 2, 3, 4]);
+        interest.interest_lifetime = Some(InterestLifetime { val: 100 });
+        interest.hop_limit = Some(HopLimit { val: 5 });
+        let mut packet = Vec::new();
+        let _ = interest.encode(&mut packet);
+        assert!(Interest::try_decode_strict(&packet).is_ok());
+
+        // The lifetime 100 written with two bytes
+        let mut lifetime = packet.clone();
+        let index = lifetime.iter().position(|b| *b == 12).unwrap();
+        lifetime.splice(index + 1..index + 3, [2, 0, 100]);
+        lifetime[1] += 1;
+        assert!(Interest::try_decode(&lifetime).is_ok());
+        let err = Interest::try_decode_strict(&lifetime).err().unwrap();
+        assert_eq!((err.typ, err.offset), (Some(12), index));
+        assert_eq!(err.reason, PacketDecodingReason::NotMinimal);
+
+        // The HopLimit written with two bytes
+        let mut hop_limit = packet.clone();
+        let last = hop_limit.len() - 3;
+        hop_limit.splice(last.., [34, 2, 0, 5]);
+        hop_limit[1] += 1;
+        assert!(Interest::try_decode(&hop_limit).is_ok());
+        let err = Interest::try_decode_strict(&hop_limit).err().unwrap();
+        assert_eq!(err.reason, PacketDecodingReason::InvalidValue);
+
+        // Names that are too long or empty
+        let component = NameComponent::generic(b"a");
+        let long = (0..=STRICT_MAX_NAME_COMPONENTS)
+            .fold(NameBuf::new(), |name, _| name.pushing(component));
+        let mut packet = Vec::new();
+        let _ = Interest::new(long.as_name(), false, [1, 2, 3, 4]).encode(&mut packet);
+        assert!(Interest::try_decode(&packet).is_ok());
+        let err = Interest::try_decode_strict(&packet).err().unwrap();
+        assert_eq!(err.reason, PacketDecodingReason::TooManyComponents);
+        let mut packet = Vec::new();
+        let _ = Interest::new(root, false, [1, 2, 3, 4]).encode(&mut packet);
+        assert!(Interest::try_decode_strict(&packet).is_err());
+
+        // The FreshnessPeriod 10 written with two bytes
+        let mut packet = DataBuilder::new(name)
+            .freshness_period(10)
+            .digest_sign(&mut Sha256Hasher::new());
+        assert!(Data::try_decode_strict(&packet).is_ok());
+        let index = packet.iter().position(|b| *b == 25).unwrap();
+        packet.splice(index + 1..index + 3, [2, 0, 10]);
+        packet[1] += 1;
+        packet[index - 1] += 1;
+        assert!(Data::try_decode(&packet).is_ok());
+        let err = Data::try_decode_strict(&packet).err().unwrap();
+        assert_eq!((err.typ, err.offset), (Some(20), index - 2));
+        assert_eq!(err.reason, PacketDecodingReason::NotMinimal);
+    }
+
+    #[test]
+    fn test_reexpressed() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"hello")];
+        let name = root.adding_components(comp);
+
+        let mut interest = Interest::new(name, true, [1, 2, 3, 4]);
+        interest.unknown_tlvs[1] = &[200, 1, 7];
+        let original = InterestBuf::from(&interest);
+
+        // A nonce equal to the previous one is never used
+        let mut nonces = [[1, 2, 3, 4], [5, 6, 7, 8]].into_iter();
+        let retry = original.reexpressed(|| nonces.next().unwrap(), Some(500));
+        let interest = retry.as_interest();
+        assert_eq!(interest.nonce.unwrap().bytes, [5, 6, 7, 8]);
+        assert_eq!(interest.interest_lifetime.unwrap().val, 500);
+        assert!(interest.name == name);
+        assert!(interest.can_be_prefix.is_some());
+        assert_eq!(interest.unknown_tlvs[1], &[200, 1, 7]);
+
+        // The lifetime is kept unless a new one is given
+        let retry = retry.reexpressed(|| [9, 9, 9, 9], None);
+        let interest = retry.as_interest();
+        assert_eq!(interest.nonce.unwrap().bytes, [9, 9, 9, 9]);
+        assert_eq!(interest.interest_lifetime.unwrap().val, 500);
+    }
+
+    #[test]
+    fn test_data_builder() {
+        let root = Name::new();
+        let comp = &[
+            NameComponent::generic(b"file"),
+            NameComponent::generic(b"0"),
+        ];
+        let name = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"key")];
+        let key_name = root.adding_components(comp);
+
+        let packet = DataBuilder::new(name)
+            .content(b"hello")
+            .content_type(ContentType::KEY)
+            .freshness_period(1000)
+            .final_block_id(NameComponent::generic(b"9"))
+            .digest_sign(&mut Sha256Hasher::new());
+        let (data, len) = Data::try_decode(&packet).unwrap();
+        assert_eq!(len, packet.len());
+        assert!(data.name == name);
+        assert_eq!(data.content.unwrap().bytes, b"hello");
+        let meta_info = data.meta_info.as_ref().unwrap();
+        assert_eq!(meta_info.content_type.unwrap().val, ContentType::KEY);
+        assert_eq!(meta_info.freshness_period.unwrap().val, 1000);
+        assert_eq!(meta_info.final_block_id.unwrap().component.bytes, b"9");
+        assert_eq!(
+            data.signature_info.signature_type.val,
+            SignatureType::DIGEST_SHA256
+        );
+        assert!(data.verify_signature(&mut DigestSha256::new(Sha256Hasher::new())));
+
+        // Without any of its fields there is no MetaInfo at all
+        let mut signer = HmacSha256Signer::new(key_name, b"secret", Sha256Hasher::new());
+        let packet = DataBuilder::new(name).sign(&mut signer);
+        let (data, _) = Data::try_decode(&packet).unwrap();
+        assert!(data.meta_info.is_none());
+        assert!(data.content.is_none());
+        match &data.signature_info.key_locator {
+            Some(KeyLocator::Name(locator)) => assert!(*locator == key_name),
+            _ => panic!(),
+        }
+        assert!(data.verify_signature(&mut signer));
+        assert_eq!(data.content_type(), ContentType::BLOB);
+        assert!(!data.is_nack());
+    }
+
+    #[test]
+    fn test_app_nack() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"missing")];
+        let name = root.adding_components(comp);
+
+        let packet = DataBuilder::nack(name).digest_sign(&mut Sha256Hasher::new());
+        let (data, _) = Data::try_decode(&packet).unwrap();
+        assert!(data.name == name);
+        assert!(data.is_nack());
+        assert_eq!(data.content.unwrap().bytes, b"");
+        let meta_info = data.meta_info.as_ref().unwrap();
+        assert_eq!(
+            meta_info.freshness_period.unwrap().val,
+            DataBuilder::DEFAULT_NACK_FRESHNESS_PERIOD
+        );
+
+        let packet = DataBuilder::nack(name)
+            .freshness_period(0)
+            .digest_sign(&mut Sha256Hasher::new());
+        let (data, _) = Data::try_decode(&packet).unwrap();
+        assert!(data.is_nack());
+        assert_eq!(data.meta_info.unwrap().freshness_period.unwrap().val, 0);
+    }
 }
@@ -19,6 +19,7 @@ pub mod sha {
 
     use crate::hash::{Hasher, Sha256Digest};
 
+    #[derive(Clone)]
     pub struct Sha256Hasher {
         inner: Sha256,
     }
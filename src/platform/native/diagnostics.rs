@@ -0,0 +1,19 @@
+use core::fmt::Arguments;
+use std::io::Write;
+
+use crate::diagnostics::{DiagnosticsSink, Level, Subsystem};
+
+// Writes every diagnostic message as a line to the standard error.
+pub struct StderrDiagnostics {}
+
+impl DiagnosticsSink for StderrDiagnostics {
+    fn record(&mut self, subsystem: Subsystem, level: Level, message: Arguments<'_>) {
+        let _ = writeln!(
+            std::io::stderr().lock(),
+            "[{}] {:?}: {}",
+            subsystem.name(),
+            level,
+            message
+        );
+    }
+}
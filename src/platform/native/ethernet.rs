@@ -0,0 +1,275 @@
+use std::{
+    ffi::CString,
+    io::{Error, ErrorKind},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use crate::platform::native::{face_error_from_io, notifying::Notifying};
+use crate::{
+    face::{FaceError, FaceReceiver, FaceSender},
+    forwarder::MAX_PACKET_SIZE,
+    io::Decode,
+    tlv::TLV,
+};
+
+use super::notifying::{SocketId, Waker};
+
+// The EtherType of the NDN packets, sent without the IP
+pub const NDN_ETHERTYPE: u16 = 0x8624;
+
+// The group that all the NDN nodes of a segment listen to
+pub const NDN_MULTICAST_ADDRESS: [u8; 6] = [0x01, 0x00, 0x5e, 0x00, 0x17, 0xaa];
+
+pub struct EthernetSender {
+    socket: OwnedFd,
+    buffer: Vec<u8>,
+    address: libc::sockaddr_ll,
+}
+
+pub struct EthernetReceiver {
+    socket: OwnedFd,
+    remote: [u8; 6],
+}
+
+impl FaceSender for EthernetSender {
+    fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+        self.buffer.extend_from_slice(src);
+        Ok(src.len())
+    }
+
+    fn flush(&mut self) -> Result<(), FaceError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        // SAFETY: the buffer and the address outlive the call
+        let sent = unsafe {
+            libc::sendto(
+                self.socket.as_raw_fd(),
+                self.buffer.as_ptr() as *const libc::c_void,
+                self.buffer.len(),
+                0,
+                &self.address as *const libc::sockaddr_ll as *const libc::sockaddr,
+                core::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if sent >= 0 {
+            self.buffer.drain(..sent as usize);
+            return Ok(());
+        }
+        let io_err = Error::last_os_error();
+        match io_err.kind() {
+            ErrorKind::WouldBlock => Ok(()),
+            _ => {
+                // The frame is dropped so that it does not poison the next ones
+                self.buffer.clear();
+                Err(face_error_from_io(&io_err))
+            }
+        }
+    }
+}
+
+impl FaceReceiver for EthernetReceiver {
+    fn try_recv(&mut self, dst: &mut [u8]) -> Result<usize, FaceError> {
+        loop {
+            // SAFETY: all zeroes is a valid sockaddr_ll
+            let mut address: libc::sockaddr_ll = unsafe { core::mem::zeroed() };
+            let mut length = core::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+            // SAFETY: the buffer and the address outlive the call, with their lengths
+            let received = unsafe {
+                libc::recvfrom(
+                    self.socket.as_raw_fd(),
+                    dst.as_mut_ptr() as *mut libc::c_void,
+                    dst.len(),
+                    0,
+                    &mut address as *mut libc::sockaddr_ll as *mut libc::sockaddr,
+                    &mut length,
+                )
+            };
+            if received < 0 {
+                let io_err = Error::last_os_error();
+                return match io_err.kind() {
+                    ErrorKind::WouldBlock => Ok(0),
+                    _ => Err(face_error_from_io(&io_err)),
+                };
+            }
+            if !self.accepts(&address) {
+                continue;
+            }
+            // The short frames are padded, so only the packet is kept. The frames
+            //  that do not hold a whole packet are dropped.
+            if let Ok((_, len)) = TLV::try_decode(&dst[..received as usize]) {
+                return Ok(len);
+            }
+        }
+    }
+}
+
+impl EthernetReceiver {
+    // All the faces of the interface get all its NDN frames, so each only takes
+    //  those of its peer, or those sent to a group for a multicast face
+    fn accepts(&self, address: &libc::sockaddr_ll) -> bool {
+        match address.sll_pkttype {
+            libc::PACKET_MULTICAST => is_multicast(&self.remote),
+            libc::PACKET_HOST => {
+                !is_multicast(&self.remote) && address.sll_addr[..6] == self.remote[..]
+            }
+            // Including the frames that this host sent
+            _ => false,
+        }
+    }
+}
+
+impl Notifying for EthernetReceiver {
+    fn socket_id(&self) -> Option<SocketId> {
+        Some(SocketId(self.socket.try_clone().ok()?))
+    }
+
+    fn register_waker(&mut self, _waker: Waker) {}
+}
+
+// A face to the peer with the MAC address on the interface, e.g. "eth0", or to all
+//  the NDN nodes of the segment with NDN_MULTICAST_ADDRESS, or another group.
+// Needs CAP_NET_RAW. The packets must fit in the MTU of the interface, so the larger
+//  ones need the fragmentation of the LP.
+pub fn ethernet_face(
+    interface: &str,
+    remote: [u8; 6],
+) -> Result<(EthernetSender, EthernetReceiver), Error> {
+    let name = CString::new(interface).map_err(|_| Error::from(ErrorKind::InvalidInput))?;
+    // SAFETY: the name is terminated by a zero
+    let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    if index == 0 {
+        return Err(Error::last_os_error());
+    }
+
+    // SAFETY: no pointers are involved
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_PACKET,
+            libc::SOCK_DGRAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            NDN_ETHERTYPE.to_be() as libc::c_int,
+        )
+    };
+    if fd < 0 {
+        return Err(Error::last_os_error());
+    }
+    // SAFETY: the descriptor was just opened and nothing else owns it
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut address = link_address(index as libc::c_int);
+    // Only the frames of the interface
+    // SAFETY: the address outlives the call
+    let bound = unsafe {
+        libc::bind(
+            fd,
+            &address as *const libc::sockaddr_ll as *const libc::sockaddr,
+            core::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+        )
+    };
+    if bound < 0 {
+        return Err(Error::last_os_error());
+    }
+    if is_multicast(&remote) {
+        // SAFETY: all zeroes is a valid packet_mreq
+        let mut membership: libc::packet_mreq = unsafe { core::mem::zeroed() };
+        membership.mr_ifindex = index as libc::c_int;
+        membership.mr_type = libc::PACKET_MR_MULTICAST as libc::c_ushort;
+        membership.mr_alen = 6;
+        membership.mr_address[..6].copy_from_slice(&remote);
+        // Left when the socket is closed
+        // SAFETY: the membership outlives the call
+        let joined = unsafe {
+            libc::setsockopt(
+                fd,
+                libc::SOL_PACKET,
+                libc::PACKET_ADD_MEMBERSHIP,
+                &membership as *const libc::packet_mreq as *const libc::c_void,
+                core::mem::size_of::<libc::packet_mreq>() as libc::socklen_t,
+            )
+        };
+        if joined < 0 {
+            return Err(Error::last_os_error());
+        }
+    }
+
+    address.sll_halen = 6;
+    address.sll_addr[..6].copy_from_slice(&remote);
+    let sender = EthernetSender {
+        socket: socket.try_clone()?,
+        buffer: Vec::with_capacity(MAX_PACKET_SIZE),
+        address,
+    };
+    let receiver = EthernetReceiver { socket, remote };
+    Ok((sender, receiver))
+}
+
+fn link_address(index: libc::c_int) -> libc::sockaddr_ll {
+    // SAFETY: all zeroes is a valid sockaddr_ll
+    let mut address: libc::sockaddr_ll = unsafe { core::mem::zeroed() };
+    address.sll_family = libc::AF_PACKET as libc::c_ushort;
+    address.sll_protocol = NDN_ETHERTYPE.to_be();
+    address.sll_ifindex = index;
+    address
+}
+
+fn is_multicast(address: &[u8; 6]) -> bool {
+    address[0] & 1 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        face::{FaceReceiver, FaceSender},
+        io::Encode,
+        name::{Name, NameComponent},
+        packet::Interest,
+        platform::ethernet::{ethernet_face, NDN_MULTICAST_ADDRESS},
+    };
+
+    fn recv_within(receiver: &mut impl FaceReceiver, dst: &mut [u8]) -> usize {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(1) {
+            let len = receiver.try_recv(dst).unwrap();
+            if len > 0 {
+                return len;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        0
+    }
+
+    // Needs CAP_NET_RAW, run with "sudo -E cargo test ethernet -- --ignored"
+    #[test]
+    #[ignore]
+    fn test_ethernet_face() {
+        let comps = [NameComponent::generic(b"ethernet")];
+        let root = Name::new();
+        let name = root.adding_components(&comps);
+        let mut interest = Vec::new();
+        let _ = Interest::new(name, false, [1; 4]).encode(&mut interest);
+        let mut buffer = [0u8; 1024];
+
+        // The loopback comes back from the all-zero address, padded as a short frame
+        let (mut sender, mut own) = ethernet_face("lo", [0; 6]).unwrap();
+        let (_, mut peer) = ethernet_face("lo", [0; 6]).unwrap();
+        let (_, mut other) = ethernet_face("lo", [2, 0, 0, 0, 0, 1]).unwrap();
+        assert_eq!(sender.try_send(&interest), Ok(interest.len()));
+        assert!(sender.flush().is_ok());
+        let len = recv_within(&mut peer, &mut buffer);
+        assert_eq!(&buffer[..len], &interest[..]);
+        assert_eq!(recv_within(&mut own, &mut buffer), interest.len());
+        // Not from its peer
+        assert_eq!(other.try_recv(&mut buffer), Ok(0));
+
+        // Only the multicast faces get the frames sent to the group
+        let (mut group_sender, _) = ethernet_face("lo", NDN_MULTICAST_ADDRESS).unwrap();
+        let (_, mut group) = ethernet_face("lo", NDN_MULTICAST_ADDRESS).unwrap();
+        assert_eq!(group_sender.try_send(&interest), Ok(interest.len()));
+        assert!(group_sender.flush().is_ok());
+        let len = recv_within(&mut group, &mut buffer);
+        assert_eq!(&buffer[..len], &interest[..]);
+        assert_eq!(peer.try_recv(&mut buffer), Ok(0));
+    }
+}
@@ -1,9 +1,12 @@
 use core::{marker::PhantomData, time::Duration};
 
 use std::{
+    cell::RefCell,
     collections::VecDeque,
+    net::{SocketAddr, TcpStream, UdpSocket},
+    rc::Rc,
     sync::{
-        mpsc::{Sender, TryRecvError},
+        mpsc::{Receiver, SendError, Sender, TryRecvError},
         Arc, Mutex,
     },
     thread::Thread,
@@ -12,12 +15,29 @@ use std::{
 
 use crate::{
     clock::Clock,
-    face::{FaceReceiver, FaceSender},
-    forwarder::{FaceToken, Forwarder, ForwarderError, ForwarderMetrics},
+    diagnostics::{Diagnostics, Level, Subsystem},
+    face::{lp::LpFragmenter, FaceReceiver, FaceSender},
+    forwarder::{
+        CongestionMarking, EgressScheduling, FaceToken, Forwarder, ForwarderError,
+        ForwarderMetrics, ForwarderObserver, InboundPacketFilter, OutboundQueueStats,
+        PacketCapture, PacketFilter, Persistency, ReadinessReport, RefreshAheadHook, ReliableFetch,
+        Scope, TrafficClass,
+    },
     hash::{Hasher, Sha256Digest},
-    name::Name,
-    platform::native::notifying::{Notifying, SocketId, Waker},
-    tables::Tables,
+    mgmt::{BoxedFace, CommandValidator, FaceCreationError, FaceFactory},
+    name::{Name, NameBuf},
+    nonce::NonceSource,
+    platform::native::{
+        nonce::RandomNonces,
+        notifying::{Notifying, SocketId, Waker},
+        tcp::tcp_face,
+        udp::udp_face,
+    },
+    stats::PrefixStats,
+    tables::{
+        rib::{RegistrationPolicy, Rib, Route, RouteOrigin},
+        ContentStoreUsage, Tables,
+    },
 };
 
 pub struct BlockingForwarder<C, H, M, T>
@@ -31,8 +51,16 @@ where
     local_queue: VecDeque<FaceToken>,
     shared_queue: FaceQueue,
     forwarding_thread: Thread,
-    poller_sender: Sender<PollerMessage>,
+    poller_sender: PollerSender,
     socket_faces: Vec<FaceToken>,
+    // The socket faces whose writability is polled, because their output is queued
+    writable_faces: Vec<FaceToken>,
+    socket_face_changes: Rc<RefCell<SocketFaceChanges>>,
+    // The requests of the control handles, applied between the forwarding rounds
+    control_sender: Sender<ControlMessage>,
+    control_receiver: Receiver<ControlMessage>,
+    timers: Vec<Timer<C, H, M, T>>,
+    next_timer_token: u64,
     _marker: PhantomData<*const ()>, // !Send
 }
 
@@ -44,24 +72,35 @@ where
     T: Tables,
 {
     pub fn new(clock: C, hasher: H, metrics: M, tables: T) -> Self {
-        let forwarder = Forwarder::new(clock, hasher, metrics, tables);
+        Self::from_forwarder(Forwarder::new(clock, hasher, metrics, tables))
+    }
+
+    // Wraps a forwarder that was already configured, e.g. by a ForwarderBuilder.
+    // Its nonces then come from RandomNonces, as with new.
+    pub fn from_forwarder(mut forwarder: Forwarder<C, H, M, T>) -> Self {
+        forwarder.set_nonce_source(RandomNonces::new());
         let shared_queue = FaceQueue::new();
         let poller_queue = shared_queue.clone();
         let forwarding_thread = std::thread::current();
         let wakeup_thread = forwarding_thread.clone();
-        let (poller_sender, poller_receiver) = std::sync::mpsc::channel();
+        let (sender, poller_receiver) = std::sync::mpsc::channel();
+        let (control_sender, control_receiver) = std::sync::mpsc::channel();
+        let poller = poller::ReadPoller::new();
+        let poller_sender = PollerSender {
+            sender: Some(sender),
+            notifier: poller.as_ref().ok().map(|poller| poller.notifier()),
+        };
 
         // We spin up another thread on which we listen to socket events and notify the queue
         std::thread::spawn(move || {
             let mut latest_faces = Vec::with_capacity(32);
-            let mut poller = match poller::ReadPoller::new() {
+            let mut poller = match poller {
                 Ok(poller) => poller,
                 Err(_) => return,
             };
 
-            // This is the timeout that we wait while polling
-            //  (and the latency with which we process register/unregister messages)
-            let timeout = Some(Duration::from_millis(100));
+            // The messages wake the poller up, so it waits for as long as needed
+            let timeout = None;
 
             'poll: loop {
                 'recv: loop {
@@ -71,6 +110,9 @@ where
                                 poller.register(face, socket)
                             }
                             PollerMessage::Unregister { face } => poller.unregister(face),
+                            PollerMessage::WatchWritable { face, writable } => {
+                                poller.watch_writable(face, writable)
+                            }
                         },
                         Err(TryRecvError::Disconnected) => break 'poll, // Disconnected, so we exit
                         Err(TryRecvError::Empty) => break 'recv, // Empty, so we do the polling
@@ -94,17 +136,82 @@ where
             forwarding_thread,
             poller_sender,
             socket_faces: Vec::new(),
+            writable_faces: Vec::new(),
+            socket_face_changes: Rc::default(),
+            control_sender,
+            control_receiver,
+            timers: Vec::new(),
+            next_timer_token: 0,
             _marker: PhantomData::default(),
         }
     }
 
+    // A handle through which the other threads manage the faces and the routes.
+    // Their requests are only applied while this forwarder is in forward.
+    pub fn control_handle(&self) -> ControlHandle {
+        ControlHandle {
+            sender: self.control_sender.clone(),
+            forwarding_thread: self.forwarding_thread.clone(),
+        }
+    }
+
     pub fn add_face<FS, FR>(&mut self, sender: FS, mut receiver: FR) -> Option<FaceToken>
     where
         FS: FaceSender + 'static,
         FR: FaceReceiver + Notifying + 'static,
     {
         let face = self.forwarder.next_face_token()?;
+        self.register_face(face, &mut receiver);
+
+        match self.forwarder.add_face(sender, receiver) {
+            Some(token) => assert!(token == face),
+            None => todo!(), // TODO: handle error here
+        }
+
+        Some(face)
+    }
+
+    // See Forwarder::add_face_with_scope
+    pub fn add_face_with_scope<FS, FR>(
+        &mut self,
+        sender: FS,
+        receiver: FR,
+        scope: Scope,
+    ) -> Option<FaceToken>
+    where
+        FS: FaceSender + 'static,
+        FR: FaceReceiver + Notifying + 'static,
+    {
+        let face = self.add_face(sender, receiver)?;
+        self.forwarder.set_face_scope(face, scope);
+        Some(face)
+    }
 
+    // See Forwarder::add_face_with_mtu
+    pub fn add_face_with_mtu<FS, FR>(
+        &mut self,
+        sender: FS,
+        mut receiver: FR,
+        mtu: usize,
+    ) -> Option<FaceToken>
+    where
+        FS: FaceSender + 'static,
+        FR: FaceReceiver + Notifying + 'static,
+    {
+        // Checked first so that a face is never registered without being added
+        LpFragmenter::new(mtu)?;
+        let face = self.forwarder.next_face_token()?;
+        self.register_face(face, &mut receiver);
+
+        if self.forwarder.add_face_with_mtu(sender, receiver, mtu) != Some(face) {
+            self.unregister_face(face);
+            return None;
+        }
+
+        Some(face)
+    }
+
+    fn register_face<FR: Notifying + ?Sized>(&mut self, face: FaceToken, receiver: &mut FR) {
         // Want to register with poller, if this is a socket
         if let Some(socket) = receiver.socket_id() {
             let _ = self
@@ -121,35 +228,297 @@ where
             self.shared_queue.clone(),
         );
         receiver.register_waker(waker);
+    }
 
-        match self.forwarder.add_face(sender, receiver) {
-            Some(token) => assert!(token == face),
-            None => todo!(), // TODO: handle error here
+    // Undoes register_face
+    fn unregister_face(&mut self, face: FaceToken) {
+        let _ = self.poller_sender.send(PollerMessage::Unregister { face });
+        if let Ok(index) = self.socket_faces.binary_search(&face) {
+            self.socket_faces.remove(index);
         }
+    }
 
-        Some(face)
+    // Calls the callback every period, first after one period, from forward. Meant for
+    //  the periodic work of the applications on the forwarding thread, e.g. refreshing
+    //  their registrations. A callback that falls behind is not called to catch up.
+    pub fn add_timer<F>(&mut self, period: Duration, callback: F) -> TimerToken
+    where
+        F: FnMut(&mut Self) + 'static,
+    {
+        let token = TimerToken(self.next_timer_token);
+        self.next_timer_token += 1;
+        self.timers.push(Timer {
+            token,
+            period,
+            next_run: Instant::now() + period,
+            callback: Some(Box::new(callback)),
+        });
+        token
+    }
+
+    // Can be called from the callback of the timer itself
+    pub fn remove_timer(&mut self, token: TimerToken) -> bool {
+        let count = self.timers.len();
+        self.timers.retain(|timer| timer.token != token);
+        self.timers.len() < count
+    }
+
+    pub fn has_face(&self, token: FaceToken) -> bool {
+        self.forwarder.has_face(token)
     }
 
     pub fn remove_face(&mut self, token: FaceToken) -> bool {
-        let _ = self
-            .poller_sender
-            .send(PollerMessage::Unregister { face: token });
-        if let Ok(index) = self.socket_faces.binary_search(&token) {
-            self.socket_faces.remove(index);
-        }
+        self.unregister_face(token);
         self.forwarder.remove_face(token)
     }
 
+    // See Forwarder::replace_face, the new receiver is registered with the poller instead
+    //  of the old one.
+    pub fn replace_face<FS, FR>(&mut self, token: FaceToken, sender: FS, mut receiver: FR) -> bool
+    where
+        FS: FaceSender + 'static,
+        FR: FaceReceiver + Notifying + 'static,
+    {
+        if !self.forwarder.has_face(token) {
+            return false;
+        }
+
+        self.unregister_face(token);
+        if let Some(socket) = receiver.socket_id() {
+            let _ = self.poller_sender.send(PollerMessage::Register {
+                face: token,
+                socket,
+            });
+            self.socket_faces.push(token);
+            self.socket_faces.sort();
+        }
+
+        let waker = Waker::new(
+            self.forwarding_thread.clone(),
+            token,
+            self.shared_queue.clone(),
+        );
+        receiver.register_waker(waker);
+
+        self.forwarder.replace_face(token, sender, receiver)
+    }
+
+    pub fn set_inbound_packet_filter(
+        &mut self,
+        token: FaceToken,
+        filter: InboundPacketFilter,
+    ) -> bool {
+        self.forwarder.set_inbound_packet_filter(token, filter)
+    }
+
+    pub fn set_default_hop_limit(&mut self, token: FaceToken, hop_limit: Option<u8>) -> bool {
+        self.forwarder.set_default_hop_limit(token, hop_limit)
+    }
+
+    pub fn set_nack_on_no_route(&mut self, token: FaceToken, enabled: bool) -> bool {
+        self.forwarder.set_nack_on_no_route(token, enabled)
+    }
+
+    pub fn set_face_persistency(&mut self, token: FaceToken, persistency: Persistency) -> bool {
+        self.forwarder.set_face_persistency(token, persistency)
+    }
+
+    pub fn set_idle_timeout(&mut self, token: FaceToken, timeout_ms: Option<u64>) -> bool {
+        self.forwarder.set_idle_timeout(token, timeout_ms)
+    }
+
+    pub fn set_face_scope(&mut self, token: FaceToken, scope: Scope) -> bool {
+        self.forwarder.set_face_scope(token, scope)
+    }
+
+    pub fn face_scope(&self, token: FaceToken) -> Option<Scope> {
+        self.forwarder.face_scope(token)
+    }
+
+    pub fn set_outbound_queue_limit(&mut self, token: FaceToken, limit: usize) -> bool {
+        self.forwarder.set_outbound_queue_limit(token, limit)
+    }
+
+    pub fn outbound_queue(&self, token: FaceToken) -> Option<OutboundQueueStats> {
+        self.forwarder.outbound_queue(token)
+    }
+
+    pub fn set_congestion_marking(
+        &mut self,
+        token: FaceToken,
+        marking: Option<CongestionMarking>,
+    ) -> bool {
+        self.forwarder.set_congestion_marking(token, marking)
+    }
+
+    pub fn set_egress_scheduling(
+        &mut self,
+        token: FaceToken,
+        scheduling: Option<EgressScheduling>,
+    ) -> bool {
+        self.forwarder.set_egress_scheduling(token, scheduling)
+    }
+
+    pub fn set_traffic_class(
+        &mut self,
+        token: FaceToken,
+        prefix: Name<'_>,
+        class: Option<TrafficClass>,
+    ) -> bool {
+        self.forwarder.set_traffic_class(token, prefix, class)
+    }
+
+    pub fn metrics(&self) -> &M {
+        self.forwarder.metrics()
+    }
+
+    pub fn metrics_mut(&mut self) -> &mut M {
+        self.forwarder.metrics_mut()
+    }
+
+    pub fn diagnostics(&self) -> &Diagnostics {
+        self.forwarder.diagnostics()
+    }
+
+    pub fn diagnostics_mut(&mut self) -> &mut Diagnostics {
+        self.forwarder.diagnostics_mut()
+    }
+
+    pub fn prefix_stats(&self) -> &PrefixStats {
+        self.forwarder.prefix_stats()
+    }
+
+    pub fn prefix_stats_mut(&mut self) -> &mut PrefixStats {
+        self.forwarder.prefix_stats_mut()
+    }
+
+    pub fn content_store_usage(&self) -> ContentStoreUsage {
+        self.forwarder.content_store_usage()
+    }
+
+    pub fn erase_cached_data(&mut self, prefix: Name<'_>, limit: Option<usize>) -> usize {
+        self.forwarder.erase_cached_data(prefix, limit)
+    }
+
+    pub fn tables(&self) -> &T {
+        self.forwarder.tables()
+    }
+
+    pub fn tables_mut(&mut self) -> &mut T {
+        self.forwarder.tables_mut()
+    }
+
+    pub fn set_refresh_ahead_hook<R>(&mut self, lead_time_ms: u64, min_hits: u32, hook: R)
+    where
+        R: RefreshAheadHook + 'static,
+    {
+        self.forwarder
+            .set_refresh_ahead_hook(lead_time_ms, min_hits, hook)
+    }
+
+    pub fn clear_refresh_ahead_hook(&mut self) {
+        self.forwarder.clear_refresh_ahead_hook()
+    }
+
+    pub fn set_observer<O>(&mut self, observer: O)
+    where
+        O: ForwarderObserver + 'static,
+    {
+        self.forwarder.set_observer(observer)
+    }
+
+    pub fn clear_observer(&mut self) {
+        self.forwarder.clear_observer()
+    }
+
+    pub fn set_packet_filter<F>(&mut self, filter: F)
+    where
+        F: PacketFilter + 'static,
+    {
+        self.forwarder.set_packet_filter(filter)
+    }
+
+    pub fn clear_packet_filter(&mut self) {
+        self.forwarder.clear_packet_filter()
+    }
+
+    pub fn set_packet_capture<P>(&mut self, capture: P)
+    where
+        P: PacketCapture + 'static,
+    {
+        self.forwarder.set_packet_capture(capture)
+    }
+
+    pub fn clear_packet_capture(&mut self) {
+        self.forwarder.clear_packet_capture()
+    }
+
+    pub fn set_reap_dead_faces(&mut self, reap: bool) {
+        self.forwarder.set_reap_dead_faces(reap)
+    }
+
+    pub fn set_flood_on_no_route(&mut self, route_lifetime_ms: Option<u64>) {
+        self.forwarder.set_flood_on_no_route(route_lifetime_ms)
+    }
+
+    pub fn set_strategy_parameters(
+        &mut self,
+        prefix: Name<'_>,
+        parameters: Option<Name<'_>>,
+    ) -> bool {
+        self.forwarder.set_strategy_parameters(prefix, parameters)
+    }
+
+    pub fn set_reliable_fetch(&mut self, prefix: Name<'_>, fetch: Option<ReliableFetch>) {
+        self.forwarder.set_reliable_fetch(prefix, fetch)
+    }
+
+    pub fn allow_unsolicited_data(&mut self, prefix: Name<'_>, from: FaceToken) {
+        self.forwarder.allow_unsolicited_data(prefix, from)
+    }
+
+    pub fn disallow_unsolicited_data(&mut self, prefix: Name<'_>, from: FaceToken) -> bool {
+        self.forwarder.disallow_unsolicited_data(prefix, from)
+    }
+
+    pub fn export_routes(&mut self) -> Vec<u8> {
+        self.forwarder.export_routes()
+    }
+
+    pub fn import_routes(
+        &mut self,
+        snapshot: &[u8],
+        map_face: impl FnMut(FaceToken) -> Option<FaceToken>,
+    ) -> Option<usize> {
+        self.forwarder.import_routes(snapshot, map_face)
+    }
+
+    pub fn set_serve_stale(&mut self, serve_stale: bool) {
+        self.forwarder.set_serve_stale(serve_stale)
+    }
+
+    pub fn paused_faces(&self) -> impl Iterator<Item = FaceToken> + '_ {
+        self.forwarder.paused_faces()
+    }
+
     pub fn register_name_prefix_for_forwarding<'a>(
         &mut self,
         name_prefix: Name<'a>,
         forward_to: FaceToken,
         cost: u32,
-    ) {
+    ) -> bool {
         self.forwarder
             .register_name_prefix_for_forwarding(name_prefix, forward_to, cost)
     }
 
+    pub fn register_default_route(&mut self, forward_to: FaceToken, cost: u32) -> bool {
+        self.forwarder.register_default_route(forward_to, cost)
+    }
+
+    pub fn unregister_default_route(&mut self, forward_to: FaceToken) -> bool {
+        self.forwarder.unregister_default_route(forward_to)
+    }
+
     pub fn unregister_name_prefix_for_forwarding<'a>(
         &mut self,
         name_prefix: Name<'a>,
@@ -159,10 +528,87 @@ where
             .unregister_name_prefix_for_forwarding(name_prefix, forward_to)
     }
 
+    pub fn add_route<'a>(
+        &mut self,
+        name_prefix: Name<'a>,
+        route: Route,
+        lifetime_ms: Option<u64>,
+    ) -> bool {
+        self.forwarder.add_route(name_prefix, route, lifetime_ms)
+    }
+
+    pub fn set_registration_policy<P>(&mut self, policy: P)
+    where
+        P: RegistrationPolicy + 'static,
+    {
+        self.forwarder.set_registration_policy(policy)
+    }
+
+    pub fn clear_registration_policy(&mut self) {
+        self.forwarder.clear_registration_policy()
+    }
+
+    pub fn remove_route<'a>(
+        &mut self,
+        name_prefix: Name<'a>,
+        face: FaceToken,
+        origin: RouteOrigin,
+    ) -> bool {
+        self.forwarder.remove_route(name_prefix, face, origin)
+    }
+
+    pub fn enable_management<V>(&mut self, validator: V)
+    where
+        V: CommandValidator + 'static,
+    {
+        self.forwarder.enable_management(validator)
+    }
+
+    pub fn disable_management(&mut self) {
+        self.forwarder.disable_management()
+    }
+
+    // Lets faces/create open UDP and TCP faces, see SocketFaceFactory
+    pub fn enable_face_creation(&mut self) {
+        self.forwarder.set_face_factory(SocketFaceFactory {
+            forwarding_thread: self.forwarding_thread.clone(),
+            shared_queue: self.shared_queue.clone(),
+            poller_sender: self.poller_sender.clone(),
+            changes: Rc::clone(&self.socket_face_changes),
+        });
+    }
+
+    pub fn disable_face_creation(&mut self) {
+        self.forwarder.clear_face_factory()
+    }
+
+    pub fn rib(&self) -> &Rib {
+        self.forwarder.rib()
+    }
+
+    pub fn set_nonce_source<N>(&mut self, source: N)
+    where
+        N: NonceSource + 'static,
+    {
+        self.forwarder.set_nonce_source(source)
+    }
+
+    pub fn next_nonce(&mut self) -> [u8; 4] {
+        self.forwarder.next_nonce()
+    }
+
+    pub fn warm_up<'a>(&mut self, probe_names: &[Name<'a>]) -> ReadinessReport {
+        self.forwarder.warm_up(probe_names)
+    }
+
     pub fn forward(&mut self, timeout: Option<Duration>) -> Result<FaceToken, ForwarderError> {
         let deadline = timeout.map(|t| Instant::now() + t);
 
         loop {
+            self.apply_control_messages();
+            self.run_due_timers();
+            self.apply_socket_face_changes();
+
             // First we try to forward on all the faces that we already have in the local queue
             while let Some(face) = self.local_queue.pop_front() {
                 match self.forwarder.try_forward_from_face(face) {
@@ -196,20 +642,371 @@ where
 
             // If we are here, there are no pending faces to poll and we need to wait
             //  until we get a new notification or until the deadline
+            let mut timeout = None;
             if let Some(deadline) = deadline {
                 let now = Instant::now();
                 if now >= deadline {
                     // If we are past the deadline, we return empty
                     return Err(ForwarderError::NothingToForward);
-                } else {
-                    // Otherwise we part the thread hoping for a waker to wake us up
-                    std::thread::park_timeout(deadline - now);
                 }
-            } else {
-                std::thread::park();
+                timeout = Some(deadline - now);
+            }
+
+            // We also wake up for the next timer
+            if let Some(next_run) = self.timers.iter().map(|timer| timer.next_run).min() {
+                let until = next_run.saturating_duration_since(Instant::now());
+                timeout = Some(timeout.map_or(until, |timeout| timeout.min(until)));
+            }
+
+            // The socket faces with queued output wake us up once they are writable
+            self.watch_writable_faces();
+
+            // Nothing notifies us when the faces paused by backpressure can be read again,
+            //  or when the other faces with queued output can take it, so we check on
+            //  them periodically
+            let socket_faces = &self.socket_faces;
+            let paused_faces: Vec<FaceToken> = self
+                .forwarder
+                .paused_faces()
+                .chain(
+                    self.forwarder
+                        .faces_with_queued_output()
+                        .filter(|face| socket_faces.binary_search(face).is_err()),
+                )
+                .collect();
+            if !paused_faces.is_empty() {
+                timeout = Some(timeout.map_or(PAUSED_FACES_CHECK_INTERVAL, |timeout| {
+                    timeout.min(PAUSED_FACES_CHECK_INTERVAL)
+                }));
+            }
+
+            // We park the thread hoping for a waker to wake us up
+            match timeout {
+                Some(timeout) => std::thread::park_timeout(timeout),
+                None => std::thread::park(),
+            }
+            self.local_queue.extend(paused_faces);
+        }
+    }
+
+    fn watch_writable_faces(&mut self) {
+        let socket_faces = &self.socket_faces;
+        let queued: Vec<FaceToken> = self
+            .forwarder
+            .faces_with_queued_output()
+            .filter(|face| socket_faces.binary_search(face).is_ok())
+            .collect();
+        for &face in queued.iter() {
+            if !self.writable_faces.contains(&face) {
+                let _ = self.poller_sender.send(PollerMessage::WatchWritable {
+                    face,
+                    writable: true,
+                });
+            }
+        }
+        for &face in self.writable_faces.iter() {
+            if !queued.contains(&face) {
+                let _ = self.poller_sender.send(PollerMessage::WatchWritable {
+                    face,
+                    writable: false,
+                });
+            }
+        }
+        self.writable_faces = queued;
+    }
+
+    fn run_due_timers(&mut self) {
+        let now = Instant::now();
+        // The callbacks may add and remove the timers, so we look each one up again
+        let due: Vec<TimerToken> = self
+            .timers
+            .iter()
+            .filter(|timer| timer.next_run <= now)
+            .map(|timer| timer.token)
+            .collect();
+        for token in due {
+            let Some(timer) = self.timers.iter_mut().find(|timer| timer.token == token) else {
+                continue;
+            };
+            timer.next_run = (timer.next_run + timer.period).max(now);
+            let Some(mut callback) = timer.callback.take() else {
+                continue;
+            };
+            callback(self);
+            if let Some(timer) = self.timers.iter_mut().find(|timer| timer.token == token) {
+                timer.callback = Some(callback);
+            }
+        }
+    }
+
+    fn apply_control_messages(&mut self) {
+        while let Ok(message) = self.control_receiver.try_recv() {
+            match message {
+                ControlMessage::AddFace {
+                    sender,
+                    mut receiver,
+                    scope,
+                    reply,
+                } => {
+                    let mut face = self.forwarder.next_face_token();
+                    if let Some(token) = face {
+                        self.register_face(token, &mut *receiver);
+                        if self.forwarder.add_boxed_face(sender, receiver) == Some(token) {
+                            self.forwarder.set_face_scope(token, scope);
+                        } else {
+                            self.unregister_face(token);
+                            face = None;
+                        }
+                    }
+                    // The handle may have stopped waiting
+                    let _ = reply.send(face);
+                }
+                ControlMessage::RemoveFace(face) => {
+                    self.remove_face(face);
+                }
+                ControlMessage::RegisterPrefix {
+                    prefix,
+                    forward_to,
+                    cost,
+                } => {
+                    self.register_name_prefix_for_forwarding(prefix.as_name(), forward_to, cost);
+                }
+                ControlMessage::UnregisterPrefix { prefix, forward_to } => {
+                    self.unregister_name_prefix_for_forwarding(prefix.as_name(), forward_to);
+                }
+                ControlMessage::SetDiagnosticsLevel { subsystem, level } => {
+                    let diagnostics = self.forwarder.diagnostics_mut();
+                    diagnostics.set_level(subsystem, level);
+                    diagnostics.record(
+                        Subsystem::Management,
+                        Level::Info,
+                        format_args!("{} diagnostics set to {:?}", subsystem.name(), level),
+                    );
+                }
+            }
+        }
+    }
+
+    fn apply_socket_face_changes(&mut self) {
+        // The forwarder closes the idle and, if asked to, the dead faces on its own
+        for face in self.forwarder.take_reaped_faces() {
+            let _ = self.poller_sender.send(PollerMessage::Unregister { face });
+            self.socket_face_changes.borrow_mut().removed.push(face);
+        }
+        let mut changes = self.socket_face_changes.borrow_mut();
+        if changes.added.is_empty() && changes.removed.is_empty() {
+            return;
+        }
+        self.socket_faces.append(&mut changes.added);
+        let removed = core::mem::take(&mut changes.removed);
+        self.socket_faces.retain(|face| !removed.contains(face));
+        self.socket_faces.sort();
+    }
+}
+
+const PAUSED_FACES_CHECK_INTERVAL: Duration = Duration::from_millis(1);
+
+// Identifies a timer of a BlockingForwarder, see BlockingForwarder::add_timer
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TimerToken(u64);
+
+type TimerCallback<C, H, M, T> = Box<dyn FnMut(&mut BlockingForwarder<C, H, M, T>)>;
+
+struct Timer<C, H, M, T>
+where
+    C: Clock,
+    H: Hasher<Digest = Sha256Digest>,
+    M: ForwarderMetrics,
+    T: Tables,
+{
+    token: TimerToken,
+    period: Duration,
+    next_run: Instant,
+    // Taken out while it runs
+    callback: Option<TimerCallback<C, H, M, T>>,
+}
+
+// Lets the other threads add and remove the faces and register the prefixes of a
+//  BlockingForwarder, which is bound to its thread. See BlockingForwarder::control_handle.
+#[derive(Clone)]
+pub struct ControlHandle {
+    sender: Sender<ControlMessage>,
+    forwarding_thread: Thread,
+}
+
+impl ControlHandle {
+    // Waits until the forwarding thread has added the face, so it must not be called
+    //  from that thread. None if the forwarder is gone or out of the face tokens.
+    // The face is NonLocal, see add_face_with_scope for the local ones.
+    pub fn add_face<FS, FR>(&self, sender: FS, receiver: FR) -> Option<FaceToken>
+    where
+        FS: FaceSender + Send + 'static,
+        FR: FaceReceiver + Notifying + Send + 'static,
+    {
+        self.add_face_with_scope(sender, receiver, Scope::default())
+    }
+
+    // As add_face, e.g. with Scope::Local for the applications on the same host, which
+    //  can reach /localhost
+    pub fn add_face_with_scope<FS, FR>(
+        &self,
+        sender: FS,
+        receiver: FR,
+        scope: Scope,
+    ) -> Option<FaceToken>
+    where
+        FS: FaceSender + Send + 'static,
+        FR: FaceReceiver + Notifying + Send + 'static,
+    {
+        let (reply, replied) = std::sync::mpsc::channel();
+        self.send(ControlMessage::AddFace {
+            sender: Box::new(sender),
+            receiver: Box::new(receiver),
+            scope,
+            reply,
+        });
+        replied.recv().ok().flatten()
+    }
+
+    // The requests below return at once, and are applied in the order they were made
+    pub fn remove_face(&self, face: FaceToken) -> bool {
+        self.send(ControlMessage::RemoveFace(face))
+    }
+
+    pub fn register_prefix(&self, prefix: Name<'_>, forward_to: FaceToken, cost: u32) -> bool {
+        self.send(ControlMessage::RegisterPrefix {
+            prefix: NameBuf::from(prefix),
+            forward_to,
+            cost,
+        })
+    }
+
+    pub fn unregister_prefix(&self, prefix: Name<'_>, forward_to: FaceToken) -> bool {
+        self.send(ControlMessage::UnregisterPrefix {
+            prefix: NameBuf::from(prefix),
+            forward_to,
+        })
+    }
+
+    // Changes the verbosity of a subsystem of the running forwarder, e.g. to debug it
+    //  without a restart
+    pub fn set_diagnostics_level(&self, subsystem: Subsystem, level: Level) -> bool {
+        self.send(ControlMessage::SetDiagnosticsLevel { subsystem, level })
+    }
+
+    // Returns false if the forwarder is gone
+    fn send(&self, message: ControlMessage) -> bool {
+        if self.sender.send(message).is_err() {
+            return false;
+        }
+        self.forwarding_thread.unpark();
+        true
+    }
+}
+
+trait ControlledReceiver: FaceReceiver + Notifying + Send {}
+
+impl<FR: FaceReceiver + Notifying + Send> ControlledReceiver for FR {}
+
+enum ControlMessage {
+    AddFace {
+        sender: Box<dyn FaceSender + Send>,
+        receiver: Box<dyn ControlledReceiver>,
+        scope: Scope,
+        reply: Sender<Option<FaceToken>>,
+    },
+    RemoveFace(FaceToken),
+    RegisterPrefix {
+        prefix: NameBuf,
+        forward_to: FaceToken,
+        cost: u32,
+    },
+    UnregisterPrefix {
+        prefix: NameBuf,
+        forward_to: FaceToken,
+    },
+    SetDiagnosticsLevel {
+        subsystem: Subsystem,
+        level: Level,
+    },
+}
+
+// How long faces/create waits for a TCP connection, during which nothing is forwarded
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+// The socket faces that the management commands added and removed while forwarding,
+//  which are only taken into account in the next round
+#[derive(Default)]
+struct SocketFaceChanges {
+    added: Vec<FaceToken>,
+    removed: Vec<FaceToken>,
+}
+
+// Opens the faces to the canonical URIs, which are udp4://, udp6://, tcp4:// and tcp6://
+//  followed by an IP address and a port, e.g. udp4://192.0.2.1:6363
+struct SocketFaceFactory {
+    forwarding_thread: Thread,
+    shared_queue: FaceQueue,
+    poller_sender: PollerSender,
+    changes: Rc<RefCell<SocketFaceChanges>>,
+}
+
+impl SocketFaceFactory {
+    fn register<FR: Notifying>(&mut self, token: FaceToken, receiver: &mut FR) {
+        if let Some(socket) = receiver.socket_id() {
+            let _ = self.poller_sender.send(PollerMessage::Register {
+                face: token,
+                socket,
+            });
+            self.changes.borrow_mut().added.push(token);
+        }
+        let waker = Waker::new(
+            self.forwarding_thread.clone(),
+            token,
+            self.shared_queue.clone(),
+        );
+        receiver.register_waker(waker);
+    }
+}
+
+impl FaceFactory for SocketFaceFactory {
+    fn create_face(&mut self, token: FaceToken, uri: &str) -> Result<BoxedFace, FaceCreationError> {
+        let (scheme, address) = uri
+            .split_once("://")
+            .ok_or(FaceCreationError::UnsupportedUri)?;
+        let address: SocketAddr = address
+            .parse()
+            .map_err(|_| FaceCreationError::UnsupportedUri)?;
+        match (scheme, address) {
+            ("udp4", SocketAddr::V4(_)) | ("udp6", SocketAddr::V6(_)) => {
+                let local: SocketAddr = match address {
+                    SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+                    SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+                };
+                let socket = UdpSocket::bind(local).map_err(|_| FaceCreationError::Failed)?;
+                let (sender, mut receiver) = udp_face(socket, address.ip(), address.port())
+                    .map_err(|_| FaceCreationError::Failed)?;
+                self.register(token, &mut receiver);
+                Ok((Box::new(sender), Box::new(receiver)))
+            }
+            ("tcp4", SocketAddr::V4(_)) | ("tcp6", SocketAddr::V6(_)) => {
+                let stream = TcpStream::connect_timeout(&address, TCP_CONNECT_TIMEOUT)
+                    .map_err(|_| FaceCreationError::Failed)?;
+                let (sender, mut receiver) =
+                    tcp_face(stream).map_err(|_| FaceCreationError::Failed)?;
+                self.register(token, &mut receiver);
+                Ok((Box::new(sender), Box::new(receiver)))
             }
+            _ => Err(FaceCreationError::UnsupportedUri),
         }
     }
+
+    fn face_destroyed(&mut self, token: FaceToken) {
+        let _ = self
+            .poller_sender
+            .send(PollerMessage::Unregister { face: token });
+        self.changes.borrow_mut().removed.push(token);
+    }
 }
 
 pub(crate) struct FaceQueue {
@@ -239,9 +1036,51 @@ impl Clone for FaceQueue {
     }
 }
 
+// Wakes the poller up for every message, and once the last sender is dropped
+//  so that the poller thread exits
+struct PollerSender {
+    sender: Option<Sender<PollerMessage>>,
+    notifier: Option<poller::Notifier>,
+}
+
+impl PollerSender {
+    fn send(&self, message: PollerMessage) -> Result<(), SendError<PollerMessage>> {
+        if let Some(sender) = &self.sender {
+            sender.send(message)?;
+        }
+        self.notify();
+        Ok(())
+    }
+
+    fn notify(&self) {
+        if let Some(notifier) = &self.notifier {
+            notifier.notify();
+        }
+    }
+}
+
+impl Clone for PollerSender {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            notifier: self.notifier.clone(),
+        }
+    }
+}
+
+impl Drop for PollerSender {
+    fn drop(&mut self) {
+        // The channel has to be disconnected by the time the poller wakes up
+        drop(self.sender.take());
+        self.notify();
+    }
+}
+
 enum PollerMessage {
     Register { face: FaceToken, socket: SocketId },
     Unregister { face: FaceToken },
+    // The face is also reported when its socket becomes writable
+    WatchWritable { face: FaceToken, writable: bool },
 }
 
 #[cfg(all(
@@ -265,20 +1104,23 @@ enum PollerMessage {
     )
 ))]
 mod poller {
+    use std::sync::Arc;
+
     use polling::{Event, PollMode};
 
     use crate::{forwarder::FaceToken, platform::native::notifying::SocketId};
 
     pub(crate) struct ReadPoller {
-        faces: Vec<(FaceToken, SocketId)>,
-        socket_poller: polling::Poller,
+        // With whether the writability is polled as well
+        faces: Vec<(FaceToken, SocketId, bool)>,
+        socket_poller: Arc<polling::Poller>,
         socket_events: polling::Events,
         should_re_add: bool,
     }
 
     impl ReadPoller {
         pub(crate) fn new() -> Result<Self, std::io::Error> {
-            let socket_poller = polling::Poller::new()?;
+            let socket_poller = Arc::new(polling::Poller::new()?);
             let should_re_add = !socket_poller.supports_level();
             Ok(Self {
                 faces: Vec::new(),
@@ -288,17 +1130,22 @@ mod poller {
             })
         }
 
+        pub(crate) fn notifier(&self) -> Notifier {
+            Notifier(Arc::clone(&self.socket_poller))
+        }
+
         pub(crate) fn register(&mut self, face: FaceToken, socket: SocketId) {
             let idx = match self.find_face(face) {
                 Ok(idx) => {
                     let mut old = socket;
                     std::mem::swap(&mut old, &mut self.faces[idx].1);
+                    self.faces[idx].2 = false;
                     // TODO: what happens if this is one of our other sockets?
                     let _ = self.socket_poller.delete(old.source());
                     idx
                 }
                 Err(idx) => {
-                    self.faces.insert(idx, (face, socket));
+                    self.faces.insert(idx, (face, socket, false));
                     idx
                 }
             };
@@ -330,6 +1177,23 @@ mod poller {
             }
         }
 
+        pub(crate) fn watch_writable(&mut self, face: FaceToken, writable: bool) {
+            if let Ok(idx) = self.find_face(face) {
+                self.faces[idx].2 = writable;
+                let _ = self
+                    .socket_poller
+                    .modify(self.faces[idx].1.source(), Self::interest(face, writable));
+            }
+        }
+
+        fn interest(face: FaceToken, writable: bool) -> Event {
+            if writable {
+                Event::all(face.0 as usize)
+            } else {
+                Event::readable(face.0 as usize)
+            }
+        }
+
         pub(crate) fn wait(
             &mut self,
             faces: &mut Vec<FaceToken>,
@@ -345,15 +1209,16 @@ mod poller {
             debug_assert!(self.socket_events.len() == new_count);
 
             for ee in self.socket_events.iter() {
-                if ee.readable {
+                if ee.readable || ee.writable {
                     let face = FaceToken(ee.key as u32);
                     faces.push(face);
 
                     if self.should_re_add {
                         if let Ok(idx) = self.find_face(face) {
+                            let (_, socket, writable) = &self.faces[idx];
                             let _ = self
                                 .socket_poller
-                                .modify(self.faces[idx].1.source(), Event::readable(ee.key));
+                                .modify(socket.source(), Self::interest(face, *writable));
                         }
                     }
                 }
@@ -361,13 +1226,23 @@ mod poller {
         }
 
         fn find_face(&self, face: FaceToken) -> Result<usize, usize> {
-            self.faces.binary_search_by(|(f, _)| f.cmp(&face))
+            self.faces.binary_search_by(|(f, _, _)| f.cmp(&face))
+        }
+    }
+
+    // Interrupts the wait of the poller from any thread
+    #[derive(Clone)]
+    pub(crate) struct Notifier(Arc<polling::Poller>);
+
+    impl Notifier {
+        pub(crate) fn notify(&self) {
+            let _ = self.0.notify();
         }
     }
 
     impl Drop for ReadPoller {
         fn drop(&mut self) {
-            for (_face, socket) in self.faces.drain(..) {
+            for (_face, socket, _) in self.faces.drain(..) {
                 let _ = self.socket_poller.delete(socket.source());
             }
         }
@@ -431,15 +1306,28 @@ mod poller {
 
     pub(crate) struct ReadPoller {}
 
+    #[derive(Clone)]
+    pub(crate) struct Notifier {}
+
+    impl Notifier {
+        pub(crate) fn notify(&self) {}
+    }
+
     impl ReadPoller {
         pub(crate) fn new() -> Result<Self, std::io::Error> {
             Self {}
         }
 
+        pub(crate) fn notifier(&self) -> Notifier {
+            Notifier {}
+        }
+
         pub(crate) fn register(&mut self, face: FaceToken, socket: SocketId) {}
 
         pub(crate) fn unregister(&mut self, face: FaceToken) {}
 
+        pub(crate) fn watch_writable(&mut self, face: FaceToken, writable: bool) {}
+
         pub(crate) fn wait(
             &mut self,
             faces: &mut Vec<FaceToken>,
@@ -448,3 +1336,132 @@ mod poller {
         }
     }
 }
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+    use core::{cell::Cell, time::Duration};
+    use std::{rc::Rc, time::Instant};
+
+    use crate::{
+        diagnostics::{Level, Subsystem},
+        face::{FaceReceiver, FaceSender},
+        forwarder::Scope,
+        io::Encode,
+        name::{Name, NameComponent},
+        packet::Interest,
+        platform::native::{shared::shared_face, udp::udp_face, DefaultForwarder},
+    };
+
+    #[test]
+    fn test_control_handle() {
+        let mut forwarder = DefaultForwarder::default();
+        let handle = forwarder.control_handle();
+        let comps = [
+            NameComponent::generic(b"control"),
+            NameComponent::generic(b"x"),
+        ];
+
+        // The faces are added from another thread while this one forwards
+        let (consumer_sender, _consumer_end) = shared_face::<4096>();
+        let (mut consumer_end, consumer_receiver) = shared_face::<4096>();
+        let (producer_sender, mut producer_end) = shared_face::<4096>();
+        let (_producer_end, producer_receiver) = shared_face::<4096>();
+        let control = std::thread::spawn(move || {
+            let consumer = handle.add_face(consumer_sender, consumer_receiver);
+            let producer =
+                handle.add_face_with_scope(producer_sender, producer_receiver, Scope::Local);
+            let root = Name::new();
+            let prefix = root.adding_components(&comps[..1]);
+            assert!(handle.register_prefix(prefix, producer.unwrap(), 0));
+            (handle, consumer.unwrap(), producer.unwrap())
+        });
+        let mut rounds = 0;
+        while !control.is_finished() && rounds < 1000 {
+            let _ = forwarder.forward(Some(Duration::from_millis(10)));
+            rounds += 1;
+        }
+        let (handle, consumer, producer) = control.join().unwrap();
+        assert!(forwarder.has_face(consumer) && forwarder.has_face(producer));
+        // Only trusted when it is added as a local one
+        assert_eq!(forwarder.face_scope(consumer), Some(Scope::NonLocal));
+        assert_eq!(forwarder.face_scope(producer), Some(Scope::Local));
+
+        // The route registered through the handle is used
+        let root = Name::new();
+        let name = root.adding_components(&comps);
+        let mut interest = Vec::new();
+        let _ = Interest::new(name, false, [1; 4]).encode(&mut interest);
+        assert_eq!(consumer_end.try_send(&interest), Ok(interest.len()));
+        let mut buffer = [0u8; 4096];
+        let mut received = 0;
+        for _ in 0..100 {
+            let _ = forwarder.forward(Some(Duration::from_millis(10)));
+            received += producer_end.try_recv(&mut buffer[received..]).unwrap();
+            if received > 0 {
+                break;
+            }
+        }
+        assert!(received > 0);
+
+        assert!(handle.remove_face(producer));
+        let _ = forwarder.forward(Some(Duration::from_millis(10)));
+        assert!(!forwarder.has_face(producer));
+
+        // The diagnostics can be turned up while it runs
+        assert!(handle.set_diagnostics_level(Subsystem::Management, Level::Debug));
+        let _ = forwarder.forward(Some(Duration::from_millis(10)));
+        let level = forwarder.diagnostics().level(Subsystem::Management);
+        assert_eq!(level, Level::Debug);
+    }
+
+    #[test]
+    fn test_poller_wake() {
+        let mut forwarder = DefaultForwarder::default();
+        // The poller is already waiting by the time the face is added
+        std::thread::sleep(Duration::from_millis(20));
+
+        let local = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let remote = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let remote_address = remote.local_addr().unwrap();
+        remote.connect(local.local_addr().unwrap()).unwrap();
+        let (sender, receiver) =
+            udp_face(local, remote_address.ip(), remote_address.port()).unwrap();
+        let face = forwarder.add_face(sender, receiver).unwrap();
+
+        // The socket is polled right away, not once the poller times out
+        let comps = [NameComponent::generic(b"wake")];
+        let root = Name::new();
+        let mut interest = Vec::new();
+        let _ = Interest::new(root.adding_components(&comps), false, [1; 4]).encode(&mut interest);
+        remote.send(&interest).unwrap();
+        let forwarded = forwarder.forward(Some(Duration::from_millis(50)));
+        assert!(matches!(forwarded, Ok(from) if from == face));
+    }
+
+    #[test]
+    fn test_timers() {
+        let mut forwarder = DefaultForwarder::default();
+        let runs = Rc::new(Cell::new(0));
+        let timer = Rc::new(Cell::new(None));
+
+        // The timer removes itself on its third run
+        let (counted, own_token) = (runs.clone(), timer.clone());
+        let token = forwarder.add_timer(Duration::from_millis(5), move |forwarder| {
+            counted.set(counted.get() + 1);
+            if counted.get() == 3 {
+                assert!(forwarder.remove_timer(own_token.get().unwrap()));
+            }
+        });
+        timer.set(Some(token));
+
+        // Nothing is forwarded, yet forward wakes up for the timer
+        let start = Instant::now();
+        while runs.get() < 3 && start.elapsed() < Duration::from_secs(5) {
+            let _ = forwarder.forward(Some(Duration::from_millis(100)));
+        }
+        assert_eq!(runs.get(), 3);
+        let _ = forwarder.forward(Some(Duration::from_millis(20)));
+        assert_eq!(runs.get(), 3);
+        assert!(!forwarder.remove_timer(token));
+    }
+}
@@ -1,8 +1,27 @@
 pub mod clock;
+pub mod diagnostics;
+pub mod nonce;
 mod notifying;
 
 impl<const SIZE: usize> notifying::Notifying for crate::face::local::LocalReceiver<SIZE> {}
 
+// The timers of the reliability only run when the face is used, which is fine
+//  as long as there is some traffic on the link
+impl<R, S, C> notifying::Notifying for crate::face::lp::LpReliableReceiver<R, S, C>
+where
+    R: crate::face::FaceReceiver + notifying::Notifying,
+    S: crate::face::FaceSender,
+    C: crate::clock::Clock,
+{
+    fn socket_id(&self) -> Option<notifying::SocketId> {
+        self.receiver.socket_id()
+    }
+
+    fn register_waker(&mut self, waker: notifying::Waker) {
+        self.receiver.register_waker(waker)
+    }
+}
+
 pub mod shared;
 pub mod tcp;
 pub mod udp;
@@ -10,8 +29,60 @@ pub mod udp;
 #[cfg(unix)]
 pub mod unix;
 
+#[cfg(target_os = "linux")]
+pub mod ethernet;
+
 pub mod forwarder;
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const EMSGSIZE: i32 = 90;
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+const EMSGSIZE: i32 = 40;
+#[cfg(target_os = "windows")]
+const EMSGSIZE: i32 = 10040; // WSAEMSGSIZE
+
+// Maps the errors of the sockets to the FaceError that tells the forwarder
+//  whether the face is still usable.
+pub(crate) fn face_error_from_io(err: &std::io::Error) -> crate::face::FaceError {
+    use crate::face::FaceError;
+    use std::io::ErrorKind;
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+        target_os = "windows"
+    ))]
+    if err.raw_os_error() == Some(EMSGSIZE) {
+        return FaceError::MessageTooLarge;
+    }
+
+    match err.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => FaceError::WouldBlock,
+        ErrorKind::Interrupted => FaceError::Interrupted,
+        ErrorKind::PermissionDenied => FaceError::PermissionDenied,
+        ErrorKind::ConnectionRefused
+        | ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionAborted
+        | ErrorKind::NotConnected
+        | ErrorKind::BrokenPipe
+        | ErrorKind::UnexpectedEof => FaceError::Disconnected,
+        _ => FaceError::Fatal(err.raw_os_error().unwrap_or(0)),
+    }
+}
+
 #[cfg(feature = "sha2")]
 use crate::{
     forwarder::InertMetrics,
@@ -0,0 +1,40 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::nonce::{NonceSource, SplitMixNonces};
+
+// Nonces seeded differently in every run, from the random keys that the standard library
+//  gets from the operating system, mixed with the time and the process
+pub struct RandomNonces {
+    inner: SplitMixNonces,
+}
+
+impl RandomNonces {
+    pub fn new() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        hasher.write_u128(nanos);
+        hasher.write_u32(std::process::id());
+        Self {
+            inner: SplitMixNonces::new(hasher.finish()),
+        }
+    }
+}
+
+impl Default for RandomNonces {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NonceSource for RandomNonces {
+    fn next_nonce(&mut self) -> [u8; 4] {
+        self.inner.next_nonce()
+    }
+}
@@ -103,6 +103,11 @@ impl<const SIZE: usize> FaceSender for SharedSender<SIZE> {
 
         Ok(bytes_written)
     }
+
+    fn send_capacity(&self) -> Option<usize> {
+        let g = self.inner.ring.lock().ok()?;
+        Some(g.0.free_space())
+    }
 }
 
 impl<const SIZE: usize> BlockingFaceSender for SharedSender<SIZE> {
@@ -312,6 +317,7 @@ mod tests {
             match receiver.try_recv(&mut buffer[..]) {
                 Ok(rec) => received += rec,
                 Err(FaceError::Disconnected) => break,
+                Err(err) => panic!("{:?}", err),
             }
         }
 
@@ -5,7 +5,7 @@ use std::{
 };
 
 use crate::face::{BlockingFaceReceiver, BlockingFaceSender, FaceError, FaceReceiver, FaceSender};
-use crate::platform::native::notifying::Notifying;
+use crate::platform::native::{face_error_from_io, notifying::Notifying};
 
 use super::notifying::SocketId;
 #[cfg(any(unix, target_os = "hermit"))]
@@ -25,7 +25,7 @@ impl FaceSender for TcpSender {
             Ok(bytes_sent) => Ok(bytes_sent),
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(0),
-                _ => Err(FaceError::Disconnected),
+                _ => Err(face_error_from_io(&io_err)),
             },
         }
     }
@@ -37,7 +37,7 @@ impl FaceReceiver for TcpReceiver {
             Ok(bytes_received) => Ok(bytes_received),
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(0),
-                _ => Err(FaceError::Disconnected),
+                _ => Err(face_error_from_io(&io_err)),
             },
         }
     }
@@ -88,7 +88,7 @@ impl BlockingFaceSender for BlockingTcpSender {
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(0),
                 ErrorKind::TimedOut => Ok(0),
-                _ => return Err(FaceError::Disconnected),
+                _ => return Err(face_error_from_io(&io_err)),
             },
         }
     }
@@ -104,7 +104,7 @@ impl BlockingFaceReceiver for BlockingTcpReceiver {
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(0),
                 ErrorKind::TimedOut => Ok(0),
-                _ => return Err(FaceError::Disconnected),
+                _ => return Err(face_error_from_io(&io_err)),
             },
         }
     }
@@ -1,13 +1,22 @@
-use core::net::IpAddr;
+use core::{net::IpAddr, time::Duration};
 use std::{
+    collections::HashMap,
     io::{Error, ErrorKind},
-    net::UdpSocket,
+    net::{SocketAddr, UdpSocket},
+    time::Instant,
 };
 
-use crate::platform::native::notifying::Notifying;
+use crate::platform::native::{
+    face_error_from_io,
+    notifying::Notifying,
+    shared::{shared_face, SharedSender},
+};
 use crate::{
     face::{FaceError, FaceReceiver, FaceSender},
-    forwarder::MAX_PACKET_SIZE,
+    forwarder::{FaceToken, Scope, MAX_PACKET_SIZE},
+    io::Decode,
+    platform::forwarder::ControlHandle,
+    tlv::TLV,
 };
 
 use super::notifying::SocketId;
@@ -17,7 +26,7 @@ use super::notifying::Waker;
 pub struct UdpSender {
     socket: UdpSocket,
     buffer: Vec<u8>,
-    addr: (IpAddr, u16)
+    addr: (IpAddr, u16),
 }
 
 pub struct UdpReceiver {
@@ -38,7 +47,11 @@ impl FaceSender for UdpSender {
             }
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(()),
-                _ => Err(FaceError::Disconnected),
+                _ => {
+                    // The datagram is dropped so that it does not poison the next ones
+                    self.buffer.clear();
+                    Err(face_error_from_io(&io_err))
+                }
             },
         }
     }
@@ -50,7 +63,7 @@ impl FaceReceiver for UdpReceiver {
             Ok(bytes_received) => Ok(bytes_received),
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(0),
-                _ => Err(FaceError::Disconnected),
+                _ => Err(face_error_from_io(&io_err)),
             },
         }
     }
@@ -74,7 +87,11 @@ impl Notifying for UdpReceiver {
     }
 }
 
-pub fn udp_face(socket: UdpSocket, remote_address: impl Into<IpAddr>, remote_port: u16) -> Result<(UdpSender, UdpReceiver), Error> {
+pub fn udp_face(
+    socket: UdpSocket,
+    remote_address: impl Into<IpAddr>,
+    remote_port: u16,
+) -> Result<(UdpSender, UdpReceiver), Error> {
     socket.set_nonblocking(true)?;
     let sender = UdpSender {
         socket: socket.try_clone()?,
@@ -84,3 +101,360 @@ pub fn udp_face(socket: UdpSocket, remote_address: impl Into<IpAddr>, remote_por
     let receiver = UdpReceiver { socket };
     Ok((sender, receiver))
 }
+
+// How many bytes of the datagrams of a peer can wait for the forwarder
+const UDP_DEMUX_QUEUE_SIZE: usize = 8 * MAX_PACKET_SIZE;
+
+// Serves all the peers of one socket, e.g. bound to the NDN port 6363, with a face for
+//  every remote endpoint the datagrams come from, whose packets go back to that endpoint.
+// The faces of the peers that sent nothing for the idle timeout are removed, and
+//  created again if they come back. The faces are non-local, so whoever can reach the
+//  socket cannot reach /localhost.
+pub struct UdpDemux {
+    socket: UdpSocket,
+    idle_timeout: Duration,
+    peers: HashMap<SocketAddr, UdpPeer>,
+    max_peers: Option<usize>,
+}
+
+struct UdpPeer {
+    face: FaceToken,
+    queue: SharedSender<UDP_DEMUX_QUEUE_SIZE>,
+    last_received: Instant,
+}
+
+impl UdpDemux {
+    pub fn new(socket: UdpSocket, idle_timeout: Duration) -> Self {
+        Self {
+            socket,
+            idle_timeout,
+            peers: HashMap::new(),
+            max_peers: None,
+        }
+    }
+
+    // Once that many peers have a face, the datagrams of the new ones are dropped until
+    //  some of them go idle, so that spoofed addresses cannot use up the face tokens
+    pub fn set_max_peers(&mut self, max_peers: Option<usize>) {
+        self.max_peers = max_peers;
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        self.socket.local_addr()
+    }
+
+    pub fn face_of(&self, peer: SocketAddr) -> Option<FaceToken> {
+        self.peers.get(&peer).map(|peer| peer.face)
+    }
+
+    // Adds the faces to the forwarder of the handle as the peers show up, so it runs on
+    //  a thread of its own. Only returns once the forwarder is gone or the socket fails.
+    pub fn serve(&mut self, control: &ControlHandle) -> Result<(), Error> {
+        self.socket.set_nonblocking(false)?;
+        // Wakes up regularly to remove the idle peers even when nothing comes
+        let wake_up = (self.idle_timeout / 4).max(Duration::from_millis(10));
+        self.socket.set_read_timeout(Some(wake_up))?;
+        let mut buffer = vec![0; MAX_PACKET_SIZE];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((len, peer)) => {
+                    if !self.dispatch(&buffer[..len], peer, control)? {
+                        return Ok(());
+                    }
+                }
+                // The reset is what some platforms report for an earlier datagram that
+                //  did not reach its peer
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        ErrorKind::WouldBlock
+                            | ErrorKind::TimedOut
+                            | ErrorKind::Interrupted
+                            | ErrorKind::ConnectionReset
+                    ) => {}
+                Err(err) => return Err(err),
+            }
+            if !self.remove_idle_peers(control) {
+                return Ok(());
+            }
+        }
+    }
+
+    // Returns false once the forwarder is gone
+    fn dispatch(
+        &mut self,
+        datagram: &[u8],
+        peer: SocketAddr,
+        control: &ControlHandle,
+    ) -> Result<bool, Error> {
+        // The queue only takes whole packets, so that a bad datagram does not spoil the
+        //  ones after it
+        if !matches!(TLV::try_decode(datagram), Ok((_, len)) if len == datagram.len()) {
+            return Ok(true);
+        }
+        let now = Instant::now();
+        if let Some(entry) = self.peers.get_mut(&peer) {
+            entry.last_received = now;
+            // Dropped as by the network when the forwarder falls behind
+            if entry
+                .queue
+                .send_capacity()
+                .is_some_and(|capacity| capacity < datagram.len())
+            {
+                return Ok(true);
+            }
+            if entry.queue.try_send(datagram).is_ok() {
+                return Ok(true);
+            }
+            // The forwarder removed the face, so it is added again
+            self.peers.remove(&peer);
+        }
+        if self.max_peers.is_some_and(|max| self.peers.len() >= max) {
+            return Ok(true);
+        }
+
+        let sender = UdpSender {
+            socket: self.socket.try_clone()?,
+            buffer: Vec::with_capacity(MAX_PACKET_SIZE),
+            addr: (peer.ip(), peer.port()),
+        };
+        let (mut queue, receiver) = shared_face::<UDP_DEMUX_QUEUE_SIZE>();
+        let Some(face) = control.add_face_with_scope(sender, receiver, Scope::NonLocal) else {
+            return Ok(false);
+        };
+        // Only once the face is added, so that the forwarder is woken up for it
+        let _ = queue.try_send(datagram);
+        self.peers.insert(
+            peer,
+            UdpPeer {
+                face,
+                queue,
+                last_received: now,
+            },
+        );
+        Ok(true)
+    }
+
+    // Returns false once the forwarder is gone
+    fn remove_idle_peers(&mut self, control: &ControlHandle) -> bool {
+        let now = Instant::now();
+        let idle_timeout = self.idle_timeout;
+        let mut forwarder_gone = false;
+        self.peers.retain(|_, peer| {
+            if now.duration_since(peer.last_received) < idle_timeout {
+                return true;
+            }
+            forwarder_gone |= !control.remove_face(peer.face);
+            false
+        });
+        !forwarder_gone
+    }
+}
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+    use core::time::Duration;
+    use std::{net::UdpSocket, time::Instant};
+
+    use crate::{
+        face::{FaceReceiver, FaceSender},
+        io::{Decode, Encode},
+        mgmt,
+        name::{Name, NameComponent},
+        packet::{DataBuilder, Interest},
+        platform::{
+            native::{shared::shared_face, DefaultForwarder},
+            sha::Sha256Hasher,
+            udp::UdpDemux,
+        },
+        tlv::{TlvDecode, TLV},
+    };
+
+    #[test]
+    fn test_udp_demux() {
+        let mut forwarder = DefaultForwarder::default();
+        let (producer_sender, mut producer_end) = shared_face::<16384>();
+        let (mut producer_in, producer_receiver) = shared_face::<16384>();
+        let producer = forwarder
+            .add_face(producer_sender, producer_receiver)
+            .unwrap();
+        let comps = [
+            NameComponent::generic(b"demux"),
+            NameComponent::generic(b"a"),
+            NameComponent::generic(b"b"),
+            NameComponent::generic(mgmt::LOCALHOST_COMPONENT),
+        ];
+        let root = Name::new();
+        let prefix = root.adding_components(&comps[..1]);
+        assert!(forwarder.register_name_prefix_for_forwarding(prefix, producer, 0));
+        let localhost = root.adding_components(&comps[3..]);
+        assert!(forwarder.register_name_prefix_for_forwarding(localhost, producer, 0));
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = socket.local_addr().unwrap();
+        let handle = forwarder.control_handle();
+        let demux = std::thread::spawn(move || {
+            let mut demux = UdpDemux::new(socket, Duration::from_millis(300));
+            demux.serve(&handle)
+        });
+
+        // Each client gets a face of its own, and the data for its interest
+        let clients: Vec<_> = (0..2)
+            .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+            .collect();
+        let names = [
+            prefix.adding_components(&comps[1..2]),
+            prefix.adding_components(&comps[2..3]),
+        ];
+        for (client, name) in clients.iter().zip(names) {
+            client.set_nonblocking(true).unwrap();
+            // The /localhost interest, sent first, never reaches the producer
+            for (name, nonce) in [(localhost.adding_components(&comps[1..2]), 3), (name, 1)] {
+                let mut interest = Vec::new();
+                let _ = Interest::new(name, false, [nonce; 4]).encode(&mut interest);
+                client.send_to(&interest, server).unwrap();
+            }
+        }
+        let mut faces = Vec::new();
+        let mut received = Vec::new();
+        let mut interests = 0;
+        let mut buffer = [0u8; 16384];
+        let start = Instant::now();
+        while interests < 2 && start.elapsed() < Duration::from_secs(5) {
+            if let Ok(face) = forwarder.forward(Some(Duration::from_millis(10))) {
+                if face != producer && !faces.contains(&face) {
+                    faces.push(face);
+                }
+            }
+            let len = producer_end.try_recv(&mut buffer).unwrap();
+            received.extend_from_slice(&buffer[..len]);
+            while let Ok((tlv, len)) = TLV::try_decode(&received) {
+                let interest = Interest::try_decode_from_inner(tlv.val).unwrap();
+                assert!(prefix.is_prefix_of(interest.name));
+                received.drain(..len);
+                interests += 1;
+            }
+        }
+        assert_eq!(interests, 2);
+        assert_eq!(faces.len(), 2);
+
+        let packets: Vec<_> = names
+            .iter()
+            .map(|name| {
+                DataBuilder::new(*name)
+                    .content(b"x")
+                    .digest_sign(&mut Sha256Hasher::new())
+            })
+            .collect();
+        for packet in packets.iter() {
+            assert_eq!(producer_in.try_send(packet), Ok(packet.len()));
+        }
+        let mut replies = [None, None];
+        let start = Instant::now();
+        while replies.contains(&None) && start.elapsed() < Duration::from_secs(5) {
+            let _ = forwarder.forward(Some(Duration::from_millis(10)));
+            for (client, reply) in clients.iter().zip(replies.iter_mut()) {
+                if let Ok((len, from)) = client.recv_from(&mut buffer) {
+                    assert_eq!(from, server);
+                    *reply = Some(buffer[..len].to_vec());
+                }
+            }
+        }
+        assert_eq!(replies[0].as_ref(), Some(&packets[0]));
+        assert_eq!(replies[1].as_ref(), Some(&packets[1]));
+
+        // The faces go once the clients are idle
+        let start = Instant::now();
+        while faces.iter().any(|face| forwarder.has_face(*face))
+            && start.elapsed() < Duration::from_secs(5)
+        {
+            let _ = forwarder.forward(Some(Duration::from_millis(10)));
+        }
+        assert!(!faces.iter().any(|face| forwarder.has_face(*face)));
+
+        // The demux stops with the forwarder
+        drop(forwarder);
+        let mut interest = Vec::new();
+        let _ = Interest::new(names[0], false, [2; 4]).encode(&mut interest);
+        clients[0].send_to(&interest, server).unwrap();
+        assert!(demux.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_udp_demux_max_peers() {
+        let mut forwarder = DefaultForwarder::default();
+        let (producer_sender, mut producer_end) = shared_face::<16384>();
+        let (_producer_in, producer_receiver) = shared_face::<16384>();
+        let producer = forwarder
+            .add_face(producer_sender, producer_receiver)
+            .unwrap();
+        let root = Name::new();
+        let comps = [NameComponent::generic(b"demux")];
+        let prefix = root.adding_components(&comps);
+        assert!(forwarder.register_name_prefix_for_forwarding(prefix, producer, 0));
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = socket.local_addr().unwrap();
+        let handle = forwarder.control_handle();
+        let demux = std::thread::spawn(move || {
+            let mut demux = UdpDemux::new(socket, Duration::from_millis(300));
+            demux.set_max_peers(Some(1));
+            demux.serve(&handle)
+        });
+
+        // Returns the faces the interests came from, once the producer has seen them all
+        let mut buffer = [0u8; 16384];
+        let mut forward_interests = |count: usize, wait: Duration| {
+            let mut faces = Vec::new();
+            let mut received = Vec::new();
+            let mut interests = 0;
+            let start = Instant::now();
+            while interests < count && start.elapsed() < wait {
+                if let Ok(face) = forwarder.forward(Some(Duration::from_millis(10))) {
+                    if face != producer && !faces.contains(&face) {
+                        faces.push(face);
+                    }
+                }
+                let len = producer_end.try_recv(&mut buffer).unwrap();
+                received.extend_from_slice(&buffer[..len]);
+                while let Ok((_, len)) = TLV::try_decode(&received) {
+                    received.drain(..len);
+                    interests += 1;
+                }
+            }
+            (faces, interests)
+        };
+
+        let clients: Vec<_> = (0..2)
+            .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+            .collect();
+        // A name of its own for every interest, so that none is aggregated
+        let send = |client: &UdpSocket, nonce: u8| {
+            let nonce_bytes = [nonce];
+            let comp = [NameComponent::generic(&nonce_bytes)];
+            let name = prefix.adding_components(&comp);
+            let mut interest = Vec::new();
+            let _ = Interest::new(name, false, [nonce; 4]).encode(&mut interest);
+            client.send_to(&interest, server).unwrap();
+        };
+        send(&clients[0], 1);
+        let (faces, interests) = forward_interests(1, Duration::from_secs(5));
+        assert_eq!((faces.len(), interests), (1, 1));
+
+        // The second peer gets no face while the first one is active
+        send(&clients[0], 2);
+        send(&clients[1], 3);
+        let (faces, interests) = forward_interests(2, Duration::from_millis(200));
+        assert_eq!((faces.len(), interests), (1, 1));
+
+        // It does once the first one went idle
+        std::thread::sleep(Duration::from_millis(600));
+        send(&clients[1], 4);
+        let (faces, interests) = forward_interests(1, Duration::from_secs(5));
+        assert_eq!((faces.len(), interests), (1, 1));
+
+        drop(forwarder);
+        send(&clients[0], 5);
+        assert!(demux.join().unwrap().is_ok());
+    }
+}
@@ -1,12 +1,20 @@
 use std::{
     io::{Error, ErrorKind, Read, Write},
-    os::unix::net::{UnixDatagram, UnixStream},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::{
+            ffi::OsStrExt,
+            net::{UnixDatagram, UnixListener, UnixStream},
+        },
+    },
+    path::{Path, PathBuf},
 };
 
-use crate::platform::native::notifying::Notifying;
+use crate::platform::native::{face_error_from_io, notifying::Notifying};
 use crate::{
     face::{FaceError, FaceReceiver, FaceSender},
-    forwarder::MAX_PACKET_SIZE,
+    forwarder::{Scope, MAX_PACKET_SIZE},
+    platform::forwarder::ControlHandle,
 };
 
 use super::notifying::SocketId;
@@ -36,7 +44,11 @@ impl FaceSender for UnixDatagramSender {
             }
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(()),
-                _ => Err(FaceError::Disconnected),
+                _ => {
+                    // The datagram is dropped so that it does not poison the next ones
+                    self.buffer.clear();
+                    Err(face_error_from_io(&io_err))
+                }
             },
         }
     }
@@ -48,7 +60,7 @@ impl FaceReceiver for UnixDatagramReceiver {
             Ok(bytes_received) => Ok(bytes_received),
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(0),
-                _ => Err(FaceError::Disconnected),
+                _ => Err(face_error_from_io(&io_err)),
             },
         }
     }
@@ -98,7 +110,7 @@ impl FaceSender for UnixStreamSender {
             Ok(bytes_sent) => Ok(bytes_sent),
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(0),
-                _ => Err(FaceError::Disconnected),
+                _ => Err(face_error_from_io(&io_err)),
             },
         }
     }
@@ -110,7 +122,7 @@ impl FaceReceiver for UnixStreamReceiver {
             Ok(bytes_received) => Ok(bytes_received),
             Err(io_err) => match io_err.kind() {
                 ErrorKind::WouldBlock => Ok(0),
-                _ => Err(FaceError::Disconnected),
+                _ => Err(face_error_from_io(&io_err)),
             },
         }
     }
@@ -144,3 +156,337 @@ pub fn unix_stream_face(
     let receiver = UnixStreamReceiver { stream };
     Ok((sender, receiver))
 }
+
+// Where the daemon listens for the local applications, as NFD does on /run/nfd.sock
+pub const DEFAULT_UNIX_SOCKET_PATH: &str = "/run/reto.sock";
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum UnixSocketType {
+    Stream,
+    // Keeps the packets apart like a datagram socket, but is connected like a stream
+    SeqPacket,
+}
+
+// A face of the listener, see UnixFaceListener and unix_face_connect
+pub enum UnixFaceSender {
+    Stream(UnixStreamSender),
+    SeqPacket(UnixDatagramSender),
+}
+
+pub enum UnixFaceReceiver {
+    Stream(UnixStreamReceiver),
+    SeqPacket(UnixSeqPacketReceiver),
+}
+
+pub struct UnixSeqPacketReceiver {
+    socket: UnixDatagram,
+}
+
+impl FaceSender for UnixFaceSender {
+    fn try_send(&mut self, src: &[u8]) -> Result<usize, FaceError> {
+        match self {
+            UnixFaceSender::Stream(sender) => sender.try_send(src),
+            UnixFaceSender::SeqPacket(sender) => sender.try_send(src),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), FaceError> {
+        match self {
+            UnixFaceSender::Stream(sender) => sender.flush(),
+            // An empty packet would look like the end of the connection to the other end
+            UnixFaceSender::SeqPacket(sender) if sender.buffer.is_empty() => Ok(()),
+            UnixFaceSender::SeqPacket(sender) => sender.flush(),
+        }
+    }
+}
+
+impl FaceReceiver for UnixFaceReceiver {
+    fn try_recv(&mut self, dst: &mut [u8]) -> Result<usize, FaceError> {
+        match self {
+            UnixFaceReceiver::Stream(receiver) => receiver.try_recv(dst),
+            UnixFaceReceiver::SeqPacket(receiver) => receiver.try_recv(dst),
+        }
+    }
+}
+
+impl FaceReceiver for UnixSeqPacketReceiver {
+    fn try_recv(&mut self, dst: &mut [u8]) -> Result<usize, FaceError> {
+        match self.socket.recv(dst) {
+            // The packets are never empty, so this is the other end closing
+            Ok(0) => Err(FaceError::Disconnected),
+            Ok(bytes_received) => Ok(bytes_received),
+            Err(io_err) => match io_err.kind() {
+                ErrorKind::WouldBlock => Ok(0),
+                _ => Err(face_error_from_io(&io_err)),
+            },
+        }
+    }
+}
+
+#[cfg(any(unix, target_os = "hermit"))]
+impl Notifying for UnixFaceReceiver {
+    fn socket_id(&self) -> Option<SocketId> {
+        use std::os::fd::AsFd;
+        let fd = match self {
+            UnixFaceReceiver::Stream(receiver) => receiver.stream.as_fd(),
+            UnixFaceReceiver::SeqPacket(receiver) => receiver.socket.as_fd(),
+        };
+        Some(SocketId(fd.try_clone_to_owned().ok()?))
+    }
+
+    fn register_waker(&mut self, _waker: Waker) {}
+}
+
+// Accepts the connections of the local applications on a socket file, each of which
+//  becomes a face of its own. The file is removed when the listener is dropped.
+pub struct UnixFaceListener {
+    listener: UnixListener,
+    socket_type: UnixSocketType,
+    path: PathBuf,
+}
+
+impl UnixFaceListener {
+    // A file left behind by a listener that is gone is replaced, but not one that
+    //  is still listened on
+    pub fn bind<P: AsRef<Path>>(path: P, socket_type: UnixSocketType) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let listener = match Self::bind_listener(path, socket_type) {
+            Err(err) if err.kind() == ErrorKind::AddrInUse => match UnixStream::connect(path) {
+                Err(err) if err.kind() == ErrorKind::ConnectionRefused => {
+                    std::fs::remove_file(path)?;
+                    Self::bind_listener(path, socket_type)?
+                }
+                _ => return Err(err),
+            },
+            bound => bound?,
+        };
+        Ok(Self {
+            listener,
+            socket_type,
+            path: path.to_path_buf(),
+        })
+    }
+
+    fn bind_listener(path: &Path, socket_type: UnixSocketType) -> Result<UnixListener, Error> {
+        match socket_type {
+            UnixSocketType::Stream => UnixListener::bind(path),
+            UnixSocketType::SeqPacket => {
+                let socket = seqpacket_socket()?;
+                let (address, length) = socket_address(path)?;
+                // SAFETY: the address is a valid sockaddr_un of that length
+                let bound = unsafe {
+                    libc::bind(
+                        socket.as_raw_fd(),
+                        &address as *const libc::sockaddr_un as *const libc::sockaddr,
+                        length,
+                    )
+                };
+                // SAFETY: the socket is open
+                if bound < 0 || unsafe { libc::listen(socket.as_raw_fd(), 128) } < 0 {
+                    return Err(Error::last_os_error());
+                }
+                Ok(UnixListener::from(socket))
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Blocks until an application connects
+    pub fn accept(&self) -> Result<(UnixFaceSender, UnixFaceReceiver), Error> {
+        let (stream, _) = self.listener.accept()?;
+        match self.socket_type {
+            UnixSocketType::Stream => {
+                let (sender, receiver) = unix_stream_face(stream)?;
+                Ok((
+                    UnixFaceSender::Stream(sender),
+                    UnixFaceReceiver::Stream(receiver),
+                ))
+            }
+            UnixSocketType::SeqPacket => seqpacket_face(OwnedFd::from(stream)),
+        }
+    }
+
+    // Adds every application that connects as a local face of the forwarder of the handle,
+    //  e.g. from a thread of its own. Only returns once the forwarder is gone or has
+    //  no face tokens left, or the listener fails.
+    pub fn serve(&self, control: &ControlHandle) -> Result<(), Error> {
+        loop {
+            let (sender, receiver) = match self.accept() {
+                Ok(face) => face,
+                // Only the connection failed
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        ErrorKind::ConnectionAborted | ErrorKind::Interrupted
+                    ) =>
+                {
+                    continue
+                }
+                Err(err) => return Err(err),
+            };
+            if control
+                .add_face_with_scope(sender, receiver, Scope::Local)
+                .is_none()
+            {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Drop for UnixFaceListener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// The application end, e.g. to connect to DEFAULT_UNIX_SOCKET_PATH
+pub fn unix_face_connect<P: AsRef<Path>>(
+    path: P,
+    socket_type: UnixSocketType,
+) -> Result<(UnixFaceSender, UnixFaceReceiver), Error> {
+    match socket_type {
+        UnixSocketType::Stream => {
+            let (sender, receiver) = unix_stream_face(UnixStream::connect(path)?)?;
+            Ok((
+                UnixFaceSender::Stream(sender),
+                UnixFaceReceiver::Stream(receiver),
+            ))
+        }
+        UnixSocketType::SeqPacket => {
+            let socket = seqpacket_socket()?;
+            let (address, length) = socket_address(path.as_ref())?;
+            // SAFETY: the address is a valid sockaddr_un of that length
+            let connected = unsafe {
+                libc::connect(
+                    socket.as_raw_fd(),
+                    &address as *const libc::sockaddr_un as *const libc::sockaddr,
+                    length,
+                )
+            };
+            if connected < 0 {
+                return Err(Error::last_os_error());
+            }
+            seqpacket_face(socket)
+        }
+    }
+}
+
+// The connected SOCK_SEQPACKET sockets are used like the datagram sockets
+fn seqpacket_face(socket: OwnedFd) -> Result<(UnixFaceSender, UnixFaceReceiver), Error> {
+    let (sender, receiver) = unix_datagram_face(UnixDatagram::from(socket))?;
+    Ok((
+        UnixFaceSender::SeqPacket(sender),
+        UnixFaceReceiver::SeqPacket(UnixSeqPacketReceiver {
+            socket: receiver.socket,
+        }),
+    ))
+}
+
+// The standard library does not open them
+fn seqpacket_socket() -> Result<OwnedFd, Error> {
+    // SAFETY: no pointers are involved
+    let fd = unsafe { libc::socket(libc::AF_UNIX, libc::SOCK_SEQPACKET, 0) };
+    if fd < 0 {
+        return Err(Error::last_os_error());
+    }
+    // SAFETY: the descriptor was just opened and nothing else owns it
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    // Not inherited by the child processes, as for the sockets of the standard library
+    // SAFETY: the socket is open
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(socket)
+}
+
+fn socket_address(path: &Path) -> Result<(libc::sockaddr_un, libc::socklen_t), Error> {
+    // SAFETY: all zeroes is a valid sockaddr_un
+    let mut address: libc::sockaddr_un = unsafe { core::mem::zeroed() };
+    let bytes = path.as_os_str().as_bytes();
+    // The path is terminated by a zero
+    if bytes.len() >= address.sun_path.len() || bytes.contains(&0) {
+        return Err(ErrorKind::InvalidInput.into());
+    }
+    address.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    for (dst, src) in address.sun_path.iter_mut().zip(bytes) {
+        *dst = *src as libc::c_char;
+    }
+    let length = core::mem::size_of::<libc::sockaddr_un>() as libc::socklen_t;
+    Ok((address, length))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    use crate::{
+        face::{FaceError, FaceReceiver, FaceSender},
+        platform::unix::{unix_face_connect, UnixFaceListener, UnixSocketType},
+    };
+
+    #[test]
+    fn test_unix_face_listener() {
+        for socket_type in [UnixSocketType::Stream, UnixSocketType::SeqPacket] {
+            let path = std::env::temp_dir().join(format!(
+                "reto-test-{}-{:?}.sock",
+                std::process::id(),
+                socket_type
+            ));
+            let listener = UnixFaceListener::bind(&path, socket_type).unwrap();
+
+            // Each application gets a face of its own
+            let mut buffer = [0; 16];
+            let mut clients = Vec::new();
+            for packet in [b"first", b"other"] {
+                let (mut app_sender, app_receiver) = unix_face_connect(&path, socket_type).unwrap();
+                let (mut sender, mut receiver) = listener.accept().unwrap();
+                assert_eq!(app_sender.try_send(packet), Ok(5));
+                assert_eq!(app_sender.flush(), Ok(()));
+                assert_eq!(receiver.try_recv(&mut buffer), Ok(5));
+                assert_eq!(&buffer[..5], packet);
+                assert_eq!(sender.try_send(b"back"), Ok(4));
+                assert_eq!(sender.flush(), Ok(()));
+                clients.push((app_sender, app_receiver, receiver));
+            }
+            for (_, app_receiver, _) in clients.iter_mut() {
+                assert_eq!(app_receiver.try_recv(&mut buffer), Ok(4));
+                assert_eq!(&buffer[..4], b"back");
+            }
+            if socket_type == UnixSocketType::SeqPacket {
+                let (app_sender, app_receiver, mut receiver) = clients.remove(0);
+                drop((app_sender, app_receiver));
+                assert_eq!(receiver.try_recv(&mut buffer), Err(FaceError::Disconnected));
+            }
+
+            // The file is in use
+            assert!(UnixFaceListener::bind(&path, socket_type).is_err());
+
+            drop(clients);
+            drop(listener);
+            assert!(!path.exists());
+
+            // The file left behind, e.g. by a crash, is replaced
+            drop(UnixListener::bind(&path).unwrap());
+            assert!(path.exists());
+            let listener = UnixFaceListener::bind(&path, socket_type).unwrap();
+            drop(listener);
+            assert!(!path.exists());
+        }
+
+        // A stream client can also talk to the stream listener with the plain socket
+        let path = std::env::temp_dir().join(format!("reto-test-{}.sock", std::process::id()));
+        let listener = UnixFaceListener::bind(&path, UnixSocketType::Stream).unwrap();
+        let mut client = UnixStream::connect(&path).unwrap();
+        let (_, mut receiver) = listener.accept().unwrap();
+        client.write_all(b"plain").unwrap();
+        let mut buffer = [0; 16];
+        assert_eq!(receiver.try_recv(&mut buffer), Ok(5));
+        drop(receiver);
+        assert_eq!(client.read(&mut buffer).unwrap(), 0);
+    }
+}
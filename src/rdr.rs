@@ -0,0 +1,163 @@
+use alloc::vec::Vec;
+
+use crate::{
+    io::{Decode, Encode},
+    name::{Name, NameComponent},
+    packet::{Data, DataBuilder, Interest, MustBeFresh},
+    security::Signer,
+};
+
+// Realtime Data Retrieval (RDR), see https://redmine.named-data.net/projects/ndn-tlv/wiki/RDR
+// A consumer discovers the latest version of the data under a prefix by asking for
+//  <prefix>/32=metadata, and the producer replies with a short-lived metadata packet
+//  named <prefix>/32=metadata/<version>/<segment 0> whose content is the full name
+//  of the latest version.
+
+pub const METADATA_KEYWORD: &[u8] = b"metadata";
+
+// The metadata must expire quickly, so that new versions are discovered
+pub const DEFAULT_METADATA_FRESHNESS_MS: u64 = 10;
+
+pub fn metadata_component() -> NameComponent<'static> {
+    NameComponent::new(NameComponent::TYPE_KEYWORD, METADATA_KEYWORD).unwrap()
+}
+
+fn is_metadata_component(component: NameComponent<'_>) -> bool {
+    component == metadata_component()
+}
+
+// Returns the version number if the last component of the name is a version
+pub fn version_of(name: Name<'_>) -> Option<u64> {
+    let last = name.components().last()?;
+    if last.typ.get() != NameComponent::TYPE_VERSION_NAME {
+        return None;
+    }
+    Some(u64::try_decode(last.bytes).ok()?.0)
+}
+
+// Consumer side: the encoded discovery interest for the prefix
+pub fn discovery_interest(prefix: Name<'_>, nonce: [u8; 4]) -> Vec<u8> {
+    let comp = &[metadata_component()];
+    let name = prefix.adding_components(comp);
+    let mut interest = Interest::new(name, true, nonce);
+    interest.must_be_fresh = Some(MustBeFresh {});
+    let mut packet = Vec::with_capacity(interest.encoded_length());
+    let _ = interest.encode(&mut packet);
+    packet
+}
+
+// Consumer side: the name of the latest version carried by the metadata packet
+pub fn parse_metadata<'a>(data: &Data<'a>) -> Option<Name<'a>> {
+    // The name ends with 32=metadata/<version>/<segment>
+    let without_segment = data.name.dropping_last_component()?;
+    version_of(without_segment)?;
+    let keyword = without_segment
+        .dropping_last_component()?
+        .components()
+        .last()?;
+    if !is_metadata_component(keyword) {
+        return None;
+    }
+    Some(Name::try_decode(data.content.as_ref()?.bytes).ok()?.0)
+}
+
+// Producer side: the prefix for which the interest asks the latest version,
+//  or None if it is not a discovery interest
+pub fn discovery_prefix<'a>(interest: &Interest<'a>) -> Option<Name<'a>> {
+    let last = interest.name.components().last()?;
+    if !is_metadata_component(last) {
+        return None;
+    }
+    interest.name.dropping_last_component()
+}
+
+// Producer side: the encoded metadata packet pointing to the latest versioned name,
+//  which must be under the prefix and end with a version component
+pub fn metadata_data<S: Signer + ?Sized>(
+    prefix: Name<'_>,
+    latest: Name<'_>,
+    signer: &mut S,
+) -> Option<Vec<u8>> {
+    if !prefix.is_prefix_of(latest) {
+        return None;
+    }
+    let version = version_of(latest)?;
+
+    let mut version_bytes = Vec::new();
+    let _ = version.encode(&mut version_bytes);
+    let mut segment_bytes = Vec::new();
+    let _ = 0u64.encode(&mut segment_bytes);
+    let comp = &[
+        metadata_component(),
+        NameComponent::new(NameComponent::TYPE_VERSION_NAME, &version_bytes)?,
+        NameComponent::new(NameComponent::TYPE_SEGMENT, &segment_bytes)?,
+    ];
+    let name = prefix.adding_components(comp);
+
+    let mut content = Vec::with_capacity(latest.encoded_length());
+    let _ = latest.encode(&mut content);
+
+    Some(
+        DataBuilder::new(name)
+            .content(&content)
+            .freshness_period(DEFAULT_METADATA_FRESHNESS_MS)
+            .sign(signer),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        io::Decode,
+        name::{Name, NameComponent},
+        packet::{Data, Interest},
+        platform::sha::Sha256Hasher,
+        rdr::{
+            discovery_interest, discovery_prefix, metadata_data, parse_metadata, version_of,
+            DEFAULT_METADATA_FRESHNESS_MS,
+        },
+        security::DigestSha256,
+    };
+
+    #[test]
+    fn test_discovery() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"video")];
+        let prefix = root.adding_components(comp);
+        let comp = &[NameComponent::new(NameComponent::TYPE_VERSION_NAME, &[7]).unwrap()];
+        let latest = prefix.adding_components(comp);
+        assert_eq!(version_of(latest), Some(7));
+        assert_eq!(version_of(prefix), None);
+
+        // The consumer asks
+        let packet = discovery_interest(prefix, [1, 2, 3, 4]);
+        let (interest, _) = Interest::try_decode(&packet).unwrap();
+        assert!(interest.can_be_prefix.is_some());
+        assert!(interest.must_be_fresh.is_some());
+
+        // The producer replies
+        let asked = discovery_prefix(&interest).unwrap();
+        assert!(asked == prefix);
+        let mut signer = DigestSha256::new(Sha256Hasher::new());
+        assert!(metadata_data(latest, prefix, &mut signer).is_none());
+        let packet = metadata_data(asked, latest, &mut signer).unwrap();
+
+        // The consumer learns the latest version
+        let (data, _) = Data::try_decode(&packet).unwrap();
+        assert!(interest.name.is_prefix_of(data.name));
+        assert_eq!(
+            data.meta_info
+                .as_ref()
+                .unwrap()
+                .freshness_period
+                .unwrap()
+                .val,
+            DEFAULT_METADATA_FRESHNESS_MS
+        );
+        assert!(parse_metadata(&data).unwrap() == latest);
+
+        // Regular interests are not mistaken for discovery
+        let regular = Interest::new(latest, false, [0; 4]);
+        assert!(discovery_prefix(&regular).is_none());
+    }
+}
@@ -0,0 +1,346 @@
+use alloc::{rc::Rc, vec::Vec};
+use core::cell::{Cell, RefCell};
+
+use crate::{
+    clock::{Clock, Timestamp},
+    face::{
+        local::{default_local_face, LocalReceiver, LocalSender},
+        FaceReceiver, FaceSender,
+    },
+    forwarder::{
+        CapturedPacket, Direction, FaceToken, Forwarder, ForwarderMetrics, PacketCapture,
+        MAX_PACKET_SIZE,
+    },
+    hash::{Hasher, Sha256Digest},
+    io::Decode,
+    tables::Tables,
+    tlv::TLV,
+};
+
+// A packet of a capture, see CaptureLog
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CaptureRecord {
+    pub face: FaceToken,
+    pub direction: Direction,
+    pub timestamp: Timestamp,
+    pub bytes: Vec<u8>,
+}
+
+// Keeps the packets in memory. The forwarder is given a clone, see
+//  Forwarder::set_packet_capture, and the packets are taken from this one.
+#[derive(Clone, Default)]
+pub struct CaptureLog {
+    records: Rc<RefCell<Vec<CaptureRecord>>>,
+}
+
+impl CaptureLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The packets captured since the last time, in order
+    pub fn take(&self) -> Vec<CaptureRecord> {
+        core::mem::take(&mut *self.records.borrow_mut())
+    }
+}
+
+impl PacketCapture for CaptureLog {
+    fn capture(&mut self, packet: &CapturedPacket<'_>) {
+        self.records.borrow_mut().push(CaptureRecord {
+            face: packet.face,
+            direction: packet.direction,
+            timestamp: packet.timestamp,
+            bytes: packet.bytes.to_vec(),
+        });
+    }
+}
+
+// Each record is the face (4 bytes), the direction (1 byte, 0 for ingress), the timestamp
+//  (8 bytes) and the length of the packet (4 bytes), all big-endian, then the packet
+const RECORD_HEADER_LENGTH: usize = 17;
+
+// So that a capture can be attached to a bug report
+pub fn encode_capture(records: &[CaptureRecord]) -> Vec<u8> {
+    let length = records
+        .iter()
+        .map(|record| RECORD_HEADER_LENGTH + record.bytes.len())
+        .sum();
+    let mut encoded = Vec::with_capacity(length);
+    for record in records {
+        encoded.extend_from_slice(&record.face.0.to_be_bytes());
+        encoded.push(match record.direction {
+            Direction::Ingress => 0,
+            Direction::Egress => 1,
+        });
+        encoded.extend_from_slice(&record.timestamp.ms_since_1970.to_be_bytes());
+        encoded.extend_from_slice(&(record.bytes.len() as u32).to_be_bytes());
+        encoded.extend_from_slice(&record.bytes);
+    }
+    encoded
+}
+
+// None if the bytes are not a whole capture
+pub fn decode_capture(mut bytes: &[u8]) -> Option<Vec<CaptureRecord>> {
+    let mut records = Vec::new();
+    while !bytes.is_empty() {
+        let header = bytes.get(..RECORD_HEADER_LENGTH)?;
+        let face = u32::from_be_bytes(header[0..4].try_into().ok()?);
+        let direction = match header[4] {
+            0 => Direction::Ingress,
+            1 => Direction::Egress,
+            _ => return None,
+        };
+        let timestamp = u64::from_be_bytes(header[5..13].try_into().ok()?);
+        let length = u32::from_be_bytes(header[13..17].try_into().ok()?) as usize;
+        let end = RECORD_HEADER_LENGTH.checked_add(length)?;
+        records.push(CaptureRecord {
+            face: FaceToken(face),
+            direction,
+            timestamp: Timestamp {
+                ms_since_1970: timestamp,
+            },
+            bytes: bytes.get(RECORD_HEADER_LENGTH..end)?.to_vec(),
+        });
+        bytes = &bytes[end..];
+    }
+    Some(records)
+}
+
+// Only moves when the replay sets it
+#[derive(Clone, Default)]
+pub struct ReplayClock {
+    now: Rc<Cell<Timestamp>>,
+}
+
+impl ReplayClock {
+    pub fn set(&self, now: Timestamp) {
+        self.now.set(now);
+    }
+}
+
+impl Clock for ReplayClock {
+    fn now(&mut self) -> Timestamp {
+        self.now.get()
+    }
+}
+
+// Feeds the packets that a forwarder received, as captured, to another forwarder in the
+//  same order and at the same times, e.g. to reproduce a bug in the tables. The other
+//  forwarder is built with the clock of the replay and configured as the captured one
+//  was, including its routes, but keeps the deterministic nonces of Forwarder::new.
+pub struct Replay {
+    records: Vec<CaptureRecord>,
+    clock: ReplayClock,
+    faces: Vec<ReplayFace>,
+}
+
+// The other end of the face added for a face of the capture
+struct ReplayFace {
+    captured: FaceToken,
+    token: FaceToken,
+    sender: LocalSender,
+    receiver: LocalReceiver,
+    // What the forwarder sent that is not a whole packet yet
+    received: Vec<u8>,
+}
+
+impl Replay {
+    pub fn new(records: Vec<CaptureRecord>) -> Self {
+        let clock = ReplayClock::default();
+        if let Some(first) = records.first() {
+            clock.set(first.timestamp);
+        }
+        Self {
+            records,
+            clock,
+            faces: Vec::new(),
+        }
+    }
+
+    pub fn clock(&self) -> ReplayClock {
+        self.clock.clone()
+    }
+
+    // Adds a face to the forwarder for every face of the capture, in the order they
+    //  first appear. Returns false if the forwarder ran out of the face tokens.
+    pub fn add_faces<C, H, M, T>(&mut self, forwarder: &mut Forwarder<C, H, M, T>) -> bool
+    where
+        C: Clock,
+        H: Hasher<Digest = Sha256Digest>,
+        M: ForwarderMetrics,
+        T: Tables,
+    {
+        for record in self.records.iter() {
+            if self.faces.iter().any(|face| face.captured == record.face) {
+                continue;
+            }
+            let (sender, forwarder_receiver) = default_local_face();
+            let (forwarder_sender, receiver) = default_local_face();
+            let Some(token) = forwarder.add_face(forwarder_sender, forwarder_receiver) else {
+                return false;
+            };
+            self.faces.push(ReplayFace {
+                captured: record.face,
+                token,
+                sender,
+                receiver,
+                received: Vec::new(),
+            });
+        }
+        true
+    }
+
+    // The face added for a face of the capture, e.g. to register the routes towards,
+    //  or for Forwarder::import_routes
+    pub fn face(&self, captured: FaceToken) -> Option<FaceToken> {
+        self.faces
+            .iter()
+            .find(|face| face.captured == captured)
+            .map(|face| face.token)
+    }
+
+    // Replays the packets received in the capture, and returns those the forwarder sent
+    //  in turn, with the faces and the times of the capture, e.g. to compare them with
+    //  the packets sent in the capture
+    pub fn run<C, H, M, T>(&mut self, forwarder: &mut Forwarder<C, H, M, T>) -> Vec<CaptureRecord>
+    where
+        C: Clock,
+        H: Hasher<Digest = Sha256Digest>,
+        M: ForwarderMetrics,
+        T: Tables,
+    {
+        let mut sent = Vec::new();
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        for record in self.records.iter() {
+            if record.direction != Direction::Ingress {
+                continue;
+            }
+            self.clock.set(record.timestamp);
+            let Some(face) = self.faces.iter_mut().find(|f| f.captured == record.face) else {
+                continue;
+            };
+            if face.sender.try_send(&record.bytes) != Ok(record.bytes.len()) {
+                continue;
+            }
+            while forwarder.try_forward_from_face(face.token).is_ok() {}
+
+            for face in self.faces.iter_mut() {
+                while let Ok(len @ 1..) = face.receiver.try_recv(&mut buffer) {
+                    face.received.extend_from_slice(&buffer[..len]);
+                }
+                while let Ok((_, len)) = TLV::try_decode(&face.received) {
+                    sent.push(CaptureRecord {
+                        face: face.captured,
+                        direction: Direction::Egress,
+                        timestamp: record.timestamp,
+                        bytes: face.received.drain(..len).collect(),
+                    });
+                }
+            }
+        }
+        sent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clock::Timestamp,
+        face::local::default_local_face,
+        forwarder::{Direction, Forwarder, InertMetrics},
+        io::{Encode, Write},
+        name::{Name, NameComponent},
+        packet::{DataBuilder, Interest},
+        platform::sha::Sha256Hasher,
+        replay::{decode_capture, encode_capture, CaptureLog, Replay, ReplayClock},
+        tables::reference::ReferenceTables,
+    };
+
+    #[test]
+    fn test_capture_and_replay() {
+        let clock = ReplayClock::default();
+        let mut forwarder = Forwarder::new(
+            clock.clone(),
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        let log = CaptureLog::new();
+        forwarder.set_packet_capture(log.clone());
+        let (fs1, _consumer) = default_local_face();
+        let (mut face1sender, fr1) = default_local_face();
+        let face1 = forwarder.add_face(fs1, fr1).unwrap();
+        let (fs2, _producer) = default_local_face();
+        let (mut face2sender, fr2) = default_local_face();
+        let face2 = forwarder.add_face(fs2, fr2).unwrap();
+        forwarder.set_nack_on_no_route(face1, true);
+
+        let comps = [
+            NameComponent::generic(b"replay"),
+            NameComponent::generic(b"a"),
+        ];
+        let root = Name::new();
+        let prefix = root.adding_components(&comps[..1]);
+        let name = root.adding_components(&comps);
+        forwarder.register_name_prefix_for_forwarding(prefix, face2, 0);
+
+        clock.set(Timestamp {
+            ms_since_1970: 1000,
+        });
+        assert!(Interest::new(name, false, [1; 4])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+        clock.set(Timestamp {
+            ms_since_1970: 1010,
+        });
+        let packet = DataBuilder::new(name)
+            .content(b"a")
+            .digest_sign(&mut Sha256Hasher::new());
+        assert!(face2sender.write(&packet).is_ok());
+        assert!(forwarder.try_forward_from_face(face2).is_ok());
+        // Without a route
+        clock.set(Timestamp {
+            ms_since_1970: 1020,
+        });
+        let other = root.adding_components(&comps[1..]);
+        assert!(Interest::new(other, false, [2; 4])
+            .encode(&mut face1sender)
+            .is_ok());
+        assert!(forwarder.try_forward_from_face(face1).is_ok());
+
+        // Interest in, interest out, data in, data out, interest in, nack out
+        let records = log.take();
+        let directions: Vec<Direction> = records.iter().map(|r| r.direction).collect();
+        assert_eq!(
+            directions,
+            [Direction::Ingress, Direction::Egress].repeat(3)
+        );
+        assert_eq!(records[1].face, face2);
+        assert_eq!(records[3].face, face1);
+        assert_eq!(records[3].bytes, packet);
+
+        let encoded = encode_capture(&records);
+        assert_eq!(decode_capture(&encoded), Some(records.clone()));
+        assert_eq!(decode_capture(&encoded[..encoded.len() - 1]), None);
+
+        // The other forwarder, with the same route, sends the same packets
+        let mut replay = Replay::new(decode_capture(&encoded).unwrap());
+        let mut replayed = Forwarder::new(
+            replay.clock(),
+            Sha256Hasher::new(),
+            InertMetrics {},
+            ReferenceTables::default(),
+        );
+        assert!(replay.add_faces(&mut replayed));
+        let replayed_face1 = replay.face(face1).unwrap();
+        replayed.set_nack_on_no_route(replayed_face1, true);
+        replayed.register_name_prefix_for_forwarding(prefix, replay.face(face2).unwrap(), 0);
+        let sent = replay.run(&mut replayed);
+        let captured_sent: Vec<_> = records
+            .into_iter()
+            .filter(|r| r.direction == Direction::Egress)
+            .collect();
+        assert_eq!(sent, captured_sent);
+    }
+}
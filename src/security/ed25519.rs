@@ -0,0 +1,174 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use ed25519_dalek::{
+    pkcs8::{DecodePrivateKey, EncodePrivateKey},
+    Signature, Signer as _, SigningKey, VerifyingKey,
+};
+
+use crate::{
+    name::Name,
+    packet::SignatureType,
+    security::{keychain::KeyMaterial, Signer, Verifier},
+};
+
+// An Ed25519 private key.
+// Ed25519 needs the whole message at once, so the signer and the verifier
+//  collect the signed portion in a buffer until it is finalized.
+pub struct Ed25519Key {
+    signing_key: SigningKey,
+}
+
+impl Ed25519Key {
+    pub fn from_secret_bytes(secret: &[u8; 32]) -> Self {
+        Self {
+            signing_key: SigningKey::from_bytes(secret),
+        }
+    }
+
+    // Reads the key from an unencrypted PKCS#8 PrivateKeyInfo
+    pub fn from_pkcs8_der(der: &[u8]) -> Option<Self> {
+        Some(Self {
+            signing_key: SigningKey::from_pkcs8_der(der).ok()?,
+        })
+    }
+
+    pub fn public_key_bytes(&self) -> [u8; 32] {
+        self.signing_key.verifying_key().to_bytes()
+    }
+}
+
+impl KeyMaterial for Ed25519Key {
+    fn signature_type(&self) -> u64 {
+        SignatureType::ED25519
+    }
+
+    fn signer<'a>(&'a self, key_locator: Name<'a>) -> Box<dyn Signer + 'a> {
+        Box::new(Ed25519Signer {
+            key_locator,
+            signing_key: &self.signing_key,
+            message: Vec::new(),
+        })
+    }
+
+    fn verifier<'a>(&'a self) -> Box<dyn Verifier + 'a> {
+        Box::new(Ed25519Verifier {
+            verifying_key: self.signing_key.verifying_key(),
+            message: Vec::new(),
+        })
+    }
+
+    fn to_pkcs8_der(&self) -> Option<Vec<u8>> {
+        Some(Vec::from(self.signing_key.to_pkcs8_der().ok()?.as_bytes()))
+    }
+}
+
+pub struct Ed25519Signer<'a> {
+    key_locator: Name<'a>,
+    signing_key: &'a SigningKey,
+    message: Vec<u8>,
+}
+
+impl<'a> Signer for Ed25519Signer<'a> {
+    fn signature_type(&self) -> u64 {
+        SignatureType::ED25519
+    }
+
+    fn key_locator(&self) -> Option<Name<'_>> {
+        Some(self.key_locator)
+    }
+
+    fn reset(&mut self) {
+        self.message.clear();
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.message.extend_from_slice(input);
+    }
+
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        let signature = self.signing_key.sign(&self.message);
+        self.message.clear();
+        Vec::from(signature.to_bytes())
+    }
+}
+
+// Verifies Ed25519 signatures with a public key, e.g. taken from a certificate
+pub struct Ed25519Verifier {
+    verifying_key: VerifyingKey,
+    message: Vec<u8>,
+}
+
+impl Ed25519Verifier {
+    pub fn from_public_key_bytes(public_key: &[u8; 32]) -> Option<Self> {
+        Some(Self {
+            verifying_key: VerifyingKey::from_bytes(public_key).ok()?,
+            message: Vec::new(),
+        })
+    }
+}
+
+impl Verifier for Ed25519Verifier {
+    fn signature_type(&self) -> u64 {
+        SignatureType::ED25519
+    }
+
+    fn reset(&mut self) {
+        self.message.clear();
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.message.extend_from_slice(input);
+    }
+
+    fn finalize_verify_reset(&mut self, signature: &[u8]) -> bool {
+        let valid = match Signature::from_slice(signature) {
+            Ok(signature) => self
+                .verifying_key
+                .verify_strict(&self.message, &signature)
+                .is_ok(),
+            Err(_) => false,
+        };
+        self.message.clear();
+        valid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        name::{Name, NameComponent},
+        packet::{Data, SignatureInfo, SignatureValue},
+        security::{
+            ed25519::{Ed25519Key, Ed25519Verifier},
+            keychain::KeyMaterial,
+        },
+    };
+
+    #[test]
+    fn test_sign_verify() {
+        let key = Ed25519Key::from_secret_bytes(&[7; 32]);
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"key")];
+        let key_name = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"data")];
+        let name = root.adding_components(comp);
+
+        let mut signer = key.signer(key_name);
+        let signature_info = SignatureInfo::new(signer.signature_type(), Some(key_name));
+        let mut data = Data::new_unsigned(name, b"content", signature_info);
+        let signature = data.sign_signed_portion(signer.as_mut());
+        assert_eq!(signature.len(), 64);
+        data.signature_value = SignatureValue { bytes: &signature };
+
+        assert!(data.verify_signature(key.verifier().as_mut()));
+        let mut verifier = Ed25519Verifier::from_public_key_bytes(&key.public_key_bytes()).unwrap();
+        assert!(data.verify_signature(&mut verifier));
+
+        let other = Ed25519Key::from_secret_bytes(&[8; 32]);
+        assert!(!data.verify_signature(other.verifier().as_mut()));
+
+        let der = key.to_pkcs8_der().unwrap();
+        let restored = Ed25519Key::from_pkcs8_der(&der).unwrap();
+        assert_eq!(restored.public_key_bytes(), key.public_key_bytes());
+    }
+}
@@ -0,0 +1,163 @@
+use alloc::vec::Vec;
+
+use crate::{
+    hash::{Hasher, Sha256Digest},
+    name::Name,
+    packet::SignatureType,
+    security::{constant_time_eq, Signer, Verifier},
+};
+
+const BLOCK_SIZE: usize = 64;
+
+// HMAC (RFC 2104) on top of any SHA256 Hasher implementation.
+pub struct HmacSha256<H: Hasher<Digest = Sha256Digest>> {
+    hasher: H,
+    inner_pad: [u8; BLOCK_SIZE],
+    outer_pad: [u8; BLOCK_SIZE],
+}
+
+impl<H: Hasher<Digest = Sha256Digest>> HmacSha256<H> {
+    pub fn new(key: &[u8], mut hasher: H) -> Self {
+        let mut padded_key = [0u8; BLOCK_SIZE];
+        if key.len() > BLOCK_SIZE {
+            // Keys longer than the block are hashed first
+            hasher.reset();
+            hasher.update(key);
+            padded_key[..32].copy_from_slice(&hasher.finalize_reset().0);
+        } else {
+            padded_key[..key.len()].copy_from_slice(key);
+        }
+
+        let inner_pad = padded_key.map(|b| b ^ 0x36);
+        let outer_pad = padded_key.map(|b| b ^ 0x5c);
+
+        hasher.reset();
+        hasher.update(&inner_pad);
+
+        Self {
+            hasher,
+            inner_pad,
+            outer_pad,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.hasher.reset();
+        self.hasher.update(&self.inner_pad);
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.hasher.update(input);
+    }
+
+    pub fn finalize_reset(&mut self) -> Sha256Digest {
+        let inner = self.hasher.finalize_reset();
+        self.hasher.update(&self.outer_pad);
+        self.hasher.update(&inner.0);
+        let outer = self.hasher.finalize_reset();
+        self.hasher.update(&self.inner_pad);
+        outer
+    }
+}
+
+// Signs and verifies packets with a shared secret, naming the key in the KeyLocator.
+pub struct HmacSha256Signer<'a, H: Hasher<Digest = Sha256Digest>> {
+    key_name: Name<'a>,
+    mac: HmacSha256<H>,
+}
+
+impl<'a, H: Hasher<Digest = Sha256Digest>> HmacSha256Signer<'a, H> {
+    pub fn new(key_name: Name<'a>, key: &[u8], hasher: H) -> Self {
+        Self {
+            key_name,
+            mac: HmacSha256::new(key, hasher),
+        }
+    }
+}
+
+impl<'a, H: Hasher<Digest = Sha256Digest>> Signer for HmacSha256Signer<'a, H> {
+    fn signature_type(&self) -> u64 {
+        SignatureType::HMAC_SHA256
+    }
+
+    fn key_locator(&self) -> Option<Name<'_>> {
+        Some(self.key_name)
+    }
+
+    fn reset(&mut self) {
+        self.mac.reset()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.mac.update(input)
+    }
+
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        Vec::from(self.mac.finalize_reset().0)
+    }
+}
+
+impl<'a, H: Hasher<Digest = Sha256Digest>> Verifier for HmacSha256Signer<'a, H> {
+    fn signature_type(&self) -> u64 {
+        SignatureType::HMAC_SHA256
+    }
+
+    fn reset(&mut self) {
+        self.mac.reset()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.mac.update(input)
+    }
+
+    fn finalize_verify_reset(&mut self, signature: &[u8]) -> bool {
+        constant_time_eq(&self.mac.finalize_reset().0, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{platform::sha::Sha256Hasher, security::hmac::HmacSha256};
+
+    #[test]
+    fn test_rfc4231_vectors() {
+        // Test case 1
+        let mut mac = HmacSha256::new(&[0x0b; 20], Sha256Hasher::new());
+        mac.update(b"Hi There");
+        assert_eq!(
+            mac.finalize_reset().0,
+            [
+                0xb0, 0x34, 0x4c, 0x61, 0xd8, 0xdb, 0x38, 0x53, 0x5c, 0xa8, 0xaf, 0xce, 0xaf, 0x0b,
+                0xf1, 0x2b, 0x88, 0x1d, 0xc2, 0x00, 0xc9, 0x83, 0x3d, 0xa7, 0x26, 0xe9, 0x37, 0x6c,
+                0x2e, 0x32, 0xcf, 0xf7
+            ]
+        );
+
+        // Test case 2, also checking that the state is reset after finalization
+        let mut mac = HmacSha256::new(b"Jefe", Sha256Hasher::new());
+        for _ in 0..2 {
+            mac.update(b"what do ya want ");
+            mac.update(b"for nothing?");
+            assert_eq!(
+                mac.finalize_reset().0,
+                [
+                    0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08,
+                    0x95, 0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec,
+                    0x58, 0xb9, 0x64, 0xec, 0x38, 0x43
+                ]
+            );
+        }
+
+        // Test case 6, with a key longer than the block size
+        let mut mac = HmacSha256::new(&[0xaa; 131], Sha256Hasher::new());
+        mac.update(b"Test Using Larger Than Block-Size Key - Hash Key First");
+        assert_eq!(
+            mac.finalize_reset().0,
+            [
+                0x60, 0xe4, 0x31, 0x59, 0x1e, 0xe0, 0xb6, 0x7f, 0x0d, 0x8a, 0x26, 0xaa, 0xcb, 0xf5,
+                0xb7, 0x7f, 0x8e, 0x0b, 0xc6, 0x21, 0x37, 0x28, 0xc5, 0x14, 0x05, 0x46, 0x04, 0x0f,
+                0x0e, 0xe3, 0x7f, 0x54
+            ]
+        );
+    }
+}
@@ -0,0 +1,547 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    hash::{Hasher, Sha256Digest},
+    io::Decode,
+    name::{Name, NameBuf, NameComponent},
+    packet::{Data, SignatureType},
+    security::{hmac::HmacSha256Signer, Signer, Verifier},
+};
+
+// Key material kept in the KeyChain.
+// Implementations provide signing and verification for one particular algorithm.
+pub trait KeyMaterial {
+    // One of the SignatureType constants
+    fn signature_type(&self) -> u64;
+
+    // Creates a signer that will put the key_locator into the signed packets
+    fn signer<'a>(&'a self, key_locator: Name<'a>) -> Box<dyn Signer + 'a>;
+
+    fn verifier<'a>(&'a self) -> Box<dyn Verifier + 'a>;
+
+    // The unencrypted PKCS#8 PrivateKeyInfo, if the key can be exported
+    fn to_pkcs8_der(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+// A shared secret used for HMAC-SHA256 signatures
+pub struct HmacSha256Key<H: Hasher<Digest = Sha256Digest> + Clone> {
+    secret: Vec<u8>,
+    hasher: H,
+}
+
+impl<H: Hasher<Digest = Sha256Digest> + Clone> HmacSha256Key<H> {
+    pub fn new(secret: &[u8], hasher: H) -> Self {
+        Self {
+            secret: Vec::from(secret),
+            hasher,
+        }
+    }
+}
+
+impl<H: Hasher<Digest = Sha256Digest> + Clone> KeyMaterial for HmacSha256Key<H> {
+    fn signature_type(&self) -> u64 {
+        SignatureType::HMAC_SHA256
+    }
+
+    fn signer<'a>(&'a self, key_locator: Name<'a>) -> Box<dyn Signer + 'a> {
+        Box::new(HmacSha256Signer::new(
+            key_locator,
+            &self.secret,
+            self.hasher.clone(),
+        ))
+    }
+
+    fn verifier<'a>(&'a self) -> Box<dyn Verifier + 'a> {
+        Box::new(HmacSha256Signer::new(
+            Name::new(),
+            &self.secret,
+            self.hasher.clone(),
+        ))
+    }
+}
+
+// A certificate is a regular data packet named /<key name>/<issuer>/<version>
+//  whose content is the public key.
+pub struct Certificate {
+    name: NameBuf,
+    packet: Vec<u8>,
+}
+
+impl Certificate {
+    pub fn name(&self) -> Name<'_> {
+        self.name.as_name()
+    }
+
+    // The whole encoded packet, including the outer Data TLV
+    pub fn packet(&self) -> &[u8] {
+        &self.packet
+    }
+
+    pub fn data(&self) -> Data<'_> {
+        // Would not have been created otherwise
+        Data::try_decode(&self.packet).ok().unwrap().0
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum KeyChainError {
+    IdentityNotFound,
+    IdentityAlreadyExists,
+    KeyNotFound,
+    KeyAlreadyExists,
+    KeyOutsideOfIdentity,
+    CertificateNotFound,
+    CertificateOutsideOfKey,
+    InvalidCertificate,
+}
+
+// The in-memory store of identities, their keys, and the certificates of those keys.
+// Each level has a default element, which is always kept first in its list.
+#[derive(Default)]
+pub struct KeyChain {
+    identities: Vec<Identity>,
+}
+
+struct Identity {
+    name: NameBuf,
+    keys: Vec<Key>,
+}
+
+struct Key {
+    name: NameBuf,
+    material: Box<dyn KeyMaterial>,
+    certificates: Vec<Certificate>,
+}
+
+impl Key {
+    // Signed packets point to the default certificate if there is one, otherwise to the key
+    fn key_locator(&self) -> Name<'_> {
+        match self.certificates.first() {
+            Some(certificate) => certificate.name(),
+            None => self.name.as_name(),
+        }
+    }
+}
+
+impl KeyChain {
+    pub fn new() -> Self {
+        Self {
+            identities: Vec::new(),
+        }
+    }
+
+    // The conventional key name /<identity>/KEY/<key id>
+    pub fn key_name(identity: Name<'_>, key_id: &[u8]) -> NameBuf {
+        NameBuf::from(identity)
+            .pushing(NameComponent::generic(b"KEY"))
+            .pushing(NameComponent::generic(key_id))
+    }
+
+    pub fn add_identity(&mut self, identity: Name<'_>) -> Result<(), KeyChainError> {
+        if self.find_identity(identity).is_some() {
+            return Err(KeyChainError::IdentityAlreadyExists);
+        }
+        self.identities.push(Identity {
+            name: NameBuf::from(identity),
+            keys: Vec::new(),
+        });
+        Ok(())
+    }
+
+    pub fn remove_identity(&mut self, identity: Name<'_>) -> bool {
+        match self.find_identity(identity) {
+            Some(idx) => {
+                self.identities.remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn add_key<K: KeyMaterial + 'static>(
+        &mut self,
+        identity: Name<'_>,
+        key_name: Name<'_>,
+        material: K,
+    ) -> Result<(), KeyChainError> {
+        self.add_boxed_key(identity, key_name, Box::new(material))
+    }
+
+    pub fn add_boxed_key(
+        &mut self,
+        identity: Name<'_>,
+        key_name: Name<'_>,
+        material: Box<dyn KeyMaterial>,
+    ) -> Result<(), KeyChainError> {
+        if !identity.is_prefix_of(key_name)
+            || identity.component_count() == key_name.component_count()
+        {
+            return Err(KeyChainError::KeyOutsideOfIdentity);
+        }
+        if self.find_key(key_name).is_some() {
+            return Err(KeyChainError::KeyAlreadyExists);
+        }
+        let idx = self
+            .find_identity(identity)
+            .ok_or(KeyChainError::IdentityNotFound)?;
+        self.identities[idx].keys.push(Key {
+            name: NameBuf::from(key_name),
+            material,
+            certificates: Vec::new(),
+        });
+        Ok(())
+    }
+
+    pub fn remove_key(&mut self, key_name: Name<'_>) -> bool {
+        match self.find_key(key_name) {
+            Some((identity_idx, key_idx)) => {
+                self.identities[identity_idx].keys.remove(key_idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Stores the encoded certificate packet for the key it is named under
+    pub fn add_certificate(&mut self, packet: &[u8]) -> Result<(), KeyChainError> {
+        let (data, data_len) =
+            Data::try_decode(packet).map_err(|_| KeyChainError::InvalidCertificate)?;
+        if data_len != packet.len() {
+            return Err(KeyChainError::InvalidCertificate);
+        }
+
+        let (identity_idx, key_idx) = self
+            .find_key_for_name(data.name)
+            .ok_or(KeyChainError::CertificateOutsideOfKey)?;
+        let key = &mut self.identities[identity_idx].keys[key_idx];
+        if key.name.component_count() == data.name.component_count() {
+            return Err(KeyChainError::CertificateOutsideOfKey);
+        }
+
+        let certificate = Certificate {
+            name: NameBuf::from(data.name),
+            packet: Vec::from(packet),
+        };
+        match key
+            .certificates
+            .iter()
+            .position(|c| c.name == certificate.name)
+        {
+            Some(idx) => key.certificates[idx] = certificate,
+            None => key.certificates.push(certificate),
+        }
+        Ok(())
+    }
+
+    pub fn remove_certificate(&mut self, certificate_name: Name<'_>) -> bool {
+        if let Some((identity_idx, key_idx)) = self.find_key_for_name(certificate_name) {
+            let key = &mut self.identities[identity_idx].keys[key_idx];
+            if let Some(idx) = key
+                .certificates
+                .iter()
+                .position(|c| c.name() == certificate_name)
+            {
+                key.certificates.remove(idx);
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn set_default_identity(&mut self, identity: Name<'_>) -> Result<(), KeyChainError> {
+        let idx = self
+            .find_identity(identity)
+            .ok_or(KeyChainError::IdentityNotFound)?;
+        self.identities[..=idx].rotate_right(1);
+        Ok(())
+    }
+
+    pub fn set_default_key(&mut self, key_name: Name<'_>) -> Result<(), KeyChainError> {
+        let (identity_idx, key_idx) = self.find_key(key_name).ok_or(KeyChainError::KeyNotFound)?;
+        self.identities[identity_idx].keys[..=key_idx].rotate_right(1);
+        Ok(())
+    }
+
+    pub fn set_default_certificate(
+        &mut self,
+        certificate_name: Name<'_>,
+    ) -> Result<(), KeyChainError> {
+        let (identity_idx, key_idx) = self
+            .find_key_for_name(certificate_name)
+            .ok_or(KeyChainError::CertificateNotFound)?;
+        let certificates = &mut self.identities[identity_idx].keys[key_idx].certificates;
+        let idx = certificates
+            .iter()
+            .position(|c| c.name() == certificate_name)
+            .ok_or(KeyChainError::CertificateNotFound)?;
+        certificates[..=idx].rotate_right(1);
+        Ok(())
+    }
+
+    pub fn default_identity(&self) -> Option<Name<'_>> {
+        self.identities.first().map(|i| i.name.as_name())
+    }
+
+    pub fn default_key(&self, identity: Name<'_>) -> Option<Name<'_>> {
+        let idx = self.find_identity(identity)?;
+        self.identities[idx].keys.first().map(|k| k.name.as_name())
+    }
+
+    pub fn identities(&self) -> impl Iterator<Item = Name<'_>> {
+        self.identities.iter().map(|i| i.name.as_name())
+    }
+
+    pub fn keys(&self, identity: Name<'_>) -> impl Iterator<Item = Name<'_>> {
+        let keys = match self.find_identity(identity) {
+            Some(idx) => self.identities[idx].keys.as_slice(),
+            None => &[],
+        };
+        keys.iter().map(|k| k.name.as_name())
+    }
+
+    pub fn certificates(&self, key_name: Name<'_>) -> impl Iterator<Item = &Certificate> {
+        let certificates = match self.find_key(key_name) {
+            Some((identity_idx, key_idx)) => self.identities[identity_idx].keys[key_idx]
+                .certificates
+                .as_slice(),
+            None => &[],
+        };
+        certificates.iter()
+    }
+
+    pub fn certificate(&self, certificate_name: Name<'_>) -> Option<&Certificate> {
+        let (identity_idx, key_idx) = self.find_key_for_name(certificate_name)?;
+        self.identities[identity_idx].keys[key_idx]
+            .certificates
+            .iter()
+            .find(|c| c.name() == certificate_name)
+    }
+
+    // The certificate a KeyLocator name refers to, which is either the named
+    //  certificate or the default certificate of the named key.
+    pub fn certificate_for_key_locator(&self, key_locator: Name<'_>) -> Option<&Certificate> {
+        let (identity_idx, key_idx) = self.find_key_for_name(key_locator)?;
+        let key = &self.identities[identity_idx].keys[key_idx];
+        if key.name.component_count() == key_locator.component_count() {
+            key.certificates.first()
+        } else {
+            key.certificates.iter().find(|c| c.name() == key_locator)
+        }
+    }
+
+    // The signer for the default key of the default identity
+    pub fn signer(&self) -> Option<Box<dyn Signer + '_>> {
+        let key = self.identities.first()?.keys.first()?;
+        Some(key.material.signer(key.key_locator()))
+    }
+
+    // The signer for the default key of the given identity
+    pub fn signer_for_identity(&self, identity: Name<'_>) -> Option<Box<dyn Signer + '_>> {
+        let idx = self.find_identity(identity)?;
+        let key = self.identities[idx].keys.first()?;
+        Some(key.material.signer(key.key_locator()))
+    }
+
+    pub fn signer_for_key(&self, key_name: Name<'_>) -> Option<Box<dyn Signer + '_>> {
+        let (identity_idx, key_idx) = self.find_key(key_name)?;
+        let key = &self.identities[identity_idx].keys[key_idx];
+        Some(key.material.signer(key.key_locator()))
+    }
+
+    // Finds the key a KeyLocator name refers to, which can be either
+    //  the name of the key itself or the name of one of its certificates.
+    pub fn verifier_for_key_locator(
+        &self,
+        key_locator: Name<'_>,
+    ) -> Option<Box<dyn Verifier + '_>> {
+        let (identity_idx, key_idx) = self.find_key_for_name(key_locator)?;
+        Some(
+            self.identities[identity_idx].keys[key_idx]
+                .material
+                .verifier(),
+        )
+    }
+
+    pub fn key_material(&self, key_name: Name<'_>) -> Option<&dyn KeyMaterial> {
+        let (identity_idx, key_idx) = self.find_key(key_name)?;
+        Some(
+            self.identities[identity_idx].keys[key_idx]
+                .material
+                .as_ref(),
+        )
+    }
+
+    fn find_identity(&self, identity: Name<'_>) -> Option<usize> {
+        self.identities
+            .iter()
+            .position(|i| i.name.as_name() == identity)
+    }
+
+    fn find_key(&self, key_name: Name<'_>) -> Option<(usize, usize)> {
+        for (identity_idx, identity) in self.identities.iter().enumerate() {
+            if let Some(key_idx) = identity
+                .keys
+                .iter()
+                .position(|k| k.name.as_name() == key_name)
+            {
+                return Some((identity_idx, key_idx));
+            }
+        }
+        None
+    }
+
+    // Finds the key whose name is the longest prefix of the given name
+    fn find_key_for_name(&self, name: Name<'_>) -> Option<(usize, usize)> {
+        let mut found: Option<(usize, usize, usize)> = None;
+        for (identity_idx, identity) in self.identities.iter().enumerate() {
+            for (key_idx, key) in identity.keys.iter().enumerate() {
+                let key_name = key.name.as_name();
+                if key_name.is_prefix_of(name) {
+                    let len = key_name.component_count();
+                    if found.map(|f| f.2 < len).unwrap_or(true) {
+                        found = Some((identity_idx, key_idx, len));
+                    }
+                }
+            }
+        }
+        found.map(|(identity_idx, key_idx, _)| (identity_idx, key_idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        io::Encode,
+        name::{Name, NameBuf, NameComponent},
+        packet::{Data, SignatureInfo, SignatureValue},
+        platform::sha::Sha256Hasher,
+        security::keychain::{HmacSha256Key, KeyChain, KeyChainError},
+    };
+
+    #[test]
+    fn test_keychain() {
+        let mut keychain = KeyChain::new();
+        assert!(keychain.signer().is_none());
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"alice")];
+        let alice = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"bob")];
+        let bob = root.adding_components(comp);
+
+        assert_eq!(keychain.add_identity(alice), Ok(()));
+        assert_eq!(
+            keychain.add_identity(alice),
+            Err(KeyChainError::IdentityAlreadyExists)
+        );
+        assert_eq!(keychain.add_identity(bob), Ok(()));
+        assert!(keychain.default_identity().unwrap() == alice);
+
+        let alice_key = KeyChain::key_name(alice, b"1");
+        let bob_key = KeyChain::key_name(bob, b"1");
+        let key = HmacSha256Key::new(b"alice secret", Sha256Hasher::new());
+        assert_eq!(
+            keychain.add_key(bob, alice_key.as_name(), key),
+            Err(KeyChainError::KeyOutsideOfIdentity)
+        );
+        let key = HmacSha256Key::new(b"alice secret", Sha256Hasher::new());
+        assert_eq!(keychain.add_key(alice, alice_key.as_name(), key), Ok(()));
+        let key = HmacSha256Key::new(b"bob secret", Sha256Hasher::new());
+        assert_eq!(keychain.add_key(bob, bob_key.as_name(), key), Ok(()));
+        assert!(keychain.default_key(alice).unwrap() == alice_key.as_name());
+
+        // Signing with the default identity
+        let comp = &[NameComponent::generic(b"hello")];
+        let name = alice.adding_components(comp);
+        let mut signer = keychain.signer().unwrap();
+        let key_locator = NameBuf::from(signer.key_locator().unwrap());
+        assert_eq!(key_locator, alice_key);
+        let signature_info =
+            SignatureInfo::new(signer.signature_type(), Some(key_locator.as_name()));
+        let mut data = Data::new_unsigned(name, b"content", signature_info);
+        let signature = data.sign_signed_portion(signer.as_mut());
+        data.signature_value = SignatureValue { bytes: &signature };
+
+        let mut verifier = keychain
+            .verifier_for_key_locator(alice_key.as_name())
+            .unwrap();
+        assert!(data.verify_signature(verifier.as_mut()));
+        drop(verifier);
+
+        // Bob's key cannot verify Alice's data
+        let mut verifier = keychain
+            .verifier_for_key_locator(bob_key.as_name())
+            .unwrap();
+        assert!(!data.verify_signature(verifier.as_mut()));
+        drop(verifier);
+        drop(signer);
+
+        // Changing the default identity changes the default signer
+        assert_eq!(keychain.set_default_identity(bob), Ok(()));
+        assert!(keychain.default_identity().unwrap() == bob);
+        let signer = keychain.signer().unwrap();
+        assert!(signer.key_locator().unwrap() == bob_key.as_name());
+        drop(signer);
+
+        assert!(keychain.remove_key(bob_key.as_name()));
+        assert!(keychain.signer().is_none());
+        assert!(keychain.signer_for_identity(alice).is_some());
+        assert!(keychain.remove_identity(bob));
+        assert_eq!(keychain.identities().count(), 1);
+    }
+
+    #[test]
+    fn test_certificates() {
+        let mut keychain = KeyChain::new();
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"alice")];
+        let alice = root.adding_components(comp);
+        let alice_key = KeyChain::key_name(alice, b"1");
+        let key_name = alice_key.as_name();
+        assert_eq!(keychain.add_identity(alice), Ok(()));
+        let key = HmacSha256Key::new(b"alice secret", Sha256Hasher::new());
+        assert_eq!(keychain.add_key(alice, key_name, key), Ok(()));
+
+        let comp = &[
+            NameComponent::generic(b"self"),
+            NameComponent::generic(b"v1"),
+        ];
+        let certificate_name = key_name.adding_components(comp);
+        let mut packet = Vec::new();
+        {
+            let mut signer = keychain.signer().unwrap();
+            let signature_info = SignatureInfo::new(signer.signature_type(), Some(key_name));
+            let mut certificate = Data::new_unsigned(certificate_name, b"key bits", signature_info);
+            let signature = certificate.sign_signed_portion(signer.as_mut());
+            certificate.signature_value = SignatureValue { bytes: &signature };
+            let _ = certificate.encode(&mut packet);
+        }
+
+        assert_eq!(
+            keychain.add_certificate(&packet[1..]),
+            Err(KeyChainError::InvalidCertificate)
+        );
+        assert_eq!(keychain.add_certificate(&packet), Ok(()));
+        assert_eq!(keychain.certificates(key_name).count(), 1);
+        let stored = keychain.certificate(certificate_name).unwrap();
+        assert_eq!(stored.packet(), packet.as_slice());
+        assert!(stored.data().name == certificate_name);
+
+        // Once there is a certificate it becomes the key locator
+        let signer = keychain.signer().unwrap();
+        assert!(signer.key_locator().unwrap() == certificate_name);
+        drop(signer);
+
+        // And the verifier can be found through it
+        assert!(keychain
+            .verifier_for_key_locator(certificate_name)
+            .is_some());
+
+        assert!(keychain.remove_certificate(certificate_name));
+        assert_eq!(keychain.certificates(key_name).count(), 0);
+    }
+}
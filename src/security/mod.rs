@@ -0,0 +1,111 @@
+use alloc::vec::Vec;
+
+use crate::{
+    hash::{Hasher, Sha256Digest},
+    name::Name,
+    packet::SignatureType,
+};
+
+#[cfg(feature = "ed25519")]
+pub mod ed25519;
+
+pub mod hmac;
+
+pub mod keychain;
+
+#[cfg(feature = "nac")]
+pub mod nac;
+
+#[cfg(feature = "safebag")]
+pub mod safebag;
+
+pub mod trust;
+
+// Produces signatures over the signed portion of a packet.
+// The signed portion is fed incrementally with update, similarly to a Hasher,
+//  so that packets do not need to be encoded into an intermediate buffer.
+pub trait Signer {
+    // One of the SignatureType constants
+    fn signature_type(&self) -> u64;
+
+    // The name to put into the KeyLocator of the packet, if any
+    fn key_locator(&self) -> Option<Name<'_>>;
+
+    fn reset(&mut self);
+    fn update(&mut self, input: &[u8]);
+    fn finalize_reset(&mut self) -> Vec<u8>;
+}
+
+// Checks signatures over the signed portion of a packet.
+pub trait Verifier {
+    // One of the SignatureType constants
+    fn signature_type(&self) -> u64;
+
+    fn reset(&mut self);
+    fn update(&mut self, input: &[u8]);
+    fn finalize_verify_reset(&mut self, signature: &[u8]) -> bool;
+}
+
+// The DigestSha256 "signature", which only protects against corruption.
+// It can be used both for signing and verification.
+pub struct DigestSha256<H: Hasher<Digest = Sha256Digest>> {
+    hasher: H,
+}
+
+impl<H: Hasher<Digest = Sha256Digest>> DigestSha256<H> {
+    pub fn new(mut hasher: H) -> Self {
+        hasher.reset();
+        Self { hasher }
+    }
+}
+
+impl<H: Hasher<Digest = Sha256Digest>> Signer for DigestSha256<H> {
+    fn signature_type(&self) -> u64 {
+        SignatureType::DIGEST_SHA256
+    }
+
+    fn key_locator(&self) -> Option<Name<'_>> {
+        None
+    }
+
+    fn reset(&mut self) {
+        self.hasher.reset()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.hasher.update(input)
+    }
+
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        Vec::from(self.hasher.finalize_reset().0)
+    }
+}
+
+impl<H: Hasher<Digest = Sha256Digest>> Verifier for DigestSha256<H> {
+    fn signature_type(&self) -> u64 {
+        SignatureType::DIGEST_SHA256
+    }
+
+    fn reset(&mut self) {
+        self.hasher.reset()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.hasher.update(input)
+    }
+
+    fn finalize_verify_reset(&mut self, signature: &[u8]) -> bool {
+        constant_time_eq(&self.hasher.finalize_reset().0, signature)
+    }
+}
+
+// Compares the two slices without short-circuiting on the first difference
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
@@ -0,0 +1,546 @@
+use alloc::vec::Vec;
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use rsa::{
+    pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey},
+    rand_core::{OsRng, RngCore},
+    Oaep, RsaPrivateKey, RsaPublicKey,
+};
+use sha2::Sha256;
+
+use crate::{
+    io::{Decode, Encode, Write},
+    name::{Name, NameBuf, NameComponent},
+    packet::{Data, DataBuilder},
+    security::Signer,
+    tlv::{TlvDecode, TlvEncode, TypedBytes, TLV},
+};
+
+// Name-based Access Control, see https://github.com/named-data/name-based-access-control
+// The content is encrypted with a content key (CK) using AES-CBC. The CK is published
+//  encrypted with the key-encryption key (KEK), the RSA public key of an access manager.
+// The matching private key, the key-decryption key (KDK), is published encrypted
+//  for each authorized member with their own RSA public key.
+// The keys are named:
+//  KEK: /<access prefix>/NAC/<dataset>/KEK/<key id>
+//  KDK: /<access prefix>/NAC/<dataset>/KDK/<key id>/ENCRYPTED-BY/<member key name>
+//  CK:  /<ck prefix>/CK/<key id>/ENCRYPTED-BY/<KEK name>
+
+pub const NAC_COMPONENT: &[u8] = b"NAC";
+pub const KEK_COMPONENT: &[u8] = b"KEK";
+pub const KDK_COMPONENT: &[u8] = b"KDK";
+pub const CK_COMPONENT: &[u8] = b"CK";
+pub const ENCRYPTED_BY_COMPONENT: &[u8] = b"ENCRYPTED-BY";
+
+pub const AES_KEY_SIZE: usize = 32;
+pub const AES_IV_SIZE: usize = 16;
+pub const RSA_KEY_BITS: usize = 2048;
+const KEY_ID_SIZE: usize = 8;
+
+type AesCbcEncryptor = cbc::Encryptor<aes::Aes256>;
+type AesCbcDecryptor = cbc::Decryptor<aes::Aes256>;
+
+// EncryptedContent = ENCRYPTED-CONTENT-TYPE TLV-LENGTH
+//                      EncryptedPayload
+//                      [InitializationVector]
+//                      [EncryptedPayloadKey]
+//                      [Name] ; the key needed to decrypt the payload
+pub struct EncryptedContent<'a> {
+    pub payload: EncryptedPayload<'a>,
+    pub iv: Option<InitializationVector<'a>>,
+    pub payload_key: Option<EncryptedPayloadKey<'a>>,
+    pub name: Option<Name<'a>>,
+}
+
+pub type EncryptedPayload<'a> = TypedBytes<'a, 132>;
+pub type InitializationVector<'a> = TypedBytes<'a, 133>;
+pub type EncryptedPayloadKey<'a> = TypedBytes<'a, 134>;
+
+impl<'a> TlvEncode for EncryptedContent<'a> {
+    const TLV_TYPE: u32 = 130;
+
+    fn inner_length(&self) -> usize {
+        self.payload.encoded_length()
+            + self.iv.encoded_length()
+            + self.payload_key.encoded_length()
+            + self.name.encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.payload.encode(writer)?;
+        self.iv.encode(writer)?;
+        self.payload_key.encode(writer)?;
+        self.name.encode(writer)
+    }
+}
+
+impl<'a> TlvDecode<'a> for EncryptedContent<'a> {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let (payload_tlv, mut offset) = TLV::try_decode(inner_bytes).ok()?;
+        if payload_tlv.typ.get() != EncryptedPayload::TLV_TYPE {
+            return None;
+        }
+        let mut content = Self {
+            payload: EncryptedPayload {
+                bytes: payload_tlv.val,
+            },
+            iv: None,
+            payload_key: None,
+            name: None,
+        };
+
+        // The optional elements must come in order
+        let mut next = 0;
+        while offset < inner_bytes.len() {
+            let (tlv, tlv_len) = TLV::try_decode(&inner_bytes[offset..]).ok()?;
+            let idx = match tlv.typ.get() {
+                InitializationVector::TLV_TYPE if next <= 0 => {
+                    content.iv = Some(InitializationVector { bytes: tlv.val });
+                    0
+                }
+                EncryptedPayloadKey::TLV_TYPE if next <= 1 => {
+                    content.payload_key = Some(EncryptedPayloadKey { bytes: tlv.val });
+                    1
+                }
+                Name::TLV_TYPE if next <= 2 => {
+                    content.name = Some(Name::try_decode_from_inner(tlv.val)?);
+                    2
+                }
+                _ => return None,
+            };
+            next = idx + 1;
+            offset += tlv_len;
+        }
+        Some(content)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum NacError {
+    Malformed,
+    KeyGenerationFailed,
+    EncryptionFailed,
+    // The key is wrong or the content was tampered with
+    DecryptionFailed,
+    // The CK named by the encrypted content has not been added yet
+    CkNotFound,
+    // The KDK needed to decrypt the CK has not been added yet
+    KdkNotFound,
+}
+
+fn component(bytes: &[u8]) -> NameComponent<'_> {
+    NameComponent::generic(bytes)
+}
+
+fn random_key_id() -> [u8; KEY_ID_SIZE] {
+    let mut key_id = [0u8; KEY_ID_SIZE];
+    OsRng.fill_bytes(&mut key_id);
+    key_id
+}
+
+fn aes_encrypt(key: &[u8; AES_KEY_SIZE], plaintext: &[u8]) -> ([u8; AES_IV_SIZE], Vec<u8>) {
+    let mut iv = [0u8; AES_IV_SIZE];
+    OsRng.fill_bytes(&mut iv);
+    let ciphertext =
+        AesCbcEncryptor::new(key.into(), (&iv).into()).encrypt_padded_vec_mut::<Pkcs7>(plaintext);
+    (iv, ciphertext)
+}
+
+fn aes_decrypt(key: &[u8], iv: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, NacError> {
+    AesCbcDecryptor::new_from_slices(key, iv)
+        .map_err(|_| NacError::Malformed)?
+        .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+        .map_err(|_| NacError::DecryptionFailed)
+}
+
+fn rsa_encrypt(key: &RsaPublicKey, plaintext: &[u8]) -> Result<Vec<u8>, NacError> {
+    key.encrypt(&mut OsRng, Oaep::new::<Sha256>(), plaintext)
+        .map_err(|_| NacError::EncryptionFailed)
+}
+
+fn rsa_decrypt(key: &RsaPrivateKey, ciphertext: &[u8]) -> Result<Vec<u8>, NacError> {
+    key.decrypt(Oaep::new::<Sha256>(), ciphertext)
+        .map_err(|_| NacError::DecryptionFailed)
+}
+
+fn encode_content(content: &EncryptedContent<'_>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(content.encoded_length());
+    let _ = content.encode(&mut bytes);
+    bytes
+}
+
+fn decode_content<'a>(bytes: &'a [u8]) -> Result<EncryptedContent<'a>, NacError> {
+    let (content, len) = EncryptedContent::try_decode(bytes).map_err(|_| NacError::Malformed)?;
+    if len != bytes.len() {
+        return Err(NacError::Malformed);
+    }
+    Ok(content)
+}
+
+fn data_content<'a>(data: &Data<'a>) -> Result<EncryptedContent<'a>, NacError> {
+    decode_content(data.content.as_ref().ok_or(NacError::Malformed)?.bytes)
+}
+
+// Splits <key name>/ENCRYPTED-BY/<encrypting key name>
+fn split_encrypted_by(name: Name<'_>) -> Option<(Name<'_>, Name<'_>)> {
+    let index = name
+        .components()
+        .position(|c| c == component(ENCRYPTED_BY_COMPONENT))?;
+    let key_name = name.prefix(index);
+    let encrypting_key_name = name.suffix(name.component_count() - index - 1)?;
+    Some((key_name, encrypting_key_name))
+}
+
+// Replaces KEK with KDK in the KEK name and appends ENCRYPTED-BY/<member key name>
+fn kdk_name(kek_name: Name<'_>, member_key_name: Name<'_>) -> Option<NameBuf> {
+    let count = kek_name.component_count();
+    if count < 2 || kek_name.component(count - 2)? != component(KEK_COMPONENT) {
+        return None;
+    }
+    let mut name = NameBuf::from(kek_name.prefix(count - 2))
+        .pushing(component(KDK_COMPONENT))
+        .pushing(kek_name.component(count - 1)?)
+        .pushing(component(ENCRYPTED_BY_COMPONENT));
+    for c in member_key_name.components() {
+        name.push(c);
+    }
+    Some(name)
+}
+
+// The inverse of kdk_name, checking that the KDK is encrypted for the member
+fn kek_name_of_kdk(kdk_name: Name<'_>, member_key_name: Name<'_>) -> Option<NameBuf> {
+    let (key_name, encrypting_key_name) = split_encrypted_by(kdk_name)?;
+    let count = key_name.component_count();
+    if encrypting_key_name != member_key_name
+        || count < 2
+        || key_name.component(count - 2)? != component(KDK_COMPONENT)
+    {
+        return None;
+    }
+    Some(
+        NameBuf::from(key_name.prefix(count - 2))
+            .pushing(component(KEK_COMPONENT))
+            .pushing(key_name.component(count - 1)?),
+    )
+}
+
+// Generates the KEK/KDK pair of a dataset and grants access to it to the members.
+pub struct AccessManager {
+    kek_name: NameBuf,
+    kdk: RsaPrivateKey,
+}
+
+impl AccessManager {
+    pub fn new(access_prefix: Name<'_>, dataset: &[u8]) -> Result<Self, NacError> {
+        let kdk = RsaPrivateKey::new(&mut OsRng, RSA_KEY_BITS)
+            .map_err(|_| NacError::KeyGenerationFailed)?;
+        Ok(Self::with_key(
+            access_prefix,
+            dataset,
+            &random_key_id(),
+            kdk,
+        ))
+    }
+
+    pub fn with_key(
+        access_prefix: Name<'_>,
+        dataset: &[u8],
+        key_id: &[u8],
+        kdk: RsaPrivateKey,
+    ) -> Self {
+        let kek_name = NameBuf::from(access_prefix)
+            .pushing(component(NAC_COMPONENT))
+            .pushing(component(dataset))
+            .pushing(component(KEK_COMPONENT))
+            .pushing(component(key_id));
+        Self { kek_name, kdk }
+    }
+
+    pub fn kek_name(&self) -> Name<'_> {
+        self.kek_name.as_name()
+    }
+
+    // The KEK packet, with the public key as SubjectPublicKeyInfo
+    pub fn kek_data<S: Signer + ?Sized>(&self, signer: &mut S) -> Result<Vec<u8>, NacError> {
+        let public_key = self
+            .kdk
+            .to_public_key()
+            .to_public_key_der()
+            .map_err(|_| NacError::EncryptionFailed)?;
+        Ok(DataBuilder::new(self.kek_name())
+            .content(public_key.as_bytes())
+            .sign(signer))
+    }
+
+    // The KDK packet for a member, given their SubjectPublicKeyInfo.
+    // Unlike ndn-cxx, which wraps the KDK into a password-protected SafeBag,
+    //  the PKCS#8 KDK is encrypted with a random AES key and that key with RSA.
+    pub fn kdk_data<S: Signer + ?Sized>(
+        &self,
+        member_key_name: Name<'_>,
+        member_public_key: &[u8],
+        signer: &mut S,
+    ) -> Result<Vec<u8>, NacError> {
+        let member_key = RsaPublicKey::from_public_key_der(member_public_key)
+            .map_err(|_| NacError::Malformed)?;
+        let name = kdk_name(self.kek_name(), member_key_name).ok_or(NacError::Malformed)?;
+
+        let der = self
+            .kdk
+            .to_pkcs8_der()
+            .map_err(|_| NacError::EncryptionFailed)?;
+        let mut key = [0u8; AES_KEY_SIZE];
+        OsRng.fill_bytes(&mut key);
+        let (iv, payload) = aes_encrypt(&key, der.as_bytes());
+        let payload_key = rsa_encrypt(&member_key, &key)?;
+
+        let content = encode_content(&EncryptedContent {
+            payload: EncryptedPayload { bytes: &payload },
+            iv: Some(InitializationVector { bytes: &iv }),
+            payload_key: Some(EncryptedPayloadKey {
+                bytes: &payload_key,
+            }),
+            name: None,
+        });
+        Ok(DataBuilder::new(name.as_name())
+            .content(&content)
+            .sign(signer))
+    }
+}
+
+// Producer side: encrypts content with a CK, which is published encrypted with the KEK.
+pub struct Encryptor {
+    ck_prefix: NameBuf,
+    ck_name: NameBuf,
+    ck: [u8; AES_KEY_SIZE],
+    kek_name: NameBuf,
+    kek: RsaPublicKey,
+}
+
+impl Encryptor {
+    // Takes the KEK packet fetched from the access manager
+    pub fn new(ck_prefix: Name<'_>, kek: &Data<'_>) -> Result<Self, NacError> {
+        let count = kek.name.component_count();
+        if count < 2 || kek.name.component(count - 2) != Some(component(KEK_COMPONENT)) {
+            return Err(NacError::Malformed);
+        }
+        let public_key = kek.content.as_ref().ok_or(NacError::Malformed)?.bytes;
+        let kek_key =
+            RsaPublicKey::from_public_key_der(public_key).map_err(|_| NacError::Malformed)?;
+
+        let mut encryptor = Self {
+            ck_prefix: NameBuf::from(ck_prefix),
+            ck_name: NameBuf::new(),
+            ck: [0; AES_KEY_SIZE],
+            kek_name: NameBuf::from(kek.name),
+            kek: kek_key,
+        };
+        encryptor.regenerate_ck();
+        Ok(encryptor)
+    }
+
+    // Starts using a new CK, which must then be published
+    pub fn regenerate_ck(&mut self) {
+        OsRng.fill_bytes(&mut self.ck);
+        self.ck_name = NameBuf::from(self.ck_prefix.as_name())
+            .pushing(component(CK_COMPONENT))
+            .pushing(component(&random_key_id()));
+    }
+
+    pub fn ck_name(&self) -> Name<'_> {
+        self.ck_name.as_name()
+    }
+
+    // The EncryptedContent to put into the Data
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let (iv, payload) = aes_encrypt(&self.ck, plaintext);
+        encode_content(&EncryptedContent {
+            payload: EncryptedPayload { bytes: &payload },
+            iv: Some(InitializationVector { bytes: &iv }),
+            payload_key: None,
+            name: Some(self.ck_name()),
+        })
+    }
+
+    // The CK packet, encrypted with the KEK
+    pub fn ck_data<S: Signer + ?Sized>(&self, signer: &mut S) -> Result<Vec<u8>, NacError> {
+        let mut name = NameBuf::from(self.ck_name()).pushing(component(ENCRYPTED_BY_COMPONENT));
+        for c in self.kek_name.as_name().components() {
+            name.push(c);
+        }
+        let payload = rsa_encrypt(&self.kek, &self.ck)?;
+        let content = encode_content(&EncryptedContent {
+            payload: EncryptedPayload { bytes: &payload },
+            iv: None,
+            payload_key: None,
+            name: None,
+        });
+        Ok(DataBuilder::new(name.as_name())
+            .content(&content)
+            .sign(signer))
+    }
+}
+
+// Consumer side: decrypts content once the CK and the KDK it is encrypted with
+//  have been fetched. Decrypting fails with CkNotFound or KdkNotFound until then,
+//  and ck_name and kdk_name tell what to fetch next.
+pub struct Decryptor {
+    member_key_name: NameBuf,
+    member_key: RsaPrivateKey,
+    // By KEK name
+    kdks: Vec<(NameBuf, RsaPrivateKey)>,
+    cks: Vec<(NameBuf, Vec<u8>)>,
+}
+
+impl Decryptor {
+    pub fn new(member_key_name: Name<'_>, member_key: RsaPrivateKey) -> Self {
+        Self {
+            member_key_name: NameBuf::from(member_key_name),
+            member_key,
+            kdks: Vec::new(),
+            cks: Vec::new(),
+        }
+    }
+
+    // Reads the member key from an unencrypted PKCS#8 PrivateKeyInfo
+    pub fn from_pkcs8_der(member_key_name: Name<'_>, der: &[u8]) -> Result<Self, NacError> {
+        let member_key = RsaPrivateKey::from_pkcs8_der(der).map_err(|_| NacError::Malformed)?;
+        Ok(Self::new(member_key_name, member_key))
+    }
+
+    // The name of the CK needed for the encrypted content, to be fetched
+    //  with CanBePrefix since the full name also has the KEK
+    pub fn ck_name(content: &[u8]) -> Option<Name<'_>> {
+        decode_content(content).ok()?.name
+    }
+
+    // The name of the KDK needed for the CK packet
+    pub fn kdk_name(&self, ck: &Data<'_>) -> Option<NameBuf> {
+        let (_, kek_name) = split_encrypted_by(ck.name)?;
+        kdk_name(kek_name, self.member_key_name.as_name())
+    }
+
+    pub fn decrypt(&self, content: &[u8]) -> Result<Vec<u8>, NacError> {
+        let content = decode_content(content)?;
+        let ck_name = content.name.ok_or(NacError::Malformed)?;
+        let iv = content.iv.ok_or(NacError::Malformed)?;
+        let (_, ck) = self
+            .cks
+            .iter()
+            .find(|(name, _)| name.as_name() == ck_name)
+            .ok_or(NacError::CkNotFound)?;
+        aes_decrypt(ck, iv.bytes, content.payload.bytes)
+    }
+
+    pub fn add_ck(&mut self, ck: &Data<'_>) -> Result<(), NacError> {
+        let (ck_name, kek_name) = split_encrypted_by(ck.name).ok_or(NacError::Malformed)?;
+        let (_, kdk) = self
+            .kdks
+            .iter()
+            .find(|(name, _)| name.as_name() == kek_name)
+            .ok_or(NacError::KdkNotFound)?;
+        let content = data_content(ck)?;
+        let key = rsa_decrypt(kdk, content.payload.bytes)?;
+        if !self.cks.iter().any(|(name, _)| name.as_name() == ck_name) {
+            self.cks.push((NameBuf::from(ck_name), key));
+        }
+        Ok(())
+    }
+
+    pub fn add_kdk(&mut self, kdk: &Data<'_>) -> Result<(), NacError> {
+        let kek_name =
+            kek_name_of_kdk(kdk.name, self.member_key_name.as_name()).ok_or(NacError::Malformed)?;
+        let content = data_content(kdk)?;
+        let iv = content.iv.ok_or(NacError::Malformed)?;
+        let payload_key = content.payload_key.ok_or(NacError::Malformed)?;
+        let key = rsa_decrypt(&self.member_key, payload_key.bytes)?;
+        let der = aes_decrypt(&key, iv.bytes, content.payload.bytes)?;
+        let kdk_key = RsaPrivateKey::from_pkcs8_der(&der).map_err(|_| NacError::Malformed)?;
+        if !self
+            .kdks
+            .iter()
+            .any(|(name, _)| name.as_name() == kek_name.as_name())
+        {
+            self.kdks.push((kek_name, kdk_key));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::{pkcs8::EncodePublicKey, rand_core::OsRng, RsaPrivateKey};
+
+    use crate::{
+        io::Decode,
+        name::{Name, NameComponent},
+        packet::Data,
+        platform::sha::Sha256Hasher,
+        security::{
+            nac::{AccessManager, Decryptor, Encryptor, NacError},
+            DigestSha256,
+        },
+    };
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        // Small keys to keep the test fast
+        let kdk = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let member_key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let member_public_key = member_key.to_public_key().to_public_key_der().unwrap();
+
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"lab")];
+        let access_prefix = root.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"lab"),
+            NameComponent::generic(b"data"),
+        ];
+        let ck_prefix = root.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"alice"),
+            NameComponent::generic(b"KEY"),
+            NameComponent::generic(b"1"),
+        ];
+        let alice_key = root.adding_components(comp);
+        let mut signer = DigestSha256::new(Sha256Hasher::new());
+
+        let manager = AccessManager::with_key(access_prefix, b"sensors", b"k1", kdk);
+        let kek = manager.kek_data(&mut signer).unwrap();
+        let kdk = manager
+            .kdk_data(alice_key, member_public_key.as_bytes(), &mut signer)
+            .unwrap();
+
+        let (kek, _) = Data::try_decode(&kek).unwrap();
+        assert!(kek.name == manager.kek_name());
+        let encryptor = Encryptor::new(ck_prefix, &kek).unwrap();
+        let content = encryptor.encrypt(b"secret reading");
+        let ck = encryptor.ck_data(&mut signer).unwrap();
+        assert!(ck_prefix.is_prefix_of(encryptor.ck_name()));
+
+        // The consumer needs the CK, which needs the KDK
+        let mut decryptor = Decryptor::new(alice_key, member_key);
+        assert_eq!(decryptor.decrypt(&content), Err(NacError::CkNotFound));
+        let ck_name = Decryptor::ck_name(&content).unwrap();
+        let (ck, _) = Data::try_decode(&ck).unwrap();
+        assert!(ck_name.is_prefix_of(ck.name));
+        assert_eq!(decryptor.add_ck(&ck), Err(NacError::KdkNotFound));
+
+        let (kdk, _) = Data::try_decode(&kdk).unwrap();
+        assert!(decryptor.kdk_name(&ck).unwrap().as_name() == kdk.name);
+        assert_eq!(decryptor.add_kdk(&kdk), Ok(()));
+        assert_eq!(decryptor.add_ck(&ck), Ok(()));
+        assert_eq!(decryptor.decrypt(&content).unwrap(), b"secret reading");
+
+        // Someone else cannot use the KDK
+        let comp = &[
+            NameComponent::generic(b"bob"),
+            NameComponent::generic(b"KEY"),
+            NameComponent::generic(b"1"),
+        ];
+        let bob_key = root.adding_components(comp);
+        let bob = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let mut decryptor = Decryptor::new(bob_key, bob);
+        assert_eq!(decryptor.add_kdk(&kdk), Err(NacError::Malformed));
+    }
+}
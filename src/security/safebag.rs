@@ -0,0 +1,273 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use pkcs8::{
+    pkcs5::pbes2, rand_core::OsRng, rand_core::RngCore, EncryptedPrivateKeyInfo, PrivateKeyInfo,
+};
+
+use crate::{
+    io::{Decode, Encode, Write},
+    name::Name,
+    packet::Data,
+    security::{
+        ed25519::Ed25519Key,
+        keychain::{KeyChain, KeyChainError, KeyMaterial},
+    },
+    tlv::{TlvDecode, TlvEncode, TypedBytes, TLV},
+};
+
+// The format used by ndn-cxx to move keys between machines:
+//  SafeBag = SAFE-BAG-TYPE TLV-LENGTH
+//              Data ; the certificate
+//              EncryptedKey ; the private key as a PKCS#8 EncryptedPrivateKeyInfo
+pub struct SafeBag<'a> {
+    // The whole encoded certificate, including the outer Data TLV
+    pub certificate: &'a [u8],
+    pub encrypted_key: EncryptedKey<'a>,
+}
+
+pub type EncryptedKey<'a> = TypedBytes<'a, 129>;
+
+impl<'a> TlvEncode for SafeBag<'a> {
+    const TLV_TYPE: u32 = 128;
+
+    fn inner_length(&self) -> usize {
+        self.certificate.len() + self.encrypted_key.encoded_length()
+    }
+
+    fn encode_inner<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), W::Error> {
+        writer.write(self.certificate)?;
+        self.encrypted_key.encode(writer)
+    }
+}
+
+impl<'a> TlvDecode<'a> for SafeBag<'a> {
+    fn try_decode_from_inner(inner_bytes: &'a [u8]) -> Option<Self> {
+        let (certificate_tlv, certificate_len) = TLV::try_decode(inner_bytes).ok()?;
+        if certificate_tlv.typ.get() != Data::TLV_TYPE {
+            return None;
+        }
+        Data::try_decode_from_inner(certificate_tlv.val)?;
+
+        let (encrypted_key_tlv, encrypted_key_len) =
+            TLV::try_decode(&inner_bytes[certificate_len..]).ok()?;
+        if encrypted_key_tlv.typ.get() != EncryptedKey::TLV_TYPE
+            || certificate_len + encrypted_key_len != inner_bytes.len()
+        {
+            return None;
+        }
+
+        Some(Self {
+            certificate: &inner_bytes[..certificate_len],
+            encrypted_key: EncryptedKey {
+                bytes: encrypted_key_tlv.val,
+            },
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SafeBagError {
+    Malformed,
+    // The password is wrong or the encryption scheme is not supported
+    DecryptionFailed,
+    UnsupportedKeyType,
+    // The key cannot be exported, either because it is not in the KeyChain
+    //  or because its material does not support PKCS#8
+    KeyNotExportable,
+    EncryptionFailed,
+    KeyChain(KeyChainError),
+}
+
+// Matches the default of OpenSSL, which ndn-cxx relies on
+const PBKDF2_ITERATIONS: u32 = 2048;
+
+// The certificate name is /<identity>/KEY/<key id>/<issuer>/<version>
+fn key_and_identity_names(certificate_name: Name<'_>) -> Option<(Name<'_>, Name<'_>)> {
+    let key_name = certificate_name
+        .dropping_last_component()?
+        .dropping_last_component()?;
+    let identity = key_name
+        .dropping_last_component()?
+        .dropping_last_component()?;
+    Some((key_name, identity))
+}
+
+fn key_material_from_pkcs8(der: &[u8]) -> Result<Box<dyn KeyMaterial>, SafeBagError> {
+    let info = PrivateKeyInfo::try_from(der).map_err(|_| SafeBagError::Malformed)?;
+    if info.algorithm.oid == ed25519_dalek::pkcs8::ALGORITHM_OID {
+        let key = Ed25519Key::from_pkcs8_der(der).ok_or(SafeBagError::Malformed)?;
+        return Ok(Box::new(key));
+    }
+    Err(SafeBagError::UnsupportedKeyType)
+}
+
+impl KeyChain {
+    // Decrypts the key from the SafeBag and adds it with its certificate,
+    //  creating the identity if needed. A failed import leaves the KeyChain unchanged.
+    pub fn import_safe_bag(
+        &mut self,
+        safe_bag: &[u8],
+        password: &[u8],
+    ) -> Result<(), SafeBagError> {
+        let (safe_bag, _) = SafeBag::try_decode(safe_bag).map_err(|_| SafeBagError::Malformed)?;
+        let (certificate, _) =
+            Data::try_decode(safe_bag.certificate).map_err(|_| SafeBagError::Malformed)?;
+        let (key_name, identity) =
+            key_and_identity_names(certificate.name).ok_or(SafeBagError::Malformed)?;
+
+        let encrypted = EncryptedPrivateKeyInfo::try_from(safe_bag.encrypted_key.bytes)
+            .map_err(|_| SafeBagError::Malformed)?;
+        let der = encrypted
+            .decrypt(password)
+            .map_err(|_| SafeBagError::DecryptionFailed)?;
+        let material = key_material_from_pkcs8(der.as_bytes())?;
+        // The key may be under another identity, which would be found too late
+        if self.key_material(key_name).is_some() {
+            return Err(SafeBagError::KeyChain(KeyChainError::KeyAlreadyExists));
+        }
+
+        let created_identity = match self.add_identity(identity) {
+            Ok(()) => true,
+            Err(KeyChainError::IdentityAlreadyExists) => false,
+            Err(err) => return Err(SafeBagError::KeyChain(err)),
+        };
+        let added = self
+            .add_boxed_key(identity, key_name, material)
+            .and_then(|()| self.add_certificate(safe_bag.certificate));
+        if let Err(err) = added {
+            self.remove_key(key_name);
+            if created_identity {
+                self.remove_identity(identity);
+            }
+            return Err(SafeBagError::KeyChain(err));
+        }
+        Ok(())
+    }
+
+    // Produces a SafeBag with the certificate and its key encrypted with the password
+    pub fn export_safe_bag(
+        &self,
+        certificate_name: Name<'_>,
+        password: &[u8],
+    ) -> Result<Vec<u8>, SafeBagError> {
+        let certificate = self
+            .certificate(certificate_name)
+            .ok_or(SafeBagError::KeyChain(KeyChainError::CertificateNotFound))?;
+        let (key_name, _) =
+            key_and_identity_names(certificate_name).ok_or(SafeBagError::Malformed)?;
+        let der = self
+            .key_material(key_name)
+            .and_then(|material| material.to_pkcs8_der())
+            .ok_or(SafeBagError::KeyNotExportable)?;
+        let info = PrivateKeyInfo::try_from(der.as_slice()).map_err(|_| SafeBagError::Malformed)?;
+
+        let mut salt = [0u8; 16];
+        let mut iv = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut iv);
+        let params = pbes2::Parameters::pbkdf2_sha256_aes256cbc(PBKDF2_ITERATIONS, &salt, &iv)
+            .map_err(|_| SafeBagError::EncryptionFailed)?;
+        let encrypted = info
+            .encrypt_with_params(params, password)
+            .map_err(|_| SafeBagError::EncryptionFailed)?;
+
+        let safe_bag = SafeBag {
+            certificate: certificate.packet(),
+            encrypted_key: EncryptedKey {
+                bytes: encrypted.as_bytes(),
+            },
+        };
+        let mut packet = Vec::new();
+        let _ = safe_bag.encode(&mut packet);
+        Ok(packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        io::{Decode, Encode},
+        name::{Name, NameComponent},
+        packet::{Data, SignatureInfo, SignatureValue},
+        security::{
+            ed25519::Ed25519Key,
+            keychain::{KeyChain, KeyChainError},
+            safebag::{SafeBag, SafeBagError},
+        },
+    };
+
+    #[test]
+    fn test_export_import() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"alice")];
+        let alice = root.adding_components(comp);
+        let alice_key = KeyChain::key_name(alice, b"1");
+        let key_name = alice_key.as_name();
+
+        let mut keychain = KeyChain::new();
+        keychain.add_identity(alice).unwrap();
+        let key = Ed25519Key::from_secret_bytes(&[3; 32]);
+        let public_key = key.public_key_bytes();
+        keychain.add_key(alice, key_name, key).unwrap();
+
+        let comp = &[
+            NameComponent::generic(b"self"),
+            NameComponent::generic(b"v1"),
+        ];
+        let certificate_name = key_name.adding_components(comp);
+        let mut certificate = Vec::new();
+        {
+            let mut signer = keychain.signer().unwrap();
+            let signature_info = SignatureInfo::new(signer.signature_type(), Some(key_name));
+            let mut data = Data::new_unsigned(certificate_name, &public_key, signature_info);
+            let signature = data.sign_signed_portion(signer.as_mut());
+            data.signature_value = SignatureValue { bytes: &signature };
+            let _ = data.encode(&mut certificate);
+        }
+        keychain.add_certificate(&certificate).unwrap();
+
+        let exported = keychain
+            .export_safe_bag(certificate_name, b"password")
+            .unwrap();
+        let (safe_bag, _) = SafeBag::try_decode(&exported).unwrap();
+        assert_eq!(safe_bag.certificate, certificate.as_slice());
+
+        let mut imported = KeyChain::new();
+        assert_eq!(
+            imported.import_safe_bag(&exported, b"wrong"),
+            Err(SafeBagError::DecryptionFailed)
+        );
+        assert_eq!(imported.import_safe_bag(&exported, b"password"), Ok(()));
+        assert!(imported.default_identity().unwrap() == alice);
+        assert_eq!(
+            imported.import_safe_bag(&exported, b"password"),
+            Err(SafeBagError::KeyChain(KeyChainError::KeyAlreadyExists))
+        );
+
+        // The failed import does not leave the identity behind
+        let mut elsewhere = KeyChain::new();
+        elsewhere.add_identity(root).unwrap();
+        let key = Ed25519Key::from_secret_bytes(&[3; 32]);
+        elsewhere.add_key(root, key_name, key).unwrap();
+        assert_eq!(
+            elsewhere.import_safe_bag(&exported, b"password"),
+            Err(SafeBagError::KeyChain(KeyChainError::KeyAlreadyExists))
+        );
+        assert_eq!(elsewhere.identities().count(), 1);
+        assert!(elsewhere.certificate(certificate_name).is_none());
+
+        // The imported key produces the same signatures
+        let comp = &[NameComponent::generic(b"hello")];
+        let name = alice.adding_components(comp);
+        let mut signer = imported.signer().unwrap();
+        assert!(signer.key_locator().unwrap() == certificate_name);
+        let signature_info = SignatureInfo::new(signer.signature_type(), Some(certificate_name));
+        let mut data = Data::new_unsigned(name, b"content", signature_info);
+        let signature = data.sign_signed_portion(signer.as_mut());
+        data.signature_value = SignatureValue { bytes: &signature };
+        let mut verifier = keychain.verifier_for_key_locator(certificate_name).unwrap();
+        assert!(data.verify_signature(verifier.as_mut()));
+    }
+}
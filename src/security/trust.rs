@@ -0,0 +1,430 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::num::NonZeroU16;
+
+use crate::{
+    name::{Name, NameBuf, NameComponent},
+    packet::{Data, KeyLocator},
+    security::{
+        keychain::{Certificate, KeyChain},
+        Verifier,
+    },
+};
+
+// One element of a NamePattern.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PatternComponent {
+    // Matches exactly this component
+    Literal { typ: NonZeroU16, bytes: Vec<u8> },
+    // Matches any single component
+    Any,
+    // Matches any single component. The first occurrence of an index binds the component,
+    //  every later occurrence (also in the signer pattern of the same rule) must be equal to it.
+    Capture(usize),
+    // Matches zero or more remaining components, only allowed as the last element
+    Rest,
+}
+
+// A pattern over names, e.g. /<site>/blog/<author>/... is built as
+//  NamePattern::new().capture(0).literal(blog).capture(1).rest()
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct NamePattern {
+    components: Vec<PatternComponent>,
+}
+
+impl NamePattern {
+    pub fn new() -> Self {
+        Self {
+            components: Vec::new(),
+        }
+    }
+
+    // Matches the prefix and everything under it
+    pub fn from_prefix(prefix: Name<'_>) -> Self {
+        let mut pattern = Self::new();
+        for component in prefix.components() {
+            pattern = pattern.literal(component);
+        }
+        pattern.rest()
+    }
+
+    pub fn literal(mut self, component: NameComponent<'_>) -> Self {
+        self.components.push(PatternComponent::Literal {
+            typ: component.typ,
+            bytes: Vec::from(component.bytes),
+        });
+        self
+    }
+
+    pub fn any(mut self) -> Self {
+        self.components.push(PatternComponent::Any);
+        self
+    }
+
+    pub fn capture(mut self, index: usize) -> Self {
+        self.components.push(PatternComponent::Capture(index));
+        self
+    }
+
+    pub fn rest(mut self) -> Self {
+        debug_assert!(self.components.last() != Some(&PatternComponent::Rest));
+        self.components.push(PatternComponent::Rest);
+        self
+    }
+
+    pub fn matches(&self, name: Name<'_>) -> bool {
+        self.match_with(name, &mut Vec::new())
+    }
+
+    fn match_with<'a>(
+        &self,
+        name: Name<'a>,
+        captures: &mut Vec<Option<NameComponent<'a>>>,
+    ) -> bool {
+        let mut components = name.components();
+        for pattern in self.components.iter() {
+            let component = match pattern {
+                PatternComponent::Rest => return true,
+                _ => match components.next() {
+                    Some(component) => component,
+                    None => return false,
+                },
+            };
+            match pattern {
+                PatternComponent::Literal { typ, bytes } => {
+                    if component.typ != *typ || component.bytes != bytes.as_slice() {
+                        return false;
+                    }
+                }
+                PatternComponent::Any => {}
+                PatternComponent::Capture(index) => {
+                    if captures.len() <= *index {
+                        captures.resize(index + 1, None);
+                    }
+                    match captures[*index] {
+                        Some(captured) => {
+                            if captured != component {
+                                return false;
+                            }
+                        }
+                        None => captures[*index] = Some(component),
+                    }
+                }
+                PatternComponent::Rest => unreachable!(),
+            }
+        }
+        components.next().is_none()
+    }
+}
+
+// Packets named according to "packet" may be signed by keys named according to "signer".
+// The signer is matched against the KeyLocator name, which may be the name of the
+//  key or of one of its certificates, so signer patterns usually end with rest().
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TrustRule {
+    pub packet: NamePattern,
+    pub signer: NamePattern,
+}
+
+impl TrustRule {
+    pub fn new(packet: NamePattern, signer: NamePattern) -> Self {
+        Self { packet, signer }
+    }
+
+    pub fn allows(&self, packet_name: Name<'_>, signer_name: Name<'_>) -> bool {
+        let mut captures = Vec::new();
+        self.packet.match_with(packet_name, &mut captures)
+            && self.signer.match_with(signer_name, &mut captures)
+    }
+}
+
+// Where the validator looks up the keys and certificates referred to by KeyLocators.
+pub trait CertificateStore {
+    fn verifier_for_key_locator(&self, key_locator: Name<'_>) -> Option<Box<dyn Verifier + '_>>;
+
+    // The certificate a KeyLocator refers to, which is either the named certificate
+    //  or the default certificate of the named key.
+    fn certificate_for_key_locator(&self, key_locator: Name<'_>) -> Option<&Certificate>;
+}
+
+impl CertificateStore for KeyChain {
+    fn verifier_for_key_locator(&self, key_locator: Name<'_>) -> Option<Box<dyn Verifier + '_>> {
+        KeyChain::verifier_for_key_locator(self, key_locator)
+    }
+
+    fn certificate_for_key_locator(&self, key_locator: Name<'_>) -> Option<&Certificate> {
+        KeyChain::certificate_for_key_locator(self, key_locator)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TrustError {
+    // The packet is not signed with a KeyLocator name
+    MissingKeyLocator,
+    // No rule allows this signer to sign this packet
+    NoMatchingRule,
+    KeyNotFound,
+    InvalidSignature,
+    // The chain does not reach a trust anchor because a certificate is missing
+    CertificateNotFound,
+    ChainTooLong,
+}
+
+// Validates that a packet is signed with a valid signature by a key that is
+//  allowed to sign it, following the certificate chain up to a trust anchor.
+// Every link of the chain (the packet itself and every intermediate certificate)
+//  must be allowed by some rule.
+pub struct TrustSchema {
+    rules: Vec<TrustRule>,
+    anchors: Vec<NameBuf>,
+    max_chain_length: usize,
+}
+
+impl TrustSchema {
+    pub const DEFAULT_MAX_CHAIN_LENGTH: usize = 8;
+
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            anchors: Vec::new(),
+            max_chain_length: Self::DEFAULT_MAX_CHAIN_LENGTH,
+        }
+    }
+
+    pub fn add_rule(&mut self, rule: TrustRule) {
+        self.rules.push(rule);
+    }
+
+    // Keys under this name are trusted without further certificates
+    pub fn add_trust_anchor(&mut self, key_name: Name<'_>) {
+        self.anchors.push(NameBuf::from(key_name));
+    }
+
+    pub fn set_max_chain_length(&mut self, max_chain_length: usize) {
+        self.max_chain_length = max_chain_length;
+    }
+
+    pub fn rules(&self) -> &[TrustRule] {
+        &self.rules
+    }
+
+    pub fn validate<S>(&self, data: &Data<'_>, store: &S) -> Result<(), TrustError>
+    where
+        S: CertificateStore + ?Sized,
+    {
+        let mut packet = self.validate_link(data, store)?;
+        for _ in 0..self.max_chain_length {
+            match packet {
+                Some(certificate) => packet = self.validate_link(&certificate.data(), store)?,
+                None => return Ok(()),
+            }
+        }
+        Err(TrustError::ChainTooLong)
+    }
+
+    // Checks one packet and returns the certificate of its signer,
+    //  unless the signer is a trust anchor.
+    fn validate_link<'s, S>(
+        &self,
+        data: &Data<'_>,
+        store: &'s S,
+    ) -> Result<Option<&'s Certificate>, TrustError>
+    where
+        S: CertificateStore + ?Sized,
+    {
+        let key_locator = match &data.signature_info.key_locator {
+            Some(KeyLocator::Name(name)) => *name,
+            _ => return Err(TrustError::MissingKeyLocator),
+        };
+
+        if !self.rules.iter().any(|r| r.allows(data.name, key_locator)) {
+            return Err(TrustError::NoMatchingRule);
+        }
+
+        let mut verifier = store
+            .verifier_for_key_locator(key_locator)
+            .ok_or(TrustError::KeyNotFound)?;
+        if !data.verify_signature(verifier.as_mut()) {
+            return Err(TrustError::InvalidSignature);
+        }
+
+        if self
+            .anchors
+            .iter()
+            .any(|a| a.as_name().is_prefix_of(key_locator))
+        {
+            return Ok(None);
+        }
+
+        store
+            .certificate_for_key_locator(key_locator)
+            .map(Some)
+            .ok_or(TrustError::CertificateNotFound)
+    }
+}
+
+impl Default for TrustSchema {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        io::{Decode, Encode},
+        name::{Name, NameBuf, NameComponent},
+        packet::{Data, SignatureInfo, SignatureValue},
+        platform::sha::Sha256Hasher,
+        security::{
+            keychain::{HmacSha256Key, KeyChain},
+            trust::{NamePattern, TrustError, TrustRule, TrustSchema},
+        },
+    };
+
+    fn signed_packet(keychain: &KeyChain, key_name: Name<'_>, name: Name<'_>) -> Vec<u8> {
+        let mut signer = keychain.signer_for_key(key_name).unwrap();
+        let key_locator = NameBuf::from(signer.key_locator().unwrap());
+        let signature_info =
+            SignatureInfo::new(signer.signature_type(), Some(key_locator.as_name()));
+        let mut data = Data::new_unsigned(name, b"content", signature_info);
+        let signature = data.sign_signed_portion(signer.as_mut());
+        data.signature_value = SignatureValue { bytes: &signature };
+        let mut packet = Vec::new();
+        let _ = data.encode(&mut packet);
+        packet
+    }
+
+    #[test]
+    fn test_patterns() {
+        let root = Name::new();
+        let comps = &[
+            NameComponent::generic(b"site"),
+            NameComponent::generic(b"blog"),
+            NameComponent::generic(b"alice"),
+        ];
+        let name = root.adding_components(comps);
+
+        let blog = NameComponent::generic(b"blog");
+        assert!(NamePattern::new().any().literal(blog).any().matches(name));
+        assert!(!NamePattern::new().any().literal(blog).matches(name));
+        assert!(NamePattern::new().any().literal(blog).rest().matches(name));
+        assert!(NamePattern::new().rest().matches(root));
+        assert!(!NamePattern::new().capture(0).any().capture(0).matches(name));
+        assert!(NamePattern::from_prefix(name).matches(name));
+        assert!(!NamePattern::from_prefix(name).matches(root));
+    }
+
+    #[test]
+    fn test_validation() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"site")];
+        let site = root.adding_components(comp);
+        let comp = &[NameComponent::generic(b"alice")];
+        let alice = site.adding_components(comp);
+        let comp = &[NameComponent::generic(b"bob")];
+        let bob = site.adding_components(comp);
+
+        let site_key = KeyChain::key_name(site, b"root");
+        let alice_key = KeyChain::key_name(alice, b"1");
+        let bob_key = KeyChain::key_name(bob, b"1");
+
+        let mut keychain = KeyChain::new();
+        for (identity, key, secret) in [
+            (site, &site_key, b"site secret".as_slice()),
+            (alice, &alice_key, b"alice secret".as_slice()),
+            (bob, &bob_key, b"bob secret".as_slice()),
+        ] {
+            keychain.add_identity(identity).unwrap();
+            let material = HmacSha256Key::new(secret, Sha256Hasher::new());
+            keychain.add_key(identity, key.as_name(), material).unwrap();
+        }
+
+        // Alice's key is certified by the site, Bob's is not
+        let comp = &[
+            NameComponent::generic(b"site"),
+            NameComponent::generic(b"v1"),
+        ];
+        let alice_key_name = alice_key.as_name();
+        let certificate_name = alice_key_name.adding_components(comp);
+        let certificate = signed_packet(&keychain, site_key.as_name(), certificate_name);
+        keychain.add_certificate(&certificate).unwrap();
+
+        // /<site>/blog/<author>/... is signed by /<site>/<author>/KEY/...
+        // /<site>/<author>/KEY/... is signed by /<site>/KEY/...
+        let blog = NameComponent::generic(b"blog");
+        let key = NameComponent::generic(b"KEY");
+        let mut schema = TrustSchema::new();
+        schema.add_rule(TrustRule::new(
+            NamePattern::new()
+                .capture(0)
+                .literal(blog)
+                .capture(1)
+                .rest(),
+            NamePattern::new().capture(0).capture(1).literal(key).rest(),
+        ));
+        schema.add_rule(TrustRule::new(
+            NamePattern::new().capture(0).any().literal(key).rest(),
+            NamePattern::new().capture(0).literal(key).rest(),
+        ));
+        schema.add_trust_anchor(site_key.as_name());
+
+        let comp = &[
+            NameComponent::generic(b"blog"),
+            NameComponent::generic(b"alice"),
+            NameComponent::generic(b"post"),
+        ];
+        let alice_post = site.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"blog"),
+            NameComponent::generic(b"bob"),
+            NameComponent::generic(b"post"),
+        ];
+        let bob_post = site.adding_components(comp);
+
+        // Alice signs her own post
+        let packet = signed_packet(&keychain, alice_key.as_name(), alice_post);
+        let data = Data::try_decode(&packet).unwrap().0;
+        assert_eq!(schema.validate(&data, &keychain), Ok(()));
+
+        // But she cannot sign Bob's post
+        let packet = signed_packet(&keychain, alice_key.as_name(), bob_post);
+        let data = Data::try_decode(&packet).unwrap().0;
+        assert_eq!(
+            schema.validate(&data, &keychain),
+            Err(TrustError::NoMatchingRule)
+        );
+
+        // Bob can sign his post, but his key is not certified
+        let packet = signed_packet(&keychain, bob_key.as_name(), bob_post);
+        let data = Data::try_decode(&packet).unwrap().0;
+        assert_eq!(
+            schema.validate(&data, &keychain),
+            Err(TrustError::CertificateNotFound)
+        );
+
+        // A corrupted packet fails the signature check
+        let mut packet = signed_packet(&keychain, alice_key.as_name(), alice_post);
+        let last = packet.len() - 1;
+        packet[last] ^= 1;
+        let data = Data::try_decode(&packet).unwrap().0;
+        assert_eq!(
+            schema.validate(&data, &keychain),
+            Err(TrustError::InvalidSignature)
+        );
+
+        // Without the anchor nothing can be trusted
+        let mut schema = TrustSchema::new();
+        schema.add_rule(TrustRule::new(
+            NamePattern::new().rest(),
+            NamePattern::new().rest(),
+        ));
+        let packet = signed_packet(&keychain, site_key.as_name(), alice_post);
+        let data = Data::try_decode(&packet).unwrap().0;
+        assert_eq!(
+            schema.validate(&data, &keychain),
+            Err(TrustError::CertificateNotFound)
+        );
+    }
+}
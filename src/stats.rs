@@ -0,0 +1,504 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::cmp::Reverse;
+
+use crate::{
+    face::lp::NackReason,
+    forwarder::{FaceCounters, FaceToken, ForwarderMetrics},
+    name::{Name, NameBuf},
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct PrefixCounters {
+    pub interests: u64,
+    // Interests that were satisfied from the content store
+    pub cache_hits: u64,
+}
+
+impl PrefixCounters {
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        if self.interests == 0 {
+            return None;
+        }
+        Some(self.cache_hits as f64 / self.interests as f64)
+    }
+}
+
+// An entry of the top-K sketch. The counters can overestimate the real ones
+//  by at most "error", which is the count of the entry it replaced.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UnregisteredPrefix {
+    pub prefix: NameBuf,
+    pub counters: PrefixCounters,
+    pub error: u64,
+}
+
+// Interest and cache hit counts per prefix, e.g. for sizing the content store
+//  or finding popular content.
+// Interests are attributed to the longest registered prefix matching their name.
+// The rest are grouped by their first few components and only the most popular
+//  of those groups are kept, using the Space-Saving algorithm, so that the memory
+//  stays bounded no matter how many distinct names are requested.
+// Disabled by default, since matching every interest has a cost. Only the prefixes
+//  registered while enabled are tracked, so it is best enabled before the faces are added.
+pub struct PrefixStats {
+    enabled: bool,
+    registered: BTreeMap<NameBuf, RegisteredPrefix>,
+    unregistered: Vec<UnregisteredPrefix>,
+    top_k: usize,
+    unregistered_prefix_length: usize,
+}
+
+impl PrefixStats {
+    pub const DEFAULT_TOP_K: usize = 32;
+    pub const DEFAULT_UNREGISTERED_PREFIX_LENGTH: usize = 2;
+
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            registered: BTreeMap::new(),
+            unregistered: Vec::new(),
+            top_k: Self::DEFAULT_TOP_K,
+            unregistered_prefix_length: Self::DEFAULT_UNREGISTERED_PREFIX_LENGTH,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // Disabling forgets the registered prefixes with their counters
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.registered.clear();
+        }
+    }
+
+    // The number of unregistered prefixes to keep track of
+    pub fn set_top_k(&mut self, top_k: usize) {
+        self.top_k = top_k;
+        if self.unregistered.len() > top_k {
+            self.sort_unregistered();
+            self.unregistered.truncate(top_k);
+        }
+    }
+
+    // How many leading components of the unregistered names form the prefix.
+    // Changing it clears the unregistered prefixes collected so far.
+    pub fn set_unregistered_prefix_length(&mut self, length: usize) {
+        if length != self.unregistered_prefix_length {
+            self.unregistered_prefix_length = length;
+            self.unregistered.clear();
+        }
+    }
+
+    // The prefixes are listed as long as a face has a route for them
+    pub fn registered(&self) -> impl Iterator<Item = (Name<'_>, PrefixCounters)> {
+        self.registered
+            .iter()
+            .map(|(prefix, registered)| (prefix.as_name(), registered.counters))
+    }
+
+    pub fn registered_prefix(&self, prefix: Name<'_>) -> Option<PrefixCounters> {
+        self.registered
+            .get(&NameBuf::from(prefix))
+            .map(|registered| registered.counters)
+    }
+
+    // The most requested unregistered prefixes, the most popular first
+    pub fn top_unregistered(&self) -> Vec<&UnregisteredPrefix> {
+        let mut top: Vec<_> = self.unregistered.iter().collect();
+        top.sort_by_key(|entry| Reverse(entry.counters.interests));
+        top
+    }
+
+    // Clears all the counters
+    pub fn reset(&mut self) {
+        for registered in self.registered.values_mut() {
+            registered.counters = PrefixCounters::default();
+        }
+        self.unregistered.clear();
+    }
+
+    pub(crate) fn prefix_registered(&mut self, prefix: Name<'_>, face: FaceToken) {
+        if !self.enabled {
+            return;
+        }
+        let registered = self.registered.entry(NameBuf::from(prefix)).or_default();
+        if !registered.faces.contains(&face) {
+            registered.faces.push(face);
+        }
+    }
+
+    // Once no face has a route for the prefix, it is forgotten with its counters
+    pub(crate) fn prefix_unregistered(&mut self, prefix: Name<'_>, face: FaceToken) {
+        let prefix = NameBuf::from(prefix);
+        if let Some(registered) = self.registered.get_mut(&prefix) {
+            registered.faces.retain(|f| *f != face);
+            if registered.faces.is_empty() {
+                self.registered.remove(&prefix);
+            }
+        }
+    }
+
+    pub(crate) fn face_removed(&mut self, face: FaceToken) {
+        self.registered.retain(|_, registered| {
+            registered.faces.retain(|f| *f != face);
+            !registered.faces.is_empty()
+        });
+    }
+
+    pub(crate) fn record_interest(&mut self, name: Name<'_>, cache_hit: bool) {
+        if !self.enabled {
+            return;
+        }
+
+        // The prefixes of the name are looked up from the shortest, the root included
+        let mut prefix = NameBuf::new();
+        let mut longest = self.registered.contains_key(&prefix).then(NameBuf::new);
+        for component in name.components() {
+            prefix.push(component);
+            if self.registered.contains_key(&prefix) {
+                longest = Some(prefix.clone());
+            }
+        }
+        if let Some(registered) = longest.and_then(|p| self.registered.get_mut(&p)) {
+            Self::count(&mut registered.counters, cache_hit);
+            return;
+        }
+
+        let length = self.unregistered_prefix_length.min(name.component_count());
+        if let Some(entry) = self.unregistered.iter_mut().find(|entry| {
+            let prefix = entry.prefix.as_name();
+            prefix.component_count() == length && prefix.is_prefix_of(name)
+        }) {
+            Self::count(&mut entry.counters, cache_hit);
+            return;
+        }
+
+        if self.top_k == 0 {
+            return;
+        }
+        let prefix = name
+            .components()
+            .take(length)
+            .fold(NameBuf::new(), |prefix, component| {
+                prefix.pushing(component)
+            });
+        let mut entry = UnregisteredPrefix {
+            prefix,
+            counters: PrefixCounters::default(),
+            error: 0,
+        };
+        if self.unregistered.len() < self.top_k {
+            Self::count(&mut entry.counters, cache_hit);
+            self.unregistered.push(entry);
+        } else {
+            // Replace the least popular prefix, inheriting its count
+            let (index, least) = self
+                .unregistered
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.counters.interests)
+                .unwrap();
+            entry.error = least.counters.interests;
+            entry.counters.interests = least.counters.interests;
+            Self::count(&mut entry.counters, cache_hit);
+            self.unregistered[index] = entry;
+        }
+    }
+
+    fn count(counters: &mut PrefixCounters, cache_hit: bool) {
+        counters.interests += 1;
+        if cache_hit {
+            counters.cache_hits += 1;
+        }
+    }
+
+    fn sort_unregistered(&mut self) {
+        self.unregistered
+            .sort_by_key(|entry| Reverse(entry.counters.interests));
+    }
+}
+
+#[derive(Default)]
+struct RegisteredPrefix {
+    // The faces with a route for the prefix
+    faces: Vec<FaceToken>,
+    counters: PrefixCounters,
+}
+
+impl Default for PrefixStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// What CountingMetrics counted so far
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MetricsSnapshot {
+    // Ordered by the token, including the faces that were removed since the last reset
+    pub faces: Vec<(FaceToken, FaceCounters)>,
+    pub cs_hits: u64,
+    pub cs_misses: u64,
+    // The interests that only joined the PIT entry of an identical one from the same batch
+    pub aggregated_interests: u64,
+    pub dropped_interests: u64,
+    pub dropped_data: u64,
+    pub invalid_packets: u64,
+    pub oversized_packets: u64,
+    pub expired_routes: u64,
+}
+
+impl MetricsSnapshot {
+    pub fn face(&self, face: FaceToken) -> Option<FaceCounters> {
+        self.faces
+            .binary_search_by_key(&face, |(token, _)| *token)
+            .ok()
+            .map(|index| self.faces[index].1)
+    }
+
+    // The counters of all the faces added together
+    pub fn totals(&self) -> FaceCounters {
+        let mut totals = FaceCounters::default();
+        for (_, counters) in self.faces.iter() {
+            totals += *counters;
+        }
+        totals
+    }
+
+    pub fn cs_hit_rate(&self) -> Option<f64> {
+        let lookups = self.cs_hits + self.cs_misses;
+        if lookups == 0 {
+            return None;
+        }
+        Some(self.cs_hits as f64 / lookups as f64)
+    }
+}
+
+// Metrics that keep every count, per face where it makes sense, which also makes the
+//  status datasets report them
+#[derive(Default)]
+pub struct CountingMetrics {
+    snapshot: MetricsSnapshot,
+}
+
+impl CountingMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.snapshot.clone()
+    }
+
+    // Clears all the counters
+    pub fn reset(&mut self) {
+        self.snapshot = MetricsSnapshot::default();
+    }
+
+    fn face_mut(&mut self, face: FaceToken) -> &mut FaceCounters {
+        let faces = &mut self.snapshot.faces;
+        let index = match faces.binary_search_by_key(&face, |(token, _)| *token) {
+            Ok(index) => index,
+            Err(index) => {
+                faces.insert(index, (face, FaceCounters::default()));
+                index
+            }
+        };
+        &mut faces[index].1
+    }
+}
+
+impl ForwarderMetrics for CountingMetrics {
+    fn interest_received(&mut self, from_face: FaceToken) {
+        self.face_mut(from_face).in_interests += 1;
+    }
+
+    fn interest_dropped(&mut self, _from_face: FaceToken) {
+        self.snapshot.dropped_interests += 1;
+    }
+
+    fn interest_satisfied(&mut self, from_face: FaceToken) {
+        self.face_mut(from_face).satisfied_interests += 1;
+    }
+
+    fn interest_timed_out(&mut self, from_face: FaceToken) {
+        self.face_mut(from_face).unsatisfied_interests += 1;
+    }
+
+    fn interest_sent(&mut self, to_face: FaceToken) {
+        self.face_mut(to_face).out_interests += 1;
+    }
+
+    fn interest_coalesced(&mut self, _from_face: FaceToken) {
+        self.snapshot.aggregated_interests += 1;
+    }
+
+    fn nack_sent(&mut self, to_face: FaceToken) {
+        self.face_mut(to_face).out_nacks += 1;
+    }
+
+    fn nack_received(&mut self, from_face: FaceToken, _reason: NackReason) {
+        self.face_mut(from_face).in_nacks += 1;
+    }
+
+    fn data_received(&mut self, from_face: FaceToken) {
+        self.face_mut(from_face).in_data += 1;
+    }
+
+    fn data_sent(&mut self, to_face: FaceToken) {
+        self.face_mut(to_face).out_data += 1;
+    }
+
+    fn data_dropped(&mut self, _from_face: FaceToken) {
+        self.snapshot.dropped_data += 1;
+    }
+
+    fn bytes_received(&mut self, from_face: FaceToken, len: usize) {
+        self.face_mut(from_face).in_bytes += len as u64;
+    }
+
+    fn bytes_sent(&mut self, to_face: FaceToken, len: usize) {
+        self.face_mut(to_face).out_bytes += len as u64;
+    }
+
+    fn cs_hit(&mut self, _from_face: FaceToken) {
+        self.snapshot.cs_hits += 1;
+    }
+
+    fn cs_miss(&mut self, _from_face: FaceToken) {
+        self.snapshot.cs_misses += 1;
+    }
+
+    fn invalid_packet_received(&mut self, _from_face: FaceToken) {
+        self.snapshot.invalid_packets += 1;
+    }
+
+    fn oversized_packet_skipped(&mut self, _from_face: FaceToken, _len: usize) {
+        self.snapshot.oversized_packets += 1;
+    }
+
+    fn route_expired(&mut self, _to_face: FaceToken) {
+        self.snapshot.expired_routes += 1;
+    }
+
+    fn face_counters(&self, face: FaceToken) -> FaceCounters {
+        self.snapshot.face(face).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        forwarder::FaceToken,
+        name::{Name, NameComponent},
+        stats::{PrefixCounters, PrefixStats},
+    };
+
+    #[test]
+    fn test_prefix_stats() {
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"video")];
+        let video = root.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"video"),
+            NameComponent::generic(b"hd"),
+        ];
+        let video_hd = root.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"video"),
+            NameComponent::generic(b"hd"),
+            NameComponent::generic(b"1"),
+        ];
+        let video_hd_1 = root.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"video"),
+            NameComponent::generic(b"sd"),
+        ];
+        let video_sd = root.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"news"),
+            NameComponent::generic(b"a"),
+            NameComponent::generic(b"1"),
+        ];
+        let news_a_1 = root.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"news"),
+            NameComponent::generic(b"a"),
+            NameComponent::generic(b"2"),
+        ];
+        let news_a_2 = root.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"news"),
+            NameComponent::generic(b"b"),
+        ];
+        let news_b = root.adding_components(comp);
+        let comp = &[
+            NameComponent::generic(b"news"),
+            NameComponent::generic(b"c"),
+        ];
+        let news_c = root.adding_components(comp);
+
+        // Nothing is kept while disabled
+        let mut stats = PrefixStats::new();
+        stats.prefix_registered(video, FaceToken(1));
+        stats.record_interest(video_hd_1, false);
+        assert_eq!(stats.registered().count(), 0);
+        assert!(stats.top_unregistered().is_empty());
+
+        stats.set_enabled(true);
+        stats.prefix_registered(video, FaceToken(1));
+        stats.prefix_registered(video_hd, FaceToken(1));
+        stats.prefix_registered(video, FaceToken(2));
+        assert_eq!(stats.registered().count(), 2);
+        stats.record_interest(video_hd_1, false);
+        stats.record_interest(video_hd_1, true);
+        stats.record_interest(video_sd, true);
+        assert_eq!(
+            stats.registered_prefix(video_hd),
+            Some(PrefixCounters {
+                interests: 2,
+                cache_hits: 1
+            })
+        );
+        assert_eq!(stats.registered_prefix(video).unwrap().interests, 1);
+        assert_eq!(
+            stats.registered_prefix(video_hd).unwrap().cache_hit_rate(),
+            Some(0.5)
+        );
+
+        // Unregistered names are grouped by their first two components
+        stats.set_top_k(2);
+        stats.record_interest(news_a_1, false);
+        stats.record_interest(news_a_2, false);
+        stats.record_interest(news_b, false);
+        let top = stats.top_unregistered();
+        assert_eq!(top.len(), 2);
+        assert!(top[0].prefix.as_name() == news_a_1.dropping_last_component().unwrap());
+        assert_eq!(top[0].counters.interests, 2);
+
+        // The least popular one is replaced and the new one inherits its count
+        stats.record_interest(news_c, true);
+        let top = stats.top_unregistered();
+        assert_eq!(top.len(), 2);
+        assert!(top[1].prefix.as_name() == news_c);
+        assert_eq!(top[1].counters.interests, 2);
+        assert_eq!(top[1].counters.cache_hits, 1);
+        assert_eq!(top[1].error, 1);
+
+        stats.reset();
+        assert_eq!(stats.top_unregistered().len(), 0);
+        assert_eq!(stats.registered_prefix(video_hd), Some(Default::default()));
+
+        // The prefixes go with the last of their routes
+        stats.prefix_unregistered(video, FaceToken(1));
+        assert!(stats.registered_prefix(video).is_some());
+        stats.prefix_unregistered(video, FaceToken(2));
+        assert_eq!(stats.registered_prefix(video), None);
+        stats.record_interest(video_sd, false);
+        assert!(stats.top_unregistered()[0].prefix.as_name() == video_sd);
+        stats.face_removed(FaceToken(1));
+        assert_eq!(stats.registered().count(), 0);
+    }
+}
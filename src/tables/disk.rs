@@ -0,0 +1,482 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use alloc::vec::Vec;
+
+use crate::{
+    clock::Timestamp,
+    io::Decode,
+    name::{Name, NameBuf, NameComponent},
+    packet::Data,
+    tables::{CanBePrefixSelection, ContentStore, ContentStoreUsage, CsEntryInfo, TableParams},
+    tlv::{TlvDecode, TlvEncode},
+};
+
+// A content store that keeps the packets in segment files in a directory, so that they
+//  survive restarts and can take far more than the memory. Only the index of the names
+//  is kept in memory. It can be paired with the reference tables (see CombinedTables).
+// The packets are appended to the newest segment, and once the segments take more than
+//  the byte budget the oldest one is deleted with all its packets, so the eviction is
+//  first in, first out. The data is not removed otherwise, however long it is not used.
+// The freshness does not survive restarts: the data loaded from the disk is stale until
+//  it arrives again.
+pub struct DiskContentStore {
+    directory: PathBuf,
+    segment_size: u64,
+    byte_budget: u64,
+    // In the order they were written, the packets are appended to the last one
+    segments: VecDeque<Segment>,
+    // Keyed by the full names, whose encoding sorts them in the canonical order
+    index: BTreeMap<NameBuf, DiskEntry>,
+    packet_bytes: usize,
+    can_be_prefix_selection: CanBePrefixSelection,
+    read_buffer: Vec<u8>,
+    io_errors: u64,
+}
+
+struct Segment {
+    number: u64,
+    file: File,
+    len: u64,
+    // The full names of the packets in the segment, to remove them with it
+    names: Vec<NameBuf>,
+}
+
+struct DiskEntry {
+    segment: u64,
+    // Where the packet starts within the segment
+    offset: u64,
+    len: usize,
+    freshness_deadline: Timestamp,
+}
+
+pub const DEFAULT_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+const SEGMENT_EXTENSION: &str = "seg";
+
+impl DiskContentStore {
+    // Opens the store in the directory, creating it if needed, and indexes the packets
+    //  that are already there. A record cut short, e.g. by a crash, is dropped.
+    pub fn open(directory: impl AsRef<Path>, byte_budget: u64) -> io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)?;
+        let mut numbers = Vec::new();
+        for entry in fs::read_dir(&directory)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|e| e != SEGMENT_EXTENSION) {
+                continue;
+            }
+            let number = path.file_stem().and_then(|s| s.to_str()?.parse().ok());
+            if let Some(number) = number {
+                numbers.push(number);
+            }
+        }
+        numbers.sort();
+
+        let mut store = Self {
+            directory,
+            segment_size: DEFAULT_SEGMENT_SIZE,
+            byte_budget,
+            segments: VecDeque::new(),
+            index: BTreeMap::new(),
+            packet_bytes: 0,
+            can_be_prefix_selection: CanBePrefixSelection::default(),
+            read_buffer: Vec::new(),
+            io_errors: 0,
+        };
+        for number in numbers {
+            store.load_segment(number)?;
+        }
+        store.evict_if_needed();
+        Ok(store)
+    }
+
+    // A new segment is started once the current one would grow beyond the size.
+    // Smaller segments follow the byte budget more closely, but each is a file.
+    pub fn with_segment_size(mut self, bytes: u64) -> Self {
+        self.segment_size = bytes;
+        self
+    }
+
+    // Makes sure that the packets written so far are on the disk
+    pub fn sync(&mut self) -> io::Result<()> {
+        match self.segments.back() {
+            Some(segment) => segment.file.sync_data(),
+            None => Ok(()),
+        }
+    }
+
+    // The size of the segments, which is a bit more than the packets they hold
+    pub fn disk_usage(&self) -> u64 {
+        self.segments.iter().map(|segment| segment.len).sum()
+    }
+
+    // The reads and writes that failed, or read back something other than a data packet,
+    //  after which the packets were not cached or not found
+    pub fn io_error_count(&self) -> u64 {
+        self.io_errors
+    }
+
+    fn segment_path(&self, number: u64) -> PathBuf {
+        self.directory
+            .join(format!("{number:016}.{SEGMENT_EXTENSION}"))
+    }
+
+    fn load_segment(&mut self, number: u64) -> io::Result<()> {
+        let path = self.segment_path(number);
+        let bytes = fs::read(&path)?;
+        let mut names = Vec::new();
+        let mut offset = 0;
+        while let Some((name, packet_offset, end)) = parse_record(&bytes, offset) {
+            let full_name = NameBuf::from(name);
+            let entry = DiskEntry {
+                segment: number,
+                offset: packet_offset as u64,
+                len: end - packet_offset,
+                freshness_deadline: Timestamp { ms_since_1970: 0 },
+            };
+            // The same packet may have been written again after its segment was
+            //  deleted but before the deletion reached the disk
+            self.packet_bytes += entry.len;
+            if let Some(previous) = self.index.insert(full_name.clone(), entry) {
+                self.packet_bytes -= previous.len;
+            }
+            names.push(full_name);
+            offset = end;
+        }
+
+        let file = OpenOptions::new().read(true).append(true).open(&path)?;
+        if offset < bytes.len() {
+            file.set_len(offset as u64)?;
+        }
+        self.segments.push_back(Segment {
+            number,
+            file,
+            len: offset as u64,
+            names,
+        });
+        Ok(())
+    }
+
+    // Returns the segment and the offset the packet was written at
+    fn append(&mut self, full_name: &NameBuf, packet: &[u8]) -> io::Result<(u64, u64)> {
+        let name_len = full_name.inner_length();
+        let (Ok(record_name_len), Ok(rest_len)) = (
+            u16::try_from(name_len),
+            u32::try_from(2 + name_len + packet.len()),
+        ) else {
+            return Err(io::ErrorKind::InvalidInput.into());
+        };
+        let record_len = 4 + rest_len as u64;
+
+        let segment_size = self.segment_size;
+        let is_full =
+            |segment: &Segment| segment.len > 0 && segment.len + record_len > segment_size;
+        if self.segments.back().is_none_or(is_full) {
+            let number = self.segments.back().map_or(0, |segment| segment.number + 1);
+            let path = self.segment_path(number);
+            let file = OpenOptions::new()
+                .read(true)
+                .append(true)
+                .create_new(true)
+                .open(path)?;
+            self.segments.push_back(Segment {
+                number,
+                file,
+                len: 0,
+                names: Vec::new(),
+            });
+        }
+
+        let mut record = Vec::with_capacity(record_len as usize);
+        record.extend_from_slice(&rest_len.to_be_bytes());
+        record.extend_from_slice(&record_name_len.to_be_bytes());
+        let _ = full_name.encode_inner(&mut record);
+        record.extend_from_slice(packet);
+
+        // Was just added otherwise
+        let segment = self.segments.back_mut().unwrap();
+        if let Err(err) = segment.file.write_all(&record) {
+            // Whatever was written would not parse as a record
+            let _ = segment.file.set_len(segment.len);
+            return Err(err);
+        }
+        let offset = segment.len + 6 + name_len as u64;
+        segment.len += record_len;
+        segment.names.push(full_name.clone());
+        Ok((segment.number, offset))
+    }
+
+    fn evict_if_needed(&mut self) {
+        while self.segments.len() > 1 && self.disk_usage() > self.byte_budget {
+            // There are more than one
+            let segment = self.segments.pop_front().unwrap();
+            for name in segment.names {
+                if self
+                    .index
+                    .get(&name)
+                    .is_some_and(|entry| entry.segment == segment.number)
+                {
+                    if let Some(entry) = self.index.remove(&name) {
+                        self.packet_bytes -= entry.len;
+                    }
+                }
+            }
+            drop(segment.file);
+            if fs::remove_file(self.segment_path(segment.number)).is_err() {
+                self.io_errors += 1;
+            }
+        }
+    }
+
+    fn read_packet(&mut self, full_name: &NameBuf) -> Option<&[u8]> {
+        let entry = self.index.get(full_name)?;
+        let (number, offset, len) = (entry.segment, entry.offset, entry.len);
+        // The numbers may have gaps, e.g. when a segment file was deleted by hand
+        let idx = self
+            .segments
+            .binary_search_by_key(&number, |segment| segment.number)
+            .ok()?;
+        let segment = &mut self.segments[idx];
+        self.read_buffer.resize(len, 0);
+        let result = segment
+            .file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| segment.file.read_exact(&mut self.read_buffer));
+        // The file may have been changed behind the store's back
+        let packet = &self.read_buffer;
+        let is_data = Data::try_decode(packet).is_ok_and(|(_, len)| len == packet.len());
+        if result.is_err() || !is_data {
+            self.io_errors += 1;
+            return None;
+        }
+        Some(&self.read_buffer)
+    }
+}
+
+// A record is the length of the rest (u32), the length of the name (u16), the full name
+//  (its components, without the TLV header of the name) and the packet, big-endian.
+// Returns the name, the offset of the packet and the end of the record.
+fn parse_record(bytes: &[u8], offset: usize) -> Option<(Name<'_>, usize, usize)> {
+    let header = bytes.get(offset..offset + 6)?;
+    let rest_len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let name_len = u16::from_be_bytes([header[4], header[5]]) as usize;
+    let end = offset + 4 + rest_len;
+    let packet_offset = offset + 6 + name_len;
+    if end > bytes.len() || packet_offset > end {
+        return None;
+    }
+    let name = Name::try_decode_from_inner(&bytes[offset + 6..packet_offset])?;
+    Some((name, packet_offset, end))
+}
+
+impl ContentStore for DiskContentStore {
+    fn insert_data<'a>(
+        &mut self,
+        name: Name<'a>,
+        digest: [u8; 32],
+        freshness: u64,
+        now: Timestamp,
+        packet: &'a [u8],
+    ) {
+        let full_name =
+            NameBuf::from(name).pushing(NameComponent::implicit_sha256(digest.as_slice()));
+        let freshness_deadline = now.adding(freshness);
+        if let Some(entry) = self.index.get_mut(&full_name) {
+            // The same digest means the same packet, which is only refreshed
+            entry.freshness_deadline = entry.freshness_deadline.max(freshness_deadline);
+            return;
+        }
+
+        match self.append(&full_name, packet) {
+            Ok((segment, offset)) => {
+                self.index.insert(
+                    full_name,
+                    DiskEntry {
+                        segment,
+                        offset,
+                        len: packet.len(),
+                        freshness_deadline,
+                    },
+                );
+                self.packet_bytes += packet.len();
+                self.evict_if_needed();
+            }
+            Err(_) => self.io_errors += 1,
+        }
+    }
+
+    fn get_data<'a>(
+        &mut self,
+        name: Name<'a>,
+        can_be_prefix: bool,
+        must_be_fresh: bool,
+        now: Timestamp,
+    ) -> Option<&[u8]> {
+        let usable = |entry: &DiskEntry| !must_be_fresh || now <= entry.freshness_deadline;
+        let prefix = NameBuf::from(name);
+        let component_count = name.component_count();
+        let mut under = self
+            .index
+            .range(&prefix..)
+            .take_while(|(full_name, _)| name.is_prefix_of(full_name.as_name()))
+            .filter(|(_, entry)| usable(entry));
+
+        let found = if can_be_prefix {
+            match self.can_be_prefix_selection {
+                CanBePrefixSelection::Leftmost => under.next(),
+                CanBePrefixSelection::Rightmost => under.last(),
+            }
+        } else {
+            // Either the name has the digest, or it is one of the versions with the digest
+            //  right after the name, preferring the one that stays fresh for the longest
+            under
+                .filter(|(full_name, _)| full_name.component_count() <= component_count + 1)
+                .max_by_key(|(_, entry)| entry.freshness_deadline)
+        };
+        let full_name = found?.0.clone();
+        self.read_packet(&full_name)
+    }
+
+    // The data is only removed when its segment is evicted
+    fn prune_cs_if_needed(&mut self, _now: Timestamp) {}
+
+    fn content_store_usage(&self) -> ContentStoreUsage {
+        ContentStoreUsage {
+            entries: self.index.len(),
+            bytes: self.packet_bytes,
+        }
+    }
+
+    fn list_cs_entries(&self, report: &mut dyn FnMut(CsEntryInfo<'_>)) {
+        for (full_name, entry) in self.index.iter() {
+            report(CsEntryInfo {
+                name: full_name.as_name(),
+                bytes: entry.len,
+                fresh_until: entry.freshness_deadline,
+                expiry: Timestamp {
+                    ms_since_1970: u64::MAX,
+                },
+            });
+        }
+    }
+
+    fn set_cs_params(&mut self, params: &TableParams) {
+        self.can_be_prefix_selection = params.can_be_prefix_selection;
+    }
+
+    // The packets stay in their segments until these are evicted, they are only unindexed
+    fn erase_data(&mut self, prefix: Name<'_>, limit: Option<usize>) -> usize {
+        let erased: Vec<NameBuf> = self
+            .index
+            .range(NameBuf::from(prefix)..)
+            .take_while(|(full_name, _)| prefix.is_prefix_of(full_name.as_name()))
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(full_name, _)| full_name.clone())
+            .collect();
+        for full_name in erased.iter() {
+            if let Some(entry) = self.index.remove(full_name) {
+                self.packet_bytes -= entry.len;
+            }
+        }
+        erased.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{Seek, SeekFrom, Write},
+    };
+
+    use crate::{
+        clock::Timestamp,
+        io::Encode,
+        name::{Name, NameComponent},
+        packet::{Data, SignatureInfo, SignatureValue},
+        tables::{disk::DiskContentStore, ContentStore},
+    };
+
+    #[test]
+    fn test_disk_content_store() {
+        let directory = std::env::temp_dir().join(format!("reto-disk-cs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let components: Vec<_> = [b"a", b"b", b"c", b"d"]
+            .iter()
+            .map(|c| [NameComponent::generic(*c)])
+            .collect();
+        let names: Vec<_> = components
+            .iter()
+            .map(|c| root.adding_components(c))
+            .collect();
+        let packets: Vec<_> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let content = [i as u8; 40];
+                let mut data =
+                    Data::new_unsigned(*name, &content, SignatureInfo::new_digest_sha256());
+                data.signature_value = SignatureValue { bytes: &[0; 32] };
+                let mut packet = Vec::new();
+                let _ = data.encode(&mut packet);
+                packet
+            })
+            .collect();
+
+        // Each packet gets its own segment, and only three fit in the budget
+        let budget = 400;
+        let mut store = DiskContentStore::open(&directory, budget)
+            .unwrap()
+            .with_segment_size(50);
+        for (i, packet) in packets.iter().enumerate() {
+            store.insert_data(names[i], [i as u8; 32], 1000, now, packet);
+        }
+        let packet = |i: usize| Some(packets[i].as_slice());
+        assert_eq!(store.get_data(names[0], false, false, now), None);
+        assert_eq!(store.get_data(names[3], false, true, now), packet(3));
+        assert_eq!(store.content_store_usage().entries, 3);
+        assert!(store.sync().is_ok());
+        drop(store);
+
+        // A record cut short is dropped when the store is opened again
+        let segment = |number: u64| directory.join(format!("{number:016}.seg"));
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(segment(3))
+            .unwrap();
+        assert!(file.write_all(&[0, 0, 1, 0, 0]).is_ok());
+        drop(file);
+        // And a segment that is gone leaves a gap in the numbers
+        assert!(fs::remove_file(segment(2)).is_ok());
+
+        // The packets are still there after a restart, but stale
+        let mut store = DiskContentStore::open(&directory, budget).unwrap();
+        assert_eq!(store.content_store_usage().entries, 2);
+        assert_eq!(store.get_data(names[3], false, true, now), None);
+        assert_eq!(store.get_data(names[3], false, false, now), packet(3));
+        assert_eq!(store.get_data(root, true, false, now), packet(1));
+        assert_eq!(store.io_error_count(), 0);
+        drop(store);
+
+        // The bytes that are no longer a data packet are not served
+        let mut file = fs::OpenOptions::new().write(true).open(segment(1)).unwrap();
+        let full_name_len = 3 + 34;
+        assert!(file.seek(SeekFrom::Start(6 + full_name_len)).is_ok());
+        assert!(file.write_all(&[5]).is_ok());
+        drop(file);
+        let mut store = DiskContentStore::open(&directory, budget).unwrap();
+        assert_eq!(store.get_data(names[1], false, false, now), None);
+        assert_eq!(store.io_error_count(), 1);
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,1437 @@
+use core::hash::{BuildHasherDefault, Hasher};
+use std::collections::HashMap;
+
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    clock::Timestamp,
+    forwarder::FaceToken,
+    name::{Name, NameBuf, NameComponent},
+    packet::Interest,
+    tables::{
+        measurements::Measurements,
+        policy::CsEntryId,
+        reference::{
+            add_fib_entry, remove_fib_entry, report_default_routes, DataEntry, DeadNonceList,
+            EncodedComponent, FibEntry, InterestLifetimes, PitEntry, DEFAULT_DNL_CAPACITY,
+            DEFAULT_DNL_FALSE_POSITIVE_ONE_IN,
+        },
+        strategy::{
+            BestRouteStrategy, NextHop, PendingInterest, PitEntryView, RetransmissionSuppression,
+            Strategy, StrategyParameters,
+        },
+        CanBePrefixSelection, ContentStore, ContentStoreUsage, CsEntryInfo, Fib, FibEntryInfo, Pit,
+        PitEntryInfo, TableParams, Tables,
+    },
+};
+
+// The same tables as the reference ones with the best route strategy, but the name tree
+//  is a single hash map keyed by the parent node and the hash of the component, so that
+//  finding a child takes the same time however many siblings it has, e.g. for the FIBs
+//  with thousands of prefixes under the same parent. The longest prefix match walks down
+//  the name one component at a time, as in the reference tables.
+// The children of a node are not ordered, so an interest that can be a prefix gets
+//  whichever matching data is found first.
+pub struct HashedTables {
+    // Indexed by NodeId, the removed nodes are reused
+    nodes: Vec<Option<Node>>,
+    free_nodes: Vec<NodeId>,
+    // The first node with the parent and the component hash, the others are chained
+    index: HashMap<(NodeId, u64), NodeId, BuildHasherDefault<KeyHasher>>,
+    dead_nonce_list: DeadNonceList,
+    data_cache_duration_ms: u64,
+    interest_lifetimes: InterestLifetimes,
+    retransmission_suppression: RetransmissionSuppression,
+    can_be_prefix_selection: CanBePrefixSelection,
+    face_scratchpad: Vec<(u32, FaceToken)>,
+    // Where the walk of process_interest stopped, the deepest node on the path of the name
+    //  and its depth, so that register_interest goes on from there
+    collected_path: Option<(NodeId, usize)>,
+    default_routes: Vec<FibEntry>,
+    strategy: BestRouteStrategy,
+    measurements: Measurements,
+    next_hops: Vec<NextHop>,
+    forward_to: Vec<FaceToken>,
+    prune_interval_ms: u64,
+    last_pit_prune_time: Timestamp,
+    // The names with in-records that expired during the last prune, with their faces
+    timed_out: Vec<(NameBuf, Vec<FaceToken>)>,
+    last_cs_prune_time: Timestamp,
+    cs_entries: usize,
+    cs_bytes: usize,
+    next_cs_entry_id: u64,
+}
+
+type NodeId = u32;
+
+const ROOT: NodeId = 0;
+
+struct Node {
+    parent: NodeId,
+    component: EncodedComponent,
+    hash: u64,
+    // The next node with the same parent and hash, if the hashes collided
+    next_collision: Option<NodeId>,
+    // Where the node is in the children of its parent, to remove it without a search
+    index_in_parent: usize,
+    children: Vec<NodeId>,
+    // Ordered by cost
+    fib: Vec<FibEntry>,
+    pit_normal: PitEntry,
+    pit_prefix: PitEntry,
+    data: Option<DataEntry>,
+}
+
+impl Node {
+    fn new(parent: NodeId, component: EncodedComponent, hash: u64) -> Self {
+        Self {
+            parent,
+            component,
+            hash,
+            next_collision: None,
+            index_in_parent: 0,
+            children: Vec::new(),
+            fib: Vec::new(),
+            pit_normal: PitEntry::new(),
+            pit_prefix: PitEntry::new(),
+            data: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_none()
+            && self.fib.is_empty()
+            && self.pit_normal.pit_in.is_empty()
+            && self.pit_prefix.pit_in.is_empty()
+            && self.children.is_empty()
+    }
+}
+
+impl HashedTables {
+    pub fn new(
+        data_cache_duration_ms: u32,
+        dead_nonce_duration_ms: u32,
+        prune_interval_ms: u32,
+    ) -> Self {
+        let root = Node::new(
+            ROOT,
+            EncodedComponent::from_named_component(NameComponent::generic(&[])),
+            0,
+        );
+        Self {
+            nodes: vec![Some(root)],
+            free_nodes: Vec::new(),
+            index: HashMap::default(),
+            dead_nonce_list: DeadNonceList::new(
+                dead_nonce_duration_ms as u64,
+                DEFAULT_DNL_CAPACITY,
+                DEFAULT_DNL_FALSE_POSITIVE_ONE_IN,
+            ),
+            data_cache_duration_ms: data_cache_duration_ms as u64,
+            interest_lifetimes: InterestLifetimes::default(),
+            retransmission_suppression: RetransmissionSuppression::default(),
+            can_be_prefix_selection: CanBePrefixSelection::default(),
+            face_scratchpad: Vec::new(),
+            collected_path: None,
+            default_routes: Vec::new(),
+            strategy: BestRouteStrategy::default(),
+            measurements: Measurements::new(),
+            next_hops: Vec::new(),
+            forward_to: Vec::new(),
+            prune_interval_ms: prune_interval_ms as u64,
+            last_pit_prune_time: Timestamp { ms_since_1970: 0 },
+            timed_out: Vec::new(),
+            last_cs_prune_time: Timestamp { ms_since_1970: 0 },
+            cs_entries: 0,
+            cs_bytes: 0,
+            next_cs_entry_id: 0,
+        }
+    }
+
+    // The number of nodes in the name tree, including the root
+    pub fn node_count(&self) -> usize {
+        self.nodes.len() - self.free_nodes.len()
+    }
+
+    // Checks that the nodes, their links, the collision chains and the content store
+    //  counts are consistent and tells what is broken otherwise. Walks everything,
+    //  so it is meant for the tests and the fuzzing harnesses.
+    #[cfg(debug_assertions)]
+    pub fn check_invariants(&self) -> Result<(), &'static str> {
+        let live = |id: NodeId| self.nodes.get(id as usize).and_then(Option::as_ref);
+        if live(ROOT).is_none() {
+            return Err("root node removed");
+        }
+        super::reference::check_fib_invariants(&self.default_routes)?;
+        let mut is_free = vec![false; self.nodes.len()];
+        for id in self.free_nodes.iter() {
+            match self.nodes.get(*id as usize) {
+                Some(None) if !is_free[*id as usize] => is_free[*id as usize] = true,
+                _ => return Err("free node in use or freed twice"),
+            }
+        }
+
+        let (mut cs_entries, mut cs_bytes) = (0, 0);
+        for (id, node) in self.nodes.iter().enumerate() {
+            let Some(node) = node else {
+                if !is_free[id] {
+                    return Err("removed node not freed");
+                }
+                continue;
+            };
+            let id = id as NodeId;
+            super::reference::check_fib_invariants(&node.fib)?;
+            node.pit_normal.check_invariants()?;
+            node.pit_prefix.check_invariants()?;
+            if let Some(entry) = &node.data {
+                cs_entries += 1;
+                cs_bytes += entry.data.len();
+            }
+            for (index, child) in node.children.iter().enumerate() {
+                let child = live(*child).ok_or("removed node among the children")?;
+                if child.parent != id || child.index_in_parent != index {
+                    return Err("child not linked back to its parent");
+                }
+            }
+            if id == ROOT {
+                continue;
+            }
+            if node.is_empty() {
+                return Err("empty node left in the name tree");
+            }
+            if node.hash != hash_component(node.component.as_name_component()) {
+                return Err("node hash does not match its component");
+            }
+            let parent = live(node.parent).ok_or("parent node removed")?;
+            if parent.children.get(node.index_in_parent) != Some(&id) {
+                return Err("node not among the children of its parent");
+            }
+            // Also catches the siblings with the same component, only the first is found
+            if self.find_child(node.parent, node.component.as_name_component()) != Some(id) {
+                return Err("node not found from its parent");
+            }
+        }
+
+        // Each chain has the nodes with its key, and each node is in one chain
+        let mut chained = 0;
+        for (&(parent, hash), &first) in self.index.iter() {
+            let mut next = Some(first);
+            while let Some(id) = next {
+                let node = live(id).ok_or("removed node in a collision chain")?;
+                if node.parent != parent || node.hash != hash {
+                    return Err("node chained under another key");
+                }
+                chained += 1;
+                if chained > self.nodes.len() {
+                    return Err("collision chain loops");
+                }
+                next = node.next_collision;
+            }
+        }
+        if chained != self.node_count() - 1 {
+            return Err("nodes missing from the index or chained twice");
+        }
+
+        if cs_entries != self.cs_entries || cs_bytes != self.cs_bytes {
+            return Err("CS counts do not match the cached packets");
+        }
+        Ok(())
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        // The ids are only handed out for the nodes that are in use
+        self.nodes[id as usize].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.nodes[id as usize].as_mut().unwrap()
+    }
+
+    fn find_child(&self, parent: NodeId, component: NameComponent<'_>) -> Option<NodeId> {
+        let hash = hash_component(component);
+        let mut next = self.index.get(&(parent, hash)).copied();
+        while let Some(id) = next {
+            let node = self.node(id);
+            if node.component.compare_to_name_component(component).is_eq() {
+                return Some(id);
+            }
+            next = node.next_collision;
+        }
+        None
+    }
+
+    fn get_or_insert_child(&mut self, parent: NodeId, component: NameComponent<'_>) -> NodeId {
+        if let Some(id) = self.find_child(parent, component) {
+            return id;
+        }
+        let hash = hash_component(component);
+        let mut node = Node::new(
+            parent,
+            EncodedComponent::from_named_component(component),
+            hash,
+        );
+        let id = match self.free_nodes.pop() {
+            Some(id) => id,
+            None => {
+                self.nodes.push(None);
+                (self.nodes.len() - 1) as NodeId
+            }
+        };
+        node.next_collision = self.index.insert((parent, hash), id);
+        node.index_in_parent = self.node(parent).children.len();
+        self.nodes[id as usize] = Some(node);
+        self.node_mut(parent).children.push(id);
+        id
+    }
+
+    fn find(&self, name: Name<'_>) -> Option<NodeId> {
+        let mut id = ROOT;
+        for component in name.components() {
+            id = self.find_child(id, component)?;
+        }
+        Some(id)
+    }
+
+    fn get_or_insert(&mut self, name: Name<'_>) -> NodeId {
+        self.get_or_insert_under(ROOT, name.components())
+    }
+
+    fn get_or_insert_under<'a>(
+        &mut self,
+        mut id: NodeId,
+        components: impl Iterator<Item = NameComponent<'a>>,
+    ) -> NodeId {
+        for component in components {
+            id = self.get_or_insert_child(id, component);
+        }
+        id
+    }
+
+    // Removes the node if it is empty, and then its ancestors that became empty
+    fn remove_if_empty(&mut self, mut id: NodeId) {
+        while id != ROOT && self.node(id).is_empty() {
+            let Some(node) = self.nodes[id as usize].take() else {
+                return;
+            };
+            let key = (node.parent, node.hash);
+            if self.index.get(&key) == Some(&id) {
+                match node.next_collision {
+                    Some(next) => self.index.insert(key, next),
+                    None => self.index.remove(&key),
+                };
+            } else {
+                let mut previous = self.index.get(&key).copied();
+                while let Some(other) = previous {
+                    let other = self.node_mut(other);
+                    if other.next_collision == Some(id) {
+                        other.next_collision = node.next_collision;
+                        break;
+                    }
+                    previous = other.next_collision;
+                }
+            }
+            let siblings = &mut self.node_mut(node.parent).children;
+            siblings.swap_remove(node.index_in_parent);
+            if let Some(moved) = siblings.get(node.index_in_parent).copied() {
+                self.node_mut(moved).index_in_parent = node.index_in_parent;
+            }
+            self.free_nodes.push(id);
+            id = node.parent;
+        }
+    }
+
+    // Removes all the empty nodes, e.g. after taking a face out of the entries
+    fn remove_empty_nodes(&mut self) {
+        for id in 1..self.nodes.len() as NodeId {
+            if self.nodes[id as usize]
+                .as_ref()
+                .is_some_and(|node| node.is_empty())
+            {
+                self.remove_if_empty(id);
+            }
+        }
+    }
+
+    // Adds the FIB entries along the path of the name in the increasing order of preference,
+    //  the longest matching prefix last. Returns the deepest node on the path and its depth.
+    fn collect_routes(&mut self, name: Name<'_>) -> (NodeId, usize) {
+        let mut id = ROOT;
+        let mut depth = 0;
+        let mut components = name.components();
+        loop {
+            let node = self.nodes[id as usize].as_ref().unwrap();
+            self.face_scratchpad
+                .extend(node.fib.iter().rev().map(|x| (x.cost, x.next_hop)));
+            let Some(component) = components.next() else {
+                return (id, depth);
+            };
+            match self.find_child(id, component) {
+                Some(child) => id = child,
+                None => return (id, depth),
+            }
+            depth += 1;
+        }
+    }
+
+    fn name_of(&self, mut id: NodeId) -> Vec<EncodedComponent> {
+        let mut components = Vec::new();
+        while id != ROOT {
+            let node = self.node(id);
+            components.push(node.component.clone());
+            id = node.parent;
+        }
+        components.reverse();
+        components
+    }
+
+    // Calls "visit" with the name of every node for which "wanted" holds
+    fn visit(&self, wanted: impl Fn(&Node) -> bool, visit: &mut dyn FnMut(Name<'_>, &Node)) {
+        for (id, node) in self.nodes.iter().enumerate() {
+            let Some(node) = node.as_ref().filter(|node| wanted(node)) else {
+                continue;
+            };
+            let owned = self.name_of(id as NodeId);
+            let components: Vec<_> = owned
+                .iter()
+                .map(|component| NameComponent {
+                    typ: component.typ,
+                    bytes: &component.bytes,
+                })
+                .collect();
+            let root = Name::new();
+            visit(root.adding_components(&components), node);
+        }
+    }
+
+    fn satisfy_node(&mut self, id: NodeId, name: Name<'_>, now: Timestamp, exact: bool) {
+        let node = self.nodes[id as usize].as_mut().unwrap();
+        if exact {
+            node.pit_normal.satisfy(
+                name,
+                now,
+                &mut self.dead_nonce_list,
+                &mut self.face_scratchpad,
+            );
+        }
+        node.pit_prefix.satisfy(
+            name,
+            now,
+            &mut self.dead_nonce_list,
+            &mut self.face_scratchpad,
+        );
+    }
+
+    fn take_data_if_usable(&mut self, id: NodeId, must_be_fresh: bool, now: Timestamp) -> bool {
+        let data_cache_duration_ms = self.data_cache_duration_ms;
+        let Some(entry) = self.node_mut(id).data.as_mut() else {
+            return false;
+        };
+        if must_be_fresh && now > entry.freshness_deadline {
+            return false;
+        }
+        entry.removal_deadline = now.adding(data_cache_duration_ms);
+        entry.hits = entry.hits.saturating_add(1);
+        true
+    }
+
+    // The node with the data under the node chosen by the can be prefix selection.
+    // The children are not kept in order, so they are sorted on the way.
+    fn select_data_under(
+        &mut self,
+        id: NodeId,
+        must_be_fresh: bool,
+        now: Timestamp,
+    ) -> Option<NodeId> {
+        let rightmost = self.can_be_prefix_selection == CanBePrefixSelection::Rightmost;
+        let mut children = self.node(id).children.clone();
+        children.sort_by(|a, b| {
+            let b = self.node(*b).component.as_name_component();
+            self.node(*a).component.compare_to_name_component(b)
+        });
+        if rightmost {
+            children.reverse();
+        }
+        for child in children {
+            if !rightmost && self.take_data_if_usable(child, must_be_fresh, now) {
+                return Some(child);
+            }
+            if let Some(found) = self.select_data_under(child, must_be_fresh, now) {
+                return Some(found);
+            }
+            if rightmost && self.take_data_if_usable(child, must_be_fresh, now) {
+                return Some(child);
+            }
+        }
+        None
+    }
+
+    // The node with the cached data that satisfies the name of the node "id"
+    fn find_data(
+        &mut self,
+        id: NodeId,
+        can_be_prefix: bool,
+        must_be_fresh: bool,
+        now: Timestamp,
+    ) -> Option<NodeId> {
+        if self.take_data_if_usable(id, must_be_fresh, now) {
+            Some(id)
+        } else if can_be_prefix {
+            self.select_data_under(id, must_be_fresh, now)
+        } else {
+            // The name was without the digest, so the data is in one of the children,
+            //  preferring the version that stays fresh for the longest
+            let freshest = self
+                .node(id)
+                .children
+                .iter()
+                .filter_map(|child| {
+                    let entry = self.node(*child).data.as_ref()?;
+                    let usable = !must_be_fresh || now <= entry.freshness_deadline;
+                    usable.then_some((entry.freshness_deadline, *child))
+                })
+                .max()
+                .map(|(_, child)| child);
+            freshest.filter(|child| self.take_data_if_usable(*child, must_be_fresh, now))
+        }
+    }
+
+    fn return_faces(&self) -> impl Iterator<Item = FaceToken> + '_ {
+        self.face_scratchpad.iter().map(|x| x.1)
+    }
+}
+
+impl Default for HashedTables {
+    fn default() -> Self {
+        Self::new(10 * 1000, 6 * 1000, 1000)
+    }
+}
+
+impl Fib for HashedTables {
+    fn register_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken, cost: u32) {
+        let id = self.get_or_insert(name_prefix);
+        add_fib_entry(&mut self.node_mut(id).fib, face, cost);
+    }
+
+    fn unregister_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken) -> bool {
+        let Some(id) = self.find(name_prefix) else {
+            return false;
+        };
+        if !remove_fib_entry(&mut self.node_mut(id).fib, face) {
+            return false;
+        }
+        self.remove_if_empty(id);
+        true
+    }
+
+    fn unregister_face_routes(&mut self, face: FaceToken) {
+        self.default_routes.retain(|y| y.next_hop != face);
+        for node in self.nodes.iter_mut().flatten() {
+            node.fib.retain(|y| y.next_hop != face);
+        }
+        self.remove_empty_nodes();
+    }
+
+    fn list_routes(&self, report: &mut dyn FnMut(FibEntryInfo<'_>)) {
+        let mut next_hops = Vec::new();
+        self.visit(|node| !node.fib.is_empty(), &mut |name, node| {
+            next_hops.clear();
+            next_hops.extend(node.fib.iter().map(|x| NextHop {
+                face: x.next_hop,
+                cost: x.cost,
+                congested: false,
+            }));
+            report(FibEntryInfo {
+                name,
+                next_hops: &next_hops,
+                is_default: false,
+            });
+        });
+        report_default_routes(&self.default_routes, report);
+    }
+
+    fn has_route(&self, name: Name<'_>) -> bool {
+        if !self.default_routes.is_empty() {
+            return true;
+        }
+        let mut id = ROOT;
+        let mut components = name.components();
+        loop {
+            if !self.node(id).fib.is_empty() {
+                return true;
+            }
+            let Some(child) = components.next().and_then(|c| self.find_child(id, c)) else {
+                return false;
+            };
+            id = child;
+        }
+    }
+
+    fn register_default_route(&mut self, face: FaceToken, cost: u32) {
+        add_fib_entry(&mut self.default_routes, face, cost);
+    }
+
+    fn unregister_default_route(&mut self, face: FaceToken) -> bool {
+        remove_fib_entry(&mut self.default_routes, face)
+    }
+}
+
+impl Pit for HashedTables {
+    fn register_interest<'s>(
+        &'s mut self,
+        interest: &Interest<'_>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+    ) -> impl Iterator<Item = FaceToken> + use<'s> {
+        let name = interest.name;
+        let forwarding_hint = interest.forwarding_hint;
+        let can_be_prefix = interest.can_be_prefix.is_some();
+        let interest_lifetime = interest.interest_lifetime.map(|x| x.val);
+        let collected_path = self.collected_path.take();
+        if collected_path.is_none() {
+            self.face_scratchpad.clear();
+        }
+
+        if name.component_count() == 0 || self.dead_nonce_list.contains(name, nonce) {
+            self.face_scratchpad.clear();
+            return self.return_faces();
+        }
+
+        // As in the reference tables, the delegations are only used without a route for the name
+        //  and skipped when they only lead back to the downstream face
+        let (deepest, depth) = match collected_path {
+            Some(path) => path,
+            None => self.collect_routes(name),
+        };
+        if self.face_scratchpad.is_empty() {
+            if let Some(forwarding_hint) = forwarding_hint {
+                for delegation in forwarding_hint.delegations() {
+                    self.collect_routes(delegation);
+                    self.face_scratchpad.retain(|(_, face)| *face != reply_to);
+                    if !self.face_scratchpad.is_empty() {
+                        break;
+                    }
+                }
+            }
+        }
+        if self.face_scratchpad.is_empty() {
+            self.face_scratchpad.extend(
+                self.default_routes
+                    .iter()
+                    .rev()
+                    .map(|x| (x.cost, x.next_hop)),
+            );
+        }
+        if self.face_scratchpad.is_empty() {
+            // There are no valid faces so we do not even try to create a PIT
+            return self.return_faces();
+        }
+
+        let deadline = self.interest_lifetimes.deadline(now, interest_lifetime);
+
+        let id = self.get_or_insert_under(deepest, name.components().skip(depth));
+        let node = self.nodes[id as usize].as_mut().unwrap();
+        let pit_entry = if can_be_prefix {
+            &mut node.pit_prefix
+        } else {
+            &mut node.pit_normal
+        };
+        let Some(is_new) = pit_entry.register(
+            name,
+            reply_to,
+            now,
+            deadline,
+            nonce,
+            &mut self.dead_nonce_list,
+        ) else {
+            self.face_scratchpad.clear();
+            return self.return_faces();
+        };
+
+        // The faces were collected in the increasing order of preference
+        self.next_hops.clear();
+        self.next_hops.extend(
+            self.face_scratchpad
+                .iter()
+                .rev()
+                .map(|(cost, face)| NextHop {
+                    face: *face,
+                    cost: *cost,
+                    congested: false,
+                }),
+        );
+        let interest = PendingInterest {
+            name,
+            can_be_prefix,
+            reply_to,
+            now,
+            is_new,
+            transmission_count: pit_entry.transmission_count,
+            latest_transmission_time: pit_entry.latest_transmission_time,
+            pit_entry: PitEntryView::new(pit_entry),
+            has_route: true,
+            suppression: self.retransmission_suppression,
+            parameters: StrategyParameters::default(),
+        };
+
+        self.forward_to.clear();
+        self.strategy.after_receive_interest(
+            &interest,
+            &self.next_hops,
+            &mut self.measurements,
+            &mut self.forward_to,
+        );
+        pit_entry.record_transmission(&self.forward_to, reply_to, nonce, now, deadline);
+
+        self.face_scratchpad.clear();
+        self.face_scratchpad
+            .extend(self.forward_to.iter().map(|face| (0, *face)));
+        self.return_faces()
+    }
+
+    fn add_pit_in_record(
+        &mut self,
+        name: Name<'_>,
+        can_be_prefix: bool,
+        interest_lifetime: Option<u64>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+    ) -> bool {
+        if name.component_count() == 0 {
+            return false;
+        }
+
+        // A dead nonce would be dropped anyway, so there is nothing left to do
+        if self.dead_nonce_list.contains(name, nonce) {
+            return true;
+        }
+
+        let deadline = self.interest_lifetimes.deadline(now, interest_lifetime);
+
+        let Some(id) = self.find(name) else {
+            return false;
+        };
+        let node = self.nodes[id as usize].as_mut().unwrap();
+        let pit_entry = if can_be_prefix {
+            &mut node.pit_prefix
+        } else {
+            &mut node.pit_normal
+        };
+        pit_entry.add_in_record(
+            name,
+            reply_to,
+            now,
+            deadline,
+            nonce,
+            &mut self.dead_nonce_list,
+        )
+    }
+
+    fn satisfy_interests<H>(
+        &mut self,
+        name: Name<'_>,
+        _from: FaceToken,
+        now: Timestamp,
+        digest_computation: &mut H,
+    ) -> impl Iterator<Item = FaceToken>
+    where
+        H: FnMut() -> [u8; 32],
+    {
+        self.face_scratchpad.clear();
+
+        // The "can be prefix" PITs along the way, and then both PITs for the name itself
+        //  and for the full name with the digest
+        let mut id = ROOT;
+        let mut deepest = Some(ROOT);
+        for component in name.components() {
+            self.satisfy_node(id, name, now, false);
+            match self.find_child(id, component) {
+                Some(child) => id = child,
+                None => {
+                    deepest = None;
+                    break;
+                }
+            }
+        }
+        if deepest.is_some() {
+            self.satisfy_node(id, name, now, true);
+            deepest = Some(id);
+            if !self.node(id).children.is_empty() {
+                let digest = digest_computation();
+                let component = NameComponent::implicit_sha256(&digest);
+                if let Some(child) = self.find_child(id, component) {
+                    self.satisfy_node(child, name, now, true);
+                    deepest = Some(child);
+                }
+            }
+        }
+        self.remove_if_empty(deepest.unwrap_or(id));
+
+        // Only want distinct faces
+        self.face_scratchpad.sort();
+        self.face_scratchpad.dedup();
+        self.return_faces()
+    }
+
+    fn unregister_face_records(&mut self, face: FaceToken) {
+        for node in self.nodes.iter_mut().flatten() {
+            node.pit_normal.remove_face_records(face);
+            node.pit_prefix.remove_face_records(face);
+        }
+        self.remove_empty_nodes();
+        self.strategy
+            .after_face_removed(face, &mut self.measurements);
+        self.measurements.remove_face(face);
+    }
+
+    fn prune_pit_if_needed(&mut self, now: Timestamp) {
+        if now < self.last_pit_prune_time.adding(self.prune_interval_ms) {
+            return;
+        }
+        self.last_pit_prune_time = now;
+        self.timed_out.clear();
+
+        // The names are only needed for the dead nonces, so they are rebuilt
+        //  for the entries that have expired in-records
+        for id in 1..self.nodes.len() as NodeId {
+            let Some(node) = self.nodes[id as usize].as_ref() else {
+                continue;
+            };
+            let has_expired = |pit: &PitEntry| pit.has_expired_in_records(now);
+            if !has_expired(&node.pit_normal) && !has_expired(&node.pit_prefix) {
+                continue;
+            }
+            let owned = self.name_of(id);
+            let components: Vec<_> = owned
+                .iter()
+                .map(|component| NameComponent {
+                    typ: component.typ,
+                    bytes: &component.bytes,
+                })
+                .collect();
+            let root = Name::new();
+            let name = root.adding_components(&components);
+
+            let node = self.nodes[id as usize].as_mut().unwrap();
+            for pit in [&mut node.pit_normal, &mut node.pit_prefix] {
+                if pit.pit_in.is_empty() {
+                    continue;
+                }
+                let mut expired = Vec::new();
+                pit.expire_in_records(name, now, &mut self.dead_nonce_list, &mut expired);
+                if !expired.is_empty() {
+                    self.timed_out.push((NameBuf::from(name), expired));
+                }
+                if pit.pit_in.is_empty() {
+                    pit.reset(name, now, &mut self.dead_nonce_list);
+                }
+            }
+        }
+        self.remove_empty_nodes();
+        self.dead_nonce_list.prune(now);
+    }
+
+    fn report_timed_out_interests(&mut self, report: &mut dyn FnMut(Name<'_>, &[FaceToken])) {
+        for (name, faces) in self.timed_out.drain(..) {
+            report(name.as_name(), &faces);
+        }
+    }
+
+    fn list_pit_entries(&self, report: &mut dyn FnMut(PitEntryInfo<'_>)) {
+        let mut in_records = Vec::new();
+        let mut out_records = Vec::new();
+        self.visit(
+            |node| !node.pit_normal.pit_in.is_empty() || !node.pit_prefix.pit_in.is_empty(),
+            &mut |name, node| {
+                for (pit, can_be_prefix) in [(&node.pit_normal, false), (&node.pit_prefix, true)] {
+                    if pit.pit_in.is_empty() {
+                        continue;
+                    }
+                    in_records.clear();
+                    in_records.extend(pit.in_records());
+                    out_records.clear();
+                    out_records.extend(pit.out_records());
+                    report(PitEntryInfo {
+                        name,
+                        can_be_prefix,
+                        in_records: &in_records,
+                        out_records: &out_records,
+                    });
+                }
+            },
+        );
+    }
+
+    fn set_pit_params(&mut self, params: &TableParams) {
+        self.dead_nonce_list
+            .set_duration(params.dead_nonce_duration_ms);
+        self.interest_lifetimes = InterestLifetimes::from_params(params);
+        self.prune_interval_ms = params.prune_interval_ms;
+        self.retransmission_suppression = params.retransmission_suppression;
+    }
+}
+
+// A single walk down the name collects the routes and finds the node for the cached data,
+//  and the interest is registered from where the walk stopped
+impl Tables for HashedTables {
+    fn process_interest<'s, 'a>(
+        &'s mut self,
+        interest: &Interest<'a>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+        satisfy: &mut dyn FnMut(&[u8]),
+    ) -> Option<impl Iterator<Item = FaceToken> + use<'s, 'a>> {
+        let name = interest.name;
+        self.face_scratchpad.clear();
+        let (deepest, depth) = self.collect_routes(name);
+        if depth == name.component_count() {
+            let can_be_prefix = interest.can_be_prefix.is_some();
+            let must_be_fresh = interest.must_be_fresh.is_some();
+            if let Some(found) = self.find_data(deepest, can_be_prefix, must_be_fresh, now) {
+                if let Some(entry) = self.node(found).data.as_ref() {
+                    satisfy(&entry.data);
+                }
+                self.face_scratchpad.clear();
+                return None;
+            }
+        }
+        self.collected_path = Some((deepest, depth));
+        Some(self.register_interest(interest, nonce, reply_to, now))
+    }
+}
+
+impl ContentStore for HashedTables {
+    fn insert_data<'a>(
+        &mut self,
+        name: Name<'a>,
+        digest: [u8; 32],
+        freshness: u64,
+        now: Timestamp,
+        packet: &'a [u8],
+    ) {
+        let parent = self.get_or_insert(name);
+        let id =
+            self.get_or_insert_child(parent, NameComponent::implicit_sha256(digest.as_slice()));
+
+        // As in the reference tables, the stale versions with the same name are replaced
+        let siblings = self.node(parent).children.clone();
+        for sibling in siblings.into_iter().filter(|sibling| *sibling != id) {
+            let node = self.node_mut(sibling);
+            if let Some(entry) = node.data.take_if(|entry| entry.freshness_deadline < now) {
+                self.cs_entries -= 1;
+                self.cs_bytes -= entry.data.len();
+                self.remove_if_empty(sibling);
+            }
+        }
+
+        let cs_entry_id = CsEntryId(self.next_cs_entry_id);
+        let data_cache_duration_ms = self.data_cache_duration_ms;
+        let node = self.node_mut(id);
+        match node.data.as_mut() {
+            Some(entry) => {
+                // The same digest means the same packet
+                debug_assert!(packet == entry.data.as_ref());
+                if now.adding(freshness) > entry.freshness_deadline {
+                    // The data was refreshed, so its popularity is counted anew
+                    entry.freshness_deadline = now.adding(freshness);
+                    entry.hits = 0;
+                    entry.expiry_reported = false;
+                }
+            }
+            None => {
+                node.data = Some(DataEntry {
+                    id: cs_entry_id,
+                    data: Box::from(packet),
+                    freshness_deadline: now.adding(freshness),
+                    removal_deadline: now.adding(data_cache_duration_ms),
+                    hits: 0,
+                    expiry_reported: false,
+                });
+                self.next_cs_entry_id += 1;
+                self.cs_entries += 1;
+                self.cs_bytes += packet.len();
+            }
+        }
+    }
+
+    fn get_data<'a>(
+        &mut self,
+        name: Name<'a>,
+        can_be_prefix: bool,
+        must_be_fresh: bool,
+        now: Timestamp,
+    ) -> Option<&[u8]> {
+        let id = self.find(name)?;
+        let found = self.find_data(id, can_be_prefix, must_be_fresh, now)?;
+        self.node(found)
+            .data
+            .as_ref()
+            .map(|entry| entry.data.as_ref())
+    }
+
+    fn prune_cs_if_needed(&mut self, now: Timestamp) {
+        if now < self.last_cs_prune_time.adding(self.prune_interval_ms) {
+            return;
+        }
+        self.last_cs_prune_time = now;
+        for node in self.nodes.iter_mut().flatten() {
+            if let Some(entry) = &node.data {
+                if entry.removal_deadline < now {
+                    self.cs_entries -= 1;
+                    self.cs_bytes -= entry.data.len();
+                    node.data = None;
+                }
+            }
+        }
+        self.remove_empty_nodes();
+    }
+
+    fn content_store_usage(&self) -> ContentStoreUsage {
+        ContentStoreUsage {
+            entries: self.cs_entries,
+            bytes: self.cs_bytes,
+        }
+    }
+
+    fn list_cs_entries(&self, report: &mut dyn FnMut(CsEntryInfo<'_>)) {
+        self.visit(|node| node.data.is_some(), &mut |name, node| {
+            if let Some(data) = &node.data {
+                report(CsEntryInfo {
+                    name,
+                    bytes: data.data.len(),
+                    fresh_until: data.freshness_deadline,
+                    expiry: data.removal_deadline,
+                });
+            }
+        });
+    }
+
+    fn set_cs_params(&mut self, params: &TableParams) {
+        self.data_cache_duration_ms = params.data_cache_duration_ms;
+        self.prune_interval_ms = params.prune_interval_ms;
+        self.can_be_prefix_selection = params.can_be_prefix_selection;
+    }
+
+    fn erase_data(&mut self, prefix: Name<'_>, limit: Option<usize>) -> usize {
+        let Some(top) = self.find(prefix) else {
+            return 0;
+        };
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut with_data = Vec::new();
+        let mut to_visit = vec![top];
+        while let Some(id) = to_visit.pop() {
+            if with_data.len() == limit {
+                break;
+            }
+            let node = self.node(id);
+            if node.data.is_some() {
+                with_data.push(id);
+            }
+            to_visit.extend_from_slice(&node.children);
+        }
+        for id in with_data.iter() {
+            if let Some(entry) = self.node_mut(*id).data.take() {
+                self.cs_entries -= 1;
+                self.cs_bytes -= entry.data.len();
+            }
+            self.remove_if_empty(*id);
+        }
+        with_data.len()
+    }
+}
+
+fn hash_component(component: NameComponent<'_>) -> u64 {
+    let mut hash = component.typ.get() as u64;
+    for chunk in component.bytes.chunks(8) {
+        let mut arr = [0u8; 8];
+        arr[..chunk.len()].copy_from_slice(chunk);
+        hash = DeadNonceList::mix(hash ^ u64::from_le_bytes(arr));
+    }
+    DeadNonceList::mix(hash ^ component.bytes.len() as u64)
+}
+
+// The keys already hold a good hash, so it only needs to be combined with the parent
+#[derive(Default)]
+struct KeyHasher {
+    hash: u64,
+}
+
+impl Hasher for KeyHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_u64(*byte as u64);
+        }
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.hash = self.hash.rotate_left(5) ^ i;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use alloc::vec::Vec;
+
+    use crate::{
+        clock::Timestamp,
+        forwarder::FaceToken,
+        name::{Name, NameComponent},
+        nonce::{NonceSource, SplitMixNonces},
+        packet::{Interest, InterestLifetime, MustBeFresh},
+        tables::{
+            hashed::HashedTables, reference::ReferenceTables, sharded::ShardedTables,
+            CanBePrefixSelection, ContentStore, Fib, Pit, TableParams, Tables,
+        },
+    };
+
+    #[test]
+    fn test_hashed_tables() {
+        let mut tables = HashedTables::default();
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let video = &[NameComponent::generic(b"video")];
+        let clip = &[video[0], NameComponent::generic(b"clip")];
+
+        tables.register_prefix(root.adding_components(video), FaceToken(1), 10);
+        tables.register_prefix(root.adding_components(clip), FaceToken(2), 20);
+        let name = root.adding_components(clip);
+
+        // The longest matching prefix goes first
+        let faces: Vec<_> = tables
+            .register_interest(
+                &Interest::new(name, false, [1; 4]),
+                [1; 4],
+                FaceToken(0),
+                now,
+            )
+            .collect();
+        assert_eq!(faces, [FaceToken(2)]);
+        // The same nonce from elsewhere is a loop
+        let faces = tables.register_interest(
+            &Interest::new(name, false, [1; 4]),
+            [1; 4],
+            FaceToken(3),
+            now,
+        );
+        assert_eq!(faces.count(), 0);
+
+        let faces: Vec<_> = tables
+            .satisfy_interests(name, FaceToken(2), now, &mut || [7; 32])
+            .collect();
+        assert_eq!(faces, [FaceToken(0), FaceToken(3)]);
+
+        tables.insert_data(name, [7; 32], 1000, now, &[42]);
+        assert_eq!(
+            tables.get_data(name, false, true, now),
+            Some([42].as_slice())
+        );
+        let prefix = root.adding_components(video);
+        assert!(tables.get_data(prefix, false, false, now).is_none());
+        assert_eq!(
+            tables.get_data(prefix, true, false, now),
+            Some([42].as_slice())
+        );
+        assert_eq!(tables.content_store_usage().entries, 1);
+
+        // A newer version replaces the stale one
+        let later = now.adding(2000);
+        tables.insert_data(name, [8; 32], 1000, later, &[43]);
+        assert_eq!(tables.content_store_usage().entries, 1);
+        assert_eq!(
+            tables.get_data(name, false, true, later),
+            Some([43].as_slice())
+        );
+
+        // Erasing the namespace takes the cached packets under it
+        assert_eq!(
+            tables.erase_data(root.adding_components(&clip[1..]), None),
+            0
+        );
+        assert_eq!(tables.erase_data(prefix, None), 1);
+        assert_eq!(tables.content_store_usage().entries, 0);
+        assert_eq!(tables.check_invariants(), Ok(()));
+
+        // Once everything is gone only the root is left
+        let later = now.adding(60 * 1000);
+        tables.prune_cs_if_needed(later);
+        tables.prune_pit_if_needed(later);
+        tables.unregister_face_routes(FaceToken(1));
+        assert!(tables.unregister_prefix(name, FaceToken(2)));
+        assert_eq!(tables.node_count(), 1);
+        assert_eq!(tables.content_store_usage().bytes, 0);
+        assert_eq!(tables.check_invariants(), Ok(()));
+    }
+
+    fn check_default_route<T: Tables>(mut tables: T) {
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let video = &[NameComponent::generic(b"video")];
+        tables.register_prefix(root.adding_components(video), FaceToken(1), 10);
+        tables.register_default_route(FaceToken(5), 30);
+        tables.register_default_route(FaceToken(6), 20);
+
+        let forward = |tables: &mut T, name: &[u8], nonce: u8| -> Vec<FaceToken> {
+            let comp = &[NameComponent::generic(name)];
+            let name = root.adding_components(comp);
+            tables
+                .register_interest(
+                    &Interest::new(name, false, [nonce; 4]),
+                    [nonce; 4],
+                    FaceToken(0),
+                    now,
+                )
+                .collect()
+        };
+        // The cheapest default route is only used without any other route
+        assert_eq!(forward(&mut tables, b"video", 1), [FaceToken(1)]);
+        assert_eq!(forward(&mut tables, b"audio", 2), [FaceToken(6)]);
+
+        let mut listed = Vec::new();
+        tables.list_routes(&mut |entry| {
+            let faces: Vec<_> = entry.next_hops.iter().map(|x| x.face).collect();
+            listed.push((entry.name.component_count(), entry.is_default, faces));
+        });
+        assert_eq!(
+            listed,
+            [
+                (1, false, vec![FaceToken(1)]),
+                (0, true, vec![FaceToken(6), FaceToken(5)])
+            ]
+        );
+
+        tables.unregister_face_routes(FaceToken(6));
+        assert_eq!(forward(&mut tables, b"photo", 3), [FaceToken(5)]);
+        assert!(tables.unregister_default_route(FaceToken(5)));
+        assert!(!tables.unregister_default_route(FaceToken(5)));
+        assert!(forward(&mut tables, b"text", 4).is_empty());
+    }
+
+    #[test]
+    fn test_default_route() {
+        check_default_route(ReferenceTables::default());
+        check_default_route(HashedTables::default());
+    }
+
+    fn check_process_interest<T: Tables>(tables: &mut T) {
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"a"), NameComponent::generic(b"b")];
+        let name = root.adding_components(comp);
+        tables.register_prefix(root.adding_components(&comp[..1]), FaceToken(1), 0);
+
+        let mut cached = Vec::new();
+        let mut process = |tables: &mut T, interest: &Interest, from: u32, now: Timestamp| {
+            cached.clear();
+            let mut satisfy = |packet: &[u8]| cached.extend_from_slice(packet);
+            let nonce = [from as u8; 4];
+            let faces: Option<Vec<_>> = tables
+                .process_interest(interest, nonce, FaceToken(from), now, &mut satisfy)
+                .map(|faces| faces.collect());
+            (faces, cached.clone())
+        };
+
+        let mut interest = Interest::new(name, false, [0; 4]);
+        assert_eq!(
+            process(tables, &interest, 2, now),
+            (Some(vec![FaceToken(1)]), vec![])
+        );
+        let faces: Vec<_> = tables
+            .satisfy_interests(name, FaceToken(1), now, &mut || [7; 32])
+            .collect();
+        assert_eq!(faces, [FaceToken(2)]);
+        tables.insert_data(name, [7; 32], 100, now, &[42]);
+
+        // Satisfied from the cache until the data is stale
+        assert_eq!(process(tables, &interest, 3, now), (None, vec![42]));
+        interest.must_be_fresh = Some(MustBeFresh {});
+        let later = now.adding(500);
+        assert_eq!(
+            process(tables, &interest, 4, later),
+            (Some(vec![FaceToken(1)]), vec![])
+        );
+    }
+
+    #[test]
+    fn test_process_interest() {
+        check_process_interest(&mut ReferenceTables::default());
+        let mut tables = HashedTables::default();
+        check_process_interest(&mut tables);
+        assert_eq!(tables.check_invariants(), Ok(()));
+        check_process_interest(&mut ShardedTables::new(4, HashedTables::default));
+    }
+
+    // Takes one of the operations on a name of up to two components, picked by "random"
+    fn churn_step<T: Tables>(tables: &mut T, random: u32, now: Timestamp) {
+        let root = Name::new();
+        let first = [b"a", b"b", b"c"][random as usize % 3];
+        let second = [(random >> 4) as u8 % 4];
+        let comp = [
+            NameComponent::generic(first),
+            NameComponent::generic(&second),
+        ];
+        let name = root.adding_components(&comp[..(random >> 8) as usize % 3]);
+        let face = FaceToken((random >> 12) % 4);
+        let nonce = random.to_be_bytes();
+        match (random >> 16) % 8 {
+            0 => tables.register_prefix(name, face, random >> 28),
+            1 => {
+                tables.unregister_prefix(name, face);
+            }
+            2 | 3 => {
+                let can_be_prefix = random >> 20 & 1 == 0;
+                let lifetime = Some((random >> 21) as u64 % 1000);
+                let _ = tables
+                    .register_interest(
+                        &Interest {
+                            interest_lifetime: lifetime.map(|val| InterestLifetime { val }),
+                            ..Interest::new(name, can_be_prefix, nonce)
+                        },
+                        nonce,
+                        face,
+                        now,
+                    )
+                    .count();
+            }
+            4 => {
+                let _ = tables
+                    .satisfy_interests(name, face, now, &mut || [second[0]; 32])
+                    .count();
+            }
+            5 => tables.insert_data(name, [second[0]; 32], 500, now, &second),
+            6 => tables.prune_if_needed(now),
+            _ => tables.unregister_face(face),
+        }
+    }
+
+    #[test]
+    fn test_invariants_under_churn() {
+        let mut reference = ReferenceTables::default();
+        let mut hashed = HashedTables::default();
+        let mut nonces = SplitMixNonces::new(7);
+        let mut now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        for _ in 0..3000 {
+            let random = u32::from_be_bytes(nonces.next_nonce());
+            now = now.adding(random as u64 % 50);
+            churn_step(&mut reference, random, now);
+            churn_step(&mut hashed, random, now);
+            assert_eq!(reference.check_invariants(), Ok(()));
+            assert_eq!(hashed.check_invariants(), Ok(()));
+        }
+    }
+
+    // Caches /v/1, /v/2 and /v/10, each with its version as the packet, and gets /v
+    //  with can be prefix
+    fn selected_version<T: Tables>(mut tables: T, selection: CanBePrefixSelection) -> Vec<u8> {
+        let params = TableParams {
+            can_be_prefix_selection: selection,
+            ..TableParams::default()
+        };
+        tables.set_params(&params);
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let v = &[NameComponent::generic(b"v")];
+        for version in [b"2".as_slice(), b"10", b"1"] {
+            let comp = &[v[0], NameComponent::generic(version)];
+            let name = root.adding_components(comp);
+            tables.insert_data(
+                name,
+                [version.len() as u8 + version[0]; 32],
+                1000,
+                now,
+                version,
+            );
+        }
+        let prefix = root.adding_components(v);
+        tables.get_data(prefix, true, false, now).unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_can_be_prefix_selection() {
+        // In the canonical order the shorter components go first, so 10 is the last
+        let leftmost = CanBePrefixSelection::Leftmost;
+        let rightmost = CanBePrefixSelection::Rightmost;
+        assert_eq!(selected_version(ReferenceTables::default(), leftmost), b"1");
+        assert_eq!(selected_version(HashedTables::default(), leftmost), b"1");
+        assert_eq!(
+            selected_version(ReferenceTables::default(), rightmost),
+            b"10"
+        );
+        assert_eq!(selected_version(HashedTables::default(), rightmost), b"10");
+    }
+
+    // Registers /app and the prefixes /app/<i>, and then forwards and satisfies the interests
+    //  for the names next to them, so that the PIT entries come and go among many siblings
+    fn forward_under_wide_fib<T: Tables>(mut tables: T, prefix_count: u32) -> Duration {
+        let root = Name::new();
+        let app = [NameComponent::generic(b"app")];
+        tables.register_prefix(root.adding_components(&app), FaceToken(0), 0);
+        let ids: Vec<[u8; 4]> = (0..prefix_count * 2).map(|i| i.to_be_bytes()).collect();
+        let (prefix_ids, interest_ids) = ids.split_at(prefix_count as usize);
+        for (i, id) in prefix_ids.iter().enumerate() {
+            let prefix = [app[0], NameComponent::generic(id)];
+            tables.register_prefix(root.adding_components(&prefix), FaceToken(i as u32 % 16), 0);
+        }
+
+        let start = Instant::now();
+        for round in 0..20u32 {
+            // The dead nonces of the previous rounds are forgotten by then
+            let now = Timestamp {
+                ms_since_1970: 1000 + round as u64 * 60 * 1000,
+            };
+            for (i, id) in interest_ids.iter().enumerate() {
+                let components = [app[0], NameComponent::generic(id)];
+                let name = root.adding_components(&components);
+                let nonce = (round * prefix_count + i as u32).to_be_bytes();
+                let forwarded = tables
+                    .register_interest(
+                        &Interest::new(name, false, nonce),
+                        nonce,
+                        FaceToken(100),
+                        now,
+                    )
+                    .count();
+                assert_eq!(forwarded, 1);
+                let satisfied = tables
+                    .satisfy_interests(name, FaceToken(0), now, &mut || [0; 32])
+                    .count();
+                assert_eq!(satisfied, 1);
+            }
+        }
+        start.elapsed() / (20 * prefix_count)
+    }
+
+    // Run with "cargo test --release bench_wide_fib -- --ignored --nocapture"
+    #[test]
+    #[ignore]
+    fn bench_wide_fib() {
+        for prefix_count in [100, 1_000, 10_000] {
+            let reference = forward_under_wide_fib(ReferenceTables::default(), prefix_count);
+            let hashed = forward_under_wide_fib(HashedTables::default(), prefix_count);
+            std::println!(
+                "{prefix_count} prefixes: {reference:?} with the reference tables, {hashed:?} with the hashed ones per packet"
+            );
+        }
+    }
+}
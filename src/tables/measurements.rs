@@ -0,0 +1,252 @@
+use core::any::Any;
+
+use alloc::{boxed::Box, collections::btree_map::BTreeMap, vec::Vec};
+
+use crate::{
+    clock::Timestamp,
+    forwarder::FaceToken,
+    name::{Name, NameBuf},
+};
+
+// What the strategies have learned about the namespaces, e.g. how fast each face
+//  answers. The entries live for as long as the strategies keep using them, and are
+//  removed once their lifetime lapses when the tables are pruned.
+#[derive(Default)]
+pub struct Measurements {
+    entries: BTreeMap<NameBuf, MeasurementsEntry>,
+}
+
+impl Measurements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, namespace: Name<'_>) -> Option<&MeasurementsEntry> {
+        self.entries.get(&NameBuf::from(namespace))
+    }
+
+    pub fn get_mut(&mut self, namespace: Name<'_>) -> Option<&mut MeasurementsEntry> {
+        self.entries.get_mut(&NameBuf::from(namespace))
+    }
+
+    // Gets or creates the entry, which then lives for at least "lifetime_ms"
+    pub fn entry(
+        &mut self,
+        namespace: Name<'_>,
+        now: Timestamp,
+        lifetime_ms: u64,
+    ) -> &mut MeasurementsEntry {
+        let entry = self
+            .entries
+            .entry(NameBuf::from(namespace))
+            .or_insert_with(|| MeasurementsEntry {
+                expiry: now,
+                faces: Vec::new(),
+                state: None,
+            });
+        entry.extend_lifetime(now, lifetime_ms);
+        entry
+    }
+
+    // The entry for the longest namespace that is a prefix of the name, if any
+    pub fn longest_prefix_match_mut(&mut self, name: Name<'_>) -> Option<&mut MeasurementsEntry> {
+        let namespace = (0..=name.component_count())
+            .rev()
+            .map(|count| NameBuf::from(name.prefix(count)))
+            .find(|namespace| self.entries.contains_key(namespace))?;
+        self.entries.get_mut(&namespace)
+    }
+
+    pub fn remove(&mut self, namespace: Name<'_>) -> bool {
+        self.entries.remove(&NameBuf::from(namespace)).is_some()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut MeasurementsEntry> + '_ {
+        self.entries.values_mut()
+    }
+
+    // Forgets how the face did in every namespace, e.g. once it is gone and its token
+    //  may be given to another face. The state of the strategies is theirs to clean up
+    //  (see Strategy::after_face_removed).
+    pub fn remove_face(&mut self, face: FaceToken) {
+        for entry in self.entries.values_mut() {
+            entry.faces.retain(|measurements| measurements.face != face);
+        }
+    }
+
+    // Removes the entries whose lifetime lapsed
+    pub fn prune(&mut self, now: Timestamp) {
+        self.entries.retain(|_, entry| now < entry.expiry);
+    }
+}
+
+pub struct MeasurementsEntry {
+    expiry: Timestamp,
+    faces: Vec<FaceMeasurements>,
+    // Whatever else the strategy wants to keep for the namespace, which can be sent
+    //  along with the tables to another thread (see ShardedTables)
+    state: Option<Box<dyn Any + Send>>,
+}
+
+impl MeasurementsEntry {
+    pub fn expiry(&self) -> Timestamp {
+        self.expiry
+    }
+
+    // Never shortens the lifetime
+    pub fn extend_lifetime(&mut self, now: Timestamp, lifetime_ms: u64) {
+        self.expiry = self.expiry.max(now.adding(lifetime_ms));
+    }
+
+    pub fn face(&self, face: FaceToken) -> Option<&FaceMeasurements> {
+        self.faces
+            .iter()
+            .find(|measurements| measurements.face == face)
+    }
+
+    // Creates the measurements of the face if there are none yet
+    pub fn face_mut(&mut self, face: FaceToken) -> &mut FaceMeasurements {
+        let index = match self.faces.iter().position(|m| m.face == face) {
+            Some(index) => index,
+            None => {
+                self.faces.push(FaceMeasurements::new(face));
+                self.faces.len() - 1
+            }
+        };
+        &mut self.faces[index]
+    }
+
+    pub fn faces(&self) -> impl Iterator<Item = &FaceMeasurements> + '_ {
+        self.faces.iter()
+    }
+
+    // The state of the given type, created with its default value if the entry has none
+    //  or has the state of another type, e.g. left by another strategy
+    pub fn state_mut<S: Any + Send + Default>(&mut self) -> &mut S {
+        if !self.state.as_ref().is_some_and(|state| state.is::<S>()) {
+            self.state = Some(Box::new(S::default()));
+        }
+        // Was just set otherwise
+        self.state
+            .as_mut()
+            .and_then(|state| state.downcast_mut::<S>())
+            .unwrap()
+    }
+
+    pub fn state<S: Any>(&self) -> Option<&S> {
+        self.state.as_ref()?.downcast_ref::<S>()
+    }
+}
+
+// How a face did in a namespace
+#[derive(Clone, Debug)]
+pub struct FaceMeasurements {
+    pub face: FaceToken,
+    // Smoothed as in TCP (RFC 6298), none until the first sample
+    pub srtt_ms: Option<u64>,
+    pub rttvar_ms: u64,
+    pub successes: u64,
+    pub failures: u64,
+    // Failures since the last success
+    pub consecutive_failures: u32,
+}
+
+impl FaceMeasurements {
+    pub fn new(face: FaceToken) -> Self {
+        Self {
+            face,
+            srtt_ms: None,
+            rttvar_ms: 0,
+            successes: 0,
+            failures: 0,
+            consecutive_failures: 0,
+        }
+    }
+
+    // The face brought the data after "rtt_ms"
+    pub fn add_rtt_sample(&mut self, rtt_ms: u64) {
+        match self.srtt_ms {
+            Some(srtt_ms) => {
+                self.rttvar_ms = (self.rttvar_ms * 3 + srtt_ms.abs_diff(rtt_ms)) / 4;
+                self.srtt_ms = Some((srtt_ms * 7 + rtt_ms) / 8);
+            }
+            None => {
+                self.srtt_ms = Some(rtt_ms);
+                self.rttvar_ms = rtt_ms / 2;
+            }
+        }
+        self.successes += 1;
+        self.consecutive_failures = 0;
+    }
+
+    // The interest timed out or was nacked on the face
+    pub fn add_failure(&mut self) {
+        self.failures += 1;
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clock::Timestamp,
+        forwarder::FaceToken,
+        name::{Name, NameComponent},
+        tables::measurements::Measurements,
+    };
+
+    #[derive(Default)]
+    struct Counter {
+        count: u32,
+    }
+
+    #[test]
+    fn test_measurements() {
+        let mut measurements = Measurements::new();
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let video = &[NameComponent::generic(b"video")];
+        let clip = &[video[0], NameComponent::generic(b"clip")];
+
+        let entry = measurements.entry(root.adding_components(video), at(1000), 100);
+        entry.face_mut(FaceToken(1)).add_rtt_sample(40);
+        entry.face_mut(FaceToken(1)).add_rtt_sample(80);
+        entry.face_mut(FaceToken(2)).add_failure();
+        entry.state_mut::<Counter>().count += 1;
+        entry.state_mut::<Counter>().count += 1;
+
+        let entry = measurements
+            .longest_prefix_match_mut(root.adding_components(clip))
+            .unwrap();
+        let face = entry.face(FaceToken(1)).unwrap();
+        assert_eq!(face.srtt_ms, Some(45));
+        assert_eq!(face.rttvar_ms, 25);
+        assert_eq!(face.successes, 2);
+        assert_eq!(entry.face(FaceToken(2)).unwrap().consecutive_failures, 1);
+        measurements.remove_face(FaceToken(2));
+        let entry = measurements
+            .longest_prefix_match_mut(root.adding_components(clip))
+            .unwrap();
+        assert!(entry.face(FaceToken(2)).is_none());
+        assert_eq!(entry.faces().count(), 1);
+        assert_eq!(entry.state::<Counter>().unwrap().count, 2);
+        // The state of another type replaces it
+        assert_eq!(entry.state_mut::<u64>(), &0);
+        assert!(entry.state::<Counter>().is_none());
+
+        // Using the entry again keeps it around for longer
+        measurements.entry(root.adding_components(video), at(1050), 100);
+        measurements.prune(at(1120));
+        assert_eq!(measurements.len(), 1);
+        measurements.prune(at(1150));
+        assert!(measurements.is_empty());
+    }
+}
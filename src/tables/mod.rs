@@ -1,19 +1,148 @@
+#[cfg(feature = "std")]
+pub mod disk;
+
+#[cfg(feature = "std")]
+pub mod hashed;
+
+pub mod measurements;
+
+pub mod policy;
+
+pub mod quota;
+
+pub mod ranking;
+
 pub mod reference;
 
-use crate::{clock::Timestamp, forwarder::FaceToken, name::Name};
+pub mod rib;
+
+#[cfg(feature = "std")]
+pub mod sharded;
+
+pub mod strategy;
+
+use crate::{
+    clock::Timestamp,
+    face::lp::NackReason,
+    forwarder::FaceToken,
+    name::Name,
+    packet::Interest,
+    tables::{
+        quota::FaceLimits,
+        strategy::{NextHop, RetransmissionSuppression},
+    },
+};
 
-pub trait Tables {
-    // FIB methods
+// The forwarder keeps its state in three tables, each behind its own trait, so that
+//  they can come from different implementations (see CombinedTables):
+//  - The FIB, which says where the interests for a name prefix can be forwarded
+//  - The PIT, which keeps the pending interests and makes the forwarding decisions
+//  - The content store, which caches the data
+// The PIT needs the routes to decide, so it is usually implemented together with the FIB.
+
+pub trait Fib {
     // Interests that have "name_prefix" will be forwarded to "face", possibly using "cost"
     //  for prioritisation. Call this again to update the cost.
     fn register_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken, cost: u32);
     // Remove this prefix registration.
     fn unregister_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken) -> bool;
 
-    // PIT methods
-    // Registers a newly-arrived interest and uses a forwarding strategy to determine the 
-    //  faces to which this interest should be forwarded, if any.
-    fn register_interest(
+    // Removes the face from all FIB entries
+    fn unregister_face_routes(&mut self, face: FaceToken);
+
+    // Reports every FIB entry, e.g. for the status datasets.
+    // Implementations that cannot enumerate their entries report nothing.
+    fn list_routes(&self, _report: &mut dyn FnMut(FibEntryInfo<'_>)) {}
+
+    // Whether the name or any of its prefixes has a route, e.g. to tell the interests
+    //  that had nowhere to go from the ones the strategy held back.
+    // Implementations that cannot tell assume there is one.
+    fn has_route(&self, _name: Name<'_>) -> bool {
+        true
+    }
+
+    // The default routes take the interests that no other route matches, e.g. so that an
+    //  edge node sends everything else upstream. Unlike the routes for the empty prefix,
+    //  they are not among the next hops of the names that have routes of their own.
+    // Implementations without default routes ignore them.
+    fn register_default_route(&mut self, _face: FaceToken, _cost: u32) {}
+    fn unregister_default_route(&mut self, _face: FaceToken) -> bool {
+        false
+    }
+}
+
+// The next hops are ordered by cost
+pub struct FibEntryInfo<'a> {
+    pub name: Name<'a>,
+    pub next_hops: &'a [NextHop],
+    // The default routes are reported with the empty name and this set
+    pub is_default: bool,
+}
+
+pub struct PitEntryInfo<'a> {
+    pub name: Name<'a>,
+    pub can_be_prefix: bool,
+    pub in_records: &'a [PitInRecordInfo],
+    pub out_records: &'a [PitOutRecordInfo],
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PitInRecordInfo {
+    pub face: FaceToken,
+    pub expiry: Timestamp,
+}
+
+// An upstream the interest was sent to
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PitOutRecordInfo {
+    pub face: FaceToken,
+    pub expiry: Timestamp,
+    // The reason of the Nack if the upstream sent one for the latest interest
+    pub nack_reason: Option<NackReason>,
+}
+
+// What the PIT made of a Nack from an upstream (see Pit::process_nack)
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum NackOutcome {
+    // The interest with the nonce was not sent to the upstream, or it was satisfied
+    //  or expired meanwhile
+    Unmatched,
+    // The interest goes to the returned next hops, which have not failed yet
+    Retried,
+    // Nothing else to try, but the other upstreams may still answer
+    Pending,
+    // The pending interest was removed and the Nack goes to the returned downstreams
+    AllUpstreamsFailed,
+}
+
+pub struct CsEntryInfo<'a> {
+    // The full name, with the implicit digest
+    pub name: Name<'a>,
+    pub bytes: usize,
+    pub fresh_until: Timestamp,
+    // When the data is removed unless it is used again
+    pub expiry: Timestamp,
+}
+
+pub trait Pit {
+    // Registers a newly-arrived interest, forwarded with the nonce, and uses a forwarding
+    //  strategy to determine the faces to which this interest should be forwarded, if any.
+    // The routes for the delegations in the forwarding hint are used only if there
+    //  is no route for the name itself, skipping the delegations whose routes would
+    //  only send the interest back to "reply_to".
+    fn register_interest<'s>(
+        &'s mut self,
+        interest: &Interest<'_>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+    ) -> impl Iterator<Item = FaceToken> + use<'s, Self>;
+
+    // Adds the interest to the in-records of an already existing PIT entry without
+    //  making any forwarding decision, e.g. for an identical interest that arrived
+    //  from another face right after the first one was forwarded.
+    // Returns false if there is no PIT entry to add to.
+    fn add_pit_in_record(
         &mut self,
         name: Name<'_>,
         can_be_prefix: bool,
@@ -21,19 +150,147 @@ pub trait Tables {
         nonce: [u8; 4],
         reply_to: FaceToken,
         now: Timestamp,
-    ) -> impl Iterator<Item = FaceToken>;
+    ) -> bool;
 
     // Checks if the newly-arriving data satisfies any of the interests registered in the PIT
     //  and returns all faces the data packet should be sent to.
     fn satisfy_interests<H>(
         &mut self,
         name: Name<'_>,
+        from: FaceToken,
         now: Timestamp,
         digest_computation: &mut H,
-    ) -> impl Iterator<Item = FaceToken> where H: FnMut()->[u8; 32];
+    ) -> impl Iterator<Item = FaceToken>
+    where
+        H: FnMut() -> [u8; 32];
+
+    // The upstream face could not satisfy the interest for the name,
+    //  which is of interest to the forwarding strategy
+    fn report_nack(&mut self, _name: Name<'_>, _from: FaceToken, _now: Timestamp) {}
+
+    // Matches the Nack to the out-record of the upstream by the nonce of the interest it
+    //  carries, reports it to the strategy and lets it pick among the next hops the
+    //  interest was not sent to yet.
+    // The tables that do not keep track of the upstreams only report it.
+    fn process_nack(
+        &mut self,
+        name: Name<'_>,
+        _can_be_prefix: bool,
+        _nonce: [u8; 4],
+        _reason: NackReason,
+        from: FaceToken,
+        now: Timestamp,
+    ) -> (NackOutcome, impl Iterator<Item = FaceToken>) {
+        self.report_nack(name, from, now);
+        (NackOutcome::Unmatched, core::iter::empty())
+    }
 
-    // CS methods
-    // Inserts the data packet into the cache 
+    // Tells about a new face of the forwarder, e.g. for the strategies that
+    //  flood the interests on all the faces
+    fn register_face(&mut self, _face: FaceToken) {}
+
+    // Removes the face from all PIT entries
+    fn unregister_face_records(&mut self, face: FaceToken);
+
+    // Removes the stale PIT entries
+    fn prune_pit_if_needed(&mut self, now: Timestamp);
+
+    // Reports the names whose in-records expired unsatisfied during the last prune,
+    //  with the faces that were waiting for the data
+    fn report_timed_out_interests(&mut self, _report: &mut dyn FnMut(Name<'_>, &[FaceToken])) {}
+
+    // Reports every PIT entry that has in-records
+    fn list_pit_entries(&self, _report: &mut dyn FnMut(PitEntryInfo<'_>)) {}
+
+    // Whether the last interest given to register_interest or add_pit_in_record was
+    //  rejected for going over the limits of its face (see FaceLimits)
+    fn interest_over_quota(&self) -> bool {
+        false
+    }
+
+    // Takes the parameters that concern the PIT and the dead nonces
+    fn set_pit_params(&mut self, _params: &TableParams) {}
+
+    // With a lifetime, the interests without any route are flooded on all the faces
+    //  whatever the strategy, and a route is learned from the data that answers them,
+    //  which lapses after the lifetime unless learned again. None turns it off.
+    // The implementations that cannot flood ignore it.
+    fn set_flood_without_route(&mut self, _route_lifetime_ms: Option<u64>) {}
+
+    // Passes the parameters to the strategy of the names under the prefix, see
+    //  StrategyChoice::set_parameters, and None removes those of the prefix.
+    // Returns false for the implementations without a strategy choice, which ignore them.
+    fn set_strategy_parameters(
+        &mut self,
+        _prefix: Name<'_>,
+        _parameters: Option<Name<'_>>,
+    ) -> bool {
+        false
+    }
+
+    // Tells whether the packets queued for the face wait too long, which the strategies
+    //  see in the next hops so that they can send the interests elsewhere.
+    // The implementations that do not pass it to the strategies ignore it.
+    fn set_face_congested(&mut self, _face: FaceToken, _congested: bool) {}
+}
+
+// The timing of the tables, which can be changed while they are in use (see Tables::set_params).
+// The implementations ignore the parameters they have no use for.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TableParams {
+    // How long the data is kept after it was last used
+    pub data_cache_duration_ms: u64,
+    // How long the nonces of the satisfied and expired interests are remembered
+    pub dead_nonce_duration_ms: u64,
+    // The lifetime of the interests that come without one
+    pub default_interest_lifetime_ms: u64,
+    // The lifetimes of the interests are clamped to these in the PIT, so that a huge
+    //  lifetime cannot keep an entry there for hours. There are no limits by default.
+    pub min_interest_lifetime_ms: u64,
+    pub max_interest_lifetime_ms: u64,
+    pub prune_interval_ms: u64,
+    pub retransmission_suppression: RetransmissionSuppression,
+    pub can_be_prefix_selection: CanBePrefixSelection,
+    pub face_limits: FaceLimits,
+}
+
+impl Default for TableParams {
+    fn default() -> Self {
+        Self {
+            data_cache_duration_ms: 10 * 1000,
+            dead_nonce_duration_ms: 6 * 1000,
+            default_interest_lifetime_ms: 4000,
+            min_interest_lifetime_ms: 0,
+            max_interest_lifetime_ms: u64::MAX,
+            prune_interval_ms: 1000,
+            retransmission_suppression: RetransmissionSuppression::default(),
+            can_be_prefix_selection: CanBePrefixSelection::default(),
+            face_limits: FaceLimits::default(),
+        }
+    }
+}
+
+// Which of the cached packets under the name satisfies an interest that can be prefix,
+//  going by the canonical order of their full names (by type, then length, then bytes
+//  of the components, with a name before the names under it).
+// With the version numbers in the names, the leftmost is the oldest version and the
+//  rightmost the latest, which is what version discovery looks for.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum CanBePrefixSelection {
+    #[default]
+    Leftmost,
+    Rightmost,
+}
+
+// How much the content store holds, counting the whole encoded packets
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ContentStoreUsage {
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+pub trait ContentStore {
+    // Inserts the data packet into the cache
     fn insert_data<'a>(
         &mut self,
         name: Name<'a>,
@@ -52,10 +309,288 @@ pub trait Tables {
         now: Timestamp,
     ) -> Option<&[u8]>;
 
-    // Common methods
+    // Reports the names (without the implicit digest) of cached data that was retrieved
+    //  at least "min_hits" times and whose freshness lapses within "lead_time_ms",
+    //  so that it can be refreshed ahead of time. Each entry is reported once
+    //  per freshness period.
+    fn report_expiring_data(
+        &mut self,
+        _now: Timestamp,
+        _lead_time_ms: u64,
+        _min_hits: u32,
+        _report: &mut dyn FnMut(Name<'_>),
+    ) {
+    }
+
+    // Removes the stale cached data
+    fn prune_cs_if_needed(&mut self, now: Timestamp);
+
+    // Implementations that do not keep track of their size report nothing
+    fn content_store_usage(&self) -> ContentStoreUsage {
+        ContentStoreUsage::default()
+    }
+
+    // Reports every cached packet
+    fn list_cs_entries(&self, _report: &mut dyn FnMut(CsEntryInfo<'_>)) {}
+
+    // Takes the parameters that concern the cached data
+    fn set_cs_params(&mut self, _params: &TableParams) {}
+
+    // Removes the cached packets whose full names are under the prefix, at most "limit"
+    //  of them if given, and returns how many were removed.
+    // Implementations that cannot enumerate their entries remove nothing.
+    fn erase_data(&mut self, _prefix: Name<'_>, _limit: Option<usize>) -> usize {
+        0
+    }
+}
+
+// Everything the forwarder needs. The provided methods combine the three tables,
+//  which the implementations that keep them together can do better.
+pub trait Tables: Fib + Pit + ContentStore {
     // Removes the face from all FIB and PIT entries
-    fn unregister_face(&mut self, face: FaceToken);
-    
+    fn unregister_face(&mut self, face: FaceToken) {
+        self.unregister_face_routes(face);
+        self.unregister_face_records(face);
+    }
+
     // Cleans up the internal state, for example removing stale PIT entries and cached data
-    fn prune_if_needed(&mut self, now: Timestamp);
+    fn prune_if_needed(&mut self, now: Timestamp) {
+        self.prune_pit_if_needed(now);
+        self.prune_cs_if_needed(now);
+    }
+
+    fn set_params(&mut self, params: &TableParams) {
+        self.set_pit_params(params);
+        self.set_cs_params(params);
+    }
+
+    // Satisfies the interest from the content store, or otherwise registers it as
+    //  register_interest does, e.g. with a single walk down the name tree.
+    // The cached packet goes to "satisfy", and then None is returned.
+    fn process_interest<'s, 'a>(
+        &'s mut self,
+        interest: &Interest<'a>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+        satisfy: &mut dyn FnMut(&[u8]),
+    ) -> Option<impl Iterator<Item = FaceToken> + use<'s, 'a, Self>> {
+        if let Some(packet) = self.get_data(
+            interest.name,
+            interest.can_be_prefix.is_some(),
+            interest.must_be_fresh.is_some(),
+            now,
+        ) {
+            satisfy(packet);
+            return None;
+        }
+        Some(self.register_interest(interest, nonce, reply_to, now))
+    }
+}
+
+// Pairs the FIB and PIT of one implementation with the content store of another,
+//  e.g. the reference tables with a disk-backed cache
+pub struct CombinedTables<FP: Fib + Pit, CS: ContentStore> {
+    pub fib_and_pit: FP,
+    pub content_store: CS,
+}
+
+impl<FP: Fib + Pit, CS: ContentStore> CombinedTables<FP, CS> {
+    pub fn new(fib_and_pit: FP, content_store: CS) -> Self {
+        Self {
+            fib_and_pit,
+            content_store,
+        }
+    }
+}
+
+impl<FP: Fib + Pit, CS: ContentStore> Fib for CombinedTables<FP, CS> {
+    fn register_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken, cost: u32) {
+        self.fib_and_pit.register_prefix(name_prefix, face, cost)
+    }
+
+    fn unregister_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken) -> bool {
+        self.fib_and_pit.unregister_prefix(name_prefix, face)
+    }
+
+    fn unregister_face_routes(&mut self, face: FaceToken) {
+        self.fib_and_pit.unregister_face_routes(face)
+    }
+
+    fn list_routes(&self, report: &mut dyn FnMut(FibEntryInfo<'_>)) {
+        self.fib_and_pit.list_routes(report)
+    }
+
+    fn has_route(&self, name: Name<'_>) -> bool {
+        self.fib_and_pit.has_route(name)
+    }
+
+    fn register_default_route(&mut self, face: FaceToken, cost: u32) {
+        self.fib_and_pit.register_default_route(face, cost)
+    }
+
+    fn unregister_default_route(&mut self, face: FaceToken) -> bool {
+        self.fib_and_pit.unregister_default_route(face)
+    }
+}
+
+impl<FP: Fib + Pit, CS: ContentStore> Pit for CombinedTables<FP, CS> {
+    fn register_interest<'s>(
+        &'s mut self,
+        interest: &Interest<'_>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+    ) -> impl Iterator<Item = FaceToken> + use<'s, FP, CS> {
+        self.fib_and_pit
+            .register_interest(interest, nonce, reply_to, now)
+    }
+
+    fn add_pit_in_record(
+        &mut self,
+        name: Name<'_>,
+        can_be_prefix: bool,
+        interest_lifetime: Option<u64>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+    ) -> bool {
+        self.fib_and_pit.add_pit_in_record(
+            name,
+            can_be_prefix,
+            interest_lifetime,
+            nonce,
+            reply_to,
+            now,
+        )
+    }
+
+    fn satisfy_interests<H>(
+        &mut self,
+        name: Name<'_>,
+        from: FaceToken,
+        now: Timestamp,
+        digest_computation: &mut H,
+    ) -> impl Iterator<Item = FaceToken>
+    where
+        H: FnMut() -> [u8; 32],
+    {
+        self.fib_and_pit
+            .satisfy_interests(name, from, now, digest_computation)
+    }
+
+    fn report_nack(&mut self, name: Name<'_>, from: FaceToken, now: Timestamp) {
+        self.fib_and_pit.report_nack(name, from, now)
+    }
+
+    fn process_nack(
+        &mut self,
+        name: Name<'_>,
+        can_be_prefix: bool,
+        nonce: [u8; 4],
+        reason: NackReason,
+        from: FaceToken,
+        now: Timestamp,
+    ) -> (NackOutcome, impl Iterator<Item = FaceToken>) {
+        self.fib_and_pit
+            .process_nack(name, can_be_prefix, nonce, reason, from, now)
+    }
+
+    fn register_face(&mut self, face: FaceToken) {
+        self.fib_and_pit.register_face(face)
+    }
+
+    fn unregister_face_records(&mut self, face: FaceToken) {
+        self.fib_and_pit.unregister_face_records(face)
+    }
+
+    fn prune_pit_if_needed(&mut self, now: Timestamp) {
+        self.fib_and_pit.prune_pit_if_needed(now)
+    }
+
+    fn report_timed_out_interests(&mut self, report: &mut dyn FnMut(Name<'_>, &[FaceToken])) {
+        self.fib_and_pit.report_timed_out_interests(report)
+    }
+
+    fn list_pit_entries(&self, report: &mut dyn FnMut(PitEntryInfo<'_>)) {
+        self.fib_and_pit.list_pit_entries(report)
+    }
+
+    fn interest_over_quota(&self) -> bool {
+        self.fib_and_pit.interest_over_quota()
+    }
+
+    fn set_pit_params(&mut self, params: &TableParams) {
+        self.fib_and_pit.set_pit_params(params)
+    }
+
+    fn set_flood_without_route(&mut self, route_lifetime_ms: Option<u64>) {
+        self.fib_and_pit.set_flood_without_route(route_lifetime_ms)
+    }
+
+    fn set_strategy_parameters(&mut self, prefix: Name<'_>, parameters: Option<Name<'_>>) -> bool {
+        self.fib_and_pit.set_strategy_parameters(prefix, parameters)
+    }
+
+    fn set_face_congested(&mut self, face: FaceToken, congested: bool) {
+        self.fib_and_pit.set_face_congested(face, congested)
+    }
+}
+
+impl<FP: Fib + Pit, CS: ContentStore> Tables for CombinedTables<FP, CS> {}
+
+impl<FP: Fib + Pit, CS: ContentStore> ContentStore for CombinedTables<FP, CS> {
+    fn insert_data<'a>(
+        &mut self,
+        name: Name<'a>,
+        digest: [u8; 32],
+        freshness: u64,
+        now: Timestamp,
+        packet: &'a [u8],
+    ) {
+        self.content_store
+            .insert_data(name, digest, freshness, now, packet)
+    }
+
+    fn get_data<'a>(
+        &mut self,
+        name: Name<'a>,
+        can_be_prefix: bool,
+        must_be_fresh: bool,
+        now: Timestamp,
+    ) -> Option<&[u8]> {
+        self.content_store
+            .get_data(name, can_be_prefix, must_be_fresh, now)
+    }
+
+    fn report_expiring_data(
+        &mut self,
+        now: Timestamp,
+        lead_time_ms: u64,
+        min_hits: u32,
+        report: &mut dyn FnMut(Name<'_>),
+    ) {
+        self.content_store
+            .report_expiring_data(now, lead_time_ms, min_hits, report)
+    }
+
+    fn prune_cs_if_needed(&mut self, now: Timestamp) {
+        self.content_store.prune_cs_if_needed(now)
+    }
+
+    fn content_store_usage(&self) -> ContentStoreUsage {
+        self.content_store.content_store_usage()
+    }
+
+    fn list_cs_entries(&self, report: &mut dyn FnMut(CsEntryInfo<'_>)) {
+        self.content_store.list_cs_entries(report)
+    }
+
+    fn set_cs_params(&mut self, params: &TableParams) {
+        self.content_store.set_cs_params(params)
+    }
+
+    fn erase_data(&mut self, prefix: Name<'_>, limit: Option<usize>) -> usize {
+        self.content_store.erase_data(prefix, limit)
+    }
 }
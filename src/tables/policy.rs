@@ -0,0 +1,348 @@
+use alloc::{collections::btree_map::BTreeMap, vec::Vec};
+
+use crate::name::{Name, NameBuf};
+
+// Identifies a packet in the content store for the replacement policy
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct CsEntryId(pub(crate) u64);
+
+// Decides which cached packet goes when the content store is full.
+// The content store tells the policy about every entry it adds, uses and removes,
+//  and asks it for a victim for as long as it is over capacity.
+pub trait ReplacementPolicy {
+    // The name is the full name of the data, with the implicit digest
+    fn on_insert(&mut self, entry: CsEntryId, name: Name<'_>);
+    // The entry was used to satisfy an interest
+    fn on_hit(&mut self, entry: CsEntryId);
+    // The entry was removed by the content store itself, e.g. because it got stale,
+    //  and must not be chosen anymore
+    fn on_remove(&mut self, entry: CsEntryId);
+    // The entry to evict, which is then considered removed
+    fn choose_victim(&mut self) -> Option<CsEntryId>;
+}
+
+// Keeps the entries ordered by a key, with the victim at the front.
+// The ticks make the keys unique and break the ties by age.
+#[derive(Default)]
+struct OrderedEntries<K: Ord + Copy> {
+    by_key: BTreeMap<(K, u64), CsEntryId>,
+    keys: BTreeMap<CsEntryId, (K, u64)>,
+    tick: u64,
+}
+
+impl<K: Ord + Copy> OrderedEntries<K> {
+    fn set(&mut self, entry: CsEntryId, key: K) {
+        self.remove(entry);
+        self.tick += 1;
+        self.by_key.insert((key, self.tick), entry);
+        self.keys.insert(entry, (key, self.tick));
+    }
+
+    fn key(&self, entry: CsEntryId) -> Option<K> {
+        self.keys.get(&entry).map(|(key, _)| *key)
+    }
+
+    fn remove(&mut self, entry: CsEntryId) {
+        if let Some(key) = self.keys.remove(&entry) {
+            self.by_key.remove(&key);
+        }
+    }
+
+    fn pop_first(&mut self) -> Option<CsEntryId> {
+        let (_, entry) = self.by_key.pop_first()?;
+        self.keys.remove(&entry);
+        Some(entry)
+    }
+}
+
+// Least recently used, the default
+#[derive(Default)]
+pub struct LruPolicy {
+    entries: OrderedEntries<()>,
+}
+
+impl ReplacementPolicy for LruPolicy {
+    fn on_insert(&mut self, entry: CsEntryId, _name: Name<'_>) {
+        self.entries.set(entry, ());
+    }
+
+    fn on_hit(&mut self, entry: CsEntryId) {
+        self.entries.set(entry, ());
+    }
+
+    fn on_remove(&mut self, entry: CsEntryId) {
+        self.entries.remove(entry);
+    }
+
+    fn choose_victim(&mut self) -> Option<CsEntryId> {
+        self.entries.pop_first()
+    }
+}
+
+// First in, first out, so that how long a packet stays cached does not depend on the traffic
+#[derive(Default)]
+pub struct FifoPolicy {
+    entries: OrderedEntries<()>,
+}
+
+impl ReplacementPolicy for FifoPolicy {
+    fn on_insert(&mut self, entry: CsEntryId, _name: Name<'_>) {
+        self.entries.set(entry, ());
+    }
+
+    fn on_hit(&mut self, _entry: CsEntryId) {}
+
+    fn on_remove(&mut self, entry: CsEntryId) {
+        self.entries.remove(entry);
+    }
+
+    fn choose_victim(&mut self) -> Option<CsEntryId> {
+        self.entries.pop_first()
+    }
+}
+
+// Least frequently used, keeping the popular content. Among the entries
+//  with the same number of hits the least recently used one goes first.
+#[derive(Default)]
+pub struct LfuPolicy {
+    entries: OrderedEntries<u32>,
+}
+
+impl ReplacementPolicy for LfuPolicy {
+    fn on_insert(&mut self, entry: CsEntryId, _name: Name<'_>) {
+        self.entries.set(entry, 0);
+    }
+
+    fn on_hit(&mut self, entry: CsEntryId) {
+        if let Some(hits) = self.entries.key(entry) {
+            self.entries.set(entry, hits.saturating_add(1));
+        }
+    }
+
+    fn on_remove(&mut self, entry: CsEntryId) {
+        self.entries.remove(entry);
+    }
+
+    fn choose_victim(&mut self) -> Option<CsEntryId> {
+        self.entries.pop_first()
+    }
+}
+
+// The data under the prefixes with a lower priority is evicted first, using LRU
+//  within the same priority. The longest matching prefix decides, and the data
+//  that matches none of them gets the default priority.
+pub struct PriorityPolicy {
+    prefixes: Vec<(NameBuf, u8)>,
+    default_priority: u8,
+    entries: OrderedEntries<u8>,
+}
+
+impl PriorityPolicy {
+    pub fn new(default_priority: u8) -> Self {
+        Self {
+            prefixes: Vec::new(),
+            default_priority,
+            entries: OrderedEntries::default(),
+        }
+    }
+
+    // Only affects the data inserted afterwards
+    pub fn set_priority(&mut self, prefix: Name<'_>, priority: u8) {
+        match self
+            .prefixes
+            .iter_mut()
+            .find(|(p, _)| p.as_name() == prefix)
+        {
+            Some((_, existing)) => *existing = priority,
+            None => self.prefixes.push((NameBuf::from(prefix), priority)),
+        }
+    }
+
+    fn priority_of(&self, name: Name<'_>) -> u8 {
+        self.prefixes
+            .iter()
+            .filter(|(prefix, _)| prefix.as_name().is_prefix_of(name))
+            .max_by_key(|(prefix, _)| prefix.component_count())
+            .map_or(self.default_priority, |(_, priority)| *priority)
+    }
+}
+
+impl ReplacementPolicy for PriorityPolicy {
+    fn on_insert(&mut self, entry: CsEntryId, name: Name<'_>) {
+        let priority = self.priority_of(name);
+        self.entries.set(entry, priority);
+    }
+
+    fn on_hit(&mut self, entry: CsEntryId) {
+        if let Some(priority) = self.entries.key(entry) {
+            self.entries.set(entry, priority);
+        }
+    }
+
+    fn on_remove(&mut self, entry: CsEntryId) {
+        self.entries.remove(entry);
+    }
+
+    fn choose_victim(&mut self) -> Option<CsEntryId> {
+        self.entries.pop_first()
+    }
+}
+
+// Decides whether the arriving data is cached at all, e.g. to keep out the huge
+//  packets or the namespaces that are never fetched twice. The name is that of
+//  the data, without the implicit digest.
+pub trait AdmissionPolicy {
+    fn admit(&mut self, name: Name<'_>, size: usize, freshness_ms: u64) -> bool;
+}
+
+// Caches everything, the default
+#[derive(Default)]
+pub struct AdmitAllPolicy {}
+
+impl AdmissionPolicy for AdmitAllPolicy {
+    fn admit(&mut self, _name: Name<'_>, _size: usize, _freshness_ms: u64) -> bool {
+        true
+    }
+}
+
+// Refuses the data under the denied prefixes, the packets over the size limit
+//  and, if asked to, the data that is never fresh
+#[derive(Default)]
+pub struct FilterAdmissionPolicy {
+    denied_prefixes: Vec<NameBuf>,
+    max_size: Option<usize>,
+    require_freshness: bool,
+}
+
+impl FilterAdmissionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn deny_prefix(mut self, prefix: Name<'_>) -> Self {
+        self.denied_prefixes.push(NameBuf::from(prefix));
+        self
+    }
+
+    pub fn with_max_size(mut self, bytes: usize) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    // Stale data can only satisfy the interests that do not ask for fresh data
+    pub fn requiring_freshness(mut self) -> Self {
+        self.require_freshness = true;
+        self
+    }
+}
+
+impl AdmissionPolicy for FilterAdmissionPolicy {
+    fn admit(&mut self, name: Name<'_>, size: usize, freshness_ms: u64) -> bool {
+        if self.max_size.is_some_and(|max_size| size > max_size) {
+            return false;
+        }
+        if self.require_freshness && freshness_ms == 0 {
+            return false;
+        }
+        !self
+            .denied_prefixes
+            .iter()
+            .any(|prefix| prefix.as_name().is_prefix_of(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, vec::Vec};
+
+    use crate::{
+        clock::Timestamp,
+        name::{Name, NameComponent},
+        tables::{
+            policy::{
+                FifoPolicy, FilterAdmissionPolicy, LfuPolicy, LruPolicy, PriorityPolicy,
+                ReplacementPolicy,
+            },
+            reference::ReferenceTables,
+            ContentStore,
+        },
+    };
+
+    // Caches three packets in a store with room for two, using the first one in between
+    fn surviving_packets(policy: Box<dyn ReplacementPolicy>) -> Vec<u8> {
+        let mut tables = ReferenceTables::default().with_content_store_capacity(2, policy);
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let prefixes = [b"low", b"top", b"low"];
+        let packets = [[1u8], [2], [3]];
+        let mut names = Vec::new();
+        for (i, prefix) in prefixes.iter().enumerate() {
+            let comp = [
+                NameComponent::generic(*prefix),
+                NameComponent::generic(&packets[i]),
+            ];
+            names.push(comp);
+        }
+
+        for (i, comp) in names.iter().enumerate() {
+            let name = root.adding_components(comp);
+            tables.insert_data(name, [i as u8; 32], 1000, now, &packets[i]);
+            if i == 1 {
+                let first = root.adding_components(&names[0]);
+                assert!(tables.get_data(first, false, false, now).is_some());
+            }
+        }
+
+        names
+            .iter()
+            .filter_map(|comp| {
+                let name = root.adding_components(comp);
+                tables.get_data(name, false, false, now).map(|p| p[0])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replacement_policies() {
+        assert_eq!(surviving_packets(Box::new(LruPolicy::default())), [1, 3]);
+        assert_eq!(surviving_packets(Box::new(FifoPolicy::default())), [2, 3]);
+        assert_eq!(surviving_packets(Box::new(LfuPolicy::default())), [1, 3]);
+
+        // The data under /top stays even though it is the least recently used
+        let mut policy = PriorityPolicy::new(0);
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"top")];
+        policy.set_priority(root.adding_components(comp), 1);
+        assert_eq!(surviving_packets(Box::new(policy)), [2, 3]);
+    }
+
+    #[test]
+    fn test_admission_policy() {
+        let root = Name::new();
+        let video = &[NameComponent::generic(b"video")];
+        let clip = &[video[0], NameComponent::generic(b"clip")];
+        let small = &[NameComponent::generic(b"small")];
+        let large = &[NameComponent::generic(b"large")];
+        let stale = &[NameComponent::generic(b"stale")];
+        let policy = FilterAdmissionPolicy::new()
+            .deny_prefix(root.adding_components(video))
+            .with_max_size(4)
+            .requiring_freshness();
+        let mut tables = ReferenceTables::default().with_admission_policy(Box::new(policy));
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+
+        let mut cached = |comp: &[NameComponent], freshness, packet: &[u8]| {
+            let name = root.adding_components(comp);
+            tables.insert_data(name, [0; 32], freshness, now, packet);
+            tables.get_data(name, false, false, now).is_some()
+        };
+        assert!(cached(small, 100, &[1, 2, 3]));
+        assert!(!cached(clip, 100, &[1, 2, 3]));
+        assert!(!cached(large, 100, &[1, 2, 3, 4, 5]));
+        assert!(!cached(stale, 0, &[1, 2]));
+    }
+}
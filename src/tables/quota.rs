@@ -0,0 +1,187 @@
+use alloc::collections::btree_map::BTreeMap;
+
+use crate::{clock::Timestamp, forwarder::FaceToken};
+
+// Limits on how much of the PIT a single face can take, so that a misbehaving face
+//  cannot fill it with interests. The interests over the limits are rejected by the
+//  tables (see Pit::interest_over_quota), which the forwarder counts as dropped.
+// There are no limits by default, and the hashed tables do not enforce them.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct FaceLimits {
+    // The in-records that a face can have pending at a time
+    pub max_pending_interests: Option<usize>,
+    // The interests that a face can send per second, in bursts of up to as many
+    pub max_interests_per_second: Option<u32>,
+}
+
+// Keeps track of the faces for the limits.
+// The pending in-records are counted as the interests are admitted and satisfied, and
+//  recounted when the PIT is pruned, so that the interests which did not add an in-record
+//  (e.g. the retransmissions) and the expired ones only count until then.
+#[derive(Default)]
+pub struct FaceQuotas {
+    limits: FaceLimits,
+    faces: BTreeMap<FaceToken, FaceUsage>,
+}
+
+struct FaceUsage {
+    pending: usize,
+    // In thousandths of an interest, refilled at the allowed rate
+    rate_tokens: u64,
+    last_refill: Timestamp,
+}
+
+impl FaceQuotas {
+    pub fn new(limits: FaceLimits) -> Self {
+        Self {
+            limits,
+            faces: BTreeMap::new(),
+        }
+    }
+
+    pub fn limits(&self) -> FaceLimits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: FaceLimits) {
+        self.limits = limits;
+    }
+
+    // Takes an interest from the face if it is within the limits
+    pub fn admit(&mut self, face: FaceToken, now: Timestamp) -> bool {
+        if self.limits == FaceLimits::default() {
+            return true;
+        }
+        let usage = self.faces.entry(face).or_insert(FaceUsage {
+            pending: 0,
+            rate_tokens: u64::MAX,
+            last_refill: now,
+        });
+
+        if let Some(max) = self.limits.max_pending_interests {
+            if usage.pending >= max {
+                return false;
+            }
+        }
+        if let Some(rate) = self.limits.max_interests_per_second {
+            // A millisecond brings "rate" thousandths of an interest
+            let capacity = rate as u64 * 1000;
+            let elapsed = now.difference(&usage.last_refill).unwrap_or(0);
+            usage.rate_tokens = usage
+                .rate_tokens
+                .saturating_add(elapsed.saturating_mul(rate as u64))
+                .min(capacity);
+            usage.last_refill = now;
+            if usage.rate_tokens < 1000 {
+                return false;
+            }
+            usage.rate_tokens -= 1000;
+        }
+        usage.pending += 1;
+        true
+    }
+
+    // An admitted interest of the face has no in-record anymore, e.g. it was satisfied
+    pub fn release(&mut self, face: FaceToken) {
+        if let Some(usage) = self.faces.get_mut(&face) {
+            usage.pending = usage.pending.saturating_sub(1);
+        }
+    }
+
+    // Starts a recount, after which "add_pending" is called for each in-record
+    pub fn clear_pending(&mut self) {
+        for usage in self.faces.values_mut() {
+            usage.pending = 0;
+        }
+    }
+
+    pub fn add_pending(&mut self, face: FaceToken) {
+        if let Some(usage) = self.faces.get_mut(&face) {
+            usage.pending += 1;
+        }
+    }
+
+    pub fn pending(&self, face: FaceToken) -> usize {
+        self.faces.get(&face).map_or(0, |usage| usage.pending)
+    }
+
+    pub fn remove_face(&mut self, face: FaceToken) {
+        self.faces.remove(&face);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        clock::Timestamp,
+        forwarder::FaceToken,
+        name::{Name, NameComponent},
+        packet::Interest,
+        tables::{quota::FaceLimits, reference::ReferenceTables, Fib, Pit, TableParams, Tables},
+    };
+
+    // Registers the interest for /a/<i> and returns where it is forwarded
+    fn forward(tables: &mut ReferenceTables, i: u32, from: u32, now: Timestamp) -> Vec<FaceToken> {
+        let root = Name::new();
+        let id = i.to_be_bytes();
+        let comp = &[NameComponent::generic(b"a"), NameComponent::generic(&id)];
+        let name = root.adding_components(comp);
+        tables
+            .register_interest(&Interest::new(name, false, id), id, FaceToken(from), now)
+            .collect()
+    }
+
+    #[test]
+    fn test_face_limits() {
+        let mut tables = ReferenceTables::default();
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let a = &[NameComponent::generic(b"a")];
+        tables.register_prefix(root.adding_components(a), FaceToken(0), 0);
+
+        // At most two pending interests per face
+        tables.set_params(&TableParams {
+            face_limits: FaceLimits {
+                max_pending_interests: Some(2),
+                max_interests_per_second: None,
+            },
+            ..TableParams::default()
+        });
+        assert_eq!(forward(&mut tables, 1, 1, now), [FaceToken(0)]);
+        assert_eq!(forward(&mut tables, 2, 1, now), [FaceToken(0)]);
+        assert!(!tables.interest_over_quota());
+        assert!(forward(&mut tables, 3, 1, now).is_empty());
+        assert!(tables.interest_over_quota());
+        assert_eq!(forward(&mut tables, 4, 2, now), [FaceToken(0)]);
+
+        // Satisfying one makes room for another
+        let id = 1u32.to_be_bytes();
+        let comp = &[NameComponent::generic(b"a"), NameComponent::generic(&id)];
+        let name = root.adding_components(comp);
+        let faces: Vec<_> = tables
+            .satisfy_interests(name, FaceToken(0), now, &mut || [0; 32])
+            .collect();
+        assert_eq!(faces, [FaceToken(1)]);
+        assert_eq!(forward(&mut tables, 5, 1, now), [FaceToken(0)]);
+        assert!(forward(&mut tables, 6, 1, now).is_empty());
+
+        // At most two interests per second, which are refilled over time
+        tables.set_params(&TableParams {
+            face_limits: FaceLimits {
+                max_pending_interests: None,
+                max_interests_per_second: Some(2),
+            },
+            ..TableParams::default()
+        });
+        assert_eq!(forward(&mut tables, 7, 3, now), [FaceToken(0)]);
+        assert_eq!(forward(&mut tables, 8, 3, now), [FaceToken(0)]);
+        assert!(forward(&mut tables, 9, 3, now).is_empty());
+        let later = now.adding(500);
+        assert_eq!(forward(&mut tables, 10, 3, later), [FaceToken(0)]);
+        assert!(forward(&mut tables, 11, 3, later).is_empty());
+    }
+}
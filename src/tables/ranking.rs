@@ -0,0 +1,122 @@
+use crate::tables::strategy::NextHop;
+
+// Orders the next hops of a FIB entry before the strategy sees them, and the strategies
+//  prefer the first ones, e.g. the best route strategy only uses the first one for a new
+//  interest. Without a ranking the next hops are ordered by cost.
+pub trait NextHopRanking {
+    // The next hops come ordered by cost and are reordered in place. The nonce of the
+    //  interest can be used as the source of randomness.
+    fn rank(&mut self, next_hops: &mut [NextHop], nonce: [u8; 4]);
+}
+
+// The lowest cost first, the same as without a ranking
+#[derive(Default)]
+pub struct CostRanking {}
+
+impl NextHopRanking for CostRanking {
+    fn rank(&mut self, _next_hops: &mut [NextHop], _nonce: [u8; 4]) {}
+}
+
+// Takes turns, so that the load is spread evenly whatever the costs
+#[derive(Default)]
+pub struct RoundRobinRanking {
+    turn: usize,
+}
+
+impl NextHopRanking for RoundRobinRanking {
+    fn rank(&mut self, next_hops: &mut [NextHop], _nonce: [u8; 4]) {
+        if next_hops.is_empty() {
+            return;
+        }
+        next_hops.rotate_left(self.turn % next_hops.len());
+        self.turn = self.turn.wrapping_add(1);
+    }
+}
+
+// Puts a random next hop first, each with the chance proportional to 1 / (cost + 1),
+//  so that the load is spread by the costs. The others stay ordered by cost.
+#[derive(Default)]
+pub struct WeightedRandomRanking {}
+
+impl NextHopRanking for WeightedRandomRanking {
+    fn rank(&mut self, next_hops: &mut [NextHop], nonce: [u8; 4]) {
+        let weight = |next_hop: &NextHop| u32::MAX as u64 / (next_hop.cost as u64 + 1);
+        let total: u64 = next_hops.iter().map(weight).sum();
+        if total == 0 {
+            return;
+        }
+        // The nonces are random already, but not necessarily their low bits
+        let random = (u32::from_be_bytes(nonce) as u64).wrapping_mul(0x9e3779b97f4a7c15) >> 32;
+        let mut target = ((random as u128 * total as u128) >> 32) as u64;
+        for index in 0..next_hops.len() {
+            let w = weight(&next_hops[index]);
+            if target < w {
+                next_hops[..=index].rotate_right(1);
+                return;
+            }
+            target -= w;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, vec::Vec};
+
+    use crate::{
+        clock::Timestamp,
+        forwarder::FaceToken,
+        name::{Name, NameComponent},
+        packet::Interest,
+        tables::{
+            ranking::{NextHopRanking, RoundRobinRanking, WeightedRandomRanking},
+            reference::ReferenceTables,
+            Fib, Pit,
+        },
+    };
+
+    // The face each of the interests for /a/<i> is forwarded to
+    fn first_faces(ranking: Box<dyn NextHopRanking>, count: u32) -> Vec<u32> {
+        let mut tables = ReferenceTables::default();
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let a = &[NameComponent::generic(b"a")];
+        for (face, cost) in [(1, 10), (2, 20), (3, 40)] {
+            tables.register_prefix(root.adding_components(a), FaceToken(face), cost);
+        }
+        tables.set_next_hop_ranking(root.adding_components(a), ranking);
+
+        (0..count)
+            .map(|i| {
+                let id = i.to_be_bytes();
+                let comp = &[a[0], NameComponent::generic(&id)];
+                let name = root.adding_components(comp);
+                let nonce = i.wrapping_mul(2654435761).to_be_bytes();
+                let mut faces = tables.register_interest(
+                    &Interest::new(name, false, nonce),
+                    nonce,
+                    FaceToken(0),
+                    now,
+                );
+                faces.next().unwrap().0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_next_hop_rankings() {
+        assert_eq!(
+            first_faces(Box::new(RoundRobinRanking::default()), 4),
+            [1, 2, 3, 1]
+        );
+
+        // Roughly in the proportions 4:2:1
+        let faces = first_faces(Box::new(WeightedRandomRanking::default()), 700);
+        let count = |face| faces.iter().filter(|f| **f == face).count();
+        assert!((300..500).contains(&count(1)));
+        assert!((100..300).contains(&count(2)));
+        assert!((30..170).contains(&count(3)));
+    }
+}
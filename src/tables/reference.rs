@@ -1,12 +1,25 @@
 use core::{cmp::Ordering, num::NonZeroU16};
 
-use alloc::{boxed::Box, collections::btree_map::BTreeMap, vec::Vec};
+use alloc::{boxed::Box, collections::btree_map::BTreeMap, vec, vec::Vec};
 
 use crate::{
     clock::Timestamp,
+    face::lp::NackReason,
     forwarder::FaceToken,
-    name::{Name, NameComponent},
-    tables::Tables,
+    name::{Name, NameBuf, NameComponent},
+    packet::Interest,
+    tables::{
+        measurements::Measurements,
+        policy::{AdmissionPolicy, AdmitAllPolicy, CsEntryId, LruPolicy, ReplacementPolicy},
+        quota::FaceQuotas,
+        ranking::NextHopRanking,
+        strategy::{
+            forward_to_all, LearnedRoute, NextHop, PendingInterest, PitEntryView,
+            RetransmissionSuppression, SelfLearningStrategy, Strategy, StrategyChoice,
+        },
+        CanBePrefixSelection, ContentStore, ContentStoreUsage, CsEntryInfo, Fib, FibEntryInfo,
+        NackOutcome, Pit, PitEntryInfo, PitInRecordInfo, PitOutRecordInfo, TableParams, Tables,
+    },
 };
 
 // The reference implementation of Tables encodes the following forwarding strategy:
@@ -16,14 +29,14 @@ use crate::{
 //      (in case of a false positive the source will retransmit it with a new nonce).
 // 3. If we are here, the interest needs to be admitted by the PIT, so we get or create a
 //      PIT entry correspoding to the interest's full name. Then we have options:
-//      -- If the PIT entry is new we store the interest and notify lowest-cost face
-//          in the FIB entry corrseponding to the longest match.
 //      -- If the PIT entry is not new and there is an interest registed from _any_ face
 //          with the same nonce as this packet, we treat it as a loop and drop the packet.
-//      -- Otherwise, this is treated as a new interest and is sent to the _next_ face
+//      -- Otherwise the strategy chosen for the name decides where to forward the interest.
+//          The default best route strategy notifies the lowest-cost face in the FIB entry
+//          corrseponding to the longest match for a new PIT entry, and the _next_ face
 //          in terms of cost at the longest prefix (or then also on higher levels ordered
-//          by costs within level), unless it was sent recently. Here "recently" is an
-//          exponential backoff that starts at 10 ms and doubles until the maximum of 250 ms
+//          by costs within level) for the others, unless it was sent recently. Here "recently"
+//          is an exponential backoff that starts at 16 ms and doubles until the maximum of 256 ms
 
 // Data satisfaction
 // When the data arrives we want to check all of the PIT entries it can satisfy.
@@ -35,13 +48,62 @@ use crate::{
 // After a PIT is satisfied we take out all the faces that were registered,
 //  add the nonces to Dead Nonce List, and rest the pit entry (possibly also cleaning it up).
 
+// How much the tables hold, to observe their growth
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableStats {
+    // The prefixes with at least one next hop
+    pub fib_entries: usize,
+    pub fib_next_hops: usize,
+    // The normal and the "can be prefix" entries are counted separately
+    pub pit_entries: usize,
+    pub pit_in_records: usize,
+    pub cs_entries: usize,
+    // Only the bytes of the packets
+    pub cs_bytes: usize,
+    // The nonces inserted in the generations that are kept, the repeated ones included
+    pub dead_nonce_entries: usize,
+    pub dead_nonce_bytes: usize,
+    // Including the root and the nodes kept as the parents of the others
+    pub name_tree_nodes: usize,
+}
+
 pub struct ReferenceTables {
     root: TableEntry,
     dead_nonce_list: DeadNonceList,
+    dead_nonce_duration_ms: u64,
     data_cache_duration_ms: u64,
+    interest_lifetimes: InterestLifetimes,
+    retransmission_suppression: RetransmissionSuppression,
+    can_be_prefix_selection: CanBePrefixSelection,
     face_scratchpad: Vec<(u32, FaceToken)>,
+    // Ordered by cost, like the next hops of the FIB entries
+    default_routes: Vec<FibEntry>,
+    strategy_choice: StrategyChoice,
+    next_hops: Vec<NextHop>,
+    forward_to: Vec<FaceToken>,
+    // All the faces, for the strategies that flood the interests without a route
+    faces: Vec<FaceToken>,
+    // The routes learned by the strategies, with their expiry
+    learned_routes: Vec<(NameBuf, FaceToken, Timestamp)>,
+    // See Pit::set_flood_without_route
+    flood_route_lifetime_ms: Option<u64>,
+    // See Pit::set_face_congested
+    congested_faces: Vec<FaceToken>,
+    measurements: Measurements,
+    quotas: FaceQuotas,
+    // Whether the last interest was rejected by the quotas
+    over_quota: bool,
     prune_interval_ms: u64,
     last_prune_time: Timestamp,
+    cs_capacity: usize,
+    cs_byte_budget: usize,
+    cs_bytes: usize,
+    replacement_policy: Box<dyn ReplacementPolicy>,
+    admission_policy: Box<dyn AdmissionPolicy>,
+    // The full names and sizes of the cached packets, to find the ones to evict
+    cs_names: BTreeMap<CsEntryId, (NameBuf, usize)>,
+    next_cs_entry_id: u64,
+    pruned: PrunedEntries,
 }
 
 impl ReferenceTables {
@@ -52,11 +114,203 @@ impl ReferenceTables {
     ) -> Self {
         Self {
             root: TableEntry::new(),
-            dead_nonce_list: DeadNonceList::new(dead_nonce_duration_ms as u64),
+            dead_nonce_list: DeadNonceList::new(
+                dead_nonce_duration_ms as u64,
+                DEFAULT_DNL_CAPACITY,
+                DEFAULT_DNL_FALSE_POSITIVE_ONE_IN,
+            ),
+            dead_nonce_duration_ms: dead_nonce_duration_ms as u64,
             data_cache_duration_ms: data_cache_duration_ms as u64,
+            interest_lifetimes: InterestLifetimes::default(),
+            retransmission_suppression: RetransmissionSuppression::default(),
+            can_be_prefix_selection: CanBePrefixSelection::default(),
             face_scratchpad: Default::default(),
+            default_routes: Vec::new(),
+            strategy_choice: StrategyChoice::default(),
+            next_hops: Vec::new(),
+            forward_to: Vec::new(),
+            faces: Vec::new(),
+            learned_routes: Vec::new(),
+            flood_route_lifetime_ms: None,
+            congested_faces: Vec::new(),
+            measurements: Measurements::new(),
+            quotas: FaceQuotas::default(),
+            over_quota: false,
             prune_interval_ms: prune_interval_ms as u64,
             last_prune_time: Timestamp { ms_since_1970: 0 },
+            cs_capacity: usize::MAX,
+            cs_byte_budget: usize::MAX,
+            cs_bytes: 0,
+            replacement_policy: Box::new(LruPolicy::default()),
+            admission_policy: Box::new(AdmitAllPolicy::default()),
+            cs_names: BTreeMap::new(),
+            next_cs_entry_id: 0,
+            pruned: PrunedEntries::default(),
+        }
+    }
+
+    // Bounds the number of cached packets, evicting the ones chosen by the policy
+    //  when there are too many. By default the data is only removed once it
+    //  was not used for data_cache_duration_ms.
+    pub fn with_content_store_capacity(
+        mut self,
+        capacity: usize,
+        policy: Box<dyn ReplacementPolicy>,
+    ) -> Self {
+        self.cs_capacity = capacity;
+        self.replacement_policy = policy;
+        self
+    }
+
+    // Bounds the total size of the cached packets, which is a better measure of the
+    //  memory used than their number when the sizes vary. The victims are chosen by
+    //  the policy set with with_content_store_capacity, LRU by default.
+    pub fn with_content_store_byte_budget(mut self, bytes: usize) -> Self {
+        self.cs_byte_budget = bytes;
+        self
+    }
+
+    // Consulted before caching each packet, everything is cached by default
+    pub fn with_admission_policy(mut self, policy: Box<dyn AdmissionPolicy>) -> Self {
+        self.admission_policy = policy;
+        self
+    }
+
+    // Sizes the dead nonce list for "capacity" nonces per dead nonce duration, with at most
+    //  one in "false_positive_one_in" new interests dropped as a loop by mistake.
+    // The memory does not grow beyond that, but more nonces raise the false positive rate.
+    pub fn with_dead_nonce_list_capacity(
+        mut self,
+        capacity: usize,
+        false_positive_one_in: u32,
+    ) -> Self {
+        self.dead_nonce_list =
+            DeadNonceList::new(self.dead_nonce_duration_ms, capacity, false_positive_one_in);
+        self
+    }
+
+    // Assigns the strategy to the names under the prefix, replacing the default
+    //  best route strategy for the root prefix
+    pub fn set_strategy(&mut self, prefix: Name<'_>, strategy: Box<dyn Strategy>) {
+        self.strategy_choice.set_strategy(prefix, strategy);
+    }
+
+    pub fn unset_strategy(&mut self, prefix: Name<'_>) -> bool {
+        self.strategy_choice.unset_strategy(prefix)
+    }
+
+    // See StrategyChoice::set_parameters
+    pub fn set_strategy_parameters(&mut self, prefix: Name<'_>, parameters: Name<'_>) {
+        self.strategy_choice.set_parameters(prefix, parameters);
+    }
+
+    pub fn unset_strategy_parameters(&mut self, prefix: Name<'_>) -> bool {
+        self.strategy_choice.unset_parameters(prefix)
+    }
+
+    // Orders the next hops of the FIB entry for the prefix before the strategy picks
+    //  among them, instead of by cost. Only used for the names of the interests,
+    //  not for the delegations of the forwarding hints.
+    pub fn set_next_hop_ranking(&mut self, prefix: Name<'_>, ranking: Box<dyn NextHopRanking>) {
+        self.root
+            .get_or_insert_entry(&mut prefix.components())
+            .ranking = Some(ranking);
+    }
+
+    pub fn unset_next_hop_ranking(&mut self, prefix: Name<'_>) -> bool {
+        self.root.unset_ranking(&mut prefix.components())
+    }
+
+    // What the strategies have learned about the namespaces
+    pub fn measurements(&self) -> &Measurements {
+        &self.measurements
+    }
+
+    // The parameters in use, which are changed with Tables::set_params
+    pub fn params(&self) -> TableParams {
+        TableParams {
+            data_cache_duration_ms: self.data_cache_duration_ms,
+            dead_nonce_duration_ms: self.dead_nonce_duration_ms,
+            default_interest_lifetime_ms: self.interest_lifetimes.default_ms,
+            min_interest_lifetime_ms: self.interest_lifetimes.min_ms,
+            max_interest_lifetime_ms: self.interest_lifetimes.max_ms,
+            prune_interval_ms: self.prune_interval_ms,
+            retransmission_suppression: self.retransmission_suppression,
+            can_be_prefix_selection: self.can_be_prefix_selection,
+            face_limits: self.quotas.limits(),
+        }
+    }
+
+    // Walks the whole name tree, so better not called for every packet
+    pub fn stats(&self) -> TableStats {
+        let mut stats = TableStats {
+            dead_nonce_entries: self.dead_nonce_list.len(),
+            dead_nonce_bytes: self.dead_nonce_list.memory_bytes(),
+            ..Default::default()
+        };
+        self.root.add_to_stats(&mut stats);
+        stats
+    }
+
+    // Checks that the name tree and the content store accounting are consistent and
+    //  tells what is broken otherwise. Walks everything, so it is meant for the tests
+    //  and the fuzzing harnesses.
+    #[cfg(debug_assertions)]
+    pub fn check_invariants(&self) -> Result<(), &'static str> {
+        check_fib_invariants(&self.default_routes)?;
+        let mut cached = Vec::new();
+        self.root.check_invariants(true, &mut cached)?;
+        if cached.len() != self.cs_names.len() {
+            return Err("cached packets missing from the CS index");
+        }
+        let mut bytes = 0;
+        for (id, len) in cached {
+            match self.cs_names.get(&id) {
+                Some((_, size)) if *size == len => bytes += len,
+                _ => return Err("CS index does not match the cached packets"),
+            }
+        }
+        if bytes != self.cs_bytes {
+            return Err("CS byte count does not match the cached packets");
+        }
+        Ok(())
+    }
+
+    fn add_learned_route(
+        &mut self,
+        prefix: Name<'_>,
+        face: FaceToken,
+        route: LearnedRoute,
+        now: Timestamp,
+    ) {
+        let expiry = now.adding(route.lifetime_ms);
+        if let Some(learned) = self
+            .learned_routes
+            .iter_mut()
+            .find(|(p, f, _)| *f == face && p.as_name() == prefix)
+        {
+            learned.2 = expiry;
+            return;
+        }
+        // The routes that were registered explicitly are left alone
+        if self.root.has_route(&mut prefix.components(), face) {
+            return;
+        }
+        self.root
+            .register_prefix(&mut prefix.components(), face, route.cost);
+        self.learned_routes
+            .push((NameBuf::from(prefix), face, expiry));
+    }
+
+    fn evict_if_needed(&mut self) {
+        while self.cs_names.len() > self.cs_capacity || self.cs_bytes > self.cs_byte_budget {
+            let Some(victim) = self.replacement_policy.choose_victim() else {
+                return;
+            };
+            if let Some((name, size)) = self.cs_names.remove(&victim) {
+                self.root.remove_data(&mut name.as_name().components());
+                self.cs_bytes -= size;
+            }
         }
     }
 
@@ -71,49 +325,142 @@ impl Default for ReferenceTables {
     }
 }
 
-impl Tables for ReferenceTables {
-    fn unregister_face(&mut self, face: FaceToken) {
+impl Fib for ReferenceTables {
+    fn register_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken, cost: u32) {
+        // A learned route that is registered explicitly does not expire anymore
+        self.learned_routes
+            .retain(|(p, f, _)| *f != face || p.as_name() != name_prefix);
+        self.root
+            .register_prefix(&mut name_prefix.components(), face, cost);
+    }
+
+    fn unregister_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken) -> bool {
+        self.learned_routes
+            .retain(|(p, f, _)| *f != face || p.as_name() != name_prefix);
         self.root
-            .unregister_prefix(&mut None.into_iter(), face, true, true);
+            .unregister_prefix(&mut name_prefix.components(), face, false)
     }
 
-    fn prune_if_needed(&mut self, now: Timestamp) {
-        if self.last_prune_time.adding(self.prune_interval_ms) < now {
-            self.root
-                .prune_if_needed(Name::new(), now, &mut self.dead_nonce_list);
-            self.dead_nonce_list.prune(now);
+    fn unregister_face_routes(&mut self, face: FaceToken) {
+        self.learned_routes.retain(|(_, f, _)| *f != face);
+        self.default_routes.retain(|x| x.next_hop != face);
+        self.root
+            .unregister_prefix(&mut None.into_iter(), face, true);
+    }
 
-            // TODO: Maybe need to have metrics here? Or return number of removed data/intrests
+    fn list_routes(&self, report: &mut dyn FnMut(FibEntryInfo<'_>)) {
+        let mut next_hops = Vec::new();
+        self.root.visit(Name::new(), &mut |name, entry| {
+            if entry.fib.is_empty() {
+                return;
+            }
+            next_hops.clear();
+            next_hops.extend(entry.fib.iter().map(|x| NextHop {
+                face: x.next_hop,
+                cost: x.cost,
+                congested: false,
+            }));
+            report(FibEntryInfo {
+                name,
+                next_hops: &next_hops,
+                is_default: false,
+            });
+        });
+        report_default_routes(&self.default_routes, report);
+    }
 
-            // TODO: we could check the CS count here and if it is too big
-            //  could prune with now = (actual_now - 0.5 * data_cache_duration_ms), then 0.75, etc.
+    fn has_route(&self, name: Name<'_>) -> bool {
+        !self.default_routes.is_empty() || self.root.has_any_route(&mut name.components())
+    }
 
-            // LRU cache policy LRU cache policy implements the Least Recently Used cache replacement algorithm, which discards the least recently used items first. LRU evicts upon every insertion, because its performance is more predictable; the alternative, periodic cleanup of a batch of entries, can cause jitter in packet forwarding.
-            // LRU uses one queue to keep track of data usage in CS. The Table iterator is stored in the queue. At any time, when an entry is used or refreshed, its Table iterator is relocated to the tail of the queue. Also, when an entry is newly inserted, its Table iterator is pushed at the tail of the queue. When an entry needs to be evicted, its Table iterator is erased from the head of its queue, and the entry is erased from the Table.
-            // Could be done if we store Rc<child> and store those in some queue
+    fn register_default_route(&mut self, face: FaceToken, cost: u32) {
+        add_fib_entry(&mut self.default_routes, face, cost);
+    }
+
+    fn unregister_default_route(&mut self, face: FaceToken) -> bool {
+        remove_fib_entry(&mut self.default_routes, face)
+    }
+}
+
+impl Pit for ReferenceTables {
+    fn register_face(&mut self, face: FaceToken) {
+        if !self.faces.contains(&face) {
+            self.faces.push(face);
         }
     }
 
-    fn register_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken, cost: u32) {
-        self.root
-            .register_prefix(&mut name_prefix.components(), face, cost);
+    fn unregister_face_records(&mut self, face: FaceToken) {
+        self.faces.retain(|f| *f != face);
+        self.congested_faces.retain(|f| *f != face);
+        self.quotas.remove_face(face);
+        self.root.unregister_face_records(face);
+        // Nothing learned about the face may outlive it, since its token can be reused
+        self.learned_routes.retain(|(_, f, _)| *f != face);
+        self.strategy_choice
+            .after_face_removed(face, &mut self.measurements);
+        self.measurements.remove_face(face);
     }
 
-    fn unregister_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken) -> bool {
-        self.root
-            .unregister_prefix(&mut name_prefix.components(), face, false, false)
+    fn prune_pit_if_needed(&mut self, now: Timestamp) {
+        if self.last_prune_time.adding(self.prune_interval_ms) < now {
+            let root = &mut self.root;
+            self.learned_routes.retain(|(prefix, face, expiry)| {
+                if now < *expiry {
+                    return true;
+                }
+                root.unregister_prefix(&mut prefix.as_name().components(), *face, false);
+                false
+            });
+
+            self.pruned.timed_out.clear();
+            self.root.prune_if_needed(
+                Name::new(),
+                now,
+                &mut self.dead_nonce_list,
+                true,
+                false,
+                &mut self.pruned,
+            );
+            self.dead_nonce_list.prune(now);
+            if self.quotas.limits().max_pending_interests.is_some() {
+                let quotas = &mut self.quotas;
+                quotas.clear_pending();
+                self.root.visit(Name::new(), &mut |_, entry| {
+                    for pit in [&entry.pit_normal, &entry.pit_prefix] {
+                        pit.pit_in
+                            .iter()
+                            .for_each(|ee| quotas.add_pending(ee.reply_to));
+                    }
+                });
+            }
+            for name in self.pruned.interests.drain(..) {
+                self.strategy_choice
+                    .find_strategy(name.as_name())
+                    .after_interest_timeout(name.as_name(), now, &mut self.measurements);
+            }
+            self.measurements.prune(now);
+        }
     }
 
-    fn register_interest(
-        &mut self,
-        name: Name<'_>,
-        can_be_prefix: bool,
-        interest_lifetime: Option<u64>,
+    fn report_timed_out_interests(&mut self, report: &mut dyn FnMut(Name<'_>, &[FaceToken])) {
+        for (name, faces) in self.pruned.timed_out.drain(..) {
+            report(name.as_name(), &faces);
+        }
+    }
+
+    fn register_interest<'s>(
+        &'s mut self,
+        interest: &Interest<'_>,
         nonce: [u8; 4],
         reply_to: FaceToken,
         now: Timestamp,
-    ) -> impl Iterator<Item = FaceToken> {
+    ) -> impl Iterator<Item = FaceToken> + use<'s> {
+        let name = interest.name;
+        let forwarding_hint = interest.forwarding_hint;
+        let can_be_prefix = interest.can_be_prefix.is_some();
+        let interest_lifetime = interest.interest_lifetime.map(|x| x.val);
         self.face_scratchpad.clear();
+        self.over_quota = false;
 
         if name.component_count() == 0 {
             return self.return_faces();
@@ -123,12 +470,76 @@ impl Tables for ReferenceTables {
             return self.return_faces();
         }
 
-        let deadline = match interest_lifetime {
-            Some(ms) => now.adding(ms),
-            None => now.adding(DEFAULT_DEADLINE_INCREMENT_MS),
-        };
+        if !self.quotas.admit(reply_to, now) {
+            self.over_quota = true;
+            return self.return_faces();
+        }
+
+        if let Some(forwarding_hint) = forwarding_hint {
+            // The faces for the first delegation (in the order of preference) that has
+            //  a route are put in front, so that they are used while registering the
+            //  interest in the PIT as if they were on the path of its name.
+            // The routes back to the downstream face would only make the interest loop,
+            //  so the delegations that have no others are skipped.
+            self.root
+                .collect_routes(&mut name.components(), &mut self.face_scratchpad);
+            if self.face_scratchpad.is_empty() {
+                for delegation in forwarding_hint.delegations() {
+                    self.root
+                        .collect_routes(&mut delegation.components(), &mut self.face_scratchpad);
+                    self.face_scratchpad.retain(|(_, face)| *face != reply_to);
+                    if !self.face_scratchpad.is_empty() {
+                        break;
+                    }
+                }
+            } else {
+                self.face_scratchpad.clear();
+            }
+        }
+
+        // The default routes are only for the names without any other route, which are
+        //  also put in front
+        if self.face_scratchpad.is_empty()
+            && !self.default_routes.is_empty()
+            && !self.root.has_any_route(&mut name.components())
+        {
+            self.face_scratchpad.extend(
+                self.default_routes
+                    .iter()
+                    .rev()
+                    .map(|x| (x.cost, x.next_hop)),
+            );
+        }
+
+        // Without any route the strategy may still want the interest to go to all the faces,
+        //  which are then put in front as with the forwarding hint
+        let mut has_route = true;
+        if self.face_scratchpad.is_empty()
+            && (self.flood_route_lifetime_ms.is_some()
+                || self
+                    .strategy_choice
+                    .find_strategy(name)
+                    .floods_without_route(name))
+        {
+            self.root
+                .collect_routes(&mut name.components(), &mut self.face_scratchpad);
+            if self.face_scratchpad.is_empty() {
+                has_route = false;
+                self.face_scratchpad.extend(
+                    self.faces
+                        .iter()
+                        .rev()
+                        .filter(|face| **face != reply_to)
+                        .map(|face| (0, *face)),
+                );
+            } else {
+                self.face_scratchpad.clear();
+            }
+        }
+
+        let deadline = self.interest_lifetimes.deadline(now, interest_lifetime);
 
-        self.root.register_interest(
+        let Some((pit_entry, is_new)) = self.root.register_interest(
             name,
             &mut name.components(),
             can_be_prefix,
@@ -138,14 +549,123 @@ impl Tables for ReferenceTables {
             nonce,
             &mut self.dead_nonce_list,
             &mut self.face_scratchpad,
+        ) else {
+            self.face_scratchpad.clear();
+            return self.return_faces();
+        };
+
+        // The faces were collected in the increasing order of preference
+        self.next_hops.clear();
+        self.next_hops.extend(
+            self.face_scratchpad
+                .iter()
+                .rev()
+                .map(|(cost, face)| NextHop {
+                    face: *face,
+                    cost: *cost,
+                    congested: self.congested_faces.contains(face),
+                }),
         );
+        let (strategy, parameters) = self.strategy_choice.find_strategy_with_parameters(name);
+        let interest = PendingInterest {
+            name,
+            can_be_prefix,
+            reply_to,
+            now,
+            is_new,
+            transmission_count: pit_entry.transmission_count,
+            latest_transmission_time: pit_entry.latest_transmission_time,
+            pit_entry: PitEntryView::new(pit_entry),
+            has_route,
+            suppression: parameters.suppression(self.retransmission_suppression),
+            parameters,
+        };
+
+        self.forward_to.clear();
+        if !has_route && self.flood_route_lifetime_ms.is_some() {
+            // The strategies that do not flood would only pick one of the faces
+            if !interest.is_suppressed() {
+                forward_to_all(&self.next_hops, &mut self.forward_to);
+            }
+        } else {
+            strategy.after_receive_interest(
+                &interest,
+                &self.next_hops,
+                &mut self.measurements,
+                &mut self.forward_to,
+            );
+        }
+        if !self.forward_to.is_empty() {
+            strategy.probe(
+                &interest,
+                &self.next_hops,
+                &mut self.measurements,
+                &mut self.forward_to,
+            );
+        }
+        pit_entry.record_transmission(&self.forward_to, reply_to, nonce, now, deadline);
 
+        self.face_scratchpad.clear();
+        self.face_scratchpad
+            .extend(self.forward_to.iter().map(|face| (0, *face)));
         return self.return_faces();
     }
 
+    fn add_pit_in_record(
+        &mut self,
+        name: Name<'_>,
+        can_be_prefix: bool,
+        interest_lifetime: Option<u64>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+    ) -> bool {
+        self.over_quota = false;
+        if name.component_count() == 0 {
+            return false;
+        }
+
+        // A dead nonce would be dropped anyway, so there is nothing left to do
+        if self.dead_nonce_list.contains(name, nonce) {
+            return true;
+        }
+
+        let deadline = self.interest_lifetimes.deadline(now, interest_lifetime);
+
+        let mut entry = &mut self.root;
+        for component in name.components() {
+            entry = match entry.get_child(component) {
+                Some((child, _)) => child,
+                None => return false,
+            };
+        }
+
+        let relevant_pit = if can_be_prefix {
+            &mut entry.pit_prefix
+        } else {
+            &mut entry.pit_normal
+        };
+        if relevant_pit.pit_in.is_empty() {
+            return false;
+        }
+        if !self.quotas.admit(reply_to, now) {
+            self.over_quota = true;
+            return false;
+        }
+        relevant_pit.add_in_record(
+            name,
+            reply_to,
+            now,
+            deadline,
+            nonce,
+            &mut self.dead_nonce_list,
+        )
+    }
+
     fn satisfy_interests<H>(
         &mut self,
         name: Name<'_>,
+        from: FaceToken,
         now: Timestamp,
         digest_computation: &mut H,
     ) -> impl Iterator<Item = FaceToken>
@@ -161,6 +681,29 @@ impl Tables for ReferenceTables {
             &mut self.face_scratchpad,
             digest_computation,
         );
+        for (_, face) in self.face_scratchpad.iter() {
+            self.quotas.release(*face);
+        }
+        if !self.face_scratchpad.is_empty() {
+            let strategy = self.strategy_choice.find_strategy(name);
+            strategy.after_receive_data(name, from, now, &mut self.measurements);
+            let learned = strategy.learn_route(name, from, now).or_else(|| {
+                // Only the data for the names without a route was asked for by flooding
+                let lifetime_ms = self.flood_route_lifetime_ms?;
+                if self.root.has_any_route(&mut name.components()) {
+                    return None;
+                }
+                Some(LearnedRoute {
+                    prefix_length: name.component_count().saturating_sub(1).max(1),
+                    cost: SelfLearningStrategy::LEARNED_ROUTE_COST,
+                    lifetime_ms,
+                })
+            });
+            if let Some(route) = learned {
+                let prefix = name.prefix(route.prefix_length.min(name.component_count()));
+                self.add_learned_route(prefix, from, route, now);
+            }
+        }
 
         // Only want distinct faces
         self.face_scratchpad.sort();
@@ -168,6 +711,211 @@ impl Tables for ReferenceTables {
         return self.return_faces();
     }
 
+    fn report_nack(&mut self, name: Name<'_>, from: FaceToken, now: Timestamp) {
+        self.strategy_choice.find_strategy(name).after_receive_nack(
+            name,
+            from,
+            now,
+            &mut self.measurements,
+        );
+    }
+
+    fn process_nack(
+        &mut self,
+        name: Name<'_>,
+        can_be_prefix: bool,
+        nonce: [u8; 4],
+        reason: NackReason,
+        from: FaceToken,
+        now: Timestamp,
+    ) -> (NackOutcome, impl Iterator<Item = FaceToken>) {
+        self.face_scratchpad.clear();
+        self.forward_to.clear();
+
+        // The routes that could be tried instead, in the increasing order of preference
+        self.root
+            .collect_routes(&mut name.components(), &mut self.face_scratchpad);
+        if self.face_scratchpad.is_empty() {
+            self.face_scratchpad.extend(
+                self.default_routes
+                    .iter()
+                    .rev()
+                    .map(|x| (x.cost, x.next_hop)),
+            );
+        }
+
+        let pit_entry = self
+            .root
+            .find_pit_entry(&mut name.components(), can_be_prefix)
+            .filter(|pit_entry| pit_entry.has_out_record(from, nonce));
+        let Some(pit_entry) = pit_entry else {
+            self.face_scratchpad.clear();
+            return (NackOutcome::Unmatched, self.return_faces());
+        };
+        pit_entry.record_nack(from, reason);
+        let (strategy, parameters) = self.strategy_choice.find_strategy_with_parameters(name);
+        strategy.after_receive_nack(name, from, now, &mut self.measurements);
+
+        // The strategy picks again among the routes the interest was not sent to yet,
+        //  as for a new interest. The downstreams are never tried.
+        self.next_hops.clear();
+        self.next_hops.extend(
+            self.face_scratchpad
+                .iter()
+                .rev()
+                .filter(|(_, face)| {
+                    !pit_entry.pit_out.iter().any(|out| out.face == *face)
+                        && !pit_entry.pit_in.iter().any(|ee| ee.reply_to == *face)
+                })
+                .map(|(cost, face)| NextHop {
+                    face: *face,
+                    cost: *cost,
+                    congested: self.congested_faces.contains(face),
+                }),
+        );
+        let downstream = pit_entry
+            .pit_in
+            .iter()
+            .find(|ee| ee.last_nonce == nonce)
+            .map(|ee| (ee.reply_to, ee.expiry));
+        if let Some((reply_to, expiry)) = downstream {
+            if !self.next_hops.is_empty() {
+                let interest = PendingInterest {
+                    name,
+                    can_be_prefix,
+                    reply_to,
+                    now,
+                    is_new: true,
+                    transmission_count: pit_entry.transmission_count,
+                    latest_transmission_time: pit_entry.latest_transmission_time,
+                    pit_entry: PitEntryView::new(pit_entry),
+                    has_route: true,
+                    suppression: parameters.suppression(self.retransmission_suppression),
+                    parameters,
+                };
+                strategy.after_receive_interest(
+                    &interest,
+                    &self.next_hops,
+                    &mut self.measurements,
+                    &mut self.forward_to,
+                );
+                pit_entry.record_transmission(&self.forward_to, reply_to, nonce, now, expiry);
+            }
+        }
+
+        self.face_scratchpad.clear();
+        if !self.forward_to.is_empty() {
+            self.face_scratchpad
+                .extend(self.forward_to.iter().map(|face| (0, *face)));
+            return (NackOutcome::Retried, self.return_faces());
+        }
+        if pit_entry.has_pending_out_records(now) {
+            return (NackOutcome::Pending, self.return_faces());
+        }
+
+        // All the upstreams failed, so the downstreams are told rather than left waiting
+        pit_entry.satisfy(
+            name,
+            now,
+            &mut self.dead_nonce_list,
+            &mut self.face_scratchpad,
+        );
+        for (_, face) in self.face_scratchpad.iter() {
+            self.quotas.release(*face);
+        }
+        self.root.remove_empty_entries(&mut name.components());
+        (NackOutcome::AllUpstreamsFailed, self.return_faces())
+    }
+
+    fn list_pit_entries(&self, report: &mut dyn FnMut(PitEntryInfo<'_>)) {
+        let mut in_records = Vec::new();
+        let mut out_records = Vec::new();
+        self.root.visit(Name::new(), &mut |name, entry| {
+            for (pit, can_be_prefix) in [(&entry.pit_normal, false), (&entry.pit_prefix, true)] {
+                if pit.pit_in.is_empty() {
+                    continue;
+                }
+                in_records.clear();
+                in_records.extend(pit.in_records());
+                out_records.clear();
+                out_records.extend(pit.out_records());
+                report(PitEntryInfo {
+                    name,
+                    can_be_prefix,
+                    in_records: &in_records,
+                    out_records: &out_records,
+                });
+            }
+        });
+    }
+
+    fn set_flood_without_route(&mut self, route_lifetime_ms: Option<u64>) {
+        self.flood_route_lifetime_ms = route_lifetime_ms;
+    }
+
+    fn set_strategy_parameters(&mut self, prefix: Name<'_>, parameters: Option<Name<'_>>) -> bool {
+        match parameters {
+            Some(parameters) => self.strategy_choice.set_parameters(prefix, parameters),
+            None => {
+                self.strategy_choice.unset_parameters(prefix);
+            }
+        }
+        true
+    }
+
+    fn set_face_congested(&mut self, face: FaceToken, congested: bool) {
+        self.congested_faces.retain(|f| *f != face);
+        if congested {
+            self.congested_faces.push(face);
+        }
+    }
+
+    fn set_pit_params(&mut self, params: &TableParams) {
+        self.dead_nonce_duration_ms = params.dead_nonce_duration_ms;
+        self.dead_nonce_list
+            .set_duration(params.dead_nonce_duration_ms);
+        self.interest_lifetimes = InterestLifetimes::from_params(params);
+        self.prune_interval_ms = params.prune_interval_ms;
+        self.retransmission_suppression = params.retransmission_suppression;
+        self.quotas.set_limits(params.face_limits);
+    }
+
+    fn interest_over_quota(&self) -> bool {
+        self.over_quota
+    }
+}
+
+// The cache and the PIT are looked up one after the other, which is easier to follow
+impl Tables for ReferenceTables {}
+
+impl ContentStore for ReferenceTables {
+    fn prune_cs_if_needed(&mut self, now: Timestamp) {
+        if self.last_prune_time.adding(self.prune_interval_ms) < now {
+            self.pruned.data.clear();
+            self.root.prune_if_needed(
+                Name::new(),
+                now,
+                &mut self.dead_nonce_list,
+                false,
+                true,
+                &mut self.pruned,
+            );
+            for removed in self.pruned.data.drain(..) {
+                self.replacement_policy.on_remove(removed);
+                if let Some((_, size)) = self.cs_names.remove(&removed) {
+                    self.cs_bytes -= size;
+                }
+            }
+
+            // TODO: we could check the CS count here and if it is too big
+            //  could prune with now = (actual_now - 0.5 * data_cache_duration_ms), then 0.75, etc.
+
+            // LRU cache policy LRU cache policy implements the Least Recently Used cache replacement algorithm, which discards the least recently used items first. LRU evicts upon every insertion, because its performance is more predictable; the alternative, periodic cleanup of a batch of entries, can cause jitter in packet forwarding.
+            // LRU uses one queue to keep track of data usage in CS. The Table iterator is stored in the queue. At any time, when an entry is used or refreshed, its Table iterator is relocated to the tail of the queue. Also, when an entry is newly inserted, its Table iterator is pushed at the tail of the queue. When an entry needs to be evicted, its Table iterator is erased from the head of its queue, and the entry is erased from the Table.
+            // Could be done if we store Rc<child> and store those in some queue
+        }
+    }
+
     fn insert_data<'a>(
         &mut self,
         name: Name<'a>,
@@ -176,14 +924,37 @@ impl Tables for ReferenceTables {
         now: Timestamp,
         packet: &'a [u8],
     ) {
-        self.root.insert_data(
-            &mut name.components(),
+        if !self.admission_policy.admit(name, packet.len(), freshness) {
+            return;
+        }
+        let mut replaced = Vec::new();
+        self.root
+            .remove_stale_versions(&mut name.components(), digest, now, &mut replaced);
+        for removed in replaced {
+            self.replacement_policy.on_remove(removed);
+            if let Some((_, size)) = self.cs_names.remove(&removed) {
+                self.cs_bytes -= size;
+            }
+        }
+
+        let id = CsEntryId(self.next_cs_entry_id);
+        let entry = NewDataEntry {
+            id,
             digest,
-            freshness,
-            now,
-            self.data_cache_duration_ms,
             packet,
-        );
+            freshness_deadline: now.adding(freshness),
+            removal_deadline: now.adding(self.data_cache_duration_ms),
+        };
+        let inserted = self.root.insert_data(&mut name.components(), &entry);
+        if inserted {
+            self.next_cs_entry_id += 1;
+            let full_name =
+                NameBuf::from(name).pushing(NameComponent::implicit_sha256(digest.as_slice()));
+            self.replacement_policy.on_insert(id, full_name.as_name());
+            self.cs_names.insert(id, (full_name, packet.len()));
+            self.cs_bytes += packet.len();
+            self.evict_if_needed();
+        }
     }
 
     fn get_data<'a>(
@@ -193,35 +964,104 @@ impl Tables for ReferenceTables {
         must_be_fresh: bool,
         now: Timestamp,
     ) -> Option<&[u8]> {
-        self.root.get_data(
+        let entry = self.root.get_data(
             &mut name.components(),
             can_be_prefix,
             must_be_fresh,
             now,
             self.data_cache_duration_ms,
-        )
+            self.can_be_prefix_selection,
+        )?;
+        self.replacement_policy.on_hit(entry.id);
+        Some(entry.data.as_ref())
+    }
+
+    fn report_expiring_data(
+        &mut self,
+        now: Timestamp,
+        lead_time_ms: u64,
+        min_hits: u32,
+        report: &mut dyn FnMut(Name<'_>),
+    ) {
+        let deadline = now.adding(lead_time_ms);
+        self.root
+            .report_expiring_data(Name::new(), now, deadline, min_hits, report);
+    }
+
+    fn content_store_usage(&self) -> ContentStoreUsage {
+        ContentStoreUsage {
+            entries: self.cs_names.len(),
+            bytes: self.cs_bytes,
+        }
+    }
+
+    fn list_cs_entries(&self, report: &mut dyn FnMut(CsEntryInfo<'_>)) {
+        self.root.visit(Name::new(), &mut |name, entry| {
+            if let Some(data) = &entry.data {
+                report(CsEntryInfo {
+                    name,
+                    bytes: data.data.len(),
+                    fresh_until: data.freshness_deadline,
+                    expiry: data.removal_deadline,
+                });
+            }
+        });
+    }
+
+    // The data already cached keeps its removal deadline until it is used again
+    fn set_cs_params(&mut self, params: &TableParams) {
+        self.data_cache_duration_ms = params.data_cache_duration_ms;
+        self.prune_interval_ms = params.prune_interval_ms;
+        self.can_be_prefix_selection = params.can_be_prefix_selection;
+    }
+
+    // The oldest packets go first when there is a limit
+    fn erase_data(&mut self, prefix: Name<'_>, limit: Option<usize>) -> usize {
+        let erased: Vec<CsEntryId> = self
+            .cs_names
+            .iter()
+            .filter(|(_, (name, _))| prefix.is_prefix_of(name.as_name()))
+            .map(|(id, _)| *id)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        for id in erased.iter() {
+            self.replacement_policy.on_remove(*id);
+            if let Some((name, size)) = self.cs_names.remove(id) {
+                self.root.remove_data(&mut name.as_name().components());
+                self.cs_bytes -= size;
+            }
+        }
+        erased.len()
     }
 }
 
-#[derive(Clone, Ord, PartialOrd, Eq, PartialEq)]
-struct EncodedComponent {
-    typ: NonZeroU16,
-    bytes: Box<[u8]>,
+#[derive(Clone, Eq, PartialEq)]
+pub(super) struct EncodedComponent {
+    pub(super) typ: NonZeroU16,
+    pub(super) bytes: Box<[u8]>,
 }
 
 impl EncodedComponent {
-    fn from_named_component<'a>(component: NameComponent<'a>) -> Self {
+    pub(super) fn from_named_component<'a>(component: NameComponent<'a>) -> Self {
         Self {
             typ: component.typ,
             bytes: Box::from(component.bytes),
         }
     }
 
-    fn compare_to_name_component<'a>(&self, component: NameComponent<'a>) -> Ordering {
-        if self.typ == component.typ {
-            return self.bytes.as_ref().cmp(component.bytes);
+    // The canonical order of the components: by type, then by length, then by bytes
+    pub(super) fn compare_to_name_component<'a>(&self, component: NameComponent<'a>) -> Ordering {
+        self.typ
+            .cmp(&component.typ)
+            .then(self.bytes.len().cmp(&component.bytes.len()))
+            .then_with(|| self.bytes.as_ref().cmp(component.bytes))
+    }
+
+    pub(super) fn as_name_component(&self) -> NameComponent<'_> {
+        NameComponent {
+            typ: self.typ,
+            bytes: &self.bytes,
         }
-        self.typ.cmp(&component.typ)
     }
 }
 
@@ -231,7 +1071,9 @@ struct TableEntry {
     pit_normal: PitEntry,
     pit_prefix: PitEntry,
     data: Option<DataEntry>,
-    // Ordered by EncodedComponent
+    // Replaces the cost order of the FIB entry
+    ranking: Option<Box<dyn NextHopRanking>>,
+    // In the canonical order of the components
     children: Vec<(EncodedComponent, TableEntry)>,
 }
 
@@ -242,10 +1084,61 @@ impl TableEntry {
             pit_normal: PitEntry::new(),
             pit_prefix: PitEntry::new(),
             data: None,
+            ranking: None,
             children: Vec::new(),
         }
     }
 
+    fn get_or_insert_entry<'a, I>(&mut self, remaining_components: &mut I) -> &mut TableEntry
+    where
+        I: Iterator<Item = NameComponent<'a>>,
+    {
+        match remaining_components.next() {
+            Some(component) => self
+                .get_or_insert_child(component)
+                .get_or_insert_entry(remaining_components),
+            None => self,
+        }
+    }
+
+    fn unset_ranking<'a, I>(&mut self, remaining_components: &mut I) -> bool
+    where
+        I: Iterator<Item = NameComponent<'a>>,
+    {
+        match remaining_components.next() {
+            Some(component) => {
+                let Some((child, idx)) = self.get_child(component) else {
+                    return false;
+                };
+                let removed = child.unset_ranking(remaining_components);
+                if child.is_empty() {
+                    self.children.remove(idx);
+                }
+                removed
+            }
+            None => self.ranking.take().is_some(),
+        }
+    }
+
+    // Adds the faces of the FIB entry in the increasing order of preference
+    fn add_ranked_faces(&mut self, nonce: [u8; 4], faces: &mut Vec<(u32, FaceToken)>) {
+        let Some(ranking) = self.ranking.as_mut() else {
+            faces.extend(self.fib.iter().rev().map(|x| (x.cost, x.next_hop)));
+            return;
+        };
+        let mut next_hops: Vec<NextHop> = self
+            .fib
+            .iter()
+            .map(|x| NextHop {
+                face: x.next_hop,
+                cost: x.cost,
+                congested: false,
+            })
+            .collect();
+        ranking.rank(&mut next_hops, nonce);
+        faces.extend(next_hops.iter().rev().map(|x| (x.cost, x.face)));
+    }
+
     fn insert_child<'a>(&mut self, index: usize, component: NameComponent<'a>) {
         let comp = EncodedComponent::from_named_component(component);
         let entry = TableEntry::new();
@@ -286,16 +1179,7 @@ impl TableEntry {
                 .register_prefix(remaining_components, face, cost)
         } else {
             // No more components, can add to this node's FIB
-            // Check if already present
-            if let Some(index) = self.fib.iter().position(|y| y.next_hop == face) {
-                self.fib[index].cost = cost
-            } else {
-                self.fib.push(FibEntry {
-                    cost,
-                    next_hop: face,
-                });
-            }
-            self.fib.sort();
+            add_fib_entry(&mut self.fib, face, cost);
         }
     }
 
@@ -304,21 +1188,20 @@ impl TableEntry {
         remaining_components: &mut I,
         face: FaceToken,
         recursive: bool,
-        remove_pit: bool,
     ) -> bool
     where
         I: Iterator<Item = NameComponent<'a>>,
     {
         if let Some(component) = remaining_components.next() {
             // There are more components, so we need to check children
-            match self.get_child(component) {
-                Some(child) => {
-                    child
-                        .0
-                        .unregister_prefix(remaining_components, face, recursive, remove_pit)
-                }
-                None => return false,
+            let Some((child, index)) = self.get_child(component) else {
+                return false;
+            };
+            let removed = child.unregister_prefix(remaining_components, face, recursive);
+            if child.is_empty() {
+                self.children.remove(index);
             }
+            removed
         } else {
             // No more components, can remove from this node's FIB, if present
             let mut any_removed = false;
@@ -326,14 +1209,10 @@ impl TableEntry {
                 self.fib.remove(index);
                 any_removed = true
             }
-            if remove_pit {
-                self.pit_normal.pit_in.retain(|x| x.reply_to != face);
-                self.pit_prefix.pit_in.retain(|x| x.reply_to != face);
-            }
             if recursive {
                 for cc in self.children.iter_mut() {
                     any_removed |=
-                        cc.1.unregister_prefix(remaining_components, face, recursive, remove_pit)
+                        cc.1.unregister_prefix(remaining_components, face, recursive)
                 }
             }
             self.children.retain(|c| !c.1.is_empty());
@@ -341,6 +1220,103 @@ impl TableEntry {
         }
     }
 
+    fn unregister_face_records(&mut self, face: FaceToken) {
+        self.pit_normal.remove_face_records(face);
+        self.pit_prefix.remove_face_records(face);
+        for cc in self.children.iter_mut() {
+            cc.1.unregister_face_records(face);
+        }
+        self.children.retain(|c| !c.1.is_empty());
+    }
+
+    fn has_route<'a, I>(&self, remaining_components: &mut I, face: FaceToken) -> bool
+    where
+        I: Iterator<Item = NameComponent<'a>>,
+    {
+        match remaining_components.next() {
+            Some(component) => match self
+                .children
+                .binary_search_by(|x| x.0.compare_to_name_component(component))
+            {
+                Ok(idx) => self.children[idx].1.has_route(remaining_components, face),
+                Err(_) => false,
+            },
+            None => self.fib.iter().any(|x| x.next_hop == face),
+        }
+    }
+
+    fn has_any_route<'a, I>(&self, remaining_components: &mut I) -> bool
+    where
+        I: Iterator<Item = NameComponent<'a>>,
+    {
+        if !self.fib.is_empty() {
+            return true;
+        }
+        match remaining_components.next() {
+            Some(component) => match self
+                .children
+                .binary_search_by(|x| x.0.compare_to_name_component(component))
+            {
+                Ok(idx) => self.children[idx].1.has_any_route(remaining_components),
+                Err(_) => false,
+            },
+            None => false,
+        }
+    }
+
+    fn find_pit_entry<'a, I>(
+        &mut self,
+        remaining_components: &mut I,
+        can_be_prefix: bool,
+    ) -> Option<&mut PitEntry>
+    where
+        I: Iterator<Item = NameComponent<'a>>,
+    {
+        match remaining_components.next() {
+            Some(component) => self
+                .get_child(component)?
+                .0
+                .find_pit_entry(remaining_components, can_be_prefix),
+            None if can_be_prefix => Some(&mut self.pit_prefix),
+            None => Some(&mut self.pit_normal),
+        }
+    }
+
+    // Removes the entries along the path of the name that were left empty
+    fn remove_empty_entries<'a, I>(&mut self, remaining_components: &mut I)
+    where
+        I: Iterator<Item = NameComponent<'a>>,
+    {
+        let Some(component) = remaining_components.next() else {
+            return;
+        };
+        let Some((child, idx)) = self.get_child(component) else {
+            return;
+        };
+        child.remove_empty_entries(remaining_components);
+        if child.is_empty() {
+            self.children.remove(idx);
+        }
+    }
+
+    // Adds the FIB entries along the path of the name, in the same order as register_interest
+    fn collect_routes<'a, I>(&self, remaining_components: &mut I, faces: &mut Vec<(u32, FaceToken)>)
+    where
+        I: Iterator<Item = NameComponent<'a>>,
+    {
+        faces.extend(self.fib.iter().rev().map(|x| (x.cost, x.next_hop)));
+        if let Some(component) = remaining_components.next() {
+            if let Ok(idx) = self
+                .children
+                .binary_search_by(|x| x.0.compare_to_name_component(component))
+            {
+                self.children[idx]
+                    .1
+                    .collect_routes(remaining_components, faces)
+            }
+        }
+    }
+
     fn register_interest<'a, I>(
         &mut self,
         name: Name<'a>,
@@ -352,19 +1328,23 @@ impl TableEntry {
         nonce: [u8; 4],
         dead_nonce_list: &mut DeadNonceList,
         faces: &mut Vec<(u32, FaceToken)>,
-    ) where
+    ) -> Option<(&mut PitEntry, bool)>
+    where
         I: Iterator<Item = NameComponent<'a>>,
     {
         // The task is twofold:
         // - Register the interest in the necessary PIT so we know whom to
         //      notify when we get the suitable data
-        // - Populate the "faces" array with faces we should _forward_ the interest to,
+        // - Populate the "faces" array with faces we could _forward_ the interest to,
         //      which in general can be any face that is in the prefix of the interest name.
+        // The PIT entry is returned, along with whether it is new, unless the interest
+        //  must not be forwarded at all, and the strategy then picks among the faces.
 
         // We are adding all the faces in this node's FIB to "faces" as they could be used to
         //  to forward the interest. We ignore the possible duplicates of faces along the way
-        //  and add the faces in reverse cost order (assuming they are sorted in ascending cost in FIB).
-        faces.extend(self.fib.iter().rev().map(|x| (x.cost, x.next_hop)));
+        //  and add the faces in reverse cost order (assuming they are sorted in ascending cost in FIB),
+        //  unless the entry has its own ranking.
+        self.add_ranked_faces(nonce, faces);
 
         if let Some(component) = remaining_components.next() {
             // There are more components, so we need to go to children to use their PIT
@@ -376,14 +1356,14 @@ impl TableEntry {
                 Err(idx) => {
                     if faces.len() == 0 {
                         // There are no valid faces on this path so far so we do not even try to create a PIT
-                        return;
+                        return None;
                     }
                     self.insert_child(idx, component);
                     idx
                 }
             };
 
-            self.children[idx].1.register_interest(
+            return self.children[idx].1.register_interest(
                 name,
                 remaining_components,
                 can_be_prefix,
@@ -393,13 +1373,13 @@ impl TableEntry {
                 nonce,
                 dead_nonce_list,
                 faces,
-            )
+            );
         } else {
             // This is the final name component, will work with this node's PIT
 
             if faces.len() == 0 {
                 // There are no valid faces on this path so far so we do not even try to create a PIT
-                return;
+                return None;
             }
 
             let relevant_pit = if can_be_prefix {
@@ -409,85 +1389,9 @@ impl TableEntry {
             };
 
             // Once we are here, the "faces" contain all the relevant faces in _increased_ priority
-            // We need to prune it and leave only the faces to forward on in _ascending_ order
-
-            if relevant_pit.pit_in.len() == 0 {
-                // The PIT entry is new, so we always transmit on the highest-priority face
-                relevant_pit.pit_in.push(PitInEntry {
-                    reply_to,
-                    last_nonce: nonce,
-                });
-                relevant_pit.removal_deadline = deadline;
-                relevant_pit.latest_transmission_time = now;
-                relevant_pit.transmission_count = 1;
-                let reply_to = faces[faces.len() - 1];
-                faces.clear();
-                faces.push(reply_to);
-                return;
-            }
-
-            relevant_pit.removal_deadline = relevant_pit.removal_deadline.max(deadline);
-
-            // We next check for nonce loops
-            let mut nonce_loop = false;
-            let mut reply_to_found = false;
-            for ff in relevant_pit.pit_in.iter_mut() {
-                if ff.last_nonce == nonce {
-                    nonce_loop = true;
-                }
-                if ff.reply_to == reply_to {
-                    if ff.last_nonce != nonce {
-                        // Updating the nonce on the entry and storing the old one in dead ones
-                        dead_nonce_list.insert(name, nonce, now);
-                        ff.last_nonce = nonce;
-                    }
-                    reply_to_found = true;
-                }
-            }
-
-            if !reply_to_found {
-                // Adding the in entry if it was not there
-                relevant_pit.pit_in.push(PitInEntry {
-                    reply_to,
-                    last_nonce: nonce,
-                });
-            }
-
-            if nonce_loop {
-                // We have a likely loop, so we do not forward
-                faces.clear();
-                return;
-            }
-
-            // We next check if we should suppress this interest
-            let minimum_retransamission_delay = MIN_RETRANSMISSION_DELAY_MS
-                * (1 << relevant_pit
-                    .transmission_count
-                    .min(MAX_RETRANSMISSION_DELAY_DOUBLINGS));
-            if now
-                < relevant_pit
-                    .latest_transmission_time
-                    .adding(minimum_retransamission_delay)
-            {
-                // The interest is not forwarded due to retransmission suppression
-                faces.clear();
-                return;
-            }
-
-            // TODO: if we use more complex strategies, e.g. probabilistic ones, we can use the
-            //  incoming nonce as the source of randomness (perhaps merging it with local state)
-
-            // We now know that we should forward and pick the latest unused face
-            // We pick the face using only the index of the transmission, which assumes
-            //  that FIB is stable, but changes to FIB are not critical for correctness.
-            relevant_pit.latest_transmission_time = now;
-            relevant_pit.transmission_count += 1;
-            // We go through all faces one by one _from the end_ wrapping around the list as needed.
-            let face_idx =
-                faces.len() - 1 - (relevant_pit.transmission_count as usize % faces.len());
-            let reply_to = faces[face_idx];
-            faces.clear();
-            faces.push(reply_to);
+            let is_new =
+                relevant_pit.register(name, reply_to, now, deadline, nonce, dead_nonce_list)?;
+            Some((relevant_pit, is_new))
         }
     }
 
@@ -564,43 +1468,93 @@ impl TableEntry {
         }
     }
 
-    fn insert_data<'a, I>(
-        &mut self,
-        remaining_components: &mut I,
-        digest: [u8; 32],
-        freshness: u64,
-        now: Timestamp,
-        data_cache_duration_ms: u64,
-        packet: &'a [u8],
-    ) where
+    fn insert_data<'a, I>(&mut self, remaining_components: &mut I, new: &NewDataEntry<'_>) -> bool
+    where
         I: Iterator<Item = NameComponent<'a>>,
     {
         if let Some(component) = remaining_components.next() {
             // There are more normal components, so we need to go to children
-            self.get_or_insert_child(component).insert_data(
-                remaining_components,
-                digest,
-                freshness,
-                now,
-                data_cache_duration_ms,
-                packet,
-            );
+            self.get_or_insert_child(component)
+                .insert_data(remaining_components, new)
         } else {
             // We get to the implicit digest component
-            let child = self.get_or_insert_child(NameComponent::implicit_sha256(digest.as_slice()));
+            let digest = NameComponent::implicit_sha256(new.digest.as_slice());
+            let child = self.get_or_insert_child(digest);
             match child.data.as_mut() {
                 Some(entry) => {
-                    debug_assert!(packet == entry.data.as_ref());
-                    entry.freshness_deadline = entry.freshness_deadline.max(now.adding(freshness));
+                    // The same digest means the same packet
+                    debug_assert!(new.packet == entry.data.as_ref());
+                    if new.freshness_deadline > entry.freshness_deadline {
+                        // The data was refreshed, so its popularity is counted anew
+                        entry.freshness_deadline = new.freshness_deadline;
+                        entry.hits = 0;
+                        entry.expiry_reported = false;
+                    }
+                    false
                 }
                 None => {
                     child.data = Some(DataEntry {
-                        data: Box::from(packet),
-                        freshness_deadline: now.adding(freshness),
-                        removal_deadline: now.adding(data_cache_duration_ms),
-                    })
+                        id: new.id,
+                        data: Box::from(new.packet),
+                        freshness_deadline: new.freshness_deadline,
+                        removal_deadline: new.removal_deadline,
+                        hits: 0,
+                        expiry_reported: false,
+                    });
+                    true
+                }
+            }
+        }
+    }
+
+    // The versions of the data with the same name are kept by their digest, but the
+    //  stale ones are replaced when another version arrives
+    fn remove_stale_versions<'a, I>(
+        &mut self,
+        remaining_components: &mut I,
+        digest: [u8; 32],
+        now: Timestamp,
+        removed: &mut Vec<CsEntryId>,
+    ) where
+        I: Iterator<Item = NameComponent<'a>>,
+    {
+        if let Some(component) = remaining_components.next() {
+            if let Some((child, idx)) = self.get_child(component) {
+                child.remove_stale_versions(remaining_components, digest, now, removed);
+                if child.is_empty() {
+                    self.children.remove(idx);
+                }
+            }
+            return;
+        }
+        let digest_component = NameComponent::implicit_sha256(digest.as_slice());
+        for cc in self.children.iter_mut() {
+            if cc.0.compare_to_name_component(digest_component) == Ordering::Equal {
+                continue;
+            }
+            if let Some(entry) = cc.1.data.take_if(|entry| entry.freshness_deadline < now) {
+                removed.push(entry.id);
+            }
+        }
+        self.children.retain(|cc| !cc.1.is_empty());
+    }
+
+    fn remove_data<'a, I>(&mut self, remaining_components: &mut I) -> bool
+    where
+        I: Iterator<Item = NameComponent<'a>>,
+    {
+        match remaining_components.next() {
+            Some(component) => {
+                let Some((child, idx)) = self.get_child(component) else {
+                    return false;
+                };
+                let removed = child.remove_data(remaining_components);
+                if child.is_empty() {
+                    self.children.remove(idx);
                 }
+                removed
             }
+            None => self.data.take().is_some(),
         }
     }
 
@@ -610,9 +1564,9 @@ impl TableEntry {
         can_be_prefix: bool,
         must_be_fresh: bool,
         now: Timestamp,
-
         data_cache_duration_ms: u64,
-    ) -> Option<&[u8]>
+        selection: CanBePrefixSelection,
+    ) -> Option<&DataEntry>
     where
         I: Iterator<Item = NameComponent<'a>>,
     {
@@ -625,75 +1579,181 @@ impl TableEntry {
                     must_be_fresh,
                     now,
                     data_cache_duration_ms,
+                    selection,
                 );
             } else {
                 return None;
             }
+        } else if can_be_prefix {
+            // If this can be prefix, we descend into children recursively
+            self.check_for_data_recursively(must_be_fresh, now, data_cache_duration_ms, selection)
         } else {
             // There are no components, so we need to satisfy from this entry
-            if let Some(entry) = self.data.as_mut() {
-                if !must_be_fresh || (must_be_fresh && now <= entry.freshness_deadline) {
-                    entry.removal_deadline = now.adding(data_cache_duration_ms);
-                    return Some(entry.data.as_ref());
-                }
+            if let Some(entry) =
+                Self::use_data(&mut self.data, must_be_fresh, now, data_cache_duration_ms)
+            {
+                return Some(entry);
             }
 
-            if can_be_prefix {
-                // If this can be prefix, we descend into children recursively
-                for cc in self.children.iter_mut() {
-                    if let Some(data) =
-                        cc.1.check_for_data_recursively(must_be_fresh, now, data_cache_duration_ms)
-                    {
-                        return Some(data);
-                    }
-                }
-            } else {
-                // Otherwise, we could also satisfy the interest if the query was without digest
-                //  (it is fine to just go to children since if we are already in the digest
-                //  entry there will be no children)
-                for cc in self.children.iter_mut() {
-                    if let Some(entry) = cc.1.data.as_mut() {
-                        if !must_be_fresh || (must_be_fresh && now <= entry.freshness_deadline) {
-                            entry.removal_deadline = now.adding(data_cache_duration_ms);
-                            return Some(entry.data.as_ref());
-                        }
-                    }
-                }
+            // Otherwise, we could also satisfy the interest if the query was without digest
+            //  (it is fine to just go to children since if we are already in the digest
+            //  entry there will be no children). Of the versions with the same name
+            //  the one that stays fresh for the longest is preferred.
+            let freshest = self
+                .children
+                .iter_mut()
+                .filter_map(|cc| cc.1.data.as_mut())
+                .filter(|entry| !must_be_fresh || now <= entry.freshness_deadline)
+                .max_by_key(|entry| entry.freshness_deadline);
+            if let Some(entry) = freshest {
+                entry.removal_deadline = now.adding(data_cache_duration_ms);
+                entry.hits = entry.hits.saturating_add(1);
+                return Some(entry);
             }
             None
         }
     }
 
+    // The children are in the canonical order, and the name of an entry comes before
+    //  the names under it, so the leftmost data is found going forward and the rightmost
+    //  going backward
     fn check_for_data_recursively(
         &mut self,
         must_be_fresh: bool,
         now: Timestamp,
         data_cache_duration_ms: u64,
-    ) -> Option<&[u8]> {
-        if let Some(entry) = self.data.as_mut() {
-            if !must_be_fresh || (must_be_fresh && now <= entry.freshness_deadline) {
-                entry.removal_deadline = now.adding(data_cache_duration_ms);
-                return Some(entry.data.as_ref());
+        selection: CanBePrefixSelection,
+    ) -> Option<&DataEntry> {
+        let usable = |entry: &DataEntry| !must_be_fresh || now <= entry.freshness_deadline;
+        if selection == CanBePrefixSelection::Leftmost && self.data.as_ref().is_some_and(usable) {
+            return Self::use_data(&mut self.data, must_be_fresh, now, data_cache_duration_ms);
+        }
+
+        let mut forward;
+        let mut backward;
+        let children: &mut dyn Iterator<Item = &mut (EncodedComponent, TableEntry)> =
+            match selection {
+                CanBePrefixSelection::Leftmost => {
+                    forward = self.children.iter_mut();
+                    &mut forward
+                }
+                CanBePrefixSelection::Rightmost => {
+                    backward = self.children.iter_mut().rev();
+                    &mut backward
+                }
+            };
+        for cc in children {
+            if let Some(data) = cc.1.check_for_data_recursively(
+                must_be_fresh,
+                now,
+                data_cache_duration_ms,
+                selection,
+            ) {
+                return Some(data);
             }
         }
 
+        if selection == CanBePrefixSelection::Rightmost {
+            return Self::use_data(&mut self.data, must_be_fresh, now, data_cache_duration_ms);
+        }
+        None
+    }
+
+    // The data if it can satisfy the interest, which then counts as its use
+    fn use_data(
+        data: &mut Option<DataEntry>,
+        must_be_fresh: bool,
+        now: Timestamp,
+        data_cache_duration_ms: u64,
+    ) -> Option<&DataEntry> {
+        let entry = data.as_mut()?;
+        if must_be_fresh && now > entry.freshness_deadline {
+            return None;
+        }
+        entry.removal_deadline = now.adding(data_cache_duration_ms);
+        entry.hits = entry.hits.saturating_add(1);
+        Some(entry)
+    }
+
+    fn prune_if_needed(
+        &mut self,
+        name_so_far: Name<'_>,
+        now: Timestamp,
+        dead_nonce_list: &mut DeadNonceList,
+        prune_pit: bool,
+        prune_data: bool,
+        pruned: &mut PrunedEntries,
+    ) {
+        // First, we ask all chidren to clean up
         for cc in self.children.iter_mut() {
-            if let Some(data) =
-                cc.1.check_for_data_recursively(must_be_fresh, now, data_cache_duration_ms)
-            {
-                return Some(data);
+            let component = NameComponent {
+                typ: cc.0.typ,
+                bytes: &cc.0.bytes,
+            };
+            let comp = &[component];
+            let name_so_far = name_so_far.adding_components(comp);
+            cc.1.prune_if_needed(
+                name_so_far,
+                now,
+                dead_nonce_list,
+                prune_pit,
+                prune_data,
+                pruned,
+            );
+        }
+
+        // Then we only keep the children that are not empty
+        self.children.retain(|cc| !cc.1.is_empty());
+
+        // Then we clean up ourselves
+        // Prune stale data
+        if let Some(entry) = &self.data {
+            if prune_data && entry.removal_deadline < now {
+                pruned.data.push(entry.id);
+                self.data = None;
+            }
+        }
+
+        // Prune stale PIT in-records, and the entries once they have none left
+        for pit in [&mut self.pit_normal, &mut self.pit_prefix] {
+            if prune_pit && !pit.pit_in.is_empty() {
+                let mut expired = Vec::new();
+                pit.expire_in_records(name_so_far, now, dead_nonce_list, &mut expired);
+                if !expired.is_empty() {
+                    pruned.timed_out.push((NameBuf::from(name_so_far), expired));
+                }
+                if pit.pit_in.is_empty() {
+                    pruned.interests.push(NameBuf::from(name_so_far));
+                    pit.reset(name_so_far, now, dead_nonce_list);
+                }
             }
         }
-        None
+
+        // If this entry is empty the parent will clean it up
     }
 
-    fn prune_if_needed(
+    // Calls "visit" for this entry and all the ones under it
+    fn visit(&self, name_so_far: Name<'_>, visit: &mut dyn FnMut(Name<'_>, &TableEntry)) {
+        visit(name_so_far, self);
+        for cc in self.children.iter() {
+            let component = NameComponent {
+                typ: cc.0.typ,
+                bytes: &cc.0.bytes,
+            };
+            let comp = &[component];
+            let name_so_far = name_so_far.adding_components(comp);
+            cc.1.visit(name_so_far, visit);
+        }
+    }
+
+    fn report_expiring_data(
         &mut self,
         name_so_far: Name<'_>,
         now: Timestamp,
-        dead_nonce_list: &mut DeadNonceList,
+        deadline: Timestamp,
+        min_hits: u32,
+        report: &mut dyn FnMut(Name<'_>),
     ) {
-        // First, we ask all chidren to clean up
         for cc in self.children.iter_mut() {
             let component = NameComponent {
                 typ: cc.0.typ,
@@ -701,58 +1761,125 @@ impl TableEntry {
             };
             let comp = &[component];
             let name_so_far = name_so_far.adding_components(comp);
-            cc.1.prune_if_needed(name_so_far, now, dead_nonce_list);
+            cc.1.report_expiring_data(name_so_far, now, deadline, min_hits, report);
         }
 
-        // Then we only keep the children that are not empty
-        self.children.retain(|cc| !cc.1.is_empty());
-
-        // Then we clean up ourselves
-        // Prune stale data
-        if let Some(entry) = &self.data {
-            if entry.removal_deadline < now {
-                self.data = None;
+        // Data that is already stale is not reported, since it is too late to refresh ahead
+        if let Some(entry) = self.data.as_mut() {
+            if !entry.expiry_reported
+                && entry.hits >= min_hits
+                && now < entry.freshness_deadline
+                && entry.freshness_deadline <= deadline
+            {
+                entry.expiry_reported = true;
+                // The data entry is in the child named with the implicit digest
+                if let Some(name) = name_so_far.dropping_last_component() {
+                    report(name);
+                }
             }
         }
-
-        // Prune stale PIT entries
-        if self.pit_normal.removal_deadline < now {
-            self.pit_normal.reset(name_so_far, now, dead_nonce_list);
-        }
-        if self.pit_prefix.removal_deadline < now {
-            self.pit_prefix.reset(name_so_far, now, dead_nonce_list);
-        }
-
-        // If this entry is empty the parent will clean it up
     }
 
     fn is_empty(&self) -> bool {
         self.data.is_none()
+            && self.ranking.is_none()
             && self.fib.len() == 0
             && self.pit_normal.pit_in.len() == 0
             && self.pit_prefix.pit_in.len() == 0
             && self.children.len() == 0
     }
 
-    /*
-    fn fib_len(&self) -> usize {
-        self.fib.len() + self.children.iter().fold(0, |x, y| x + y.1.fib_len())
+    // Collects the ids and sizes of the cached packets
+    #[cfg(debug_assertions)]
+    fn check_invariants(
+        &self,
+        is_root: bool,
+        cached: &mut Vec<(CsEntryId, usize)>,
+    ) -> Result<(), &'static str> {
+        if !is_root && self.is_empty() {
+            return Err("empty entry left in the name tree");
+        }
+        check_fib_invariants(&self.fib)?;
+        self.pit_normal.check_invariants()?;
+        self.pit_prefix.check_invariants()?;
+        let is_ordered = self.children.windows(2).all(|pair| {
+            pair[0]
+                .0
+                .compare_to_name_component(pair[1].0.as_name_component())
+                .is_lt()
+        });
+        if !is_ordered {
+            return Err("children out of the canonical order");
+        }
+        if let Some(entry) = &self.data {
+            cached.push((entry.id, entry.data.len()));
+        }
+        for (_, child) in &self.children {
+            child.check_invariants(false, cached)?;
+        }
+        Ok(())
+    }
+
+    fn add_to_stats(&self, stats: &mut TableStats) {
+        stats.name_tree_nodes += 1;
+        if !self.fib.is_empty() {
+            stats.fib_entries += 1;
+            stats.fib_next_hops += self.fib.len();
+        }
+        for pit in [&self.pit_normal, &self.pit_prefix] {
+            if !pit.pit_in.is_empty() {
+                stats.pit_entries += 1;
+                stats.pit_in_records += pit.pit_in.len();
+            }
+        }
+        if let Some(data) = &self.data {
+            stats.cs_entries += 1;
+            stats.cs_bytes += data.data.len();
+        }
+        for (_, child) in &self.children {
+            child.add_to_stats(stats);
+        }
+    }
+}
+
+// How long the interests are kept in the PIT
+#[derive(Copy, Clone)]
+pub(super) struct InterestLifetimes {
+    pub(super) default_ms: u64,
+    pub(super) min_ms: u64,
+    pub(super) max_ms: u64,
+}
+
+impl InterestLifetimes {
+    pub(super) fn from_params(params: &TableParams) -> Self {
+        Self {
+            default_ms: params.default_interest_lifetime_ms,
+            min_ms: params.min_interest_lifetime_ms,
+            max_ms: params.max_interest_lifetime_ms,
+        }
     }
 
-    fn pit_len(&self) -> usize {
-        self.pit.pit_in.len() + self.children.iter().fold(0, |x, y| x + y.1.pit_len())
+    // The minimum wins if the limits overlap
+    pub(super) fn deadline(&self, now: Timestamp, interest_lifetime: Option<u64>) -> Timestamp {
+        let ms = interest_lifetime.unwrap_or(self.default_ms);
+        now.adding(ms.min(self.max_ms).max(self.min_ms))
     }
+}
 
-    fn cs_len(&self) -> usize {
-        let l = if self.data.is_none() { 0 } else { 1 };
-        l + self.children.iter().fold(0, |x, y| x + y.1.cs_len())
-    }*/
+impl Default for InterestLifetimes {
+    fn default() -> Self {
+        Self {
+            default_ms: DEFAULT_DEADLINE_INCREMENT_MS,
+            min_ms: 0,
+            max_ms: u64::MAX,
+        }
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-struct FibEntry {
-    cost: u32,
-    next_hop: FaceToken,
+pub(super) struct FibEntry {
+    pub(super) cost: u32,
+    pub(super) next_hop: FaceToken,
 }
 
 impl Default for FibEntry {
@@ -764,23 +1891,93 @@ impl Default for FibEntry {
     }
 }
 
-struct PitInEntry {
-    reply_to: FaceToken,
+pub(super) struct PitInEntry {
+    pub(super) reply_to: FaceToken,
     last_nonce: [u8; 4],
+    // Each face waits for as long as the lifetime of its own latest interest
+    expiry: Timestamp,
 }
 
-struct PitEntry {
-    pit_in: Vec<PitInEntry>,
-    removal_deadline: Timestamp,
-    latest_transmission_time: Timestamp,
-    transmission_count: u8,
+// The upstreams keep their out-record until the entry is satisfied or reset, so that
+//  the interest is not sent to them again after a Nack
+pub(super) struct PitOutEntry {
+    pub(super) face: FaceToken,
+    // The nonce of the latest interest sent to the face, which its Nack carries
+    last_nonce: [u8; 4],
+    expiry: Timestamp,
+    nack_reason: Option<NackReason>,
+}
+
+pub(super) struct PitEntry {
+    pub(super) pit_in: Vec<PitInEntry>,
+    pub(super) pit_out: Vec<PitOutEntry>,
+    pub(super) latest_transmission_time: Timestamp,
+    pub(super) transmission_count: u8,
+}
+
+// Adds the next hop or updates its cost, keeping the next hops ordered by cost
+pub(super) fn add_fib_entry(fib: &mut Vec<FibEntry>, face: FaceToken, cost: u32) {
+    match fib.iter_mut().find(|y| y.next_hop == face) {
+        Some(entry) => entry.cost = cost,
+        None => fib.push(FibEntry {
+            cost,
+            next_hop: face,
+        }),
+    }
+    fib.sort();
+}
+
+pub(super) fn remove_fib_entry(fib: &mut Vec<FibEntry>, face: FaceToken) -> bool {
+    match fib.iter().position(|y| y.next_hop == face) {
+        Some(index) => {
+            fib.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+pub(super) fn report_default_routes(
+    default_routes: &[FibEntry],
+    report: &mut dyn FnMut(FibEntryInfo<'_>),
+) {
+    if default_routes.is_empty() {
+        return;
+    }
+    let next_hops: Vec<_> = default_routes
+        .iter()
+        .map(|x| NextHop {
+            face: x.next_hop,
+            cost: x.cost,
+            congested: false,
+        })
+        .collect();
+    report(FibEntryInfo {
+        name: Name::new(),
+        next_hops: &next_hops,
+        is_default: true,
+    });
+}
+
+// The next hops are ordered by cost and each face is there once
+#[cfg(debug_assertions)]
+pub(super) fn check_fib_invariants(fib: &[FibEntry]) -> Result<(), &'static str> {
+    if fib.windows(2).any(|pair| pair[0].cost > pair[1].cost) {
+        return Err("FIB entry out of the cost order");
+    }
+    for (index, entry) in fib.iter().enumerate() {
+        if fib[..index].iter().any(|e| e.next_hop == entry.next_hop) {
+            return Err("face twice in a FIB entry");
+        }
+    }
+    Ok(())
 }
 
 impl PitEntry {
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             pit_in: Default::default(),
-            removal_deadline: Timestamp { ms_since_1970: 0 },
+            pit_out: Vec::new(),
             latest_transmission_time: Timestamp {
                 ms_since_1970: u64::MAX,
             },
@@ -788,18 +1985,236 @@ impl PitEntry {
         }
     }
 
-    fn reset(&mut self, name: Name<'_>, now: Timestamp, dead_nonce_list: &mut DeadNonceList) {
+    // Adds the in-record for the interest and tells whether the entry is new,
+    //  or returns none if the nonce shows that the interest looped
+    pub(super) fn register(
+        &mut self,
+        name: Name<'_>,
+        reply_to: FaceToken,
+        now: Timestamp,
+        deadline: Timestamp,
+        nonce: [u8; 4],
+        dead_nonce_list: &mut DeadNonceList,
+    ) -> Option<bool> {
+        if self.pit_in.is_empty() {
+            // The PIT entry is new
+            self.pit_out.clear();
+            self.pit_in.push(PitInEntry {
+                reply_to,
+                last_nonce: nonce,
+                expiry: deadline,
+            });
+            return Some(true);
+        }
+
+        // We next check for nonce loops
+        let mut nonce_loop = false;
+        let mut reply_to_found = false;
+        for ff in self.pit_in.iter_mut() {
+            if ff.last_nonce == nonce {
+                nonce_loop = true;
+            }
+            if ff.reply_to == reply_to {
+                if ff.last_nonce != nonce {
+                    // Updating the nonce on the entry and storing the old one in dead ones
+                    dead_nonce_list.insert(name, nonce, now);
+                    ff.last_nonce = nonce;
+                }
+                // The latest interest from the face decides how long it waits
+                ff.expiry = deadline;
+                reply_to_found = true;
+            }
+        }
+
+        if !reply_to_found {
+            // Adding the in entry if it was not there
+            self.pit_in.push(PitInEntry {
+                reply_to,
+                last_nonce: nonce,
+                expiry: deadline,
+            });
+        }
+
+        if nonce_loop {
+            // We have a likely loop, so we do not forward
+            return None;
+        }
+
+        // TODO: if we use more complex strategies, e.g. probabilistic ones, we can use the
+        //  incoming nonce as the source of randomness (perhaps merging it with local state)
+        Some(false)
+    }
+
+    pub(super) fn add_in_record(
+        &mut self,
+        name: Name<'_>,
+        reply_to: FaceToken,
+        now: Timestamp,
+        deadline: Timestamp,
+        nonce: [u8; 4],
+        dead_nonce_list: &mut DeadNonceList,
+    ) -> bool {
+        if self.pit_in.is_empty() {
+            return false;
+        }
+
+        match self.pit_in.iter_mut().find(|ff| ff.reply_to == reply_to) {
+            Some(ff) => {
+                if ff.last_nonce != nonce {
+                    // Updating the nonce on the entry and storing the old one in dead ones
+                    dead_nonce_list.insert(name, ff.last_nonce, now);
+                    ff.last_nonce = nonce;
+                }
+                ff.expiry = deadline;
+            }
+            None => self.pit_in.push(PitInEntry {
+                reply_to,
+                last_nonce: nonce,
+                expiry: deadline,
+            }),
+        }
+        true
+    }
+
+    pub(super) fn in_records(&self) -> impl Iterator<Item = PitInRecordInfo> + '_ {
+        self.pit_in.iter().map(|ee| PitInRecordInfo {
+            face: ee.reply_to,
+            expiry: ee.expiry,
+        })
+    }
+
+    pub(super) fn out_records(&self) -> impl Iterator<Item = PitOutRecordInfo> + '_ {
+        self.pit_out.iter().map(|out| PitOutRecordInfo {
+            face: out.face,
+            expiry: out.expiry,
+            nack_reason: out.nack_reason,
+        })
+    }
+
+    // Updates the out-records of the faces the interest was just sent to, except the one
+    //  it came from, where the forwarder does not send it
+    pub(super) fn record_transmission(
+        &mut self,
+        faces: &[FaceToken],
+        reply_to: FaceToken,
+        nonce: [u8; 4],
+        now: Timestamp,
+        expiry: Timestamp,
+    ) {
+        if faces.is_empty() {
+            return;
+        }
+        self.latest_transmission_time = now;
+        self.transmission_count = self.transmission_count.wrapping_add(1);
+        for face in faces.iter().filter(|face| **face != reply_to) {
+            match self.pit_out.iter_mut().find(|out| out.face == *face) {
+                Some(out) => {
+                    out.last_nonce = nonce;
+                    out.expiry = expiry;
+                    out.nack_reason = None;
+                }
+                None => self.pit_out.push(PitOutEntry {
+                    face: *face,
+                    last_nonce: nonce,
+                    expiry,
+                    nack_reason: None,
+                }),
+            }
+        }
+    }
+
+    // Whether the latest interest sent to the face had the nonce and was not nacked yet
+    pub(super) fn has_out_record(&self, face: FaceToken, nonce: [u8; 4]) -> bool {
+        self.pit_out
+            .iter()
+            .any(|out| out.face == face && out.last_nonce == nonce && out.nack_reason.is_none())
+    }
+
+    pub(super) fn record_nack(&mut self, from: FaceToken, reason: NackReason) {
+        if let Some(out) = self.pit_out.iter_mut().find(|out| out.face == from) {
+            out.nack_reason = Some(reason);
+        }
+    }
+
+    // Whether an upstream may still answer
+    pub(super) fn has_pending_out_records(&self, now: Timestamp) -> bool {
+        self.pit_out
+            .iter()
+            .any(|out| out.nack_reason.is_none() && now <= out.expiry)
+    }
+
+    pub(super) fn remove_face_records(&mut self, face: FaceToken) {
+        self.pit_in.retain(|ee| ee.reply_to != face);
+        self.pit_out.retain(|out| out.face != face);
+    }
+
+    // Each face has one in-record and one out-record
+    #[cfg(debug_assertions)]
+    pub(super) fn check_invariants(&self) -> Result<(), &'static str> {
+        for (index, ee) in self.pit_in.iter().enumerate() {
+            if self.pit_in[..index]
+                .iter()
+                .any(|other| other.reply_to == ee.reply_to)
+            {
+                return Err("face twice in the in-records of a PIT entry");
+            }
+        }
+        for (index, out) in self.pit_out.iter().enumerate() {
+            if self.pit_out[..index]
+                .iter()
+                .any(|other| other.face == out.face)
+            {
+                return Err("face twice in the out-records of a PIT entry");
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn nonces(&self) -> impl Iterator<Item = [u8; 4]> + '_ {
+        self.pit_in.iter().map(|ee| ee.last_nonce)
+    }
+
+    pub(super) fn has_expired_in_records(&self, now: Timestamp) -> bool {
+        self.pit_in.iter().any(|ee| ee.expiry < now)
+    }
+
+    // Removes the in-records whose interest lifetime lapsed, and the out-records too
+    // Adds the faces of the expired in-records to "expired"
+    pub(super) fn expire_in_records(
+        &mut self,
+        name: Name<'_>,
+        now: Timestamp,
+        dead_nonce_list: &mut DeadNonceList,
+        expired: &mut Vec<FaceToken>,
+    ) {
+        self.pit_out.retain(|out| now <= out.expiry);
+        self.pit_in.retain(|ee| {
+            if now <= ee.expiry {
+                return true;
+            }
+            dead_nonce_list.insert(name, ee.last_nonce, now);
+            expired.push(ee.reply_to);
+            false
+        });
+    }
+
+    pub(super) fn reset(
+        &mut self,
+        name: Name<'_>,
+        now: Timestamp,
+        dead_nonce_list: &mut DeadNonceList,
+    ) {
         for ee in self.pit_in.drain(..) {
             dead_nonce_list.insert(name, ee.last_nonce, now);
         }
-        self.removal_deadline = Timestamp { ms_since_1970: 0 };
         self.latest_transmission_time = Timestamp {
             ms_since_1970: u64::MAX,
         };
         self.transmission_count = 0;
+        self.pit_out.clear();
     }
 
-    fn satisfy(
+    pub(super) fn satisfy(
         &mut self,
         name: Name<'_>,
         now: Timestamp,
@@ -810,47 +2225,167 @@ impl PitEntry {
             faces.push((0, ee.reply_to));
             dead_nonce_list.insert(name, ee.last_nonce, now);
         }
-        self.removal_deadline = Timestamp { ms_since_1970: 0 };
         self.latest_transmission_time = Timestamp {
             ms_since_1970: u64::MAX,
         };
         self.transmission_count = 0;
+        self.pit_out.clear();
     }
 }
 
-struct DataEntry {
-    data: Box<[u8]>,
+// What was removed while pruning, for the replacement policy and the strategies
+#[derive(Default)]
+struct PrunedEntries {
+    data: Vec<CsEntryId>,
+    // The entries that were removed because all their in-records expired
+    interests: Vec<NameBuf>,
+    // Every name with expired in-records, with the faces of those records
+    timed_out: Vec<(NameBuf, Vec<FaceToken>)>,
+}
+
+pub(super) struct DataEntry {
+    pub(super) id: CsEntryId,
+    pub(super) data: Box<[u8]>,
+    pub(super) freshness_deadline: Timestamp,
+    pub(super) removal_deadline: Timestamp,
+    // The number of times the data was retrieved during its freshness period
+    pub(super) hits: u32,
+    pub(super) expiry_reported: bool,
+}
+
+// The data being inserted, which only becomes a DataEntry if it is not cached yet
+struct NewDataEntry<'a> {
+    id: CsEntryId,
+    digest: [u8; 32],
+    packet: &'a [u8],
     freshness_deadline: Timestamp,
     removal_deadline: Timestamp,
 }
 
-struct DeadNonceList {
-    elements: BTreeMap<u64, Timestamp>,
-    duration_to_keep_ms: u64,
+// Remembers the (name, nonce) pairs of the interests that were satisfied or expired,
+//  to detect the loops. It is a ring of Bloom filters, each taking the insertions
+//  for an equal slice of the duration, so the memory stays the same under floods,
+//  and the pairs are forgotten between one and 1 + 1/(DNL_GENERATIONS - 1) durations
+//  after they were inserted. Too many pairs per slice only make it drop more interests.
+pub(super) struct DeadNonceList {
+    // Each generation is "words_per_generation" words of bits
+    bits: Vec<u64>,
+    words_per_generation: usize,
+    hash_count: u32,
+    current: usize,
+    current_start: Timestamp,
+    generation_duration_ms: u64,
+    // The insertions into each generation since it was started
+    inserted: [usize; DNL_GENERATIONS],
 }
 
 impl DeadNonceList {
-    fn new(duration_to_keep_ms: u64) -> Self {
+    // Keeps the false positive rate under 1/"false_positive_one_in" for up to
+    //  "capacity" insertions per duration
+    pub(super) fn new(
+        duration_to_keep_ms: u64,
+        capacity: usize,
+        false_positive_one_in: u32,
+    ) -> Self {
+        // A nonce is looked up in all the generations, each has its share of the rate.
+        // With the optimal number of bits, k hashes give the rate of 1/2^k.
+        let per_generation_one_in = (false_positive_one_in.max(2) as u64) * DNL_GENERATIONS as u64;
+        let hash_count = u64::BITS - (per_generation_one_in - 1).leading_zeros();
+        let per_generation_capacity = capacity.max(1).div_ceil(DNL_GENERATIONS - 1);
+        // m = n * k / ln(2)
+        let bit_count = per_generation_capacity * hash_count as usize * 1443 / 1000;
+        let words_per_generation = bit_count.div_ceil(64).max(1);
         Self {
-            elements: Default::default(),
-            duration_to_keep_ms,
+            bits: vec![0; words_per_generation * DNL_GENERATIONS],
+            words_per_generation,
+            hash_count,
+            current: 0,
+            current_start: Timestamp { ms_since_1970: 0 },
+            generation_duration_ms: Self::generation_duration_ms(duration_to_keep_ms),
+            inserted: [0; DNL_GENERATIONS],
         }
     }
 
-    fn contains(&mut self, name: Name<'_>, nonce: [u8; 4]) -> bool {
+    pub(super) fn len(&self) -> usize {
+        self.inserted.iter().sum()
+    }
+
+    pub(super) fn memory_bytes(&self) -> usize {
+        self.bits.len() * core::mem::size_of::<u64>()
+    }
+
+    // The nonces are kept for the new duration once the current generation ends
+    pub(super) fn set_duration(&mut self, duration_to_keep_ms: u64) {
+        self.generation_duration_ms = Self::generation_duration_ms(duration_to_keep_ms);
+    }
+
+    // A nonce is kept for at least the duration, as the oldest generation is only
+    //  forgotten once all the others have ended
+    fn generation_duration_ms(duration_to_keep_ms: u64) -> u64 {
+        (duration_to_keep_ms / (DNL_GENERATIONS as u64 - 1)).max(1)
+    }
+
+    pub(super) fn contains(&mut self, name: Name<'_>, nonce: [u8; 4]) -> bool {
         let name_hash = Self::hash_name_and_nonce(name, nonce);
-        self.elements.contains_key(&name_hash)
+        (0..DNL_GENERATIONS).any(|generation| self.generation_contains(generation, name_hash))
     }
 
     fn insert(&mut self, name: Name<'_>, nonce: [u8; 4], now: Timestamp) {
+        self.prune(now);
         let name_hash = Self::hash_name_and_nonce(name, nonce);
-        self.elements
-            .insert(name_hash, now.adding(self.duration_to_keep_ms));
+        let bit_count = (self.words_per_generation * 64) as u64;
+        let offset = self.current * self.words_per_generation;
+        for index in Self::bit_indices(name_hash, self.hash_count, bit_count) {
+            self.bits[offset + index / 64] |= 1 << (index % 64);
+        }
+        self.inserted[self.current] += 1;
+    }
+
+    // Starts the new generations that are due, forgetting the oldest ones
+    pub(super) fn prune(&mut self, now: Timestamp) {
+        let elapsed = now
+            .ms_since_1970
+            .saturating_sub(self.current_start.ms_since_1970);
+        let due = elapsed / self.generation_duration_ms;
+        if due == 0 {
+            return;
+        }
+        if due >= DNL_GENERATIONS as u64 {
+            self.bits.fill(0);
+            self.inserted = [0; DNL_GENERATIONS];
+            self.current_start = now;
+            return;
+        }
+        for _ in 0..due {
+            self.current = (self.current + 1) % DNL_GENERATIONS;
+            let offset = self.current * self.words_per_generation;
+            self.bits[offset..offset + self.words_per_generation].fill(0);
+            self.inserted[self.current] = 0;
+        }
+        self.current_start = self.current_start.adding(due * self.generation_duration_ms);
+    }
+
+    fn generation_contains(&self, generation: usize, name_hash: u64) -> bool {
+        let bit_count = (self.words_per_generation * 64) as u64;
+        let offset = generation * self.words_per_generation;
+        Self::bit_indices(name_hash, self.hash_count, bit_count)
+            .all(|index| self.bits[offset + index / 64] & (1 << (index % 64)) != 0)
     }
 
-    fn prune(&mut self, now: Timestamp) {
-        self.elements
-            .retain(|_, v| v.ms_since_1970 <= now.ms_since_1970);
+    // Double hashing, the second hash is odd so that it never repeats the same bit
+    //  for a power of two bits
+    fn bit_indices(name_hash: u64, hash_count: u32, bit_count: u64) -> impl Iterator<Item = usize> {
+        let first = name_hash;
+        let second = Self::mix(name_hash) | 1;
+        (0..hash_count as u64)
+            .map(move |i| (first.wrapping_add(i.wrapping_mul(second)) % bit_count) as usize)
+    }
+
+    // The finalizer of SplitMix64, so that the similar names do not share the bits
+    pub(super) fn mix(mut x: u64) -> u64 {
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^ (x >> 31)
     }
 
     fn hash_name_and_nonce(name: Name<'_>, nonce: [u8; 4]) -> u64 {
@@ -858,7 +2393,11 @@ impl DeadNonceList {
         let mut arr = [0u8; 8];
 
         for cc in name.components() {
-            Self::add_to_hash(&mut hash, cc.typ.get() as u64);
+            // The length tells apart the components that only differ by trailing zeroes
+            Self::add_to_hash(
+                &mut hash,
+                (cc.typ.get() as u64) << 32 | cc.bytes.len() as u64,
+            );
             let mut offset = 0;
             while offset + 8 < cc.bytes.len() {
                 arr.copy_from_slice(&cc.bytes[offset..offset + 8]);
@@ -872,7 +2411,7 @@ impl DeadNonceList {
             }
         }
         Self::add_to_hash(&mut hash, u32::from_be_bytes(nonce) as u64);
-        hash
+        Self::mix(hash)
     }
 
     #[inline]
@@ -885,9 +2424,444 @@ impl DeadNonceList {
     }
 }
 
-const DEFAULT_DEADLINE_INCREMENT_MS: u64 = 4000; // 4 sec
+const DNL_GENERATIONS: usize = 4;
+pub(crate) const DEFAULT_DNL_CAPACITY: usize = 1 << 16;
+pub(crate) const DEFAULT_DNL_FALSE_POSITIVE_ONE_IN: u32 = 1 << 16;
+
+pub(super) const DEFAULT_DEADLINE_INCREMENT_MS: u64 = 4000; // 4 sec
 
 //const RETRANSMISSION_PERIOD_MS: u64 = 1000; // 1 sec
 
-const MIN_RETRANSMISSION_DELAY_MS: u64 = 8;
-const MAX_RETRANSMISSION_DELAY_DOUBLINGS: u8 = 5;
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use alloc::boxed::Box;
+
+    use crate::{
+        clock::Timestamp,
+        face::lp::NackReason,
+        forwarder::FaceToken,
+        io::Encode,
+        name::{Name, NameComponent},
+        packet::{ForwardingHint, Interest, InterestLifetime},
+        tables::{
+            reference::{DeadNonceList, ReferenceTables},
+            strategy::{MulticastStrategy, RetransmissionSuppression},
+            ContentStore, Fib, NackOutcome, Pit, PitInRecordInfo, PitOutRecordInfo, TableParams,
+            Tables,
+        },
+    };
+
+    #[test]
+    fn test_forwarding_hint_loop() {
+        let mut tables = ReferenceTables::default();
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let near = &[NameComponent::generic(b"near")];
+        let far = &[NameComponent::generic(b"far")];
+        tables.register_prefix(root.adding_components(near), FaceToken(1), 0);
+        tables.register_prefix(root.adding_components(far), FaceToken(2), 0);
+
+        let mut hint = Vec::new();
+        for delegation in [near, far] {
+            let _ = root.adding_components(delegation).encode(&mut hint);
+        }
+        let forwarding_hint = ForwardingHint { bytes: &hint };
+        let mut forward = |photo: &[u8], reply_to| {
+            let comp = &[NameComponent::generic(photo)];
+            let name = root.adding_components(comp);
+            let hint = Some(forwarding_hint);
+            tables
+                .register_interest(
+                    &Interest {
+                        forwarding_hint: hint,
+                        ..Interest::new(name, false, [1; 4])
+                    },
+                    [1; 4],
+                    reply_to,
+                    now,
+                )
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(forward(b"photo1", FaceToken(3)), [FaceToken(1)]);
+        // The first delegation only leads back to where the interest came from
+        assert_eq!(forward(b"photo2", FaceToken(1)), [FaceToken(2)]);
+    }
+
+    #[test]
+    fn test_data_versions() {
+        let mut tables = ReferenceTables::default();
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"a")];
+        let name = root.adding_components(comp);
+
+        // Both versions are kept, and the one fresh for longer is preferred
+        tables.insert_data(name, [1; 32], 100, at(1000), &[1]);
+        tables.insert_data(name, [2; 32], 500, at(1000), &[2]);
+        assert_eq!(tables.content_store_usage().entries, 2);
+        assert_eq!(
+            tables.get_data(name, false, false, at(1000)),
+            Some([2].as_slice())
+        );
+        let digest = &[NameComponent::implicit_sha256(&[1; 32])];
+        let full_name = name.adding_components(digest);
+        assert_eq!(
+            tables.get_data(full_name, false, false, at(1000)),
+            Some([1].as_slice())
+        );
+
+        // The stale version is replaced by the next one, the fresh one is kept
+        tables.insert_data(name, [3; 32], 1000, at(1200), &[3]);
+        assert_eq!(tables.content_store_usage().entries, 2);
+        assert_eq!(tables.get_data(full_name, false, false, at(1200)), None);
+        assert_eq!(
+            tables.get_data(name, false, true, at(1200)),
+            Some([3].as_slice())
+        );
+    }
+
+    #[test]
+    fn test_table_params() {
+        let mut tables = ReferenceTables::default();
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"a")];
+        let name = root.adding_components(comp);
+        tables.register_prefix(name, FaceToken(1), 0);
+
+        let params = TableParams {
+            default_interest_lifetime_ms: 100,
+            retransmission_suppression: RetransmissionSuppression {
+                initial_delay_ms: 50,
+                max_delay_ms: 50,
+            },
+            ..TableParams::default()
+        };
+        tables.set_params(&params);
+        assert_eq!(tables.params(), params);
+
+        let mut forward = |nonce, reply_to, now| {
+            tables
+                .register_interest(
+                    &Interest::new(name, false, nonce),
+                    nonce,
+                    FaceToken(reply_to),
+                    now,
+                )
+                .count()
+        };
+        assert_eq!(forward([1; 4], 2, at(1000)), 1);
+        // The retransmissions wait for the configured delay
+        assert_eq!(forward([2; 4], 3, at(1040)), 0);
+        assert_eq!(forward([3; 4], 3, at(1060)), 1);
+
+        let mut expiries = Vec::new();
+        tables.list_pit_entries(&mut |entry| {
+            expiries.extend(entry.in_records.iter().map(|record| record.expiry))
+        });
+        assert_eq!(expiries, [at(1100), at(1160)]);
+    }
+
+    #[test]
+    fn test_interest_lifetime_limits() {
+        let mut tables = ReferenceTables::default();
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let a = &[NameComponent::generic(b"a")];
+        tables.register_prefix(root.adding_components(a), FaceToken(1), 0);
+        let params = TableParams {
+            min_interest_lifetime_ms: 100,
+            max_interest_lifetime_ms: 2000,
+            ..TableParams::default()
+        };
+        tables.set_params(&params);
+        assert_eq!(tables.params(), params);
+
+        // Too short, too long, and without a lifetime, which gets the default one clamped
+        for (id, lifetime) in [(b"1", Some(10)), (b"2", Some(3600 * 1000)), (b"3", None)] {
+            let comp = &[a[0], NameComponent::generic(id)];
+            let name = root.adding_components(comp);
+            let faces = tables.register_interest(
+                &Interest {
+                    interest_lifetime: lifetime.map(|val| InterestLifetime { val }),
+                    ..Interest::new(name, false, [1; 4])
+                },
+                [1; 4],
+                FaceToken(0),
+                at(1000),
+            );
+            assert_eq!(faces.count(), 1);
+        }
+        let mut expiries = Vec::new();
+        tables.list_pit_entries(&mut |entry| {
+            expiries.extend(entry.in_records.iter().map(|record| record.expiry))
+        });
+        assert_eq!(expiries, [at(1100), at(3000), at(3000)]);
+    }
+
+    #[test]
+    fn test_table_enumeration() {
+        let mut tables = ReferenceTables::default();
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let a = &[NameComponent::generic(b"a")];
+        let ab = &[a[0], NameComponent::generic(b"b")];
+        tables.register_prefix(root.adding_components(a), FaceToken(2), 20);
+        tables.register_prefix(root.adding_components(a), FaceToken(1), 10);
+        tables.register_prefix(root.adding_components(ab), FaceToken(3), 0);
+        let name = root.adding_components(ab);
+        let _ = tables.register_interest(
+            &Interest {
+                interest_lifetime: Some(InterestLifetime { val: 500 }),
+                ..Interest::new(name, true, [1; 4])
+            },
+            [1; 4],
+            FaceToken(0),
+            at(1000),
+        );
+        tables.insert_data(name, [9; 32], 100, at(1000), &[1, 2, 3]);
+
+        let mut routes = Vec::new();
+        tables.list_routes(&mut |entry| {
+            let faces: Vec<_> = entry.next_hops.iter().map(|h| (h.face.0, h.cost)).collect();
+            routes.push((entry.name.component_count(), faces));
+        });
+        assert_eq!(
+            routes,
+            [(1, Vec::from([(1, 10), (2, 20)])), (2, Vec::from([(3, 0)]))]
+        );
+
+        let mut pit_entries = Vec::new();
+        tables.list_pit_entries(&mut |entry| {
+            assert!(entry.name == name);
+            pit_entries.push((entry.can_be_prefix, entry.in_records.to_vec()));
+        });
+        let in_record = PitInRecordInfo {
+            face: FaceToken(0),
+            expiry: at(1500),
+        };
+        assert_eq!(pit_entries, [(true, Vec::from([in_record]))]);
+
+        let mut cs_entries = Vec::new();
+        tables.list_cs_entries(&mut |entry| {
+            cs_entries.push((entry.name.component_count(), entry.bytes, entry.fresh_until));
+        });
+        assert_eq!(cs_entries, [(3, 3, at(1100))]);
+    }
+
+    #[test]
+    fn test_table_stats() {
+        let mut tables = ReferenceTables::default();
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let a = &[NameComponent::generic(b"a")];
+        let ab = &[a[0], NameComponent::generic(b"b")];
+        let abc = &[a[0], ab[1], NameComponent::generic(b"c")];
+        assert_eq!(tables.stats().name_tree_nodes, 1);
+
+        tables.register_prefix(root.adding_components(a), FaceToken(1), 10);
+        tables.register_prefix(root.adding_components(a), FaceToken(2), 20);
+        let name = root.adding_components(ab);
+        for (nonce, face) in [([1; 4], 3), ([2; 4], 4)] {
+            let _ = tables.register_interest(
+                &Interest::new(name, false, nonce),
+                nonce,
+                FaceToken(face),
+                at(1000),
+            );
+        }
+        let _ = tables.register_interest(
+            &Interest::new(root.adding_components(abc), true, [3; 4]),
+            [3; 4],
+            FaceToken(3),
+            at(1000),
+        );
+        tables.insert_data(
+            root.adding_components(abc),
+            [9; 32],
+            100,
+            at(1000),
+            &[1, 2, 3],
+        );
+
+        let stats = tables.stats();
+        assert_eq!(stats.fib_entries, 1);
+        assert_eq!(stats.fib_next_hops, 2);
+        assert_eq!(stats.pit_entries, 2);
+        assert_eq!(stats.pit_in_records, 3);
+        assert_eq!((stats.cs_entries, stats.cs_bytes), (1, 3));
+        assert_eq!(stats.dead_nonce_entries, 0);
+        assert!(stats.dead_nonce_bytes > 0);
+        // The root, /a, /a/b, /a/b/c and the digest of the data
+        assert_eq!(stats.name_tree_nodes, 5);
+
+        // The satisfied nonces go to the dead nonce list
+        assert_eq!(
+            tables
+                .satisfy_interests(name, FaceToken(1), at(1010), &mut || [0; 32])
+                .count(),
+            2
+        );
+        let stats = tables.stats();
+        assert_eq!(stats.pit_entries, 1);
+        assert_eq!(stats.dead_nonce_entries, 2);
+    }
+
+    #[test]
+    fn test_in_record_lifetimes() {
+        let mut tables = ReferenceTables::default();
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"a")];
+        let name = root.adding_components(comp);
+        tables.register_prefix(name, FaceToken(0), 0);
+
+        let _ = tables.register_interest(
+            &Interest {
+                interest_lifetime: Some(InterestLifetime { val: 100 }),
+                ..Interest::new(name, false, [1; 4])
+            },
+            [1; 4],
+            FaceToken(1),
+            at(1000),
+        );
+        let _ = tables.register_interest(
+            &Interest {
+                interest_lifetime: Some(InterestLifetime { val: 1000 }),
+                ..Interest::new(name, false, [2; 4])
+            },
+            [2; 4],
+            FaceToken(2),
+            at(1000),
+        );
+
+        // The short interest expires on its own, without being extended by the long one
+        tables.prune_pit_if_needed(at(1200));
+        let faces: Vec<_> = tables
+            .satisfy_interests(name, FaceToken(0), at(1200), &mut || [0; 32])
+            .collect();
+        assert_eq!(faces, [FaceToken(2)]);
+
+        // The entry goes once all of its in-records expired
+        let _ = tables.register_interest(
+            &Interest {
+                interest_lifetime: Some(InterestLifetime { val: 100 }),
+                ..Interest::new(name, false, [3; 4])
+            },
+            [3; 4],
+            FaceToken(1),
+            at(2000),
+        );
+        tables.prune_pit_if_needed(at(2200));
+        let mut digest = || [0; 32];
+        let faces = tables.satisfy_interests(name, FaceToken(0), at(2200), &mut digest);
+        assert_eq!(faces.count(), 0);
+    }
+
+    #[test]
+    fn test_out_records() {
+        let mut tables = ReferenceTables::default();
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"a")];
+        let name = root.adding_components(comp);
+        tables.register_prefix(name, FaceToken(10), 0);
+        tables.register_prefix(name, FaceToken(11), 10);
+        tables.set_strategy(name, Box::new(MulticastStrategy::default()));
+        let out_records = |tables: &ReferenceTables| {
+            let mut records = Vec::new();
+            tables.list_pit_entries(&mut |entry| records.extend_from_slice(entry.out_records));
+            records
+        };
+
+        let faces: Vec<_> = tables
+            .register_interest(
+                &Interest {
+                    interest_lifetime: Some(InterestLifetime { val: 100 }),
+                    ..Interest::new(name, false, [1; 4])
+                },
+                [1; 4],
+                FaceToken(1),
+                at(1000),
+            )
+            .collect();
+        assert_eq!(faces, [FaceToken(10), FaceToken(11)]);
+        let out = |face, nack_reason| PitOutRecordInfo {
+            face,
+            expiry: at(1100),
+            nack_reason,
+        };
+        assert_eq!(
+            out_records(&tables),
+            [out(FaceToken(10), None), out(FaceToken(11), None)]
+        );
+
+        // Only the upstreams the interest went to can nack it, with its nonce
+        let nack = |tables: &mut ReferenceTables, nonce, reason, from| {
+            let (outcome, faces) = tables.process_nack(name, false, nonce, reason, from, at(1010));
+            (outcome, faces.collect::<Vec<_>>())
+        };
+        assert_eq!(
+            nack(&mut tables, [1; 4], NackReason::NoRoute, FaceToken(12)),
+            (NackOutcome::Unmatched, Vec::new())
+        );
+        assert_eq!(
+            nack(&mut tables, [2; 4], NackReason::NoRoute, FaceToken(10)),
+            (NackOutcome::Unmatched, Vec::new())
+        );
+
+        // The other upstream may still answer
+        assert_eq!(
+            nack(&mut tables, [1; 4], NackReason::NoRoute, FaceToken(10)),
+            (NackOutcome::Pending, Vec::new())
+        );
+        assert_eq!(
+            out_records(&tables),
+            [
+                out(FaceToken(10), Some(NackReason::NoRoute)),
+                out(FaceToken(11), None)
+            ]
+        );
+        assert_eq!(
+            nack(&mut tables, [1; 4], NackReason::Congestion, FaceToken(11)),
+            (NackOutcome::AllUpstreamsFailed, vec![FaceToken(1)])
+        );
+        assert!(out_records(&tables).is_empty());
+    }
+
+    #[test]
+    fn test_dead_nonce_list() {
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let mut list = DeadNonceList::new(3000, 1000, 1000);
+        let words = list.bits.len();
+        let root = Name::new();
+        let comp = &[NameComponent::generic(b"a")];
+        let name = root.adding_components(comp);
+
+        list.prune(at(10_000));
+        list.insert(name, [1, 2, 3, 4], at(10_000));
+        assert!(list.contains(name, [1, 2, 3, 4]));
+        assert!(!list.contains(name, [1, 2, 3, 5]));
+        assert!(!list.contains(root, [1, 2, 3, 4]));
+
+        // A flood of nonces does not take more memory and barely causes false positives
+        for i in 0..1000u32 {
+            list.insert(name, (i + 100).to_be_bytes(), at(10_000 + i as u64 * 3));
+        }
+        assert_eq!(list.bits.len(), words);
+        let false_positives = (0..10_000u32)
+            .filter(|i| list.contains(name, (i + 1_000_000).to_be_bytes()))
+            .count();
+        assert!(false_positives < 30);
+
+        // Remembered for at least the duration, and forgotten not long after
+        list.prune(at(12_999));
+        assert!(list.contains(name, [1, 2, 3, 4]));
+        list.prune(at(14_000));
+        assert!(!list.contains(name, [1, 2, 3, 4]));
+    }
+}
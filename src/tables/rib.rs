@@ -0,0 +1,446 @@
+use alloc::{collections::btree_map::BTreeMap, vec::Vec};
+
+use crate::{
+    clock::Timestamp,
+    forwarder::FaceToken,
+    name::{Name, NameBuf},
+    tables::Fib,
+};
+
+// Who added the route, so that the same face can have a route for the same prefix
+//  from several sources, e.g. an application and the static configuration, and each
+//  source only removes its own
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum RouteOrigin {
+    // Registered by an application for itself
+    App,
+    // Configured by the operator
+    Static,
+    // Learned from the data coming back, e.g. by the self-learning strategy
+    SelfLearned,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RouteFlags {
+    // The route is also used for the longer prefixes that have routes of their own
+    pub child_inherit: bool,
+    // The routes of the shorter prefixes are not used for this prefix and the longer ones
+    pub capture: bool,
+}
+
+impl Default for RouteFlags {
+    fn default() -> Self {
+        Self {
+            child_inherit: true,
+            capture: false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Route {
+    pub face: FaceToken,
+    pub origin: RouteOrigin,
+    pub cost: u32,
+    pub flags: RouteFlags,
+    // When the route is removed unless it is added again, e.g. so that the routes
+    //  of an application that crashed go away. None for the permanent routes.
+    pub expiry: Option<Timestamp>,
+}
+
+// Decides whether a face may register a prefix, e.g. so that an untrusted tunnel cannot
+//  attract the interests for the namespaces it does not serve. The forwarder consults it
+//  for the routes it is asked to add, but not for the FIB changes made on the tables.
+pub trait RegistrationPolicy {
+    fn may_register(&mut self, face: FaceToken, name_prefix: Name<'_>) -> bool;
+}
+
+impl<F> RegistrationPolicy for F
+where
+    F: FnMut(FaceToken, Name<'_>) -> bool,
+{
+    fn may_register(&mut self, face: FaceToken, name_prefix: Name<'_>) -> bool {
+        self(face, name_prefix)
+    }
+}
+
+// Restricts the faces that were given namespaces to the prefixes under them,
+//  while the others may register anything
+#[derive(Default)]
+pub struct NamespaceRestrictions {
+    allowed: BTreeMap<FaceToken, Vec<NameBuf>>,
+}
+
+impl NamespaceRestrictions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The first namespace of a face restricts it, the next ones widen what it may register
+    pub fn allow(&mut self, face: FaceToken, namespace: Name<'_>) {
+        let namespaces = self.allowed.entry(face).or_default();
+        if !namespaces.iter().any(|n| n.as_name() == namespace) {
+            namespaces.push(NameBuf::from(namespace));
+        }
+    }
+
+    // Lifts the restrictions of the face, e.g. before its token is given to another face
+    pub fn remove_face(&mut self, face: FaceToken) -> bool {
+        self.allowed.remove(&face).is_some()
+    }
+}
+
+impl RegistrationPolicy for NamespaceRestrictions {
+    fn may_register(&mut self, face: FaceToken, name_prefix: Name<'_>) -> bool {
+        match self.allowed.get(&face) {
+            Some(namespaces) => namespaces
+                .iter()
+                .any(|n| n.as_name().is_prefix_of(name_prefix)),
+            None => true,
+        }
+    }
+}
+
+// The routes as they were registered, which are compiled into the FIB entries:
+//  a prefix gets the lowest cost route of each face, plus the child-inherit routes of
+//  the shorter prefixes up to the first one with a capture route, unless it has a
+//  capture route itself. Only the prefixes with routes of their own get FIB entries.
+// The tables that also use the routes of the shorter prefixes during the lookup,
+//  like the reference ones, still do so regardless of the flags.
+#[derive(Default)]
+pub struct Rib {
+    entries: BTreeMap<NameBuf, Vec<Route>>,
+    // The next hops and costs that were registered in the FIB for each prefix
+    installed: BTreeMap<NameBuf, Vec<(FaceToken, u32)>>,
+    compiled: Vec<(FaceToken, u32)>,
+    // The earliest expiry, so that pruning is cheap until then
+    next_expiry: Option<Timestamp>,
+}
+
+impl Rib {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds the route, or replaces the one with the same face and origin, and updates the FIB
+    pub fn add_route<F: Fib + ?Sized>(&mut self, prefix: Name<'_>, route: Route, fib: &mut F) {
+        let routes = self.entries.entry(NameBuf::from(prefix)).or_default();
+        match routes
+            .iter_mut()
+            .find(|r| r.face == route.face && r.origin == route.origin)
+        {
+            Some(existing) => *existing = route,
+            None => routes.push(route),
+        }
+        if let Some(expiry) = route.expiry {
+            self.next_expiry = Some(self.next_expiry.map_or(expiry, |next| next.min(expiry)));
+        }
+        self.update_fib_under(prefix, fib);
+    }
+
+    pub fn remove_route<F: Fib + ?Sized>(
+        &mut self,
+        prefix: Name<'_>,
+        face: FaceToken,
+        origin: RouteOrigin,
+        fib: &mut F,
+    ) -> bool {
+        let key = NameBuf::from(prefix);
+        let Some(routes) = self.entries.get_mut(&key) else {
+            return false;
+        };
+        let count = routes.len();
+        routes.retain(|r| r.face != face || r.origin != origin);
+        if routes.len() == count {
+            return false;
+        }
+        if routes.is_empty() {
+            self.entries.remove(&key);
+        }
+        self.update_fib_under(prefix, fib);
+        true
+    }
+
+    // Removes all the routes to the face, e.g. once it is closed
+    pub fn remove_face<F: Fib + ?Sized>(&mut self, face: FaceToken, fib: &mut F) {
+        let mut affected = Vec::new();
+        self.entries.retain(|prefix, routes| {
+            let count = routes.len();
+            routes.retain(|r| r.face != face);
+            if routes.len() != count {
+                affected.push(prefix.clone());
+            }
+            !routes.is_empty()
+        });
+        for prefix in affected {
+            self.update_fib_under(prefix.as_name(), fib);
+        }
+    }
+
+    // Removes the routes that expired before "now", reporting each of them
+    pub fn prune<F: Fib + ?Sized>(
+        &mut self,
+        now: Timestamp,
+        fib: &mut F,
+        expired: &mut dyn FnMut(Name<'_>, &Route),
+    ) {
+        if self.next_expiry.is_none_or(|next| now <= next) {
+            return;
+        }
+        self.next_expiry = None;
+        let mut affected = Vec::new();
+        let next_expiry = &mut self.next_expiry;
+        self.entries.retain(|prefix, routes| {
+            let count = routes.len();
+            routes.retain(|route| match route.expiry {
+                Some(expiry) if expiry < now => {
+                    expired(prefix.as_name(), route);
+                    false
+                }
+                Some(expiry) => {
+                    *next_expiry = Some(next_expiry.map_or(expiry, |next| next.min(expiry)));
+                    true
+                }
+                None => true,
+            });
+            if routes.len() != count {
+                affected.push(prefix.clone());
+            }
+            !routes.is_empty()
+        });
+        for prefix in affected {
+            self.update_fib_under(prefix.as_name(), fib);
+        }
+    }
+
+    pub fn routes(&self, prefix: Name<'_>) -> &[Route] {
+        self.entries
+            .get(&NameBuf::from(prefix))
+            .map_or(&[], |routes| routes.as_slice())
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (Name<'_>, &[Route])> + '_ {
+        self.entries
+            .iter()
+            .map(|(prefix, routes)| (prefix.as_name(), routes.as_slice()))
+    }
+
+    // The next hops and costs of the FIB entry compiled for the prefix
+    pub fn next_hops(&self, prefix: Name<'_>) -> &[(FaceToken, u32)] {
+        self.installed
+            .get(&NameBuf::from(prefix))
+            .map_or(&[], |hops| hops.as_slice())
+    }
+
+    // The flags of a route affect the longer prefixes, so they are all compiled again
+    fn update_fib_under<F: Fib + ?Sized>(&mut self, prefix: Name<'_>, fib: &mut F) {
+        let mut affected: Vec<NameBuf> = self
+            .entries
+            .keys()
+            .chain(self.installed.keys())
+            .filter(|p| prefix.is_prefix_of(p.as_name()))
+            .cloned()
+            .collect();
+        affected.sort();
+        affected.dedup();
+        for prefix in affected {
+            self.update_fib(prefix, fib);
+        }
+    }
+
+    fn update_fib<F: Fib + ?Sized>(&mut self, prefix: NameBuf, fib: &mut F) {
+        self.compile(prefix.as_name());
+        let previous = self.installed.remove(&prefix).unwrap_or_default();
+        for (face, _) in previous.iter() {
+            if !self.compiled.iter().any(|(f, _)| f == face) {
+                fib.unregister_prefix(prefix.as_name(), *face);
+            }
+        }
+        for hop in self.compiled.iter() {
+            if !previous.contains(hop) {
+                fib.register_prefix(prefix.as_name(), hop.0, hop.1);
+            }
+        }
+        if !self.compiled.is_empty() {
+            self.installed.insert(prefix, self.compiled.clone());
+        }
+    }
+
+    fn compile(&mut self, prefix: Name<'_>) {
+        self.compiled.clear();
+        let Some(routes) = self.entries.get(&NameBuf::from(prefix)) else {
+            return;
+        };
+        for route in routes {
+            add_hop(&mut self.compiled, route.face, route.cost);
+        }
+        if routes.iter().any(|r| r.flags.capture) {
+            return;
+        }
+
+        // From the longest shorter prefix, where the faces keep the cost of the longest one
+        let mut inherited = Vec::new();
+        for count in (0..prefix.component_count()).rev() {
+            let Some(routes) = self.entries.get(&NameBuf::from(prefix.prefix(count))) else {
+                continue;
+            };
+            for route in routes.iter().filter(|r| r.flags.child_inherit) {
+                if !self.compiled.iter().any(|(f, _)| *f == route.face)
+                    && !inherited.iter().any(|(f, _)| *f == route.face)
+                {
+                    inherited.push((route.face, route.cost));
+                }
+            }
+            if routes.iter().any(|r| r.flags.capture) {
+                break;
+            }
+        }
+        self.compiled.extend(inherited);
+    }
+}
+
+// Keeps the lowest cost of the face
+fn add_hop(hops: &mut Vec<(FaceToken, u32)>, face: FaceToken, cost: u32) {
+    match hops.iter_mut().find(|(f, _)| *f == face) {
+        Some(hop) => hop.1 = hop.1.min(cost),
+        None => hops.push((face, cost)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::btree_map::BTreeMap, vec::Vec};
+
+    use crate::{
+        clock::Timestamp,
+        forwarder::FaceToken,
+        name::{Name, NameBuf, NameComponent},
+        tables::{
+            rib::{Rib, Route, RouteFlags, RouteOrigin},
+            Fib,
+        },
+    };
+
+    #[derive(Default)]
+    struct RecordingFib {
+        routes: BTreeMap<(NameBuf, FaceToken), u32>,
+    }
+
+    impl Fib for RecordingFib {
+        fn register_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken, cost: u32) {
+            self.routes.insert((NameBuf::from(name_prefix), face), cost);
+        }
+
+        fn unregister_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken) -> bool {
+            self.routes
+                .remove(&(NameBuf::from(name_prefix), face))
+                .is_some()
+        }
+
+        fn unregister_face_routes(&mut self, face: FaceToken) {
+            self.routes.retain(|(_, f), _| *f != face);
+        }
+    }
+
+    impl RecordingFib {
+        fn faces(&self, prefix: Name<'_>) -> Vec<(u32, u32)> {
+            self.routes
+                .iter()
+                .filter(|((p, _), _)| p.as_name() == prefix)
+                .map(|((_, face), cost)| (face.0, *cost))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_rib() {
+        let mut rib = Rib::new();
+        let mut fib = RecordingFib::default();
+        let root = Name::new();
+        let a = &[NameComponent::generic(b"a")];
+        let ab = &[a[0], NameComponent::generic(b"b")];
+        let ac = &[a[0], NameComponent::generic(b"c")];
+        let route = |face, origin, cost, capture| Route {
+            face: FaceToken(face),
+            origin,
+            cost,
+            flags: RouteFlags {
+                child_inherit: true,
+                capture,
+            },
+            expiry: None,
+        };
+
+        rib.add_route(
+            root.adding_components(ab),
+            route(2, RouteOrigin::App, 5, false),
+            &mut fib,
+        );
+        rib.add_route(
+            root.adding_components(ac),
+            route(3, RouteOrigin::App, 5, true),
+            &mut fib,
+        );
+        rib.add_route(
+            root.adding_components(a),
+            route(1, RouteOrigin::Static, 10, false),
+            &mut fib,
+        );
+        // The same face from another origin keeps the lowest cost
+        rib.add_route(
+            root.adding_components(ab),
+            route(2, RouteOrigin::Static, 1, false),
+            &mut fib,
+        );
+
+        assert_eq!(fib.faces(root.adding_components(a)), [(1, 10)]);
+        assert_eq!(fib.faces(root.adding_components(ab)), [(1, 10), (2, 1)]);
+        // The capture keeps the route of /a out
+        assert_eq!(fib.faces(root.adding_components(ac)), [(3, 5)]);
+        assert_eq!(rib.routes(root.adding_components(ab)).len(), 2);
+
+        // Each origin removes its own route
+        let ab_name = root.adding_components(ab);
+        assert!(rib.remove_route(ab_name, FaceToken(2), RouteOrigin::Static, &mut fib));
+        assert!(!rib.remove_route(ab_name, FaceToken(2), RouteOrigin::Static, &mut fib));
+        assert_eq!(fib.faces(ab_name), [(1, 10), (2, 5)]);
+
+        rib.remove_face(FaceToken(1), &mut fib);
+        assert!(fib.faces(root.adding_components(a)).is_empty());
+        assert_eq!(fib.faces(ab_name), [(2, 5)]);
+        assert_eq!(rib.entries().count(), 2);
+    }
+
+    #[test]
+    fn test_route_expiry() {
+        let mut rib = Rib::new();
+        let mut fib = RecordingFib::default();
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let a = &[NameComponent::generic(b"a")];
+        let name = root.adding_components(a);
+        let route = |face, expiry| Route {
+            face: FaceToken(face),
+            origin: RouteOrigin::App,
+            cost: 0,
+            flags: RouteFlags::default(),
+            expiry,
+        };
+
+        rib.add_route(name, route(1, Some(at(1000))), &mut fib);
+        rib.add_route(name, route(2, None), &mut fib);
+        // Adding it again refreshes it
+        rib.add_route(name, route(1, Some(at(2000))), &mut fib);
+
+        let mut expired = Vec::new();
+        rib.prune(at(1500), &mut fib, &mut |_, route| {
+            expired.push(route.face.0)
+        });
+        assert!(expired.is_empty());
+        rib.prune(at(2500), &mut fib, &mut |_, route| {
+            expired.push(route.face.0)
+        });
+        assert_eq!(expired, [1]);
+        assert_eq!(fib.faces(name), [(2, 0)]);
+    }
+}
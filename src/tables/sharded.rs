@@ -0,0 +1,477 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+};
+
+use alloc::vec::Vec;
+
+use crate::{
+    clock::Timestamp,
+    face::lp::NackReason,
+    forwarder::FaceToken,
+    name::{Name, NameBuf},
+    packet::{Data, Interest},
+    tables::{
+        CanBePrefixSelection, ContentStore, ContentStoreUsage, CsEntryInfo, Fib, FibEntryInfo,
+        NackOutcome, Pit, PitEntryInfo, TableParams, Tables,
+    },
+};
+
+// Tables split into shards by the hash of the first name component, each behind its own
+//  lock, so that several forwarder workers can process packets at the same time as long
+//  as their names fall into different shards.
+// Each worker takes its own clone, which shares the shards but has its own scratchpads,
+//  since the faces and packets are copied out before the lock of the shard is released.
+// The routes are kept in every shard, so that the longest prefix match and the routes
+//  for the forwarding hints are found in whichever shard the interest falls into.
+//  The strategies, their measurements and the face limits are per shard.
+// All the interests and data under a non-empty name share the first component and so
+//  the shard, which is then the only one to look at, also for the interests that can be
+//  prefix. Only the lookups of the data under the empty name go through all the shards.
+pub struct ShardedTables<T> {
+    shared: Arc<Shards<T>>,
+    faces: Vec<FaceToken>,
+    packet: Vec<u8>,
+    over_quota: bool,
+}
+
+struct Shards<T> {
+    shards: Vec<Mutex<T>>,
+    rightmost: AtomicBool,
+}
+
+impl<T> ShardedTables<T> {
+    // Creates at least one shard with "make_shard"
+    pub fn new(shard_count: usize, make_shard: impl FnMut() -> T) -> Self {
+        let shards = core::iter::repeat_with(make_shard)
+            .take(shard_count.max(1))
+            .map(Mutex::new)
+            .collect();
+        Self {
+            shared: Arc::new(Shards {
+                shards,
+                rightmost: AtomicBool::new(false),
+            }),
+            faces: Vec::new(),
+            packet: Vec::new(),
+            over_quota: false,
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shared.shards.len()
+    }
+
+    // For the configuration that is not behind the traits, e.g. the strategies
+    pub fn for_each_shard(&self, mut f: impl FnMut(&mut T)) {
+        for index in 0..self.shard_count() {
+            f(&mut self.shared.lock(index));
+        }
+    }
+}
+
+impl<T> Shards<T> {
+    fn shard_of(&self, name: Name<'_>) -> usize {
+        let Some(component) = name.components().next() else {
+            return 0;
+        };
+        let mut hasher = DefaultHasher::new();
+        component.typ.hash(&mut hasher);
+        component.bytes.hash(&mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+
+    fn lock(&self, index: usize) -> MutexGuard<'_, T> {
+        // A worker that panicked could have left the shard inconsistent, but only
+        //  in the ways that the pruning cleans up eventually
+        self.shards[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> Clone for ShardedTables<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            faces: Vec::new(),
+            packet: Vec::new(),
+            over_quota: false,
+        }
+    }
+}
+
+impl<T: Fib> Fib for ShardedTables<T> {
+    fn register_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken, cost: u32) {
+        self.for_each_shard(|shard| shard.register_prefix(name_prefix, face, cost));
+    }
+
+    fn unregister_prefix(&mut self, name_prefix: Name<'_>, face: FaceToken) -> bool {
+        let mut removed = false;
+        self.for_each_shard(|shard| removed |= shard.unregister_prefix(name_prefix, face));
+        removed
+    }
+
+    fn unregister_face_routes(&mut self, face: FaceToken) {
+        self.for_each_shard(|shard| shard.unregister_face_routes(face));
+    }
+
+    // The routes are the same in every shard
+    fn list_routes(&self, report: &mut dyn FnMut(FibEntryInfo<'_>)) {
+        self.shared.lock(0).list_routes(report);
+    }
+
+    fn has_route(&self, name: Name<'_>) -> bool {
+        self.shared.lock(0).has_route(name)
+    }
+
+    fn register_default_route(&mut self, face: FaceToken, cost: u32) {
+        self.for_each_shard(|shard| shard.register_default_route(face, cost));
+    }
+
+    fn unregister_default_route(&mut self, face: FaceToken) -> bool {
+        let mut removed = false;
+        self.for_each_shard(|shard| removed |= shard.unregister_default_route(face));
+        removed
+    }
+}
+
+impl<T: Fib + Pit> Pit for ShardedTables<T> {
+    fn register_interest<'s>(
+        &'s mut self,
+        interest: &Interest<'_>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+    ) -> impl Iterator<Item = FaceToken> + use<'s, T> {
+        let mut shard = self.shared.lock(self.shared.shard_of(interest.name));
+        let faces = shard.register_interest(interest, nonce, reply_to, now);
+        self.faces.clear();
+        self.faces.extend(faces);
+        self.over_quota = shard.interest_over_quota();
+        drop(shard);
+        self.faces.drain(..)
+    }
+
+    fn add_pit_in_record(
+        &mut self,
+        name: Name<'_>,
+        can_be_prefix: bool,
+        interest_lifetime: Option<u64>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+    ) -> bool {
+        let mut shard = self.shared.lock(self.shared.shard_of(name));
+        let added =
+            shard.add_pit_in_record(name, can_be_prefix, interest_lifetime, nonce, reply_to, now);
+        self.over_quota = shard.interest_over_quota();
+        added
+    }
+
+    fn satisfy_interests<H>(
+        &mut self,
+        name: Name<'_>,
+        from: FaceToken,
+        now: Timestamp,
+        digest_computation: &mut H,
+    ) -> impl Iterator<Item = FaceToken>
+    where
+        H: FnMut() -> [u8; 32],
+    {
+        let mut shard = self.shared.lock(self.shared.shard_of(name));
+        let faces = shard.satisfy_interests(name, from, now, digest_computation);
+        self.faces.clear();
+        self.faces.extend(faces);
+        drop(shard);
+        self.faces.drain(..)
+    }
+
+    fn report_nack(&mut self, name: Name<'_>, from: FaceToken, now: Timestamp) {
+        self.shared
+            .lock(self.shared.shard_of(name))
+            .report_nack(name, from, now);
+    }
+
+    fn process_nack(
+        &mut self,
+        name: Name<'_>,
+        can_be_prefix: bool,
+        nonce: [u8; 4],
+        reason: NackReason,
+        from: FaceToken,
+        now: Timestamp,
+    ) -> (NackOutcome, impl Iterator<Item = FaceToken>) {
+        let mut shard = self.shared.lock(self.shared.shard_of(name));
+        let (outcome, faces) = shard.process_nack(name, can_be_prefix, nonce, reason, from, now);
+        self.faces.clear();
+        self.faces.extend(faces);
+        drop(shard);
+        (outcome, self.faces.drain(..))
+    }
+
+    fn register_face(&mut self, face: FaceToken) {
+        self.for_each_shard(|shard| shard.register_face(face));
+    }
+
+    fn unregister_face_records(&mut self, face: FaceToken) {
+        self.for_each_shard(|shard| shard.unregister_face_records(face));
+    }
+
+    fn prune_pit_if_needed(&mut self, now: Timestamp) {
+        self.for_each_shard(|shard| shard.prune_pit_if_needed(now));
+    }
+
+    fn report_timed_out_interests(&mut self, report: &mut dyn FnMut(Name<'_>, &[FaceToken])) {
+        self.for_each_shard(|shard| shard.report_timed_out_interests(report));
+    }
+
+    fn list_pit_entries(&self, report: &mut dyn FnMut(PitEntryInfo<'_>)) {
+        self.for_each_shard(|shard| shard.list_pit_entries(report));
+    }
+
+    fn interest_over_quota(&self) -> bool {
+        self.over_quota
+    }
+
+    fn set_pit_params(&mut self, params: &TableParams) {
+        self.for_each_shard(|shard| shard.set_pit_params(params));
+    }
+
+    fn set_flood_without_route(&mut self, route_lifetime_ms: Option<u64>) {
+        self.for_each_shard(|shard| shard.set_flood_without_route(route_lifetime_ms));
+    }
+
+    fn set_strategy_parameters(&mut self, prefix: Name<'_>, parameters: Option<Name<'_>>) -> bool {
+        let mut supported = false;
+        self.for_each_shard(|shard| supported |= shard.set_strategy_parameters(prefix, parameters));
+        supported
+    }
+
+    fn set_face_congested(&mut self, face: FaceToken, congested: bool) {
+        self.for_each_shard(|shard| shard.set_face_congested(face, congested));
+    }
+}
+
+// The cache and the PIT of a name are in the same shard, which is only locked once
+impl<T: Tables> Tables for ShardedTables<T> {
+    fn process_interest<'s, 'a>(
+        &'s mut self,
+        interest: &Interest<'a>,
+        nonce: [u8; 4],
+        reply_to: FaceToken,
+        now: Timestamp,
+        satisfy: &mut dyn FnMut(&[u8]),
+    ) -> Option<impl Iterator<Item = FaceToken> + use<'s, 'a, T>> {
+        // The data for the empty name can be in any of the shards
+        if interest.name.component_count() == 0 {
+            let can_be_prefix = interest.can_be_prefix.is_some();
+            let must_be_fresh = interest.must_be_fresh.is_some();
+            if let Some(packet) = self.get_data(interest.name, can_be_prefix, must_be_fresh, now) {
+                satisfy(packet);
+                return None;
+            }
+        }
+
+        let mut shard = self.shared.lock(self.shared.shard_of(interest.name));
+        let faces = shard.process_interest(interest, nonce, reply_to, now, satisfy);
+        let satisfied = faces.is_none();
+        self.faces.clear();
+        self.faces.extend(faces.into_iter().flatten());
+        self.over_quota = shard.interest_over_quota();
+        drop(shard);
+        (!satisfied).then(|| self.faces.drain(..))
+    }
+}
+
+impl<T: ContentStore> ContentStore for ShardedTables<T> {
+    fn insert_data<'a>(
+        &mut self,
+        name: Name<'a>,
+        digest: [u8; 32],
+        freshness: u64,
+        now: Timestamp,
+        packet: &'a [u8],
+    ) {
+        self.shared
+            .lock(self.shared.shard_of(name))
+            .insert_data(name, digest, freshness, now, packet);
+    }
+
+    fn get_data<'a>(
+        &mut self,
+        name: Name<'a>,
+        can_be_prefix: bool,
+        must_be_fresh: bool,
+        now: Timestamp,
+    ) -> Option<&[u8]> {
+        if name.component_count() > 0 || !can_be_prefix {
+            let mut shard = self.shared.lock(self.shared.shard_of(name));
+            let packet = shard.get_data(name, can_be_prefix, must_be_fresh, now)?;
+            self.packet.clear();
+            self.packet.extend_from_slice(packet);
+            return Some(&self.packet);
+        }
+
+        // Every shard has its pick, and the one to take is the leftmost or the rightmost
+        //  of those by their names
+        let rightmost = self.shared.rightmost.load(Ordering::Relaxed);
+        let mut selected: Option<NameBuf> = None;
+        for index in 0..self.shard_count() {
+            let mut shard = self.shared.lock(index);
+            let Some(packet) = shard.get_data(name, true, must_be_fresh, now) else {
+                continue;
+            };
+            let Ok((data, _)) = Data::try_decode_diagnosed(packet) else {
+                continue;
+            };
+            let candidate = NameBuf::from(data.name);
+            let is_better = selected.as_ref().is_none_or(|selected| {
+                (rightmost && candidate > *selected) || (!rightmost && candidate < *selected)
+            });
+            if is_better {
+                self.packet.clear();
+                self.packet.extend_from_slice(packet);
+                selected = Some(candidate);
+            }
+        }
+        selected.map(|_| self.packet.as_slice())
+    }
+
+    fn report_expiring_data(
+        &mut self,
+        now: Timestamp,
+        lead_time_ms: u64,
+        min_hits: u32,
+        report: &mut dyn FnMut(Name<'_>),
+    ) {
+        self.for_each_shard(|shard| {
+            shard.report_expiring_data(now, lead_time_ms, min_hits, report)
+        });
+    }
+
+    fn prune_cs_if_needed(&mut self, now: Timestamp) {
+        self.for_each_shard(|shard| shard.prune_cs_if_needed(now));
+    }
+
+    fn content_store_usage(&self) -> ContentStoreUsage {
+        let mut usage = ContentStoreUsage::default();
+        self.for_each_shard(|shard| {
+            let shard_usage = shard.content_store_usage();
+            usage.entries += shard_usage.entries;
+            usage.bytes += shard_usage.bytes;
+        });
+        usage
+    }
+
+    fn list_cs_entries(&self, report: &mut dyn FnMut(CsEntryInfo<'_>)) {
+        self.for_each_shard(|shard| shard.list_cs_entries(report));
+    }
+
+    fn set_cs_params(&mut self, params: &TableParams) {
+        let rightmost = params.can_be_prefix_selection == CanBePrefixSelection::Rightmost;
+        self.shared.rightmost.store(rightmost, Ordering::Relaxed);
+        self.for_each_shard(|shard| shard.set_cs_params(params));
+    }
+
+    // The prefix can span the shards, so all of them are asked
+    fn erase_data(&mut self, prefix: Name<'_>, limit: Option<usize>) -> usize {
+        let mut erased = 0;
+        self.for_each_shard(|shard| {
+            erased += shard.erase_data(prefix, limit.map(|limit| limit - erased));
+        });
+        erased
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        clock::Timestamp,
+        forwarder::FaceToken,
+        io::Encode,
+        name::{Name, NameComponent},
+        packet::{Data, Interest, SignatureInfo, SignatureValue},
+        tables::{
+            hashed::HashedTables, sharded::ShardedTables, CanBePrefixSelection, ContentStore, Fib,
+            Pit, TableParams, Tables,
+        },
+    };
+
+    #[test]
+    fn test_sharded_tables() {
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let mut tables = ShardedTables::new(4, HashedTables::default);
+        for first in [b"a", b"b", b"c", b"d"] {
+            let prefix = [NameComponent::generic(first)];
+            tables.register_prefix(root.adding_components(&prefix), FaceToken(1), 0);
+        }
+
+        // The workers forward and satisfy the interests under their own prefixes
+        std::thread::scope(|scope| {
+            for (worker, first) in [b"a", b"b", b"c", b"d"].into_iter().enumerate() {
+                let mut tables = tables.clone();
+                scope.spawn(move || {
+                    let downstream = FaceToken(10 + worker as u32);
+                    for i in 0u32..100 {
+                        let id = i.to_be_bytes();
+                        let comp = [NameComponent::generic(first), NameComponent::generic(&id)];
+                        let name = root.adding_components(&comp);
+                        let nonce = (i + 1000 * worker as u32).to_be_bytes();
+                        let faces: Vec<_> = tables
+                            .register_interest(
+                                &Interest::new(name, false, nonce),
+                                nonce,
+                                downstream,
+                                now,
+                            )
+                            .collect();
+                        assert_eq!(faces, [FaceToken(1)]);
+                        let faces: Vec<_> = tables
+                            .satisfy_interests(name, FaceToken(1), now, &mut || [0; 32])
+                            .collect();
+                        assert_eq!(faces, [downstream]);
+                    }
+                });
+            }
+        });
+
+        // The data under the empty name is selected among all the shards
+        let mut packets = Vec::new();
+        for (first, second) in [(b"b", b"1"), (b"a", b"2"), (b"d", b"3")] {
+            let comp = [
+                NameComponent::generic(first),
+                NameComponent::generic(second),
+            ];
+            let name = root.adding_components(&comp);
+            let mut data = Data::new_unsigned(name, b"", SignatureInfo::new_digest_sha256());
+            data.signature_value = SignatureValue { bytes: &[0; 32] };
+            let mut packet = Vec::new();
+            assert!(data.encode(&mut packet).is_ok());
+            tables.insert_data(name, [second[0]; 32], 1000, now, &packet);
+            packets.push(packet);
+        }
+        assert_eq!(tables.content_store_usage().entries, 3);
+        assert_eq!(
+            tables.get_data(root, true, false, now),
+            Some(&packets[1][..])
+        );
+        tables.set_params(&TableParams {
+            can_be_prefix_selection: CanBePrefixSelection::Rightmost,
+            ..TableParams::default()
+        });
+        assert_eq!(
+            tables.get_data(root, true, false, now),
+            Some(&packets[2][..])
+        );
+    }
+}
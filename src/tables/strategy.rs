@@ -0,0 +1,1254 @@
+use alloc::{boxed::Box, collections::btree_map::BTreeMap, vec::Vec};
+
+use crate::{
+    clock::Timestamp,
+    forwarder::FaceToken,
+    name::{Name, NameBuf},
+    tables::{
+        measurements::{FaceMeasurements, Measurements},
+        reference::PitEntry,
+        PitInRecordInfo, PitOutRecordInfo,
+    },
+};
+
+// A route the interest can be forwarded on
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct NextHop {
+    pub face: FaceToken,
+    pub cost: u32,
+    // Set while the packets wait too long in the queue of the face, only known to the
+    //  tables that are told, see Pit::set_face_congested
+    pub congested: bool,
+}
+
+// The interest that the strategy is asked to forward, along with the state of its PIT entry
+#[derive(Copy, Clone)]
+pub struct PendingInterest<'a> {
+    pub name: Name<'a>,
+    pub can_be_prefix: bool,
+    pub reply_to: FaceToken,
+    pub now: Timestamp,
+    // True if the PIT entry was just created, false for retransmissions and
+    //  interests from other faces that are aggregated with the pending ones
+    pub is_new: bool,
+    // How many times the PIT entry was forwarded and when it was last
+    pub transmission_count: u8,
+    pub latest_transmission_time: Timestamp,
+    // The in-records of the entry, including the one for this interest
+    pub pit_entry: PitEntryView<'a>,
+    // False if there is no route for the interest and the next hops are all the faces
+    //  (see Strategy::floods_without_route)
+    pub has_route: bool,
+    // Those of the namespace, already taken into account here
+    pub suppression: RetransmissionSuppression,
+    // See StrategyChoice::set_parameters
+    pub parameters: StrategyParameters<'a>,
+}
+
+impl<'a> PendingInterest<'a> {
+    // Retransmissions are only forwarded after an exponential backoff,
+    //  so that they do not flood the upstreams
+    pub fn is_suppressed(&self) -> bool {
+        if self.is_new {
+            return false;
+        }
+        let minimum_retransmission_delay = self.suppression.delay_ms(self.transmission_count);
+        self.now
+            < self
+                .latest_transmission_time
+                .adding(minimum_retransmission_delay)
+    }
+}
+
+// A read-only view of the PIT entry of a pending interest, e.g. to see how many
+//  downstreams are waiting for an aggregated interest before retransmitting it
+#[derive(Copy, Clone)]
+pub struct PitEntryView<'a> {
+    entry: &'a PitEntry,
+}
+
+impl<'a> PitEntryView<'a> {
+    pub(super) fn new(entry: &'a PitEntry) -> Self {
+        Self { entry }
+    }
+
+    // The number of downstream faces waiting for the data
+    pub fn in_record_count(&self) -> usize {
+        self.entry.pit_in.len()
+    }
+
+    pub fn in_records(&self) -> impl Iterator<Item = PitInRecordInfo> + 'a {
+        self.entry.in_records()
+    }
+
+    // The upstreams the interest was already sent to, and whether they sent back a Nack
+    pub fn out_records(&self) -> impl Iterator<Item = PitOutRecordInfo> + 'a {
+        self.entry.out_records()
+    }
+
+    // The latest nonce from each of the downstream faces
+    pub fn nonces(&self) -> impl Iterator<Item = [u8; 4]> + 'a {
+        self.entry.nonces()
+    }
+}
+
+// The parameters of the strategies for a namespace, each a name component of the form
+//  key~value as in the strategy names of NFD, e.g. probing-interval~30000
+#[derive(Copy, Clone, Default)]
+pub struct StrategyParameters<'a> {
+    parameters: Option<Name<'a>>,
+}
+
+impl<'a> StrategyParameters<'a> {
+    pub fn new(parameters: Name<'a>) -> Self {
+        Self {
+            parameters: Some(parameters),
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&'a [u8]> {
+        self.parameters?.components().find_map(|component| {
+            let separator = component.bytes.iter().position(|b| *b == b'~')?;
+            let (k, value) = component.bytes.split_at(separator);
+            (k == key).then_some(&value[1..])
+        })
+    }
+
+    // The value in decimal
+    pub fn get_u64(&self, key: &[u8]) -> Option<u64> {
+        core::str::from_utf8(self.get(key)?).ok()?.parse().ok()
+    }
+
+    // With suppression-initial and suppression-max, in milliseconds, instead
+    pub fn suppression(&self, default: RetransmissionSuppression) -> RetransmissionSuppression {
+        RetransmissionSuppression {
+            initial_delay_ms: self
+                .get_u64(b"suppression-initial")
+                .unwrap_or(default.initial_delay_ms),
+            max_delay_ms: self
+                .get_u64(b"suppression-max")
+                .unwrap_or(default.max_delay_ms),
+        }
+    }
+}
+
+// The backoff of the retransmissions: the first one is forwarded no sooner than
+//  "initial_delay_ms" after the interest, and the delay doubles with every
+//  transmission until "max_delay_ms". By default it starts at 16 ms and goes up to 256 ms.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RetransmissionSuppression {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl RetransmissionSuppression {
+    pub fn delay_ms(&self, transmission_count: u8) -> u64 {
+        let doublings = transmission_count.saturating_sub(1).min(32);
+        self.initial_delay_ms
+            .saturating_mul(1 << doublings)
+            .min(self.max_delay_ms)
+    }
+}
+
+impl Default for RetransmissionSuppression {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 16,
+            max_delay_ms: 256,
+        }
+    }
+}
+
+// A route that a strategy learned from the data it got
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct LearnedRoute {
+    // The number of leading components of the data name that make the prefix
+    pub prefix_length: usize,
+    pub cost: u32,
+    pub lifetime_ms: u64,
+}
+
+// Decides where the interests under a prefix are forwarded (see StrategyChoice).
+// The PIT takes care of the loops, so the strategy is only asked about the interests
+//  that may be forwarded, and the routes are ordered from the most preferred one:
+//  the longest matching prefix first and the lowest cost first within a prefix.
+// What the strategy learns about the namespaces can be kept in the measurements,
+//  which are shared by all the strategies.
+pub trait Strategy {
+    // Adds the faces to forward the interest to, if any
+    fn after_receive_interest(
+        &mut self,
+        interest: &PendingInterest<'_>,
+        next_hops: &[NextHop],
+        measurements: &mut Measurements,
+        forward_to: &mut Vec<FaceToken>,
+    );
+
+    // Adds the faces to send the interest to on top of the ones it was forwarded to,
+    //  e.g. to measure the alternatives. Only called if the interest was forwarded.
+    fn probe(
+        &mut self,
+        _interest: &PendingInterest<'_>,
+        _next_hops: &[NextHop],
+        _measurements: &mut Measurements,
+        _probe_to: &mut Vec<FaceToken>,
+    ) {
+    }
+
+    // The data satisfied the pending interests for its name
+    fn after_receive_data(
+        &mut self,
+        _name: Name<'_>,
+        _from: FaceToken,
+        _now: Timestamp,
+        _measurements: &mut Measurements,
+    ) {
+    }
+
+    // The upstream face could not satisfy the interest
+    fn after_receive_nack(
+        &mut self,
+        _name: Name<'_>,
+        _from: FaceToken,
+        _now: Timestamp,
+        _measurements: &mut Measurements,
+    ) {
+    }
+
+    // The pending interests for the name expired without getting any data
+    fn after_interest_timeout(
+        &mut self,
+        _name: Name<'_>,
+        _now: Timestamp,
+        _measurements: &mut Measurements,
+    ) {
+    }
+
+    // Whether the interests without any route are offered to after_receive_interest
+    //  with all the faces as the next hops, rather than dropped
+    fn floods_without_route(&mut self, _name: Name<'_>) -> bool {
+        false
+    }
+
+    // Called after after_receive_data, the tables add the returned route to the FIB
+    //  towards the face the data came from, and remove it once its lifetime lapses
+    //  unless it is learned again in the meantime
+    fn learn_route(
+        &mut self,
+        _name: Name<'_>,
+        _from: FaceToken,
+        _now: Timestamp,
+    ) -> Option<LearnedRoute> {
+        None
+    }
+
+    // The face was removed, so whatever the strategy keeps about it is forgotten before
+    //  its token is given to another face. The tables forget its measurements after this.
+    fn after_face_removed(&mut self, _face: FaceToken, _measurements: &mut Measurements) {}
+}
+
+// Forwards a new interest to the most preferred route that is not congested, or the most
+//  preferred one if all are, and every retransmission that is not suppressed to the next
+//  one, wrapping around the list as needed.
+// The face is picked using only the index of the transmission, which assumes
+//  that FIB is stable, but changes to FIB are not critical for correctness.
+#[derive(Default)]
+pub struct BestRouteStrategy {}
+
+impl Strategy for BestRouteStrategy {
+    fn after_receive_interest(
+        &mut self,
+        interest: &PendingInterest<'_>,
+        next_hops: &[NextHop],
+        _measurements: &mut Measurements,
+        forward_to: &mut Vec<FaceToken>,
+    ) {
+        if interest.is_new {
+            let next_hop = next_hops
+                .iter()
+                .find(|next_hop| !next_hop.congested)
+                .unwrap_or(&next_hops[0]);
+            forward_to.push(next_hop.face);
+            return;
+        }
+        if interest.is_suppressed() {
+            return;
+        }
+        let index = interest.transmission_count.wrapping_add(1) as usize % next_hops.len();
+        forward_to.push(next_hops[index].face);
+    }
+}
+
+// Forwards every interest that is not suppressed to all the routes,
+//  e.g. for the prefixes served by many producers
+#[derive(Default)]
+pub struct MulticastStrategy {}
+
+impl Strategy for MulticastStrategy {
+    fn after_receive_interest(
+        &mut self,
+        interest: &PendingInterest<'_>,
+        next_hops: &[NextHop],
+        _measurements: &mut Measurements,
+        forward_to: &mut Vec<FaceToken>,
+    ) {
+        if interest.is_suppressed() {
+            return;
+        }
+        forward_to_all(next_hops, forward_to);
+    }
+}
+
+pub(crate) fn forward_to_all(next_hops: &[NextHop], forward_to: &mut Vec<FaceToken>) {
+    for next_hop in next_hops {
+        if !forward_to.contains(&next_hop.face) {
+            forward_to.push(next_hop.face);
+        }
+    }
+}
+
+// Meant for the routers at the edge of the network, in front of many hosts that
+//  register the same prefixes: the first interest in a namespace goes to all the
+//  faces, and the following ones only to the face that brought the data the last time.
+// Retransmissions go to all the faces again, and the face is forgotten if the interest
+//  times out or is nacked on it. The namespace is the data name without the last
+//  component, and the interests use the longest namespace that matches their name.
+#[derive(Default)]
+pub struct AccessStrategy {}
+
+// Kept in the measurements of the namespace
+#[derive(Default)]
+struct AccessState {
+    last_working: Option<FaceToken>,
+}
+
+impl Strategy for AccessStrategy {
+    fn after_receive_interest(
+        &mut self,
+        interest: &PendingInterest<'_>,
+        next_hops: &[NextHop],
+        measurements: &mut Measurements,
+        forward_to: &mut Vec<FaceToken>,
+    ) {
+        if !interest.is_new {
+            if !interest.is_suppressed() {
+                forward_to_all(next_hops, forward_to);
+            }
+            return;
+        }
+
+        let last_working = measurements
+            .longest_prefix_match_mut(interest.name)
+            .and_then(|entry| entry.state::<AccessState>())
+            .and_then(|state| state.last_working)
+            .filter(|face| *face != interest.reply_to)
+            .filter(|face| next_hops.iter().any(|next_hop| next_hop.face == *face));
+        match last_working {
+            Some(face) => forward_to.push(face),
+            None => forward_to_all(next_hops, forward_to),
+        }
+    }
+
+    fn after_receive_data(
+        &mut self,
+        name: Name<'_>,
+        from: FaceToken,
+        now: Timestamp,
+        measurements: &mut Measurements,
+    ) {
+        let namespace = namespace_of(name);
+        let entry = measurements.entry(namespace.as_name(), now, ACCESS_MEASUREMENT_LIFETIME_MS);
+        entry.state_mut::<AccessState>().last_working = Some(from);
+    }
+
+    fn after_receive_nack(
+        &mut self,
+        name: Name<'_>,
+        from: FaceToken,
+        _now: Timestamp,
+        measurements: &mut Measurements,
+    ) {
+        if let Some(entry) = measurements.longest_prefix_match_mut(name) {
+            let state = entry.state_mut::<AccessState>();
+            if state.last_working == Some(from) {
+                state.last_working = None;
+            }
+        }
+    }
+
+    fn after_interest_timeout(
+        &mut self,
+        name: Name<'_>,
+        _now: Timestamp,
+        measurements: &mut Measurements,
+    ) {
+        if let Some(entry) = measurements.longest_prefix_match_mut(name) {
+            entry.state_mut::<AccessState>().last_working = None;
+        }
+    }
+
+    fn after_face_removed(&mut self, face: FaceToken, measurements: &mut Measurements) {
+        for entry in measurements.iter_mut() {
+            let state = entry.state::<AccessState>();
+            if state.is_some_and(|state| state.last_working == Some(face)) {
+                entry.state_mut::<AccessState>().last_working = None;
+            }
+        }
+    }
+}
+
+// Adaptive Smoothed RTT-based Forwarding: forwards to the face with the lowest
+//  smoothed round-trip time for the namespace of the interest (its name without the
+//  last component), and every probing interval also sends the interest to one of the
+//  other faces, taking turns, so that their measurements stay up to date.
+// The probing-interval parameter of a namespace, in milliseconds, takes precedence.
+// The faces that were not measured yet come after the measured ones, ordered by cost,
+//  and the faces that timed out too many times in a row come last.
+pub struct AsfStrategy {
+    probing_interval_ms: u64,
+    max_silent_timeouts: u32,
+    // When the pending interests were sent to each face, to measure the RTT
+    outstanding: BTreeMap<NameBuf, Vec<(FaceToken, Timestamp)>>,
+    last_prune_time: Timestamp,
+    ranked: Vec<FaceToken>,
+}
+
+// Kept in the measurements of the namespace, along with the RTT of the faces
+#[derive(Default)]
+struct AsfState {
+    last_probe_time: Option<Timestamp>,
+    probe_count: usize,
+}
+
+impl AsfStrategy {
+    pub const DEFAULT_PROBING_INTERVAL_MS: u64 = 60 * 1000;
+    pub const DEFAULT_MAX_SILENT_TIMEOUTS: u32 = 1;
+
+    // A face stops being used once it timed out "max_silent_timeouts" times in a row
+    pub fn new(probing_interval_ms: u64, max_silent_timeouts: u32) -> Self {
+        Self {
+            probing_interval_ms,
+            max_silent_timeouts,
+            outstanding: BTreeMap::new(),
+            last_prune_time: Timestamp { ms_since_1970: 0 },
+            ranked: Vec::new(),
+        }
+    }
+
+    // Orders the next hops from the best one, leaving out the face the interest came from
+    fn rank(
+        &mut self,
+        interest: &PendingInterest<'_>,
+        next_hops: &[NextHop],
+        measurements: &mut Measurements,
+    ) {
+        self.ranked.clear();
+        let namespace = namespace_of(interest.name);
+        let entry = measurements.entry(
+            namespace.as_name(),
+            interest.now,
+            ASF_MEASUREMENT_LIFETIME_MS,
+        );
+        let state = entry.state_mut::<AsfState>();
+        if state.last_probe_time.is_none() {
+            state.last_probe_time = Some(interest.now);
+        }
+
+        let max_silent_timeouts = self.max_silent_timeouts;
+        let rank = |face: &FaceToken| match entry.face(*face) {
+            Some(m) if m.consecutive_failures >= max_silent_timeouts => (2, 0),
+            Some(FaceMeasurements {
+                srtt_ms: Some(srtt_ms),
+                ..
+            }) => (0, *srtt_ms),
+            _ => (1, 0),
+        };
+        for next_hop in next_hops {
+            if next_hop.face != interest.reply_to && !self.ranked.contains(&next_hop.face) {
+                self.ranked.push(next_hop.face);
+            }
+        }
+        // Stable, so that the faces with the same rank stay in the order of preference
+        self.ranked.sort_by_key(rank);
+    }
+
+    fn record_sent(&mut self, name: Name<'_>, face: FaceToken, now: Timestamp) {
+        let sent = self.outstanding.entry(NameBuf::from(name)).or_default();
+        match sent.iter_mut().find(|(f, _)| *f == face) {
+            Some((_, time)) => *time = now,
+            None => sent.push((face, now)),
+        }
+    }
+
+    // Forgets the interests that were never answered nor reported as timed out
+    fn prune_if_needed(&mut self, now: Timestamp) {
+        if now < self.last_prune_time.adding(ASF_PRUNE_INTERVAL_MS) {
+            return;
+        }
+        self.last_prune_time = now;
+        self.outstanding.retain(|_, sent| {
+            sent.retain(|(_, time)| now < time.adding(ASF_MEASUREMENT_LIFETIME_MS));
+            !sent.is_empty()
+        });
+    }
+}
+
+impl Default for AsfStrategy {
+    fn default() -> Self {
+        Self::new(
+            Self::DEFAULT_PROBING_INTERVAL_MS,
+            Self::DEFAULT_MAX_SILENT_TIMEOUTS,
+        )
+    }
+}
+
+impl Strategy for AsfStrategy {
+    fn after_face_removed(&mut self, face: FaceToken, _measurements: &mut Measurements) {
+        self.outstanding.retain(|_, sent| {
+            sent.retain(|(f, _)| *f != face);
+            !sent.is_empty()
+        });
+    }
+
+    fn after_receive_interest(
+        &mut self,
+        interest: &PendingInterest<'_>,
+        next_hops: &[NextHop],
+        measurements: &mut Measurements,
+        forward_to: &mut Vec<FaceToken>,
+    ) {
+        self.prune_if_needed(interest.now);
+        if interest.is_suppressed() {
+            return;
+        }
+        self.rank(interest, next_hops, measurements);
+        if let Some(face) = self.ranked.first().copied() {
+            forward_to.push(face);
+            self.record_sent(interest.name, face, interest.now);
+        }
+    }
+
+    fn probe(
+        &mut self,
+        interest: &PendingInterest<'_>,
+        _next_hops: &[NextHop],
+        measurements: &mut Measurements,
+        probe_to: &mut Vec<FaceToken>,
+    ) {
+        let namespace = namespace_of(interest.name);
+        let Some(entry) = measurements.get_mut(namespace.as_name()) else {
+            return;
+        };
+        let state = entry.state_mut::<AsfState>();
+        let last_probe_time = state.last_probe_time.unwrap_or(interest.now);
+        let probing_interval_ms = interest
+            .parameters
+            .get_u64(b"probing-interval")
+            .unwrap_or(self.probing_interval_ms);
+        if interest.now < last_probe_time.adding(probing_interval_ms) {
+            return;
+        }
+        // The best face was already used
+        let candidates = self.ranked.len().saturating_sub(1);
+        if candidates == 0 {
+            return;
+        }
+        let face = self.ranked[1 + state.probe_count % candidates];
+        state.last_probe_time = Some(interest.now);
+        state.probe_count += 1;
+        if !probe_to.contains(&face) {
+            probe_to.push(face);
+            self.record_sent(interest.name, face, interest.now);
+        }
+    }
+
+    fn after_receive_data(
+        &mut self,
+        name: Name<'_>,
+        from: FaceToken,
+        now: Timestamp,
+        measurements: &mut Measurements,
+    ) {
+        // The interest may have been for a prefix of the data name
+        let mut interest_name = None;
+        for count in (1..=name.component_count()).rev() {
+            let prefix = NameBuf::from(name.prefix(count));
+            if self.outstanding.contains_key(&prefix) {
+                interest_name = Some(prefix);
+                break;
+            }
+        }
+        let Some(interest_name) = interest_name else {
+            return;
+        };
+        let Some(sent) = self.outstanding.remove(&interest_name) else {
+            return;
+        };
+        let Some((_, sent_time)) = sent.iter().find(|(face, _)| *face == from) else {
+            return;
+        };
+        let rtt_ms = now.ms_since_1970.saturating_sub(sent_time.ms_since_1970);
+        let namespace = namespace_of(interest_name.as_name());
+        measurements
+            .entry(namespace.as_name(), now, ASF_MEASUREMENT_LIFETIME_MS)
+            .face_mut(from)
+            .add_rtt_sample(rtt_ms);
+    }
+
+    fn after_receive_nack(
+        &mut self,
+        name: Name<'_>,
+        from: FaceToken,
+        now: Timestamp,
+        measurements: &mut Measurements,
+    ) {
+        let key = NameBuf::from(name);
+        if let Some(sent) = self.outstanding.get_mut(&key) {
+            sent.retain(|(face, _)| *face != from);
+            if sent.is_empty() {
+                self.outstanding.remove(&key);
+            }
+        }
+        // A nack takes the face out of use right away
+        let namespace = namespace_of(name);
+        let face = measurements
+            .entry(namespace.as_name(), now, ASF_MEASUREMENT_LIFETIME_MS)
+            .face_mut(from);
+        face.add_failure();
+        face.consecutive_failures = face.consecutive_failures.max(self.max_silent_timeouts);
+    }
+
+    fn after_interest_timeout(
+        &mut self,
+        name: Name<'_>,
+        now: Timestamp,
+        measurements: &mut Measurements,
+    ) {
+        let Some(sent) = self.outstanding.remove(&NameBuf::from(name)) else {
+            return;
+        };
+        let namespace = namespace_of(name);
+        let entry = measurements.entry(namespace.as_name(), now, ASF_MEASUREMENT_LIFETIME_MS);
+        for (face, _) in sent {
+            entry.face_mut(face).add_failure();
+        }
+    }
+}
+
+// Floods the interests that have no route on all the faces, and learns a route for the
+//  namespace of the data that comes back (its name without the last component) towards
+//  the face it came from. The interests with a route go to the most preferred one,
+//  but their retransmissions are flooded on all the routes again.
+pub struct SelfLearningStrategy {
+    route_lifetime_ms: u64,
+}
+
+impl SelfLearningStrategy {
+    pub const DEFAULT_ROUTE_LIFETIME_MS: u64 = 10 * 60 * 1000;
+    pub const LEARNED_ROUTE_COST: u32 = 100;
+
+    pub fn new(route_lifetime_ms: u64) -> Self {
+        Self { route_lifetime_ms }
+    }
+}
+
+impl Default for SelfLearningStrategy {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_ROUTE_LIFETIME_MS)
+    }
+}
+
+impl Strategy for SelfLearningStrategy {
+    fn after_receive_interest(
+        &mut self,
+        interest: &PendingInterest<'_>,
+        next_hops: &[NextHop],
+        _measurements: &mut Measurements,
+        forward_to: &mut Vec<FaceToken>,
+    ) {
+        if interest.is_suppressed() {
+            return;
+        }
+        if interest.is_new && interest.has_route {
+            forward_to.push(next_hops[0].face);
+        } else {
+            forward_to_all(next_hops, forward_to);
+        }
+    }
+
+    fn floods_without_route(&mut self, _name: Name<'_>) -> bool {
+        true
+    }
+
+    fn learn_route(
+        &mut self,
+        name: Name<'_>,
+        _from: FaceToken,
+        _now: Timestamp,
+    ) -> Option<LearnedRoute> {
+        Some(LearnedRoute {
+            prefix_length: name.component_count().saturating_sub(1).max(1),
+            cost: Self::LEARNED_ROUTE_COST,
+            lifetime_ms: self.route_lifetime_ms,
+        })
+    }
+}
+
+fn namespace_of(name: Name<'_>) -> NameBuf {
+    match name.dropping_last_component() {
+        Some(namespace) if namespace.component_count() > 0 => NameBuf::from(namespace),
+        _ => NameBuf::from(name),
+    }
+}
+
+// The strategies assigned to the name prefixes, where the longest matching prefix decides.
+// The root prefix always has a strategy, the best route one by default.
+pub struct StrategyChoice {
+    default: Box<dyn Strategy>,
+    choices: Vec<(NameBuf, Box<dyn Strategy>)>,
+    parameters: Vec<(NameBuf, NameBuf)>,
+}
+
+impl StrategyChoice {
+    pub fn new(default: Box<dyn Strategy>) -> Self {
+        Self {
+            default,
+            choices: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    // The parameters are passed to the strategy of the names under the prefix, see
+    //  StrategyParameters. The longest prefix with parameters decides, whether or not
+    //  the strategy is set for the same prefix. Replaces those the prefix already had.
+    pub fn set_parameters(&mut self, prefix: Name<'_>, parameters: Name<'_>) {
+        match self
+            .parameters
+            .iter_mut()
+            .find(|(p, _)| p.as_name() == prefix)
+        {
+            Some((_, existing)) => *existing = NameBuf::from(parameters),
+            None => self
+                .parameters
+                .push((NameBuf::from(prefix), NameBuf::from(parameters))),
+        }
+    }
+
+    pub fn unset_parameters(&mut self, prefix: Name<'_>) -> bool {
+        let count = self.parameters.len();
+        self.parameters.retain(|(p, _)| p.as_name() != prefix);
+        self.parameters.len() != count
+    }
+
+    pub fn find_parameters(&self, name: Name<'_>) -> StrategyParameters<'_> {
+        longest_match_parameters(&self.parameters, name)
+    }
+
+    pub fn find_strategy_with_parameters(
+        &mut self,
+        name: Name<'_>,
+    ) -> (&mut dyn Strategy, StrategyParameters<'_>) {
+        let choice = self
+            .choices
+            .iter_mut()
+            .filter(|(prefix, _)| prefix.as_name().is_prefix_of(name))
+            .max_by_key(|(prefix, _)| prefix.component_count());
+        let strategy = match choice {
+            Some((_, strategy)) => strategy.as_mut(),
+            None => self.default.as_mut(),
+        };
+        (strategy, longest_match_parameters(&self.parameters, name))
+    }
+
+    // Replaces the strategy if the prefix already had one
+    pub fn set_strategy(&mut self, prefix: Name<'_>, strategy: Box<dyn Strategy>) {
+        if prefix.component_count() == 0 {
+            self.default = strategy;
+            return;
+        }
+        match self.choices.iter_mut().find(|(p, _)| p.as_name() == prefix) {
+            Some((_, existing)) => *existing = strategy,
+            None => self.choices.push((NameBuf::from(prefix), strategy)),
+        }
+    }
+
+    // The names under the prefix go back to the strategy of a shorter prefix.
+    // The root prefix cannot be unset.
+    pub fn unset_strategy(&mut self, prefix: Name<'_>) -> bool {
+        let count = self.choices.len();
+        self.choices.retain(|(p, _)| p.as_name() != prefix);
+        self.choices.len() != count
+    }
+
+    // Tells every strategy, see Strategy::after_face_removed
+    pub fn after_face_removed(&mut self, face: FaceToken, measurements: &mut Measurements) {
+        self.default.after_face_removed(face, measurements);
+        for (_, strategy) in self.choices.iter_mut() {
+            strategy.after_face_removed(face, measurements);
+        }
+    }
+
+    pub fn find_strategy(&mut self, name: Name<'_>) -> &mut dyn Strategy {
+        let choice = self
+            .choices
+            .iter_mut()
+            .filter(|(prefix, _)| prefix.as_name().is_prefix_of(name))
+            .max_by_key(|(prefix, _)| prefix.component_count());
+        match choice {
+            Some((_, strategy)) => strategy.as_mut(),
+            None => self.default.as_mut(),
+        }
+    }
+}
+
+fn longest_match_parameters<'a>(
+    parameters: &'a [(NameBuf, NameBuf)],
+    name: Name<'_>,
+) -> StrategyParameters<'a> {
+    parameters
+        .iter()
+        .filter(|(prefix, _)| prefix.as_name().is_prefix_of(name))
+        .max_by_key(|(prefix, _)| prefix.component_count())
+        .map(|(_, parameters)| StrategyParameters::new(parameters.as_name()))
+        .unwrap_or_default()
+}
+
+impl Default for StrategyChoice {
+    fn default() -> Self {
+        Self::new(Box::new(BestRouteStrategy::default()))
+    }
+}
+
+const ASF_PRUNE_INTERVAL_MS: u64 = 10 * 1000;
+const ASF_MEASUREMENT_LIFETIME_MS: u64 = 5 * 60 * 1000;
+
+const ACCESS_MEASUREMENT_LIFETIME_MS: u64 = 5 * 60 * 1000;
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc, vec, vec::Vec};
+    use core::cell::RefCell;
+
+    use crate::{
+        clock::Timestamp,
+        forwarder::FaceToken,
+        name::{Name, NameComponent},
+        packet::{Interest, InterestLifetime},
+        tables::{
+            measurements::Measurements,
+            reference::ReferenceTables,
+            strategy::{
+                AccessStrategy, AsfStrategy, MulticastStrategy, NextHop, PendingInterest,
+                RetransmissionSuppression, SelfLearningStrategy, Strategy, StrategyChoice,
+            },
+            Fib, Pit, Tables,
+        },
+    };
+
+    // Uses the best route and records where the data came from
+    struct RecordingStrategy {
+        data_from: Rc<RefCell<Vec<FaceToken>>>,
+    }
+
+    impl Strategy for RecordingStrategy {
+        fn after_receive_interest(
+            &mut self,
+            _interest: &PendingInterest<'_>,
+            next_hops: &[NextHop],
+            _measurements: &mut Measurements,
+            forward_to: &mut Vec<FaceToken>,
+        ) {
+            forward_to.push(next_hops[0].face);
+        }
+
+        fn after_receive_data(
+            &mut self,
+            _name: Name<'_>,
+            from: FaceToken,
+            _now: Timestamp,
+            _measurements: &mut Measurements,
+        ) {
+            self.data_from.borrow_mut().push(from);
+        }
+    }
+
+    #[test]
+    fn test_strategy_choice() {
+        let mut tables = ReferenceTables::default();
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let video = &[NameComponent::generic(b"video")];
+        let live = &[
+            NameComponent::generic(b"video"),
+            NameComponent::generic(b"live"),
+        ];
+        let news = &[NameComponent::generic(b"news")];
+        for name in [video.as_slice(), news.as_slice()] {
+            tables.register_prefix(root.adding_components(name), FaceToken(1), 10);
+            tables.register_prefix(root.adding_components(name), FaceToken(2), 20);
+        }
+        let data_from = Rc::new(RefCell::new(Vec::new()));
+        tables.set_strategy(
+            root.adding_components(video),
+            Box::new(MulticastStrategy::default()),
+        );
+        tables.set_strategy(
+            root.adding_components(live),
+            Box::new(RecordingStrategy {
+                data_from: data_from.clone(),
+            }),
+        );
+
+        let forward = |tables: &mut ReferenceTables, comp: &[NameComponent<'_>]| {
+            let name = root.adding_components(comp);
+            tables
+                .register_interest(
+                    &Interest::new(name, false, [1, 2, 3, 4]),
+                    [1, 2, 3, 4],
+                    FaceToken(0),
+                    now,
+                )
+                .collect::<Vec<_>>()
+        };
+
+        // The default strategy only uses the cheapest route
+        let news_item = &[news[0], NameComponent::generic(b"1")];
+        assert_eq!(forward(&mut tables, news_item), [FaceToken(1)]);
+        let video_item = &[video[0], NameComponent::generic(b"1")];
+        assert_eq!(
+            forward(&mut tables, video_item),
+            [FaceToken(1), FaceToken(2)]
+        );
+        // The longest prefix wins
+        let live_item = &[live[0], live[1], NameComponent::generic(b"1")];
+        assert_eq!(forward(&mut tables, live_item), [FaceToken(1)]);
+
+        let name = root.adding_components(live_item);
+        let faces: Vec<_> = tables
+            .satisfy_interests(name, FaceToken(1), now, &mut || [0; 32])
+            .collect();
+        assert_eq!(faces, [FaceToken(0)]);
+        assert_eq!(*data_from.borrow(), [FaceToken(1)]);
+
+        // Without its own strategy /video/live goes back to multicast
+        assert!(tables.unset_strategy(root.adding_components(live)));
+        assert!(!tables.unset_strategy(root.adding_components(live)));
+        let live_item = &[live[0], live[1], NameComponent::generic(b"2")];
+        assert_eq!(
+            forward(&mut tables, live_item),
+            [FaceToken(1), FaceToken(2)]
+        );
+    }
+
+    #[test]
+    fn test_asf_strategy() {
+        let mut tables = ReferenceTables::default();
+        let root = Name::new();
+        let app = &[NameComponent::generic(b"app")];
+        tables.register_prefix(root.adding_components(app), FaceToken(1), 10);
+        tables.register_prefix(root.adding_components(app), FaceToken(2), 20);
+        tables.set_strategy(
+            root.adding_components(app),
+            Box::new(AsfStrategy::new(1000, 1)),
+        );
+
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let items: Vec<_> = [b"1", b"2", b"3", b"4"]
+            .iter()
+            .map(|item| [app[0], NameComponent::generic(*item)])
+            .collect();
+        let forward = |tables: &mut ReferenceTables, item: usize, now| {
+            let name = root.adding_components(&items[item]);
+            tables
+                .register_interest(
+                    &Interest {
+                        interest_lifetime: Some(InterestLifetime { val: 100 }),
+                        ..Interest::new(name, false, [1, 2, 3, 4])
+                    },
+                    [1, 2, 3, 4],
+                    FaceToken(0),
+                    now,
+                )
+                .collect::<Vec<_>>()
+        };
+        let reply = |tables: &mut ReferenceTables, item: usize, from, now| {
+            let name = root.adding_components(&items[item]);
+            let _ = tables.satisfy_interests(name, from, now, &mut || [0; 32]);
+        };
+
+        // Nothing is measured yet, so the cheapest face is used
+        assert_eq!(forward(&mut tables, 0, at(1000)), [FaceToken(1)]);
+        reply(&mut tables, 0, FaceToken(1), at(1100));
+
+        // Once the probing interval passes the other face is also tried and turns out faster
+        assert_eq!(
+            forward(&mut tables, 1, at(2001)),
+            [FaceToken(1), FaceToken(2)]
+        );
+        reply(&mut tables, 1, FaceToken(2), at(2011));
+        assert_eq!(forward(&mut tables, 2, at(2100)), [FaceToken(2)]);
+
+        // It stops being used when it times out
+        tables.prune_pit_if_needed(at(2300));
+        assert_eq!(forward(&mut tables, 3, at(2400)), [FaceToken(1)]);
+
+        // The measurements of a removed face are not passed on to the next one with its token
+        let namespace = root.adding_components(app);
+        assert!(tables
+            .measurements()
+            .get(namespace)
+            .unwrap()
+            .face(FaceToken(2))
+            .is_some());
+        tables.unregister_face(FaceToken(2));
+        let entry = tables.measurements().get(namespace).unwrap();
+        assert!(entry.face(FaceToken(2)).is_none());
+        assert!(entry.face(FaceToken(1)).is_some());
+    }
+
+    #[test]
+    fn test_strategy_parameters() {
+        let root = Name::new();
+        let app = &[NameComponent::generic(b"app")];
+        let sub = &[app[0], NameComponent::generic(b"sub")];
+        let params = &[
+            NameComponent::generic(b"probing-interval~1000"),
+            NameComponent::generic(b"suppression-max~500"),
+            NameComponent::generic(b"label"),
+        ];
+
+        let mut choice = StrategyChoice::default();
+        choice.set_parameters(root.adding_components(app), root.adding_components(params));
+        choice.set_parameters(
+            root.adding_components(sub),
+            root.adding_components(&params[2..]),
+        );
+        let found = choice.find_parameters(root.adding_components(&[app[0], app[0]]));
+        assert_eq!(found.get(b"probing-interval"), Some(b"1000".as_slice()));
+        assert_eq!(found.get_u64(b"probing-interval"), Some(1000));
+        assert_eq!(found.get(b"label"), None);
+        let suppression = found.suppression(RetransmissionSuppression::default());
+        assert_eq!(suppression.initial_delay_ms, 16);
+        assert_eq!(suppression.max_delay_ms, 500);
+        // The longest prefix wins, without merging
+        let found = choice.find_parameters(root.adding_components(sub));
+        assert_eq!(found.get_u64(b"probing-interval"), None);
+        assert!(choice.unset_parameters(root.adding_components(sub)));
+        let found = choice.find_parameters(root.adding_components(sub));
+        assert_eq!(found.get_u64(b"probing-interval"), Some(1000));
+
+        // ASF probes at the interval of the namespace instead of its own
+        let mut tables = ReferenceTables::default();
+        tables.register_prefix(root.adding_components(app), FaceToken(1), 10);
+        tables.register_prefix(root.adding_components(app), FaceToken(2), 20);
+        tables.set_strategy(
+            root.adding_components(app),
+            Box::new(AsfStrategy::new(100_000, 1)),
+        );
+        tables.set_strategy_parameters(root.adding_components(app), root.adding_components(params));
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let items = [
+            [app[0], NameComponent::generic(b"1")],
+            [app[0], NameComponent::generic(b"2")],
+        ];
+        let mut forward = |item: usize, now| {
+            let name = root.adding_components(&items[item]);
+            tables
+                .register_interest(
+                    &Interest {
+                        interest_lifetime: Some(InterestLifetime { val: 100 }),
+                        ..Interest::new(name, false, [1, 2, 3, 4])
+                    },
+                    [1, 2, 3, 4],
+                    FaceToken(0),
+                    now,
+                )
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(forward(0, at(1000)), [FaceToken(1)]);
+        assert_eq!(forward(1, at(2001)), [FaceToken(1), FaceToken(2)]);
+    }
+
+    #[test]
+    fn test_access_strategy() {
+        let mut tables = ReferenceTables::default();
+        let root = Name::new();
+        let edge = &[NameComponent::generic(b"edge")];
+        tables.register_prefix(root.adding_components(edge), FaceToken(1), 10);
+        tables.register_prefix(root.adding_components(edge), FaceToken(2), 10);
+        tables.set_strategy(
+            root.adding_components(edge),
+            Box::new(AccessStrategy::default()),
+        );
+
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let items: Vec<_> = [b"1", b"2", b"3"]
+            .iter()
+            .map(|item| {
+                [
+                    edge[0],
+                    NameComponent::generic(b"camera"),
+                    NameComponent::generic(*item),
+                ]
+            })
+            .collect();
+        let forward = |tables: &mut ReferenceTables, item: usize, nonce: u8, now| {
+            let name = root.adding_components(&items[item]);
+            tables
+                .register_interest(
+                    &Interest {
+                        interest_lifetime: Some(InterestLifetime { val: 100 }),
+                        ..Interest::new(name, false, [nonce; 4])
+                    },
+                    [nonce; 4],
+                    FaceToken(0),
+                    now,
+                )
+                .collect::<Vec<_>>()
+        };
+
+        // Nobody answered yet, so everyone is asked
+        assert_eq!(
+            forward(&mut tables, 0, 1, at(1000)),
+            [FaceToken(1), FaceToken(2)]
+        );
+        let name = root.adding_components(&items[0]);
+        let _ = tables.satisfy_interests(name, FaceToken(2), at(1010), &mut || [0; 32]);
+
+        // Then only the face that answered, unless the consumer retransmits
+        assert_eq!(forward(&mut tables, 1, 2, at(1020)), [FaceToken(2)]);
+        assert_eq!(
+            forward(&mut tables, 1, 3, at(1050)),
+            [FaceToken(1), FaceToken(2)]
+        );
+
+        // The face is forgotten once the interest times out
+        tables.prune_pit_if_needed(at(1200));
+        assert_eq!(
+            forward(&mut tables, 2, 4, at(1300)),
+            [FaceToken(1), FaceToken(2)]
+        );
+
+        // Or once it is removed, even if its token is given to another face
+        let name = root.adding_components(&items[2]);
+        let _ = tables.satisfy_interests(name, FaceToken(2), at(1310), &mut || [0; 32]);
+        tables.unregister_face(FaceToken(2));
+        tables.register_prefix(root.adding_components(edge), FaceToken(2), 10);
+        assert_eq!(
+            forward(&mut tables, 2, 5, at(1400)),
+            [FaceToken(1), FaceToken(2)]
+        );
+    }
+
+    #[test]
+    fn test_self_learning_strategy() {
+        let mut tables = ReferenceTables::default();
+        for face in 0..4 {
+            tables.register_face(FaceToken(face));
+        }
+        tables.set_strategy(Name::new(), Box::new(SelfLearningStrategy::new(1000)));
+
+        let at = |ms| Timestamp { ms_since_1970: ms };
+        let root = Name::new();
+        let items: Vec<_> = [b"1", b"2", b"3"]
+            .iter()
+            .map(|item| {
+                [
+                    NameComponent::generic(b"mesh"),
+                    NameComponent::generic(b"sensor"),
+                    NameComponent::generic(*item),
+                ]
+            })
+            .collect();
+        let forward = |tables: &mut ReferenceTables, item: usize, now| {
+            let name = root.adding_components(&items[item]);
+            tables
+                .register_interest(
+                    &Interest {
+                        interest_lifetime: Some(InterestLifetime { val: 100 }),
+                        ..Interest::new(name, false, [1, 2, 3, 4])
+                    },
+                    [1, 2, 3, 4],
+                    FaceToken(0),
+                    now,
+                )
+                .collect::<Vec<_>>()
+        };
+
+        // Without a route the interest goes everywhere but back
+        assert_eq!(
+            forward(&mut tables, 0, at(1000)),
+            [FaceToken(1), FaceToken(2), FaceToken(3)]
+        );
+        let name = root.adding_components(&items[0]);
+        let faces: Vec<_> = tables
+            .satisfy_interests(name, FaceToken(2), at(1010), &mut || [0; 32])
+            .collect();
+        assert_eq!(faces, [FaceToken(0)]);
+
+        // The data taught where /mesh/sensor is
+        assert_eq!(forward(&mut tables, 1, at(1100)), [FaceToken(2)]);
+        let name = root.adding_components(&items[1]);
+        let _ = tables.satisfy_interests(name, FaceToken(2), at(1110), &mut || [0; 32]);
+
+        // ... until the route expires
+        tables.prune_pit_if_needed(at(2200));
+        assert_eq!(
+            forward(&mut tables, 2, at(2300)),
+            [FaceToken(1), FaceToken(2), FaceToken(3)]
+        );
+    }
+
+    // Forwards only the new interests and records what it saw of the PIT entries
+    struct AggregationStrategy {
+        // The nonces of the in-records, for each interest
+        seen: Rc<RefCell<Vec<Vec<[u8; 4]>>>>,
+    }
+
+    impl Strategy for AggregationStrategy {
+        fn after_receive_interest(
+            &mut self,
+            interest: &PendingInterest<'_>,
+            next_hops: &[NextHop],
+            _measurements: &mut Measurements,
+            forward_to: &mut Vec<FaceToken>,
+        ) {
+            let entry = interest.pit_entry;
+            assert_eq!(entry.in_records().count(), entry.in_record_count());
+            self.seen.borrow_mut().push(entry.nonces().collect());
+            if interest.is_new {
+                forward_to.push(next_hops[0].face);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pit_entry_view() {
+        let mut tables = ReferenceTables::default();
+        let now = Timestamp {
+            ms_since_1970: 1000,
+        };
+        let root = Name::new();
+        let a = &[NameComponent::generic(b"a")];
+        tables.register_prefix(root.adding_components(a), FaceToken(1), 0);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        tables.set_strategy(
+            root.adding_components(a),
+            Box::new(AggregationStrategy { seen: seen.clone() }),
+        );
+
+        // The second interest is aggregated with the first one
+        let name = root.adding_components(a);
+        for (face, nonce) in [(2, [1; 4]), (3, [2; 4])] {
+            let _ = tables.register_interest(
+                &Interest::new(name, false, nonce),
+                nonce,
+                FaceToken(face),
+                now,
+            );
+        }
+        assert_eq!(*seen.borrow(), [vec![[1; 4]], vec![[1; 4], [2; 4]]]);
+    }
+}
@@ -303,7 +303,7 @@ impl<'a, const TYPE: u32, const LEN: usize> TlvEncode for TypedArray<TYPE, LEN>
 //  of the number we are reading from the stream. Hence, it takes 1,3,5, or 9 bytes.
 // The u64 above is instead used as the _value_ in a TLV, and we already know the length
 //  from the L field in TLV, so the u64 can be encoded as 1,2,4, or 8 bytes.
-struct Varint(u64);
+pub(crate) struct Varint(pub(crate) u64);
 
 impl Varint {
     pub fn try_decode(bytes: &[u8]) -> Result<(Self, usize), VarintDecodingError> {